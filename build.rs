@@ -3,6 +3,7 @@ fn main() {
         &[
             "src/schema/keys.proto",
             "src/schema/noise.proto",
+            "src/schema/peer_store.proto",
             "src/schema/webrtc.proto",
             "src/protocol/libp2p/schema/identify.proto",
             "src/protocol/libp2p/schema/kademlia.proto",
@@ -229,7 +229,7 @@ impl UserProtocol for CustomProtocol {
                         }
                     }
                     // connection closed, remove all peer context
-                    Some(TransportEvent::ConnectionClosed { peer }) => {
+                    Some(TransportEvent::ConnectionClosed { peer, .. }) => {
                         self.peers.remove(&peer);
                     }
                     None => return Err(litep2p::Error::EssentialTaskClosed),
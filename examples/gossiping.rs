@@ -137,7 +137,7 @@ impl TransactionProtocol {
                             if connected != &peer && not_seen {
                                 self.tx_handle.send_sync_notification(
                                     *connected,
-                                    notification.clone().into(),
+                                    notification.clone(),
                                 ).unwrap();
                             }
                         }
@@ -22,24 +22,37 @@
 
 use crate::{
     crypto::ed25519::Keypair,
+    error::ConfigError,
     executor::{DefaultExecutor, Executor},
+    external_addresses,
+    peer_store::PeerStoreBackend,
     protocol::{
         libp2p::{bitswap, identify, kademlia, ping},
         mdns::Config as MdnsConfig,
-        notification, request_response, UserProtocol,
+        notification, request_response, SlowProtocolPolicy, UserProtocol,
     },
+    reputation::Config as ReputationConfig,
     transport::{
-        quic::config::Config as QuicConfig, tcp::config::Config as TcpConfig,
+        allow_list::AllowList, bootstrap::Config as BootstrapConfig,
+        connection_rate::Config as ConnectionRateConfig,
+        dial_scheduler::Config as DialSchedulerConfig, gater::ConnectionGater,
+        ip_limit::Config as IpLimitConfig, local, quic::config::Config as QuicConfig,
+        reconnect::Config as ReconnectConfig, tcp::config::Config as TcpConfig,
         webrtc::config::Config as WebRtcConfig, websocket::config::Config as WebSocketConfig,
-        MAX_PARALLEL_DIALS,
+        MAX_PARALLEL_DIALS, SHUTDOWN_TIMEOUT, TRANSPORT_CHANNEL_SIZE,
     },
     types::protocol::ProtocolName,
-    PeerId,
+    PeerId, DEFAULT_CHANNEL_SIZE,
 };
 
-use multiaddr::Multiaddr;
+use multiaddr::{Multiaddr, Protocol};
 
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    net::IpAddr,
+    sync::Arc,
+    time::Duration,
+};
 
 /// Connection role.
 #[derive(Debug, Copy, Clone)]
@@ -90,13 +103,13 @@ pub struct ConfigBuilder {
     bitswap: Option<bitswap::Config>,
 
     /// Notification protocols.
-    notification_protocols: HashMap<ProtocolName, notification::Config>,
+    notification_protocols: Vec<(ProtocolName, notification::Config)>,
 
     /// Request-response protocols.
-    request_response_protocols: HashMap<ProtocolName, request_response::Config>,
+    request_response_protocols: Vec<(ProtocolName, request_response::Config)>,
 
     /// User protocols.
-    user_protocols: HashMap<ProtocolName, Box<dyn UserProtocol>>,
+    user_protocols: Vec<(ProtocolName, Box<dyn UserProtocol>)>,
 
     /// mDNS configuration.
     mdns: Option<MdnsConfig>,
@@ -109,6 +122,69 @@ pub struct ConfigBuilder {
 
     /// Maximum number of parallel dial attempts.
     max_parallel_dials: usize,
+
+    /// Should inbound connections be approved by the user.
+    connection_approval: bool,
+
+    /// Should connections be probed before they're reported to the user.
+    connection_probing: bool,
+
+    /// Maximum lifetime of a connection.
+    max_connection_lifetime: Option<Duration>,
+
+    /// How long connections without open substreams are kept open.
+    keep_alive_timeout: Option<Duration>,
+
+    /// Maximum number of concurrently open substreams per connection.
+    max_substreams: Option<usize>,
+
+    /// Policy for handling substreams opened for slow protocols.
+    slow_protocol_policy: SlowProtocolPolicy,
+
+    /// Inbound connection rate configuration.
+    connection_rate: Option<ConnectionRateConfig>,
+
+    /// Connection gater.
+    connection_gater: Option<Arc<dyn ConnectionGater>>,
+
+    /// Allow-list of peers and addresses.
+    allow_list: Option<AllowList>,
+
+    /// Limits for inbound connections per IP address and subnet.
+    ip_limits: Option<IpLimitConfig>,
+
+    /// Reputation configuration.
+    reputation: Option<ReputationConfig>,
+
+    /// Reconnection configuration for persistent peers.
+    reconnect: Option<ReconnectConfig>,
+
+    /// Configuration for connecting to bootnodes.
+    bootstrap: Option<BootstrapConfig>,
+
+    /// Configuration for scheduling outbound dials.
+    dial_scheduler: Option<DialSchedulerConfig>,
+
+    /// Persistence backend of the peer store.
+    peer_store_backend: Option<Box<dyn PeerStoreBackend>>,
+
+    /// Number of distinct peers that must observe an external address before it's confirmed.
+    external_address_confirmations: usize,
+
+    /// Size of the channel each installed protocol receives connection events from.
+    protocol_channel_size: usize,
+
+    /// Size of the command and event channels of the transport manager and connections.
+    transport_channel_size: usize,
+
+    /// How long each stage of an ordered shutdown may take.
+    shutdown_timeout: Duration,
+
+    /// Should failed transports be restarted.
+    transport_restart: bool,
+
+    /// Should dialing and listening be restricted to local addresses.
+    local_only: bool,
 }
 
 impl ConfigBuilder {
@@ -127,9 +203,30 @@ impl ConfigBuilder {
             mdns: None,
             executor: None,
             max_parallel_dials: MAX_PARALLEL_DIALS,
-            user_protocols: HashMap::new(),
-            notification_protocols: HashMap::new(),
-            request_response_protocols: HashMap::new(),
+            connection_approval: false,
+            connection_probing: false,
+            max_connection_lifetime: None,
+            keep_alive_timeout: None,
+            max_substreams: None,
+            slow_protocol_policy: SlowProtocolPolicy::default(),
+            connection_rate: None,
+            connection_gater: None,
+            allow_list: None,
+            ip_limits: None,
+            reputation: None,
+            reconnect: None,
+            bootstrap: None,
+            dial_scheduler: None,
+            peer_store_backend: None,
+            external_address_confirmations: external_addresses::DEFAULT_CONFIRMATIONS,
+            protocol_channel_size: DEFAULT_CHANNEL_SIZE,
+            transport_channel_size: TRANSPORT_CHANNEL_SIZE,
+            shutdown_timeout: SHUTDOWN_TIMEOUT,
+            transport_restart: false,
+            local_only: false,
+            user_protocols: Vec::new(),
+            notification_protocols: Vec::new(),
+            request_response_protocols: Vec::new(),
             known_addresses: Vec::new(),
         }
     }
@@ -168,7 +265,7 @@ impl ConfigBuilder {
 
     /// Enable notification protocol.
     pub fn with_notification_protocol(mut self, config: notification::Config) -> Self {
-        self.notification_protocols.insert(config.protocol_name().clone(), config);
+        self.notification_protocols.push((config.protocol_name().clone(), config));
         self
     }
 
@@ -198,13 +295,13 @@ impl ConfigBuilder {
 
     /// Enable request-response protocol.
     pub fn with_request_response_protocol(mut self, config: request_response::Config) -> Self {
-        self.request_response_protocols.insert(config.protocol_name().clone(), config);
+        self.request_response_protocols.push((config.protocol_name().clone(), config));
         self
     }
 
     /// Enable user protocol.
     pub fn with_user_protocol(mut self, protocol: Box<dyn UserProtocol>) -> Self {
-        self.user_protocols.insert(protocol.protocol(), protocol);
+        self.user_protocols.push((protocol.protocol(), protocol));
         self
     }
 
@@ -225,6 +322,11 @@ impl ConfigBuilder {
 
     /// Add executor for running futures spawned by `litep2p`.
     ///
+    /// All background tasks of `litep2p`, such as the event loops of the installed protocols and
+    /// the tasks of open connections, are spawned using the executor with
+    /// [`Executor::run_with_name()`] so the executor can, for example, track them under their
+    /// names.
+    ///
     /// If no executor is specified, `litep2p` defaults to calling `tokio::spawn()`.
     pub fn with_executor(mut self, executor: Arc<dyn Executor>) -> Self {
         self.executor = Some(executor);
@@ -237,6 +339,242 @@ impl ConfigBuilder {
         self
     }
 
+    /// Hold negotiated inbound connections until they've been approved by the user.
+    ///
+    /// If enabled, each negotiated inbound connection is reported to the user in
+    /// [`Litep2pEvent::IncomingConnection`](crate::Litep2pEvent::IncomingConnection) and the
+    /// connection is accepted or rejected using the approval handle of the event. Protocols are
+    /// not notified of the connection until it has been accepted.
+    pub fn with_connection_approval(mut self) -> Self {
+        self.connection_approval = true;
+        self
+    }
+
+    /// Verify negotiated connections with a ping round-trip before reporting them to the user.
+    ///
+    /// If enabled,
+    /// [`Litep2pEvent::ConnectionEstablished`](crate::Litep2pEvent::ConnectionEstablished)
+    /// is only emitted after a ping over the new connection has succeeded. Connections that fail
+    /// the probe are closed without ever being reported to the user. If ping hasn't been
+    /// enabled with [`ConfigBuilder::with_libp2p_ping()`], it's enabled with the default
+    /// configuration.
+    pub fn with_connection_probing(mut self) -> Self {
+        self.connection_probing = true;
+        self
+    }
+
+    /// Close connections once they've been open for `lifetime`.
+    ///
+    /// Protocols are notified of the closed connection as usual. If the expired connection was
+    /// opened by the local node and it was the last connection to the peer, the peer is dialed
+    /// again.
+    ///
+    /// If TCP port reuse is enabled, redialing the same address may fail until the socket of the
+    /// closed connection has left `TIME_WAIT`.
+    pub fn with_max_connection_lifetime(mut self, lifetime: Duration) -> Self {
+        self.max_connection_lifetime = Some(lifetime);
+        self
+    }
+
+    /// Close connections which have had no open substreams for `timeout`.
+    ///
+    /// Connections are kept open for as long as any protocol has a substream open over them and
+    /// once the last substream is closed, the connection is closed if no new substream is opened
+    /// within `timeout`. This also applies to newly established connections over which no
+    /// substream is opened.
+    ///
+    /// By default connections are closed as soon as protocols are no longer interested in them.
+    /// The timeout can be changed at runtime using [`Litep2p::limits()`](crate::Litep2p::limits).
+    pub fn with_keep_alive_timeout(mut self, timeout: Duration) -> Self {
+        self.keep_alive_timeout = Some(timeout);
+        self
+    }
+
+    /// Limit the number of concurrently open substreams per connection to `max_substreams`.
+    ///
+    /// The limit is shared by all protocols and includes substreams which are still being
+    /// negotiated. Once the limit is reached, substreams opened by the remote peer are rejected
+    /// and opening an outbound substream fails with
+    /// [`Error::SubstreamLimitReached`](crate::Error::SubstreamLimitReached).
+    ///
+    /// By default the number of substreams is not limited. The limit can be changed at runtime
+    /// using [`Litep2p::limits()`](crate::Litep2p::limits).
+    pub fn with_max_substreams_per_connection(mut self, max_substreams: usize) -> Self {
+        self.max_substreams = Some(max_substreams);
+        self
+    }
+
+    /// Set the policy for handling substreams opened for protocols which aren't keeping up with
+    /// their events.
+    ///
+    /// By default the connection waits until the protocol has room for the substream. See
+    /// [`SlowProtocolPolicy`] for more details.
+    pub fn with_slow_protocol_policy(mut self, policy: SlowProtocolPolicy) -> Self {
+        self.slow_protocol_policy = policy;
+        self
+    }
+
+    /// Configure how inbound connection rates are tracked.
+    ///
+    /// Inbound connection attempts and handshake failures are tracked per source subnet and
+    /// can be queried using [`Litep2p::connection_rates()`](crate::Litep2p::connection_rates).
+    /// If a source exceeds the configured thresholds,
+    /// [`Litep2pEvent::ConnectionRateExceeded`](crate::Litep2pEvent::ConnectionRateExceeded) is
+    /// emitted.
+    pub fn with_connection_rate_config(mut self, config: ConnectionRateConfig) -> Self {
+        self.connection_rate = Some(config);
+        self
+    }
+
+    /// Gate inbound connections using `gater`.
+    ///
+    /// The gater is consulted when an inbound socket is accepted and again when the handshake
+    /// has authenticated the remote peer, allowing connections to be rejected before any
+    /// protocol is notified of them. See [`ConnectionGater`] for more details.
+    pub fn with_connection_gater(mut self, gater: Arc<dyn ConnectionGater>) -> Self {
+        self.connection_gater = Some(gater);
+        self
+    }
+
+    /// Only allow connections to and from the peers and addresses on `allow_list`.
+    ///
+    /// Dials to other peers are refused and inbound connections from them are rejected before
+    /// any protocol is notified of them. See [`AllowList`] for more details.
+    pub fn with_allow_list(mut self, allow_list: AllowList) -> Self {
+        self.allow_list = Some(allow_list);
+        self
+    }
+
+    /// Limit the number of inbound connections from the same IP address or subnet.
+    ///
+    /// The limits can be changed at runtime using
+    /// [`Litep2p::limits()`](crate::Litep2p::limits). See [`IpLimitConfig`] for more details.
+    pub fn with_ip_limits(mut self, config: IpLimitConfig) -> Self {
+        self.ip_limits = Some(config);
+        self
+    }
+
+    /// Configure how the behavior of remote peers affects their reputation.
+    ///
+    /// See [`Litep2p::reputation()`](crate::Litep2p::reputation) and [`ReputationConfig`] for
+    /// more details.
+    pub fn with_reputation_config(mut self, config: ReputationConfig) -> Self {
+        self.reputation = Some(config);
+        self
+    }
+
+    /// Configure how persistent peers are reconnected to.
+    ///
+    /// See [`Litep2p::add_persistent_peer()`](crate::Litep2p::add_persistent_peer) and
+    /// [`ReconnectConfig`] for more details.
+    pub fn with_reconnect_config(mut self, config: ReconnectConfig) -> Self {
+        self.reconnect = Some(config);
+        self
+    }
+
+    /// Configure how bootnodes are connected to.
+    ///
+    /// See [`Litep2p::connect_to_bootnodes()`](crate::Litep2p::connect_to_bootnodes) and
+    /// [`BootstrapConfig`] for more details.
+    pub fn with_bootstrap_config(mut self, config: BootstrapConfig) -> Self {
+        self.bootstrap = Some(config);
+        self
+    }
+
+    /// Configure how outbound dials are scheduled.
+    ///
+    /// Limits the number of outbound handshakes in progress across all transports and spaces
+    /// out dials, globally and per destination. See [`DialSchedulerConfig`] for more details.
+    pub fn with_dial_scheduler_config(mut self, config: DialSchedulerConfig) -> Self {
+        self.dial_scheduler = Some(config);
+        self
+    }
+
+    /// Persist the known peers and their addresses using `backend`.
+    ///
+    /// The stored peers are loaded when [`Litep2p`](crate::Litep2p) is created and added as
+    /// known addresses so they can be dialed without bootstrapping the node again. The known
+    /// peers are persisted periodically and when the peer store is dropped.
+    ///
+    /// By default the known peers are not persisted.
+    pub fn with_peer_store_backend(mut self, backend: impl PeerStoreBackend) -> Self {
+        self.peer_store_backend = Some(Box::new(backend));
+        self
+    }
+
+    /// Set how many distinct peers must report to have observed the local node at an address
+    /// before the address is confirmed as an external address.
+    ///
+    /// Confirmed external addresses are advertised to remote peers and returned by
+    /// [`Litep2p::public_addresses()`](crate::Litep2p::public_addresses) with
+    /// [`AddressConfidence::Confirmed`](crate::AddressConfidence::Confirmed). The addresses are
+    /// observed by remote peers over `/ipfs/id/1.0.0`.
+    ///
+    /// Defaults to 3.
+    pub fn with_external_address_confirmations(mut self, confirmations: usize) -> Self {
+        self.external_address_confirmations = confirmations;
+        self
+    }
+
+    /// Set the size of the channel each installed protocol receives connection events, such as
+    /// opened substreams, from.
+    ///
+    /// The sizes of the channels between a protocol and its handle are configured separately in
+    /// the configuration of the protocol.
+    ///
+    /// Defaults to 4096.
+    pub fn with_protocol_channel_size(mut self, size: usize) -> Self {
+        self.protocol_channel_size = size;
+        self
+    }
+
+    /// Set the size of the command and event channels of the transport manager and of each
+    /// open connection.
+    ///
+    /// Defaults to 256.
+    pub fn with_transport_channel_size(mut self, size: usize) -> Self {
+        self.transport_channel_size = size;
+        self
+    }
+
+    /// Set how long each stage of [`Litep2p::shutdown()`](crate::Litep2p::shutdown) may take.
+    ///
+    /// Defaults to 5 seconds.
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Restart transports that fail while the node is running.
+    ///
+    /// A failed transport is reported in
+    /// [`Litep2pEvent::TransportFailed`](crate::Litep2pEvent::TransportFailed) and removed from
+    /// the node while the other transports keep running. If enabled, the failed transport is
+    /// started again with its original configuration and its listen addresses are reported in
+    /// [`Litep2pEvent::NewListenAddress`](crate::Litep2pEvent::NewListenAddress). If the listen
+    /// addresses use port `0`, the restarted transport may listen on different ports.
+    pub fn with_transport_restart(mut self) -> Self {
+        self.transport_restart = true;
+        self
+    }
+
+    /// Restrict dialing and listening to loopback and private addresses.
+    ///
+    /// Dials to public addresses are rejected with [`Error::NonLocalAddress`] and public
+    /// addresses of remote peers are never selected for dialing. Unspecified listen addresses
+    /// (`0.0.0.0` and `::`) are replaced with the loopback address and any other public listen
+    /// address fails the configuration validation with
+    /// [`ConfigError::NonLocalListenAddress`].
+    ///
+    /// Meant for test networks and CI environments which must not leak traffic to the public
+    /// internet. Disabled by default.
+    ///
+    /// [`Error::NonLocalAddress`]: crate::Error::NonLocalAddress
+    pub fn with_local_only(mut self) -> Self {
+        self.local_only = true;
+        self
+    }
+
     /// Build [`Litep2pConfig`].
     pub fn build(mut self) -> Litep2pConfig {
         let keypair = match self.keypair {
@@ -244,6 +582,26 @@ impl ConfigBuilder {
             None => Keypair::generate(),
         };
 
+        if self.local_only {
+            let listen_addresses = self
+                .tcp
+                .iter_mut()
+                .flat_map(|config| config.listen_addresses.iter_mut())
+                .chain(
+                    self.websocket.iter_mut().flat_map(|config| config.listen_addresses.iter_mut()),
+                )
+                .chain(self.quic.iter_mut().flat_map(|config| config.listen_addresses.iter_mut()))
+                .chain(
+                    self.webrtc.iter_mut().flat_map(|config| config.listen_addresses.iter_mut()),
+                );
+
+            for address in listen_addresses {
+                if let Some(local) = local::local_listen_address(address.clone()) {
+                    *address = local;
+                }
+            }
+        }
+
         Litep2pConfig {
             keypair,
             tcp: self.tcp.take(),
@@ -256,6 +614,27 @@ impl ConfigBuilder {
             kademlia: self.kademlia.take(),
             bitswap: self.bitswap.take(),
             max_parallel_dials: self.max_parallel_dials,
+            connection_approval: self.connection_approval,
+            connection_probing: self.connection_probing,
+            max_connection_lifetime: self.max_connection_lifetime,
+            keep_alive_timeout: self.keep_alive_timeout,
+            max_substreams: self.max_substreams,
+            slow_protocol_policy: self.slow_protocol_policy,
+            connection_rate: self.connection_rate.take(),
+            connection_gater: self.connection_gater.take(),
+            allow_list: self.allow_list.take(),
+            ip_limits: self.ip_limits.take(),
+            reputation: self.reputation.take(),
+            reconnect: self.reconnect.take(),
+            bootstrap: self.bootstrap.take(),
+            dial_scheduler: self.dial_scheduler.take(),
+            peer_store_backend: self.peer_store_backend.take(),
+            external_address_confirmations: self.external_address_confirmations,
+            protocol_channel_size: self.protocol_channel_size,
+            transport_channel_size: self.transport_channel_size,
+            shutdown_timeout: self.shutdown_timeout,
+            transport_restart: self.transport_restart,
+            local_only: self.local_only,
             executor: self.executor.map_or(Arc::new(DefaultExecutor {}), |executor| executor),
             user_protocols: self.user_protocols,
             notification_protocols: self.notification_protocols,
@@ -295,13 +674,13 @@ pub struct Litep2pConfig {
     pub(crate) bitswap: Option<bitswap::Config>,
 
     /// Notification protocols.
-    pub(crate) notification_protocols: HashMap<ProtocolName, notification::Config>,
+    pub(crate) notification_protocols: Vec<(ProtocolName, notification::Config)>,
 
     /// Request-response protocols.
-    pub(crate) request_response_protocols: HashMap<ProtocolName, request_response::Config>,
+    pub(crate) request_response_protocols: Vec<(ProtocolName, request_response::Config)>,
 
     /// User protocols.
-    pub(crate) user_protocols: HashMap<ProtocolName, Box<dyn UserProtocol>>,
+    pub(crate) user_protocols: Vec<(ProtocolName, Box<dyn UserProtocol>)>,
 
     /// mDNS configuration.
     pub(crate) mdns: Option<MdnsConfig>,
@@ -312,6 +691,239 @@ pub struct Litep2pConfig {
     /// Maximum number of parallel dial attempts.
     pub(crate) max_parallel_dials: usize,
 
+    /// Should inbound connections be approved by the user.
+    pub(crate) connection_approval: bool,
+
+    /// Should connections be probed before they're reported to the user.
+    pub(crate) connection_probing: bool,
+
+    /// Maximum lifetime of a connection.
+    pub(crate) max_connection_lifetime: Option<Duration>,
+
+    /// How long connections without open substreams are kept open.
+    pub(crate) keep_alive_timeout: Option<Duration>,
+
+    /// Maximum number of concurrently open substreams per connection.
+    pub(crate) max_substreams: Option<usize>,
+
+    /// Policy for handling substreams opened for slow protocols.
+    pub(crate) slow_protocol_policy: SlowProtocolPolicy,
+
+    /// Inbound connection rate configuration.
+    pub(crate) connection_rate: Option<ConnectionRateConfig>,
+
+    /// Connection gater.
+    pub(crate) connection_gater: Option<Arc<dyn ConnectionGater>>,
+
+    /// Allow-list of peers and addresses.
+    pub(crate) allow_list: Option<AllowList>,
+
+    /// Limits for inbound connections per IP address and subnet.
+    pub(crate) ip_limits: Option<IpLimitConfig>,
+
+    /// Reputation configuration.
+    pub(crate) reputation: Option<ReputationConfig>,
+
+    /// Reconnection configuration for persistent peers.
+    pub(crate) reconnect: Option<ReconnectConfig>,
+
+    /// Configuration for connecting to bootnodes.
+    pub(crate) bootstrap: Option<BootstrapConfig>,
+
+    /// Configuration for scheduling outbound dials.
+    pub(crate) dial_scheduler: Option<DialSchedulerConfig>,
+
+    /// Persistence backend of the peer store.
+    pub(crate) peer_store_backend: Option<Box<dyn PeerStoreBackend>>,
+
+    /// Number of distinct peers that must observe an external address before it's confirmed.
+    pub(crate) external_address_confirmations: usize,
+
+    /// Size of the channel each installed protocol receives connection events from.
+    pub(crate) protocol_channel_size: usize,
+
+    /// Size of the command and event channels of the transport manager and connections.
+    pub(crate) transport_channel_size: usize,
+
+    /// How long each stage of an ordered shutdown may take.
+    pub(crate) shutdown_timeout: Duration,
+
+    /// Should failed transports be restarted.
+    pub(crate) transport_restart: bool,
+
+    /// Should dialing and listening be restricted to local addresses.
+    pub(crate) local_only: bool,
+
     /// Known addresses.
     pub(crate) known_addresses: Vec<(PeerId, Vec<Multiaddr>)>,
 }
+
+impl Litep2pConfig {
+    /// Validate the configuration.
+    ///
+    /// Verifies that at least one transport is enabled, that each protocol name (including
+    /// fallback names) is used by only one protocol and that no two listen addresses use the same
+    /// socket. If local-only mode is enabled, also verifies that all listen addresses are local.
+    pub(crate) fn validate(&self) -> Result<(), ConfigError> {
+        if self.tcp.is_none()
+            && self.quic.is_none()
+            && self.webrtc.is_none()
+            && self.websocket.is_none()
+        {
+            return Err(ConfigError::NoTransport);
+        }
+
+        let protocol_names = self
+            .notification_protocols
+            .iter()
+            .flat_map(|(protocol, config)| {
+                std::iter::once(protocol).chain(config.fallback_names.iter())
+            })
+            .chain(
+                self.request_response_protocols.iter().flat_map(|(protocol, config)| {
+                    std::iter::once(protocol).chain(config.fallback_names.iter())
+                }),
+            )
+            .chain(self.user_protocols.iter().map(|(protocol, _)| protocol))
+            .chain(self.ping.iter().map(|config| &config.protocol))
+            .chain(self.identify.iter().map(|config| &config.protocol))
+            .chain(self.kademlia.iter().flat_map(|config| config.protocol_names.iter()))
+            .chain(self.bitswap.iter().map(|config| &config.protocol));
+
+        let mut protocols = HashSet::new();
+        for protocol in protocol_names {
+            if !protocols.insert(protocol) {
+                return Err(ConfigError::DuplicateProtocol(protocol.clone()));
+            }
+        }
+
+        let listen_addresses = self
+            .tcp
+            .iter()
+            .flat_map(|config| config.listen_addresses.iter())
+            .chain(self.websocket.iter().flat_map(|config| config.listen_addresses.iter()))
+            .chain(self.quic.iter().flat_map(|config| config.listen_addresses.iter()))
+            .chain(self.webrtc.iter().flat_map(|config| config.listen_addresses.iter()));
+
+        let mut sockets = HashMap::new();
+        for address in listen_addresses {
+            if self.local_only && !local::is_local_address(address) {
+                return Err(ConfigError::NonLocalListenAddress(address.clone()));
+            }
+
+            let Some(socket) = listen_socket(address) else {
+                continue;
+            };
+
+            if let Some(other) = sockets.insert(socket, address) {
+                return Err(ConfigError::ConflictingListenAddresses(
+                    other.clone(),
+                    address.clone(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Get the IP address, transport protocol and port of the socket `address` listens on.
+///
+/// Returns `None` if the address doesn't specify a port, in which case each listener is
+/// allocated its own port.
+fn listen_socket(address: &Multiaddr) -> Option<(IpAddr, &'static str, u16)> {
+    let mut iter = address.iter();
+
+    let ip = match iter.next()? {
+        Protocol::Ip4(ip) => IpAddr::V4(ip),
+        Protocol::Ip6(ip) => IpAddr::V6(ip),
+        _ => return None,
+    };
+
+    match iter.next()? {
+        Protocol::Tcp(port) if port != 0 => Some((ip, "tcp", port)),
+        Protocol::Udp(port) if port != 0 => Some((ip, "udp", port)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicate_protocol_rejected() {
+        let (ping_config, _ping_event_stream) = ping::Config::default();
+        let (config, _handle) = request_response::ConfigBuilder::new(ping_config.protocol.clone())
+            .with_max_size(1024)
+            .build();
+
+        let config = ConfigBuilder::new()
+            .with_tcp(Default::default())
+            .with_libp2p_ping(ping_config)
+            .with_request_response_protocol(config)
+            .build();
+
+        assert!(std::matches!(
+            config.validate(),
+            Err(ConfigError::DuplicateProtocol(_))
+        ));
+    }
+
+    #[test]
+    fn conflicting_listen_addresses_rejected() {
+        let config = ConfigBuilder::new()
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/8888".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_websocket(WebSocketConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/8888/ws".parse().unwrap()],
+                ..Default::default()
+            })
+            .build();
+
+        assert!(std::matches!(
+            config.validate(),
+            Err(ConfigError::ConflictingListenAddresses(_, _))
+        ));
+    }
+
+    #[test]
+    fn no_transport_rejected() {
+        assert!(std::matches!(
+            ConfigBuilder::new().build().validate(),
+            Err(ConfigError::NoTransport)
+        ));
+    }
+
+    #[test]
+    fn local_only_listen_addresses() {
+        let config = ConfigBuilder::new()
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/0.0.0.0/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_local_only()
+            .build();
+
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.tcp.unwrap().listen_addresses,
+            vec!["/ip4/127.0.0.1/tcp/0".parse::<Multiaddr>().unwrap()]
+        );
+
+        let config = ConfigBuilder::new()
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/1.1.1.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_local_only()
+            .build();
+
+        assert!(std::matches!(
+            config.validate(),
+            Err(ConfigError::NonLocalListenAddress(_))
+        ));
+    }
+}
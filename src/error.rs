@@ -28,6 +28,7 @@
 
 use crate::{
     protocol::Direction,
+    transport::RejectReason,
     types::{protocol::ProtocolName, ConnectionId, SubstreamId},
     PeerId,
 };
@@ -35,9 +36,82 @@ use crate::{
 use multiaddr::Multiaddr;
 use multihash::{Multihash, MultihashGeneric};
 
-use std::io::{self, ErrorKind};
+use std::io;
+
+/// Broad category of an error.
+///
+/// Allows callers to react to an error, e.g., to decide whether an operation should be retried,
+/// without matching on the individual error variants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// Operation timed out.
+    Timeout,
+
+    /// Operation was refused by the remote peer or by local policy, such as a ban or a limit.
+    Refused,
+
+    /// Protocol, transport or operation is not supported.
+    Unsupported,
+
+    /// Remote peer couldn't be reached.
+    Unreachable,
+
+    /// Connection or substream was closed.
+    Closed,
+
+    /// Channel to an internal task failed, either because the task exited or the channel is
+    /// clogged.
+    ChannelFailure,
+
+    /// Peer, connection or substream doesn't exist.
+    NotFound,
+
+    /// Operation has already been done or is in progress.
+    AlreadyExists,
+
+    /// Address, configuration or data received from the remote peer is invalid.
+    InvalidInput,
+
+    /// I/O error.
+    Io,
+
+    /// Other error.
+    Other,
+}
+
+impl From<io::ErrorKind> for ErrorKind {
+    fn from(kind: io::ErrorKind) -> Self {
+        match kind {
+            io::ErrorKind::TimedOut => ErrorKind::Timeout,
+            io::ErrorKind::ConnectionRefused | io::ErrorKind::PermissionDenied =>
+                ErrorKind::Refused,
+            io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::UnexpectedEof => ErrorKind::Closed,
+            io::ErrorKind::NotFound => ErrorKind::NotFound,
+            io::ErrorKind::AddrNotAvailable => ErrorKind::Unreachable,
+            io::ErrorKind::InvalidInput | io::ErrorKind::InvalidData => ErrorKind::InvalidInput,
+            _ => ErrorKind::Io,
+        }
+    }
+}
+
+impl From<&crate::yamux::ConnectionError> for ErrorKind {
+    fn from(error: &crate::yamux::ConnectionError) -> Self {
+        match error {
+            crate::yamux::ConnectionError::Io(error) => error.kind().into(),
+            crate::yamux::ConnectionError::Decode(_) => ErrorKind::InvalidInput,
+            crate::yamux::ConnectionError::NoMoreStreamIds
+            | crate::yamux::ConnectionError::TooManyStreams => ErrorKind::Refused,
+            crate::yamux::ConnectionError::Closed => ErrorKind::Closed,
+        }
+    }
+}
 
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum Error {
     #[error("Peer `{0}` does not exist")]
     PeerDoesntExist(PeerId),
@@ -50,7 +124,7 @@ pub enum Error {
     #[error("Parse error: `{0}`")]
     ParseError(ParseError),
     #[error("I/O error: `{0}`")]
-    IoError(ErrorKind),
+    IoError(io::ErrorKind),
     #[error("Negotiation error: `{0}`")]
     NegotiationError(NegotiationError),
     #[error("Substream error: `{0}`")]
@@ -117,9 +191,26 @@ pub enum Error {
     ChannelClogged,
     #[error("Connection doesn't exist: `{0:?}`")]
     ConnectionDoesntExist(ConnectionId),
+    #[error("Failed to dial any address: `{0:?}`")]
+    DialFailures(Vec<(Multiaddr, Error)>),
+    #[error("No connection to `{0}` matches the connection preference")]
+    NoMatchingConnection(PeerId),
+    #[error("Peer `{0}` is banned")]
+    PeerBanned(PeerId),
+    #[error("Peer `{0}` is not on the allow-list")]
+    PeerNotAllowed(PeerId),
+    #[error("Invalid configuration: `{0}`")]
+    ConfigError(ConfigError),
+    #[error("Connection was rejected: `{0:?}`")]
+    ConnectionRejected(RejectReason),
+    #[error("Address `{0}` is not local")]
+    NonLocalAddress(Multiaddr),
+    #[error("Maximum number of substreams open over the connection")]
+    SubstreamLimitReached,
 }
 
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum AddressError {
     #[error("Invalid protocol")]
     InvalidProtocol,
@@ -130,6 +221,7 @@ pub enum AddressError {
 }
 
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum ParseError {
     #[error("Invalid multihash: `{0:?}`")]
     InvalidMultihash(Multihash),
@@ -138,6 +230,7 @@ pub enum ParseError {
 }
 
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum SubstreamError {
     #[error("Connection closed")]
     ConnectionClosed,
@@ -150,6 +243,7 @@ pub enum SubstreamError {
 }
 
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum NegotiationError {
     #[error("multistream-select error: `{0:?}`")]
     MultistreamSelectError(crate::multistream_select::NegotiationError),
@@ -162,6 +256,7 @@ pub enum NegotiationError {
 }
 
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum NotificationError {
     #[error("Peer already exists")]
     PeerAlreadyExists,
@@ -171,9 +266,27 @@ pub enum NotificationError {
     NotificationsClogged,
     #[error("Notification stream closed")]
     NotificationStreamClosed(PeerId),
+    #[error("Notification too large: `{size}` bytes, maximum is `{max_size}` bytes")]
+    NotificationTooLarge { size: usize, max_size: usize },
+    #[error("Notification buffer limit reached")]
+    BufferLimitReached,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum ConfigError {
+    #[error("No transport specified")]
+    NoTransport,
+    #[error("Protocol name `{0}` specified more than once")]
+    DuplicateProtocol(ProtocolName),
+    #[error("Listen addresses `{0}` and `{1}` conflict")]
+    ConflictingListenAddresses(Multiaddr, Multiaddr),
+    #[error("Listen address `{0}` is not local")]
+    NonLocalListenAddress(Multiaddr),
 }
 
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum DialError {
     #[error("Tried to dial self")]
     TriedToDialSelf,
@@ -183,12 +296,171 @@ pub enum DialError {
     NoAddressAvailable(PeerId),
 }
 
+impl Error {
+    /// Get the [`ErrorKind`] of the error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::PeerDoesntExist(_) => ErrorKind::NotFound,
+            Error::PeerAlreadyExists(_) => ErrorKind::AlreadyExists,
+            Error::ProtocolNotSupported(_) => ErrorKind::Unsupported,
+            Error::AddressError(error) => error.kind(),
+            Error::ParseError(error) => error.kind(),
+            Error::IoError(kind) => (*kind).into(),
+            Error::NegotiationError(error) => error.kind(),
+            Error::SubstreamError(error) => error.kind(),
+            Error::NotificationError(error) => error.kind(),
+            Error::EssentialTaskClosed => ErrorKind::ChannelFailure,
+            Error::Unknown => ErrorKind::Other,
+            Error::CannotDialSelf(_) => ErrorKind::InvalidInput,
+            Error::TransportNotSupported(_) => ErrorKind::Unsupported,
+            Error::YamuxError(_, error) => error.into(),
+            Error::NotSupported(_) => ErrorKind::Unsupported,
+            Error::Other(_) => ErrorKind::Other,
+            Error::ProtocolAlreadyExists(_) => ErrorKind::AlreadyExists,
+            Error::Timeout => ErrorKind::Timeout,
+            Error::InvalidState => ErrorKind::Other,
+            Error::DnsAddressResolutionFailed => ErrorKind::Unreachable,
+            Error::TransportError(_) => ErrorKind::Other,
+            Error::CertificateGeneration(_) => ErrorKind::Other,
+            Error::InvalidData => ErrorKind::InvalidInput,
+            Error::InputRejected => ErrorKind::Refused,
+            Error::WebSocket(_) => ErrorKind::Other,
+            Error::InsufficientPeers => ErrorKind::NotFound,
+            Error::SubstreamDoesntExist => ErrorKind::NotFound,
+            Error::WebRtc(_) => ErrorKind::Other,
+            Error::Disconnected => ErrorKind::Closed,
+            Error::ChannelDoesntExist => ErrorKind::NotFound,
+            Error::TriedToDialSelf => ErrorKind::InvalidInput,
+            Error::AlreadyConnected => ErrorKind::AlreadyExists,
+            Error::NoAddressAvailable(_) => ErrorKind::Unreachable,
+            Error::ConnectionClosed => ErrorKind::Closed,
+            Error::Quinn(error) => match error {
+                quinn::ConnectionError::TimedOut => ErrorKind::Timeout,
+                quinn::ConnectionError::VersionMismatch => ErrorKind::Unsupported,
+                quinn::ConnectionError::TransportError(_) => ErrorKind::Other,
+                _ => ErrorKind::Closed,
+            },
+            Error::InvalidCertificate => ErrorKind::InvalidInput,
+            Error::PeerIdMismatch(_, _) => ErrorKind::InvalidInput,
+            Error::ChannelClogged => ErrorKind::ChannelFailure,
+            Error::ConnectionDoesntExist(_) => ErrorKind::NotFound,
+            // all addresses failed for the same reason or the peer is considered unreachable
+            Error::DialFailures(errors) => {
+                let mut kinds = errors.iter().map(|(_, error)| error.kind());
+
+                match kinds.next() {
+                    Some(kind) if kinds.all(|other| other == kind) => kind,
+                    _ => ErrorKind::Unreachable,
+                }
+            }
+            Error::NoMatchingConnection(_) => ErrorKind::NotFound,
+            Error::PeerBanned(_) => ErrorKind::Refused,
+            Error::PeerNotAllowed(_) => ErrorKind::Refused,
+            Error::ConfigError(error) => error.kind(),
+            Error::ConnectionRejected(_) => ErrorKind::Refused,
+            Error::NonLocalAddress(_) => ErrorKind::InvalidInput,
+            Error::SubstreamLimitReached => ErrorKind::Refused,
+        }
+    }
+}
+
+impl AddressError {
+    /// Get the [`ErrorKind`] of the error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            AddressError::InvalidProtocol | AddressError::PeerIdMissing => ErrorKind::InvalidInput,
+            AddressError::AddressNotAvailable => ErrorKind::Unreachable,
+        }
+    }
+}
+
+impl ParseError {
+    /// Get the [`ErrorKind`] of the error.
+    pub fn kind(&self) -> ErrorKind {
+        ErrorKind::InvalidInput
+    }
+}
+
+impl SubstreamError {
+    /// Get the [`ErrorKind`] of the error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            SubstreamError::ConnectionClosed => ErrorKind::Closed,
+            SubstreamError::YamuxError(error) => error.into(),
+            SubstreamError::ReadFailure(_) | SubstreamError::WriteFailure(_) => ErrorKind::Io,
+        }
+    }
+}
+
+impl NegotiationError {
+    /// Get the [`ErrorKind`] of the error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            NegotiationError::MultistreamSelectError(
+                crate::multistream_select::NegotiationError::Failed,
+            ) => ErrorKind::Unsupported,
+            NegotiationError::MultistreamSelectError(_) => ErrorKind::Other,
+            NegotiationError::SnowError(_) => ErrorKind::Other,
+            NegotiationError::ConnectionClosed => ErrorKind::Closed,
+            NegotiationError::PeerIdMissing => ErrorKind::InvalidInput,
+        }
+    }
+}
+
+impl NotificationError {
+    /// Get the [`ErrorKind`] of the error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            NotificationError::PeerAlreadyExists => ErrorKind::AlreadyExists,
+            NotificationError::InvalidState => ErrorKind::Other,
+            NotificationError::NotificationsClogged => ErrorKind::ChannelFailure,
+            NotificationError::NotificationStreamClosed(_) => ErrorKind::Closed,
+            NotificationError::NotificationTooLarge { .. } => ErrorKind::InvalidInput,
+            NotificationError::BufferLimitReached => ErrorKind::ChannelFailure,
+        }
+    }
+}
+
+impl ConfigError {
+    /// Get the [`ErrorKind`] of the error.
+    pub fn kind(&self) -> ErrorKind {
+        ErrorKind::InvalidInput
+    }
+}
+
+impl DialError {
+    /// Get the [`ErrorKind`] of the error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            DialError::TriedToDialSelf => ErrorKind::InvalidInput,
+            DialError::AlreadyConnected => ErrorKind::AlreadyExists,
+            DialError::NoAddressAvailable(_) => ErrorKind::Unreachable,
+        }
+    }
+}
+
+impl From<DialError> for Error {
+    fn from(error: DialError) -> Self {
+        match error {
+            DialError::TriedToDialSelf => Error::TriedToDialSelf,
+            DialError::AlreadyConnected => Error::AlreadyConnected,
+            DialError::NoAddressAvailable(peer) => Error::NoAddressAvailable(peer),
+        }
+    }
+}
+
 impl From<MultihashGeneric<64>> for Error {
     fn from(hash: MultihashGeneric<64>) -> Self {
         Error::ParseError(ParseError::InvalidMultihash(hash))
     }
 }
 
+impl From<ConfigError> for Error {
+    fn from(error: ConfigError) -> Self {
+        Error::ConfigError(error)
+    }
+}
+
 impl From<io::Error> for Error {
     fn from(error: io::Error) -> Error {
         Error::IoError(error.kind())
@@ -265,4 +537,68 @@ mod tests {
             _ => panic!("invalid error"),
         }
     }
+
+    #[test]
+    fn error_kinds() {
+        assert_eq!(Error::Timeout.kind(), ErrorKind::Timeout);
+        assert_eq!(
+            Error::IoError(io::ErrorKind::TimedOut).kind(),
+            ErrorKind::Timeout
+        );
+        assert_eq!(
+            Error::IoError(io::ErrorKind::ConnectionRefused).kind(),
+            ErrorKind::Refused
+        );
+        assert_eq!(
+            Error::PeerBanned(PeerId::random()).kind(),
+            ErrorKind::Refused
+        );
+        assert_eq!(
+            Error::ProtocolNotSupported(String::from("/foo/1")).kind(),
+            ErrorKind::Unsupported
+        );
+        assert_eq!(
+            Error::from(crate::multistream_select::NegotiationError::Failed).kind(),
+            ErrorKind::Unsupported
+        );
+        assert_eq!(Error::EssentialTaskClosed.kind(), ErrorKind::ChannelFailure);
+        assert_eq!(Error::ChannelClogged.kind(), ErrorKind::ChannelFailure);
+        assert_eq!(
+            Error::SubstreamError(SubstreamError::YamuxError(
+                crate::yamux::ConnectionError::Closed
+            ))
+            .kind(),
+            ErrorKind::Closed
+        );
+        assert_eq!(
+            Error::from(DialError::AlreadyConnected).kind(),
+            ErrorKind::AlreadyExists
+        );
+    }
+
+    #[test]
+    fn dial_failures_kind() {
+        let address = Multiaddr::empty();
+
+        assert_eq!(
+            Error::DialFailures(vec![
+                (address.clone(), Error::Timeout),
+                (address.clone(), Error::IoError(io::ErrorKind::TimedOut)),
+            ])
+            .kind(),
+            ErrorKind::Timeout
+        );
+        assert_eq!(
+            Error::DialFailures(vec![
+                (address.clone(), Error::Timeout),
+                (
+                    address.clone(),
+                    Error::IoError(io::ErrorKind::ConnectionRefused)
+                ),
+            ])
+            .kind(),
+            ErrorKind::Unreachable
+        );
+        assert_eq!(Error::DialFailures(vec![]).kind(), ErrorKind::Unreachable);
+    }
 }
@@ -0,0 +1,249 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! External addresses of the local node.
+//!
+//! External addresses are the addresses through which remote peers can reach the local node,
+//! as opposed to the addresses the transports are bound to. They are either added by the user
+//! or learned from the addresses remote peers report to have observed the local node at.
+//!
+//! An observed address is first a candidate and becomes confirmed once enough distinct peers
+//! have reported it. Addresses added by the user are confirmed immediately. Only confirmed
+//! addresses are advertised to remote peers, together with the listen addresses.
+
+use crate::PeerId;
+
+use multiaddr::{Multiaddr, Protocol};
+use multihash::Multihash;
+use parking_lot::RwLock;
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::external-addresses";
+
+/// Default number of distinct peers that must observe an address before it's confirmed.
+pub(crate) const DEFAULT_CONFIRMATIONS: usize = 3;
+
+/// Maximum number of candidate addresses tracked at a time.
+const MAX_CANDIDATES: usize = 32;
+
+/// Confidence that the local node is reachable through an address.
+///
+/// The levels are ordered from the lowest confidence to the highest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AddressConfidence {
+    /// Address a transport is bound to.
+    ///
+    /// The node is reachable through the address only if it's not behind a NAT or a firewall.
+    Listen,
+
+    /// External address added by the user or observed by enough distinct remote peers.
+    Confirmed,
+}
+
+/// Address through which the local node believes it's reachable.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PublicAddress {
+    /// Address, ending in `/p2p/<local peer ID>`.
+    pub address: Multiaddr,
+
+    /// Confidence that the local node is reachable through the address.
+    pub confidence: AddressConfidence,
+}
+
+/// Shared state of the external addresses.
+#[derive(Debug)]
+struct Inner {
+    /// Confirmed external addresses.
+    confirmed: HashSet<Multiaddr>,
+
+    /// Candidate addresses and the peers who have observed them.
+    candidates: HashMap<Multiaddr, HashSet<PeerId>>,
+
+    /// Number of distinct observers required to confirm a candidate.
+    confirmations: usize,
+}
+
+/// External addresses of the local node.
+///
+/// Cloning is cheap and all clones refer to the same set of addresses.
+#[derive(Debug, Clone)]
+pub(crate) struct ExternalAddresses {
+    /// Local peer ID.
+    local_peer_id: PeerId,
+
+    /// Shared state.
+    inner: Arc<RwLock<Inner>>,
+}
+
+impl ExternalAddresses {
+    /// Create new [`ExternalAddresses`].
+    pub(crate) fn new(local_peer_id: PeerId) -> Self {
+        Self {
+            local_peer_id,
+            inner: Arc::new(RwLock::new(Inner {
+                confirmed: HashSet::new(),
+                candidates: HashMap::new(),
+                confirmations: DEFAULT_CONFIRMATIONS,
+            })),
+        }
+    }
+
+    /// Set the number of distinct peers that must observe an address before it's confirmed.
+    pub(crate) fn set_confirmations(&self, confirmations: usize) {
+        self.inner.write().confirmations = std::cmp::max(confirmations, 1);
+    }
+
+    /// Append `/p2p/<local peer ID>` to `address` if it's missing.
+    ///
+    /// Returns `None` if `address` is empty or belongs to another peer.
+    fn normalize(&self, address: Multiaddr) -> Option<Multiaddr> {
+        match address.iter().last() {
+            None => None,
+            Some(Protocol::P2p(multihash)) => (PeerId::from_multihash(multihash).ok()
+                == Some(self.local_peer_id))
+            .then_some(address),
+            Some(_) => Some(address.with(Protocol::P2p(Multihash::from(self.local_peer_id)))),
+        }
+    }
+
+    /// Add confirmed external address.
+    ///
+    /// Returns `false` if the address was invalid or it was already confirmed.
+    pub(crate) fn add(&self, address: Multiaddr) -> bool {
+        let Some(address) = self.normalize(address) else {
+            return false;
+        };
+        let mut inner = self.inner.write();
+
+        inner.candidates.remove(&address);
+        inner.confirmed.insert(address)
+    }
+
+    /// Remove external address, whether it's confirmed or a candidate.
+    ///
+    /// Returns `true` if the address existed.
+    pub(crate) fn remove(&self, address: Multiaddr) -> bool {
+        let Some(address) = self.normalize(address) else {
+            return false;
+        };
+        let mut inner = self.inner.write();
+
+        inner.candidates.remove(&address).is_some() | inner.confirmed.remove(&address)
+    }
+
+    /// Register that `peer` has observed the local node at `address`.
+    ///
+    /// Returns `true` if the address became confirmed.
+    pub(crate) fn on_address_observed(&self, peer: PeerId, address: Multiaddr) -> bool {
+        let Some(address) = self.normalize(address) else {
+            return false;
+        };
+        let mut inner = self.inner.write();
+
+        if inner.confirmed.contains(&address) {
+            return false;
+        }
+
+        if !inner.candidates.contains_key(&address) && inner.candidates.len() >= MAX_CANDIDATES {
+            // make room for the new candidate by evicting the least observed candidate
+            if let Some(evicted) = inner
+                .candidates
+                .iter()
+                .min_by_key(|(_, observers)| observers.len())
+                .map(|(address, _)| address.clone())
+            {
+                inner.candidates.remove(&evicted);
+            }
+        }
+
+        let confirmations = inner.confirmations;
+        let observers = inner.candidates.entry(address.clone()).or_default();
+        observers.insert(peer);
+
+        if observers.len() < confirmations {
+            return false;
+        }
+
+        tracing::debug!(target: LOG_TARGET, ?address, "external address confirmed");
+
+        inner.candidates.remove(&address);
+        inner.confirmed.insert(address)
+    }
+
+    /// Get confirmed external addresses.
+    pub(crate) fn confirmed(&self) -> Vec<Multiaddr> {
+        self.inner.read().confirmed.iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn observed_address_confirmed() {
+        let local_peer_id = PeerId::random();
+        let addresses = ExternalAddresses::new(local_peer_id);
+        let address = "/ip4/1.1.1.1/tcp/8888".parse::<Multiaddr>().unwrap();
+        let confirmed = address.clone().with(Protocol::P2p(Multihash::from(local_peer_id)));
+        let peer = PeerId::random();
+
+        addresses.set_confirmations(2);
+
+        // repeated observations from the same peer don't confirm the address
+        assert!(!addresses.on_address_observed(peer, address.clone()));
+        assert!(!addresses.on_address_observed(peer, address.clone()));
+        assert!(addresses.confirmed().is_empty());
+
+        assert!(addresses.on_address_observed(PeerId::random(), address.clone()));
+        assert_eq!(addresses.confirmed(), vec![confirmed.clone()]);
+
+        assert!(addresses.remove(address));
+        assert!(addresses.confirmed().is_empty());
+    }
+
+    #[test]
+    fn added_address_confirmed() {
+        let local_peer_id = PeerId::random();
+        let addresses = ExternalAddresses::new(local_peer_id);
+        let address = "/ip4/1.1.1.1/tcp/8888".parse::<Multiaddr>().unwrap();
+
+        assert!(addresses.add(address.clone()));
+        assert!(!addresses.add(address.clone().with(Protocol::P2p(Multihash::from(local_peer_id)))));
+        assert_eq!(
+            addresses.confirmed(),
+            vec![address.with(Protocol::P2p(Multihash::from(local_peer_id)))]
+        );
+
+        // address of another peer is rejected
+        assert!(!addresses.add(
+            "/ip4/1.1.1.1/tcp/8888"
+                .parse::<Multiaddr>()
+                .unwrap()
+                .with(Protocol::P2p(Multihash::from(PeerId::random())))
+        ));
+        assert_eq!(addresses.confirmed().len(), 1);
+    }
+}
@@ -20,31 +20,54 @@
 
 use crate::{
     config::Litep2pConfig,
+    executor::Executor,
+    peer_store::PeerStore,
     protocol::{
-        libp2p::{bitswap::Bitswap, identify::Identify, kademlia::Kademlia, ping::Ping},
+        libp2p::{
+            bitswap::Bitswap,
+            identify::Identify,
+            kademlia::Kademlia,
+            ping::{self, Ping},
+        },
         mdns::Mdns,
-        notification::NotificationProtocol,
-        request_response::RequestResponseProtocol,
+        notification::{Config as NotificationConfig, NotificationProtocol},
+        request_response::{Config as RequestResponseConfig, RequestResponseProtocol},
+        UserProtocol,
     },
+    reputation::Reputation,
     transport::{
-        manager::{SupportedTransport, TransportManager},
-        quic::QuicTransport,
-        tcp::TcpTransport,
-        webrtc::WebRtcTransport,
-        websocket::WebSocketTransport,
+        connection_rate::{ConnectionRates, Source, SourceStats},
+        dns::DnsResolver,
+        limits::LimitsHandle,
+        manager::{ConnectionApprovalHandle, SupportedTransport, TransportManager},
+        quic::{config::Config as QuicConfig, PathStats, QuicConnections, QuicTransport},
+        tcp::{config::Config as TcpConfig, TcpTransport},
+        webrtc::{config::Config as WebRtcConfig, WebRtcTransport},
+        websocket::{config::Config as WebSocketConfig, WebSocketTransport},
+        ConnectionInfo, DisconnectReason, RejectReason, ShutdownSummary, Transport,
         TransportBuilder, TransportEvent,
     },
 };
 
+use futures::{Stream, StreamExt};
 use multiaddr::{Multiaddr, Protocol};
 use multihash::Multihash;
-use transport::{manager::TransportManagerHandle, Endpoint};
+use subscription::{EventStream, Subscriptions};
+use transport::{manager::TransportManagerHandle, Endpoint, RawConnection};
 use types::ConnectionId;
 
-use std::{collections::HashSet, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
 
 pub use bandwidth::BandwidthSink;
 pub use error::Error;
+pub use external_addresses::{AddressConfidence, PublicAddress};
 pub use peer_id::PeerId;
 pub use types::protocol::ProtocolName;
 
@@ -57,13 +80,17 @@ pub mod config;
 pub mod crypto;
 pub mod error;
 pub mod executor;
+pub mod peer_store;
 pub mod protocol;
+pub mod reputation;
+pub mod subscription;
 pub mod substream;
 pub mod transport;
 pub mod types;
 pub mod yamux;
 
 mod bandwidth;
+mod external_addresses;
 mod mock;
 mod multistream_select;
 
@@ -95,16 +122,238 @@ pub enum Litep2pEvent {
 
         /// Connection ID.
         connection_id: ConnectionId,
+
+        /// Reason why the connection was closed.
+        reason: DisconnectReason,
+    },
+
+    /// Negotiated connection was rejected by the local node.
+    ConnectionRejected {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// Endpoint.
+        endpoint: Endpoint,
+
+        /// Reason for the rejection.
+        reason: RejectReason,
+    },
+
+    /// Listener failed to accept an inbound connection.
+    ///
+    /// The transport keeps listening for new connections after the error.
+    ListenerError {
+        /// Error.
+        error: Error,
     },
 
     /// Failed to dial peer.
     DialFailure {
         /// Address of the peer.
+        ///
+        /// Empty if the peer was dialed through its known addresses, in which case `error` is
+        /// [`Error::DialFailures`] listing every address that was tried.
         address: Multiaddr,
 
         /// Dial error.
         error: Error,
     },
+
+    /// Inbound connection awaiting approval.
+    ///
+    /// Only reported if connection approval has been enabled with
+    /// [`ConfigBuilder::with_connection_approval()`](crate::config::ConfigBuilder::with_connection_approval).
+    /// The connection must be accepted or rejected using `approval_handle`.
+    IncomingConnection {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// Address of the connection.
+        address: Multiaddr,
+
+        /// Handle for accepting or rejecting the connection.
+        approval_handle: ConnectionApprovalHandle,
+    },
+
+    /// Peer was banned.
+    ///
+    /// Reported when a ban is placed on the peer, either by the user or by an installed
+    /// protocol. Connections to the peer are closed and new connections are refused until
+    /// the ban expires.
+    PeerBanned {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// Duration of the ban.
+        duration: Duration,
+    },
+
+    /// Path statistics of a QUIC connection.
+    ///
+    /// Reported periodically if
+    /// [`path_stats_interval`](crate::transport::quic::config::Config::path_stats_interval) is
+    /// set in the QUIC transport configuration.
+    QuicPathStats {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// Connection ID.
+        connection_id: ConnectionId,
+
+        /// Path statistics.
+        stats: PathStats,
+    },
+
+    /// Local node started listening on a new address.
+    ///
+    /// Reported when an address is added to a network interface and a transport listens on all
+    /// interfaces.
+    NewListenAddress {
+        /// Listen address.
+        address: Multiaddr,
+    },
+
+    /// Local node is no longer listening on an address.
+    ///
+    /// Reported when an address is removed from a network interface and a transport listens on
+    /// all interfaces.
+    ExpiredListenAddress {
+        /// Listen address.
+        address: Multiaddr,
+    },
+
+    /// Source of inbound connections exceeded the connection rate thresholds.
+    ///
+    /// Reported at most once per source within the window configured with
+    /// [`ConfigBuilder::with_connection_rate_config()`](crate::config::ConfigBuilder::with_connection_rate_config).
+    ConnectionRateExceeded {
+        /// Source of the connections.
+        source: Source,
+
+        /// Connection statistics of the source within the current window.
+        stats: SourceStats,
+    },
+
+    /// Connecting to bootnodes has completed.
+    ///
+    /// Reported once per bootstrap started with [`Litep2p::connect_to_bootnodes()`], when
+    /// enough bootnodes have been connected to or all of them have been tried.
+    BootstrapCompleted {
+        /// Bootnodes which were connected to.
+        connected: Vec<PeerId>,
+
+        /// Addresses of the bootnodes which couldn't be connected to and the reasons for the
+        /// failures.
+        failed: Vec<(Multiaddr, Error)>,
+    },
+
+    /// Transport failed and was removed from the local node.
+    ///
+    /// The other transports keep running. If transport restart has been enabled with
+    /// [`ConfigBuilder::with_transport_restart()`](crate::config::ConfigBuilder::with_transport_restart),
+    /// the transport is started again after the event has been reported.
+    TransportFailed {
+        /// Transport that failed.
+        transport: SupportedTransport,
+
+        /// Error.
+        error: Error,
+    },
+
+    /// Ordered shutdown of the local node has completed.
+    ///
+    /// Only published to the subscriptions created with [`Litep2p::subscribe()`], see
+    /// [`Litep2p::shutdown()`] for more details.
+    ShutdownCompleted {
+        /// Summary of the shutdown.
+        summary: ShutdownSummary,
+    },
+}
+
+impl Litep2pEvent {
+    /// Get the peer the event concerns, if any.
+    pub fn peer(&self) -> Option<&PeerId> {
+        match self {
+            Self::ConnectionEstablished { peer, .. }
+            | Self::ConnectionClosed { peer, .. }
+            | Self::ConnectionRejected { peer, .. }
+            | Self::IncomingConnection { peer, .. }
+            | Self::PeerBanned { peer, .. }
+            | Self::QuicPathStats { peer, .. } => Some(peer),
+            Self::ListenerError { .. }
+            | Self::DialFailure { .. }
+            | Self::NewListenAddress { .. }
+            | Self::ExpiredListenAddress { .. }
+            | Self::ConnectionRateExceeded { .. }
+            | Self::TransportFailed { .. }
+            | Self::BootstrapCompleted { .. }
+            | Self::ShutdownCompleted { .. } => None,
+        }
+    }
+
+    /// Clone the event for a subscriber.
+    ///
+    /// Returns `None` for events which can only be delivered to one receiver, i.e.,
+    /// [`Litep2pEvent::ListenerError`], [`Litep2pEvent::DialFailure`],
+    /// [`Litep2pEvent::TransportFailed`], [`Litep2pEvent::BootstrapCompleted`] and
+    /// [`Litep2pEvent::IncomingConnection`].
+    fn try_clone(&self) -> Option<Self> {
+        match self {
+            Self::ConnectionEstablished { peer, endpoint } => Some(Self::ConnectionEstablished {
+                peer: *peer,
+                endpoint: endpoint.clone(),
+            }),
+            Self::ConnectionClosed {
+                peer,
+                connection_id,
+                reason,
+            } => Some(Self::ConnectionClosed {
+                peer: *peer,
+                connection_id: *connection_id,
+                reason: *reason,
+            }),
+            Self::ConnectionRejected {
+                peer,
+                endpoint,
+                reason,
+            } => Some(Self::ConnectionRejected {
+                peer: *peer,
+                endpoint: endpoint.clone(),
+                reason: *reason,
+            }),
+            Self::PeerBanned { peer, duration } => Some(Self::PeerBanned {
+                peer: *peer,
+                duration: *duration,
+            }),
+            Self::QuicPathStats {
+                peer,
+                connection_id,
+                stats,
+            } => Some(Self::QuicPathStats {
+                peer: *peer,
+                connection_id: *connection_id,
+                stats: *stats,
+            }),
+            Self::NewListenAddress { address } => Some(Self::NewListenAddress {
+                address: address.clone(),
+            }),
+            Self::ExpiredListenAddress { address } => Some(Self::ExpiredListenAddress {
+                address: address.clone(),
+            }),
+            Self::ConnectionRateExceeded { source, stats } => Some(Self::ConnectionRateExceeded {
+                source: *source,
+                stats: *stats,
+            }),
+            Self::ShutdownCompleted { summary } => Some(Self::ShutdownCompleted {
+                summary: summary.clone(),
+            }),
+            Self::ListenerError { .. }
+            | Self::DialFailure { .. }
+            | Self::TransportFailed { .. }
+            | Self::BootstrapCompleted { .. }
+            | Self::IncomingConnection { .. } => None,
+        }
+    }
 }
 
 /// [`Litep2p`] object.
@@ -123,11 +372,57 @@ pub struct Litep2p {
 
     /// Bandwidth sink.
     bandwidth_sink: BandwidthSink,
+
+    /// Open QUIC connections, if QUIC is enabled.
+    quic_connections: Option<QuicConnections>,
+
+    /// Executor used to run protocols installed while `Litep2p` is running.
+    executor: Arc<dyn Executor>,
+
+    /// Event subscriptions.
+    subscriptions: Subscriptions,
+
+    /// How long each stage of [`Litep2p::shutdown()`] may take.
+    shutdown_timeout: Duration,
+
+    /// Configurations of the transports which are restarted if they fail.
+    restartable_transports: HashMap<SupportedTransport, TransportConfig>,
+
+    /// Events waiting to be reported to the user.
+    pending_events: VecDeque<Litep2pEvent>,
+}
+
+/// Configuration of an enabled transport.
+#[derive(Debug, Clone)]
+enum TransportConfig {
+    /// TCP.
+    Tcp(TcpConfig),
+
+    /// QUIC.
+    Quic(QuicConfig),
+
+    /// WebRTC.
+    WebRtc(WebRtcConfig),
+
+    /// WebSocket.
+    WebSocket(WebSocketConfig),
+}
+
+impl TransportConfig {
+    /// Get the transport the configuration is for.
+    fn transport(&self) -> SupportedTransport {
+        match self {
+            Self::Tcp(_) => SupportedTransport::Tcp,
+            Self::Quic(_) => SupportedTransport::Quic,
+            Self::WebRtc(_) => SupportedTransport::WebRtc,
+            Self::WebSocket(_) => SupportedTransport::WebSocket,
+        }
+    }
 }
 
 /// Litep2p handle.
 #[derive(Clone)]
-pub struct Litep2pHandle(TransportManagerHandle);
+pub struct Litep2pHandle(TransportManagerHandle, Subscriptions);
 
 impl Litep2pHandle {
     /// Add known address for peer.
@@ -138,23 +433,195 @@ impl Litep2pHandle {
     ) -> usize {
         self.0.add_known_address(&peer, address)
     }
+
+    /// Close all connections to `peer`.
+    ///
+    /// See [`Litep2p::disconnect()`] for more details.
+    pub fn disconnect(&self, peer: PeerId) -> crate::Result<()> {
+        self.0.disconnect(peer)
+    }
+
+    /// Ban `peer` for `duration`.
+    ///
+    /// See [`Litep2p::ban_peer()`] for more details.
+    pub fn ban_peer(&self, peer: PeerId, duration: Duration) -> crate::Result<()> {
+        self.0.ban_peer(peer, duration)
+    }
+
+    /// Mark `peer` as persistent.
+    ///
+    /// See [`Litep2p::add_persistent_peer()`] for more details.
+    pub fn add_persistent_peer(&self, peer: PeerId) -> crate::Result<()> {
+        self.0.add_persistent_peer(peer)
+    }
+
+    /// Stop reconnecting to `peer`.
+    ///
+    /// See [`Litep2p::remove_persistent_peer()`] for more details.
+    pub fn remove_persistent_peer(&self, peer: PeerId) -> crate::Result<()> {
+        self.0.remove_persistent_peer(peer)
+    }
+
+    /// Mark `peer` as reserved.
+    ///
+    /// See [`Litep2p::add_reserved_peer()`] for more details.
+    pub fn add_reserved_peer(&self, peer: PeerId) -> crate::Result<()> {
+        self.0.add_reserved_peer(peer)
+    }
+
+    /// Remove `peer` from reserved peers.
+    ///
+    /// See [`Litep2p::remove_reserved_peer()`] for more details.
+    pub fn remove_reserved_peer(&self, peer: PeerId) -> crate::Result<()> {
+        self.0.remove_reserved_peer(peer)
+    }
+
+    /// Get connected peers and the number of connections open to each of them.
+    pub fn connected_peers(&self) -> HashMap<PeerId, usize> {
+        self.0.connected_peers()
+    }
+
+    /// Add external address through which remote peers can reach the local node.
+    ///
+    /// See [`Litep2p::add_external_address()`] for more details.
+    pub fn add_external_address(&self, address: Multiaddr) -> bool {
+        self.0.external_addresses().add(address)
+    }
+
+    /// Remove external address of the local node.
+    ///
+    /// See [`Litep2p::remove_external_address()`] for more details.
+    pub fn remove_external_address(&self, address: Multiaddr) -> bool {
+        self.0.external_addresses().remove(address)
+    }
+
+    /// Get the addresses through which the local node believes it's reachable.
+    ///
+    /// See [`Litep2p::public_addresses()`] for more details.
+    pub fn public_addresses(&self) -> Vec<PublicAddress> {
+        self.0.public_addresses()
+    }
+
+    /// Get handle for adjusting the limits of the running node.
+    ///
+    /// See [`Litep2p::limits()`] for more details.
+    pub fn limits(&self) -> LimitsHandle {
+        self.0.limits().clone()
+    }
+
+    /// Get reputations of remote peers.
+    ///
+    /// See [`Litep2p::reputation()`] for more details.
+    pub fn reputation(&self) -> Reputation {
+        self.0.reputation().clone()
+    }
+
+    /// Subscribe to events accepted by `filter`.
+    ///
+    /// See [`Litep2p::subscribe()`] for more details.
+    pub fn subscribe(
+        &self,
+        filter: impl Fn(&Litep2pEvent) -> bool + Send + Sync + 'static,
+    ) -> EventStream {
+        self.1.subscribe(filter)
+    }
 }
 
 impl Litep2p {
     /// Create new [`Litep2p`].
     pub fn new(mut litep2p_config: Litep2pConfig) -> crate::Result<Litep2p> {
+        litep2p_config.validate()?;
+
         let local_peer_id = PeerId::from_public_key(&litep2p_config.keypair.public().into());
         let bandwidth_sink = BandwidthSink::new();
         let mut listen_addresses = vec![];
+        let mut quic_connections = None;
 
         let supported_transports = Self::supported_transports(&litep2p_config);
-        let (mut transport_manager, transport_handle) = TransportManager::new(
+        let (mut transport_manager, transport_handle) = TransportManager::with_channel_sizes(
             litep2p_config.keypair.clone(),
             supported_transports,
             bandwidth_sink.clone(),
             litep2p_config.max_parallel_dials,
+            litep2p_config.protocol_channel_size,
+            litep2p_config.transport_channel_size,
         );
 
+        if litep2p_config.connection_approval {
+            transport_manager.enable_connection_approval();
+        }
+
+        if litep2p_config.connection_probing {
+            transport_manager.enable_connection_probing();
+        }
+
+        if let Some(lifetime) = litep2p_config.max_connection_lifetime {
+            transport_manager.set_max_connection_lifetime(lifetime);
+        }
+
+        if let Some(timeout) = litep2p_config.keep_alive_timeout {
+            transport_manager.set_keep_alive_timeout(timeout);
+        }
+
+        if let Some(max_substreams) = litep2p_config.max_substreams {
+            transport_manager.set_max_substreams(max_substreams);
+        }
+        transport_manager.set_slow_protocol_policy(litep2p_config.slow_protocol_policy);
+
+        if let Some(config) = litep2p_config.connection_rate.take() {
+            transport_manager.set_connection_rate_config(config);
+        }
+
+        if let Some(gater) = litep2p_config.connection_gater.take() {
+            transport_manager.set_connection_gater(gater);
+        }
+
+        if let Some(allow_list) = litep2p_config.allow_list.take() {
+            transport_manager.set_allow_list(allow_list);
+        }
+
+        if let Some(config) = litep2p_config.ip_limits.take() {
+            transport_manager.set_ip_limits(config);
+        }
+
+        if let Some(config) = litep2p_config.reconnect.take() {
+            transport_manager.set_reconnect_config(config);
+        }
+
+        if let Some(config) = litep2p_config.bootstrap.take() {
+            transport_manager.set_bootstrap_config(config);
+        }
+
+        if let Some(config) = litep2p_config.dial_scheduler.take() {
+            transport_manager.set_dial_scheduler_config(config);
+        }
+
+        if litep2p_config.local_only {
+            transport_manager.set_local_only();
+        }
+
+        transport_handle
+            .external_addresses()
+            .set_confirmations(litep2p_config.external_address_confirmations);
+
+        if let Some(config) = litep2p_config.reputation.take() {
+            transport_handle.reputation().set_config(config);
+        }
+
+        // load the persisted peers and start persisting the known peers periodically
+        if let Some(backend) = litep2p_config.peer_store_backend.take() {
+            let peer_store = transport_handle.peer_store().clone();
+
+            for (peer, addresses) in peer_store.set_backend(backend)? {
+                transport_manager.add_known_address(peer, addresses.into_iter());
+            }
+
+            litep2p_config.executor.run_with_name(
+                "litep2p-peer-store",
+                Box::pin(peer_store.flush_task(peer_store::FLUSH_INTERVAL)),
+            );
+        }
+
         // add known addresses to `TransportManager`, if any exist
         if !litep2p_config.known_addresses.is_empty() {
             for (peer, addresses) in litep2p_config.known_addresses {
@@ -163,55 +630,37 @@ impl Litep2p {
         }
 
         // start notification protocol event loops
-        for (protocol, config) in litep2p_config.notification_protocols.into_iter() {
-            tracing::debug!(
-                target: LOG_TARGET,
-                ?protocol,
-                "enable notification protocol",
+        for (_, config) in litep2p_config.notification_protocols.into_iter() {
+            Self::start_notification_protocol(
+                &mut transport_manager,
+                &litep2p_config.executor,
+                config,
             );
-
-            let service = transport_manager.register_protocol(
-                protocol,
-                config.fallback_names.clone(),
-                config.codec,
-            );
-            let executor = Arc::clone(&litep2p_config.executor);
-            litep2p_config.executor.run(Box::pin(async move {
-                NotificationProtocol::new(service, config, executor).run().await
-            }));
         }
 
         // start request-response protocol event loops
-        for (protocol, config) in litep2p_config.request_response_protocols.into_iter() {
-            tracing::debug!(
-                target: LOG_TARGET,
-                ?protocol,
-                "enable request-response protocol",
-            );
-
-            let service = transport_manager.register_protocol(
-                protocol,
-                config.fallback_names.clone(),
-                config.codec,
+        for (_, config) in litep2p_config.request_response_protocols.into_iter() {
+            Self::start_request_response_protocol(
+                &mut transport_manager,
+                &litep2p_config.executor,
+                config,
             );
-            litep2p_config.executor.run(Box::pin(async move {
-                RequestResponseProtocol::new(service, config).run().await
-            }));
         }
 
         // start user protocol event loops
-        for (protocol_name, protocol) in litep2p_config.user_protocols.into_iter() {
-            tracing::debug!(target: LOG_TARGET, protocol = ?protocol_name, "enable user protocol");
+        for (_, protocol) in litep2p_config.user_protocols.into_iter() {
+            Self::start_user_protocol(&mut transport_manager, &litep2p_config.executor, protocol);
+        }
 
-            let service =
-                transport_manager.register_protocol(protocol_name, Vec::new(), protocol.codec());
-            litep2p_config.executor.run(Box::pin(async move {
-                let _ = protocol.run(service).await;
-            }));
+        // connection probing is done using ping so enable it if the user didn't
+        if litep2p_config.connection_probing && litep2p_config.ping.is_none() {
+            litep2p_config.ping = Some(ping::Config::default().0);
         }
 
         // start ping protocol event loop if enabled
-        if let Some(ping_config) = litep2p_config.ping.take() {
+        if let Some(mut ping_config) = litep2p_config.ping.take() {
+            ping_config.probe_connections = litep2p_config.connection_probing;
+
             tracing::debug!(
                 target: LOG_TARGET,
                 protocol = ?ping_config.protocol,
@@ -223,9 +672,10 @@ impl Litep2p {
                 Vec::new(),
                 ping_config.codec,
             );
-            litep2p_config.executor.run(Box::pin(async move {
-                Ping::new(service, ping_config).run().await
-            }));
+            litep2p_config.executor.run_with_name(
+                "litep2p-ping",
+                Box::pin(async move { Ping::new(service, ping_config).run().await }),
+            );
         }
 
         // start kademlia protocol event loop if enabled
@@ -245,9 +695,12 @@ impl Litep2p {
                 fallback_names,
                 kademlia_config.codec,
             );
-            litep2p_config.executor.run(Box::pin(async move {
-                let _ = Kademlia::new(service, kademlia_config).run().await;
-            }));
+            litep2p_config.executor.run_with_name(
+                "litep2p-kademlia",
+                Box::pin(async move {
+                    let _ = Kademlia::new(service, kademlia_config).run().await;
+                }),
+            );
         }
 
         // start identify protocol event loop if enabled
@@ -284,97 +737,64 @@ impl Litep2p {
                 Vec::new(),
                 bitswap_config.codec,
             );
-            litep2p_config.executor.run(Box::pin(async move {
-                Bitswap::new(service, bitswap_config).run().await
-            }));
-        }
-
-        // enable tcp transport if the config exists
-        if let Some(config) = litep2p_config.tcp.take() {
-            let handle = transport_manager.transport_handle(Arc::clone(&litep2p_config.executor));
-            let (transport, transport_listen_addresses) =
-                <TcpTransport as TransportBuilder>::new(handle, config)?;
-
-            for address in transport_listen_addresses {
-                transport_manager.register_listen_address(address.clone());
-                listen_addresses.push(address.with(Protocol::P2p(
-                    Multihash::from_bytes(&local_peer_id.to_bytes()).unwrap(),
-                )));
-            }
-
-            transport_manager.register_transport(SupportedTransport::Tcp, Box::new(transport));
+            litep2p_config.executor.run_with_name(
+                "litep2p-bitswap",
+                Box::pin(async move { Bitswap::new(service, bitswap_config).run().await }),
+            );
         }
 
-        // enable quic transport if the config exists
-        if let Some(config) = litep2p_config.quic.take() {
-            let handle = transport_manager.transport_handle(Arc::clone(&litep2p_config.executor));
-            let (transport, transport_listen_addresses) =
-                <QuicTransport as TransportBuilder>::new(handle, config)?;
-
-            for address in transport_listen_addresses {
-                transport_manager.register_listen_address(address.clone());
-                listen_addresses.push(address.with(Protocol::P2p(
-                    Multihash::from_bytes(&local_peer_id.to_bytes()).unwrap(),
-                )));
+        // start the transports whose configs exist
+        let transport_configs = [
+            litep2p_config.tcp.take().map(TransportConfig::Tcp),
+            litep2p_config.quic.take().map(TransportConfig::Quic),
+            litep2p_config.webrtc.take().map(TransportConfig::WebRtc),
+            litep2p_config.websocket.take().map(TransportConfig::WebSocket),
+        ];
+        let mut restartable_transports = HashMap::new();
+
+        for config in transport_configs.into_iter().flatten() {
+            if litep2p_config.transport_restart {
+                restartable_transports.insert(config.transport(), config.clone());
             }
 
-            transport_manager.register_transport(SupportedTransport::Quic, Box::new(transport));
-        }
-
-        // enable webrtc transport if the config exists
-        if let Some(config) = litep2p_config.webrtc.take() {
-            let handle = transport_manager.transport_handle(Arc::clone(&litep2p_config.executor));
-            let (transport, transport_listen_addresses) =
-                <WebRtcTransport as TransportBuilder>::new(handle, config)?;
+            let transport = config.transport();
+            let transport_listen_addresses =
+                Self::start_transport(&mut transport_manager, &litep2p_config.executor, config)?;
 
-            for address in transport_listen_addresses {
-                transport_manager.register_listen_address(address.clone());
-                listen_addresses.push(address.with(Protocol::P2p(
-                    Multihash::from_bytes(&local_peer_id.to_bytes()).unwrap(),
-                )));
+            if transport == SupportedTransport::Quic {
+                quic_connections = Some(transport_handle.quic_connections());
             }
 
-            transport_manager.register_transport(SupportedTransport::WebRtc, Box::new(transport));
-        }
-
-        // enable websocket transport if the config exists
-        if let Some(config) = litep2p_config.websocket.take() {
-            let handle = transport_manager.transport_handle(Arc::clone(&litep2p_config.executor));
-            let (transport, transport_listen_addresses) =
-                <WebSocketTransport as TransportBuilder>::new(handle, config)?;
-
             for address in transport_listen_addresses {
-                transport_manager.register_listen_address(address.clone());
                 listen_addresses.push(address.with(Protocol::P2p(
                     Multihash::from_bytes(&local_peer_id.to_bytes()).unwrap(),
                 )));
             }
-
-            transport_manager
-                .register_transport(SupportedTransport::WebSocket, Box::new(transport));
         }
 
         // enable mdns if the config exists
         if let Some(config) = litep2p_config.mdns.take() {
             let mdns = Mdns::new(transport_handle.clone(), config, listen_addresses.clone())?;
 
-            litep2p_config.executor.run(Box::pin(async move {
-                let _ = mdns.start().await;
-            }));
+            litep2p_config.executor.run_with_name(
+                "litep2p-mdns",
+                Box::pin(async move {
+                    let _ = mdns.start().await;
+                }),
+            );
         }
 
-        // if identify was enabled, give it the enabled protocols and listen addresses and start it
+        // if identify was enabled, give it the enabled protocols and start it
         if let Some((service, mut identify_config)) = identify_info.take() {
             identify_config.protocols = transport_manager.protocols().cloned().collect();
-            let identify = Identify::new(service, identify_config, listen_addresses.clone());
+            let identify = Identify::new(service, identify_config);
 
-            litep2p_config.executor.run(Box::pin(async move {
-                let _ = identify.run().await;
-            }));
-        }
-
-        if transport_manager.installed_transports().count() == 0 {
-            return Err(Error::Other("No transport specified".to_string()));
+            litep2p_config.executor.run_with_name(
+                "litep2p-identify",
+                Box::pin(async move {
+                    let _ = identify.run().await;
+                }),
+            );
         }
 
         // verify that at least one transport is specified
@@ -389,11 +809,225 @@ impl Litep2p {
             local_peer_id,
             bandwidth_sink,
             listen_addresses,
+            quic_connections,
             transport_manager,
             transport_manager_handle: transport_handle,
+            executor: litep2p_config.executor,
+            subscriptions: Subscriptions::new(),
+            shutdown_timeout: litep2p_config.shutdown_timeout,
+            restartable_transports,
+            pending_events: VecDeque::new(),
         })
     }
 
+    /// Start transport from `config` and register it to `transport_manager`.
+    ///
+    /// Returns the listen addresses of the transport.
+    fn start_transport(
+        transport_manager: &mut TransportManager,
+        executor: &Arc<dyn Executor>,
+        config: TransportConfig,
+    ) -> crate::Result<Vec<Multiaddr>> {
+        let name = config.transport();
+        let handle = transport_manager.transport_handle(name, Arc::clone(executor));
+
+        tracing::debug!(target: LOG_TARGET, transport = ?name, "start transport");
+
+        let (transport, listen_addresses): (Box<dyn Transport<Item = TransportEvent>>, _) =
+            match config {
+                TransportConfig::Tcp(config) => {
+                    let (transport, listen_addresses) =
+                        <TcpTransport as TransportBuilder>::new(handle, config)?;
+                    (Box::new(transport), listen_addresses)
+                }
+                TransportConfig::Quic(config) => {
+                    let (transport, listen_addresses) =
+                        <QuicTransport as TransportBuilder>::new(handle, config)?;
+                    (Box::new(transport), listen_addresses)
+                }
+                TransportConfig::WebRtc(config) => {
+                    let (transport, listen_addresses) =
+                        <WebRtcTransport as TransportBuilder>::new(handle, config)?;
+                    (Box::new(transport), listen_addresses)
+                }
+                TransportConfig::WebSocket(config) => {
+                    let (transport, listen_addresses) =
+                        <WebSocketTransport as TransportBuilder>::new(handle, config)?;
+                    (Box::new(transport), listen_addresses)
+                }
+            };
+
+        for address in &listen_addresses {
+            transport_manager.register_listen_address(address.clone());
+        }
+        transport_manager.register_transport(name, transport);
+
+        Ok(listen_addresses)
+    }
+
+    /// Restart `transport` after it has failed, if transport restart is enabled.
+    ///
+    /// The listen addresses of the restarted transport which weren't known before are returned
+    /// and reported to the user as new listen addresses.
+    fn restart_transport(&mut self, transport: SupportedTransport) -> Vec<Multiaddr> {
+        let Some(config) = self.restartable_transports.get(&transport).cloned() else {
+            return Vec::new();
+        };
+
+        match Self::start_transport(&mut self.transport_manager, &self.executor, config) {
+            Ok(addresses) => addresses
+                .into_iter()
+                .map(|address| {
+                    address.with(Protocol::P2p(
+                        Multihash::from_bytes(&self.local_peer_id.to_bytes()).unwrap(),
+                    ))
+                })
+                .filter(|address| {
+                    if self.listen_addresses.contains(address) {
+                        return false;
+                    }
+
+                    self.listen_addresses.push(address.clone());
+                    true
+                })
+                .collect(),
+            Err(error) => {
+                tracing::error!(target: LOG_TARGET, ?transport, ?error, "failed to restart transport");
+                Vec::new()
+            }
+        }
+    }
+
+    /// Register notification protocol to `transport_manager` and start its event loop.
+    fn start_notification_protocol(
+        transport_manager: &mut TransportManager,
+        executor: &Arc<dyn Executor>,
+        config: NotificationConfig,
+    ) {
+        let protocol = config.protocol_name().clone();
+        tracing::debug!(target: LOG_TARGET, ?protocol, "enable notification protocol");
+
+        let service = transport_manager.register_protocol(
+            protocol.clone(),
+            config.fallback_names.clone(),
+            config.codec,
+        );
+        transport_manager.set_stream_priority(&protocol, config.stream_priority);
+        let protocol_executor = Arc::clone(executor);
+        executor.run_with_name(
+            "litep2p-notification",
+            Box::pin(async move {
+                NotificationProtocol::new(service, config, protocol_executor).run().await
+            }),
+        );
+    }
+
+    /// Register request-response protocol to `transport_manager` and start its event loop.
+    fn start_request_response_protocol(
+        transport_manager: &mut TransportManager,
+        executor: &Arc<dyn Executor>,
+        config: RequestResponseConfig,
+    ) {
+        let protocol = config.protocol_name().clone();
+        tracing::debug!(target: LOG_TARGET, ?protocol, "enable request-response protocol");
+
+        let service = transport_manager.register_protocol(
+            protocol.clone(),
+            config.fallback_names.clone(),
+            config.codec,
+        );
+        transport_manager.set_stream_priority(&protocol, config.stream_priority);
+        executor.run_with_name(
+            "litep2p-request-response",
+            Box::pin(async move { RequestResponseProtocol::new(service, config).run().await }),
+        );
+    }
+
+    /// Register user protocol to `transport_manager` and start its event loop.
+    fn start_user_protocol(
+        transport_manager: &mut TransportManager,
+        executor: &Arc<dyn Executor>,
+        protocol: Box<dyn UserProtocol>,
+    ) {
+        let protocol_name = protocol.protocol();
+        tracing::debug!(target: LOG_TARGET, protocol = ?protocol_name, "enable user protocol");
+
+        let service =
+            transport_manager.register_protocol(protocol_name, Vec::new(), protocol.codec());
+        executor.run_with_name(
+            "litep2p-user-protocol",
+            Box::pin(async move {
+                let _ = protocol.run(service).await;
+            }),
+        );
+    }
+
+    /// Check that neither `protocol` nor any of its `fallback_names` is installed.
+    fn ensure_not_installed<'a>(
+        &self,
+        protocol: &'a ProtocolName,
+        fallback_names: impl IntoIterator<Item = &'a ProtocolName>,
+    ) -> crate::Result<()> {
+        match std::iter::once(protocol)
+            .chain(fallback_names)
+            .find(|protocol| self.transport_manager.is_protocol_installed(protocol))
+        {
+            Some(protocol) => Err(Error::ProtocolAlreadyExists(protocol.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Install notification protocol while `Litep2p` is running.
+    ///
+    /// The protocol becomes available over all connections, including the ones which are
+    /// already open, and the open connections are reported to the protocol as if they had just
+    /// been established. Call fails if the protocol, or one of its fallback names, is already
+    /// installed.
+    pub fn install_notification_protocol(
+        &mut self,
+        config: NotificationConfig,
+    ) -> crate::Result<()> {
+        self.ensure_not_installed(config.protocol_name(), &config.fallback_names)?;
+        Self::start_notification_protocol(&mut self.transport_manager, &self.executor, config);
+
+        Ok(())
+    }
+
+    /// Install request-response protocol while `Litep2p` is running.
+    ///
+    /// See [`Litep2p::install_notification_protocol()`] for more details.
+    pub fn install_request_response_protocol(
+        &mut self,
+        config: RequestResponseConfig,
+    ) -> crate::Result<()> {
+        self.ensure_not_installed(config.protocol_name(), &config.fallback_names)?;
+        Self::start_request_response_protocol(&mut self.transport_manager, &self.executor, config);
+
+        Ok(())
+    }
+
+    /// Install user protocol while `Litep2p` is running.
+    ///
+    /// See [`Litep2p::install_notification_protocol()`] for more details.
+    pub fn install_user_protocol(&mut self, protocol: Box<dyn UserProtocol>) -> crate::Result<()> {
+        self.ensure_not_installed(&protocol.protocol(), [])?;
+        Self::start_user_protocol(&mut self.transport_manager, &self.executor, protocol);
+
+        Ok(())
+    }
+
+    /// Uninstall `protocol` while `Litep2p` is running.
+    ///
+    /// The protocol is no longer advertised during protocol negotiation and new inbound
+    /// substreams for it are rejected. Its event loop exits once every connection has dropped
+    /// the protocol, closing the substreams the protocol has open. Call fails if the protocol is
+    /// not installed.
+    pub fn uninstall_protocol(&mut self, protocol: &ProtocolName) -> crate::Result<()> {
+        tracing::debug!(target: LOG_TARGET, ?protocol, "uninstall protocol");
+
+        self.transport_manager.unregister_protocol(protocol)
+    }
+
     /// Collect supported transports before initializing the transports themselves.
     ///
     /// Information of the supported transports is needed to initialize protocols but
@@ -432,23 +1066,235 @@ impl Litep2p {
         self.listen_addresses.iter()
     }
 
+    /// Add external address through which remote peers can reach the local node.
+    ///
+    /// The address is confirmed immediately and advertised to remote peers over
+    /// `/ipfs/id/1.0.0`. If the address doesn't end in `/p2p/<local peer ID>`, it's appended.
+    ///
+    /// Returns `false` if the address belongs to another peer or it was already confirmed.
+    pub fn add_external_address(&self, address: Multiaddr) -> bool {
+        self.transport_manager_handle.external_addresses().add(address)
+    }
+
+    /// Remove external address, whether it was added by the user or observed by remote peers.
+    ///
+    /// Returns `true` if the address existed.
+    pub fn remove_external_address(&self, address: Multiaddr) -> bool {
+        self.transport_manager_handle.external_addresses().remove(address)
+    }
+
+    /// Get the addresses through which the local node believes it's reachable.
+    ///
+    /// The bound listen addresses are merged with the confirmed external addresses and each
+    /// address is tagged with an [`AddressConfidence`]. An address observed by remote peers is
+    /// confirmed once it has been reported by enough distinct peers, see
+    /// [`ConfigBuilder::with_external_address_confirmations()`](crate::config::ConfigBuilder::with_external_address_confirmations).
+    ///
+    /// The addresses are sorted from the highest confidence to the lowest and they are the
+    /// addresses advertised to remote peers over `/ipfs/id/1.0.0`.
+    pub fn public_addresses(&self) -> Vec<PublicAddress> {
+        self.transport_manager_handle.public_addresses()
+    }
+
     /// Get handle to bandwidth sink.
     pub fn bandwidth_sink(&self) -> BandwidthSink {
         self.bandwidth_sink.clone()
     }
 
+    /// Get handle to the DNS resolver and its cache of resolved addresses.
+    pub fn dns_resolver(&self) -> DnsResolver {
+        self.transport_manager.dns_resolver().clone()
+    }
+
+    /// Get handle to the inbound connection rates tracked by the transports.
+    pub fn connection_rates(&self) -> ConnectionRates {
+        self.transport_manager.connection_rates().clone()
+    }
+
+    /// Get handle for adjusting the limits of the running node.
+    ///
+    /// The limits are initialized from the configuration and changing them doesn't require
+    /// restarting the node. See [`LimitsHandle`] for when the changes take effect.
+    pub fn limits(&self) -> LimitsHandle {
+        self.transport_manager_handle.limits().clone()
+    }
+
+    /// Get peer store.
+    ///
+    /// The store is shared with the installed protocols and records what the local node knows
+    /// about each peer it has learned of.
+    pub fn peer_store(&self) -> PeerStore {
+        self.transport_manager_handle.peer_store().clone()
+    }
+
+    /// Get reputations of remote peers.
+    ///
+    /// The reputations are shared with the installed protocols which report the behavior of
+    /// remote peers. See [`reputation`] for more details.
+    pub fn reputation(&self) -> Reputation {
+        self.transport_manager_handle.reputation().clone()
+    }
+
+    /// Get path statistics of an open QUIC connection.
+    ///
+    /// Returns `None` if QUIC is not enabled or `connection_id` doesn't refer to an open
+    /// QUIC connection.
+    pub fn quic_path_stats(&self, connection_id: ConnectionId) -> Option<PathStats> {
+        self.quic_connections.as_ref()?.read().get(&connection_id).map(PathStats::from)
+    }
+
+    /// Stop `transport` while the rest of the node keeps running.
+    ///
+    /// The listeners of the transport are closed and reported in
+    /// [`Litep2pEvent::ExpiredListenAddress`], connections established over the transport are
+    /// closed and its dials in progress are reported as failed. Peers are dialed over the
+    /// remaining transports from then on, and the transport isn't restarted even if transport
+    /// restart is enabled.
+    ///
+    /// Returns an error if `transport` is not running.
+    pub fn stop_transport(&mut self, transport: SupportedTransport) -> crate::Result<()> {
+        let addresses = self.transport_manager.stop_transport(transport)?;
+        self.restartable_transports.remove(&transport);
+
+        for address in addresses {
+            let address = address.with(Protocol::P2p(
+                Multihash::from_bytes(&self.local_peer_id.to_bytes()).unwrap(),
+            ));
+            self.listen_addresses.retain(|listen_address| listen_address != &address);
+            self.pending_events.push_back(Litep2pEvent::ExpiredListenAddress { address });
+        }
+
+        Ok(())
+    }
+
     /// Dial peer.
     pub async fn dial(&mut self, peer: &PeerId) -> crate::Result<()> {
-        self.transport_manager.dial(*peer).await
+        self.transport_manager.dial(*peer)
     }
 
     /// Dial address.
     pub async fn dial_address(&mut self, address: Multiaddr) -> crate::Result<()> {
-        self.transport_manager.dial_address(address).await
+        self.transport_manager.dial_address(address)
+    }
+
+    /// Dial address and wait for the result of the dial.
+    ///
+    /// The returned future resolves to the ID of the dialed peer once the connection has been
+    /// established, or to the error that caused the dial to fail. The dial starts when this
+    /// function is called and the future doesn't borrow `Litep2p` but it only makes progress
+    /// while [`Litep2p::next_event()`] is polled.
+    ///
+    /// Unlike with [`Litep2p::dial_address()`], a failed dial is not reported in
+    /// [`Litep2pEvent::DialFailure`]. A successful dial is still reported in
+    /// [`Litep2pEvent::ConnectionEstablished`].
+    pub fn dial_address_with_result(
+        &mut self,
+        address: Multiaddr,
+    ) -> impl Future<Output = crate::Result<PeerId>> {
+        let rx = self.transport_manager.dial_address_with_result(address);
+
+        async move { rx.await.unwrap_or(Err(Error::EssentialTaskClosed)) }
+    }
+
+    /// Connect to `bootnodes`.
+    ///
+    /// The bootnodes are dialed in the given order with at most
+    /// [`max_concurrent_dials`](crate::transport::bootstrap::Config::max_concurrent_dials)
+    /// dials in progress at a time. Once
+    /// [`min_connected`](crate::transport::bootstrap::Config::min_connected) bootnodes have been
+    /// connected to, or all of them have been tried, the outcome is reported in
+    /// [`Litep2pEvent::BootstrapCompleted`]. Failed dials to bootnodes are not reported in
+    /// [`Litep2pEvent::DialFailure`].
+    ///
+    /// Calling this function again before the earlier bootstrap has completed starts a new
+    /// bootstrap and the bootnodes of the earlier one which haven't been dialed yet are
+    /// discarded.
+    pub fn connect_to_bootnodes(&mut self, bootnodes: Vec<Multiaddr>) {
+        self.transport_manager.connect_to_bootnodes(bootnodes)
+    }
+
+    /// Close all connections to `peer`.
+    ///
+    /// The connections are closed even if protocols have substreams open over them and every
+    /// installed protocol is notified that the connections were closed.
+    ///
+    /// Returns [`Error::PeerDoesntExist`] if there are no open connections to `peer`.
+    pub fn disconnect(&mut self, peer: &PeerId) -> crate::Result<()> {
+        self.transport_manager.disconnect(*peer)
+    }
+
+    /// Ban `peer` for `duration`.
+    ///
+    /// Open connections to the peer are closed, inbound connections from the peer are rejected
+    /// right after the handshake and dials to the peer are refused with [`Error::PeerBanned`]
+    /// until the ban expires. Banning an already-banned peer replaces the earlier ban.
+    pub fn ban_peer(&mut self, peer: PeerId, duration: Duration) {
+        self.transport_manager.ban_peer(peer, duration)
+    }
+
+    /// Mark `peer` as persistent.
+    ///
+    /// The peer is dialed if it's not connected and re-dialed whenever all connections to it
+    /// have closed or a dial to it fails, until [`Litep2p::remove_persistent_peer()`] is called.
+    /// Consecutive attempts are delayed using exponential backoff, see
+    /// [`ConfigBuilder::with_reconnect_config()`](crate::config::ConfigBuilder::with_reconnect_config).
+    ///
+    /// The peer must have a known address for the dials to succeed.
+    pub fn add_persistent_peer(&mut self, peer: PeerId) {
+        self.transport_manager.add_persistent_peer(peer)
+    }
+
+    /// Stop reconnecting to `peer`.
+    ///
+    /// Open connections to the peer are not closed.
+    pub fn remove_persistent_peer(&mut self, peer: PeerId) {
+        self.transport_manager.remove_persistent_peer(peer)
+    }
+
+    /// Mark `peer` as reserved.
+    ///
+    /// Reserved peers are persistent, see [`Litep2p::add_persistent_peer()`], and exempt from
+    /// the connection limits of the local node:
+    ///  - inbound connections from them are accepted even if they exceed the IP limits
+    ///  - their connections are never closed to make room for more reputable peers
+    ///  - their connections are kept open even if no protocol is interested in them and the
+    ///    keep-alive timeout doesn't apply to them
+    ///
+    /// Reserved peers are managed by the transport manager and are unrelated to the peer slots of
+    /// the notification protocols.
+    pub fn add_reserved_peer(&mut self, peer: PeerId) {
+        self.transport_manager.add_reserved_peer(peer)
+    }
+
+    /// Remove `peer` from reserved peers and stop reconnecting to it.
+    ///
+    /// Open connections to the peer are not closed and the connections established while the
+    /// peer was reserved are kept open until either side closes them.
+    pub fn remove_reserved_peer(&mut self, peer: PeerId) {
+        self.transport_manager.remove_reserved_peer(peer)
+    }
+
+    /// Add pre-established connection to `peer`.
+    ///
+    /// The connection must already be secured. It's multiplexed with `yamux` by the TCP
+    /// transport, which must be enabled, and the two sides must use opposite [`Role`]s.
+    /// Injected connections are not subject to connection approval.
+    ///
+    /// [`Role`]: crate::config::Role
+    pub fn add_connection(
+        &mut self,
+        peer: PeerId,
+        connection: impl RawConnection,
+    ) -> crate::Result<()> {
+        self.transport_manager.add_connection(peer, connection)
     }
 
     /// Add one ore more known addresses for peer.
     ///
+    /// The addresses are stored in the peer store and used when the peer is dialed using
+    /// [`Litep2p::dial()`]. Addresses that don't end in `/p2p/<peer>` are extended with the
+    /// `PeerId` of `peer` and addresses that belong to another peer are ignored.
+    ///
     /// Return value denotes how many addresses were added for the peer.
     // Addresses belonging to disabled/unsupported transports will be ignored.
     pub fn add_known_address(
@@ -459,35 +1305,216 @@ impl Litep2p {
         self.transport_manager.add_known_address(peer, address)
     }
 
+    /// Get connected peers and the number of connections open to each of them.
+    ///
+    /// `litep2p` keeps at most two connections open to a peer.
+    pub fn connected_peers(&self) -> HashMap<PeerId, usize> {
+        self.transport_manager_handle.connected_peers()
+    }
+
+    /// Get information about the open connections of `peer`.
+    ///
+    /// Returns an empty list if there are no connections open to `peer`.
+    pub fn connection_info(&self, peer: &PeerId) -> Vec<ConnectionInfo> {
+        self.transport_manager.connection_info(peer)
+    }
+
     /// Get [`Litep2pHandle`].
     pub fn litep2p_handle(&self) -> Litep2pHandle {
-        Litep2pHandle(self.transport_manager_handle.clone())
+        Litep2pHandle(
+            self.transport_manager_handle.clone(),
+            self.subscriptions.clone(),
+        )
+    }
+
+    /// Subscribe to events accepted by `filter`.
+    ///
+    /// Each subscription receives its own copy of the events returned by
+    /// [`Litep2p::next_event()`], allowing several components to follow the events they're
+    /// interested in, for example only connection events
+    /// ([`subscription::connection_events()`]) or only events of a specific peer
+    /// ([`subscription::peer_events()`]).
+    ///
+    /// Events are published to subscribers only while [`Litep2p::next_event()`] is polled.
+    /// [`Litep2pEvent::ListenerError`], [`Litep2pEvent::DialFailure`],
+    /// [`Litep2pEvent::TransportFailed`], [`Litep2pEvent::BootstrapCompleted`] and
+    /// [`Litep2pEvent::IncomingConnection`] can't be copied and are only returned by
+    /// [`Litep2p::next_event()`]. If a subscriber doesn't keep up with the events, the events
+    /// that don't fit into its channel are dropped.
+    pub fn subscribe(
+        &self,
+        filter: impl Fn(&Litep2pEvent) -> bool + Send + Sync + 'static,
+    ) -> EventStream {
+        self.subscriptions.subscribe(filter)
+    }
+
+    /// Shut down the local node.
+    ///
+    /// The installed protocols are uninstalled first and their event loops are given time to
+    /// exit, after which all connections are closed and finally the listeners of the transports
+    /// are unbound. Each stage may take at most the timeout configured with
+    /// [`ConfigBuilder::with_shutdown_timeout()`](crate::config::ConfigBuilder::with_shutdown_timeout)
+    /// before moving on to the next one. Events emitted during the shutdown are not reported.
+    ///
+    /// Once the shutdown has completed, [`Litep2pEvent::ShutdownCompleted`] is published to the
+    /// subscriptions and the summary of the shutdown is returned.
+    ///
+    /// Dropping [`Litep2p`] closes the protocols and connections in the same order but without
+    /// waiting for the protocols to exit or the connections to close.
+    pub async fn shutdown(&mut self) -> ShutdownSummary {
+        tracing::debug!(target: LOG_TARGET, "shut down litep2p");
+
+        let mut summary = self.transport_manager.shutdown(self.shutdown_timeout).await;
+        summary.listen_addresses = std::mem::take(&mut self.listen_addresses);
+
+        self.subscriptions.publish(&Litep2pEvent::ShutdownCompleted {
+            summary: summary.clone(),
+        });
+
+        summary
     }
 
     /// Poll next event.
     ///
-    /// This function must be called in order for litep2p to make progress.
+    /// This function must be called in order for litep2p to make progress. The event is also
+    /// published to the subscriptions created with [`Litep2p::subscribe()`].
+    ///
+    /// [`Litep2p`] also implements [`Stream`], which yields the same events.
     pub async fn next_event(&mut self) -> Option<Litep2pEvent> {
+        self.next().await
+    }
+
+    /// Poll next event from the transport manager.
+    fn poll_event(&mut self, cx: &mut Context<'_>) -> Poll<Option<Litep2pEvent>> {
         loop {
-            match self.transport_manager.next().await? {
+            if let Some(event) = self.pending_events.pop_front() {
+                return Poll::Ready(Some(event));
+            }
+
+            let Some(event) = futures::ready!(self.transport_manager.poll_next_event(cx)) else {
+                return Poll::Ready(None);
+            };
+
+            match event {
                 TransportEvent::ConnectionEstablished { peer, endpoint, .. } =>
-                    return Some(Litep2pEvent::ConnectionEstablished { peer, endpoint }),
+                    return Poll::Ready(Some(Litep2pEvent::ConnectionEstablished {
+                        peer,
+                        endpoint,
+                    })),
                 TransportEvent::ConnectionClosed {
                     peer,
                     connection_id,
+                    reason,
                 } =>
-                    return Some(Litep2pEvent::ConnectionClosed {
+                    return Poll::Ready(Some(Litep2pEvent::ConnectionClosed {
                         peer,
                         connection_id,
-                    }),
+                        reason,
+                    })),
+                TransportEvent::ConnectionRejected {
+                    peer,
+                    endpoint,
+                    reason,
+                } =>
+                    return Poll::Ready(Some(Litep2pEvent::ConnectionRejected {
+                        peer,
+                        endpoint,
+                        reason,
+                    })),
+                TransportEvent::ListenerError { error } =>
+                    return Poll::Ready(Some(Litep2pEvent::ListenerError { error })),
                 TransportEvent::DialFailure { address, error, .. } =>
-                    return Some(Litep2pEvent::DialFailure { address, error }),
+                    return Poll::Ready(Some(Litep2pEvent::DialFailure { address, error })),
+                TransportEvent::IncomingConnection {
+                    peer,
+                    address,
+                    approval_handle,
+                } =>
+                    return Poll::Ready(Some(Litep2pEvent::IncomingConnection {
+                        peer,
+                        address,
+                        approval_handle,
+                    })),
+                TransportEvent::ConnectionRateExceeded { source, stats } =>
+                    return Poll::Ready(Some(Litep2pEvent::ConnectionRateExceeded {
+                        source,
+                        stats,
+                    })),
+                TransportEvent::PeerBanned { peer, duration } =>
+                    return Poll::Ready(Some(Litep2pEvent::PeerBanned { peer, duration })),
+                TransportEvent::QuicPathStats {
+                    peer,
+                    connection_id,
+                    stats,
+                } =>
+                    return Poll::Ready(Some(Litep2pEvent::QuicPathStats {
+                        peer,
+                        connection_id,
+                        stats,
+                    })),
+                TransportEvent::NewListenAddress { address } => {
+                    let address = address.with(Protocol::P2p(
+                        Multihash::from_bytes(&self.local_peer_id.to_bytes()).unwrap(),
+                    ));
+
+                    if !self.listen_addresses.contains(&address) {
+                        self.listen_addresses.push(address.clone());
+                    }
+
+                    return Poll::Ready(Some(Litep2pEvent::NewListenAddress { address }));
+                }
+                TransportEvent::ExpiredListenAddress { address } => {
+                    let address = address.with(Protocol::P2p(
+                        Multihash::from_bytes(&self.local_peer_id.to_bytes()).unwrap(),
+                    ));
+                    self.listen_addresses.retain(|listen_address| listen_address != &address);
+
+                    return Poll::Ready(Some(Litep2pEvent::ExpiredListenAddress { address }));
+                }
+                TransportEvent::BootstrapCompleted { connected, failed } =>
+                    return Poll::Ready(Some(Litep2pEvent::BootstrapCompleted {
+                        connected,
+                        failed,
+                    })),
+                TransportEvent::TransportFailed { transport, error } => {
+                    let addresses = self.restart_transport(transport);
+                    self.pending_events.extend(
+                        addresses
+                            .into_iter()
+                            .map(|address| Litep2pEvent::NewListenAddress { address }),
+                    );
+
+                    return Poll::Ready(Some(Litep2pEvent::TransportFailed { transport, error }));
+                }
                 _ => {}
             }
         }
     }
 }
 
+impl Stream for Litep2p {
+    type Item = Litep2pEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let event = futures::ready!(self.poll_event(cx));
+
+        if let Some(event) = &event {
+            self.subscriptions.publish(event);
+        }
+
+        Poll::Ready(event)
+    }
+}
+
+impl Drop for Litep2p {
+    fn drop(&mut self) {
+        // close protocols before the connections so their event loops exit instead of
+        // observing the connections fail
+        self.transport_manager.close_protocols();
+        self.transport_manager.close_connections(&mut HashSet::new());
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
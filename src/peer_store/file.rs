@@ -0,0 +1,136 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! File-backed peer store persistence.
+
+use crate::{error::Error, peer_store::PeerStoreBackend, PeerId};
+
+use multiaddr::Multiaddr;
+use prost::Message;
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+mod schema {
+    pub(super) mod peer_store {
+        include!(concat!(env!("OUT_DIR"), "/peer_store.rs"));
+    }
+}
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::peer-store::file";
+
+/// Peer store backend which persists the known peers into a file.
+///
+/// The peers are written into a temporary file first which then replaces the earlier file
+/// so a crash in the middle of a write doesn't corrupt the stored peers.
+#[derive(Debug, Clone)]
+pub struct FileBackend {
+    /// Path to the file.
+    path: PathBuf,
+}
+
+impl FileBackend {
+    /// Create new [`FileBackend`] which stores the peers in `path`.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl PeerStoreBackend for FileBackend {
+    fn load(&self) -> crate::Result<HashMap<PeerId, Vec<Multiaddr>>> {
+        let bytes = match fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                tracing::debug!(target: LOG_TARGET, path = ?self.path, "no stored peers");
+                return Ok(HashMap::new());
+            }
+            Err(error) => return Err(error.into()),
+        };
+
+        schema::peer_store::PeerStore::decode(bytes.as_slice())?
+            .peers
+            .into_iter()
+            .map(|peer| {
+                let addresses = peer
+                    .addresses
+                    .into_iter()
+                    .map(|address| Multiaddr::try_from(address).map_err(|_| Error::InvalidData))
+                    .collect::<crate::Result<Vec<_>>>()?;
+
+                Ok((
+                    PeerId::from_bytes(&peer.peer_id).map_err(|_| Error::InvalidData)?,
+                    addresses,
+                ))
+            })
+            .collect()
+    }
+
+    fn save(&self, peers: HashMap<PeerId, Vec<Multiaddr>>) -> crate::Result<()> {
+        let store = schema::peer_store::PeerStore {
+            peers: peers
+                .into_iter()
+                .map(|(peer, addresses)| schema::peer_store::Peer {
+                    peer_id: peer.to_bytes(),
+                    addresses: addresses.into_iter().map(|address| address.to_vec()).collect(),
+                })
+                .collect(),
+        };
+
+        let mut path = self.path.clone().into_os_string();
+        path.push(".tmp");
+
+        fs::write(&path, store.encode_to_vec())?;
+        fs::rename(&path, &self.path).map_err(From::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peers_survive_roundtrip() {
+        let path = std::env::temp_dir().join(format!("litep2p-peers-{}", PeerId::random()));
+        let backend = FileBackend::new(&path);
+        let peer = PeerId::random();
+        let address = "/ip4/127.0.0.1/tcp/8888".parse::<Multiaddr>().unwrap();
+
+        // nothing has been stored yet
+        assert!(backend.load().unwrap().is_empty());
+
+        backend.save(HashMap::from_iter([(peer, vec![address.clone()])])).unwrap();
+        assert_eq!(
+            backend.load().unwrap(),
+            HashMap::from_iter([(peer, vec![address])])
+        );
+
+        fs::write(&path, [0xff, 0xff]).unwrap();
+        assert!(backend.load().is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
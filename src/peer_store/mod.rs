@@ -0,0 +1,592 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Peer store shared by the transport manager and the installed protocols.
+//!
+//! The store records what the local node knows about each peer: its known addresses, the
+//! protocols it supports, the history of its connections and any metadata attached to it by
+//! the user.
+//!
+//! The store also tracks the outcome of each dial made to an address of a peer. Addresses which
+//! repeatedly fail to be dialed are put into an exponentially increasing backoff during which
+//! the transport manager doesn't dial them when a peer is dialed using its [`PeerId`].
+//!
+//! Known peers and their addresses can be persisted across restarts by configuring a
+//! [`PeerStoreBackend`] with
+//! [`ConfigBuilder::with_peer_store_backend()`](crate::config::ConfigBuilder::with_peer_store_backend).
+
+use crate::{types::protocol::ProtocolName, PeerId};
+
+use multiaddr::Multiaddr;
+use parking_lot::{Mutex, RwLock};
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Weak,
+    },
+    time::{Duration, Instant},
+};
+
+pub use file::FileBackend;
+
+mod file;
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::peer-store";
+
+/// How often are the known peers persisted if a persistence backend has been configured.
+pub(crate) const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Number of consecutive dial failures after which an address is put into backoff.
+const BACKOFF_THRESHOLD: u32 = 2;
+
+/// Backoff applied to an address when it reaches [`BACKOFF_THRESHOLD`].
+///
+/// The backoff is doubled for each consecutive failure after that.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Maximum backoff of an address.
+const MAX_BACKOFF: Duration = Duration::from_secs(10 * 60);
+
+/// Connection history of a peer.
+#[derive(Debug, Default, Clone)]
+pub struct ConnectionHistory {
+    /// Number of connections established with the peer.
+    pub num_established: usize,
+
+    /// Number of failed dials to the peer.
+    pub num_dial_failures: usize,
+
+    /// When was the last connection to the peer established.
+    pub last_connected: Option<Instant>,
+
+    /// When was the last connection to the peer closed.
+    pub last_disconnected: Option<Instant>,
+}
+
+/// Dial statistics of an address.
+#[derive(Debug, Default, Clone)]
+pub struct AddressStats {
+    /// Number of successful dials to the address.
+    pub num_successes: usize,
+
+    /// Number of failed dials to the address.
+    pub num_failures: usize,
+
+    /// Number of failed dials since the last successful dial.
+    pub consecutive_failures: u32,
+
+    /// When was the address last dialed successfully.
+    pub last_success: Option<Instant>,
+
+    /// Until when is the address in backoff.
+    pub backoff_until: Option<Instant>,
+}
+
+impl AddressStats {
+    /// Check if the address is in backoff at `now`.
+    pub fn is_backed_off(&self, now: Instant) -> bool {
+        self.backoff_until.is_some_and(|until| until > now)
+    }
+}
+
+/// Information the local node has about a peer.
+#[derive(Debug, Default, Clone)]
+pub struct PeerRecord {
+    /// Known addresses of the peer.
+    pub addresses: HashSet<Multiaddr>,
+
+    /// Protocols supported by the peer, as reported by the peer over `/ipfs/id/1.0.0` or
+    /// observed from substreams negotiated with the peer.
+    pub protocols: HashSet<ProtocolName>,
+
+    /// Protocols the peer has refused to negotiate while connected to the local node.
+    pub unsupported_protocols: HashSet<ProtocolName>,
+
+    /// Connection history.
+    pub history: ConnectionHistory,
+
+    /// Dial statistics of the addresses of the peer.
+    pub address_stats: HashMap<Multiaddr, AddressStats>,
+
+    /// Metadata attached to the peer by the user.
+    pub metadata: HashMap<String, Vec<u8>>,
+}
+
+/// Persistence backend of the peer store.
+pub trait PeerStoreBackend: Debug + Send + Sync + 'static {
+    /// Load the stored peers and their addresses.
+    fn load(&self) -> crate::Result<HashMap<PeerId, Vec<Multiaddr>>>;
+
+    /// Store `peers` and their addresses, replacing the earlier stored peers.
+    fn save(&self, peers: HashMap<PeerId, Vec<Multiaddr>>) -> crate::Result<()>;
+}
+
+/// Shared state of the peer store.
+#[derive(Debug, Default)]
+struct Inner {
+    /// Known peers.
+    peers: RwLock<HashMap<PeerId, PeerRecord>>,
+
+    /// Persistence backend, if configured.
+    backend: Mutex<Option<Box<dyn PeerStoreBackend>>>,
+
+    /// Have the known addresses changed since they were last persisted.
+    dirty: AtomicBool,
+}
+
+impl Inner {
+    /// Persist the known peers if they have changed since they were last persisted.
+    fn flush(&self) -> crate::Result<()> {
+        let backend = self.backend.lock();
+        let Some(backend) = backend.as_ref() else {
+            return Ok(());
+        };
+
+        if !self.dirty.swap(false, Ordering::AcqRel) {
+            return Ok(());
+        }
+
+        let peers = self
+            .peers
+            .read()
+            .iter()
+            .filter(|(_, record)| !record.addresses.is_empty())
+            .map(|(peer, record)| (*peer, record.addresses.iter().cloned().collect()))
+            .collect::<HashMap<_, _>>();
+
+        tracing::trace!(target: LOG_TARGET, num_peers = ?peers.len(), "persist known peers");
+
+        backend.save(peers).inspect_err(|_| self.dirty.store(true, Ordering::Release))
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        if let Err(error) = self.flush() {
+            tracing::warn!(target: LOG_TARGET, ?error, "failed to persist known peers");
+        }
+    }
+}
+
+/// Peer store.
+///
+/// Cloning the store is cheap and all clones refer to the same set of peers.
+#[derive(Debug, Default, Clone)]
+pub struct PeerStore {
+    /// Shared state.
+    inner: Arc<Inner>,
+}
+
+impl PeerStore {
+    /// Create new [`PeerStore`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set persistence backend of the store.
+    ///
+    /// Returns the peers that were stored by the backend. The known peers are persisted
+    /// periodically once [`PeerStore::flush_task()`] is polled and when the last reference to
+    /// the store is dropped.
+    pub(crate) fn set_backend(
+        &self,
+        backend: Box<dyn PeerStoreBackend>,
+    ) -> crate::Result<HashMap<PeerId, Vec<Multiaddr>>> {
+        let peers = backend.load()?;
+
+        tracing::debug!(target: LOG_TARGET, num_peers = ?peers.len(), "loaded stored peers");
+
+        *self.inner.backend.lock() = Some(backend);
+        Ok(peers)
+    }
+
+    /// Persist the known peers if they have changed since they were last persisted.
+    ///
+    /// This is a no-op if no persistence backend has been configured.
+    pub fn flush(&self) -> crate::Result<()> {
+        self.inner.flush()
+    }
+
+    /// Get task which persists the known peers every `interval`.
+    ///
+    /// The task exits once all other references to the store have been dropped.
+    pub(crate) fn flush_task(&self, interval: Duration) -> impl std::future::Future<Output = ()> {
+        let inner: Weak<Inner> = Arc::downgrade(&self.inner);
+
+        async move {
+            loop {
+                tokio::time::sleep(interval).await;
+
+                let Some(inner) = inner.upgrade() else {
+                    return;
+                };
+
+                if let Err(error) = inner.flush() {
+                    tracing::warn!(target: LOG_TARGET, ?error, "failed to persist known peers");
+                }
+            }
+        }
+    }
+
+    /// Get the record of `peer`.
+    ///
+    /// The returned record is a snapshot and isn't updated afterwards.
+    pub fn peer(&self, peer: &PeerId) -> Option<PeerRecord> {
+        self.inner.peers.read().get(peer).cloned()
+    }
+
+    /// Get all peers in the store.
+    pub fn peers(&self) -> Vec<PeerId> {
+        self.inner.peers.read().keys().copied().collect()
+    }
+
+    /// Get the peers known to support `protocol`.
+    pub fn peers_supporting(&self, protocol: &ProtocolName) -> Vec<PeerId> {
+        self.inner
+            .peers
+            .read()
+            .iter()
+            .filter_map(|(peer, record)| record.protocols.contains(protocol).then_some(*peer))
+            .collect()
+    }
+
+    /// Attach metadata to `peer` under `key`, replacing any earlier value.
+    pub fn set_metadata(&self, peer: PeerId, key: impl Into<String>, value: Vec<u8>) {
+        self.inner
+            .peers
+            .write()
+            .entry(peer)
+            .or_default()
+            .metadata
+            .insert(key.into(), value);
+    }
+
+    /// Get the metadata attached to `peer` under `key`.
+    pub fn metadata(&self, peer: &PeerId, key: &str) -> Option<Vec<u8>> {
+        self.inner.peers.read().get(peer)?.metadata.get(key).cloned()
+    }
+
+    /// Remove the metadata attached to `peer` under `key`.
+    pub fn remove_metadata(&self, peer: &PeerId, key: &str) -> Option<Vec<u8>> {
+        self.inner.peers.write().get_mut(peer)?.metadata.remove(key)
+    }
+
+    /// Record known addresses of `peer`.
+    pub(crate) fn add_addresses(
+        &self,
+        peer: PeerId,
+        addresses: impl IntoIterator<Item = Multiaddr>,
+    ) {
+        let mut peers = self.inner.peers.write();
+        let record = peers.entry(peer).or_default();
+        let num_addresses = record.addresses.len();
+
+        record.addresses.extend(addresses);
+
+        if record.addresses.len() != num_addresses {
+            self.inner.dirty.store(true, Ordering::Release);
+        }
+    }
+
+    /// Record the protocols supported by `peer`, replacing the earlier protocols.
+    pub(crate) fn set_protocols(
+        &self,
+        peer: PeerId,
+        protocols: impl IntoIterator<Item = ProtocolName>,
+    ) {
+        let mut peers = self.inner.peers.write();
+        let record = peers.entry(peer).or_default();
+
+        record.protocols = protocols.into_iter().collect();
+        record
+            .unsupported_protocols
+            .retain(|protocol| !record.protocols.contains(protocol));
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            ?peer,
+            protocols = ?record.protocols,
+            "update supported protocols",
+        );
+    }
+
+    /// Check if `peer` supports `protocol`.
+    ///
+    /// Returns `Some(false)` if `peer` has refused to negotiate `protocol` while connected to the
+    /// local node and `None` if it's not known whether `peer` supports `protocol`.
+    ///
+    /// Protocols missing from the protocols `peer` reported over `/ipfs/id/1.0.0` are not
+    /// considered unsupported since the peer may still accept them as fallback names.
+    pub fn supports_protocol(&self, peer: &PeerId, protocol: &ProtocolName) -> Option<bool> {
+        let peers = self.inner.peers.read();
+        let record = peers.get(peer)?;
+
+        if record.protocols.contains(protocol) {
+            return Some(true);
+        }
+
+        record.unsupported_protocols.contains(protocol).then_some(false)
+    }
+
+    /// Record that `protocol` was negotiated with `peer`.
+    pub(crate) fn on_protocol_negotiated(&self, peer: PeerId, protocol: ProtocolName) {
+        let mut peers = self.inner.peers.write();
+        let record = peers.entry(peer).or_default();
+
+        record.unsupported_protocols.remove(&protocol);
+        record.protocols.insert(protocol);
+    }
+
+    /// Record that `peer` refused to negotiate any of `protocols`.
+    pub(crate) fn on_protocols_rejected(
+        &self,
+        peer: PeerId,
+        protocols: impl IntoIterator<Item = ProtocolName>,
+    ) {
+        let mut peers = self.inner.peers.write();
+        let record = peers.entry(peer).or_default();
+
+        for protocol in protocols {
+            tracing::trace!(target: LOG_TARGET, ?peer, %protocol, "protocol not supported");
+
+            record.protocols.remove(&protocol);
+            record.unsupported_protocols.insert(protocol);
+        }
+    }
+
+    /// Record that a connection was established with `peer`.
+    pub(crate) fn on_connection_established(&self, peer: PeerId) {
+        let mut peers = self.inner.peers.write();
+        let history = &mut peers.entry(peer).or_default().history;
+
+        history.num_established += 1;
+        history.last_connected = Some(Instant::now());
+    }
+
+    /// Record that a connection to `peer` was closed.
+    pub(crate) fn on_connection_closed(&self, peer: PeerId) {
+        self.inner.peers.write().entry(peer).or_default().history.last_disconnected =
+            Some(Instant::now());
+    }
+
+    /// Record that all connections to `peer` have been closed.
+    ///
+    /// The protocols the peer refused to negotiate are forgotten since the peer may support them
+    /// when it reconnects.
+    pub(crate) fn on_peer_disconnected(&self, peer: PeerId) {
+        if let Some(record) = self.inner.peers.write().get_mut(&peer) {
+            record.unsupported_protocols.clear();
+        }
+    }
+
+    /// Record that dialing `peer` failed.
+    pub(crate) fn on_dial_failure(&self, peer: PeerId) {
+        self.inner.peers.write().entry(peer).or_default().history.num_dial_failures += 1;
+    }
+
+    /// Get dial statistics of the addresses of `peer`.
+    pub(crate) fn address_stats(&self, peer: &PeerId) -> HashMap<Multiaddr, AddressStats> {
+        self.inner
+            .peers
+            .read()
+            .get(peer)
+            .map(|record| record.address_stats.clone())
+            .unwrap_or_default()
+    }
+
+    /// Record that `address` of `peer` was dialed successfully, clearing its backoff.
+    pub(crate) fn on_address_dial_success(&self, peer: PeerId, address: Multiaddr) {
+        let mut peers = self.inner.peers.write();
+        let stats = peers.entry(peer).or_default().address_stats.entry(address).or_default();
+
+        stats.num_successes += 1;
+        stats.consecutive_failures = 0;
+        stats.last_success = Some(Instant::now());
+        stats.backoff_until = None;
+    }
+
+    /// Record that dialing `address` of `peer` failed.
+    ///
+    /// If the address has failed [`BACKOFF_THRESHOLD`] times in a row, it's put into backoff.
+    pub(crate) fn on_address_dial_failure(&self, peer: PeerId, address: Multiaddr) {
+        let mut peers = self.inner.peers.write();
+        let stats =
+            peers.entry(peer).or_default().address_stats.entry(address.clone()).or_default();
+
+        stats.num_failures += 1;
+        stats.consecutive_failures = stats.consecutive_failures.saturating_add(1);
+
+        if stats.consecutive_failures >= BACKOFF_THRESHOLD {
+            let backoff = INITIAL_BACKOFF
+                .saturating_mul(2u32.saturating_pow(stats.consecutive_failures - BACKOFF_THRESHOLD))
+                .min(MAX_BACKOFF);
+
+            tracing::trace!(
+                target: LOG_TARGET,
+                ?peer,
+                ?address,
+                consecutive_failures = ?stats.consecutive_failures,
+                ?backoff,
+                "address put into backoff",
+            );
+
+            stats.backoff_until = Some(Instant::now() + backoff);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata() {
+        let store = PeerStore::new();
+        let peer = PeerId::random();
+
+        assert!(store.metadata(&peer, "score").is_none());
+
+        store.set_metadata(peer, "score", vec![1]);
+        store.set_metadata(peer, "score", vec![2]);
+        assert_eq!(store.metadata(&peer, "score"), Some(vec![2]));
+
+        assert_eq!(store.remove_metadata(&peer, "score"), Some(vec![2]));
+        assert!(store.metadata(&peer, "score").is_none());
+        assert_eq!(store.peers(), vec![peer]);
+    }
+
+    #[test]
+    fn peers_supporting_protocol() {
+        let store = PeerStore::new();
+        let peer1 = PeerId::random();
+        let peer2 = PeerId::random();
+
+        store.set_protocols(
+            peer1,
+            [ProtocolName::from("/ping/1"), ProtocolName::from("/id/1")],
+        );
+        store.set_protocols(peer2, [ProtocolName::from("/ping/1")]);
+
+        let mut peers = store.peers_supporting(&ProtocolName::from("/ping/1"));
+        peers.sort();
+        let mut expected = vec![peer1, peer2];
+        expected.sort();
+
+        assert_eq!(peers, expected);
+        assert_eq!(
+            store.peers_supporting(&ProtocolName::from("/id/1")),
+            vec![peer1]
+        );
+
+        // protocols are replaced, not extended
+        store.set_protocols(peer1, [ProtocolName::from("/ping/1")]);
+        assert!(store.peers_supporting(&ProtocolName::from("/id/1")).is_empty());
+    }
+
+    #[test]
+    fn protocol_support_observed_from_negotiations() {
+        let store = PeerStore::new();
+        let peer = PeerId::random();
+        let ping = ProtocolName::from("/ping/1");
+        let notif = ProtocolName::from("/notif/1");
+
+        assert_eq!(store.supports_protocol(&peer, &ping), None);
+
+        store.on_protocol_negotiated(peer, ping.clone());
+        store.on_protocols_rejected(peer, [notif.clone()]);
+        assert_eq!(store.supports_protocol(&peer, &ping), Some(true));
+        assert_eq!(store.supports_protocol(&peer, &notif), Some(false));
+        assert_eq!(
+            store.supports_protocol(&peer, &ProtocolName::from("/id/1")),
+            None
+        );
+
+        // identify overrides earlier rejections but missing protocols stay unknown
+        store.set_protocols(peer, [ping.clone(), notif.clone()]);
+        assert_eq!(store.supports_protocol(&peer, &notif), Some(true));
+        assert_eq!(
+            store.supports_protocol(&peer, &ProtocolName::from("/id/1")),
+            None
+        );
+
+        store.on_protocols_rejected(peer, [notif.clone()]);
+
+        // the peer may support the protocols when it reconnects
+        store.on_peer_disconnected(peer);
+        assert_eq!(store.supports_protocol(&peer, &ping), Some(true));
+        assert_eq!(store.supports_protocol(&peer, &notif), None);
+    }
+
+    #[test]
+    fn connection_history() {
+        let store = PeerStore::new();
+        let peer = PeerId::random();
+
+        store.on_dial_failure(peer);
+        store.on_connection_established(peer);
+        store.on_connection_closed(peer);
+        store.on_connection_established(peer);
+
+        let history = store.peer(&peer).unwrap().history;
+        assert_eq!(history.num_established, 2);
+        assert_eq!(history.num_dial_failures, 1);
+        assert!(history.last_connected.unwrap() >= history.last_disconnected.unwrap());
+    }
+
+    #[test]
+    fn address_backoff() {
+        let store = PeerStore::new();
+        let peer = PeerId::random();
+        let address = "/ip4/127.0.0.1/tcp/8888".parse::<Multiaddr>().unwrap();
+
+        // a single failure doesn't put the address into backoff
+        store.on_address_dial_failure(peer, address.clone());
+        assert!(!store.address_stats(&peer)[&address].is_backed_off(Instant::now()));
+
+        store.on_address_dial_failure(peer, address.clone());
+        let first = store.address_stats(&peer)[&address].clone();
+        assert!(first.is_backed_off(Instant::now()));
+
+        // backoff grows exponentially
+        store.on_address_dial_failure(peer, address.clone());
+        let second = store.address_stats(&peer)[&address].clone();
+        assert!(second.backoff_until.unwrap() >= first.backoff_until.unwrap() + INITIAL_BACKOFF);
+
+        // backoff is capped
+        for _ in 0..64 {
+            store.on_address_dial_failure(peer, address.clone());
+        }
+        let stats = store.address_stats(&peer)[&address].clone();
+        assert_eq!(stats.num_failures, 67);
+        assert!(stats.backoff_until.unwrap() <= Instant::now() + MAX_BACKOFF);
+
+        // successful dial clears the backoff
+        store.on_address_dial_success(peer, address.clone());
+        let stats = store.address_stats(&peer)[&address].clone();
+        assert_eq!(stats.consecutive_failures, 0);
+        assert_eq!(stats.num_successes, 1);
+        assert!(stats.last_success.is_some());
+        assert!(!stats.is_backed_off(Instant::now()));
+    }
+}
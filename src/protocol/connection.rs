@@ -26,7 +26,16 @@ use crate::{
     types::{protocol::ProtocolName, ConnectionId, SubstreamId},
 };
 
-use tokio::sync::mpsc::{error::TrySendError, Sender, WeakSender};
+use parking_lot::Mutex;
+use tokio::{
+    sync::mpsc::{error::TrySendError, Sender, WeakSender},
+    time::Instant,
+};
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
 
 /// Connection type, from the point of view of the protocol.
 #[derive(Debug, Clone)]
@@ -49,6 +58,9 @@ pub struct ConnectionHandle {
 
     /// Connection ID.
     connection_id: ConnectionId,
+
+    /// Substreams open over the connection.
+    substreams: Arc<SubstreamCounter>,
 }
 
 impl ConnectionHandle {
@@ -60,6 +72,7 @@ impl ConnectionHandle {
         Self {
             connection_id,
             connection: ConnectionType::Active(connection),
+            substreams: Arc::new(SubstreamCounter::new()),
         }
     }
 
@@ -71,7 +84,11 @@ impl ConnectionHandle {
     pub fn downgrade(&mut self) -> Self {
         let connection = match &self.connection {
             ConnectionType::Active(connection) => {
-                let handle = Self::new(self.connection_id, connection.clone());
+                let handle = Self {
+                    connection_id: self.connection_id,
+                    connection: ConnectionType::Active(connection.clone()),
+                    substreams: self.substreams.clone(),
+                };
                 self.connection = ConnectionType::Inactive(connection.downgrade());
 
                 handle
@@ -89,6 +106,11 @@ impl ConnectionHandle {
         &self.connection_id
     }
 
+    /// Get reference to the substream counter of the connection.
+    pub(crate) fn substreams(&self) -> &Arc<SubstreamCounter> {
+        &self.substreams
+    }
+
     /// Mark connection as closed.
     pub fn close(&mut self) {
         if let ConnectionType::Active(connection) = &self.connection {
@@ -99,8 +121,10 @@ impl ConnectionHandle {
     /// Attempt to acquire permit which will keep the connection open for indefinite time.
     pub fn try_get_permit(&self) -> Option<Permit> {
         match &self.connection {
-            ConnectionType::Active(active) => Some(Permit::new(active.clone())),
-            ConnectionType::Inactive(inactive) => Some(Permit::new(inactive.upgrade()?)),
+            ConnectionType::Active(active) =>
+                Some(Permit::new(active.clone(), self.substreams.clone())),
+            ConnectionType::Inactive(inactive) =>
+                Some(Permit::new(inactive.upgrade()?, self.substreams.clone())),
         }
     }
 
@@ -113,6 +137,10 @@ impl ConnectionHandle {
         substream_id: SubstreamId,
         permit: Permit,
     ) -> crate::Result<()> {
+        if self.substreams.is_limit_exceeded() {
+            return Err(Error::SubstreamLimitReached);
+        }
+
         match &self.connection {
             ConnectionType::Active(active) => active.clone(),
             ConnectionType::Inactive(inactive) =>
@@ -145,17 +173,89 @@ impl ConnectionHandle {
     }
 }
 
+/// Number of substreams open over a connection.
+///
+/// Each [`Permit`] counts as one open substream, including the permits of substreams which are
+/// still being negotiated.
+#[derive(Debug)]
+pub(crate) struct SubstreamCounter {
+    /// Number of open substreams.
+    open: AtomicUsize,
+
+    /// When the connection last became idle.
+    idle_since: Mutex<Instant>,
+
+    /// Maximum number of open substreams.
+    limit: AtomicUsize,
+}
+
+impl SubstreamCounter {
+    /// Create new [`SubstreamCounter`] for an idle connection.
+    fn new() -> Self {
+        Self {
+            open: AtomicUsize::new(0usize),
+            idle_since: Mutex::new(Instant::now()),
+            limit: AtomicUsize::new(usize::MAX),
+        }
+    }
+
+    /// Set the maximum number of open substreams.
+    pub(crate) fn set_limit(&self, limit: usize) {
+        self.limit.store(limit, Ordering::Release);
+    }
+
+    /// Check if more substreams are open than the limit allows.
+    pub(crate) fn is_limit_exceeded(&self) -> bool {
+        self.open() > self.limit.load(Ordering::Acquire)
+    }
+
+    /// Get the number of open substreams.
+    pub(crate) fn open(&self) -> usize {
+        self.open.load(Ordering::Acquire)
+    }
+
+    /// Get the instant since which the connection has had no open substreams.
+    ///
+    /// Returns `None` if the connection has open substreams.
+    pub(crate) fn idle_since(&self) -> Option<Instant> {
+        let idle_since = self.idle_since.lock();
+
+        (self.open() == 0).then_some(*idle_since)
+    }
+}
+
 /// Type which allows the connection to be kept open.
 #[derive(Debug)]
 pub struct Permit {
     /// Active connection.
     _connection: Sender<ProtocolCommand>,
+
+    /// Substreams open over the connection.
+    substreams: Arc<SubstreamCounter>,
 }
 
 impl Permit {
     /// Create new [`Permit`] which allows the connection to be kept open.
-    pub fn new(_connection: Sender<ProtocolCommand>) -> Self {
-        Self { _connection }
+    pub(crate) fn new(
+        _connection: Sender<ProtocolCommand>,
+        substreams: Arc<SubstreamCounter>,
+    ) -> Self {
+        substreams.open.fetch_add(1usize, Ordering::AcqRel);
+
+        Self {
+            _connection,
+            substreams,
+        }
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut idle_since = self.substreams.idle_since.lock();
+
+        if self.substreams.open.fetch_sub(1usize, Ordering::AcqRel) == 1 {
+            *idle_since = Instant::now();
+        }
     }
 }
 
@@ -242,4 +342,33 @@ mod tests {
             error => panic!("invalid error: {error:?}"),
         }
     }
+
+    #[tokio::test]
+    async fn open_substream_limit_reached() {
+        let (tx, _rx) = channel(8);
+        let mut handle = ConnectionHandle::new(ConnectionId::new(), tx);
+        let mut handle = handle.downgrade();
+        handle.substreams().set_limit(1);
+
+        let permit = handle.try_get_permit().unwrap();
+        assert!(handle
+            .open_substream(
+                ProtocolName::from("/protocol/1"),
+                Vec::new(),
+                SubstreamId::new(),
+                permit,
+            )
+            .is_ok());
+
+        let permit = handle.try_get_permit().unwrap();
+        match handle.open_substream(
+            ProtocolName::from("/protocol/1"),
+            Vec::new(),
+            SubstreamId::new(),
+            permit,
+        ) {
+            Err(Error::SubstreamLimitReached) => {}
+            error => panic!("invalid error: {error:?}"),
+        }
+    }
 }
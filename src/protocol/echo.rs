@@ -0,0 +1,151 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+
+//! Echo protocols for measuring end-to-end throughput and latency.
+//!
+//! The echo protocols are meant to be installed on the measured node and they answer every
+//! notification or request they receive with the same payload. The measuring node uses the
+//! regular notification and request-response protocols with the protocol names defined here.
+
+use crate::{
+    protocol::{
+        notification::{
+            Config as NotificationConfig, ConfigBuilder as NotificationConfigBuilder,
+            NotificationEvent, NotificationHandle, ValidationResult,
+        },
+        request_response::{
+            Config as RequestResponseConfig, ConfigBuilder as RequestResponseConfigBuilder,
+            RequestResponseEvent, RequestResponseHandle,
+        },
+    },
+    types::protocol::ProtocolName,
+};
+
+use futures::StreamExt;
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::echo";
+
+/// Protocol name of the notification echo protocol.
+pub const NOTIFICATION_ECHO_PROTOCOL: &str = "/litep2p/echo/notification/1";
+
+/// Protocol name of the request echo protocol.
+pub const REQUEST_ECHO_PROTOCOL: &str = "/litep2p/echo/request/1";
+
+/// Handshake sent by both sides of the notification echo protocol.
+const ECHO_HANDSHAKE: &[u8] = b"echo";
+
+/// Notification echo protocol.
+///
+/// Accepts all inbound substreams and sends every received notification back to its sender.
+pub struct NotificationEcho {
+    /// Notification handle.
+    handle: NotificationHandle,
+}
+
+impl NotificationEcho {
+    /// Create new [`NotificationEcho`] which accepts notifications of at most `max_size` bytes.
+    ///
+    /// The returned [`Config`](NotificationConfig) must be installed to litep2p and
+    /// [`NotificationEcho::run()`] must be polled for the protocol to make progress.
+    pub fn new(max_size: usize) -> (NotificationConfig, Self) {
+        let (config, handle) = Self::client(max_size);
+
+        (config, Self { handle })
+    }
+
+    /// Create new configuration and handle for a client of the notification echo protocol.
+    pub fn client(max_size: usize) -> (NotificationConfig, NotificationHandle) {
+        NotificationConfigBuilder::new(ProtocolName::from(NOTIFICATION_ECHO_PROTOCOL))
+            .with_max_size(max_size)
+            .with_handshake(ECHO_HANDSHAKE.to_vec())
+            .with_auto_accept_inbound(true)
+            .build()
+    }
+
+    /// Run the event loop of the notification echo protocol.
+    pub async fn run(mut self) {
+        tracing::debug!(target: LOG_TARGET, "starting notification echo event loop");
+
+        while let Some(event) = self.handle.next().await {
+            match event {
+                NotificationEvent::ValidateSubstream { peer, .. } => {
+                    self.handle.send_validation_result(peer, ValidationResult::Accept);
+                }
+                NotificationEvent::NotificationReceived { peer, notification } => {
+                    if let Err(error) =
+                        self.handle.send_async_notification(peer, notification.freeze()).await
+                    {
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            ?peer,
+                            ?error,
+                            "failed to echo notification",
+                        );
+                    }
+                }
+                event => tracing::trace!(target: LOG_TARGET, ?event, "unhandled event"),
+            }
+        }
+    }
+}
+
+/// Request echo protocol.
+///
+/// Responds to every inbound request with the request payload.
+pub struct RequestEcho {
+    /// Request-response handle.
+    handle: RequestResponseHandle,
+}
+
+impl RequestEcho {
+    /// Create new [`RequestEcho`] which accepts requests of at most `max_size` bytes.
+    ///
+    /// The returned [`Config`](RequestResponseConfig) must be installed to litep2p and
+    /// [`RequestEcho::run()`] must be polled for the protocol to make progress.
+    pub fn new(max_size: usize) -> (RequestResponseConfig, Self) {
+        let (config, handle) = Self::client(max_size);
+
+        (config, Self { handle })
+    }
+
+    /// Create new configuration and handle for a client of the request echo protocol.
+    pub fn client(max_size: usize) -> (RequestResponseConfig, RequestResponseHandle) {
+        RequestResponseConfigBuilder::new(ProtocolName::from(REQUEST_ECHO_PROTOCOL))
+            .with_max_size(max_size)
+            .build()
+    }
+
+    /// Run the event loop of the request echo protocol.
+    pub async fn run(mut self) {
+        tracing::debug!(target: LOG_TARGET, "starting request echo event loop");
+
+        while let Some(event) = self.handle.next().await {
+            match event {
+                RequestResponseEvent::RequestReceived {
+                    request_id,
+                    request,
+                    ..
+                } => self.handle.send_response(request_id, request),
+                event => tracing::trace!(target: LOG_TARGET, ?event, "unhandled event"),
+            }
+        }
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Experimental handover of protocol state between protocol tasks.
+//!
+//! A protocol task which is about to be restarted, for example to apply a new configuration,
+//! can convert its [`TransportService`] into a [`ProtocolHandover`] and store minimal per-peer
+//! state and the substreams it wishes to keep open in it. The handover is then given to the
+//! restarted protocol task which resumes it.
+//!
+//! While the handover is in transit, the connections stay open and events from them are
+//! buffered in the channel of the [`TransportService`]. Substreams stored in the handover are not
+//! polled and thus remain paused, applying backpressure to the remote peer until the protocol
+//! is resumed. Because of this, the restart should be done promptly.
+
+use crate::{
+    protocol::TransportService, substream::Substream, types::protocol::ProtocolName, PeerId,
+};
+
+use bytes::Bytes;
+
+use std::collections::HashMap;
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::protocol::handover";
+
+/// State of a peer handed over to the restarted protocol.
+#[derive(Debug, Default)]
+pub struct PeerHandover {
+    /// Serialized protocol state of the peer.
+    pub state: Bytes,
+
+    /// Substreams kept open over the restart.
+    pub substreams: Vec<Substream>,
+}
+
+/// Protocol handover.
+#[derive(Debug)]
+pub struct ProtocolHandover {
+    /// Transport service of the protocol.
+    service: TransportService,
+
+    /// Per-peer state.
+    peers: HashMap<PeerId, PeerHandover>,
+}
+
+impl ProtocolHandover {
+    /// Create new [`ProtocolHandover`].
+    pub(crate) fn new(service: TransportService) -> Self {
+        Self {
+            service,
+            peers: HashMap::new(),
+        }
+    }
+
+    /// Get name of the protocol that is handed over.
+    pub fn protocol(&self) -> &ProtocolName {
+        self.service.protocol()
+    }
+
+    /// Get iterator over the peers the protocol is connected to.
+    pub fn connected_peers(&self) -> impl Iterator<Item = &PeerId> {
+        self.service.connected_peers()
+    }
+
+    /// Set serialized protocol state of `peer`, replacing any earlier state.
+    pub fn set_state(&mut self, peer: PeerId, state: Bytes) {
+        self.peers.entry(peer).or_default().state = state;
+    }
+
+    /// Keep `substream` of `peer` open over the restart.
+    pub fn add_substream(&mut self, peer: PeerId, substream: Substream) {
+        self.peers.entry(peer).or_default().substreams.push(substream);
+    }
+
+    /// Resume the handover in the restarted protocol task.
+    ///
+    /// Returns the [`TransportService`] of the protocol and the state of each peer that was
+    /// stored in the handover.
+    pub fn resume(self) -> (TransportService, HashMap<PeerId, PeerHandover>) {
+        tracing::debug!(
+            target: LOG_TARGET,
+            protocol = %self.service.protocol(),
+            num_peers = ?self.peers.len(),
+            "resume protocol handover",
+        );
+
+        (self.service, self.peers)
+    }
+}
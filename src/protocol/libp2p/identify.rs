@@ -31,9 +31,13 @@ use crate::{
     PeerId, DEFAULT_CHANNEL_SIZE,
 };
 
+use bytes::Bytes;
 use futures::{future::BoxFuture, stream::FuturesUnordered, Stream, StreamExt};
 use multiaddr::Multiaddr;
-use prost::Message;
+use prost::{
+    encoding::{decode_key, skip_field, DecodeContext},
+    Message,
+};
 use tokio::sync::mpsc::{channel, Sender};
 use tokio_stream::wrappers::ReceiverStream;
 
@@ -58,6 +62,9 @@ const DEFAULT_AGENT: &str = "litep2p/1.0.0";
 // TODO: what is the max size?
 const IDENTIFY_PAYLOAD_SIZE: usize = 4096;
 
+/// Tags of the `Identify` message fields known to the local schema.
+const KNOWN_FIELDS: [u32; 6] = [1, 2, 3, 4, 5, 6];
+
 mod identify_schema {
     include!(concat!(env!("OUT_DIR"), "/identify.rs"));
 }
@@ -139,6 +146,12 @@ pub enum IdentifyEvent {
 
         /// Listen addresses.
         listen_addresses: Vec<Multiaddr>,
+
+        /// Raw encoding of the fields not known to the local schema.
+        ///
+        /// The bytes are valid protobuf and can be appended as-is to an encoded identify message
+        /// so that extensions added by newer peers are preserved when the message is relayed.
+        unknown_fields: Bytes,
     },
 }
 
@@ -161,6 +174,26 @@ struct IdentifyResponse {
 
     /// Observed address.
     observed_address: Option<Multiaddr>,
+
+    /// Raw encoding of the fields not known to the local schema.
+    unknown_fields: Bytes,
+}
+
+/// Extract the raw encoding of the fields of `payload` that are not known to the local schema.
+fn unknown_fields(mut payload: &[u8]) -> crate::Result<Bytes> {
+    let mut unknown = Vec::new();
+
+    while !payload.is_empty() {
+        let field = payload;
+        let (tag, wire_type) = decode_key(&mut payload)?;
+        skip_field(wire_type, tag, &mut payload, DecodeContext::default())?;
+
+        if !KNOWN_FIELDS.contains(&tag) {
+            unknown.extend_from_slice(&field[..field.len() - payload.len()]);
+        }
+    }
+
+    Ok(unknown.into())
 }
 
 pub(crate) struct Identify {
@@ -182,9 +215,6 @@ pub(crate) struct Identify {
     /// User agent.
     user_agent: String,
 
-    /// Public addresses.
-    listen_addresses: HashSet<Multiaddr>,
-
     /// Protocols supported by the local node, filled by `Litep2p`.
     protocols: Vec<String>,
 
@@ -200,20 +230,16 @@ pub(crate) struct Identify {
 
 impl Identify {
     /// Create new [`Identify`] protocol.
-    pub(crate) fn new(
-        service: TransportService,
-        config: Config,
-        listen_addresses: Vec<Multiaddr>,
-    ) -> Self {
+    pub(crate) fn new(service: TransportService, config: Config) -> Self {
+        // public addresses are advertised together with the confirmed external addresses
+        for address in config.public_addresses {
+            service.external_addresses().add(address);
+        }
+
         Self {
             service,
             tx: config.tx_event,
             peers: HashMap::new(),
-            listen_addresses: config
-                .public_addresses
-                .into_iter()
-                .chain(listen_addresses.into_iter())
-                .collect(),
             public: config.public.expect("public key to be supplied"),
             protocol_version: config.protocol_version,
             user_agent: config.user_agent.unwrap_or(DEFAULT_AGENT.to_string()),
@@ -274,9 +300,10 @@ impl Identify {
             agent_version: Some(self.user_agent.clone()),
             public_key: Some(self.public.to_protobuf_encoding()),
             listen_addrs: self
-                .listen_addresses
-                .iter()
-                .map(|address| address.to_vec())
+                .service
+                .public_addresses()
+                .into_iter()
+                .map(|public| public.address.to_vec())
                 .collect::<Vec<_>>(),
             observed_addr,
             protocols: self.protocols.clone(),
@@ -345,7 +372,8 @@ impl Identify {
                     Ok(Some(Ok(payload))) => payload,
                 };
 
-            let info = identify_schema::Identify::decode(payload.to_vec().as_slice())?;
+            let info = identify_schema::Identify::decode(payload.as_ref())?;
+            let unknown_fields = unknown_fields(payload.as_ref())?;
 
             tracing::trace!(target: LOG_TARGET, ?peer, ?info, "peer identified");
 
@@ -366,6 +394,7 @@ impl Identify {
                 supported_protocols: HashSet::from_iter(info.protocols),
                 observed_address,
                 listen_addresses,
+                unknown_fields,
             })
         }));
     }
@@ -378,10 +407,10 @@ impl Identify {
             tokio::select! {
                 event = self.service.next() => match event {
                     None => return,
-                    Some(TransportEvent::ConnectionEstablished { peer, endpoint }) => {
+                    Some(TransportEvent::ConnectionEstablished { peer, endpoint, .. }) => {
                         let _ = self.on_connection_established(peer, endpoint);
                     }
-                    Some(TransportEvent::ConnectionClosed { peer }) => {
+                    Some(TransportEvent::ConnectionClosed { peer, .. }) => {
                         self.on_connection_closed(peer);
                     }
                     Some(TransportEvent::SubstreamOpened {
@@ -399,6 +428,17 @@ impl Identify {
                 _ = self.pending_inbound.next(), if !self.pending_inbound.is_empty() => {}
                 event = self.pending_outbound.next(), if !self.pending_outbound.is_empty() => match event {
                     Some(Ok(response)) => {
+                        if let Some(address) = &response.observed_address {
+                            self.service.external_addresses().on_address_observed(response.peer, address.clone());
+                        }
+
+                        let peer_store = self.service.peer_store();
+                        peer_store.add_addresses(response.peer, response.listen_addresses.iter().cloned());
+                        peer_store.set_protocols(
+                            response.peer,
+                            response.supported_protocols.iter().cloned().map(ProtocolName::from),
+                        );
+
                         let _ = self.tx
                             .send(IdentifyEvent::PeerIdentified {
                                 peer: response.peer,
@@ -407,6 +447,7 @@ impl Identify {
                                 supported_protocols: response.supported_protocols.into_iter().map(From::from).collect(),
                                 observed_address: response.observed_address.map_or(Multiaddr::empty(), |address| address),
                                 listen_addresses: response.listen_addresses,
+                                unknown_fields: response.unknown_fields,
                             })
                             .await;
                     }
@@ -417,3 +458,58 @@ impl Identify {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_fields_preserved() {
+        let identify = identify_schema::Identify {
+            protocol_version: Some("/proto/1".to_string()),
+            agent_version: Some("agent".to_string()),
+            public_key: None,
+            listen_addrs: vec![vec![1, 2, 3]],
+            observed_addr: None,
+            protocols: vec!["/echo/1".to_string()],
+        };
+        let mut payload = identify.encode_to_vec();
+
+        // field 8 (bytes) and field 9 (varint), unknown to the local schema
+        let extensions = vec![0x42, 0x03, 0xaa, 0xbb, 0xcc, 0x48, 0x96, 0x01];
+        payload.extend_from_slice(&extensions);
+
+        assert_eq!(
+            identify_schema::Identify::decode(payload.as_slice()).unwrap(),
+            identify
+        );
+        assert_eq!(
+            unknown_fields(&payload).unwrap(),
+            Bytes::from(extensions.clone())
+        );
+
+        // the unknown fields survive re-encoding when appended to the re-encoded message
+        let mut reencoded = identify.encode_to_vec();
+        reencoded.extend_from_slice(&unknown_fields(&payload).unwrap());
+        assert_eq!(unknown_fields(&reencoded).unwrap(), Bytes::from(extensions));
+    }
+
+    #[test]
+    fn no_unknown_fields() {
+        let identify = identify_schema::Identify {
+            protocol_version: Some("/proto/1".to_string()),
+            agent_version: None,
+            public_key: Some(vec![1, 2, 3]),
+            listen_addrs: Vec::new(),
+            observed_addr: Some(vec![4, 5, 6]),
+            protocols: Vec::new(),
+        };
+
+        assert!(unknown_fields(&identify.encode_to_vec()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn truncated_payload_rejected() {
+        assert!(unknown_fields(&[0x42, 0x03, 0xaa]).is_err());
+    }
+}
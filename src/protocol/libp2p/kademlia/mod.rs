@@ -674,7 +674,7 @@ impl Kademlia {
                             tracing::debug!(target: LOG_TARGET, ?error, "failed to handle established connection");
                         }
                     }
-                    Some(TransportEvent::ConnectionClosed { peer }) => {
+                    Some(TransportEvent::ConnectionClosed { peer, .. }) => {
                         self.disconnect_peer(peer, None).await;
                     }
                     Some(TransportEvent::SubstreamOpened { peer, direction, substream, .. }) => {
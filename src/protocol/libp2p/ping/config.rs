@@ -49,6 +49,9 @@ pub struct Config {
 
     /// TX channel for sending events to the user protocol.
     pub(crate) tx_event: Sender<PingEvent>,
+
+    /// Should ping results be reported to the transport manager as connection probes.
+    pub(crate) probe_connections: bool,
 }
 
 impl Config {
@@ -62,6 +65,7 @@ impl Config {
             Self {
                 tx_event,
                 max_failures: MAX_FAILURES,
+                probe_connections: false,
                 protocol: ProtocolName::from(PROTOCOL_NAME),
                 codec: ProtocolCodec::Identity(PING_PAYLOAD_SIZE),
             },
@@ -80,6 +84,9 @@ pub struct ConfigBuilder {
 
     /// Maximum failures before the peer is considered unreachable.
     max_failures: usize,
+
+    /// Size of the event channel.
+    channel_size: usize,
 }
 
 impl ConfigBuilder {
@@ -87,6 +94,7 @@ impl ConfigBuilder {
     pub fn new() -> Self {
         Self {
             max_failures: MAX_FAILURES,
+            channel_size: DEFAULT_CHANNEL_SIZE,
             protocol: ProtocolName::from(PROTOCOL_NAME),
             codec: ProtocolCodec::Identity(PING_PAYLOAD_SIZE),
         }
@@ -98,14 +106,23 @@ impl ConfigBuilder {
         self
     }
 
+    /// Configure size of the channel used to report [`PingEvent`]s.
+    ///
+    /// Default value is `4096`.
+    pub fn with_channel_size(mut self, size: usize) -> Self {
+        self.channel_size = size;
+        self
+    }
+
     /// Build [`Config`].
     pub fn build(self) -> (Config, Box<dyn Stream<Item = PingEvent> + Send + Unpin>) {
-        let (tx_event, rx_event) = channel(DEFAULT_CHANNEL_SIZE);
+        let (tx_event, rx_event) = channel(self.channel_size);
 
         (
             Config {
                 tx_event,
                 max_failures: self.max_failures,
+                probe_connections: false,
                 protocol: self.protocol,
                 codec: self.codec,
             },
@@ -76,10 +76,13 @@ pub(crate) struct Ping {
     pending_opens: HashMap<SubstreamId, PeerId>,
 
     /// Pending outbound substreams.
-    pending_outbound: FuturesUnordered<BoxFuture<'static, crate::Result<(PeerId, Duration)>>>,
+    pending_outbound: FuturesUnordered<BoxFuture<'static, (PeerId, crate::Result<Duration>)>>,
 
     /// Pending inbound substreams.
     pending_inbound: FuturesUnordered<BoxFuture<'static, crate::Result<()>>>,
+
+    /// Should ping results be reported to the transport manager as connection probes.
+    probe_connections: bool,
 }
 
 impl Ping {
@@ -93,6 +96,7 @@ impl Ping {
             pending_outbound: FuturesUnordered::new(),
             pending_inbound: FuturesUnordered::new(),
             _max_failures: config.max_failures,
+            probe_connections: config.probe_connections,
         }
     }
 
@@ -137,9 +141,9 @@ impl Ping {
             };
 
             match tokio::time::timeout(Duration::from_secs(10), future).await {
-                Err(_) => return Err(Error::Timeout),
-                Ok(Err(error)) => return Err(error),
-                Ok(Ok(elapsed)) => Ok((peer, elapsed)),
+                Err(_) => (peer, Err(Error::Timeout)),
+                Ok(Err(error)) => (peer, Err(error)),
+                Ok(Ok(elapsed)) => (peer, Ok(elapsed)),
             }
         }));
     }
@@ -168,6 +172,19 @@ impl Ping {
         }));
     }
 
+    /// Handle failure to ping remote peer.
+    ///
+    /// If connections are being probed, the failure is reported to the transport manager and
+    /// the unusable connection is closed.
+    fn on_ping_failure(&mut self, peer: PeerId, error: Error) {
+        tracing::debug!(target: LOG_TARGET, ?peer, ?error, "failed to ping peer");
+
+        if self.probe_connections {
+            self.service.report_connection_probe(peer, false);
+            let _ = self.service.force_close(peer);
+        }
+    }
+
     /// Start [`Ping`] event loop.
     pub async fn run(mut self) {
         tracing::debug!(target: LOG_TARGET, "starting ping event loop");
@@ -176,9 +193,11 @@ impl Ping {
             tokio::select! {
                 event = self.service.next() => match event {
                     Some(TransportEvent::ConnectionEstablished { peer, .. }) => {
-                        let _ = self.on_connection_established(peer);
+                        if let Err(error) = self.on_connection_established(peer) {
+                            self.on_ping_failure(peer, error);
+                        }
                     }
-                    Some(TransportEvent::ConnectionClosed { peer }) => {
+                    Some(TransportEvent::ConnectionClosed { peer, .. }) => {
                         self.on_connection_closed(peer);
                     }
                     Some(TransportEvent::SubstreamOpened {
@@ -202,13 +221,22 @@ impl Ping {
                             }
                         }
                     },
+                    Some(TransportEvent::SubstreamOpenFailure { substream, error }) => {
+                        if let Some(peer) = self.pending_opens.remove(&substream) {
+                            self.on_ping_failure(peer, error);
+                        }
+                    }
                     Some(_) => {}
                     None => return,
                 },
                 _event = self.pending_inbound.next(), if !self.pending_inbound.is_empty() => {}
                 event = self.pending_outbound.next(), if !self.pending_outbound.is_empty() => {
                     match event {
-                        Some(Ok((peer, elapsed))) => {
+                        Some((peer, Ok(elapsed))) => {
+                            if self.probe_connections {
+                                self.service.report_connection_probe(peer, true);
+                            }
+
                             let _ = self
                                 .tx
                                 .send(PingEvent::Ping {
@@ -217,7 +245,8 @@ impl Ping {
                                 })
                                 .await;
                         }
-                        event => tracing::debug!(target: LOG_TARGET, "failed to handle ping for an outbound peer: {event:?}"),
+                        Some((peer, Err(error))) => self.on_ping_failure(peer, error),
+                        None => {}
                     }
                 }
             }
@@ -24,8 +24,8 @@ use crate::{
     codec::ProtocolCodec,
     error::Error,
     substream::Substream,
-    transport::Endpoint,
-    types::{protocol::ProtocolName, SubstreamId},
+    transport::{Endpoint, SupportedTransport},
+    types::{protocol::ProtocolName, ConnectionId, SubstreamId},
     PeerId,
 };
 
@@ -33,17 +33,22 @@ use multiaddr::Multiaddr;
 
 use std::fmt::Debug;
 
-pub(crate) use connection::Permit;
+pub(crate) use connection::{ConnectionHandle, Permit};
+pub use protocol_set::SlowProtocolPolicy;
 pub(crate) use protocol_set::{InnerTransportEvent, ProtocolCommand, ProtocolSet};
 
-pub use transport_service::TransportService;
+pub use handover::{PeerHandover, ProtocolHandover};
+pub use transport_service::{ConnectionPreference, TransportService};
 
+#[cfg(feature = "testing")]
+pub mod echo;
 pub mod libp2p;
 pub mod mdns;
 pub mod notification;
 pub mod request_response;
 
 mod connection;
+mod handover;
 mod protocol_set;
 mod transport_service;
 
@@ -65,14 +70,28 @@ pub enum TransportEvent {
         /// Peer ID.
         peer: PeerId,
 
+        /// Connection ID.
+        connection_id: ConnectionId,
+
         /// Endpoint.
+        ///
+        /// Tells whether the connection is inbound ([`Endpoint::Listener`]) or outbound
+        /// ([`Endpoint::Dialer`]).
         endpoint: Endpoint,
+
+        /// Transport of the connection.
+        transport: SupportedTransport,
     },
 
     /// Connection closed to peer.
     ConnectionClosed {
         /// Peer ID.
         peer: PeerId,
+
+        /// ID of the connection that was closed.
+        ///
+        /// The event is reported once the last connection to the peer has been closed.
+        connection_id: ConnectionId,
     },
 
     /// Failed to dial peer.
@@ -91,6 +110,9 @@ pub enum TransportEvent {
         /// Peer ID.
         peer: PeerId,
 
+        /// ID of the connection over which the substream was opened.
+        connection_id: ConnectionId,
+
         /// Protocol name.
         ///
         /// One protocol handler may handle multiple sub-protocols (such as `/ipfs/identify/1.0.0`
@@ -0,0 +1,272 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Protocol-wide memory budget for buffered notifications.
+
+use crate::{
+    protocol::notification::types::{NotificationError, SheddingPolicy},
+    PeerId,
+};
+
+use bytes::Bytes;
+use parking_lot::Mutex;
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    task::{Context, Waker},
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::notification::budget";
+
+/// Buffering state of a peer.
+#[derive(Debug, Default)]
+struct PeerBuffer {
+    /// Number of bytes buffered for the peer.
+    bytes: usize,
+
+    /// Whether the peer has been selected for shedding.
+    shed: bool,
+
+    /// Waker of the peer's connection handler.
+    waker: Option<Waker>,
+}
+
+/// Buffering state of the protocol.
+#[derive(Debug, Default)]
+struct Inner {
+    /// Total number of buffered bytes.
+    total: usize,
+
+    /// Buffering states of peers.
+    peers: HashMap<PeerId, PeerBuffer>,
+}
+
+/// Cap on the total number of bytes buffered across the notification queues of all peers.
+#[derive(Debug)]
+pub(crate) struct BufferBudget {
+    /// Maximum number of buffered bytes.
+    limit: usize,
+
+    /// Policy applied when the limit is exceeded.
+    policy: SheddingPolicy,
+
+    /// Buffering state.
+    inner: Mutex<Inner>,
+}
+
+impl BufferBudget {
+    /// Create new [`BufferBudget`].
+    pub(crate) fn new(limit: usize, policy: SheddingPolicy) -> Self {
+        Self {
+            limit,
+            policy,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    /// Get the shedding policy of the budget.
+    pub(crate) fn policy(&self) -> SheddingPolicy {
+        self.policy
+    }
+
+    /// Reserve `size` bytes for a notification queued for `peer`.
+    ///
+    /// If the limit would be exceeded, the shedding policy is applied. With
+    /// [`SheddingPolicy::DropNew`] the notification is rejected, otherwise the peer with the most
+    /// buffered bytes is selected for shedding and the notification is accepted.
+    pub(crate) fn reserve(
+        self: &Arc<Self>,
+        peer: PeerId,
+        size: usize,
+    ) -> Result<Reservation, NotificationError> {
+        let mut inner = self.inner.lock();
+
+        if inner.total.saturating_add(size) > self.limit {
+            let slowest = match self.policy {
+                SheddingPolicy::DropNew => None,
+                SheddingPolicy::DropFromSlowest | SheddingPolicy::CloseSlowest => inner
+                    .peers
+                    .iter_mut()
+                    .filter(|(_, buffer)| buffer.bytes > 0)
+                    .max_by_key(|(_, buffer)| buffer.bytes),
+            };
+
+            let Some((slowest, buffer)) = slowest else {
+                tracing::trace!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    ?size,
+                    limit = ?self.limit,
+                    "buffer limit reached, dropping notification",
+                );
+                return Err(NotificationError::BufferLimitReached);
+            };
+
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?peer,
+                ?slowest,
+                buffered = ?buffer.bytes,
+                policy = ?self.policy,
+                "buffer limit reached, shedding slowest peer",
+            );
+
+            buffer.shed = true;
+            if let Some(waker) = buffer.waker.take() {
+                waker.wake();
+            }
+        }
+
+        inner.total += size;
+        inner.peers.entry(peer).or_default().bytes += size;
+
+        Ok(Reservation {
+            budget: Arc::clone(self),
+            peer,
+            size,
+        })
+    }
+
+    /// Release `size` bytes reserved for `peer`.
+    fn release(&self, peer: &PeerId, size: usize) {
+        let mut inner = self.inner.lock();
+
+        inner.total = inner.total.saturating_sub(size);
+        if let Some(buffer) = inner.peers.get_mut(peer) {
+            buffer.bytes = buffer.bytes.saturating_sub(size);
+        }
+    }
+
+    /// Check if `peer` has been selected for shedding and register the waker of its connection
+    /// handler.
+    pub(crate) fn poll_shed(&self, peer: PeerId, cx: &mut Context<'_>) -> bool {
+        let mut inner = self.inner.lock();
+        let buffer = inner.peers.entry(peer).or_default();
+
+        buffer.waker = Some(cx.waker().clone());
+        std::mem::take(&mut buffer.shed)
+    }
+
+    /// Remove `peer` from the budget after its connection handler has exited.
+    pub(crate) fn remove_peer(&self, peer: &PeerId) {
+        self.inner.lock().peers.remove(peer);
+    }
+
+    /// Get the total number of buffered bytes.
+    #[cfg(test)]
+    pub(crate) fn total(&self) -> usize {
+        self.inner.lock().total
+    }
+}
+
+/// Bytes reserved from [`BufferBudget`], released when dropped.
+#[derive(Debug)]
+pub(crate) struct Reservation {
+    /// Budget the bytes were reserved from.
+    budget: Arc<BufferBudget>,
+
+    /// Peer for whom the bytes were reserved.
+    peer: PeerId,
+
+    /// Number of reserved bytes.
+    size: usize,
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        self.budget.release(&self.peer, self.size);
+    }
+}
+
+/// Notification queued for sending to a peer.
+///
+/// If the protocol has a [`BufferBudget`], the notification holds a reservation which is
+/// released once the connection handler has dequeued the notification or the notification
+/// is dropped from the queue.
+#[derive(Debug, Clone)]
+pub(crate) struct QueuedNotification {
+    /// Notification.
+    payload: Bytes,
+
+    /// Reservation for the notification, if the protocol has a [`BufferBudget`].
+    _reservation: Option<Arc<Reservation>>,
+}
+
+impl QueuedNotification {
+    /// Create new [`QueuedNotification`].
+    pub(crate) fn new(payload: Bytes, reservation: Option<Reservation>) -> Self {
+        Self {
+            payload,
+            _reservation: reservation.map(Arc::new),
+        }
+    }
+
+    /// Take the notification, releasing its reservation.
+    pub(crate) fn into_payload(self) -> Bytes {
+        self.payload
+    }
+}
+
+impl From<Bytes> for QueuedNotification {
+    fn from(payload: Bytes) -> Self {
+        Self::new(payload, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::task::noop_waker_ref;
+
+    #[test]
+    fn reservation_released_on_drop() {
+        let budget = Arc::new(BufferBudget::new(10, SheddingPolicy::DropNew));
+        let peer = PeerId::random();
+
+        let first = budget.reserve(peer, 6).unwrap();
+        assert_eq!(budget.total(), 6);
+        assert_eq!(
+            budget.reserve(peer, 6).unwrap_err(),
+            NotificationError::BufferLimitReached
+        );
+
+        drop(first);
+        assert_eq!(budget.total(), 0);
+        assert!(budget.reserve(peer, 6).is_ok());
+    }
+
+    #[test]
+    fn slowest_peer_selected_for_shedding() {
+        let budget = Arc::new(BufferBudget::new(10, SheddingPolicy::DropFromSlowest));
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let (peer1, peer2) = (PeerId::random(), PeerId::random());
+
+        let _first = budget.reserve(peer1, 2).unwrap();
+        let _second = budget.reserve(peer2, 6).unwrap();
+        let _third = budget.reserve(peer1, 4).unwrap();
+
+        assert!(!budget.poll_shed(peer1, &mut cx));
+        assert!(budget.poll_shed(peer2, &mut cx));
+        assert!(!budget.poll_shed(peer2, &mut cx));
+        assert_eq!(budget.total(), 12);
+    }
+}
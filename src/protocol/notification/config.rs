@@ -20,21 +20,27 @@
 
 use crate::{
     codec::ProtocolCodec,
-    protocol::notification::{
-        handle::NotificationHandle,
-        types::{
-            InnerNotificationEvent, NotificationCommand, ASYNC_CHANNEL_SIZE, SYNC_CHANNEL_SIZE,
+    protocol::{
+        notification::{
+            handle::NotificationHandle,
+            types::{
+                ClogPolicy, InnerNotificationEvent, NotificationCommand, ReopenBackoff,
+                SheddingPolicy, ASYNC_CHANNEL_SIZE, SYNC_CHANNEL_SIZE,
+            },
         },
+        ConnectionPreference,
     },
     types::protocol::ProtocolName,
     PeerId, DEFAULT_CHANNEL_SIZE,
 };
 
 use bytes::BytesMut;
-use parking_lot::RwLock;
-use tokio::sync::mpsc::{channel, Receiver, Sender};
+use tokio::sync::{
+    mpsc::{channel, Receiver, Sender},
+    watch,
+};
 
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc, time::Duration};
 
 /// Notification configuration.
 #[derive(Debug)]
@@ -46,14 +52,20 @@ pub struct Config {
     pub(crate) codec: ProtocolCodec,
 
     /// Maximum notification size.
-    _max_notification_size: usize,
+    pub(crate) max_notification_size: usize,
 
     /// Handshake bytes.
-    pub(crate) handshake: Arc<RwLock<Vec<u8>>>,
+    ///
+    /// The value is shared with [`NotificationHandle`] and the handshaking service of the
+    /// protocol so any update to it is used for all subsequent handshakes.
+    pub(crate) handshake: Arc<watch::Sender<Vec<u8>>>,
 
     /// Auto accept inbound substream.
     pub(super) auto_accept: bool,
 
+    /// Auto accept all inbound substreams.
+    pub(super) auto_accept_all: bool,
+
     /// Protocol aliases.
     pub(crate) fallback_names: Vec<ProtocolName>,
 
@@ -69,12 +81,45 @@ pub struct Config {
     /// Synchronous channel size.
     pub(crate) sync_channel_size: usize,
 
+    /// Policy for handling clogged synchronous notification channels.
+    pub(crate) clog_policy: ClogPolicy,
+
+    /// Backoff for reopening substreams that failed to open, `None` if disabled.
+    pub(crate) reopen_backoff: Option<ReopenBackoff>,
+
+    /// Limit for bytes buffered across all peers and the policy applied when it's reached.
+    pub(crate) buffer_limit: Option<(usize, SheddingPolicy)>,
+
+    /// Keepalive interval and timeout for idle notification streams.
+    pub(crate) keepalive: Option<(Duration, Duration)>,
+
     /// Asynchronous channel size.
     pub(crate) async_channel_size: usize,
 
     /// Should `NotificationProtocol` dial the peer if there is no connection to them
     /// when an outbound substream is requested.
     pub(crate) should_dial: bool,
+
+    /// Default priority of the protocol's substreams.
+    pub(crate) stream_priority: i32,
+
+    /// Preference for the connection used to open outbound substreams.
+    pub(crate) connection_preference: ConnectionPreference,
+
+    /// Maximum number of inbound notification streams to peers that are not reserved.
+    pub(crate) inbound_slots: Option<usize>,
+
+    /// Maximum number of outbound notification streams to peers that are not reserved.
+    pub(crate) outbound_slots: Option<usize>,
+
+    /// Reserved peers.
+    pub(crate) reserved_peers: HashSet<PeerId>,
+
+    /// Timeout for inbound substream validations.
+    pub(crate) validation_timeout: Option<Duration>,
+
+    /// Only accept reserved peers.
+    pub(crate) reserved_only: bool,
 }
 
 impl Config {
@@ -89,30 +134,15 @@ impl Config {
         async_channel_size: usize,
         should_dial: bool,
     ) -> (Self, NotificationHandle) {
-        let (event_tx, event_rx) = channel(DEFAULT_CHANNEL_SIZE);
-        let (notif_tx, notif_rx) = channel(DEFAULT_CHANNEL_SIZE);
-        let (command_tx, command_rx) = channel(DEFAULT_CHANNEL_SIZE);
-        let handshake = Arc::new(RwLock::new(handshake));
-        let handle =
-            NotificationHandle::new(event_rx, notif_rx, command_tx, Arc::clone(&handshake));
-
-        (
-            Self {
-                protocol_name,
-                codec: ProtocolCodec::UnsignedVarint(Some(max_notification_size)),
-                _max_notification_size: max_notification_size,
-                auto_accept,
-                handshake,
-                fallback_names,
-                event_tx,
-                notif_tx,
-                command_rx,
-                should_dial,
-                sync_channel_size,
-                async_channel_size,
-            },
-            handle,
-        )
+        ConfigBuilder::new(protocol_name)
+            .with_max_size(max_notification_size)
+            .with_handshake(handshake)
+            .with_fallback_names(fallback_names)
+            .with_auto_accept_inbound(auto_accept)
+            .with_sync_channel_size(sync_channel_size)
+            .with_async_channel_size(async_channel_size)
+            .with_dialing_enabled(should_dial)
+            .build()
     }
 
     /// Get protocol name.
@@ -125,8 +155,7 @@ impl Config {
     /// This function is used to work around an issue in Polkadot SDK and users
     /// should not depend on its continued existence.
     pub fn set_handshake(&mut self, handshake: Vec<u8>) {
-        let mut inner = self.handshake.write();
-        *inner = handshake;
+        self.handshake.send_replace(handshake);
     }
 }
 
@@ -151,11 +180,50 @@ pub struct ConfigBuilder {
     /// Auto accept inbound substream.
     auto_accept_inbound_for_initiated: bool,
 
+    /// Auto accept all inbound substreams.
+    auto_accept_all_inbound: bool,
+
     /// Synchronous channel size.
     sync_channel_size: usize,
 
+    /// Policy for handling clogged synchronous notification channels.
+    clog_policy: ClogPolicy,
+
+    /// Backoff for reopening substreams that failed to open.
+    reopen_backoff: Option<ReopenBackoff>,
+
+    /// Limit for bytes buffered across all peers and the policy applied when it's reached.
+    buffer_limit: Option<(usize, SheddingPolicy)>,
+
+    /// Keepalive interval and timeout for idle notification streams.
+    keepalive: Option<(Duration, Duration)>,
+
     /// Asynchronous channel size.
     async_channel_size: usize,
+
+    /// Size of the channels between the protocol and its handle.
+    channel_size: usize,
+
+    /// Default priority of the protocol's substreams.
+    stream_priority: i32,
+
+    /// Preference for the connection used to open outbound substreams.
+    connection_preference: ConnectionPreference,
+
+    /// Maximum number of inbound notification streams to peers that are not reserved.
+    inbound_slots: Option<usize>,
+
+    /// Maximum number of outbound notification streams to peers that are not reserved.
+    outbound_slots: Option<usize>,
+
+    /// Reserved peers.
+    reserved_peers: HashSet<PeerId>,
+
+    /// Timeout for inbound substream validations.
+    validation_timeout: Option<Duration>,
+
+    /// Only accept reserved peers.
+    reserved_only: bool,
 }
 
 impl ConfigBuilder {
@@ -167,9 +235,22 @@ impl ConfigBuilder {
             handshake: None,
             fallback_names: Vec::new(),
             auto_accept_inbound_for_initiated: false,
+            auto_accept_all_inbound: false,
             sync_channel_size: SYNC_CHANNEL_SIZE,
+            clog_policy: ClogPolicy::default(),
+            reopen_backoff: None,
+            buffer_limit: None,
+            keepalive: None,
             async_channel_size: ASYNC_CHANNEL_SIZE,
+            channel_size: DEFAULT_CHANNEL_SIZE,
             should_dial: true,
+            stream_priority: 0,
+            connection_preference: ConnectionPreference::Any,
+            inbound_slots: None,
+            outbound_slots: None,
+            reserved_peers: HashSet::new(),
+            validation_timeout: None,
+            reserved_only: false,
         }
     }
 
@@ -216,14 +297,85 @@ impl ConfigBuilder {
         self
     }
 
+    /// Auto-accept all inbound substreams.
+    ///
+    /// By default, every inbound substream that doesn't answer a substream opened by the local
+    /// node is reported to the user in
+    /// [`ValidateSubstream`](super::types::NotificationEvent::ValidateSubstream) and the substream
+    /// is accepted or rejected based on the answer of the user.
+    ///
+    /// If auto-accepting is enabled, the local handshake is sent to the remote node right after
+    /// its handshake has been read and the user is only notified via
+    /// [`NotificationStreamOpened`](super::types::NotificationEvent::NotificationStreamOpened).
+    /// The inbound slot limits and reserved peers are still respected.
+    pub fn with_auto_accept_all_inbound(mut self, auto_accept: bool) -> Self {
+        self.auto_accept_all_inbound = auto_accept;
+        self
+    }
+
+    /// Set the timeout for answering
+    /// [`ValidateSubstream`](super::types::NotificationEvent::ValidateSubstream).
+    ///
+    /// If the user doesn't send a validation result for the inbound substream within `timeout`,
+    /// the substream is rejected and the user is notified with
+    /// [`NotificationError::ValidationTimeout`](super::types::NotificationError::ValidationTimeout).
+    ///
+    /// By default there is no timeout.
+    pub fn with_validation_timeout(mut self, timeout: Duration) -> Self {
+        self.validation_timeout = Some(timeout);
+        self
+    }
+
     /// Configure size of the channel for sending synchronous notifications.
     ///
-    /// Default value is `16`.
+    /// Default value is `2048`.
     pub fn with_sync_channel_size(mut self, size: usize) -> Self {
         self.sync_channel_size = size;
         self
     }
 
+    /// Configure how a clogged channel for sending synchronous notifications is handled.
+    ///
+    /// By default the notification is rejected and the connection to the peer is closed.
+    pub fn with_clog_policy(mut self, policy: ClogPolicy) -> Self {
+        self.clog_policy = policy;
+        self
+    }
+
+    /// Automatically retry opening substreams opened with
+    /// [`NotificationHandle::open_substream()`] if they fail to open.
+    ///
+    /// [`NotificationEvent::NotificationStreamOpenFailure`](super::NotificationEvent::NotificationStreamOpenFailure)
+    /// is only reported once all retries have failed.
+    ///
+    /// By default failed substreams are not reopened.
+    pub fn with_reopen_backoff(mut self, backoff: ReopenBackoff) -> Self {
+        self.reopen_backoff = Some(backoff);
+        self
+    }
+
+    /// Limit the total number of bytes buffered across the notification queues of all peers.
+    ///
+    /// Once `limit` is reached, `policy` is applied to new notifications.
+    ///
+    /// By default the number of buffered bytes is only limited by the channel sizes.
+    pub fn with_buffer_limit(mut self, limit: usize, policy: SheddingPolicy) -> Self {
+        self.buffer_limit = Some((limit, policy));
+        self
+    }
+
+    /// Send keepalives on notification streams that have been idle for `interval`.
+    ///
+    /// The keepalive is an empty notification and if it can't be sent to remote within
+    /// `timeout`, the notification stream is closed. Empty notifications received from remote
+    /// are treated as keepalives and not reported to the user.
+    ///
+    /// By default keepalives are not sent.
+    pub fn with_keepalive(mut self, interval: Duration, timeout: Duration) -> Self {
+        self.keepalive = Some((interval, timeout));
+        self
+    }
+
     /// Configure size of the channel for sending asynchronous notifications.
     ///
     /// Default value is `8`.
@@ -232,6 +384,15 @@ impl ConfigBuilder {
         self
     }
 
+    /// Configure size of the channels used to send events, received notifications and commands
+    /// between the protocol and its [`NotificationHandle`].
+    ///
+    /// Default value is `4096`.
+    pub fn with_channel_size(mut self, size: usize) -> Self {
+        self.channel_size = size;
+        self
+    }
+
     /// Should `NotificationProtocol` attempt to dial the peer if an outbound substream is opened
     /// but no connection to the peer exist.
     ///
@@ -241,17 +402,115 @@ impl ConfigBuilder {
         self
     }
 
+    /// Set the default priority of the protocol's substreams.
+    ///
+    /// Data of substreams with higher priority is sent before data of lower-priority substreams
+    /// sharing the same connection. Only QUIC supports substream priorities.
+    ///
+    /// By default the priority is `0`.
+    pub fn with_stream_priority(mut self, priority: i32) -> Self {
+        self.stream_priority = priority;
+        self
+    }
+
+    /// Set the preference for the connection used to open outbound substreams when more than
+    /// one connection is open to the peer.
+    ///
+    /// By default any connection is used, preferring the one that was established first.
+    pub fn with_connection_preference(mut self, preference: ConnectionPreference) -> Self {
+        self.connection_preference = preference;
+        self
+    }
+
+    /// Set the maximum number of inbound notification streams.
+    ///
+    /// A notification stream occupies an inbound slot if it was opened by the remote peer.
+    /// Inbound substreams received while all inbound slots are occupied are rejected without
+    /// notifying the user. Reserved peers don't occupy slots.
+    ///
+    /// By default the number of inbound notification streams is not limited.
+    pub fn with_inbound_slots(mut self, slots: usize) -> Self {
+        self.inbound_slots = Some(slots);
+        self
+    }
+
+    /// Set the maximum number of outbound notification streams.
+    ///
+    /// A notification stream occupies an outbound slot if it was opened by the local node.
+    /// Opening a substream while all outbound slots are occupied fails with
+    /// [`NotificationError::NoSlotAvailable`](super::types::NotificationError::NoSlotAvailable).
+    /// Reserved peers don't occupy slots.
+    ///
+    /// By default the number of outbound notification streams is not limited.
+    pub fn with_outbound_slots(mut self, slots: usize) -> Self {
+        self.outbound_slots = Some(slots);
+        self
+    }
+
+    /// Set reserved peers.
+    ///
+    /// The set can be modified after the protocol has been started using
+    /// [`NotificationHandle::add_reserved_peer()`] and
+    /// [`NotificationHandle::remove_reserved_peer()`].
+    pub fn with_reserved_peers(mut self, peers: impl IntoIterator<Item = PeerId>) -> Self {
+        self.reserved_peers = peers.into_iter().collect();
+        self
+    }
+
+    /// Only accept substreams from and open substreams to reserved peers.
+    ///
+    /// By default all peers are accepted.
+    pub fn with_reserved_only(mut self, reserved_only: bool) -> Self {
+        self.reserved_only = reserved_only;
+        self
+    }
+
     /// Build notification configuration.
     pub fn build(mut self) -> (Config, NotificationHandle) {
-        Config::new(
-            self.protocol_name,
-            self.max_notification_size.take().expect("notification size to be specified"),
-            self.handshake.take().expect("handshake to be specified"),
-            self.fallback_names,
-            self.auto_accept_inbound_for_initiated,
-            self.sync_channel_size,
-            self.async_channel_size,
-            self.should_dial,
+        let max_notification_size =
+            self.max_notification_size.take().expect("notification size to be specified");
+        let handshake = self.handshake.take().expect("handshake to be specified");
+
+        let (event_tx, event_rx) = channel(self.channel_size);
+        let (notif_tx, notif_rx) = channel(self.channel_size);
+        let (command_tx, command_rx) = channel(self.channel_size);
+        let handshake = Arc::new(watch::channel(handshake).0);
+        let handle = NotificationHandle::new(
+            event_rx,
+            notif_rx,
+            command_tx,
+            Arc::clone(&handshake),
+            self.clog_policy,
+        );
+
+        (
+            Config {
+                protocol_name: self.protocol_name,
+                codec: ProtocolCodec::UnsignedVarint(Some(max_notification_size)),
+                max_notification_size,
+                auto_accept: self.auto_accept_inbound_for_initiated,
+                auto_accept_all: self.auto_accept_all_inbound,
+                handshake,
+                fallback_names: self.fallback_names,
+                event_tx,
+                notif_tx,
+                command_rx,
+                should_dial: self.should_dial,
+                sync_channel_size: self.sync_channel_size,
+                clog_policy: self.clog_policy,
+                reopen_backoff: self.reopen_backoff,
+                buffer_limit: self.buffer_limit,
+                keepalive: self.keepalive,
+                async_channel_size: self.async_channel_size,
+                stream_priority: self.stream_priority,
+                connection_preference: self.connection_preference,
+                inbound_slots: self.inbound_slots,
+                outbound_slots: self.outbound_slots,
+                reserved_peers: self.reserved_peers,
+                validation_timeout: self.validation_timeout,
+                reserved_only: self.reserved_only,
+            },
+            handle,
         )
     }
 }
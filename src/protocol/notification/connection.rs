@@ -19,25 +19,255 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
-    protocol::notification::handle::NotificationEventHandle, substream::Substream, PeerId,
+    protocol::notification::{
+        budget::{BufferBudget, QueuedNotification},
+        handle::NotificationEventHandle,
+        types::{ClogPolicy, NotificationError, SheddingPolicy},
+    },
+    substream::Substream,
+    PeerId,
 };
 
-use bytes::BytesMut;
-use futures::{FutureExt, SinkExt, Stream, StreamExt};
+use bytes::{Bytes, BytesMut};
+use futures::{task::AtomicWaker, FutureExt, SinkExt, Stream, StreamExt};
+use futures_timer::Delay;
 use tokio::sync::{
-    mpsc::{Receiver, Sender},
+    broadcast,
+    mpsc::{channel, error::TrySendError, Receiver, Sender},
     oneshot,
 };
 use tokio_util::sync::PollSender;
 
 use std::{
     pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     task::{Context, Poll},
+    time::Duration,
 };
 
 /// Logging target for the file.
 const LOG_TARGET: &str = "litep2p::notification::connection";
 
+/// How long to wait for remote to close its side of the stream after local node closed it.
+const CLOSE_ACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Create channel for synchronous notifications which handles a clogged channel using `policy`.
+pub(crate) fn sync_channel(size: usize, policy: ClogPolicy) -> (SyncSender, SyncReceiver) {
+    match policy {
+        ClogPolicy::ForceClose | ClogPolicy::Error => {
+            let (tx, rx) = channel(size);
+            (SyncSender::Bounded(tx), SyncReceiver::Bounded(rx))
+        }
+        ClogPolicy::DropOldest => {
+            let (tx, rx) = broadcast::channel(size);
+            let capacity = size.next_power_of_two();
+
+            (
+                SyncSender::DropOldest { tx, capacity },
+                SyncReceiver::DropOldest(rx),
+            )
+        }
+    }
+}
+
+/// Sending half of the channel for synchronous notifications.
+#[derive(Debug, Clone)]
+pub(crate) enum SyncSender {
+    /// Channel which rejects notifications when it's full.
+    Bounded(Sender<QueuedNotification>),
+
+    /// Channel which drops the oldest notification when it's full.
+    DropOldest {
+        /// TX channel.
+        tx: broadcast::Sender<QueuedNotification>,
+
+        /// Capacity of the channel.
+        capacity: usize,
+    },
+}
+
+impl SyncSender {
+    /// Try to send `notification` to the channel.
+    pub(crate) fn try_send(
+        &self,
+        notification: impl Into<QueuedNotification>,
+    ) -> Result<(), NotificationError> {
+        let notification = notification.into();
+
+        match self {
+            Self::Bounded(tx) => tx.try_send(notification).map_err(|error| match error {
+                TrySendError::Closed(_) => NotificationError::NoConnection,
+                TrySendError::Full(_) => NotificationError::ChannelClogged,
+            }),
+            Self::DropOldest { tx, .. } =>
+                tx.send(notification).map(|_| ()).map_err(|_| NotificationError::NoConnection),
+        }
+    }
+
+    /// Returns `true` if the connection handler has exited.
+    pub(crate) fn is_closed(&self) -> bool {
+        match self {
+            Self::Bounded(tx) => tx.is_closed(),
+            Self::DropOldest { tx, .. } => tx.receiver_count() == 0,
+        }
+    }
+
+    /// Get the number of queued notifications.
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Self::Bounded(tx) => tx.max_capacity() - tx.capacity(),
+            Self::DropOldest { tx, .. } => tx.len(),
+        }
+    }
+
+    /// Get the capacity of the channel.
+    pub(crate) fn capacity(&self) -> usize {
+        match self {
+            Self::Bounded(tx) => tx.max_capacity(),
+            Self::DropOldest { capacity, .. } => *capacity,
+        }
+    }
+}
+
+/// Receiving half of the channel for synchronous notifications.
+pub(crate) enum SyncReceiver {
+    /// Channel which rejects notifications when it's full.
+    Bounded(Receiver<QueuedNotification>),
+
+    /// Channel which drops the oldest notification when it's full.
+    DropOldest(broadcast::Receiver<QueuedNotification>),
+}
+
+impl SyncReceiver {
+    /// Receive next notification from the channel.
+    ///
+    /// Returns `None` if all senders have been dropped.
+    pub(crate) async fn recv(&mut self) -> Option<Bytes> {
+        match self {
+            Self::Bounded(rx) => rx.recv().await.map(QueuedNotification::into_payload),
+            Self::DropOldest(rx) => loop {
+                match rx.recv().await {
+                    Ok(notification) => return Some(notification.into_payload()),
+                    Err(broadcast::error::RecvError::Lagged(dropped)) => {
+                        tracing::trace!(
+                            target: LOG_TARGET,
+                            ?dropped,
+                            "channel clogged, oldest notifications dropped",
+                        );
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            },
+        }
+    }
+
+    /// Drop all queued notifications, returning the number of dropped notifications.
+    fn drain(&mut self) -> usize {
+        let mut dropped = 0usize;
+
+        match self {
+            Self::Bounded(rx) =>
+                while rx.try_recv().is_ok() {
+                    dropped += 1;
+                },
+            Self::DropOldest(rx) => loop {
+                match rx.try_recv() {
+                    Ok(_) => dropped += 1,
+                    Err(broadcast::error::TryRecvError::Lagged(_)) => {}
+                    Err(_) => break,
+                }
+            },
+        }
+
+        dropped
+    }
+}
+
+/// Gate controlling whether [`Connection`] reads notifications from the inbound substream.
+///
+/// While receiving is paused, the inbound substream is not polled which causes the muxer to apply
+/// backpressure to the remote peer once the receive window has been exhausted.
+#[derive(Debug, Default)]
+pub(crate) struct ReceiveGate {
+    /// Whether receiving is paused.
+    paused: AtomicBool,
+
+    /// Waker of the connection handler, woken up when receiving is resumed.
+    waker: AtomicWaker,
+}
+
+impl ReceiveGate {
+    /// Pause receiving notifications.
+    pub(crate) fn pause(&self) {
+        self.paused.store(true, Ordering::Release);
+    }
+
+    /// Resume receiving notifications.
+    pub(crate) fn resume(&self) {
+        self.paused.store(false, Ordering::Release);
+        self.waker.wake();
+    }
+
+    /// Returns `true` if receiving notifications is paused.
+    pub(crate) fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire)
+    }
+
+    /// Check if receiving is paused and if so, register `cx` to be woken up when it's resumed.
+    fn poll_paused(&self, cx: &mut Context<'_>) -> bool {
+        if !self.is_paused() {
+            return false;
+        }
+
+        self.waker.register(cx.waker());
+        self.is_paused()
+    }
+}
+
+/// Keepalive state of a [`Connection`].
+struct Keepalive {
+    /// How long the notification stream can be idle before a keepalive is sent.
+    interval: Duration,
+
+    /// How long to wait for a keepalive to be flushed before the stream is considered dead.
+    timeout: Duration,
+
+    /// Timer for detecting an idle notification stream.
+    idle: Delay,
+
+    /// Deadline for flushing the sent keepalive, if any.
+    deadline: Option<Delay>,
+}
+
+impl Keepalive {
+    /// Create new [`Keepalive`].
+    fn new(interval: Duration, timeout: Duration) -> Self {
+        Self {
+            interval,
+            timeout,
+            idle: Delay::new(interval),
+            deadline: None,
+        }
+    }
+
+    /// Notification stream was active, postpone the next keepalive.
+    fn reset(&mut self) {
+        self.idle.reset(self.interval);
+    }
+
+    /// Keepalive was sent to remote.
+    fn on_keepalive_sent(&mut self) {
+        self.reset();
+
+        if self.deadline.is_none() {
+            self.deadline = Some(Delay::new(self.timeout));
+        }
+    }
+}
+
 /// Bidirectional substream pair representing a connection to a remote peer.
 pub(crate) struct Connection {
     /// Remote peer ID.
@@ -60,17 +290,48 @@ pub(crate) struct Connection {
     notif_tx: PollSender<(PeerId, BytesMut)>,
 
     /// Receiver for asynchronously sent notifications.
-    async_rx: Receiver<Vec<u8>>,
+    async_rx: Receiver<QueuedNotification>,
 
     /// Receiver for synchronously sent notifications.
-    sync_rx: Receiver<Vec<u8>>,
+    sync_rx: SyncReceiver,
+
+    /// Receiver for high-priority notifications.
+    ///
+    /// High-priority notifications are always sent before notifications queued in `async_rx`
+    /// and `sync_rx`.
+    priority_rx: Receiver<QueuedNotification>,
 
     /// Oneshot receiver used by [`NotificationProtocol`](super::NotificationProtocol)
-    /// to signal that local node wishes the close the connection.
-    rx: oneshot::Receiver<()>,
+    /// to signal that the connection must be closed.
+    rx: oneshot::Receiver<CloseRequest>,
 
     /// Next notification to send, if any.
-    next_notification: Option<Vec<u8>>,
+    next_notification: Option<Bytes>,
+
+    /// Gate for pausing reading notifications from the inbound substream.
+    receive_gate: Arc<ReceiveGate>,
+
+    /// Notification buffer budget of the protocol, if any.
+    budget: Option<Arc<BufferBudget>>,
+
+    /// Keepalive state, if keepalives are enabled.
+    keepalive: Option<Keepalive>,
+}
+
+/// Request from [`NotificationProtocol`](super::NotificationProtocol) to close the connection.
+#[derive(Debug)]
+pub(crate) enum CloseRequest {
+    /// Connection to remote peer was closed.
+    ConnectionClosed,
+
+    /// Local node closed the notification stream.
+    ///
+    /// If `ack` is `Some`, the connection waits for remote to close its side of the stream
+    /// before acknowledging the close over `ack`.
+    Local {
+        /// TX channel for acknowledging the close.
+        ack: Option<oneshot::Sender<()>>,
+    },
 }
 
 /// Notify [`NotificationProtocol`](super::NotificationProtocol) that the connection was closed.
@@ -92,9 +353,13 @@ impl Connection {
         event_handle: NotificationEventHandle,
         conn_closed_tx: Sender<PeerId>,
         notif_tx: Sender<(PeerId, BytesMut)>,
-        async_rx: Receiver<Vec<u8>>,
-        sync_rx: Receiver<Vec<u8>>,
-    ) -> (Self, oneshot::Sender<()>) {
+        async_rx: Receiver<QueuedNotification>,
+        sync_rx: SyncReceiver,
+        priority_rx: Receiver<QueuedNotification>,
+        receive_gate: Arc<ReceiveGate>,
+        budget: Option<Arc<BufferBudget>>,
+        keepalive: Option<(Duration, Duration)>,
+    ) -> (Self, oneshot::Sender<CloseRequest>) {
         let (tx, rx) = oneshot::channel();
 
         (
@@ -103,12 +368,16 @@ impl Connection {
                 peer,
                 sync_rx,
                 async_rx,
+                priority_rx,
                 inbound,
                 outbound,
                 event_handle,
                 conn_closed_tx,
                 next_notification: None,
+                receive_gate,
                 notif_tx: PollSender::new(notif_tx),
+                budget,
+                keepalive: keepalive.map(|(interval, timeout)| Keepalive::new(interval, timeout)),
             },
             tx,
         )
@@ -129,6 +398,10 @@ impl Connection {
         let _ = self.inbound.close().await;
         let _ = self.outbound.close().await;
 
+        if let Some(budget) = &self.budget {
+            budget.remove_peer(&self.peer);
+        }
+
         if std::matches!(notify_protocol, NotifyProtocol::Yes) {
             let _ = self.conn_closed_tx.send(self.peer).await;
         }
@@ -136,6 +409,55 @@ impl Connection {
         self.event_handle.report_notification_stream_closed(self.peer).await;
     }
 
+    /// Close the connection as requested by [`NotificationProtocol`](super::NotificationProtocol).
+    async fn on_close_request(mut self, request: CloseRequest) {
+        let ack = match request {
+            CloseRequest::ConnectionClosed =>
+                return self.close_connection(NotifyProtocol::No).await,
+            CloseRequest::Local { ack } => ack,
+        };
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            peer = ?self.peer,
+            acknowledged = ack.is_some(),
+            "notification stream closed by local node",
+        );
+
+        let _ = self.outbound.close().await;
+
+        if let Some(ack) = ack {
+            // read the inbound substream until remote closes it, discarding any notifications
+            // received in the meantime as the stream is already considered closed
+            let peer = self.peer;
+            let inbound = &mut self.inbound;
+
+            if tokio::time::timeout(CLOSE_ACK_TIMEOUT, async {
+                while let Some(Ok(_)) = inbound.next().await {}
+            })
+            .await
+            .is_err()
+            {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    "remote didn't close the notification stream in time",
+                );
+            }
+
+            let _ = self.inbound.close().await;
+            let _ = ack.send(());
+        } else {
+            let _ = self.inbound.close().await;
+        }
+
+        if let Some(budget) = &self.budget {
+            budget.remove_peer(&self.peer);
+        }
+
+        self.event_handle.report_notification_stream_closed_locally(self.peer).await;
+    }
+
     pub async fn start(mut self) {
         tracing::debug!(
             target: LOG_TARGET,
@@ -145,13 +467,11 @@ impl Connection {
 
         loop {
             match self.next().await {
-                None
-                | Some(ConnectionEvent::CloseConnection {
-                    notify: NotifyProtocol::Yes,
-                }) => return self.close_connection(NotifyProtocol::Yes).await,
-                Some(ConnectionEvent::CloseConnection {
-                    notify: NotifyProtocol::No,
-                }) => return self.close_connection(NotifyProtocol::No).await,
+                None => return self.close_connection(NotifyProtocol::Yes).await,
+                Some(ConnectionEvent::CloseConnection { notify }) =>
+                    return self.close_connection(notify).await,
+                Some(ConnectionEvent::CloseRequested { request }) =>
+                    return self.on_close_request(request).await,
                 Some(ConnectionEvent::NotificationReceived { notification }) => {
                     tracing::debug!(target: "client-nova", "notificaiton received");
                     if let Err(_) = self.notif_tx.send_item((self.peer, notification)) {
@@ -176,6 +496,13 @@ pub enum ConnectionEvent {
         notify: NotifyProtocol,
     },
 
+    /// [`NotificationProtocol`](super::NotificationProtocol) requested the connection to be
+    /// closed.
+    CloseRequested {
+        /// Close request.
+        request: CloseRequest,
+    },
+
     /// Notification read from the inbound substream.
     ///
     /// NOTE: [`Connection`] uses `PollSender::send_item()` to send the notification to user.
@@ -196,20 +523,64 @@ impl Stream for Connection {
         let this = Pin::into_inner(self);
         tracing::debug!(target: "client-nova", "poll notif connection handler");
 
-        if let Poll::Ready(_) = this.rx.poll_unpin(cx) {
-            return Poll::Ready(Some(ConnectionEvent::CloseConnection {
-                notify: NotifyProtocol::No,
+        if let Poll::Ready(request) = this.rx.poll_unpin(cx) {
+            return Poll::Ready(Some(ConnectionEvent::CloseRequested {
+                request: request.unwrap_or(CloseRequest::ConnectionClosed),
             }));
         }
 
+        if let Some(budget) = &this.budget {
+            if budget.poll_shed(this.peer, cx) {
+                match budget.policy() {
+                    SheddingPolicy::CloseSlowest =>
+                        return Poll::Ready(Some(ConnectionEvent::CloseConnection {
+                            notify: NotifyProtocol::Yes,
+                        })),
+                    SheddingPolicy::DropFromSlowest | SheddingPolicy::DropNew => {
+                        let mut dropped = this.next_notification.take().map_or(0, |_| 1);
+
+                        while this.async_rx.try_recv().is_ok() {
+                            dropped += 1;
+                        }
+                        while this.priority_rx.try_recv().is_ok() {
+                            dropped += 1;
+                        }
+                        dropped += this.sync_rx.drain();
+
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            peer = ?this.peer,
+                            ?dropped,
+                            "buffer limit reached, queued notifications dropped",
+                        );
+                    }
+                }
+            }
+        }
+
         loop {
             let notification = match this.next_notification.take() {
                 Some(notification) => Some(notification),
                 None => {
+                    // high-priority notifications are dequeued first and only if there are none,
+                    // the next normal-priority notification is sent
+                    let priority_rx = &mut this.priority_rx;
+                    let async_rx = &mut this.async_rx;
+                    let sync_rx = &mut this.sync_rx;
+                    let normal = async move {
+                        tokio::select! {
+                            notification = async_rx.recv() =>
+                                notification.map(QueuedNotification::into_payload),
+                            notification = sync_rx.recv() => notification,
+                        }
+                    };
                     let future = async {
                         tokio::select! {
-                            notification = this.async_rx.recv() => notification,
-                            notification = this.sync_rx.recv() => notification,
+                            biased;
+
+                            notification = priority_rx.recv() =>
+                                notification.map(QueuedNotification::into_payload),
+                            notification = normal => notification,
                         }
                     };
                     futures::pin_mut!(future);
@@ -241,11 +612,38 @@ impl Stream for Connection {
                     })),
             }
 
-            if let Err(_) = this.outbound.start_send_unpin(notification.into()) {
+            if let Err(_) = this.outbound.start_send_unpin(notification) {
                 return Poll::Ready(Some(ConnectionEvent::CloseConnection {
                     notify: NotifyProtocol::Yes,
                 }));
             }
+
+            if let Some(keepalive) = &mut this.keepalive {
+                keepalive.reset();
+            }
+        }
+
+        // send an empty notification as keepalive if the stream has been idle
+        if let Some(keepalive) = &mut this.keepalive {
+            if keepalive.idle.poll_unpin(cx).is_ready() {
+                if this.next_notification.is_none() {
+                    match this.outbound.poll_ready_unpin(cx) {
+                        Poll::Ready(Ok(())) =>
+                            if let Err(_) = this.outbound.start_send_unpin(Bytes::new()) {
+                                return Poll::Ready(Some(ConnectionEvent::CloseConnection {
+                                    notify: NotifyProtocol::Yes,
+                                }));
+                            },
+                        Poll::Pending => {}
+                        Poll::Ready(Err(_)) =>
+                            return Poll::Ready(Some(ConnectionEvent::CloseConnection {
+                                notify: NotifyProtocol::Yes,
+                            })),
+                    }
+                }
+
+                keepalive.on_keepalive_sent();
+            }
         }
 
         match this.outbound.poll_flush_unpin(cx) {
@@ -253,7 +651,33 @@ impl Stream for Connection {
                 return Poll::Ready(Some(ConnectionEvent::CloseConnection {
                     notify: NotifyProtocol::Yes,
                 })),
-            Poll::Ready(Ok(())) | Poll::Pending => {}
+            Poll::Ready(Ok(())) =>
+                if let Some(keepalive) = &mut this.keepalive {
+                    if this.next_notification.is_none() {
+                        keepalive.deadline = None;
+                    }
+                },
+            Poll::Pending => {}
+        }
+
+        if let Some(deadline) = this.keepalive.as_mut().and_then(|k| k.deadline.as_mut()) {
+            if deadline.poll_unpin(cx).is_ready() {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    peer = ?this.peer,
+                    "keepalive timed out, closing notification stream",
+                );
+
+                return Poll::Ready(Some(ConnectionEvent::CloseConnection {
+                    notify: NotifyProtocol::Yes,
+                }));
+            }
+        }
+
+        // don't read from the inbound substream while receiving is paused so that the remote
+        // peer is slowed down
+        if this.receive_gate.poll_paused(cx) {
+            return Poll::Pending;
         }
 
         if let Err(_) = futures::ready!(this.notif_tx.poll_reserve(cx)) {
@@ -262,13 +686,171 @@ impl Stream for Connection {
             }));
         }
 
-        match futures::ready!(this.inbound.poll_next_unpin(cx)) {
-            None | Some(Err(_)) =>
-                return Poll::Ready(Some(ConnectionEvent::CloseConnection {
-                    notify: NotifyProtocol::Yes,
-                })),
-            Some(Ok(notification)) =>
-                return Poll::Ready(Some(ConnectionEvent::NotificationReceived { notification })),
+        loop {
+            match futures::ready!(this.inbound.poll_next_unpin(cx)) {
+                None | Some(Err(_)) =>
+                    return Poll::Ready(Some(ConnectionEvent::CloseConnection {
+                        notify: NotifyProtocol::Yes,
+                    })),
+                Some(Ok(notification)) => {
+                    if let Some(keepalive) = &mut this.keepalive {
+                        keepalive.reset();
+
+                        // empty notifications are keepalives sent by remote
+                        if notification.is_empty() {
+                            continue;
+                        }
+                    }
+
+                    return Poll::Ready(Some(ConnectionEvent::NotificationReceived {
+                        notification,
+                    }));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock::substream::{DummySubstream, MockSubstream},
+        types::SubstreamId,
+    };
+
+    #[tokio::test]
+    async fn clogged_channel_rejects_notification() {
+        let (tx, mut rx) = sync_channel(2, ClogPolicy::Error);
+
+        assert!(tx.try_send(Bytes::from(vec![1])).is_ok());
+        assert!(tx.try_send(Bytes::from(vec![2])).is_ok());
+        assert_eq!(
+            tx.try_send(Bytes::from(vec![3])),
+            Err(NotificationError::ChannelClogged)
+        );
+
+        assert_eq!(rx.recv().await, Some(Bytes::from(vec![1])));
+        assert_eq!(rx.recv().await, Some(Bytes::from(vec![2])));
+
+        drop(tx);
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn clogged_channel_drops_oldest_notification() {
+        let (tx, mut rx) = sync_channel(2, ClogPolicy::DropOldest);
+
+        assert!(tx.try_send(Bytes::from(vec![1])).is_ok());
+        assert!(tx.try_send(Bytes::from(vec![2])).is_ok());
+        assert!(tx.try_send(Bytes::from(vec![3])).is_ok());
+
+        assert_eq!(rx.recv().await, Some(Bytes::from(vec![2])));
+        assert_eq!(rx.recv().await, Some(Bytes::from(vec![3])));
+
+        drop(tx);
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn high_priority_notifications_sent_first() {
+        let mut outbound = MockSubstream::new();
+        let mut sequence = mockall::Sequence::new();
+
+        outbound.expect_poll_ready().returning(|_| Poll::Ready(Ok(())));
+        outbound
+            .expect_start_send()
+            .withf(|notification| notification.as_ref() == [2])
+            .times(1)
+            .in_sequence(&mut sequence)
+            .return_once(|_| Ok(()));
+        outbound
+            .expect_start_send()
+            .withf(|notification| notification.as_ref() == [1])
+            .times(1)
+            .in_sequence(&mut sequence)
+            .return_once(|_| Ok(()));
+        outbound.expect_poll_flush().returning(|_| Poll::Ready(Ok(())));
+
+        let peer = PeerId::random();
+        let (event_tx, _event_rx) = channel(64);
+        let (conn_closed_tx, _conn_closed_rx) = channel(64);
+        let (notif_tx, _notif_rx) = channel(64);
+        let (async_tx, async_rx) = channel(64);
+        let (_sync_tx, sync_rx) = sync_channel(64, ClogPolicy::Error);
+        let (priority_tx, priority_rx) = channel(64);
+
+        let (mut connection, _shutdown) = Connection::new(
+            peer,
+            Substream::new_mock(
+                peer,
+                SubstreamId::from(0usize),
+                Box::new(DummySubstream::new()),
+            ),
+            Substream::new_mock(peer, SubstreamId::from(1usize), Box::new(outbound)),
+            NotificationEventHandle::new(event_tx),
+            conn_closed_tx,
+            notif_tx,
+            async_rx,
+            sync_rx,
+            priority_rx,
+            Default::default(),
+            None,
+            None,
+        );
+
+        // normal-priority notification is queued before the high-priority notification
+        async_tx.send(Bytes::from(vec![1]).into()).await.unwrap();
+        priority_tx.send(Bytes::from(vec![2]).into()).await.unwrap();
+
+        assert!(futures::poll!(connection.next()).is_pending());
+    }
+
+    #[tokio::test]
+    async fn paused_connection_doesnt_read_inbound_substream() {
+        let mut inbound = MockSubstream::new();
+        inbound
+            .expect_poll_next()
+            .times(1)
+            .return_once(|_| Poll::Ready(Some(Ok(BytesMut::from(&[1, 3, 3, 7][..])))));
+
+        let peer = PeerId::random();
+        let (event_tx, _event_rx) = channel(64);
+        let (conn_closed_tx, _conn_closed_rx) = channel(64);
+        let (notif_tx, _notif_rx) = channel(64);
+        let (_async_tx, async_rx) = channel(64);
+        let (_sync_tx, sync_rx) = sync_channel(64, ClogPolicy::Error);
+        let (_priority_tx, priority_rx) = channel(64);
+        let receive_gate = Arc::new(ReceiveGate::default());
+
+        let (mut connection, _shutdown) = Connection::new(
+            peer,
+            Substream::new_mock(peer, SubstreamId::from(0usize), Box::new(inbound)),
+            Substream::new_mock(
+                peer,
+                SubstreamId::from(1usize),
+                Box::new(DummySubstream::new()),
+            ),
+            NotificationEventHandle::new(event_tx),
+            conn_closed_tx,
+            notif_tx,
+            async_rx,
+            sync_rx,
+            priority_rx,
+            Arc::clone(&receive_gate),
+            None,
+            None,
+        );
+
+        receive_gate.pause();
+        assert!(futures::poll!(connection.next()).is_pending());
+
+        receive_gate.resume();
+        match connection.next().await {
+            Some(ConnectionEvent::NotificationReceived { notification }) => {
+                assert_eq!(notification.as_ref(), [1, 3, 3, 7]);
+            }
+            _ => panic!("invalid event"),
         }
     }
 }
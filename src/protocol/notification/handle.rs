@@ -20,27 +20,36 @@
 
 use crate::{
     error::Error,
-    protocol::notification::types::{
-        Direction, InnerNotificationEvent, NotificationCommand, NotificationError,
-        NotificationEvent, ValidationResult,
+    protocol::notification::{
+        budget::{BufferBudget, QueuedNotification, Reservation},
+        connection::{ReceiveGate, SyncSender},
+        types::{
+            ClogPolicy, Direction, InnerNotificationEvent, NotificationCommand, NotificationError,
+            NotificationEvent, NotificationPriority, NotificationQueueInfo, NotificationStreamInfo,
+            ValidationResponse, ValidationResult,
+        },
     },
     types::protocol::ProtocolName,
     PeerId,
 };
 
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
 use futures::Stream;
-use parking_lot::RwLock;
+use multiaddr::Multiaddr;
 use tokio::sync::{
     mpsc::{error::TrySendError, Receiver, Sender},
-    oneshot,
+    oneshot, watch,
 };
 
 use std::{
     collections::{HashMap, HashSet},
     pin::Pin,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     task::{Context, Poll},
+    time::Duration,
 };
 
 /// Logging target for the file.
@@ -64,7 +73,7 @@ impl NotificationEventHandle {
         fallback: Option<ProtocolName>,
         peer: PeerId,
         handshake: Vec<u8>,
-        tx: oneshot::Sender<ValidationResult>,
+        tx: oneshot::Sender<ValidationResponse>,
     ) {
         let _ = self
             .tx
@@ -106,6 +115,14 @@ impl NotificationEventHandle {
         let _ = self.tx.send(InnerNotificationEvent::NotificationStreamClosed { peer }).await;
     }
 
+    /// Notification stream closed by the local node.
+    pub(crate) async fn report_notification_stream_closed_locally(&self, peer: PeerId) {
+        let _ = self
+            .tx
+            .send(InnerNotificationEvent::NotificationStreamClosedLocally { peer })
+            .await;
+    }
+
     /// Failed to open notification stream.
     pub(crate) async fn report_notification_stream_open_failure(
         &self,
@@ -119,52 +136,241 @@ impl NotificationEventHandle {
     }
 }
 
+/// Counters for synchronous sends of a [`NotificationSink`], shared by all of its clones.
+#[derive(Debug, Default)]
+struct SinkCounters {
+    /// Number of notifications rejected because the channel was clogged.
+    clogged: AtomicUsize,
+
+    /// Number of queued notifications dropped to make room for new ones.
+    dropped: AtomicUsize,
+}
+
 /// Notification sink.
 ///
 /// Allows the user to send notifications both synchronously and asynchronously.
+///
+/// Notifications are sent as [`Bytes`] so sending the same notification to multiple peers
+/// doesn't copy the payload.
+///
+/// The sink is bound to a single notification stream and is cheap to clone, allowing
+/// notifications to be sent to the peer from other tasks without going through
+/// [`NotificationHandle`]. Once the stream closes, the sink is invalidated and can't be used to
+/// send notifications even if a new stream is opened to the peer.
 #[derive(Debug, Clone)]
 pub struct NotificationSink {
     /// Peer ID.
     peer: PeerId,
 
+    /// Maximum notification size.
+    max_size: usize,
+
     /// TX channel for sending notifications synchronously.
-    sync_tx: Sender<Vec<u8>>,
+    sync_tx: SyncSender,
 
     /// TX channel for sending notifications asynchronously.
-    async_tx: Sender<Vec<u8>>,
+    async_tx: Sender<QueuedNotification>,
+
+    /// TX channel for sending high-priority notifications.
+    priority_tx: Sender<QueuedNotification>,
+
+    /// Counters for synchronous sends.
+    counters: Arc<SinkCounters>,
+
+    /// Gate for pausing receiving notifications from the peer.
+    receive_gate: Arc<ReceiveGate>,
+
+    /// Notification buffer budget of the protocol, if any.
+    budget: Option<Arc<BufferBudget>>,
 }
 
 impl NotificationSink {
     /// Create new [`NotificationSink`].
-    pub(crate) fn new(peer: PeerId, sync_tx: Sender<Vec<u8>>, async_tx: Sender<Vec<u8>>) -> Self {
+    pub(crate) fn new(
+        peer: PeerId,
+        max_size: usize,
+        sync_tx: SyncSender,
+        async_tx: Sender<QueuedNotification>,
+        priority_tx: Sender<QueuedNotification>,
+        receive_gate: Arc<ReceiveGate>,
+        budget: Option<Arc<BufferBudget>>,
+    ) -> Self {
         Self {
             peer,
+            max_size,
             async_tx,
             sync_tx,
+            priority_tx,
+            receive_gate,
+            budget,
+            counters: Default::default(),
+        }
+    }
+
+    /// Get the ID of the peer the sink sends notifications to.
+    pub fn peer(&self) -> PeerId {
+        self.peer
+    }
+
+    /// Returns `true` if the notification stream has been closed and the sink can no longer be
+    /// used to send notifications.
+    pub fn is_closed(&self) -> bool {
+        self.async_tx.is_closed() || self.sync_tx.is_closed() || self.priority_tx.is_closed()
+    }
+
+    /// Get the fill levels of the send queues and the number of clogged and dropped
+    /// synchronous sends.
+    pub fn queue_info(&self) -> NotificationQueueInfo {
+        NotificationQueueInfo {
+            sync_queued: self.sync_tx.len(),
+            sync_capacity: self.sync_tx.capacity(),
+            async_queued: self.async_tx.max_capacity() - self.async_tx.capacity(),
+            async_capacity: self.async_tx.max_capacity(),
+            priority_queued: self.priority_tx.max_capacity() - self.priority_tx.capacity(),
+            priority_capacity: self.priority_tx.max_capacity(),
+            clogged: self.counters.clogged.load(Ordering::Relaxed),
+            dropped: self.counters.dropped.load(Ordering::Relaxed),
         }
     }
 
+    /// Wait until the notification stream is closed.
+    pub async fn closed(&self) {
+        self.async_tx.closed().await
+    }
+
     /// Send notification to `peer` synchronously.
     ///
-    /// If the channel is clogged, [`NotificationError::ChannelClogged`] is returned.
-    pub fn send_sync_notification(&self, notification: Vec<u8>) -> Result<(), NotificationError> {
-        self.sync_tx.try_send(notification).map_err(|error| match error {
-            TrySendError::Closed(_) => NotificationError::NoConnection,
-            TrySendError::Full(_) => NotificationError::ChannelClogged,
-        })
+    /// If the channel is clogged, [`NotificationError::ChannelClogged`] is returned unless the
+    /// protocol uses [`ClogPolicy::DropOldest`].
+    ///
+    /// If the notification is larger than the maximum notification size of the protocol,
+    /// [`NotificationError::NotificationTooLarge`] is returned and if the notification buffer
+    /// limit of the protocol has been reached, [`NotificationError::BufferLimitReached`] is
+    /// returned.
+    pub fn send_sync_notification(
+        &self,
+        notification: impl Into<Bytes>,
+    ) -> Result<(), NotificationError> {
+        self.send_sync_notification_with_priority(notification, NotificationPriority::Normal)
+    }
+
+    /// Send notification to `peer` synchronously with `priority`.
+    ///
+    /// Behaves like [`NotificationSink::send_sync_notification()`] except that high-priority
+    /// notifications are sent before any queued normal-priority notifications. Queued
+    /// high-priority notifications are never dropped to make room for new ones and
+    /// [`NotificationError::ChannelClogged`] is returned if the high-priority queue is full.
+    pub fn send_sync_notification_with_priority(
+        &self,
+        notification: impl Into<Bytes>,
+        priority: NotificationPriority,
+    ) -> Result<(), NotificationError> {
+        let notification = notification.into();
+        self.check_size(&notification)?;
+        let reservation = self.reserve(&notification)?;
+
+        if priority == NotificationPriority::High {
+            return self
+                .priority_tx
+                .try_send(QueuedNotification::new(notification, reservation))
+                .map_err(|error| match error {
+                    TrySendError::Closed(_) => NotificationError::NoConnection,
+                    TrySendError::Full(_) => {
+                        self.counters.clogged.fetch_add(1, Ordering::Relaxed);
+                        NotificationError::ChannelClogged
+                    }
+                });
+        }
+
+        // if the channel drops the oldest notification when it's full, the send succeeds
+        let full = self.sync_tx.len() >= self.sync_tx.capacity();
+
+        match self.sync_tx.try_send(QueuedNotification::new(notification, reservation)) {
+            Ok(()) => {
+                if full {
+                    self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+
+                Ok(())
+            }
+            Err(NotificationError::ChannelClogged) => {
+                self.counters.clogged.fetch_add(1, Ordering::Relaxed);
+                Err(NotificationError::ChannelClogged)
+            }
+            Err(error) => Err(error),
+        }
     }
 
     /// Send notification to `peer` asynchronously, waiting for the channel to have capacity
     /// if it's clogged.
     ///
     /// Returns [`Error::PeerDoesntExist(PeerId)`](crate::error::Error::PeerDoesntExist)
-    /// if the connection has been closed.
-    pub async fn send_async_notification(&self, notification: Vec<u8>) -> crate::Result<()> {
-        self.async_tx
-            .send(notification)
+    /// if the connection has been closed and
+    /// [`NotificationError::NotificationTooLarge`](crate::error::NotificationError::NotificationTooLarge)
+    /// if the notification is larger than the maximum notification size of the protocol.
+    ///
+    /// If the notification buffer limit of the protocol has been reached,
+    /// [`NotificationError::BufferLimitReached`](crate::error::NotificationError::BufferLimitReached)
+    /// is returned.
+    pub async fn send_async_notification(
+        &self,
+        notification: impl Into<Bytes>,
+    ) -> crate::Result<()> {
+        self.send_async_notification_with_priority(notification, NotificationPriority::Normal)
+            .await
+    }
+
+    /// Send notification to `peer` asynchronously with `priority`, waiting for the channel to
+    /// have capacity if it's clogged.
+    ///
+    /// High-priority notifications are sent before any queued normal-priority notifications.
+    pub async fn send_async_notification_with_priority(
+        &self,
+        notification: impl Into<Bytes>,
+        priority: NotificationPriority,
+    ) -> crate::Result<()> {
+        let notification = notification.into();
+
+        if let Err(NotificationError::NotificationTooLarge { size, max_size }) =
+            self.check_size(&notification)
+        {
+            return Err(Error::NotificationError(
+                crate::error::NotificationError::NotificationTooLarge { size, max_size },
+            ));
+        }
+
+        let reservation = self.reserve(&notification).map_err(|_| {
+            Error::NotificationError(crate::error::NotificationError::BufferLimitReached)
+        })?;
+
+        let tx = match priority {
+            NotificationPriority::High => &self.priority_tx,
+            NotificationPriority::Normal => &self.async_tx,
+        };
+
+        tx.send(QueuedNotification::new(notification, reservation))
             .await
             .map_err(|_| Error::PeerDoesntExist(self.peer))
     }
+
+    /// Reserve space for `notification` from the notification buffer budget of the protocol.
+    fn reserve(&self, notification: &Bytes) -> Result<Option<Reservation>, NotificationError> {
+        self.budget
+            .as_ref()
+            .map(|budget| budget.reserve(self.peer, notification.len()))
+            .transpose()
+    }
+
+    /// Check that `notification` doesn't exceed the maximum notification size.
+    fn check_size(&self, notification: &Bytes) -> Result<(), NotificationError> {
+        match notification.len() > self.max_size {
+            true => Err(NotificationError::NotificationTooLarge {
+                size: notification.len(),
+                max_size: self.max_size,
+            }),
+            false => Ok(()),
+        }
+    }
 }
 
 /// Handle allowing the user protocol to interact with the notification protocol.
@@ -182,14 +388,20 @@ pub struct NotificationHandle {
     /// Peers.
     peers: HashMap<PeerId, NotificationSink>,
 
+    /// Information about the open notification streams.
+    streams: HashMap<PeerId, NotificationStreamInfo>,
+
     /// Clogged peers.
     clogged: HashSet<PeerId>,
 
     /// Pending validations.
-    pending_validations: HashMap<PeerId, oneshot::Sender<ValidationResult>>,
+    pending_validations: HashMap<PeerId, oneshot::Sender<ValidationResponse>>,
 
     /// Handshake.
-    handshake: Arc<RwLock<Vec<u8>>>,
+    handshake: Arc<watch::Sender<Vec<u8>>>,
+
+    /// Policy for handling clogged synchronous notification channels.
+    clog_policy: ClogPolicy,
 }
 
 impl NotificationHandle {
@@ -198,14 +410,17 @@ impl NotificationHandle {
         event_rx: Receiver<InnerNotificationEvent>,
         notif_rx: Receiver<(PeerId, BytesMut)>,
         command_tx: Sender<NotificationCommand>,
-        handshake: Arc<RwLock<Vec<u8>>>,
+        handshake: Arc<watch::Sender<Vec<u8>>>,
+        clog_policy: ClogPolicy,
     ) -> Self {
         Self {
             event_rx,
             notif_rx,
             command_tx,
             handshake,
+            clog_policy,
             peers: HashMap::new(),
+            streams: HashMap::new(),
             clogged: HashSet::new(),
             pending_validations: HashMap::new(),
         }
@@ -234,10 +449,36 @@ impl NotificationHandle {
             .map_or(Ok(()), |_| Ok(()))
     }
 
+    /// Open substream to `peer` using `handshake` instead of the protocol-wide handshake.
+    ///
+    /// `handshake` is sent to `peer` over both substreams of the notification stream and it's
+    /// used for all handshakes sent to `peer` until the connection to them is closed.
+    ///
+    /// Returns [`Error::PeerAlreadyExists(PeerId)`](crate::error::Error::PeerAlreadyExists) if
+    /// substream is already open to `peer`.
+    pub async fn open_substream_with_handshake(
+        &self,
+        peer: PeerId,
+        handshake: Vec<u8>,
+    ) -> crate::Result<()> {
+        tracing::trace!(target: LOG_TARGET, ?peer, ?handshake, "open substream with handshake");
+
+        if self.peers.contains_key(&peer) {
+            return Err(Error::PeerAlreadyExists(peer));
+        }
+
+        self.command_tx
+            .send(NotificationCommand::OpenSubstreamWithHandshake { peer, handshake })
+            .await
+            .map_or(Ok(()), |_| Ok(()))
+    }
+
     /// Open substreams to multiple peers.
     ///
     /// Similar to [`NotificationHandle::open_substream()`] but multiple substreams are initiated
-    /// using a single call to `NotificationProtocol`.
+    /// using a single call to `NotificationProtocol`. The result of each substream open is
+    /// reported individually, either as [`NotificationEvent::NotificationStreamOpened`] or
+    /// [`NotificationEvent::NotificationStreamOpenFailure`].
     ///
     /// Peers who are already connected are ignored and returned as `Err(HashSet<PeerId>>)`.
     pub async fn open_substream_batch(
@@ -261,7 +502,10 @@ impl NotificationHandle {
             "open substream",
         );
 
-        let _ = self.command_tx.send(NotificationCommand::OpenSubstream { peers: to_add }).await;
+        if !to_add.is_empty() {
+            let _ =
+                self.command_tx.send(NotificationCommand::OpenSubstream { peers: to_add }).await;
+        }
 
         match to_ignore.is_empty() {
             true => Ok(()),
@@ -320,6 +564,103 @@ impl NotificationHandle {
             .await;
     }
 
+    /// Close substream to `peer` and wait until the close has been acknowledged.
+    ///
+    /// Unlike [`NotificationHandle::close_substream()`], the call resolves only after the outbound
+    /// substream has been flushed and closed, and `peer` has closed its side of the notification
+    /// stream, or if `peer` didn't close its side in time. Once the call returns, no more
+    /// notifications are received from `peer` and per-peer state can be safely dropped.
+    ///
+    /// [`NotificationEvent::NotificationStreamClosedLocally`] is still emitted for `peer`.
+    pub async fn close_substream_acknowledged(&self, peer: PeerId) {
+        tracing::trace!(target: LOG_TARGET, ?peer, "close substream acknowledged");
+
+        if !self.peers.contains_key(&peer) {
+            return;
+        }
+
+        let (tx, rx) = oneshot::channel();
+
+        if self
+            .command_tx
+            .send(NotificationCommand::CloseSubstreamAcknowledged { peer, tx })
+            .await
+            .is_ok()
+        {
+            let _ = rx.await;
+        }
+    }
+
+    /// Close all connections to `peer`.
+    ///
+    /// Unlike [`NotificationHandle::close_substream()`], this closes the connections themselves
+    /// and every installed protocol is notified that the peer was disconnected.
+    pub async fn disconnect(&self, peer: PeerId) {
+        tracing::trace!(target: LOG_TARGET, ?peer, "disconnect peer");
+
+        let _ = self.command_tx.send(NotificationCommand::Disconnect { peer }).await;
+    }
+
+    /// Ban `peer` for `duration`.
+    ///
+    /// See [`Litep2p::ban_peer()`](crate::Litep2p::ban_peer) for more details.
+    pub async fn ban_peer(&self, peer: PeerId, duration: Duration) {
+        tracing::trace!(target: LOG_TARGET, ?peer, ?duration, "ban peer");
+
+        let _ = self.command_tx.send(NotificationCommand::BanPeer { peer, duration }).await;
+    }
+
+    /// Add one or more known addresses for `peer`.
+    ///
+    /// See [`Litep2p::add_known_address()`](crate::Litep2p::add_known_address) for more details.
+    pub async fn add_known_address(
+        &self,
+        peer: PeerId,
+        addresses: impl IntoIterator<Item = Multiaddr>,
+    ) {
+        let addresses = addresses.into_iter().collect::<Vec<_>>();
+
+        tracing::trace!(target: LOG_TARGET, ?peer, ?addresses, "add known addresses");
+
+        let _ = self
+            .command_tx
+            .send(NotificationCommand::AddKnownAddress { peer, addresses })
+            .await;
+    }
+
+    /// Add `peer` to the set of reserved peers.
+    ///
+    /// Reserved peers don't occupy any inbound or outbound slots and they are accepted even if
+    /// the protocol only accepts reserved peers. Adding a peer to the set doesn't open a
+    /// substream to them.
+    pub async fn add_reserved_peer(&self, peer: PeerId) {
+        tracing::trace!(target: LOG_TARGET, ?peer, "add reserved peer");
+
+        let _ = self.command_tx.send(NotificationCommand::AddReservedPeer { peer }).await;
+    }
+
+    /// Remove `peer` from the set of reserved peers.
+    ///
+    /// If the protocol only accepts reserved peers, the notification stream to `peer` is closed.
+    pub async fn remove_reserved_peer(&self, peer: PeerId) {
+        tracing::trace!(target: LOG_TARGET, ?peer, "remove reserved peer");
+
+        let _ = self.command_tx.send(NotificationCommand::RemoveReservedPeer { peer }).await;
+    }
+
+    /// Only accept substreams from and open substreams to reserved peers.
+    ///
+    /// If `reserved_only` is `true`, notification streams to all peers that are not reserved are
+    /// closed.
+    pub async fn set_reserved_only(&self, reserved_only: bool) {
+        tracing::trace!(target: LOG_TARGET, ?reserved_only, "set reserved only");
+
+        let _ = self
+            .command_tx
+            .send(NotificationCommand::SetReservedOnly { reserved_only })
+            .await;
+    }
+
     /// Close substream to multiple peers.
     ///
     /// Similar to [`NotificationHandle::close_substream()`] but multiple substreams are closed
@@ -380,7 +721,7 @@ impl NotificationHandle {
     pub fn set_handshake(&mut self, handshake: Vec<u8>) {
         tracing::trace!(target: LOG_TARGET, ?handshake, "set handshake");
 
-        *self.handshake.write() = handshake;
+        self.handshake.send_replace(handshake);
     }
 
     /// Send validation result to the notification protocol for an inbound substream received from
@@ -388,26 +729,59 @@ impl NotificationHandle {
     pub fn send_validation_result(&mut self, peer: PeerId, result: ValidationResult) {
         tracing::trace!(target: LOG_TARGET, ?peer, ?result, "send validation result");
 
-        self.pending_validations.remove(&peer).map(|tx| tx.send(result));
+        self.pending_validations.remove(&peer).map(|tx| tx.send((result, None)));
+    }
+
+    /// Accept the inbound substream received from `peer` and reply with `handshake` instead of
+    /// the protocol-wide handshake.
+    ///
+    /// `handshake` is sent to `peer` over the inbound substream and, if it hasn't been opened
+    /// yet, over the outbound substream, and it's used for all handshakes sent to `peer` until
+    /// the connection to them is closed.
+    pub fn accept_with_handshake(&mut self, peer: PeerId, handshake: Vec<u8>) {
+        tracing::trace!(target: LOG_TARGET, ?peer, ?handshake, "accept with handshake");
+
+        self.pending_validations
+            .remove(&peer)
+            .map(|tx| tx.send((ValidationResult::Accept, Some(handshake))));
     }
 
     /// Send notification to `peer` synchronously.
     ///
-    /// If the channel is clogged, [`NotificationError::ChannelClogged`] is returned.
+    /// If the channel is clogged, [`NotificationError::ChannelClogged`] is returned and the
+    /// connection to `peer` is closed, unless another [`ClogPolicy`] was configured for the
+    /// protocol.
     pub fn send_sync_notification(
         &mut self,
         peer: PeerId,
-        notification: Vec<u8>,
+        notification: impl Into<Bytes>,
+    ) -> Result<(), NotificationError> {
+        self.send_sync_notification_with_priority(peer, notification, NotificationPriority::Normal)
+    }
+
+    /// Send notification to `peer` synchronously with `priority`.
+    ///
+    /// High-priority notifications are sent before any queued normal-priority notifications, see
+    /// [`NotificationSink::send_sync_notification_with_priority()`].
+    pub fn send_sync_notification_with_priority(
+        &mut self,
+        peer: PeerId,
+        notification: impl Into<Bytes>,
+        priority: NotificationPriority,
     ) -> Result<(), NotificationError> {
         match self.peers.get_mut(&peer) {
-            Some(sink) => match sink.send_sync_notification(notification) {
+            Some(sink) => match sink.send_sync_notification_with_priority(notification, priority) {
                 Ok(()) => Ok(()),
                 Err(error) => match error {
                     NotificationError::NoConnection => return Err(NotificationError::NoConnection),
+                    error @ (NotificationError::NotificationTooLarge { .. }
+                    | NotificationError::BufferLimitReached) => Err(error),
                     NotificationError::ChannelClogged => {
-                        let _ = self.clogged.insert(peer).then(|| {
-                            self.command_tx.try_send(NotificationCommand::ForceClose { peer })
-                        });
+                        if self.clog_policy == ClogPolicy::ForceClose {
+                            let _ = self.clogged.insert(peer).then(|| {
+                                self.command_tx.try_send(NotificationCommand::ForceClose { peer })
+                            });
+                        }
 
                         Err(NotificationError::ChannelClogged)
                     }
@@ -419,6 +793,46 @@ impl NotificationHandle {
         }
     }
 
+    /// Send notification synchronously to all peers with an open notification stream.
+    ///
+    /// Peers whose channel is clogged are skipped and the [`ClogPolicy`] of the protocol is applied
+    /// to them like with [`NotificationHandle::send_sync_notification()`].
+    ///
+    /// Returns the peers to whom the notification couldn't be sent.
+    pub fn send_broadcast_notification(&mut self, notification: impl Into<Bytes>) -> Vec<PeerId> {
+        tracing::trace!(target: LOG_TARGET, peers = ?self.peers.len(), "broadcast notification");
+
+        let notification = notification.into();
+        let mut failed = Vec::new();
+        let mut clogged = Vec::new();
+
+        for (peer, sink) in &self.peers {
+            if self.clogged.contains(peer) {
+                failed.push(*peer);
+                continue;
+            }
+
+            match sink.send_sync_notification(notification.clone()) {
+                Ok(()) => {}
+                Err(NotificationError::ChannelClogged) => {
+                    if self.clog_policy == ClogPolicy::ForceClose {
+                        clogged.push(*peer);
+                    }
+                    failed.push(*peer);
+                }
+                Err(_) => failed.push(*peer),
+            }
+        }
+
+        for peer in clogged {
+            if self.clogged.insert(peer) {
+                let _ = self.command_tx.try_send(NotificationCommand::ForceClose { peer });
+            }
+        }
+
+        failed
+    }
+
     /// Send notification to `peer` asynchronously, waiting for the channel to have capacity
     /// if it's clogged.
     ///
@@ -427,16 +841,85 @@ impl NotificationHandle {
     pub async fn send_async_notification(
         &mut self,
         peer: PeerId,
-        notification: Vec<u8>,
+        notification: impl Into<Bytes>,
+    ) -> crate::Result<()> {
+        self.send_async_notification_with_priority(peer, notification, NotificationPriority::Normal)
+            .await
+    }
+
+    /// Send notification to `peer` asynchronously with `priority`, waiting for the channel to
+    /// have capacity if it's clogged.
+    ///
+    /// High-priority notifications are sent before any queued normal-priority notifications.
+    pub async fn send_async_notification_with_priority(
+        &mut self,
+        peer: PeerId,
+        notification: impl Into<Bytes>,
+        priority: NotificationPriority,
     ) -> crate::Result<()> {
         match self.peers.get_mut(&peer) {
-            Some(sink) => sink.send_async_notification(notification).await,
+            Some(sink) => sink.send_async_notification_with_priority(notification, priority).await,
             None => Err(Error::PeerDoesntExist(peer)),
         }
     }
 
+    /// Get an iterator over the peers with an open notification stream.
+    ///
+    /// The set of peers is updated as [`NotificationEvent::NotificationStreamOpened`] and
+    /// [`NotificationEvent::NotificationStreamClosed`] events are read from the handle.
+    pub fn peers(&self) -> impl Iterator<Item = &PeerId> {
+        self.streams.keys()
+    }
+
+    /// Get information about the open notification stream to `peer`.
+    ///
+    /// `None` is returned if there is no open notification stream to `peer`.
+    pub fn stream_info(&self, peer: &PeerId) -> Option<&NotificationStreamInfo> {
+        self.streams.get(peer)
+    }
+
+    /// Get send queue statistics of the notification stream to `peer`.
+    ///
+    /// Can be used to identify peers who don't read notifications as fast as they are sent.
+    ///
+    /// `None` is returned if `peer` doesn't exist.
+    pub fn queue_info(&self, peer: &PeerId) -> Option<NotificationQueueInfo> {
+        self.peers.get(peer).map(|sink| sink.queue_info())
+    }
+
+    /// Pause receiving notifications from `peer`.
+    ///
+    /// Notifications are not read from the notification stream until receiving is resumed with
+    /// [`NotificationHandle::resume_receiving()`], slowing the remote peer down through the
+    /// backpressure of the underlying connection. The notification stream stays open and
+    /// notifications can still be sent to `peer`.
+    ///
+    /// The pause only applies to the currently open notification stream.
+    pub fn pause_receiving(&self, peer: PeerId) {
+        if let Some(sink) = self.peers.get(&peer) {
+            tracing::trace!(target: LOG_TARGET, ?peer, "pause receiving notifications");
+            sink.receive_gate.pause();
+        }
+    }
+
+    /// Resume receiving notifications from `peer`.
+    pub fn resume_receiving(&self, peer: PeerId) {
+        if let Some(sink) = self.peers.get(&peer) {
+            tracing::trace!(target: LOG_TARGET, ?peer, "resume receiving notifications");
+            sink.receive_gate.resume();
+        }
+    }
+
+    /// Returns `true` if receiving notifications from `peer` is paused.
+    pub fn is_receiving_paused(&self, peer: &PeerId) -> bool {
+        self.peers.get(peer).map_or(false, |sink| sink.receive_gate.is_paused())
+    }
+
     /// Get a copy of the underlying notification sink for the peer.
     ///
+    /// The sink stays valid until the notification stream to `peer` closes, see
+    /// [`NotificationSink::is_closed()`].
+    ///
     /// `None` is returned if `peer` doesn't exist.
     pub fn notification_sink(&self, peer: PeerId) -> Option<NotificationSink> {
         self.peers.get(&peer).and_then(|sink| Some(sink.clone()))
@@ -461,6 +944,15 @@ impl Stream for NotificationHandle {
                         sink,
                     } => {
                         self.peers.insert(peer, sink);
+                        self.streams.insert(
+                            peer,
+                            NotificationStreamInfo {
+                                protocol: protocol.clone(),
+                                fallback: fallback.clone(),
+                                direction,
+                                handshake: handshake.clone(),
+                            },
+                        );
 
                         return Poll::Ready(Some(NotificationEvent::NotificationStreamOpened {
                             protocol,
@@ -472,12 +964,22 @@ impl Stream for NotificationHandle {
                     }
                     InnerNotificationEvent::NotificationStreamClosed { peer } => {
                         self.peers.remove(&peer);
+                        self.streams.remove(&peer);
                         self.clogged.remove(&peer);
 
                         return Poll::Ready(Some(NotificationEvent::NotificationStreamClosed {
                             peer,
                         }));
                     }
+                    InnerNotificationEvent::NotificationStreamClosedLocally { peer } => {
+                        self.peers.remove(&peer);
+                        self.streams.remove(&peer);
+                        self.clogged.remove(&peer);
+
+                        return Poll::Ready(Some(
+                            NotificationEvent::NotificationStreamClosedLocally { peer },
+                        ));
+                    }
                     InnerNotificationEvent::ValidateSubstream {
                         protocol,
                         fallback,
@@ -494,10 +996,15 @@ impl Stream for NotificationHandle {
                             handshake,
                         }));
                     }
-                    InnerNotificationEvent::NotificationStreamOpenFailure { peer, error } =>
+                    InnerNotificationEvent::NotificationStreamOpenFailure { peer, error } => {
+                        if error == NotificationError::ValidationTimeout {
+                            self.pending_validations.remove(&peer);
+                        }
+
                         return Poll::Ready(Some(
                             NotificationEvent::NotificationStreamOpenFailure { peer, error },
-                        )),
+                        ));
+                    }
                 },
             }
 
@@ -26,10 +26,12 @@ use crate::{
     protocol::{
         self,
         notification::{
-            connection::Connection,
+            budget::BufferBudget,
+            connection::{sync_channel, CloseRequest, Connection, ReceiveGate},
             handle::NotificationEventHandle,
             negotiation::{HandshakeEvent, HandshakeService},
-            types::NotificationCommand,
+            reopen::Reopener,
+            types::{NotificationCommand, ValidationResponse},
         },
         TransportEvent, TransportService,
     },
@@ -39,23 +41,32 @@ use crate::{
 };
 
 use bytes::BytesMut;
-use futures::{future::BoxFuture, stream::FuturesUnordered, StreamExt};
+use futures::{future::BoxFuture, stream::FuturesUnordered, SinkExt, StreamExt};
 use multiaddr::Multiaddr;
 use tokio::sync::{
     mpsc::{channel, Receiver, Sender},
     oneshot,
 };
 
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 
 pub use config::{Config, ConfigBuilder};
 pub use handle::{NotificationHandle, NotificationSink};
-pub use types::{Direction, NotificationError, NotificationEvent, ValidationResult};
+pub use types::{
+    ClogPolicy, Direction, NotificationError, NotificationEvent, NotificationPriority,
+    NotificationQueueInfo, NotificationStreamInfo, ReopenBackoff, SheddingPolicy, ValidationResult,
+};
 
+mod budget;
 mod config;
 mod connection;
 mod handle;
 mod negotiation;
+mod reopen;
 mod types;
 
 #[cfg(test)]
@@ -209,10 +220,17 @@ enum PeerState {
     /// Notification stream has been opened.
     Open {
         /// `Oneshot::Sender` for shutting down the connection.
-        shutdown: oneshot::Sender<()>,
+        shutdown: oneshot::Sender<CloseRequest>,
     },
 }
 
+impl PeerState {
+    /// Check if the notification stream is open or in the process of being opened.
+    fn is_active(&self) -> bool {
+        !std::matches!(self, PeerState::Poisoned | PeerState::Closed { .. })
+    }
+}
+
 /// Peer context.
 #[derive(Debug)]
 struct PeerContext {
@@ -239,6 +257,9 @@ pub(crate) struct NotificationProtocol {
     /// Auto accept inbound substream if the outbound substream was initiated by the local node.
     auto_accept: bool,
 
+    /// Auto accept all inbound substreams without validating them with the user.
+    auto_accept_all: bool,
+
     /// TX channel passed to the protocol used for sending events.
     event_handle: NotificationEventHandle,
 
@@ -265,32 +286,71 @@ pub(crate) struct NotificationProtocol {
     /// and outbound substreams asynchronously.
     negotiation: HandshakeService,
 
+    /// Maximum notification size.
+    max_notification_size: usize,
+
     /// Synchronous channel size.
     sync_channel_size: usize,
 
+    /// Policy for handling clogged synchronous notification channels.
+    clog_policy: ClogPolicy,
+
     /// Asynchronous channel size.
     async_channel_size: usize,
 
+    /// Reopens substreams which failed to open.
+    reopener: Reopener,
+
+    /// Notification buffer budget, if the buffered bytes are limited.
+    budget: Option<Arc<BufferBudget>>,
+
+    /// Keepalive interval and timeout for idle notification streams.
+    keepalive: Option<(Duration, Duration)>,
+
     /// Executor for connection handlers.
     executor: Arc<dyn Executor>,
 
     /// Pending substream validations.
-    pending_validations: FuturesUnordered<BoxFuture<'static, (PeerId, ValidationResult)>>,
+    ///
+    /// The validation result is `None` if the user didn't answer within `validation_timeout`.
+    pending_validations: FuturesUnordered<BoxFuture<'static, (PeerId, Option<ValidationResponse>)>>,
+
+    /// Timeout for inbound substream validations.
+    validation_timeout: Option<Duration>,
 
     /// Timers for pending outbound substreams.
     timers: FuturesUnordered<BoxFuture<'static, PeerId>>,
 
     /// Should `NotificationProtocol` attempt to dial the peer.
     should_dial: bool,
+
+    /// Maximum number of inbound notification streams to peers that are not reserved.
+    inbound_slots: Option<usize>,
+
+    /// Maximum number of outbound notification streams to peers that are not reserved.
+    outbound_slots: Option<usize>,
+
+    /// Peers occupying a slot and the direction of the slot.
+    ///
+    /// Slots of peers whose notification stream is no longer active are released lazily
+    /// when a new slot is allocated.
+    slots: HashMap<PeerId, Direction>,
+
+    /// Reserved peers.
+    reserved_peers: HashSet<PeerId>,
+
+    /// Only accept reserved peers.
+    reserved_only: bool,
 }
 
 impl NotificationProtocol {
     pub(crate) fn new(
-        service: TransportService,
+        mut service: TransportService,
         config: Config,
         executor: Arc<dyn Executor>,
     ) -> Self {
         let (shutdown_tx, shutdown_rx) = channel(DEFAULT_CHANNEL_SIZE);
+        service.set_connection_preference(config.connection_preference);
 
         Self {
             service,
@@ -300,16 +360,78 @@ impl NotificationProtocol {
             peers: HashMap::new(),
             protocol: config.protocol_name,
             auto_accept: config.auto_accept,
+            auto_accept_all: config.auto_accept_all,
             pending_validations: FuturesUnordered::new(),
+            validation_timeout: config.validation_timeout,
             timers: FuturesUnordered::new(),
             event_handle: NotificationEventHandle::new(config.event_tx),
             notif_tx: config.notif_tx,
             command_rx: config.command_rx,
             pending_outbound: HashMap::new(),
-            negotiation: HandshakeService::new(config.handshake),
+            negotiation: HandshakeService::new(config.handshake.subscribe()),
+            max_notification_size: config.max_notification_size,
             sync_channel_size: config.sync_channel_size,
+            clog_policy: config.clog_policy,
             async_channel_size: config.async_channel_size,
+            reopener: Reopener::new(config.reopen_backoff),
+            budget: config
+                .buffer_limit
+                .map(|(limit, policy)| Arc::new(BufferBudget::new(limit, policy))),
+            keepalive: config.keepalive,
             should_dial: config.should_dial,
+            inbound_slots: config.inbound_slots,
+            outbound_slots: config.outbound_slots,
+            slots: HashMap::new(),
+            reserved_peers: config.reserved_peers,
+            reserved_only: config.reserved_only,
+        }
+    }
+
+    /// Check if a slot of `direction` can be allocated for `peer`.
+    ///
+    /// Reserved peers are always accepted and they don't count towards the slot limits. If the
+    /// protocol only accepts reserved peers, no slots are available for other peers.
+    fn has_free_slot(&mut self, peer: &PeerId, direction: Direction) -> bool {
+        if self.reserved_peers.contains(peer) {
+            return true;
+        }
+
+        if self.reserved_only {
+            return false;
+        }
+
+        let peers = &self.peers;
+        self.slots
+            .retain(|peer, _| peers.get(peer).map_or(false, |context| context.state.is_active()));
+
+        let limit = match direction {
+            Direction::Inbound => self.inbound_slots,
+            Direction::Outbound => self.outbound_slots,
+        };
+
+        limit.map_or(true, |limit| {
+            self.slots
+                .iter()
+                .filter(|(peer, slot)| **slot == direction && !self.reserved_peers.contains(peer))
+                .count()
+                < limit
+        })
+    }
+
+    /// Close notification streams to all peers which are not reserved.
+    async fn close_non_reserved(&mut self) {
+        let peers = self
+            .peers
+            .iter()
+            .filter_map(|(peer, context)| {
+                (std::matches!(context.state, PeerState::Open { .. })
+                    && !self.reserved_peers.contains(peer))
+                .then_some(*peer)
+            })
+            .collect::<Vec<_>>();
+
+        for peer in peers {
+            self.on_close_substream(peer).await;
         }
     }
 
@@ -398,17 +520,18 @@ impl NotificationProtocol {
         // clean up all pending state for the peer
         self.negotiation.remove_outbound(&peer);
         self.negotiation.remove_inbound(&peer);
+        self.negotiation.remove_peer_handshake(&peer);
 
         match context.state {
             // outbound initiated, report open failure to peer
             PeerState::OutboundInitiated { .. } => {
-                self.event_handle
-                    .report_notification_stream_open_failure(peer, NotificationError::Rejected)
+                self.reopener
+                    .report_open_failure(&self.event_handle, peer, NotificationError::Rejected)
                     .await;
             }
             // substream fully open, report that the notification stream is closed
             PeerState::Open { shutdown } => {
-                let _ = shutdown.send(());
+                let _ = shutdown.send(CloseRequest::ConnectionClosed);
             }
             // if the substream was being validated, user must be notified that the substream is
             // now considered rejected if they had been made aware of the existence of the pending
@@ -454,8 +577,9 @@ impl NotificationProtocol {
                             "connection closed outbound substream under negotiation",
                         );
 
-                        self.event_handle
-                            .report_notification_stream_open_failure(
+                        self.reopener
+                            .report_open_failure(
+                                &self.event_handle,
                                 peer,
                                 NotificationError::Rejected,
                             )
@@ -558,11 +682,23 @@ impl NotificationProtocol {
             }
             PeerState::Validating {
                 protocol,
-                fallback,
+                fallback: inbound_fallback,
                 inbound,
                 direction,
                 outbound: outbound_state,
             } => {
+                // notifications are sent over the outbound substream so the protocol name
+                // negotiated for it is the one reported to the user
+                if fallback != inbound_fallback {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        ?fallback,
+                        ?inbound_fallback,
+                        "substreams negotiated using different protocol names",
+                    );
+                }
+
                 // the inbound substream has been accepted by the local node since the handshake has
                 // been read and the local handshake has either already been sent or
                 // it's in the process of being sent.
@@ -652,6 +788,13 @@ impl NotificationProtocol {
         peer: PeerId,
         substream: Substream,
     ) -> crate::Result<()> {
+        // fresh inbound substreams must be allocated an inbound slot
+        let is_fresh = std::matches!(
+            self.peers.get(&peer).map(|context| &context.state),
+            Some(PeerState::Closed { pending_open: None })
+        );
+        let has_slot = !is_fresh || self.has_free_slot(&peer, Direction::Inbound);
+
         // peer must exist since an inbound substream was received from them
         let context = self.peers.get_mut(&peer).expect("peer to exist");
 
@@ -698,8 +841,22 @@ impl NotificationProtocol {
                     pending_open: Some(substream_id),
                 };
             }
+            // the peer state is closed so this is a fresh inbound substream but there are no
+            // inbound slots available for the peer, reject the substream without notifying the user
+            PeerState::Closed { pending_open: None } if !has_slot => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    protocol = %self.protocol,
+                    "no inbound slot available, rejecting substream",
+                );
+                let _ = substream.close().await;
+
+                context.state = PeerState::Closed { pending_open: None };
+            }
             // the peer state is closed so this is a fresh inbound substream.
             PeerState::Closed { pending_open: None } => {
+                self.slots.insert(peer, Direction::Inbound);
                 self.negotiation.read_handshake(peer, substream);
 
                 context.state = PeerState::Validating {
@@ -733,15 +890,34 @@ impl NotificationProtocol {
             }
             // outbound substream may have been initiated by the local node while a remote node also
             // opened a substream roughly at the same time
+            //
+            // both nodes resolve the race the same way: the substream opened by the node with the
+            // smaller `PeerId` is considered the initiating substream and the substream opened by
+            // the other node its response. The node which lost the race reports the stream as
+            // inbound and validates the handshake of the initiating substream like it would've
+            // validated a regular inbound substream.
             PeerState::OutboundInitiated {
                 substream: outbound,
             } => {
+                let direction = match self.service.local_peer_id < peer {
+                    true => Direction::Outbound,
+                    false => Direction::Inbound,
+                };
+
+                tracing::trace!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    %protocol,
+                    ?direction,
+                    "both nodes opened a substream, resolve race",
+                );
+
                 self.negotiation.read_handshake(peer, substream);
 
                 context.state = PeerState::Validating {
                     protocol,
                     fallback,
-                    direction: Direction::Outbound,
+                    direction,
                     outbound: OutboundState::OutboundInitiated {
                         substream: outbound,
                     },
@@ -835,8 +1011,8 @@ impl NotificationProtocol {
             PeerState::OutboundInitiated { .. } => {
                 context.state = PeerState::Closed { pending_open: None };
 
-                self.event_handle
-                    .report_notification_stream_open_failure(peer, NotificationError::Rejected)
+                self.reopener
+                    .report_open_failure(&self.event_handle, peer, NotificationError::Rejected)
                     .await;
             }
             // if the substream was accepted by the local node and as a result, an outbound
@@ -848,8 +1024,9 @@ impl NotificationProtocol {
                 let pending_open = match outbound {
                     OutboundState::Closed => None,
                     OutboundState::OutboundInitiated { substream } => {
-                        self.event_handle
-                            .report_notification_stream_open_failure(
+                        self.reopener
+                            .report_open_failure(
+                                &self.event_handle,
                                 peer,
                                 NotificationError::Rejected,
                             )
@@ -858,8 +1035,9 @@ impl NotificationProtocol {
                         Some(*substream)
                     }
                     OutboundState::Negotiating | OutboundState::Open { .. } => {
-                        self.event_handle
-                            .report_notification_stream_open_failure(
+                        self.reopener
+                            .report_open_failure(
+                                &self.event_handle,
                                 peer,
                                 NotificationError::Rejected,
                             )
@@ -906,6 +1084,33 @@ impl NotificationProtocol {
     async fn on_open_substream(&mut self, peer: PeerId) -> crate::Result<()> {
         tracing::trace!(target: LOG_TARGET, ?peer, protocol = %self.protocol, "open substream");
 
+        // new outbound notification streams must be allocated an outbound slot
+        let is_closed = self.peers.get(&peer).map_or(true, |context| {
+            std::matches!(context.state, PeerState::Closed { .. })
+        });
+
+        if is_closed {
+            if !self.has_free_slot(&peer, Direction::Outbound) {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    protocol = %self.protocol,
+                    "no outbound slot available",
+                );
+
+                self.reopener
+                    .report_open_failure(
+                        &self.event_handle,
+                        peer,
+                        NotificationError::NoSlotAvailable,
+                    )
+                    .await;
+                return Ok(());
+            }
+
+            self.slots.insert(peer, Direction::Outbound);
+        }
+
         let Some(context) = self.peers.get_mut(&peer) else {
             if !self.should_dial {
                 tracing::debug!(
@@ -915,8 +1120,8 @@ impl NotificationProtocol {
                     "connection to peer not open and dialing disabled",
                 );
 
-                self.event_handle
-                    .report_notification_stream_open_failure(peer, NotificationError::DialFailure)
+                self.reopener
+                    .report_open_failure(&self.event_handle, peer, NotificationError::DialFailure)
                     .await;
                 return Ok(());
             }
@@ -931,8 +1136,9 @@ impl NotificationProtocol {
                         "failed to dial peer",
                     );
 
-                    self.event_handle
-                        .report_notification_stream_open_failure(
+                    self.reopener
+                        .report_open_failure(
+                            &self.event_handle,
                             peer,
                             NotificationError::DialFailure,
                         )
@@ -978,6 +1184,21 @@ impl NotificationProtocol {
                     substream: substream_id,
                 };
             }
+            // remote is known to reject the substream, don't attempt to open it
+            PeerState::Closed { .. }
+                if self.service.peer_supports_protocol(&peer) == Some(false) =>
+            {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    protocol = %self.protocol,
+                    "peer doesn't support the protocol",
+                );
+
+                self.reopener
+                    .report_open_failure(&self.event_handle, peer, NotificationError::Rejected)
+                    .await;
+            }
             PeerState::Closed { .. } => match self.service.open_substream(peer) {
                 Ok(substream_id) => {
                     tracing::trace!(
@@ -1002,8 +1223,9 @@ impl NotificationProtocol {
                         "failed to open substream",
                     );
 
-                    self.event_handle
-                        .report_notification_stream_open_failure(
+                    self.reopener
+                        .report_open_failure(
+                            &self.event_handle,
                             peer,
                             NotificationError::NoConnection,
                         )
@@ -1021,8 +1243,9 @@ impl NotificationProtocol {
                     "validation still pending, rejecting outbound substream request",
                 );
 
-                self.event_handle
-                    .report_notification_stream_open_failure(
+                self.reopener
+                    .report_open_failure(
+                        &self.event_handle,
                         peer,
                         NotificationError::ValidationPending,
                     )
@@ -1040,7 +1263,23 @@ impl NotificationProtocol {
     /// unreachable as the user is unable to emit this command to [`NotificationProtocol`] unless
     /// the connection has been fully opened.
     async fn on_close_substream(&mut self, peer: PeerId) {
-        tracing::debug!(target: LOG_TARGET, ?peer, protocol = %self.protocol, "close substream");
+        self.close_substream(peer, None).await
+    }
+
+    /// Close substream to remote `peer` and, if `ack` is `Some`, acknowledge the close once
+    /// remote has closed its side of the stream.
+    ///
+    /// If the substream is not open, `ack` is dropped.
+    async fn close_substream(&mut self, peer: PeerId, ack: Option<oneshot::Sender<()>>) {
+        tracing::debug!(
+            target: LOG_TARGET,
+            ?peer,
+            protocol = %self.protocol,
+            acknowledged = ack.is_some(),
+            "close substream",
+        );
+
+        self.reopener.forget(&peer);
 
         let Some(context) = self.peers.get_mut(&peer) else {
             tracing::debug!(target: LOG_TARGET, ?peer, "peer doesn't exist");
@@ -1049,7 +1288,7 @@ impl NotificationProtocol {
 
         match std::mem::replace(&mut context.state, PeerState::Poisoned) {
             PeerState::Open { shutdown } => {
-                let _ = shutdown.send(());
+                let _ = shutdown.send(CloseRequest::Local { ack });
 
                 context.state = PeerState::Closed { pending_open: None };
             }
@@ -1101,13 +1340,16 @@ impl NotificationProtocol {
                 fallback,
                 outbound,
                 direction,
-                inbound: InboundState::Validating { inbound },
+                inbound: InboundState::Validating { mut inbound },
             } => match result {
                 // substream was rejected by the local node, if an outbound substream was under
                 // negotation, discard that data and if an outbound substream was
                 // initiated, save the `SubstreamId` of that substream and later if the substream
                 // is opened, the state can be corrected to `pending_open: None`.
-                ValidationResult::Reject => {
+                ValidationResult::Reject | ValidationResult::RejectWithCode(_) => {
+                    if let ValidationResult::RejectWithCode(code) = result {
+                        let _ = inbound.send(negotiation::rejection_frame(code)).await;
+                    }
                     let _ = inbound.close().await;
                     self.negotiation.remove_outbound(&peer);
                     self.negotiation.remove_inbound(&peer);
@@ -1155,8 +1397,9 @@ impl NotificationProtocol {
                             let _ = inbound.close().await;
                             context.state = PeerState::Closed { pending_open: None };
 
-                            self.event_handle
-                                .report_notification_stream_open_failure(
+                            self.reopener
+                                .report_open_failure(
+                                    &self.event_handle,
                                     peer,
                                     NotificationError::Rejected,
                                 )
@@ -1207,7 +1450,7 @@ impl NotificationProtocol {
                             .then_some(NotificationError::NoConnection)
                     }
                 } {
-                    self.event_handle.report_notification_stream_open_failure(peer, error).await;
+                    self.reopener.report_open_failure(&self.event_handle, peer, error).await;
                 }
 
                 Ok(())
@@ -1234,6 +1477,54 @@ impl NotificationProtocol {
         }
     }
 
+    /// Handle validation timeout.
+    ///
+    /// The user didn't answer to the validation request of an inbound substream in time. The
+    /// substream is rejected and the user is notified that the notification stream failed to open
+    /// so that the peer can be considered closed even though no validation result was sent.
+    async fn on_validation_timeout(&mut self, peer: PeerId) {
+        tracing::debug!(
+            target: LOG_TARGET,
+            ?peer,
+            protocol = %self.protocol,
+            "substream validation timed out",
+        );
+
+        let is_pending = self.peers.get(&peer).map_or(false, |context| {
+            std::matches!(
+                context.state,
+                PeerState::ValidationPending { .. }
+                    | PeerState::Validating {
+                        inbound: InboundState::Validating { .. },
+                        ..
+                    }
+            )
+        });
+
+        if !is_pending {
+            return;
+        }
+
+        if let Err(error) = self.on_validation_result(peer, ValidationResult::Reject).await {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?peer,
+                protocol = %self.protocol,
+                ?error,
+                "failed to reject substream after validation timeout",
+            );
+            return;
+        }
+
+        self.reopener
+            .report_open_failure(
+                &self.event_handle,
+                peer,
+                NotificationError::ValidationTimeout,
+            )
+            .await;
+    }
+
     /// Handle handshake event.
     ///
     /// There are three different handshake event types:
@@ -1271,6 +1562,11 @@ impl NotificationProtocol {
             "handle handshake event",
         );
 
+        let open_failure = match event {
+            HandshakeEvent::Rejected { code, .. } => NotificationError::RejectedWithCode(code),
+            _ => NotificationError::Rejected,
+        };
+
         match event {
             // either an inbound or outbound substream has been negotiated successfully
             HandshakeEvent::Negotiated {
@@ -1350,7 +1646,12 @@ impl NotificationProtocol {
                             outbound,
                             inbound: InboundState::ReadingHandshake,
                         } => {
-                            if !std::matches!(outbound, OutboundState::Closed) && self.auto_accept {
+                            // only the handshake of a substream sent as a response to the local
+                            // node's substream can be auto-accepted
+                            if !std::matches!(outbound, OutboundState::Closed)
+                                && direction == Direction::Outbound
+                                && self.auto_accept
+                            {
                                 tracing::trace!(
                                     target: LOG_TARGET,
                                     ?peer,
@@ -1373,6 +1674,42 @@ impl NotificationProtocol {
                                 return;
                             }
 
+                            // all inbound substreams are accepted without a validation round trip
+                            // to the user which only gets notified once the stream is open
+                            if self.auto_accept_all {
+                                tracing::trace!(
+                                    target: LOG_TARGET,
+                                    ?peer,
+                                    %protocol,
+                                    ?fallback,
+                                    ?direction,
+                                    ?outbound,
+                                    "auto-accept all inbound substreams",
+                                );
+
+                                context.state = PeerState::Validating {
+                                    protocol,
+                                    fallback,
+                                    direction,
+                                    inbound: InboundState::Validating { inbound: substream },
+                                    outbound,
+                                };
+
+                                if let Err(error) =
+                                    self.on_validation_result(peer, ValidationResult::Accept).await
+                                {
+                                    tracing::debug!(
+                                        target: LOG_TARGET,
+                                        ?peer,
+                                        protocol = %self.protocol,
+                                        ?error,
+                                        "failed to auto-accept inbound substream",
+                                    );
+                                }
+
+                                return;
+                            }
+
                             tracing::trace!(
                                 target: LOG_TARGET,
                                 ?peer,
@@ -1391,11 +1728,20 @@ impl NotificationProtocol {
                             };
 
                             let (tx, rx) = oneshot::channel();
+                            let validation_timeout = self.validation_timeout;
                             self.pending_validations.push(Box::pin(async move {
-                                match rx.await {
-                                    Ok(ValidationResult::Accept) =>
-                                        (peer, ValidationResult::Accept),
-                                    _ => (peer, ValidationResult::Reject),
+                                let result = match validation_timeout {
+                                    Some(timeout) =>
+                                        match tokio::time::timeout(timeout, rx).await {
+                                            Ok(result) => result,
+                                            Err(_) => return (peer, None),
+                                        },
+                                    None => rx.await,
+                                };
+
+                                match result {
+                                    Ok(response) => (peer, Some(response)),
+                                    Err(_) => (peer, Some((ValidationResult::Reject, None))),
                                 }
                             }));
 
@@ -1440,12 +1786,19 @@ impl NotificationProtocol {
             // user is notified of the error only if they've either initiated an outbound substream
             // or if they accepted an inbound substream and as a result initiated an outbound
             // substream.
-            HandshakeEvent::NegotiationError { peer, direction } => {
+            //
+            // if the remote peer rejected the outbound substream with a rejection code, the code
+            // is reported to the user.
+            HandshakeEvent::NegotiationError { peer, direction }
+            | HandshakeEvent::Rejected {
+                peer, direction, ..
+            } => {
                 tracing::debug!(
                     target: LOG_TARGET,
                     ?peer,
                     protocol = %self.protocol,
                     ?direction,
+                    error = ?open_failure,
                     state = ?context.state,
                     "failed to negotiate substream",
                 );
@@ -1464,11 +1817,8 @@ impl NotificationProtocol {
                         // notify user if the outbound substream is not considered closed
                         if !std::matches!(outbound, OutboundState::Closed) {
                             return self
-                                .event_handle
-                                .report_notification_stream_open_failure(
-                                    peer,
-                                    NotificationError::Rejected,
-                                )
+                                .reopener
+                                .report_open_failure(&self.event_handle, peer, open_failure)
                                 .await;
                         }
                     }
@@ -1501,8 +1851,18 @@ impl NotificationProtocol {
                 );
 
                 let (async_tx, async_rx) = channel(self.async_channel_size);
-                let (sync_tx, sync_rx) = channel(self.sync_channel_size);
-                let sink = NotificationSink::new(peer, sync_tx, async_tx);
+                let (sync_tx, sync_rx) = sync_channel(self.sync_channel_size, self.clog_policy);
+                let (priority_tx, priority_rx) = channel(self.sync_channel_size);
+                let receive_gate = Arc::new(ReceiveGate::default());
+                let sink = NotificationSink::new(
+                    peer,
+                    self.max_notification_size,
+                    sync_tx,
+                    async_tx,
+                    priority_tx,
+                    Arc::clone(&receive_gate),
+                    self.budget.clone(),
+                );
 
                 // start connection handler for the peer which only deals with sending/receiving
                 // notifications
@@ -1524,9 +1884,14 @@ impl NotificationProtocol {
                     self.notif_tx.clone(),
                     async_rx,
                     sync_rx,
+                    priority_rx,
+                    receive_gate,
+                    self.budget.clone(),
+                    self.keepalive,
                 );
 
                 context.state = PeerState::Open { shutdown };
+                self.reopener.forget(&peer);
                 self.event_handle
                     .report_notification_stream_opened(
                         protocol,
@@ -1538,9 +1903,12 @@ impl NotificationProtocol {
                     )
                     .await;
 
-                self.executor.run(Box::pin(async move {
-                    connection.start().await;
-                }));
+                self.executor.run_with_name(
+                    "litep2p-notification-connection",
+                    Box::pin(async move {
+                        connection.start().await;
+                    }),
+                );
             }
             state => {
                 tracing::trace!(
@@ -1584,8 +1952,9 @@ impl NotificationProtocol {
         match context.state {
             PeerState::Dialing => {
                 tracing::debug!(target: LOG_TARGET, ?peer, protocol = %self.protocol, ?address, "failed to dial peer");
-                self.event_handle
-                    .report_notification_stream_open_failure(peer, NotificationError::DialFailure)
+                self.negotiation.remove_peer_handshake(&peer);
+                self.reopener
+                    .report_open_failure(&self.event_handle, peer, NotificationError::DialFailure)
                     .await;
             }
             state => {
@@ -1648,8 +2017,7 @@ impl NotificationProtocol {
                                 context.state = PeerState::Closed { pending_open: None };
 
                                 let _ = outbound.close().await;
-                                self.event_handle
-                                    .report_notification_stream_open_failure(peer, NotificationError::Rejected)
+                                self.reopener.report_open_failure(&self.event_handle, peer, NotificationError::Rejected)
                                     .await;
 
                                 if let Err(error) = self.service.force_close(peer) {
@@ -1694,7 +2062,7 @@ impl NotificationProtocol {
                         );
                     }
                 }
-                Some(TransportEvent::ConnectionClosed { peer }) => {
+                Some(TransportEvent::ConnectionClosed { peer, .. }) => {
                     if let Err(error) = self.on_connection_closed(peer).await {
                         tracing::debug!(
                             target: LOG_TARGET,
@@ -1710,6 +2078,7 @@ impl NotificationProtocol {
                     direction,
                     protocol,
                     fallback,
+                    ..
                 }) => match direction {
                     protocol::Direction::Inbound => {
                         if let Err(error) = self.on_inbound_substream(protocol, fallback, peer, substream).await {
@@ -1741,17 +2110,36 @@ impl NotificationProtocol {
                 Some(TransportEvent::DialFailure { peer, address }) => self.on_dial_failure(peer, address).await,
                 None => return,
             },
-            result = self.pending_validations.select_next_some(), if !self.pending_validations.is_empty() => {
-                if let Err(error) = self.on_validation_result(result.0, result.1).await {
+            result = self.pending_validations.select_next_some(), if !self.pending_validations.is_empty() => match result {
+                (peer, Some((result, handshake))) => {
+                    if let (ValidationResult::Accept, Some(handshake)) = (result, handshake) {
+                        if self.peers.contains_key(&peer) {
+                            self.negotiation.set_peer_handshake(peer, handshake);
+                        }
+                    }
+
+                    if let Err(error) = self.on_validation_result(peer, result).await {
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            ?peer,
+                            ?result,
+                            ?error,
+                            "failed to handle validation result",
+                        );
+                    }
+                }
+                (peer, None) => self.on_validation_timeout(peer).await,
+            },
+            peer = self.reopener.next(), if !self.reopener.is_empty() => if let Some(peer) = peer {
+                if let Err(error) = self.on_open_substream(peer).await {
                     tracing::debug!(
                         target: LOG_TARGET,
-                        peer = ?result.0,
-                        result = ?result.1,
+                        ?peer,
                         ?error,
-                        "failed to handle validation result",
+                        "failed to reopen substream",
                     );
                 }
-            }
+            },
             command = self.command_rx.recv() => match command {
                 None => {
                     tracing::debug!(target: LOG_TARGET, "user protocol has exited, exiting");
@@ -1760,6 +2148,8 @@ impl NotificationProtocol {
                 Some(command) => match command {
                     NotificationCommand::OpenSubstream { peers } => {
                         for peer in peers {
+                            self.reopener.on_open_requested(peer);
+
                             if let Err(error) = self.on_open_substream(peer).await {
                                 tracing::debug!(
                                     target: LOG_TARGET,
@@ -1770,14 +2160,75 @@ impl NotificationProtocol {
                             }
                         }
                     }
+                    NotificationCommand::OpenSubstreamWithHandshake { peer, handshake } => {
+                        self.negotiation.set_peer_handshake(peer, handshake);
+                        self.reopener.on_open_requested(peer);
+
+                        if let Err(error) = self.on_open_substream(peer).await {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?peer,
+                                ?error,
+                                "failed to open substream",
+                            );
+                        }
+
+                        // peer couldn't be dialed
+                        if !self.peers.contains_key(&peer) {
+                            self.negotiation.remove_peer_handshake(&peer);
+                        }
+                    }
                     NotificationCommand::CloseSubstream { peers } => {
                         for peer in peers {
                             self.on_close_substream(peer).await;
                         }
                     }
+                    NotificationCommand::CloseSubstreamAcknowledged { peer, tx } => {
+                        self.close_substream(peer, Some(tx)).await;
+                    }
                     NotificationCommand::ForceClose { peer } => {
                         let _ = self.service.force_close(peer);
                     }
+                    NotificationCommand::BanPeer { peer, duration } => {
+                        if let Err(error) = self.service.ban_peer(peer, duration) {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?peer,
+                                protocol = %self.protocol,
+                                ?error,
+                                "failed to ban peer",
+                            );
+                        }
+                    }
+                    NotificationCommand::Disconnect { peer } => {
+                        if let Err(error) = self.service.disconnect(peer) {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?peer,
+                                protocol = %self.protocol,
+                                ?error,
+                                "failed to disconnect peer",
+                            );
+                        }
+                    }
+                    NotificationCommand::AddKnownAddress { peer, addresses } => {
+                        self.service.add_known_address(&peer, addresses.into_iter());
+                    }
+                    NotificationCommand::AddReservedPeer { peer } => {
+                        self.reserved_peers.insert(peer);
+                    }
+                    NotificationCommand::RemoveReservedPeer { peer } => {
+                        if self.reserved_peers.remove(&peer) && self.reserved_only {
+                            self.on_close_substream(peer).await;
+                        }
+                    }
+                    NotificationCommand::SetReservedOnly { reserved_only } => {
+                        self.reserved_only = reserved_only;
+
+                        if reserved_only {
+                            self.close_non_reserved().await;
+                        }
+                    }
                 }
             },
         }
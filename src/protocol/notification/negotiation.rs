@@ -22,14 +22,14 @@
 
 use crate::{substream::Substream, PeerId};
 
+use bytes::{BufMut, Bytes, BytesMut};
 use futures::{FutureExt, Sink, Stream};
 use futures_timer::Delay;
-use parking_lot::RwLock;
+use tokio::sync::watch;
 
 use std::{
     collections::{HashMap, VecDeque},
     pin::Pin,
-    sync::Arc,
     task::{Context, Poll},
     time::Duration,
 };
@@ -40,6 +40,26 @@ const LOG_TARGET: &str = "litep2p::notification::negotiation";
 /// Maximum timeout wait before for handshake before operation is considered failed.
 const NEGOTIATION_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// Prefix of the rejection frame sent in place of a handshake.
+const REJECTION_PREFIX: &[u8] = b"\0/litep2p/notification/reject\0";
+
+/// Create rejection frame for `code`.
+pub fn rejection_frame(code: u8) -> Bytes {
+    let mut frame = BytesMut::with_capacity(REJECTION_PREFIX.len() + 1);
+    frame.put_slice(REJECTION_PREFIX);
+    frame.put_u8(code);
+
+    frame.freeze()
+}
+
+/// Decode rejection code from `frame` if it's a rejection frame.
+fn rejection_code(frame: &[u8]) -> Option<u8> {
+    match frame.strip_prefix(REJECTION_PREFIX) {
+        Some([code]) => Some(*code),
+        _ => None,
+    }
+}
+
 /// Substream direction.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Direction {
@@ -76,6 +96,18 @@ pub enum HandshakeEvent {
         /// Direction.
         direction: Direction,
     },
+
+    /// Remote peer rejected the substream with a rejection code.
+    Rejected {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// Direction.
+        direction: Direction,
+
+        /// Rejection code.
+        code: u8,
+    },
 }
 
 /// Outbound substream's handshake state
@@ -96,7 +128,10 @@ enum HandshakeState {
 /// Handshake service.
 pub(crate) struct HandshakeService {
     /// Handshake.
-    handshake: Arc<RwLock<Vec<u8>>>,
+    handshake: watch::Receiver<Vec<u8>>,
+
+    /// Handshakes overriding the protocol-wide handshake for individual peers.
+    peer_handshakes: HashMap<PeerId, Vec<u8>>,
 
     /// Pending outbound substreams.
     /// Substreams:
@@ -108,14 +143,27 @@ pub(crate) struct HandshakeService {
 
 impl HandshakeService {
     /// Create new [`HandshakeService`].
-    pub fn new(handshake: Arc<RwLock<Vec<u8>>>) -> Self {
+    pub fn new(handshake: watch::Receiver<Vec<u8>>) -> Self {
         Self {
             handshake,
+            peer_handshakes: HashMap::new(),
             ready: VecDeque::new(),
             substreams: HashMap::new(),
         }
     }
 
+    /// Use `handshake` instead of the protocol-wide handshake for `peer`.
+    pub fn set_peer_handshake(&mut self, peer: PeerId, handshake: Vec<u8>) {
+        tracing::trace!(target: LOG_TARGET, ?peer, ?handshake, "set peer handshake");
+
+        self.peer_handshakes.insert(peer, handshake);
+    }
+
+    /// Remove the handshake override of `peer`, if it exists.
+    pub fn remove_peer_handshake(&mut self, peer: &PeerId) {
+        self.peer_handshakes.remove(peer);
+    }
+
     /// Remove outbound substream from [`HandshakeService`].
     pub fn remove_outbound(&mut self, peer: &PeerId) -> Option<Substream> {
         self.substreams
@@ -247,7 +295,12 @@ impl Stream for HandshakeService {
                         Poll::Pending => continue 'outer,
                     },
                     HandshakeState::SinkReady => {
-                        match pinned.start_send((*inner.handshake.read()).clone().into()) {
+                        let handshake = match inner.peer_handshakes.get(peer) {
+                            Some(handshake) => handshake.clone(),
+                            None => inner.handshake.borrow().clone(),
+                        };
+
+                        match pinned.start_send(handshake.into()) {
                             Ok(()) => {
                                 *state = HandshakeState::HandshakeSent;
                                 continue;
@@ -285,6 +338,17 @@ impl Stream for HandshakeService {
                     },
                     HandshakeState::ReadHandshake => match pinned.poll_next(cx) {
                         Poll::Ready(Some(Ok(handshake))) => {
+                            if let Some(code) = rejection_code(&handshake) {
+                                return Poll::Ready(Some((
+                                    *peer,
+                                    HandshakeEvent::Rejected {
+                                        peer: *peer,
+                                        direction: *direction,
+                                        code,
+                                    },
+                                )));
+                            }
+
                             inner.ready.push_back((*peer, *direction, handshake.freeze().into()));
                             continue 'outer;
                         }
@@ -334,7 +398,7 @@ mod tests {
 
     #[tokio::test]
     async fn substream_error_when_sending_handshake() {
-        let mut service = HandshakeService::new(Arc::new(RwLock::new(vec![1, 2, 3, 4])));
+        let mut service = HandshakeService::new(watch::channel(vec![1, 2, 3, 4]).1);
 
         futures::future::poll_fn(|cx| match service.poll_next_unpin(cx) {
             Poll::Pending => Poll::Ready(()),
@@ -368,7 +432,7 @@ mod tests {
 
     #[tokio::test]
     async fn substream_error_when_flushing_substream() {
-        let mut service = HandshakeService::new(Arc::new(RwLock::new(vec![1, 2, 3, 4])));
+        let mut service = HandshakeService::new(watch::channel(vec![1, 2, 3, 4]).1);
 
         futures::future::poll_fn(|cx| match service.poll_next_unpin(cx) {
             Poll::Pending => Poll::Ready(()),
@@ -404,11 +468,74 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn updated_handshake_is_sent() {
+        let (tx, rx) = watch::channel(vec![1, 2, 3, 4]);
+        let mut service = HandshakeService::new(rx);
+
+        tx.send_replace(vec![5, 6, 7, 8]);
+
+        let mut substream = MockSubstream::new();
+        substream.expect_poll_ready().times(1).return_once(|_| Poll::Ready(Ok(())));
+        substream
+            .expect_start_send()
+            .withf(|handshake| handshake.as_ref() == [5, 6, 7, 8])
+            .times(1)
+            .return_once(|_| Err(Error::Unknown));
+
+        let peer = PeerId::random();
+        let substream = Substream::new_mock(peer, SubstreamId::from(0usize), Box::new(substream));
+
+        service.send_handshake(peer, substream);
+        match service.next().await {
+            Some((
+                _,
+                HandshakeEvent::NegotiationError {
+                    peer: event_peer, ..
+                },
+            )) => {
+                assert_eq!(event_peer, peer);
+            }
+            _ => panic!("invalid event received"),
+        }
+    }
+
+    #[tokio::test]
+    async fn peer_handshake_is_sent() {
+        let mut service = HandshakeService::new(watch::channel(vec![1, 2, 3, 4]).1);
+        let peer = PeerId::random();
+
+        service.set_peer_handshake(peer, vec![1, 3, 3, 7]);
+
+        let mut substream = MockSubstream::new();
+        substream.expect_poll_ready().times(1).return_once(|_| Poll::Ready(Ok(())));
+        substream
+            .expect_start_send()
+            .withf(|handshake| handshake.as_ref() == [1, 3, 3, 7])
+            .times(1)
+            .return_once(|_| Err(Error::Unknown));
+
+        let substream = Substream::new_mock(peer, SubstreamId::from(0usize), Box::new(substream));
+
+        service.send_handshake(peer, substream);
+        match service.next().await {
+            Some((
+                _,
+                HandshakeEvent::NegotiationError {
+                    peer: event_peer, ..
+                },
+            )) => {
+                assert_eq!(event_peer, peer);
+            }
+            _ => panic!("invalid event received"),
+        }
+    }
+
     // inbound substream is negotiated and it pushed into `inner` but outbound substream fails to
     // negotiate
     #[tokio::test]
     async fn pop_event_but_substream_doesnt_exist() {
-        let mut service = HandshakeService::new(Arc::new(RwLock::new(vec![1, 2, 3, 4])));
+        let mut service = HandshakeService::new(watch::channel(vec![1, 2, 3, 4]).1);
         let peer = PeerId::random();
 
         // inbound substream has finished
@@ -449,4 +576,12 @@ mod tests {
         })
         .await
     }
+
+    #[test]
+    fn rejection_frame_decoded() {
+        assert_eq!(rejection_code(&rejection_frame(13)), Some(13));
+        assert_eq!(rejection_code(REJECTION_PREFIX), None);
+        assert_eq!(rejection_code(&[1, 2, 3, 4]), None);
+        assert_eq!(rejection_code(&[]), None);
+    }
 }
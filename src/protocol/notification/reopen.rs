@@ -0,0 +1,207 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Automatic reopening of notification substreams that failed to open.
+
+use crate::{
+    protocol::notification::{
+        handle::NotificationEventHandle,
+        types::{NotificationError, ReopenBackoff},
+    },
+    PeerId,
+};
+
+use futures::{future::BoxFuture, stream::FuturesUnordered, Stream, StreamExt};
+
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::notification::reopen";
+
+/// Reopens notification substreams opened by the user if they fail to open.
+pub(crate) struct Reopener {
+    /// Backoff for retrying, `None` if reopening is disabled.
+    backoff: Option<ReopenBackoff>,
+
+    /// Peers to whom the user requested a notification stream to be opened and the number of
+    /// times opening the stream has been retried.
+    attempts: HashMap<PeerId, usize>,
+
+    /// Pending retries.
+    timers: FuturesUnordered<BoxFuture<'static, PeerId>>,
+}
+
+impl Reopener {
+    /// Create new [`Reopener`].
+    pub(crate) fn new(backoff: Option<ReopenBackoff>) -> Self {
+        Self {
+            backoff,
+            attempts: HashMap::new(),
+            timers: FuturesUnordered::new(),
+        }
+    }
+
+    /// User requested a notification stream to be opened to `peer`.
+    pub(crate) fn on_open_requested(&mut self, peer: PeerId) {
+        if self.backoff.is_some() {
+            self.attempts.insert(peer, 0usize);
+        }
+    }
+
+    /// Notification stream to `peer` was opened or closed by the user.
+    pub(crate) fn forget(&mut self, peer: &PeerId) {
+        self.attempts.remove(peer);
+    }
+
+    /// Returns `true` if there are no pending retries.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+
+    /// Report that the notification stream to `peer` failed to open.
+    ///
+    /// If the stream was requested by the user and there are retries left, opening the stream
+    /// is retried after a backoff and the failure is not reported to the user.
+    pub(crate) async fn report_open_failure(
+        &mut self,
+        event_handle: &NotificationEventHandle,
+        peer: PeerId,
+        error: NotificationError,
+    ) {
+        if let (Some(backoff), Some(attempts)) = (&self.backoff, self.attempts.get_mut(&peer)) {
+            if *attempts < backoff.max_retries {
+                let delay = backoff.delay(*attempts);
+                *attempts += 1;
+
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    ?error,
+                    attempt = *attempts,
+                    ?delay,
+                    "failed to open notification stream, retrying",
+                );
+
+                self.timers.push(Box::pin(async move {
+                    futures_timer::Delay::new(delay).await;
+                    peer
+                }));
+                return;
+            }
+        }
+
+        self.attempts.remove(&peer);
+        event_handle.report_notification_stream_open_failure(peer, error).await;
+    }
+}
+
+impl Stream for Reopener {
+    type Item = PeerId;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        while let Poll::Ready(Some(peer)) = self.timers.poll_next_unpin(cx) {
+            // the stream may have been opened or closed by the user while the retry was pending
+            if self.attempts.contains_key(&peer) {
+                return Poll::Ready(Some(peer));
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::notification::types::InnerNotificationEvent;
+    use std::time::Duration;
+    use tokio::sync::mpsc::channel;
+
+    #[tokio::test]
+    async fn failure_reported_once_retries_exhausted() {
+        let (tx, mut rx) = channel(64);
+        let event_handle = NotificationEventHandle::new(tx);
+        let mut reopener = Reopener::new(Some(ReopenBackoff {
+            max_retries: 2,
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(20),
+        }));
+        let peer = PeerId::random();
+
+        reopener.on_open_requested(peer);
+
+        for _ in 0..2 {
+            reopener
+                .report_open_failure(&event_handle, peer, NotificationError::Rejected)
+                .await;
+            assert!(rx.try_recv().is_err());
+            assert_eq!(reopener.next().await, Some(peer));
+        }
+
+        reopener
+            .report_open_failure(&event_handle, peer, NotificationError::Rejected)
+            .await;
+        assert!(reopener.is_empty());
+
+        match rx.try_recv() {
+            Ok(InnerNotificationEvent::NotificationStreamOpenFailure {
+                peer: failed,
+                error,
+            }) => {
+                assert_eq!(failed, peer);
+                assert_eq!(error, NotificationError::Rejected);
+            }
+            _ => panic!("invalid event"),
+        }
+    }
+
+    #[tokio::test]
+    async fn failure_reported_for_peers_not_opened_by_user() {
+        let (tx, mut rx) = channel(64);
+        let event_handle = NotificationEventHandle::new(tx);
+        let mut reopener = Reopener::new(Some(Default::default()));
+
+        reopener
+            .report_open_failure(&event_handle, PeerId::random(), NotificationError::Rejected)
+            .await;
+
+        assert!(reopener.is_empty());
+        assert!(rx.try_recv().is_ok());
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let backoff = ReopenBackoff {
+            max_retries: 10,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(10),
+        };
+
+        assert_eq!(backoff.delay(0), Duration::from_secs(1));
+        assert_eq!(backoff.delay(1), Duration::from_secs(2));
+        assert_eq!(backoff.delay(3), Duration::from_secs(8));
+        assert_eq!(backoff.delay(4), Duration::from_secs(10));
+        assert_eq!(backoff.delay(100), Duration::from_secs(10));
+    }
+}
@@ -25,16 +25,21 @@ use crate::{
         self,
         connection::ConnectionHandle,
         notification::{
+            budget::BufferBudget,
+            connection::sync_channel,
             negotiation::HandshakeEvent,
             tests::make_notification_protocol,
-            types::{Direction, NotificationError, NotificationEvent},
-            ConnectionState, InboundState, NotificationProtocol, OutboundState, PeerContext,
-            PeerState, ValidationResult,
+            types::{
+                ClogPolicy, Direction, NotificationError, NotificationEvent, NotificationPriority,
+                NotificationQueueInfo, SheddingPolicy,
+            },
+            ConnectionState, InboundState, NotificationProtocol, NotificationSink, OutboundState,
+            PeerContext, PeerState, ValidationResult,
         },
         InnerTransportEvent, ProtocolCommand,
     },
     substream::Substream,
-    transport::Endpoint,
+    transport::{Endpoint, SupportedTransport},
     types::{protocol::ProtocolName, ConnectionId, SubstreamId},
     PeerId,
 };
@@ -46,7 +51,7 @@ use tokio::sync::{
     oneshot,
 };
 
-use std::{task::Poll, time::Duration};
+use std::{sync::Arc, task::Poll, time::Duration};
 
 fn next_inbound_state(state: usize) -> InboundState {
     match state {
@@ -367,6 +372,7 @@ async fn register_peer(
             peer,
             connection: ConnectionId::new(),
             endpoint: Endpoint::dialer(Multiaddr::empty(), ConnectionId::from(0usize)),
+            transport: SupportedTransport::Tcp,
             sender: ConnectionHandle::new(ConnectionId::from(0usize), conn_tx),
         })
         .await
@@ -464,6 +470,7 @@ async fn remote_opens_multiple_inbound_substreams() {
     // open substream, poll the result and verify that the peer is in correct state
     tx.send(InnerTransportEvent::SubstreamOpened {
         peer,
+        connection: ConnectionId::from(0usize),
         protocol: protocol.clone(),
         fallback: None,
         direction: protocol::Direction::Inbound,
@@ -500,6 +507,7 @@ async fn remote_opens_multiple_inbound_substreams() {
 
     tx.send(InnerTransportEvent::SubstreamOpened {
         peer,
+        connection: ConnectionId::from(0usize),
         protocol: protocol.clone(),
         fallback: None,
         direction: protocol::Direction::Inbound,
@@ -553,6 +561,12 @@ async fn pending_outbound_tracked_correctly() {
     }
 
     // then register inbound substream and verify that the state is changed to `Validating`
+    //
+    // the race is resolved in favor of the substream opened by the node with smaller `PeerId`
+    let expected_direction = match notif.service.local_peer_id < peer {
+        true => Direction::Outbound,
+        false => Direction::Inbound,
+    };
     notif
         .on_inbound_substream(
             protocol.clone(),
@@ -571,12 +585,12 @@ async fn pending_outbound_tracked_correctly() {
         Some(PeerContext {
             state:
                 PeerState::Validating {
-                    direction: Direction::Outbound,
+                    direction,
                     outbound: OutboundState::OutboundInitiated { .. },
                     inbound: InboundState::ReadingHandshake,
                     ..
                 },
-        }) => {}
+        }) if direction == &expected_direction => {}
         state => panic!("invalid state: {state:?}"),
     }
 
@@ -601,12 +615,12 @@ async fn pending_outbound_tracked_correctly() {
         Some(PeerContext {
             state:
                 PeerState::Validating {
-                    direction: Direction::Outbound,
+                    direction,
                     outbound: OutboundState::OutboundInitiated { .. },
                     inbound: InboundState::Validating { .. },
                     ..
                 },
-        }) => {}
+        }) if direction == &expected_direction => {}
         state => panic!("invalid state: {state:?}"),
     }
 
@@ -1109,3 +1123,533 @@ async fn second_inbound_substream_opened_while_outbound_substream_was_opening()
         state => panic!("invalid state for peer: {state:?}"),
     }
 }
+
+#[tokio::test]
+async fn inbound_substream_rejected_when_no_slots_available() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let protocol = ProtocolName::from("/notif/1");
+    let (mut notif, _handle, _sender, mut tx) = make_notification_protocol();
+    notif.inbound_slots = Some(1);
+
+    let (peer1, _receiver1) = register_peer(&mut notif, &mut tx).await;
+    let (peer2, _receiver2) = register_peer(&mut notif, &mut tx).await;
+
+    // first inbound substream occupies the only inbound slot
+    notif
+        .on_inbound_substream(
+            protocol.clone(),
+            None,
+            peer1,
+            Substream::new_mock(
+                peer1,
+                SubstreamId::from(0usize),
+                Box::new(DummySubstream::new()),
+            ),
+        )
+        .await
+        .unwrap();
+
+    assert!(std::matches!(
+        notif.peers.get(&peer1),
+        Some(PeerContext {
+            state: PeerState::Validating {
+                direction: Direction::Inbound,
+                inbound: InboundState::ReadingHandshake,
+                ..
+            }
+        })
+    ));
+
+    // second inbound substream is rejected since there are no slots available
+    let mut substream = MockSubstream::new();
+    substream.expect_poll_close().times(1).return_once(|_| Poll::Ready(Ok(())));
+
+    notif
+        .on_inbound_substream(
+            protocol.clone(),
+            None,
+            peer2,
+            Substream::new_mock(peer2, SubstreamId::from(1usize), Box::new(substream)),
+        )
+        .await
+        .unwrap();
+
+    assert!(std::matches!(
+        notif.peers.get(&peer2),
+        Some(PeerContext {
+            state: PeerState::Closed { pending_open: None }
+        })
+    ));
+
+    // reserved peers don't need a slot
+    notif.reserved_peers.insert(peer2);
+
+    notif
+        .on_inbound_substream(
+            protocol.clone(),
+            None,
+            peer2,
+            Substream::new_mock(
+                peer2,
+                SubstreamId::from(2usize),
+                Box::new(DummySubstream::new()),
+            ),
+        )
+        .await
+        .unwrap();
+
+    assert!(std::matches!(
+        notif.peers.get(&peer2),
+        Some(PeerContext {
+            state: PeerState::Validating {
+                direction: Direction::Inbound,
+                inbound: InboundState::ReadingHandshake,
+                ..
+            }
+        })
+    ));
+}
+
+#[tokio::test]
+async fn slot_released_after_stream_closes() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (mut notif, mut handle, _sender, mut tx) = make_notification_protocol();
+    notif.outbound_slots = Some(1);
+
+    let (peer1, mut receiver1) = register_peer(&mut notif, &mut tx).await;
+    let (peer2, mut receiver2) = register_peer(&mut notif, &mut tx).await;
+
+    notif.on_open_substream(peer1).await.unwrap();
+    assert!(receiver1.try_recv().is_ok());
+
+    // all outbound slots are occupied
+    notif.on_open_substream(peer2).await.unwrap();
+    assert!(receiver2.try_recv().is_err());
+
+    match handle.next().await {
+        Some(NotificationEvent::NotificationStreamOpenFailure { peer, error }) => {
+            assert_eq!(peer, peer2);
+            assert_eq!(error, NotificationError::NoSlotAvailable);
+        }
+        event => panic!("invalid event received: {event:?}"),
+    }
+
+    // close the notification stream of the first peer which releases its slot
+    notif.peers.get_mut(&peer1).unwrap().state = PeerState::Closed { pending_open: None };
+
+    notif.on_open_substream(peer2).await.unwrap();
+    assert!(receiver2.try_recv().is_ok());
+}
+
+#[tokio::test]
+async fn reserved_only_rejects_non_reserved_peers() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (mut notif, mut handle, _sender, mut tx) = make_notification_protocol();
+    let (peer1, mut receiver1) = register_peer(&mut notif, &mut tx).await;
+    let (peer2, mut receiver2) = register_peer(&mut notif, &mut tx).await;
+
+    notif.reserved_only = true;
+    notif.reserved_peers.insert(peer1);
+
+    notif.on_open_substream(peer2).await.unwrap();
+    assert!(receiver2.try_recv().is_err());
+
+    match handle.next().await {
+        Some(NotificationEvent::NotificationStreamOpenFailure { peer, error }) => {
+            assert_eq!(peer, peer2);
+            assert_eq!(error, NotificationError::NoSlotAvailable);
+        }
+        event => panic!("invalid event received: {event:?}"),
+    }
+
+    notif.on_open_substream(peer1).await.unwrap();
+    assert!(receiver1.try_recv().is_ok());
+}
+
+#[tokio::test]
+async fn inbound_substream_auto_accepted() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let protocol = ProtocolName::from("/notif/1");
+    let (mut notif, mut handle, _sender, mut tx) = make_notification_protocol();
+    notif.auto_accept_all = true;
+
+    let (peer, mut receiver) = register_peer(&mut notif, &mut tx).await;
+
+    notif
+        .on_inbound_substream(
+            protocol.clone(),
+            None,
+            peer,
+            Substream::new_mock(
+                PeerId::random(),
+                SubstreamId::from(0usize),
+                Box::new(DummySubstream::new()),
+            ),
+        )
+        .await
+        .unwrap();
+
+    notif
+        .on_handshake_event(
+            peer,
+            HandshakeEvent::Negotiated {
+                peer,
+                handshake: vec![1, 3, 3, 7],
+                substream: Substream::new_mock(
+                    PeerId::random(),
+                    SubstreamId::from(0usize),
+                    Box::new(DummySubstream::new()),
+                ),
+                direction: protocol::notification::negotiation::Direction::Inbound,
+            },
+        )
+        .await;
+
+    // verify that the local handshake is sent and an outbound substream is opened without
+    // validating the substream with the user
+    match notif.peers.get(&peer) {
+        Some(PeerContext {
+            state:
+                PeerState::Validating {
+                    direction: Direction::Inbound,
+                    outbound: OutboundState::OutboundInitiated { .. },
+                    inbound: InboundState::SendingHandshake,
+                    ..
+                },
+        }) => {}
+        state => panic!("invalid state: {state:?}"),
+    }
+
+    assert!(receiver.try_recv().is_ok());
+    assert!(tokio::time::timeout(Duration::from_secs(1), handle.next()).await.is_err());
+}
+
+#[tokio::test]
+async fn substream_validation_times_out() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let protocol = ProtocolName::from("/notif/1");
+    let (mut notif, mut handle, _sender, mut tx) = make_notification_protocol();
+    notif.validation_timeout = Some(Duration::from_millis(100));
+
+    let (peer, _receiver) = register_peer(&mut notif, &mut tx).await;
+
+    notif
+        .on_inbound_substream(
+            protocol.clone(),
+            None,
+            peer,
+            Substream::new_mock(
+                PeerId::random(),
+                SubstreamId::from(0usize),
+                Box::new(DummySubstream::new()),
+            ),
+        )
+        .await
+        .unwrap();
+
+    notif
+        .on_handshake_event(
+            peer,
+            HandshakeEvent::Negotiated {
+                peer,
+                handshake: vec![1, 3, 3, 7],
+                substream: Substream::new_mock(
+                    PeerId::random(),
+                    SubstreamId::from(0usize),
+                    Box::new(DummySubstream::new()),
+                ),
+                direction: protocol::notification::negotiation::Direction::Inbound,
+            },
+        )
+        .await;
+
+    match handle.next().await {
+        Some(NotificationEvent::ValidateSubstream {
+            peer: event_peer, ..
+        }) => {
+            assert_eq!(peer, event_peer);
+        }
+        event => panic!("invalid event received: {event:?}"),
+    }
+
+    // don't answer to the validation request and poll the protocol until the validation expires
+    tokio::time::timeout(Duration::from_secs(5), notif.next_event()).await.unwrap();
+
+    match notif.peers.get(&peer) {
+        Some(PeerContext {
+            state: PeerState::Closed { pending_open: None },
+        }) => {}
+        state => panic!("invalid state: {state:?}"),
+    }
+
+    match handle.next().await {
+        Some(NotificationEvent::NotificationStreamOpenFailure {
+            peer: event_peer,
+            error,
+        }) => {
+            assert_eq!(peer, event_peer);
+            assert_eq!(error, NotificationError::ValidationTimeout);
+        }
+        event => panic!("invalid event received: {event:?}"),
+    }
+}
+
+#[tokio::test]
+async fn custom_handshake_sent_on_accept() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let protocol = ProtocolName::from("/notif/1");
+    let (mut notif, mut handle, _sender, mut tx) = make_notification_protocol();
+    let (peer, _receiver) = register_peer(&mut notif, &mut tx).await;
+
+    notif
+        .on_inbound_substream(
+            protocol.clone(),
+            None,
+            peer,
+            Substream::new_mock(
+                PeerId::random(),
+                SubstreamId::from(0usize),
+                Box::new(DummySubstream::new()),
+            ),
+        )
+        .await
+        .unwrap();
+
+    let mut substream = MockSubstream::new();
+    substream.expect_poll_ready().times(1).return_once(|_| Poll::Ready(Ok(())));
+    substream
+        .expect_start_send()
+        .withf(|handshake| handshake.as_ref() == [1, 3, 3, 8])
+        .times(1)
+        .return_once(|_| Err(Error::Unknown));
+
+    notif
+        .on_handshake_event(
+            peer,
+            HandshakeEvent::Negotiated {
+                peer,
+                handshake: vec![1, 3, 3, 7],
+                substream: Substream::new_mock(
+                    PeerId::random(),
+                    SubstreamId::from(0usize),
+                    Box::new(substream),
+                ),
+                direction: protocol::notification::negotiation::Direction::Inbound,
+            },
+        )
+        .await;
+
+    match handle.next().await {
+        Some(NotificationEvent::ValidateSubstream {
+            peer: event_peer, ..
+        }) => {
+            assert_eq!(peer, event_peer);
+        }
+        event => panic!("invalid event received: {event:?}"),
+    }
+
+    // accept the substream with a custom handshake and verify it's sent to remote
+    handle.accept_with_handshake(peer, vec![1, 3, 3, 8]);
+    notif.next_event().await;
+
+    match notif.negotiation.next().await {
+        Some((
+            event_peer,
+            HandshakeEvent::NegotiationError {
+                peer: error_peer, ..
+            },
+        )) => {
+            assert_eq!(event_peer, peer);
+            assert_eq!(error_peer, peer);
+        }
+        _ => panic!("invalid event received"),
+    }
+}
+
+#[tokio::test]
+async fn sink_queue_info() {
+    let (sync_tx, _sync_rx) = sync_channel(2, ClogPolicy::Error);
+    let (async_tx, _async_rx) = channel(4);
+    let (priority_tx, _priority_rx) = channel(2);
+    let sink = NotificationSink::new(
+        PeerId::random(),
+        1024,
+        sync_tx,
+        async_tx,
+        priority_tx,
+        Default::default(),
+        None,
+    );
+
+    sink.send_sync_notification(vec![1]).unwrap();
+    sink.send_sync_notification(vec![2]).unwrap();
+    assert_eq!(
+        sink.send_sync_notification(vec![3]),
+        Err(NotificationError::ChannelClogged)
+    );
+    sink.send_async_notification(vec![4]).await.unwrap();
+    sink.send_sync_notification_with_priority(vec![5], NotificationPriority::High)
+        .unwrap();
+
+    assert_eq!(
+        sink.queue_info(),
+        NotificationQueueInfo {
+            sync_queued: 2,
+            sync_capacity: 2,
+            async_queued: 1,
+            async_capacity: 4,
+            priority_queued: 1,
+            priority_capacity: 2,
+            clogged: 1,
+            dropped: 0,
+        }
+    );
+
+    let (sync_tx, _sync_rx) = sync_channel(2, ClogPolicy::DropOldest);
+    let (async_tx, _async_rx) = channel(4);
+    let (priority_tx, _priority_rx) = channel(2);
+    let sink = NotificationSink::new(
+        PeerId::random(),
+        1024,
+        sync_tx,
+        async_tx,
+        priority_tx,
+        Default::default(),
+        None,
+    );
+
+    for i in 0..4u8 {
+        sink.send_sync_notification(vec![i]).unwrap();
+    }
+
+    let info = sink.queue_info();
+    assert_eq!(info.sync_queued, 2);
+    assert_eq!(info.clogged, 0);
+    assert_eq!(info.dropped, 2);
+}
+
+#[tokio::test]
+async fn sink_buffer_limit_reached() {
+    let budget = Arc::new(BufferBudget::new(4, SheddingPolicy::DropNew));
+    let (sync_tx, mut sync_rx) = sync_channel(8, ClogPolicy::Error);
+    let (async_tx, _async_rx) = channel(8);
+    let (priority_tx, _priority_rx) = channel(8);
+    let sink = NotificationSink::new(
+        PeerId::random(),
+        1024,
+        sync_tx,
+        async_tx,
+        priority_tx,
+        Default::default(),
+        Some(budget),
+    );
+
+    sink.send_sync_notification(vec![1, 2, 3]).unwrap();
+    assert_eq!(
+        sink.send_sync_notification(vec![4, 5]),
+        Err(NotificationError::BufferLimitReached)
+    );
+    assert!(std::matches!(
+        sink.send_async_notification(vec![4, 5]).await,
+        Err(Error::NotificationError(
+            crate::error::NotificationError::BufferLimitReached
+        ))
+    ));
+
+    // dequeueing the notification releases the buffered bytes
+    assert!(sync_rx.recv().await.is_some());
+    sink.send_sync_notification(vec![4, 5]).unwrap();
+}
+
+#[tokio::test]
+async fn outbound_fallback_reported_when_inbound_opened_first() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let protocol = ProtocolName::from("/notif/1");
+    let fallback = ProtocolName::from("/notif/1/fallback/1");
+    let (mut notif, mut handle, _sender, mut tx) = make_notification_protocol();
+    let (peer, _receiver) = register_peer(&mut notif, &mut tx).await;
+
+    // remote opens an inbound substream using the main protocol name
+    notif
+        .on_inbound_substream(
+            protocol.clone(),
+            None,
+            peer,
+            Substream::new_mock(
+                PeerId::random(),
+                SubstreamId::from(0usize),
+                Box::new(DummySubstream::new()),
+            ),
+        )
+        .await
+        .unwrap();
+
+    notif
+        .on_handshake_event(
+            peer,
+            HandshakeEvent::Negotiated {
+                peer,
+                handshake: vec![1, 3, 3, 7],
+                substream: Substream::new_mock(
+                    PeerId::random(),
+                    SubstreamId::from(0usize),
+                    Box::new(DummySubstream::new()),
+                ),
+                direction: protocol::notification::negotiation::Direction::Inbound,
+            },
+        )
+        .await;
+
+    // discard the validation event and accept the substream
+    assert!(tokio::time::timeout(Duration::from_secs(5), handle.next()).await.is_ok());
+    notif.on_validation_result(peer, ValidationResult::Accept).await.unwrap();
+
+    // the outbound substream is negotiated using a fallback
+    notif
+        .on_outbound_substream(
+            protocol,
+            Some(fallback.clone()),
+            peer,
+            SubstreamId::new(),
+            Substream::new_mock(
+                PeerId::random(),
+                SubstreamId::from(0usize),
+                Box::new(DummySubstream::new()),
+            ),
+        )
+        .await
+        .unwrap();
+
+    match notif.peers.get(&peer) {
+        Some(PeerContext {
+            state:
+                PeerState::Validating {
+                    fallback: Some(negotiated),
+                    outbound: OutboundState::Negotiating,
+                    ..
+                },
+        }) => assert_eq!(negotiated, &fallback),
+        state => panic!("invalid state: {state:?}"),
+    }
+}
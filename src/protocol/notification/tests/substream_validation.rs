@@ -32,7 +32,7 @@ use crate::{
         InnerTransportEvent, ProtocolCommand,
     },
     substream::Substream,
-    transport::Endpoint,
+    transport::{Endpoint, SupportedTransport},
     types::{protocol::ProtocolName, ConnectionId, SubstreamId},
     PeerId,
 };
@@ -75,6 +75,7 @@ async fn substream_accepted() {
     tx.send(InnerTransportEvent::ConnectionEstablished {
         peer,
         endpoint: Endpoint::dialer(Multiaddr::empty(), ConnectionId::from(0usize)),
+        transport: SupportedTransport::Tcp,
         sender: ConnectionHandle::new(ConnectionId::from(0usize), proto_tx.clone()),
         connection: ConnectionId::from(0usize),
     })
@@ -262,6 +263,7 @@ async fn accept_fails_due_to_closed_substream() {
     tx.send(InnerTransportEvent::ConnectionEstablished {
         peer,
         endpoint: Endpoint::dialer(Multiaddr::empty(), ConnectionId::from(0usize)),
+        transport: SupportedTransport::Tcp,
         sender: ConnectionHandle::new(ConnectionId::from(0usize), proto_tx),
         connection: ConnectionId::from(0usize),
     })
@@ -352,6 +354,7 @@ async fn accept_fails_due_to_closed_connection() {
     tx.send(InnerTransportEvent::ConnectionEstablished {
         peer,
         endpoint: Endpoint::dialer(Multiaddr::empty(), ConnectionId::from(0usize)),
+        transport: SupportedTransport::Tcp,
         sender: ConnectionHandle::new(ConnectionId::from(0usize), proto_tx),
         connection: ConnectionId::from(0usize),
     })
@@ -19,13 +19,15 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
-    protocol::notification::handle::NotificationSink, types::protocol::ProtocolName, PeerId,
+    error::ErrorKind, protocol::notification::handle::NotificationSink,
+    types::protocol::ProtocolName, PeerId,
 };
 
 use bytes::BytesMut;
+use multiaddr::Multiaddr;
 use tokio::sync::oneshot;
 
-use std::collections::HashSet;
+use std::{collections::HashSet, time::Duration};
 
 /// Default channel size for synchronous notifications.
 pub(super) const SYNC_CHANNEL_SIZE: usize = 2048;
@@ -43,6 +45,91 @@ pub enum Direction {
     Outbound,
 }
 
+/// Policy for handling a clogged synchronous notification channel.
+///
+/// The synchronous notification channel of a peer is clogged if the peer doesn't read
+/// notifications as fast as they are sent to it.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ClogPolicy {
+    /// Reject the notification with [`NotificationError::ChannelClogged`] and, if the
+    /// notification was sent using [`NotificationHandle`](super::NotificationHandle), close the
+    /// connection to the peer.
+    #[default]
+    ForceClose,
+
+    /// Reject the notification with [`NotificationError::ChannelClogged`] and keep the
+    /// connection open.
+    Error,
+
+    /// Drop the oldest queued notification to make room for the new one.
+    ///
+    /// The channel capacity is rounded up to the next power of two.
+    DropOldest,
+}
+
+/// Policy applied when the notification buffer limit of the protocol is reached.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum SheddingPolicy {
+    /// Reject the new notification with [`NotificationError::BufferLimitReached`].
+    #[default]
+    DropNew,
+
+    /// Drop all queued notifications of the peer with the most buffered bytes.
+    DropFromSlowest,
+
+    /// Close the notification stream of the peer with the most buffered bytes.
+    CloseSlowest,
+}
+
+/// Priority of a notification.
+///
+/// High-priority notifications have their own send queue which is always emptied before any
+/// normal-priority notifications are sent to the peer. The capacity of the high-priority queue is
+/// the synchronous channel size of the protocol.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum NotificationPriority {
+    /// Send the notification before any queued normal-priority notifications.
+    High,
+
+    /// Send the notification in the order it was queued.
+    #[default]
+    Normal,
+}
+
+/// Backoff for automatically reopening notification substreams that failed to open.
+///
+/// The delay before retry `n` is `initial_delay * 2^n`, capped at `max_delay`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ReopenBackoff {
+    /// Maximum number of retries before the failure is reported to the user.
+    pub max_retries: usize,
+
+    /// Delay before the first retry.
+    pub initial_delay: Duration,
+
+    /// Maximum delay between retries.
+    pub max_delay: Duration,
+}
+
+impl ReopenBackoff {
+    /// Get the delay before retry `attempt`.
+    pub(crate) fn delay(&self, attempt: usize) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.try_into().unwrap_or(u32::MAX));
+
+        self.initial_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+impl Default for ReopenBackoff {
+    fn default() -> Self {
+        Self {
+            max_retries: 5usize,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
 /// Validation result.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ValidationResult {
@@ -51,14 +138,74 @@ pub enum ValidationResult {
 
     /// Reject the inbound substream.
     Reject,
+
+    /// Reject the inbound substream and send a rejection code to remote peer.
+    ///
+    /// The code is reported to remote peer in
+    /// [`NotificationError::RejectedWithCode`] and allows it to distinguish
+    /// between different rejection reasons, such as the node being full or the peer being banned.
+    ///
+    /// Only litep2p peers understand the rejection code, other implementations will see the
+    /// substream open and then immediately close.
+    RejectWithCode(u8),
+}
+
+/// Information about an open notification stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationStreamInfo {
+    /// Protocol name.
+    pub protocol: ProtocolName,
+
+    /// Fallback, if the substream was negotiated using a fallback protocol.
+    pub fallback: Option<ProtocolName>,
+
+    /// Direction of the substream.
+    pub direction: Direction,
+
+    /// Handshake received from the remote peer.
+    pub handshake: Vec<u8>,
+}
+
+/// Send queue statistics of a notification stream.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct NotificationQueueInfo {
+    /// Number of notifications queued in the synchronous send queue.
+    pub sync_queued: usize,
+
+    /// Capacity of the synchronous send queue.
+    pub sync_capacity: usize,
+
+    /// Number of notifications queued in the asynchronous send queue.
+    pub async_queued: usize,
+
+    /// Capacity of the asynchronous send queue.
+    pub async_capacity: usize,
+
+    /// Number of notifications queued in the high-priority send queue.
+    pub priority_queued: usize,
+
+    /// Capacity of the high-priority send queue.
+    pub priority_capacity: usize,
+
+    /// Number of synchronous notifications rejected because the queue was clogged.
+    pub clogged: usize,
+
+    /// Number of queued synchronous notifications dropped to make room for new ones.
+    ///
+    /// Only notifications sent with [`ClogPolicy::DropOldest`] are dropped.
+    pub dropped: usize,
 }
 
 /// Notification error.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum NotificationError {
     /// Remote rejected the substream.
     Rejected,
 
+    /// Remote rejected the substream with a rejection code.
+    RejectedWithCode(u8),
+
     /// Connection to peer doesn't exist.
     NoConnection,
 
@@ -68,13 +215,55 @@ pub enum NotificationError {
     /// Validation for a previous substream still pending.
     ValidationPending,
 
+    /// User didn't answer to the substream validation request in time.
+    ValidationTimeout,
+
     /// Failed to dial peer.
     DialFailure,
 
     /// Notification protocol has been closed.
     EssentialTaskClosed,
+
+    /// Notification is larger than the maximum notification size of the protocol.
+    NotificationTooLarge {
+        /// Size of the notification.
+        size: usize,
+
+        /// Maximum notification size.
+        max_size: usize,
+    },
+
+    /// Notification buffer limit of the protocol has been reached.
+    BufferLimitReached,
+
+    /// All slots of the protocol are occupied or the protocol only accepts reserved peers
+    /// and the peer is not reserved.
+    NoSlotAvailable,
+}
+
+impl NotificationError {
+    /// Get the [`ErrorKind`] of the error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            NotificationError::Rejected | NotificationError::RejectedWithCode(_) =>
+                ErrorKind::Refused,
+            NotificationError::NoConnection => ErrorKind::NotFound,
+            NotificationError::ChannelClogged => ErrorKind::ChannelFailure,
+            NotificationError::ValidationPending => ErrorKind::AlreadyExists,
+            NotificationError::ValidationTimeout => ErrorKind::Timeout,
+            NotificationError::DialFailure => ErrorKind::Unreachable,
+            NotificationError::EssentialTaskClosed => ErrorKind::ChannelFailure,
+            NotificationError::NoSlotAvailable => ErrorKind::Refused,
+            NotificationError::NotificationTooLarge { .. } => ErrorKind::InvalidInput,
+            NotificationError::BufferLimitReached => ErrorKind::ChannelFailure,
+        }
+    }
 }
 
+/// Validation result and the handshake to send to the peer, if it's different from the
+/// handshake of the protocol.
+pub(crate) type ValidationResponse = (ValidationResult, Option<Vec<u8>>);
+
 /// Notification events.
 pub(crate) enum InnerNotificationEvent {
     /// Validate substream.
@@ -92,7 +281,7 @@ pub(crate) enum InnerNotificationEvent {
         handshake: Vec<u8>,
 
         /// `oneshot::Sender` for sending the validation result back to the protocol.
-        tx: oneshot::Sender<ValidationResult>,
+        tx: oneshot::Sender<ValidationResponse>,
     },
 
     /// Notification stream opened.
@@ -122,6 +311,12 @@ pub(crate) enum InnerNotificationEvent {
         peer: PeerId,
     },
 
+    /// Notification stream closed by the local node.
+    NotificationStreamClosedLocally {
+        /// Peer ID.
+        peer: PeerId,
+    },
+
     /// Failed to open notification stream.
     NotificationStreamOpenFailure {
         /// Peer ID.
@@ -156,6 +351,9 @@ pub enum NotificationEvent {
         protocol: ProtocolName,
 
         /// Fallback, if the substream was negotiated using a fallback protocol.
+        ///
+        /// This is the protocol name agreed on for the outbound substream which notifications
+        /// to the peer are sent over and can be used to select the encoding for legacy peers.
         fallback: Option<ProtocolName>,
 
         /// Direction of the substream.
@@ -173,12 +371,22 @@ pub enum NotificationEvent {
         handshake: Vec<u8>,
     },
 
-    /// Notification stream closed.
+    /// Notification stream closed by the remote peer or because the connection to them closed.
     NotificationStreamClosed {
         /// Peer ID.
         peer: PeerId,
     },
 
+    /// Notification stream closed by the local node.
+    ///
+    /// Emitted after the stream was closed using
+    /// [`NotificationHandle::close_substream()`](super::NotificationHandle::close_substream())
+    /// or one of its variants.
+    NotificationStreamClosedLocally {
+        /// Peer ID.
+        peer: PeerId,
+    },
+
     /// Failed to open notification stream.
     NotificationStreamOpenFailure {
         /// Peer ID.
@@ -206,15 +414,76 @@ pub(crate) enum NotificationCommand {
         peers: HashSet<PeerId>,
     },
 
+    /// Open substream to peer using a peer-specific handshake.
+    OpenSubstreamWithHandshake {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// Handshake.
+        handshake: Vec<u8>,
+    },
+
     /// Close substreams to one or more peers.
     CloseSubstream {
         /// Peer IDs.
         peers: HashSet<PeerId>,
     },
 
+    /// Close substream to peer and acknowledge the close over `tx` once remote has closed
+    /// its side of the stream.
+    CloseSubstreamAcknowledged {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// TX channel for acknowledging the close.
+        tx: oneshot::Sender<()>,
+    },
+
     /// Force close the connection because notification channel is clogged.
     ForceClose {
         /// Peer to disconnect.
         peer: PeerId,
     },
+
+    /// Close all connections to peer.
+    Disconnect {
+        /// Peer to disconnect.
+        peer: PeerId,
+    },
+
+    /// Ban peer.
+    BanPeer {
+        /// Peer to ban.
+        peer: PeerId,
+
+        /// Duration of the ban.
+        duration: Duration,
+    },
+
+    /// Add known addresses for peer.
+    AddKnownAddress {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// Addresses of the peer.
+        addresses: Vec<Multiaddr>,
+    },
+
+    /// Add reserved peer.
+    AddReservedPeer {
+        /// Peer ID.
+        peer: PeerId,
+    },
+
+    /// Remove reserved peer.
+    RemoveReservedPeer {
+        /// Peer ID.
+        peer: PeerId,
+    },
+
+    /// Only accept reserved peers.
+    SetReservedOnly {
+        /// Whether only reserved peers are accepted.
+        reserved_only: bool,
+    },
 }
@@ -27,19 +27,24 @@ use crate::{
     },
     substream::Substream,
     transport::{
-        manager::{ProtocolContext, TransportManagerEvent},
-        Endpoint,
+        manager::{ProtocolContext, ProtocolRegistry, SupportedTransport, TransportManagerEvent},
+        quic::PathStats,
+        DisconnectReason, Endpoint,
     },
     types::{protocol::ProtocolName, ConnectionId, SubstreamId},
     PeerId,
 };
 
-use futures::{stream::FuturesUnordered, Stream, StreamExt};
+use futures::{stream::FuturesUnordered, task::AtomicWaker, FutureExt, Stream, StreamExt};
 use multiaddr::Multiaddr;
-use tokio::sync::mpsc::{channel, Receiver, Sender};
+use parking_lot::RwLock;
+use tokio::{
+    sync::mpsc::{channel, error::TrySendError, Receiver, Sender},
+    time::{Instant, Sleep},
+};
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::Debug,
     pin::Pin,
     sync::{
@@ -47,6 +52,7 @@ use std::{
         Arc,
     },
     task::{Context, Poll},
+    time::Duration,
 };
 
 /// Logging target for the file.
@@ -66,6 +72,9 @@ pub enum InnerTransportEvent {
         /// Endpoint.
         endpoint: Endpoint,
 
+        /// Transport of the connection.
+        transport: SupportedTransport,
+
         /// Handle for communicating with the connection.
         sender: ConnectionHandle,
     },
@@ -95,6 +104,9 @@ pub enum InnerTransportEvent {
         /// Peer ID.
         peer: PeerId,
 
+        /// Connection ID.
+        connection: ConnectionId,
+
         /// Protocol name.
         ///
         /// One protocol handler may handle multiple sub-protocols (such as `/ipfs/identify/1.0.0`
@@ -143,12 +155,14 @@ impl From<InnerTransportEvent> for TransportEvent {
                 TransportEvent::DialFailure { peer, address },
             InnerTransportEvent::SubstreamOpened {
                 peer,
+                connection,
                 protocol,
                 fallback,
                 direction,
                 substream,
             } => TransportEvent::SubstreamOpened {
                 peer,
+                connection_id: connection,
                 protocol,
                 fallback,
                 direction,
@@ -203,6 +217,42 @@ pub enum ProtocolCommand {
     ForceClose,
 }
 
+/// Policy for handling substreams opened for a protocol which isn't keeping up with its events.
+///
+/// Each protocol receives the events of a connection over a bounded channel, the size of which is
+/// configured with
+/// [`ConfigBuilder::with_protocol_channel_size()`](crate::config::ConfigBuilder::with_protocol_channel_size).
+/// Once the channel is full, the protocol is considered slow and the policy decides what happens
+/// to the substreams opened for it.
+///
+/// Connection events are always delivered to the protocol, regardless of the policy.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SlowProtocolPolicy {
+    /// Wait until the protocol has room for the substream.
+    ///
+    /// The connection doesn't process any other substreams while it's waiting, so a slow
+    /// protocol also delays the other protocols of the connection.
+    #[default]
+    Park,
+
+    /// Drop the substream so that the other protocols of the connection are not delayed.
+    ///
+    /// The substream is reset and the remote peer sees it as closed.
+    Drop,
+}
+
+/// Keep-alive state of a connection.
+struct KeepAlive {
+    /// How long the connection is kept open after its last substream was closed.
+    timeout: Duration,
+
+    /// Timer for checking whether the connection has been idle for `timeout`.
+    timer: Pin<Box<Sleep>>,
+
+    /// Active handle which keeps the connection open while it has no open substreams.
+    _connection: ConnectionHandle,
+}
+
 /// Supported protocol information.
 ///
 /// Each connection gets a copy of [`ProtocolSet`] which allows it to interact
@@ -212,19 +262,41 @@ pub struct ProtocolSet {
     pub(crate) protocols: HashMap<ProtocolName, ProtocolContext>,
     mgr_tx: Sender<TransportManagerEvent>,
     connection: ConnectionHandle,
+    transport: SupportedTransport,
     rx: Receiver<ProtocolCommand>,
     next_substream_id: Arc<AtomicUsize>,
     fallback_names: HashMap<ProtocolName, ProtocolName>,
+    lifetime: Option<Pin<Box<Sleep>>>,
+    keep_alive: Option<KeepAlive>,
+    negotiated: Arc<RwLock<HashSet<ProtocolName>>>,
+    slow_protocol_policy: SlowProtocolPolicy,
+
+    /// Registry of installed protocols and the version of the protocol set in use.
+    registry: Option<(ProtocolRegistry, usize)>,
+
+    /// Waker which is woken up when the installed protocols change.
+    waker: Arc<AtomicWaker>,
+
+    /// Peer and endpoint of the connection, once it's established.
+    established: Option<(PeerId, Endpoint)>,
+
+    /// Reserved peers of the transport manager.
+    reserved_peers: Arc<RwLock<HashSet<PeerId>>>,
+
+    /// Active handle which keeps a connection to a reserved peer open.
+    keep_open: Option<ConnectionHandle>,
 }
 
 impl ProtocolSet {
     pub fn new(
         connection_id: ConnectionId,
+        transport: SupportedTransport,
         mgr_tx: Sender<TransportManagerEvent>,
         next_substream_id: Arc<AtomicUsize>,
         protocols: HashMap<ProtocolName, ProtocolContext>,
+        channel_size: usize,
     ) -> Self {
-        let (tx, rx) = channel(256);
+        let (tx, rx) = channel(channel_size);
 
         let fallback_names = protocols
             .iter()
@@ -245,6 +317,144 @@ impl ProtocolSet {
             next_substream_id,
             fallback_names,
             connection: ConnectionHandle::new(connection_id, tx),
+            transport,
+            lifetime: None,
+            keep_alive: None,
+            negotiated: Default::default(),
+            slow_protocol_policy: SlowProtocolPolicy::default(),
+            registry: None,
+            waker: Arc::new(AtomicWaker::new()),
+            established: None,
+            reserved_peers: Default::default(),
+            keep_open: None,
+        }
+    }
+
+    /// Close the connection after `lifetime` has elapsed.
+    ///
+    /// Once the lifetime has elapsed, [`ProtocolCommand::ForceClose`] is returned to the
+    /// connection which then closes itself as if a protocol had forcibly closed it.
+    pub(crate) fn set_max_lifetime(&mut self, lifetime: Duration) {
+        self.lifetime = Some(Box::pin(tokio::time::sleep(lifetime)));
+    }
+
+    /// Close the connection once it has had no open substreams for `timeout`.
+    ///
+    /// Each substream holds a [`Permit`] and the connection is kept open for as long as any
+    /// protocol has a substream open over it. Once the last substream is closed, the connection
+    /// is kept open for `timeout` to allow protocols to open new substreams, after which the
+    /// stream of commands is terminated and the connection closes itself as inactive.
+    ///
+    /// Must be called before the connection is reported to protocols.
+    pub(crate) fn set_keep_alive_timeout(&mut self, timeout: Duration) {
+        self.keep_alive = Some(KeepAlive {
+            timeout,
+            timer: Box::pin(tokio::time::sleep(timeout)),
+            _connection: self.connection.clone(),
+        });
+    }
+
+    /// Limit the number of concurrently open substreams to `max_substreams`.
+    ///
+    /// The limit applies to the [`Permit`]s of the connection so it's shared by inbound and
+    /// outbound substreams of all protocols.
+    pub(crate) fn set_max_substreams(&mut self, max_substreams: usize) {
+        self.connection.substreams().set_limit(max_substreams);
+    }
+
+    /// Keep the connection open if it's established to one of `reserved_peers`.
+    ///
+    /// Connections to reserved peers are not closed when protocols are no longer interested in
+    /// them, nor when they have been idle for the keep-alive timeout.
+    pub(crate) fn set_reserved_peers(&mut self, reserved_peers: Arc<RwLock<HashSet<PeerId>>>) {
+        self.reserved_peers = reserved_peers;
+    }
+
+    /// Set the policy for handling substreams opened for slow protocols.
+    pub(crate) fn set_slow_protocol_policy(&mut self, policy: SlowProtocolPolicy) {
+        self.slow_protocol_policy = policy;
+    }
+
+    /// Keep the protocol set in sync with `registry`.
+    ///
+    /// `version` is the version of the protocol set the connection was created with. Protocols
+    /// installed to the registry later are added to the connection and, if the connection is
+    /// already established, reported to them as open connections.
+    pub(crate) fn set_protocol_registry(&mut self, registry: ProtocolRegistry, version: usize) {
+        registry.register_waker(&self.waker);
+        self.registry = Some((registry, version));
+    }
+
+    /// Add protocols which have been installed and remove protocols which have been uninstalled
+    /// since the protocol set was last synced.
+    fn sync_protocols(&mut self) {
+        let Some((registry, version)) = self.registry.as_mut() else {
+            return;
+        };
+
+        if registry.version() == *version {
+            return;
+        }
+
+        let (new_version, protocols) = registry.protocols();
+        *version = new_version;
+
+        // dropping the context of an uninstalled protocol allows its event loop to exit once
+        // every connection has dropped it
+        let connection_id = *self.connection.connection_id();
+        self.protocols.retain(|protocol, _| {
+            let installed = protocols.contains_key(protocol);
+
+            if !installed {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    %protocol,
+                    ?connection_id,
+                    "protocol uninstalled",
+                );
+            }
+
+            installed
+        });
+        self.fallback_names.retain(|_, protocol| protocols.contains_key(protocol));
+
+        for (protocol, context) in protocols {
+            if self.protocols.contains_key(&protocol) {
+                continue;
+            }
+
+            tracing::debug!(
+                target: LOG_TARGET,
+                %protocol,
+                connection_id = ?self.connection.connection_id(),
+                "protocol installed",
+            );
+
+            for fallback in &context.fallback_names {
+                self.fallback_names.insert(fallback.clone(), protocol.clone());
+            }
+
+            if let Some((peer, endpoint)) = &self.established {
+                if let Err(error) =
+                    context.tx.try_send(InnerTransportEvent::ConnectionEstablished {
+                        peer: *peer,
+                        connection: endpoint.connection_id(),
+                        endpoint: endpoint.clone(),
+                        transport: self.transport,
+                        sender: self.connection.clone(),
+                    })
+                {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        %protocol,
+                        ?peer,
+                        ?error,
+                        "failed to report open connection to installed protocol",
+                    );
+                }
+            }
+
+            self.protocols.insert(protocol, context);
         }
     }
 
@@ -253,13 +463,23 @@ impl ProtocolSet {
         self.connection.try_get_permit()
     }
 
+    /// Check if the connection has more substreams open than it's allowed to.
+    ///
+    /// Called after the permit for an inbound substream has been acquired and if the limit has
+    /// been exceeded, the substream must be rejected.
+    pub fn substream_limit_exceeded(&self) -> bool {
+        self.connection.substreams().is_limit_exceeded()
+    }
+
     /// Get next substream ID.
     pub fn next_substream_id(&self) -> SubstreamId {
         SubstreamId::from(self.next_substream_id.fetch_add(1usize, Ordering::Relaxed))
     }
 
     /// Get the list of all supported protocols.
-    pub fn protocols(&self) -> Vec<ProtocolName> {
+    pub fn protocols(&mut self) -> Vec<ProtocolName> {
+        self.sync_protocols();
+
         self.protocols
             .keys()
             .cloned()
@@ -276,35 +496,79 @@ impl ProtocolSet {
         substream: Substream,
     ) -> crate::Result<()> {
         tracing::debug!(target: LOG_TARGET, %protocol, ?peer, ?direction, "substream opened");
+        self.sync_protocols();
 
         let (protocol, fallback) = match self.fallback_names.get(&protocol) {
             Some(main_protocol) => (main_protocol.clone(), Some(protocol)),
             None => (protocol, None),
         };
 
-        self.protocols
-            .get_mut(&protocol)
-            .ok_or(Error::ProtocolNotSupported(protocol.to_string()))?
-            .tx
-            .send(InnerTransportEvent::SubstreamOpened {
-                peer,
-                protocol: protocol.clone(),
-                fallback,
-                direction,
-                substream,
-            })
-            .await
-            .map_err(From::from)
+        // the protocol may have been uninstalled while the substream was being negotiated
+        let Some(context) = self.protocols.get_mut(&protocol) else {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %protocol,
+                ?peer,
+                "protocol has been uninstalled, dropping substream",
+            );
+            return Ok(());
+        };
+
+        if !self.negotiated.read().contains(&protocol) {
+            self.negotiated.write().insert(protocol.clone());
+        }
+
+        let event = InnerTransportEvent::SubstreamOpened {
+            peer,
+            connection: *self.connection.connection_id(),
+            protocol: protocol.clone(),
+            fallback,
+            direction,
+            substream,
+        };
+
+        match context.tx.try_send(event) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Closed(_)) => Err(Error::EssentialTaskClosed),
+            Err(TrySendError::Full(_)) if self.slow_protocol_policy == SlowProtocolPolicy::Drop => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    %protocol,
+                    ?peer,
+                    ?direction,
+                    "protocol is slow, dropping substream",
+                );
+                Ok(())
+            }
+            Err(TrySendError::Full(event)) => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    %protocol,
+                    ?peer,
+                    ?direction,
+                    "protocol is slow, waiting for it to receive substream",
+                );
+                context.tx.send(event).await.map_err(From::from)
+            }
+        }
     }
 
     /// Get codec used by the protocol.
     pub fn protocol_codec(&self, protocol: &ProtocolName) -> ProtocolCodec {
-        // NOTE: `protocol` must exist in `self.protocol` as it was negotiated
-        // using the protocols from this set
+        // NOTE: `protocol` was negotiated using the protocols from this set but it may have been
+        // uninstalled since, in which case the substream is dropped when it's reported
         self.protocols
             .get(self.fallback_names.get(&protocol).map_or(protocol, |protocol| protocol))
-            .expect("protocol to exist")
-            .codec
+            .map_or(ProtocolCodec::Unspecified, |context| context.codec)
+    }
+
+    /// Get default substream priority of the protocol.
+    pub fn stream_priority(&self, protocol: &ProtocolName) -> i32 {
+        // NOTE: `protocol` was negotiated using the protocols from this set but it may have been
+        // uninstalled since, in which case the substream is dropped when it's reported
+        self.protocols
+            .get(self.fallback_names.get(protocol).map_or(protocol, |protocol| protocol))
+            .map_or(0, |context| context.stream_priority)
     }
 
     /// Report to `protocol` that connection failed to open substream for `peer`.
@@ -322,9 +586,12 @@ impl ProtocolSet {
             "failed to open substream",
         );
 
-        self.protocols
-            .get_mut(&protocol)
-            .ok_or(Error::ProtocolNotSupported(protocol.to_string()))?
+        let Some(context) = self.protocols.get_mut(&protocol) else {
+            tracing::debug!(target: LOG_TARGET, %protocol, "protocol has been uninstalled");
+            return Ok(());
+        };
+
+        context
             .tx
             .send(InnerTransportEvent::SubstreamOpenFailure { substream, error })
             .await
@@ -337,7 +604,18 @@ impl ProtocolSet {
         peer: PeerId,
         endpoint: Endpoint,
     ) -> crate::Result<()> {
+        self.sync_protocols();
+        self.established = Some((peer, endpoint.clone()));
+
         let connection_handle = self.connection.downgrade();
+        let transport = self.transport;
+
+        if self.reserved_peers.read().contains(&peer) {
+            tracing::trace!(target: LOG_TARGET, ?peer, "keep connection to reserved peer open");
+
+            self.keep_alive = None;
+            self.keep_open = Some(connection_handle.clone());
+        }
         let mut futures = self
             .protocols
             .iter()
@@ -352,6 +630,7 @@ impl ProtocolSet {
                             peer,
                             connection: endpoint.connection_id(),
                             endpoint,
+                            transport,
                             sender: connection_handle,
                         })
                         .await
@@ -365,7 +644,19 @@ impl ProtocolSet {
             }
         }
 
-        Ok(())
+        // the handle given to the transport manager must not keep the connection open
+        let mut handle = connection_handle;
+        handle.close();
+
+        self.mgr_tx
+            .send(TransportManagerEvent::ConnectionEstablished {
+                peer,
+                connection: endpoint.connection_id(),
+                handle,
+                protocols: Arc::clone(&self.negotiated),
+            })
+            .await
+            .map_err(From::from)
     }
 
     /// Report to protocols that a connection was closed.
@@ -373,6 +664,7 @@ impl ProtocolSet {
         &mut self,
         peer: PeerId,
         connection_id: ConnectionId,
+        reason: DisconnectReason,
     ) -> crate::Result<()> {
         let mut futures = self
             .protocols
@@ -398,6 +690,24 @@ impl ProtocolSet {
             .send(TransportManagerEvent::ConnectionClosed {
                 peer,
                 connection: connection_id,
+                reason,
+            })
+            .await
+            .map_err(From::from)
+    }
+
+    /// Report path statistics of a QUIC connection.
+    pub(crate) async fn report_quic_path_stats(
+        &mut self,
+        peer: PeerId,
+        connection_id: ConnectionId,
+        stats: PathStats,
+    ) -> crate::Result<()> {
+        self.mgr_tx
+            .send(TransportManagerEvent::QuicPathStats {
+                peer,
+                connection: connection_id,
+                stats,
             })
             .await
             .map_err(From::from)
@@ -408,6 +718,41 @@ impl Stream for ProtocolSet {
     type Item = ProtocolCommand;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.waker.register(cx.waker());
+        self.sync_protocols();
+
+        if let Some(lifetime) = self.lifetime.as_mut() {
+            if lifetime.poll_unpin(cx).is_ready() {
+                tracing::debug!(target: LOG_TARGET, "maximum connection lifetime reached");
+
+                self.lifetime = None;
+                return Poll::Ready(Some(ProtocolCommand::ForceClose));
+            }
+        }
+
+        let this = &mut *self;
+
+        if let Some(keep_alive) = this.keep_alive.as_mut() {
+            while keep_alive.timer.poll_unpin(cx).is_ready() {
+                let deadline = match this.connection.substreams().idle_since() {
+                    Some(idle_since) if idle_since.elapsed() >= keep_alive.timeout => {
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            connection_id = ?this.connection.connection_id(),
+                            "connection has no open substreams, closing",
+                        );
+
+                        this.keep_alive = None;
+                        return Poll::Ready(None);
+                    }
+                    Some(idle_since) => idle_since + keep_alive.timeout,
+                    None => Instant::now() + keep_alive.timeout,
+                };
+
+                keep_alive.timer.as_mut().reset(deadline);
+            }
+        }
+
         self.rx.poll_recv(cx)
     }
 }
@@ -418,6 +763,98 @@ mod tests {
     use crate::mock::substream::MockSubstream;
     use std::collections::HashSet;
 
+    /// Create protocol set with one protocol whose channel has room for one event.
+    fn slow_protocol_set(
+        policy: SlowProtocolPolicy,
+    ) -> (ProtocolSet, Receiver<InnerTransportEvent>) {
+        let (tx, _rx) = channel(64);
+        let (tx1, rx1) = channel(1);
+
+        let mut protocol_set = ProtocolSet::new(
+            ConnectionId::from(0usize),
+            SupportedTransport::Tcp,
+            tx,
+            Default::default(),
+            HashMap::from_iter([(
+                ProtocolName::from("/notif/1"),
+                ProtocolContext {
+                    tx: tx1,
+                    codec: ProtocolCodec::Identity(32),
+                    stream_priority: 0,
+                    fallback_names: Vec::new(),
+                },
+            )]),
+            64,
+        );
+        protocol_set.set_slow_protocol_policy(policy);
+
+        (protocol_set, rx1)
+    }
+
+    fn mock_substream(substream_id: usize) -> Substream {
+        Substream::new_mock(
+            PeerId::random(),
+            SubstreamId::from(substream_id),
+            Box::new(MockSubstream::new()),
+        )
+    }
+
+    #[tokio::test]
+    async fn slow_protocol_parks_connection() {
+        let (mut protocol_set, mut rx) = slow_protocol_set(SlowProtocolPolicy::Park);
+        let peer = PeerId::random();
+        let protocol = ProtocolName::from("/notif/1");
+
+        protocol_set
+            .report_substream_open(
+                peer,
+                protocol.clone(),
+                Direction::Inbound,
+                mock_substream(0),
+            )
+            .await
+            .unwrap();
+
+        // the channel is full so the connection waits until the protocol has received the event
+        let mut future = Box::pin(protocol_set.report_substream_open(
+            peer,
+            protocol,
+            Direction::Inbound,
+            mock_substream(1),
+        ));
+        assert!(futures::poll!(&mut future).is_pending());
+
+        match rx.recv().await {
+            Some(InnerTransportEvent::SubstreamOpened { connection, .. }) =>
+                assert_eq!(connection, ConnectionId::from(0usize)),
+            event => panic!("invalid event: {event:?}"),
+        }
+        future.await.unwrap();
+        assert!(rx.recv().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn slow_protocol_substream_dropped() {
+        let (mut protocol_set, mut rx) = slow_protocol_set(SlowProtocolPolicy::Drop);
+        let peer = PeerId::random();
+        let protocol = ProtocolName::from("/notif/1");
+
+        for substream_id in 0..2 {
+            protocol_set
+                .report_substream_open(
+                    peer,
+                    protocol.clone(),
+                    Direction::Inbound,
+                    mock_substream(substream_id),
+                )
+                .await
+                .unwrap();
+        }
+
+        assert!(rx.try_recv().is_ok());
+        assert!(rx.try_recv().is_err());
+    }
+
     #[tokio::test]
     async fn fallback_is_provided() {
         let (tx, _rx) = channel(64);
@@ -425,6 +862,7 @@ mod tests {
 
         let mut protocol_set = ProtocolSet::new(
             ConnectionId::from(0usize),
+            SupportedTransport::Tcp,
             tx,
             Default::default(),
             HashMap::from_iter([(
@@ -432,12 +870,14 @@ mod tests {
                 ProtocolContext {
                     tx: tx1,
                     codec: ProtocolCodec::Identity(32),
+                    stream_priority: 0,
                     fallback_names: vec![
                         ProtocolName::from("/notif/1/fallback/1"),
                         ProtocolName::from("/notif/1/fallback/2"),
                     ],
                 },
             )]),
+            64,
         );
 
         let expected_protocols = HashSet::from([
@@ -465,6 +905,38 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn stream_priority_resolved_for_fallback() {
+        let (tx, _rx) = channel(64);
+        let (tx1, _rx1) = channel(64);
+
+        let protocol_set = ProtocolSet::new(
+            ConnectionId::from(0usize),
+            SupportedTransport::Tcp,
+            tx,
+            Default::default(),
+            HashMap::from_iter([(
+                ProtocolName::from("/notif/1"),
+                ProtocolContext {
+                    tx: tx1,
+                    codec: ProtocolCodec::Identity(32),
+                    stream_priority: 5,
+                    fallback_names: vec![ProtocolName::from("/notif/1/fallback/1")],
+                },
+            )]),
+            64,
+        );
+
+        assert_eq!(
+            protocol_set.stream_priority(&ProtocolName::from("/notif/1")),
+            5
+        );
+        assert_eq!(
+            protocol_set.stream_priority(&ProtocolName::from("/notif/1/fallback/1")),
+            5
+        );
+    }
+
     #[tokio::test]
     async fn main_protocol_reported_if_main_protocol_negotiated() {
         let (tx, _rx) = channel(64);
@@ -472,6 +944,7 @@ mod tests {
 
         let mut protocol_set = ProtocolSet::new(
             ConnectionId::from(0usize),
+            SupportedTransport::Tcp,
             tx,
             Default::default(),
             HashMap::from_iter([(
@@ -479,12 +952,14 @@ mod tests {
                 ProtocolContext {
                     tx: tx1,
                     codec: ProtocolCodec::Identity(32),
+                    stream_priority: 0,
                     fallback_names: vec![
                         ProtocolName::from("/notif/1/fallback/1"),
                         ProtocolName::from("/notif/1/fallback/2"),
                     ],
                 },
             )]),
+            64,
         );
 
         protocol_set
@@ -519,6 +994,7 @@ mod tests {
 
         let mut protocol_set = ProtocolSet::new(
             ConnectionId::from(0usize),
+            SupportedTransport::Tcp,
             tx,
             Default::default(),
             HashMap::from_iter([(
@@ -526,12 +1002,14 @@ mod tests {
                 ProtocolContext {
                     tx: tx1,
                     codec: ProtocolCodec::Identity(32),
+                    stream_priority: 0,
                     fallback_names: vec![
                         ProtocolName::from("/notif/1/fallback/1"),
                         ProtocolName::from("/notif/1/fallback/2"),
                     ],
                 },
             )]),
+            64,
         );
 
         protocol_set
@@ -558,4 +1036,182 @@ mod tests {
             _ => panic!("invalid event received"),
         }
     }
+
+    #[tokio::test]
+    async fn connection_closed_after_max_lifetime() {
+        let (tx, _rx) = channel(64);
+        let mut protocol_set = ProtocolSet::new(
+            ConnectionId::from(0usize),
+            SupportedTransport::Tcp,
+            tx,
+            Default::default(),
+            HashMap::new(),
+            64,
+        );
+        protocol_set.set_max_lifetime(Duration::from_millis(100));
+
+        match tokio::time::timeout(Duration::from_secs(5), protocol_set.next()).await {
+            Ok(Some(ProtocolCommand::ForceClose)) => {}
+            event => panic!("invalid event received: {event:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn idle_connection_closed_after_keep_alive_timeout() {
+        let (tx, _rx) = channel(64);
+        let mut protocol_set = ProtocolSet::new(
+            ConnectionId::from(0usize),
+            SupportedTransport::Tcp,
+            tx,
+            Default::default(),
+            HashMap::new(),
+            64,
+        );
+        protocol_set.set_keep_alive_timeout(Duration::from_millis(200));
+
+        let permit = protocol_set.try_get_permit().unwrap();
+        let _ = protocol_set.connection.downgrade();
+
+        // connection is kept open while a substream is open
+        assert!(
+            tokio::time::timeout(Duration::from_millis(500), protocol_set.next())
+                .await
+                .is_err()
+        );
+
+        // and closed once it has been idle for the keep-alive timeout
+        drop(permit);
+        let now = Instant::now();
+
+        match tokio::time::timeout(Duration::from_secs(5), protocol_set.next()).await {
+            Ok(None) => assert!(now.elapsed() >= Duration::from_millis(200)),
+            event => panic!("invalid event received: {event:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn reserved_peer_connection_kept_open() {
+        let (tx, _rx) = channel(64);
+        let peer = PeerId::random();
+        let mut protocol_set = ProtocolSet::new(
+            ConnectionId::from(0usize),
+            SupportedTransport::Tcp,
+            tx,
+            Default::default(),
+            HashMap::new(),
+            64,
+        );
+        protocol_set.set_keep_alive_timeout(Duration::from_millis(200));
+        protocol_set.set_reserved_peers(Arc::new(RwLock::new(HashSet::from_iter([peer]))));
+
+        protocol_set
+            .report_connection_established(
+                peer,
+                Endpoint::dialer(Multiaddr::empty(), ConnectionId::from(0usize)),
+            )
+            .await
+            .unwrap();
+
+        // no protocol has a substream open but the connection is not closed
+        assert!(
+            tokio::time::timeout(Duration::from_millis(500), protocol_set.next())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn installed_protocol_added_to_open_connection() {
+        let (tx, _rx) = channel(64);
+        let registry = ProtocolRegistry::default();
+        let mut protocol_set = ProtocolSet::new(
+            ConnectionId::from(0usize),
+            SupportedTransport::Tcp,
+            tx,
+            Default::default(),
+            HashMap::new(),
+            64,
+        );
+        protocol_set.set_protocol_registry(registry.clone(), registry.version());
+        let _permit = protocol_set.try_get_permit().unwrap();
+
+        let peer = PeerId::random();
+        protocol_set
+            .report_connection_established(
+                peer,
+                Endpoint::listener(Multiaddr::empty(), ConnectionId::from(0usize)),
+            )
+            .await
+            .unwrap();
+        assert!(protocol_set.protocols().is_empty());
+
+        let (tx1, mut rx1) = channel(64);
+        registry.update(HashMap::from_iter([(
+            ProtocolName::from("/notif/1"),
+            ProtocolContext {
+                tx: tx1,
+                codec: ProtocolCodec::Identity(32),
+                stream_priority: 0,
+                fallback_names: vec![ProtocolName::from("/notif/1/fallback/1")],
+            },
+        )]));
+
+        // connection is woken up and reports itself to the installed protocol
+        futures::future::poll_fn(|cx| match protocol_set.poll_next_unpin(cx) {
+            Poll::Ready(event) => panic!("didn't expect event: {event:?}"),
+            Poll::Pending => Poll::Ready(()),
+        })
+        .await;
+
+        match rx1.try_recv() {
+            Ok(InnerTransportEvent::ConnectionEstablished {
+                peer: connected_peer,
+                ..
+            }) => assert_eq!(connected_peer, peer),
+            event => panic!("invalid event received: {event:?}"),
+        }
+        assert_eq!(
+            protocol_set.protocols().into_iter().collect::<HashSet<_>>(),
+            HashSet::from([
+                ProtocolName::from("/notif/1"),
+                ProtocolName::from("/notif/1/fallback/1"),
+            ]),
+        );
+    }
+
+    #[tokio::test]
+    async fn uninstalled_protocol_removed_from_connection() {
+        let (tx, _rx) = channel(64);
+        let (tx1, mut rx1) = channel(64);
+        let registry = ProtocolRegistry::from(HashMap::from_iter([(
+            ProtocolName::from("/notif/1"),
+            ProtocolContext {
+                tx: tx1,
+                codec: ProtocolCodec::Identity(32),
+                stream_priority: 0,
+                fallback_names: vec![ProtocolName::from("/notif/1/fallback/1")],
+            },
+        )]));
+
+        let (version, protocols) = registry.protocols();
+        let mut protocol_set = ProtocolSet::new(
+            ConnectionId::from(0usize),
+            SupportedTransport::Tcp,
+            tx,
+            Default::default(),
+            protocols,
+            64,
+        );
+        protocol_set.set_protocol_registry(registry.clone(), version);
+        assert_eq!(protocol_set.protocols().len(), 2);
+
+        registry.update(HashMap::new());
+        assert!(protocol_set.protocols().is_empty());
+
+        // the protocol is no longer reachable through the connection
+        assert!(std::matches!(
+            rx1.try_recv(),
+            Err(tokio::sync::mpsc::error::TryRecvError::Disconnected)
+        ));
+    }
 }
@@ -20,9 +20,13 @@
 
 use crate::{
     codec::ProtocolCodec,
-    protocol::request_response::{
-        handle::{InnerRequestResponseEvent, RequestResponseCommand, RequestResponseHandle},
-        REQUEST_TIMEOUT,
+    protocol::{
+        request_response::{
+            handle::{InnerRequestResponseEvent, RequestResponseCommand, RequestResponseHandle},
+            metrics::Metrics,
+            REQUEST_TIMEOUT,
+        },
+        ConnectionPreference,
     },
     types::protocol::ProtocolName,
     DEFAULT_CHANNEL_SIZE,
@@ -49,17 +53,47 @@ pub struct Config {
     /// Codec used by the protocol.
     pub(crate) codec: ProtocolCodec,
 
+    /// Maximum size of a request.
+    pub(crate) max_request_size: usize,
+
+    /// Maximum size of a response.
+    pub(crate) max_response_size: usize,
+
+    /// Are responses sent as a stream of frames.
+    pub(crate) streaming_responses: bool,
+
     /// TX channel for sending events to the user protocol.
     pub(super) event_tx: Sender<InnerRequestResponseEvent>,
 
     /// RX channel for receiving commands from the user protocol.
     pub(crate) command_rx: Receiver<RequestResponseCommand>,
 
+    /// RX channel for receiving high-priority requests from the user protocol.
+    pub(crate) priority_command_rx: Receiver<RequestResponseCommand>,
+
     /// Next ephemeral request ID.
     pub(crate) next_request_id: Arc<AtomicUsize>,
 
+    /// Metrics of the protocol.
+    pub(crate) metrics: Arc<Metrics>,
+
     /// Maximum number of concurrent inbound requests.
     pub(crate) max_concurrent_inbound_request: Option<usize>,
+
+    /// Maximum number of concurrent inbound requests from a single peer.
+    pub(crate) max_concurrent_inbound_requests_per_peer: Option<usize>,
+
+    /// Maximum number of inbound requests waiting for a free slot.
+    pub(crate) inbound_request_queue_size: usize,
+
+    /// Maximum number of concurrent outbound requests to a single peer.
+    pub(crate) max_outbound_requests_per_peer: Option<usize>,
+
+    /// Default priority of the protocol's substreams.
+    pub(crate) stream_priority: i32,
+
+    /// Preference for the connection used to open outbound substreams.
+    pub(crate) connection_preference: ConnectionPreference,
 }
 
 impl Config {
@@ -71,24 +105,14 @@ impl Config {
         timeout: Duration,
         max_concurrent_inbound_request: Option<usize>,
     ) -> (Self, RequestResponseHandle) {
-        let (event_tx, event_rx) = channel(DEFAULT_CHANNEL_SIZE);
-        let (command_tx, command_rx) = channel(DEFAULT_CHANNEL_SIZE);
-        let next_request_id = Default::default();
-        let handle = RequestResponseHandle::new(event_rx, command_tx, Arc::clone(&next_request_id));
-
-        (
-            Self {
-                event_tx,
-                command_rx,
-                protocol_name,
-                fallback_names,
-                next_request_id,
-                timeout,
-                max_concurrent_inbound_request,
-                codec: ProtocolCodec::UnsignedVarint(Some(max_message_size)),
-            },
-            handle,
-        )
+        ConfigBuilder {
+            max_request_size: Some(max_message_size),
+            max_response_size: Some(max_message_size),
+            timeout: Some(timeout),
+            max_concurrent_inbound_request,
+            ..ConfigBuilder::new(protocol_name).with_fallback_names(fallback_names)
+        }
+        .build()
     }
 
     /// Get protocol name.
@@ -105,14 +129,38 @@ pub struct ConfigBuilder {
     /// Fallback names for the main protocol name.
     pub(crate) fallback_names: Vec<ProtocolName>,
 
-    /// Maximum message size.
-    max_message_size: Option<usize>,
+    /// Maximum request size.
+    max_request_size: Option<usize>,
+
+    /// Maximum response size.
+    max_response_size: Option<usize>,
+
+    /// Are responses sent as a stream of frames.
+    streaming_responses: bool,
 
     /// Timeout for outbound requests.
     timeout: Option<Duration>,
 
     /// Maximum number of concurrent inbound requests.
     max_concurrent_inbound_request: Option<usize>,
+
+    /// Maximum number of concurrent inbound requests from a single peer.
+    max_concurrent_inbound_requests_per_peer: Option<usize>,
+
+    /// Maximum number of inbound requests waiting for a free slot.
+    inbound_request_queue_size: usize,
+
+    /// Maximum number of concurrent outbound requests to a single peer.
+    max_outbound_requests_per_peer: Option<usize>,
+
+    /// Size of the channels between the protocol and its handle.
+    channel_size: usize,
+
+    /// Default priority of the protocol's substreams.
+    stream_priority: i32,
+
+    /// Preference for the connection used to open outbound substreams.
+    connection_preference: ConnectionPreference,
 }
 
 impl ConfigBuilder {
@@ -121,15 +169,45 @@ impl ConfigBuilder {
         Self {
             protocol_name,
             fallback_names: Vec::new(),
-            max_message_size: None,
+            max_request_size: None,
+            max_response_size: None,
+            streaming_responses: false,
             timeout: Some(REQUEST_TIMEOUT),
             max_concurrent_inbound_request: None,
+            max_concurrent_inbound_requests_per_peer: None,
+            inbound_request_queue_size: 0,
+            max_outbound_requests_per_peer: None,
+            channel_size: DEFAULT_CHANNEL_SIZE,
+            stream_priority: 0,
+            connection_preference: ConnectionPreference::Any,
         }
     }
 
     /// Set maximum message size.
+    ///
+    /// The limit applies to both requests and responses. Use
+    /// [`ConfigBuilder::with_max_request_size()`] and [`ConfigBuilder::with_max_response_size()`]
+    /// to configure the directions independently.
     pub fn with_max_size(mut self, max_message_size: usize) -> Self {
-        self.max_message_size = Some(max_message_size);
+        self.max_request_size = Some(max_message_size);
+        self.max_response_size = Some(max_message_size);
+        self
+    }
+
+    /// Set maximum request size.
+    ///
+    /// Inbound requests larger than this are rejected and outbound requests larger than this fail
+    /// with [`RequestResponseError::TooLargePayload`](super::RequestResponseError::TooLargePayload).
+    pub fn with_max_request_size(mut self, max_request_size: usize) -> Self {
+        self.max_request_size = Some(max_request_size);
+        self
+    }
+
+    /// Set maximum response size.
+    ///
+    /// Responses larger than this are neither sent nor accepted from remote peers.
+    pub fn with_max_response_size(mut self, max_response_size: usize) -> Self {
+        self.max_response_size = Some(max_response_size);
         self
     }
 
@@ -140,16 +218,40 @@ impl ConfigBuilder {
     }
 
     /// Set timeout for outbound requests.
+    ///
+    /// The timeout can be overridden for individual requests with
+    /// [`RequestResponseHandle::send_request_with_timeout()`](super::RequestResponseHandle::send_request_with_timeout).
     pub fn with_timeout(mut self, timeout: Duration) -> Self {
         self.timeout = Some(timeout);
         self
     }
 
+    /// Send responses as a stream of frames.
+    ///
+    /// Instead of a single frame, the response is sent as a sequence of frames followed by an
+    /// empty frame marking the end of the response. This allows sending and receiving large
+    /// responses without buffering them fully in memory, see
+    /// [`RequestResponseHandle::send_response_stream()`](super::RequestResponseHandle::send_response_stream)
+    /// and
+    /// [`RequestResponseHandle::send_request_with_response_stream()`](super::RequestResponseHandle::send_request_with_response_stream).
+    ///
+    /// The maximum response size then applies to each frame. Responses received through
+    /// [`RequestResponseHandle::send_request()`](super::RequestResponseHandle::send_request) are
+    /// still buffered and must not exceed the maximum response size in total.
+    ///
+    /// Both peers must agree on the response format so it's advisable to use a dedicated protocol
+    /// name for streamed responses.
+    pub fn with_streaming_responses(mut self) -> Self {
+        self.streaming_responses = true;
+        self
+    }
+
     /// Specify the maximum number of concurrent inbound requests. By default the number of inbound
     /// requests is not limited.
     ///
     /// If a new request is received while the number of inbound requests is already at a maximum,
-    /// the request is dropped.
+    /// the request is queued if there is space in the inbound request queue (see
+    /// [`ConfigBuilder::with_inbound_request_queue_size()`]) and rejected otherwise.
     pub fn with_max_concurrent_inbound_requests(
         mut self,
         max_concurrent_inbound_requests: usize,
@@ -158,14 +260,117 @@ impl ConfigBuilder {
         self
     }
 
+    /// Specify the maximum number of concurrent inbound requests from a single peer. By default
+    /// the number of inbound requests from a peer is only limited by
+    /// [`ConfigBuilder::with_max_concurrent_inbound_requests()`].
+    ///
+    /// Excess requests are handled the same way as requests exceeding the protocol-wide limit.
+    pub fn with_max_concurrent_inbound_requests_per_peer(
+        mut self,
+        max_concurrent_inbound_requests: usize,
+    ) -> Self {
+        self.max_concurrent_inbound_requests_per_peer = Some(max_concurrent_inbound_requests);
+        self
+    }
+
+    /// Specify how many inbound requests can wait for a free slot when the protocol is already
+    /// processing the maximum number of concurrent inbound requests.
+    ///
+    /// Queued requests are processed in the order they were received once earlier requests
+    /// have been answered. Requests received while the queue is full are rejected by closing
+    /// the substream, which the remote peer observes as a rejected request.
+    ///
+    /// By default the queue size is `0`, meaning excess requests are rejected immediately.
+    pub fn with_inbound_request_queue_size(mut self, size: usize) -> Self {
+        self.inbound_request_queue_size = size;
+        self
+    }
+
+    /// Specify the maximum number of concurrent outbound requests to a single peer. By default
+    /// the number of outbound requests is not limited.
+    ///
+    /// Requests sent while the peer already has the maximum number of requests in flight are
+    /// queued and sent in order as earlier requests finish. Queued requests are still subject to
+    /// the request timeout and can be canceled.
+    pub fn with_max_outbound_requests_per_peer(mut self, max_requests: usize) -> Self {
+        self.max_outbound_requests_per_peer = Some(max_requests);
+        self
+    }
+
+    /// Configure size of the channels used to send events and commands between the protocol and
+    /// its [`RequestResponseHandle`].
+    ///
+    /// Default value is `4096`.
+    pub fn with_channel_size(mut self, size: usize) -> Self {
+        self.channel_size = size;
+        self
+    }
+
+    /// Set the default priority of the protocol's substreams.
+    ///
+    /// Data of substreams with higher priority is sent before data of lower-priority substreams
+    /// sharing the same connection. Only QUIC supports substream priorities.
+    ///
+    /// By default the priority is `0`.
+    pub fn with_stream_priority(mut self, priority: i32) -> Self {
+        self.stream_priority = priority;
+        self
+    }
+
+    /// Set the preference for the connection used to open outbound substreams when more than
+    /// one connection is open to the peer.
+    ///
+    /// By default any connection is used, preferring the one that was established first.
+    pub fn with_connection_preference(mut self, preference: ConnectionPreference) -> Self {
+        self.connection_preference = preference;
+        self
+    }
+
     /// Build [`Config`].
     pub fn build(mut self) -> (Config, RequestResponseHandle) {
-        Config::new(
-            self.protocol_name,
-            self.fallback_names,
-            self.max_message_size.take().expect("maximum message size to be set"),
-            self.timeout.take().expect("timeout to exist"),
-            self.max_concurrent_inbound_request,
+        let max_request_size =
+            self.max_request_size.take().expect("maximum request size to be set");
+        let max_response_size =
+            self.max_response_size.take().expect("maximum response size to be set");
+        let (event_tx, event_rx) = channel(self.channel_size);
+        let (command_tx, command_rx) = channel(self.channel_size);
+        let (priority_command_tx, priority_command_rx) = channel(self.channel_size);
+        let next_request_id = Default::default();
+        let metrics = Arc::new(Metrics::new(self.protocol_name.clone()));
+        let handle = RequestResponseHandle::new(
+            event_rx,
+            command_tx,
+            priority_command_tx,
+            Arc::clone(&next_request_id),
+            Arc::clone(&metrics),
+        );
+
+        (
+            Config {
+                event_tx,
+                command_rx,
+                priority_command_rx,
+                protocol_name: self.protocol_name,
+                fallback_names: self.fallback_names,
+                next_request_id,
+                metrics,
+                timeout: self.timeout.take().expect("timeout to exist"),
+                max_concurrent_inbound_request: self.max_concurrent_inbound_request,
+                max_concurrent_inbound_requests_per_peer: self
+                    .max_concurrent_inbound_requests_per_peer,
+                inbound_request_queue_size: self.inbound_request_queue_size,
+                max_outbound_requests_per_peer: self.max_outbound_requests_per_peer,
+                codec: ProtocolCodec::UnsignedVarint(Some(std::cmp::max(
+                    max_request_size,
+                    max_response_size,
+                ))),
+                max_request_size,
+                max_response_size,
+                streaming_responses: self.streaming_responses,
+                stream_priority: self.stream_priority,
+                connection_preference: self.connection_preference,
+            },
+            handle,
         )
     }
 }
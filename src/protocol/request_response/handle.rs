@@ -19,31 +19,113 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
+    error::ErrorKind,
+    protocol::request_response::metrics::{Metrics, RequestResponseMetrics},
     types::{protocol::ProtocolName, RequestId},
     Error, PeerId,
 };
 
-use futures::channel;
+use bytes::Bytes;
+use futures::{channel, stream::BoxStream, Stream};
+use multiaddr::Multiaddr;
 use tokio::sync::{
     mpsc::{Receiver, Sender},
     oneshot,
 };
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     pin::Pin,
     sync::{
         atomic::{AtomicUsize, Ordering},
         Arc,
     },
     task::{Context, Poll},
+    time::Duration,
 };
 
 /// Logging target for the file.
 const LOG_TARGET: &str = "litep2p::request-response::handle";
 
+/// Number of response frames buffered for a [`ResponseStream`] before reading from the substream
+/// is paused.
+const RESPONSE_STREAM_CHANNEL_SIZE: usize = 64;
+
+/// TX channel for the frames of a streamed response.
+///
+/// `None` is sent once the end marker of the response has been received.
+pub(super) type ResponseStreamSender = Sender<Option<Bytes>>;
+
+/// Response to an inbound request.
+pub(super) enum Response {
+    /// Response is sent as one frame.
+    Buffered(Bytes),
+
+    /// Response is sent as a stream of frames.
+    Streamed(BoxStream<'static, Bytes>),
+}
+
+/// Response received as a stream of frames.
+///
+/// Returned by [`RequestResponseHandle::send_request_with_response_stream()`]. The stream ends
+/// when the complete response has been received or when the request fails, in which case the
+/// failure is reported as [`RequestResponseEvent::RequestFailed`] through the handle.
+/// [`ResponseStream::is_complete()`] can be used to distinguish between the two.
+///
+/// Frames are read from the substream only as fast as the stream is polled. Dropping the stream
+/// before it has ended cancels the request and the request fails with
+/// [`RequestResponseError::Canceled`].
+#[derive(Debug)]
+pub struct ResponseStream {
+    /// RX channel for receiving response frames.
+    rx: Receiver<Option<Bytes>>,
+
+    /// Has the end marker of the response been received.
+    complete: bool,
+}
+
+impl ResponseStream {
+    /// Create new [`ResponseStream`].
+    fn new() -> (Self, ResponseStreamSender) {
+        let (tx, rx) = tokio::sync::mpsc::channel(RESPONSE_STREAM_CHANNEL_SIZE);
+
+        (
+            Self {
+                rx,
+                complete: false,
+            },
+            tx,
+        )
+    }
+
+    /// Returns `true` if the whole response was received.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+}
+
+impl Stream for ResponseStream {
+    type Item = Bytes;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.complete {
+            return Poll::Ready(None);
+        }
+
+        match futures::ready!(self.rx.poll_recv(cx)) {
+            Some(Some(frame)) => Poll::Ready(Some(frame)),
+            Some(None) => {
+                self.complete = true;
+                Poll::Ready(None)
+            }
+            None => Poll::Ready(None),
+        }
+    }
+}
+
 /// Request-response error.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum RequestResponseError {
     /// Request was rejected.
     Rejected,
@@ -57,6 +139,9 @@ pub enum RequestResponseError {
     /// Litep2p isn't connected to the peer.
     NotConnected,
 
+    /// Failed to dial the peer.
+    DialFailure,
+
     /// Too large payload.
     TooLargePayload,
 
@@ -64,6 +149,21 @@ pub enum RequestResponseError {
     UnsupportedProtocol,
 }
 
+impl RequestResponseError {
+    /// Get the [`ErrorKind`] of the error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            RequestResponseError::Rejected => ErrorKind::Refused,
+            RequestResponseError::Canceled => ErrorKind::Other,
+            RequestResponseError::Timeout => ErrorKind::Timeout,
+            RequestResponseError::NotConnected => ErrorKind::Unreachable,
+            RequestResponseError::DialFailure => ErrorKind::Unreachable,
+            RequestResponseError::TooLargePayload => ErrorKind::InvalidInput,
+            RequestResponseError::UnsupportedProtocol => ErrorKind::Unsupported,
+        }
+    }
+}
+
 /// Request-response events.
 pub(super) enum InnerRequestResponseEvent {
     /// Request received from remote
@@ -78,10 +178,10 @@ pub(super) enum InnerRequestResponseEvent {
         request_id: RequestId,
 
         /// Received request.
-        request: Vec<u8>,
+        request: Bytes,
 
         /// `oneshot::Sender` for response.
-        response_tx: oneshot::Sender<(Vec<u8>, Option<channel::oneshot::Sender<()>>)>,
+        response_tx: oneshot::Sender<(Response, Option<channel::oneshot::Sender<()>>)>,
     },
 
     /// Response received.
@@ -96,7 +196,22 @@ pub(super) enum InnerRequestResponseEvent {
         request_id: RequestId,
 
         /// Received request.
-        response: Vec<u8>,
+        response: Bytes,
+    },
+
+    /// Request sent to multiple peers has finished.
+    FanOutFinished {
+        /// Request ID.
+        request_id: RequestId,
+
+        /// Responses and failures of the individual peers.
+        responses: Vec<(PeerId, Result<Bytes, RequestResponseError>)>,
+    },
+
+    /// Streamed response was received in full.
+    ResponseStreamFinished {
+        /// Request ID.
+        request_id: RequestId,
     },
 
     /// Request failed.
@@ -135,6 +250,13 @@ impl From<InnerRequestResponseEvent> for RequestResponseEvent {
                 request_id,
                 error,
             },
+            InnerRequestResponseEvent::FanOutFinished {
+                request_id,
+                responses,
+            } => RequestResponseEvent::FanOutFinished {
+                request_id,
+                responses,
+            },
             _ => panic!("unhandled event"),
         }
     }
@@ -159,7 +281,7 @@ pub enum RequestResponseEvent {
         request_id: RequestId,
 
         /// Received request.
-        request: Vec<u8>,
+        request: Bytes,
     },
 
     /// Response received.
@@ -174,7 +296,7 @@ pub enum RequestResponseEvent {
         fallback: Option<ProtocolName>,
 
         /// Received request.
-        response: Vec<u8>,
+        response: Bytes,
     },
 
     /// Request failed.
@@ -188,17 +310,62 @@ pub enum RequestResponseEvent {
         /// Request-response error.
         error: RequestResponseError,
     },
+
+    /// Request sent with [`RequestResponseHandle::send_request_to_peers()`] has finished.
+    FanOutFinished {
+        /// Request ID.
+        request_id: RequestId,
+
+        /// Responses and failures of the individual peers, in the order they were received.
+        ///
+        /// If the request was sent using [`FanOutMode::FirstSuccess`], the list ends with the
+        /// successful response, if any, followed by [`RequestResponseError::Canceled`] for each
+        /// peer whose request was still pending.
+        responses: Vec<(PeerId, Result<Bytes, RequestResponseError>)>,
+    },
+}
+
+/// Priority of an outbound request.
+///
+/// Requests with a higher priority are dispatched before requests with a lower priority when they
+/// have to wait, e.g., for a dial to finish or for earlier requests to the same peer to finish.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RequestPriority {
+    /// Low priority, e.g., for bulk requests.
+    Low,
+
+    /// Normal priority.
+    #[default]
+    Normal,
+
+    /// High priority.
+    ///
+    /// High-priority requests are also delivered to the request-response protocol over a separate
+    /// channel which is processed before the channel used for other commands.
+    High,
+}
+
+/// Completion behavior of a request sent to multiple peers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FanOutMode {
+    /// Finish as soon as one of the peers responds and cancel the requests to other peers.
+    FirstSuccess,
+
+    /// Finish once every peer has responded or its request has failed.
+    All,
 }
 
 /// Dial behavior when sending requests.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum DialOptions {
     /// If the peer is not currently connected, attempt to dial them before sending a request.
     ///
     /// If the dial succeeds, the request is sent to the peer once the peer has been registered
-    /// to the protocol.
+    /// to the protocol. Requests sent while the peer is being dialed are queued and sent once
+    /// the connection has been established.
     ///
-    /// If the dial fails, [`RequestResponseError::Rejected`] is returned.
+    /// If the dial fails, [`RequestResponseError::DialFailure`] is returned for each queued
+    /// request.
     Dial,
 
     /// If the peer is not connected, immediately reject the request and return
@@ -223,10 +390,37 @@ pub(crate) enum RequestResponseCommand {
         request_id: RequestId,
 
         /// Request.
-        request: Vec<u8>,
+        request: Bytes,
+
+        /// Dial options, see [`DialOptions`] for more details.
+        dial_options: DialOptions,
+
+        /// Timeout for the request, overriding the timeout of the protocol if specified.
+        timeout: Option<Duration>,
+
+        /// TX channel for the response frames if the response is consumed as a stream.
+        response_stream: Option<ResponseStreamSender>,
+
+        /// Priority of the request.
+        priority: RequestPriority,
+    },
+
+    /// Send request to multiple peers.
+    SendFanOutRequest {
+        /// Request ID reported to the user.
+        request_id: RequestId,
+
+        /// Peers and the request IDs of the individual requests sent to them.
+        peers: Vec<(PeerId, RequestId)>,
+
+        /// Request.
+        request: Bytes,
 
         /// Dial options, see [`DialOptions`] for more details.
         dial_options: DialOptions,
+
+        /// Completion behavior.
+        mode: FanOutMode,
     },
 
     SendRequestWithFallback {
@@ -237,10 +431,10 @@ pub(crate) enum RequestResponseCommand {
         request_id: RequestId,
 
         /// Request that is sent over the main protocol, if negotiated.
-        request: Vec<u8>,
+        request: Bytes,
 
-        /// Request that is sent over the fallback protocol, if negotiated.
-        fallback: (ProtocolName, Vec<u8>),
+        /// Requests that are sent over the fallback protocols, if negotiated.
+        fallbacks: Vec<(ProtocolName, Bytes)>,
 
         /// Dial options, see [`DialOptions`] for more details.
         dial_options: DialOptions,
@@ -251,6 +445,30 @@ pub(crate) enum RequestResponseCommand {
         /// Request ID.
         request_id: RequestId,
     },
+
+    /// Close all connections to peer.
+    Disconnect {
+        /// Peer ID.
+        peer: PeerId,
+    },
+
+    /// Ban peer.
+    BanPeer {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// Duration of the ban.
+        duration: Duration,
+    },
+
+    /// Add known addresses for peer.
+    AddKnownAddress {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// Addresses of the peer.
+        addresses: Vec<Multiaddr>,
+    },
 }
 
 /// Handle given to the user protocol which allows it to interact with the request-response
@@ -262,12 +480,27 @@ pub struct RequestResponseHandle {
     /// RX channel for receiving events from the request-response protocol.
     command_tx: Sender<RequestResponseCommand>,
 
+    /// TX channel for sending high-priority requests to the request-response protocol.
+    priority_command_tx: Sender<RequestResponseCommand>,
+
     /// Pending responses.
     pending_responses:
-        HashMap<RequestId, oneshot::Sender<(Vec<u8>, Option<channel::oneshot::Sender<()>>)>>,
+        HashMap<RequestId, oneshot::Sender<(Response, Option<channel::oneshot::Sender<()>>)>>,
 
     /// Next ephemeral request ID.
     next_request_id: Arc<AtomicUsize>,
+
+    /// Outbound requests for which no response or failure has been reported yet and the peers
+    /// they were sent to.
+    ///
+    /// Requests sent to multiple peers aren't associated with a peer.
+    pending_requests: HashMap<RequestId, Option<PeerId>>,
+
+    /// Canceled outbound requests whose events are discarded.
+    canceled_requests: HashSet<RequestId>,
+
+    /// Metrics of the protocol.
+    metrics: Arc<Metrics>,
 }
 
 impl RequestResponseHandle {
@@ -275,13 +508,19 @@ impl RequestResponseHandle {
     pub(super) fn new(
         event_rx: Receiver<InnerRequestResponseEvent>,
         command_tx: Sender<RequestResponseCommand>,
+        priority_command_tx: Sender<RequestResponseCommand>,
         next_request_id: Arc<AtomicUsize>,
+        metrics: Arc<Metrics>,
     ) -> Self {
         Self {
             event_rx,
             command_tx,
+            priority_command_tx,
             next_request_id,
             pending_responses: HashMap::new(),
+            pending_requests: HashMap::new(),
+            canceled_requests: HashSet::new(),
+            metrics,
         }
     }
 
@@ -304,21 +543,100 @@ impl RequestResponseHandle {
     /// Cancel an outbound request.
     ///
     /// Allows canceling an in-flight request if the local node is not interested in the answer
-    /// anymore. If the request was canceled, no event is reported to the user as the cancelation
+    /// anymore. The request can be canceled while the peer is being dialed, while the substream
+    /// is being opened or while waiting for the response.
+    ///
+    /// If the request was canceled, no event is reported to the user as the cancelation
     /// always succeeds and it's assumed that the user does the necessary state clean up in their
-    /// end after calling [`RequestResponseHandle::cancel_request()`].
+    /// end after calling [`RequestResponseHandle::cancel_request()`]. Once this function returns,
+    /// no [`RequestResponseEvent::ResponseReceived`] or [`RequestResponseEvent::RequestFailed`] is
+    /// reported for `request_id`, even if the response had already been received.
     pub async fn cancel_request(&mut self, request_id: RequestId) {
+        if self.pending_requests.remove(&request_id).is_none() {
+            tracing::debug!(target: LOG_TARGET, ?request_id, "canceled request doesn't exist");
+            return;
+        }
+
         tracing::trace!(target: LOG_TARGET, ?request_id, "cancel request");
 
+        self.canceled_requests.insert(request_id);
         let _ = self.command_tx.send(RequestResponseCommand::CancelRequest { request_id }).await;
     }
 
+    /// Close all connections to `peer`.
+    ///
+    /// Pending requests to `peer` fail once the connections are closed and every installed
+    /// protocol is notified that the peer was disconnected.
+    pub async fn disconnect(&mut self, peer: PeerId) {
+        tracing::trace!(target: LOG_TARGET, ?peer, "disconnect peer");
+
+        let _ = self.command_tx.send(RequestResponseCommand::Disconnect { peer }).await;
+    }
+
+    /// Ban `peer` for `duration`.
+    ///
+    /// See [`Litep2p::ban_peer()`](crate::Litep2p::ban_peer) for more details.
+    pub async fn ban_peer(&mut self, peer: PeerId, duration: Duration) {
+        tracing::trace!(target: LOG_TARGET, ?peer, ?duration, "ban peer");
+
+        let _ = self.command_tx.send(RequestResponseCommand::BanPeer { peer, duration }).await;
+    }
+
+    /// Add one or more known addresses for `peer`.
+    ///
+    /// See [`Litep2p::add_known_address()`](crate::Litep2p::add_known_address) for more details.
+    pub async fn add_known_address(
+        &self,
+        peer: PeerId,
+        addresses: impl IntoIterator<Item = Multiaddr>,
+    ) {
+        let addresses = addresses.into_iter().collect::<Vec<_>>();
+
+        tracing::trace!(target: LOG_TARGET, ?peer, ?addresses, "add known addresses");
+
+        let _ = self
+            .command_tx
+            .send(RequestResponseCommand::AddKnownAddress { peer, addresses })
+            .await;
+    }
+
     /// Get next request ID.
     fn next_request_id(&self) -> RequestId {
         let request_id = self.next_request_id.fetch_add(1usize, Ordering::Relaxed);
         RequestId::from(request_id)
     }
 
+    /// Outbound request was sent to the request-response protocol.
+    fn on_request_sent(&mut self, peer: Option<PeerId>, request_id: RequestId) -> RequestId {
+        self.pending_requests.insert(request_id, peer);
+        request_id
+    }
+
+    /// Get the number of outbound requests to `peer` which haven't finished yet.
+    ///
+    /// The count includes requests which are waiting for a dial to the peer or for earlier
+    /// requests to finish (see
+    /// [`ConfigBuilder::with_max_outbound_requests_per_peer()`](super::ConfigBuilder::with_max_outbound_requests_per_peer)).
+    /// Requests are counted until their response or failure has been read from the handle or
+    /// until they're canceled. Requests sent with
+    /// [`RequestResponseHandle::send_request_to_peers()`] are not included.
+    pub fn num_pending_requests(&self, peer: &PeerId) -> usize {
+        self.pending_requests
+            .values()
+            .filter(|request_peer| *request_peer == &Some(*peer))
+            .count()
+    }
+
+    /// Get snapshot of the latency, response size and failure metrics of outbound requests.
+    ///
+    /// The metrics separate the time requests spend waiting in the local node from the
+    /// round-trip time to the remote peer, which helps to tell whether slow requests are caused
+    /// by the remote peers or by local backpressure. See [`RequestResponseMetrics`] for more
+    /// details.
+    pub fn metrics(&self) -> RequestResponseMetrics {
+        self.metrics.snapshot()
+    }
+
     /// Send request to remote peer.
     ///
     /// While the returned `RequestId` is guaranteed to be unique for this request-response
@@ -328,21 +646,169 @@ impl RequestResponseHandle {
     pub async fn send_request(
         &mut self,
         peer: PeerId,
-        request: Vec<u8>,
+        request: impl Into<Bytes>,
+        dial_options: DialOptions,
+    ) -> crate::Result<RequestId> {
+        tracing::trace!(target: LOG_TARGET, ?peer, "send request to peer");
+
+        let request_id = self.next_request_id();
+        self.command_tx
+            .send(RequestResponseCommand::SendRequest {
+                peer,
+                request_id,
+                request: request.into(),
+                dial_options,
+                timeout: None,
+                response_stream: None,
+                priority: RequestPriority::Normal,
+            })
+            .await
+            .map(|_| self.on_request_sent(Some(peer), request_id))
+            .map_err(From::from)
+    }
+
+    /// Send request to remote peer with a custom `timeout`.
+    ///
+    /// Behaves like [`RequestResponseHandle::send_request()`] except that the request fails
+    /// with [`RequestResponseError::Timeout`] if no response is received within `timeout`
+    /// instead of the timeout configured for the protocol. The timeout also covers the time
+    /// spent dialing the peer and opening a substream.
+    pub async fn send_request_with_timeout(
+        &mut self,
+        peer: PeerId,
+        request: impl Into<Bytes>,
         dial_options: DialOptions,
+        timeout: Duration,
     ) -> crate::Result<RequestId> {
+        tracing::trace!(target: LOG_TARGET, ?peer, ?timeout, "send request to peer");
+
+        let request_id = self.next_request_id();
+        self.command_tx
+            .send(RequestResponseCommand::SendRequest {
+                peer,
+                request_id,
+                request: request.into(),
+                dial_options,
+                timeout: Some(timeout),
+                response_stream: None,
+                priority: RequestPriority::Normal,
+            })
+            .await
+            .map(|_| self.on_request_sent(Some(peer), request_id))
+            .map_err(From::from)
+    }
+
+    /// Send request to remote peer and receive the response as a stream of frames.
+    ///
+    /// If the protocol was configured to use streaming responses (see
+    /// [`ConfigBuilder::with_streaming_responses()`](super::ConfigBuilder::with_streaming_responses)),
+    /// the frames are returned as they're read from the substream and the response doesn't have
+    /// to be buffered in memory. Otherwise the response is returned as a single frame.
+    ///
+    /// The request timeout covers receiving the whole response.
+    pub async fn send_request_with_response_stream(
+        &mut self,
+        peer: PeerId,
+        request: impl Into<Bytes>,
+        dial_options: DialOptions,
+    ) -> crate::Result<(RequestId, ResponseStream)> {
         tracing::trace!(target: LOG_TARGET, ?peer, "send request to peer");
 
         let request_id = self.next_request_id();
+        let (response_stream, response_tx) = ResponseStream::new();
+
         self.command_tx
             .send(RequestResponseCommand::SendRequest {
                 peer,
                 request_id,
-                request,
+                request: request.into(),
+                dial_options,
+                timeout: None,
+                response_stream: Some(response_tx),
+                priority: RequestPriority::Normal,
+            })
+            .await
+            .map(|_| {
+                (
+                    self.on_request_sent(Some(peer), request_id),
+                    response_stream,
+                )
+            })
+            .map_err(From::from)
+    }
+
+    /// Send request to remote peer with `priority`.
+    ///
+    /// Behaves like [`RequestResponseHandle::send_request()`] except that the request is
+    /// dispatched before queued requests of lower priority. See [`RequestPriority`] for more
+    /// details.
+    pub async fn send_request_with_priority(
+        &mut self,
+        peer: PeerId,
+        request: impl Into<Bytes>,
+        dial_options: DialOptions,
+        priority: RequestPriority,
+    ) -> crate::Result<RequestId> {
+        tracing::trace!(target: LOG_TARGET, ?peer, ?priority, "send request to peer");
+
+        let request_id = self.next_request_id();
+        let command_tx = match priority {
+            RequestPriority::High => &self.priority_command_tx,
+            RequestPriority::Normal | RequestPriority::Low => &self.command_tx,
+        };
+
+        command_tx
+            .send(RequestResponseCommand::SendRequest {
+                peer,
+                request_id,
+                request: request.into(),
+                dial_options,
+                timeout: None,
+                response_stream: None,
+                priority,
+            })
+            .await
+            .map(|_| self.on_request_sent(Some(peer), request_id))
+            .map_err(From::from)
+    }
+
+    /// Send the same request to multiple peers concurrently.
+    ///
+    /// The requests to individual peers behave like requests sent with
+    /// [`RequestResponseHandle::send_request()`] and are subject to the timeout of the protocol.
+    /// Instead of individual events, [`RequestResponseEvent::FanOutFinished`] is reported for the
+    /// returned `RequestId` once the request has finished according to `mode`.
+    ///
+    /// Canceling the returned `RequestId` cancels the requests to all peers.
+    pub async fn send_request_to_peers(
+        &mut self,
+        peers: impl IntoIterator<Item = PeerId>,
+        request: impl Into<Bytes>,
+        dial_options: DialOptions,
+        mode: FanOutMode,
+    ) -> crate::Result<RequestId> {
+        let request_id = self.next_request_id();
+        let peers =
+            peers.into_iter().map(|peer| (peer, self.next_request_id())).collect::<Vec<_>>();
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            ?request_id,
+            num_peers = ?peers.len(),
+            ?mode,
+            "send request to multiple peers",
+        );
+
+        self.command_tx
+            .send(RequestResponseCommand::SendFanOutRequest {
+                request_id,
+                peers,
+                request: request.into(),
                 dial_options,
+                mode,
             })
             .await
-            .map(|_| request_id)
+            .map(|_| self.on_request_sent(None, request_id))
             .map_err(From::from)
     }
 
@@ -356,7 +822,7 @@ impl RequestResponseHandle {
     pub fn try_send_request(
         &mut self,
         peer: PeerId,
-        request: Vec<u8>,
+        request: impl Into<Bytes>,
         dial_options: DialOptions,
     ) -> crate::Result<RequestId> {
         tracing::trace!(target: LOG_TARGET, ?peer, "send request to peer");
@@ -366,75 +832,126 @@ impl RequestResponseHandle {
             .try_send(RequestResponseCommand::SendRequest {
                 peer,
                 request_id,
-                request,
+                request: request.into(),
                 dial_options,
+                timeout: None,
+                response_stream: None,
+                priority: RequestPriority::Normal,
             })
-            .map(|_| request_id)
+            .map(|_| self.on_request_sent(Some(peer), request_id))
             .map_err(|_| Error::ChannelClogged)
     }
 
     /// Send request to remote peer with fallback.
+    ///
+    /// `fallback` is sent instead of `request` if the substream is negotiated using the fallback
+    /// protocol.
     pub async fn send_request_with_fallback(
         &mut self,
         peer: PeerId,
-        request: Vec<u8>,
-        fallback: (ProtocolName, Vec<u8>),
+        request: impl Into<Bytes>,
+        fallback: (ProtocolName, impl Into<Bytes>),
+        dial_options: DialOptions,
+    ) -> crate::Result<RequestId> {
+        self.send_request_with_fallbacks(peer, request, vec![fallback], dial_options)
+            .await
+    }
+
+    /// Attempt to send request to peer with fallback and if the channel is clogged,
+    /// return `Error::ChannelClogged`.
+    pub fn try_send_request_with_fallback(
+        &mut self,
+        peer: PeerId,
+        request: impl Into<Bytes>,
+        fallback: (ProtocolName, impl Into<Bytes>),
+        dial_options: DialOptions,
+    ) -> crate::Result<RequestId> {
+        self.try_send_request_with_fallbacks(peer, request, vec![fallback], dial_options)
+    }
+
+    /// Send request to remote peer with multiple fallbacks.
+    ///
+    /// `fallbacks` contains the requests encoded for older versions of the protocol. If the
+    /// substream is negotiated using one of the fallback names, the request of that fallback is
+    /// sent instead of `request`. If no request was provided for the negotiated fallback, `request`
+    /// is sent.
+    ///
+    /// The fallback names must be among the fallback names the protocol was configured with.
+    pub async fn send_request_with_fallbacks(
+        &mut self,
+        peer: PeerId,
+        request: impl Into<Bytes>,
+        fallbacks: Vec<(ProtocolName, impl Into<Bytes>)>,
         dial_options: DialOptions,
     ) -> crate::Result<RequestId> {
         tracing::trace!(
             target: LOG_TARGET,
             ?peer,
-            fallback = %fallback.0,
+            fallbacks = ?fallbacks.iter().map(|(protocol, _)| protocol).collect::<Vec<_>>(),
             ?dial_options,
             "send request with fallback to peer",
         );
 
         let request_id = self.next_request_id();
+        let fallbacks = fallbacks
+            .into_iter()
+            .map(|(protocol, request)| (protocol, request.into()))
+            .collect();
+
         self.command_tx
             .send(RequestResponseCommand::SendRequestWithFallback {
                 peer,
                 request_id,
-                fallback,
-                request,
+                fallbacks,
+                request: request.into(),
                 dial_options,
             })
             .await
-            .map(|_| request_id)
+            .map(|_| self.on_request_sent(Some(peer), request_id))
             .map_err(From::from)
     }
 
-    /// Attempt to send request to peer with fallback and if the channel is clogged,
+    /// Attempt to send request to peer with multiple fallbacks and if the channel is clogged,
     /// return `Error::ChannelClogged`.
-    pub fn try_send_request_with_fallback(
+    pub fn try_send_request_with_fallbacks(
         &mut self,
         peer: PeerId,
-        request: Vec<u8>,
-        fallback: (ProtocolName, Vec<u8>),
+        request: impl Into<Bytes>,
+        fallbacks: Vec<(ProtocolName, impl Into<Bytes>)>,
         dial_options: DialOptions,
     ) -> crate::Result<RequestId> {
         tracing::trace!(
             target: LOG_TARGET,
             ?peer,
-            fallback = %fallback.0,
+            fallbacks = ?fallbacks.iter().map(|(protocol, _)| protocol).collect::<Vec<_>>(),
             ?dial_options,
             "send request with fallback to peer",
         );
 
         let request_id = self.next_request_id();
+        let fallbacks = fallbacks
+            .into_iter()
+            .map(|(protocol, request)| (protocol, request.into()))
+            .collect();
+
         self.command_tx
             .try_send(RequestResponseCommand::SendRequestWithFallback {
                 peer,
                 request_id,
-                fallback,
-                request,
+                fallbacks,
+                request: request.into(),
                 dial_options,
             })
-            .map(|_| request_id)
+            .map(|_| self.on_request_sent(Some(peer), request_id))
             .map_err(|_| Error::ChannelClogged)
     }
 
     /// Send response to remote peer.
-    pub fn send_response(&mut self, request_id: RequestId, response: Vec<u8>) {
+    ///
+    /// The response is only queued for sending. Use
+    /// [`RequestResponseHandle::send_response_with_feedback()`] to be notified when the response
+    /// has been written to the substream.
+    pub fn send_response(&mut self, request_id: RequestId, response: impl Into<Bytes>) {
         match self.pending_responses.remove(&request_id) {
             None => {
                 tracing::debug!(target: LOG_TARGET, ?request_id, "pending response doens't exist");
@@ -442,7 +959,7 @@ impl RequestResponseHandle {
             Some(response_tx) => {
                 tracing::trace!(target: LOG_TARGET, ?request_id, "send response to peer");
 
-                if let Err(_) = response_tx.send((response, None)) {
+                if let Err(_) = response_tx.send((Response::Buffered(response.into()), None)) {
                     tracing::debug!(target: LOG_TARGET, ?request_id, "substream closed");
                 }
             }
@@ -457,10 +974,15 @@ impl RequestResponseHandle {
     /// to the sender to notify them about it. If the substream has been closed or the substream
     /// failed while sending the response, the feedback channel will be dropped, notifying the
     /// sender that sending the response failed.
+    ///
+    /// The feedback is sent only after the response has been written and flushed to the
+    /// substream. Sending the response fails if the request doesn't exist, the remote peer closed
+    /// the substream, the response is too large or it couldn't be sent within the request timeout
+    /// of the protocol.
     pub fn send_response_with_feedback(
         &mut self,
         request_id: RequestId,
-        response: Vec<u8>,
+        response: impl Into<Bytes>,
         feedback: channel::oneshot::Sender<()>,
     ) {
         match self.pending_responses.remove(&request_id) {
@@ -470,7 +992,38 @@ impl RequestResponseHandle {
             Some(response_tx) => {
                 tracing::trace!(target: LOG_TARGET, ?request_id, "send response to peer");
 
-                if let Err(_) = response_tx.send((response, Some(feedback))) {
+                if let Err(_) =
+                    response_tx.send((Response::Buffered(response.into()), Some(feedback)))
+                {
+                    tracing::debug!(target: LOG_TARGET, ?request_id, "substream closed");
+                }
+            }
+        }
+    }
+
+    /// Send response to remote peer as a stream of frames.
+    ///
+    /// If the protocol was configured to use streaming responses (see
+    /// [`ConfigBuilder::with_streaming_responses()`](super::ConfigBuilder::with_streaming_responses)),
+    /// each frame is sent over the substream as soon as `response` yields it and the end of the
+    /// response is signaled to the remote peer once `response` ends. Empty frames are skipped.
+    /// Otherwise the frames are collected and sent as a single response.
+    ///
+    /// Each frame must fit within the maximum response size of the protocol and the whole
+    /// response must be sent within the request timeout of the protocol.
+    pub fn send_response_stream(
+        &mut self,
+        request_id: RequestId,
+        response: impl Stream<Item = Bytes> + Send + 'static,
+    ) {
+        match self.pending_responses.remove(&request_id) {
+            None => {
+                tracing::debug!(target: LOG_TARGET, ?request_id, "pending response doens't exist");
+            }
+            Some(response_tx) => {
+                tracing::trace!(target: LOG_TARGET, ?request_id, "send response stream to peer");
+
+                if let Err(_) = response_tx.send((Response::Streamed(Box::pin(response)), None)) {
                     tracing::debug!(target: LOG_TARGET, ?request_id, "substream closed");
                 }
             }
@@ -482,26 +1035,48 @@ impl futures::Stream for RequestResponseHandle {
     type Item = RequestResponseEvent;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        match futures::ready!(self.event_rx.poll_recv(cx)) {
-            None => return Poll::Ready(None),
-            Some(event) => match event {
-                InnerRequestResponseEvent::RequestReceived {
-                    peer,
-                    fallback,
-                    request_id,
-                    request,
-                    response_tx,
-                } => {
-                    self.pending_responses.insert(request_id, response_tx);
-                    Poll::Ready(Some(RequestResponseEvent::RequestReceived {
+        loop {
+            match futures::ready!(self.event_rx.poll_recv(cx)) {
+                None => return Poll::Ready(None),
+                Some(event) => match event {
+                    InnerRequestResponseEvent::RequestReceived {
                         peer,
                         fallback,
                         request_id,
                         request,
-                    }))
-                }
-                event => Poll::Ready(Some(event.into())),
-            },
+                        response_tx,
+                    } => {
+                        self.pending_responses.insert(request_id, response_tx);
+                        return Poll::Ready(Some(RequestResponseEvent::RequestReceived {
+                            peer,
+                            fallback,
+                            request_id,
+                            request,
+                        }));
+                    }
+                    InnerRequestResponseEvent::ResponseStreamFinished { request_id } => {
+                        self.pending_requests.remove(&request_id);
+                        self.canceled_requests.remove(&request_id);
+                    }
+                    InnerRequestResponseEvent::ResponseReceived { request_id, .. }
+                    | InnerRequestResponseEvent::RequestFailed { request_id, .. }
+                    | InnerRequestResponseEvent::FanOutFinished { request_id, .. } => {
+                        self.pending_requests.remove(&request_id);
+
+                        // events of canceled requests are not reported to the user
+                        if self.canceled_requests.remove(&request_id) {
+                            tracing::trace!(
+                                target: LOG_TARGET,
+                                ?request_id,
+                                "discard event of canceled request",
+                            );
+                            continue;
+                        }
+
+                        return Poll::Ready(Some(event.into()));
+                    }
+                },
+            }
         }
     }
 }
@@ -0,0 +1,256 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Latency, response size and failure metrics of outbound requests.
+
+use crate::{protocol::request_response::RequestResponseError, types::protocol::ProtocolName};
+
+use parking_lot::Mutex;
+
+use std::{collections::HashMap, time::Duration};
+
+/// Upper bounds of the latency buckets, in seconds.
+const LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 20.0,
+];
+
+/// Upper bounds of the response size buckets, in bytes.
+const SIZE_BUCKETS: &[f64] = &[
+    64.0, 256.0, 1024.0, 4096.0, 16384.0, 65536.0, 262144.0, 1048576.0, 4194304.0, 16777216.0,
+];
+
+/// Histogram with fixed buckets.
+#[derive(Debug)]
+struct Histogram {
+    /// Upper bounds of the buckets.
+    bounds: &'static [f64],
+
+    /// Number of observations in each bucket.
+    ///
+    /// The last element counts the observations above the largest bound.
+    counts: Vec<u64>,
+
+    /// Sum of all observations.
+    sum: f64,
+}
+
+impl Histogram {
+    /// Create new [`Histogram`].
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            counts: vec![0u64; bounds.len() + 1],
+            sum: 0f64,
+        }
+    }
+
+    /// Record `value`.
+    fn observe(&mut self, value: f64) {
+        let index = self
+            .bounds
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(self.bounds.len());
+
+        self.counts[index] += 1;
+        self.sum += value;
+    }
+
+    /// Get snapshot of the histogram.
+    fn snapshot(&self) -> HistogramSnapshot {
+        let mut cumulative = 0u64;
+
+        HistogramSnapshot {
+            buckets: self
+                .bounds
+                .iter()
+                .zip(self.counts.iter())
+                .map(|(bound, count)| {
+                    cumulative += count;
+                    (*bound, cumulative)
+                })
+                .collect(),
+            count: self.counts.iter().sum(),
+            sum: self.sum,
+        }
+    }
+}
+
+/// Snapshot of a histogram.
+///
+/// The layout matches Prometheus histograms so snapshots can be exported to a metrics registry
+/// as-is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramSnapshot {
+    /// Upper bound of each bucket and the number of observations less than or equal to it.
+    pub buckets: Vec<(f64, u64)>,
+
+    /// Total number of observations.
+    pub count: u64,
+
+    /// Sum of all observations.
+    pub sum: f64,
+}
+
+/// Snapshot of the metrics of a request-response protocol.
+///
+/// Only outbound requests are measured. Requests sent to multiple peers are measured as
+/// individual requests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestResponseMetrics {
+    /// Protocol name.
+    pub protocol: ProtocolName,
+
+    /// Time between the protocol receiving a request and sending it to the remote peer, in
+    /// seconds.
+    ///
+    /// Covers dialing the peer, waiting for earlier requests to the peer to finish and opening
+    /// the substream, i.e., the delays caused by the local node.
+    pub queue_time: HistogramSnapshot,
+
+    /// Time between sending a request to the remote peer and receiving the complete response, in
+    /// seconds.
+    pub round_trip_time: HistogramSnapshot,
+
+    /// Sizes of the received responses, in bytes.
+    pub response_size: HistogramSnapshot,
+
+    /// Number of failed requests by failure reason.
+    pub failures: HashMap<RequestResponseError, u64>,
+}
+
+/// Recorded metrics.
+#[derive(Debug)]
+struct Inner {
+    /// Queue times.
+    queue_time: Histogram,
+
+    /// Round-trip times.
+    round_trip_time: Histogram,
+
+    /// Response sizes.
+    response_size: Histogram,
+
+    /// Failure counts.
+    failures: HashMap<RequestResponseError, u64>,
+}
+
+/// Metrics of a request-response protocol, shared by the protocol and its handle.
+#[derive(Debug)]
+pub(crate) struct Metrics {
+    /// Protocol name.
+    protocol: ProtocolName,
+
+    /// Recorded metrics.
+    inner: Mutex<Inner>,
+}
+
+impl Metrics {
+    /// Create new [`Metrics`].
+    pub(crate) fn new(protocol: ProtocolName) -> Self {
+        Self {
+            protocol,
+            inner: Mutex::new(Inner {
+                queue_time: Histogram::new(LATENCY_BUCKETS),
+                round_trip_time: Histogram::new(LATENCY_BUCKETS),
+                response_size: Histogram::new(SIZE_BUCKETS),
+                failures: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Record the time a request waited before it was sent.
+    pub(crate) fn on_request_sent(&self, queue_time: Duration) {
+        self.inner.lock().queue_time.observe(queue_time.as_secs_f64());
+    }
+
+    /// Record a received response.
+    pub(crate) fn on_response_received(&self, round_trip_time: Duration, size: usize) {
+        let mut inner = self.inner.lock();
+
+        inner.round_trip_time.observe(round_trip_time.as_secs_f64());
+        inner.response_size.observe(size as f64);
+    }
+
+    /// Record a failed request.
+    pub(crate) fn on_request_failed(&self, error: &RequestResponseError) {
+        *self.inner.lock().failures.entry(error.clone()).or_default() += 1;
+    }
+
+    /// Get snapshot of the metrics.
+    pub(crate) fn snapshot(&self) -> RequestResponseMetrics {
+        let inner = self.inner.lock();
+
+        RequestResponseMetrics {
+            protocol: self.protocol.clone(),
+            queue_time: inner.queue_time.snapshot(),
+            round_trip_time: inner.round_trip_time.snapshot(),
+            response_size: inner.response_size.snapshot(),
+            failures: inner.failures.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let mut histogram = Histogram::new(&[1.0, 2.0, 3.0]);
+
+        histogram.observe(0.5);
+        histogram.observe(2.0);
+        histogram.observe(2.5);
+        histogram.observe(10.0);
+
+        assert_eq!(
+            histogram.snapshot(),
+            HistogramSnapshot {
+                buckets: vec![(1.0, 1), (2.0, 2), (3.0, 3)],
+                count: 4,
+                sum: 15.0,
+            }
+        );
+    }
+
+    #[test]
+    fn failures_counted_by_reason() {
+        let metrics = Metrics::new(ProtocolName::from("/protocol/1"));
+
+        metrics.on_request_failed(&RequestResponseError::Timeout);
+        metrics.on_request_failed(&RequestResponseError::Timeout);
+        metrics.on_request_failed(&RequestResponseError::Rejected);
+        metrics.on_response_received(Duration::from_millis(20), 1337);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(
+            snapshot.failures.get(&RequestResponseError::Timeout),
+            Some(&2)
+        );
+        assert_eq!(
+            snapshot.failures.get(&RequestResponseError::Rejected),
+            Some(&1)
+        );
+        assert_eq!(snapshot.round_trip_time.count, 1);
+        assert_eq!(snapshot.response_size.sum, 1337.0);
+        assert_eq!(snapshot.queue_time.count, 0);
+    }
+}
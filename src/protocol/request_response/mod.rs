@@ -24,7 +24,12 @@ use crate::{
     error::{Error, NegotiationError},
     multistream_select::NegotiationError::Failed as MultistreamFailed,
     protocol::{
-        request_response::handle::{InnerRequestResponseEvent, RequestResponseCommand},
+        request_response::{
+            handle::{
+                InnerRequestResponseEvent, RequestResponseCommand, Response, ResponseStreamSender,
+            },
+            metrics::Metrics,
+        },
         Direction, TransportEvent, TransportService,
     },
     substream::{Substream, SubstreamSet},
@@ -32,18 +37,19 @@ use crate::{
     PeerId,
 };
 
-use bytes::BytesMut;
-use futures::{channel, future::BoxFuture, stream::FuturesUnordered, StreamExt};
+use bytes::{Bytes, BytesMut};
+use futures::{channel, future::BoxFuture, stream::FuturesUnordered, FutureExt, StreamExt};
 use tokio::{
     sync::{
         mpsc::{Receiver, Sender},
         oneshot,
     },
-    time::sleep,
+    time::{sleep_until, timeout_at, Instant},
 };
 
 use std::{
-    collections::{hash_map::Entry, HashMap, HashSet},
+    cmp::Reverse,
+    collections::{hash_map::Entry, HashMap, HashSet, VecDeque},
     io::ErrorKind,
     sync::{
         atomic::{AtomicUsize, Ordering},
@@ -53,12 +59,19 @@ use std::{
 };
 
 pub use config::{Config, ConfigBuilder};
-pub use handle::{DialOptions, RequestResponseError, RequestResponseEvent, RequestResponseHandle};
+pub use handle::{
+    DialOptions, FanOutMode, RequestPriority, RequestResponseError, RequestResponseEvent,
+    RequestResponseHandle, ResponseStream,
+};
+pub use metrics::{HistogramSnapshot, RequestResponseMetrics};
+pub use typed::{Codec, ProstCodec, TypedRequestResponse, TypedRequestResponseEvent};
 
 mod config;
 mod handle;
+mod metrics;
 #[cfg(test)]
 mod tests;
+mod typed;
 
 // TODO: add ability to specify limit for inbound requests?
 // TODO: convert inbound/outbound substreams to use `oneshot:Sender<()>` for sending/rejecting
@@ -71,11 +84,13 @@ const LOG_TARGET: &str = "litep2p::request-response::protocol";
 const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Pending request.
+///
+/// The response is `None` if it was delivered to the user through a [`ResponseStream`].
 type PendingRequest = (
     PeerId,
     RequestId,
     Option<ProtocolName>,
-    Result<Vec<u8>, RequestResponseError>,
+    Result<Option<Bytes>, RequestResponseError>,
 );
 
 /// Request context.
@@ -87,10 +102,22 @@ struct RequestContext {
     request_id: RequestId,
 
     /// Request.
-    request: Vec<u8>,
+    request: Bytes,
+
+    /// Requests sent over fallback protocols, if one of them is negotiated.
+    fallbacks: Vec<(ProtocolName, Bytes)>,
+
+    /// Deadline for receiving the response.
+    deadline: Instant,
 
-    /// Fallback request.
-    fallback: Option<(ProtocolName, Vec<u8>)>,
+    /// When the request was received from the user protocol.
+    created: Instant,
+
+    /// TX channel for the response frames if the response is consumed as a stream.
+    response_stream: Option<ResponseStreamSender>,
+
+    /// Priority of the request.
+    priority: RequestPriority,
 }
 
 impl RequestContext {
@@ -98,18 +125,35 @@ impl RequestContext {
     fn new(
         peer: PeerId,
         request_id: RequestId,
-        request: Vec<u8>,
-        fallback: Option<(ProtocolName, Vec<u8>)>,
+        request: Bytes,
+        fallbacks: Vec<(ProtocolName, Bytes)>,
+        deadline: Instant,
     ) -> Self {
         Self {
             peer,
             request_id,
             request,
-            fallback,
+            fallbacks,
+            deadline,
+            created: Instant::now(),
+            response_stream: None,
+            priority: RequestPriority::Normal,
         }
     }
 }
 
+/// Context of a request sent to multiple peers.
+struct FanOutContext {
+    /// Completion behavior.
+    mode: FanOutMode,
+
+    /// Requests which haven't finished yet and the peers they were sent to.
+    pending: HashMap<RequestId, PeerId>,
+
+    /// Responses and failures received so far.
+    responses: Vec<(PeerId, Result<Bytes, RequestResponseError>)>,
+}
+
 /// Peer context.
 struct PeerContext {
     /// Active requests.
@@ -117,6 +161,12 @@ struct PeerContext {
 
     /// Active inbound requests and their fallback names.
     active_inbound: HashMap<RequestId, Option<ProtocolName>>,
+
+    /// Number of inbound requests which have been read and are waiting for a response to be sent.
+    pending_responses: usize,
+
+    /// Outbound requests waiting for the number of active requests to go down.
+    queued: VecDeque<RequestContext>,
 }
 
 impl PeerContext {
@@ -125,6 +175,8 @@ impl PeerContext {
         Self {
             active: HashSet::new(),
             active_inbound: HashMap::new(),
+            pending_responses: 0usize,
+            queued: VecDeque::new(),
         }
     }
 }
@@ -151,7 +203,9 @@ pub(crate) struct RequestResponseProtocol {
     ///
     /// If the substream is rejected by the local node, the `oneshot::Sender` is dropped which
     /// notifies the future that the request should be rejected by closing the substream.
-    pending_outbound_responses: FuturesUnordered<BoxFuture<'static, ()>>,
+    ///
+    /// The future returns the ID of the peer who sent the request once the response is sent.
+    pending_outbound_responses: FuturesUnordered<BoxFuture<'static, PeerId>>,
 
     /// Pending inbound responses.
     pending_inbound: FuturesUnordered<BoxFuture<'static, PendingRequest>>,
@@ -162,8 +216,30 @@ pub(crate) struct RequestResponseProtocol {
     /// Pending inbound requests.
     pending_inbound_requests: SubstreamSet<(PeerId, RequestId), Substream>,
 
-    /// Pending dials for outbound requests.
-    pending_dials: HashMap<PeerId, RequestContext>,
+    /// Inbound substreams waiting for the number of concurrent inbound requests to go down.
+    queued_inbound_substreams: VecDeque<(PeerId, Option<ProtocolName>, Substream)>,
+
+    /// Outbound requests waiting for a dial to the peer to finish.
+    pending_dials: HashMap<PeerId, Vec<RequestContext>>,
+
+    /// Timers for outbound requests which haven't been sent yet.
+    ///
+    /// If the timer of a request expires while the request is still waiting for a dial or for a
+    /// substream to open, the request is failed with [`RequestResponseError::Timeout`].
+    pending_timeouts: FuturesUnordered<BoxFuture<'static, RequestId>>,
+
+    /// Outbound substreams which are still opening but whose requests have already timed out.
+    discarded_substreams: HashSet<SubstreamId>,
+
+    /// Requests sent to multiple peers.
+    fan_outs: HashMap<RequestId, FanOutContext>,
+
+    /// Requests sent to individual peers as part of a fan-out request, mapped to the ID of the
+    /// fan-out request.
+    ///
+    /// Events of these requests are collected into [`FanOutContext`] instead of being reported
+    /// to the user.
+    fan_out_requests: HashMap<RequestId, RequestId>,
 
     /// TX channel for sending events to the user protocol.
     event_tx: Sender<InnerRequestResponseEvent>,
@@ -171,6 +247,11 @@ pub(crate) struct RequestResponseProtocol {
     /// RX channel for receive commands from the `RequestResponseHandle`.
     command_rx: Receiver<RequestResponseCommand>,
 
+    /// RX channel for receiving high-priority requests from the `RequestResponseHandle`.
+    ///
+    /// Polled before `command_rx` so high-priority requests don't wait behind other commands.
+    priority_command_rx: Receiver<RequestResponseCommand>,
+
     /// Next request ID.
     ///
     /// Inbound requests are assigned an ephemeral ID TODO: finish
@@ -181,11 +262,34 @@ pub(crate) struct RequestResponseProtocol {
 
     /// Maximum concurrent inbound requests, if specified.
     max_concurrent_inbound_requests: Option<usize>,
+
+    /// Maximum concurrent inbound requests from a single peer, if specified.
+    max_concurrent_inbound_requests_per_peer: Option<usize>,
+
+    /// Maximum number of queued inbound substreams.
+    inbound_request_queue_size: usize,
+
+    /// Maximum number of concurrent outbound requests to a single peer, if specified.
+    max_outbound_requests_per_peer: Option<usize>,
+
+    /// Maximum size of a request.
+    max_request_size: usize,
+
+    /// Maximum size of a response.
+    max_response_size: usize,
+
+    /// Are responses sent as a stream of frames.
+    streaming_responses: bool,
+
+    /// Metrics of the protocol.
+    metrics: Arc<Metrics>,
 }
 
 impl RequestResponseProtocol {
     /// Create new [`RequestResponseProtocol`].
-    pub(crate) fn new(service: TransportService, config: Config) -> Self {
+    pub(crate) fn new(mut service: TransportService, config: Config) -> Self {
+        service.set_connection_preference(config.connection_preference);
+
         Self {
             service,
             peers: HashMap::new(),
@@ -193,14 +297,28 @@ impl RequestResponseProtocol {
             next_request_id: config.next_request_id,
             event_tx: config.event_tx,
             command_rx: config.command_rx,
+            priority_command_rx: config.priority_command_rx,
             protocol: config.protocol_name,
             pending_dials: HashMap::new(),
+            pending_timeouts: FuturesUnordered::new(),
+            discarded_substreams: HashSet::new(),
+            fan_outs: HashMap::new(),
+            fan_out_requests: HashMap::new(),
             pending_outbound: HashMap::new(),
             pending_inbound: FuturesUnordered::new(),
             pending_outbound_cancels: HashMap::new(),
             pending_inbound_requests: SubstreamSet::new(),
+            queued_inbound_substreams: VecDeque::new(),
             pending_outbound_responses: FuturesUnordered::new(),
             max_concurrent_inbound_requests: config.max_concurrent_inbound_request,
+            max_concurrent_inbound_requests_per_peer: config
+                .max_concurrent_inbound_requests_per_peer,
+            inbound_request_queue_size: config.inbound_request_queue_size,
+            max_outbound_requests_per_peer: config.max_outbound_requests_per_peer,
+            max_request_size: config.max_request_size,
+            max_response_size: config.max_response_size,
+            streaming_responses: config.streaming_responses,
+            metrics: config.metrics,
         }
     }
 
@@ -223,34 +341,31 @@ impl RequestResponseProtocol {
             return Err(Error::PeerAlreadyExists(peer));
         };
 
-        match self.pending_dials.remove(&peer) {
-            None => {
-                entry.insert(PeerContext::new());
+        let context = entry.insert(PeerContext::new());
+        let mut failed = Vec::new();
+        let mut requests = self.pending_dials.remove(&peer).unwrap_or_default();
+        requests.sort_by_key(|request| Reverse(request.priority));
+
+        // send all requests that were queued while the peer was being dialed
+        for request in requests {
+            if !Self::has_outbound_capacity(self.max_outbound_requests_per_peer, context) {
+                Self::queue_request(context, request);
+                continue;
             }
-            Some(context) => match self.service.open_substream(peer) {
+
+            match self.service.open_substream(peer) {
                 Ok(substream_id) => {
                     tracing::trace!(
                         target: LOG_TARGET,
                         ?peer,
                         protocol = %self.protocol,
-                        request_id = ?context.request_id,
+                        request_id = ?request.request_id,
                         ?substream_id,
                         "dial succeeded, open substream",
                     );
 
-                    entry.insert(PeerContext {
-                        active: HashSet::from_iter([context.request_id]),
-                        active_inbound: HashMap::new(),
-                    });
-                    self.pending_outbound.insert(
-                        substream_id,
-                        RequestContext::new(
-                            peer,
-                            context.request_id,
-                            context.request,
-                            context.fallback,
-                        ),
-                    );
+                    context.active.insert(request.request_id);
+                    self.pending_outbound.insert(substream_id, request);
                 }
                 // only reason the substream would fail to open would be that the connection
                 // would've been reported to the protocol with enough delay that the keep-alive
@@ -261,20 +376,19 @@ impl RequestResponseProtocol {
                         target: LOG_TARGET,
                         ?peer,
                         protocol = %self.protocol,
-                        request_id = ?context.request_id,
+                        request_id = ?request.request_id,
                         ?error,
                         "failed to open substream",
                     );
 
-                    return self
-                        .report_request_failure(
-                            peer,
-                            context.request_id,
-                            RequestResponseError::Rejected,
-                        )
-                        .await;
+                    failed.push(request.request_id);
                 }
-            },
+            }
+        }
+
+        for request_id in failed {
+            self.report_request_failure(peer, request_id, RequestResponseError::Rejected)
+                .await?;
         }
 
         Ok(())
@@ -295,14 +409,13 @@ impl RequestResponseProtocol {
         };
 
         // sent failure events for all pending outbound requests
-        for request_id in context.active {
+        for request_id in context
+            .active
+            .into_iter()
+            .chain(context.queued.into_iter().map(|request| request.request_id))
+        {
             let _ = self
-                .event_tx
-                .send(InnerRequestResponseEvent::RequestFailed {
-                    peer,
-                    request_id,
-                    error: RequestResponseError::Rejected,
-                })
+                .report_request_failure(peer, request_id, RequestResponseError::Rejected)
                 .await;
         }
 
@@ -310,6 +423,10 @@ impl RequestResponseProtocol {
         for (request_id, _) in context.active_inbound {
             self.pending_inbound_requests.remove(&(peer, request_id));
         }
+        self.queued_inbound_substreams.retain(|(queued, _, _)| queued != &peer);
+
+        // inbound requests of the peer no longer count towards the limit
+        self.process_queued_inbound_substreams();
     }
 
     /// Local node opened a substream to remote node.
@@ -320,10 +437,26 @@ impl RequestResponseProtocol {
         mut substream: Substream,
         fallback_protocol: Option<ProtocolName>,
     ) -> crate::Result<()> {
+        if self.discarded_substreams.remove(&substream_id) {
+            tracing::trace!(
+                target: LOG_TARGET,
+                ?peer,
+                protocol = %self.protocol,
+                ?substream_id,
+                "request timed out before substream was opened, closing substream",
+            );
+
+            let _ = substream.close().await;
+            return Ok(());
+        }
+
         let Some(RequestContext {
             request_id,
             request,
-            fallback,
+            fallbacks,
+            deadline,
+            created,
+            response_stream,
             ..
         }) = self.pending_outbound.remove(&substream_id)
         else {
@@ -348,21 +481,31 @@ impl RequestResponseProtocol {
             "substream opened, send request",
         );
 
-        let request = match (&fallback_protocol, fallback) {
-            (Some(protocol), Some((fallback_protocol, fallback_request)))
-                if protocol == &fallback_protocol =>
-                fallback_request,
-            _ => request,
+        // if the substream was negotiated using a fallback protocol, send the request encoded
+        // for that protocol or the main request if no such request was provided
+        let request = match &fallback_protocol {
+            Some(protocol) => fallbacks
+                .into_iter()
+                .find_map(|(fallback, fallback_request)| {
+                    (&fallback == protocol).then_some(fallback_request)
+                })
+                .unwrap_or(request),
+            None => request,
         };
 
-        let request_timeout = self.timeout;
         let protocol = self.protocol.clone();
+        let max_response_size = self.max_response_size;
+        let streaming_responses = self.streaming_responses;
+        let metrics = Arc::clone(&self.metrics);
         let (tx, rx) = oneshot::channel();
         self.pending_outbound_cancels.insert(request_id, tx);
+        substream.set_max_frame_size(self.max_request_size);
+
+        let started = Instant::now();
+        self.metrics.on_request_sent(started.saturating_duration_since(created));
 
         self.pending_inbound.push(Box::pin(async move {
-            match tokio::time::timeout(request_timeout, substream.send_framed(request.into())).await
-            {
+            match timeout_at(deadline, substream.send_framed(request)).await {
                 Err(_) => (
                     peer,
                     request_id,
@@ -391,6 +534,8 @@ impl RequestResponseProtocol {
                     Err(RequestResponseError::NotConnected),
                 ),
                 Ok(Ok(_)) => {
+                    substream.set_max_frame_size(max_response_size);
+
                     tokio::select! {
                         _ = rx => {
                             tracing::debug!(
@@ -408,7 +553,7 @@ impl RequestResponseProtocol {
                                 fallback_protocol,
                                 Err(RequestResponseError::Canceled))
                         }
-                        _ = sleep(request_timeout) => {
+                        _ = sleep_until(deadline) => {
                             tracing::debug!(
                                 target: LOG_TARGET,
                                 ?peer,
@@ -420,11 +565,18 @@ impl RequestResponseProtocol {
                             let _ = substream.close().await;
                             (peer, request_id, fallback_protocol, Err(RequestResponseError::Timeout))
                         }
-                        event = substream.next() => match event {
-                            Some(Ok(response)) => {
-                                (peer, request_id, fallback_protocol, Ok(response.freeze().into()))
-                            }
-                            _ => (peer, request_id, fallback_protocol, Err(RequestResponseError::Rejected)),
+                        response = Self::read_response(
+                            &mut substream,
+                            streaming_responses,
+                            max_response_size,
+                            response_stream,
+                        ) => {
+                            let response = response.map(|(size, response)| {
+                                metrics.on_response_received(started.elapsed(), size);
+                                response
+                            });
+
+                            (peer, request_id, fallback_protocol, response)
                         }
                     }
                 }
@@ -434,6 +586,114 @@ impl RequestResponseProtocol {
         Ok(())
     }
 
+    /// Read response to an outbound request from `substream`.
+    ///
+    /// If `response_stream` is specified, the response frames are forwarded to it as they're
+    /// read and `None` is returned once the response has been read. Otherwise the response is
+    /// buffered and returned to the caller. The size of the response is returned in both cases.
+    async fn read_response(
+        substream: &mut Substream,
+        streaming_responses: bool,
+        max_response_size: usize,
+        response_stream: Option<ResponseStreamSender>,
+    ) -> Result<(usize, Option<Bytes>), RequestResponseError> {
+        if !streaming_responses {
+            let Some(Ok(response)) = substream.next().await else {
+                return Err(RequestResponseError::Rejected);
+            };
+
+            let size = response.len();
+
+            return match response_stream {
+                None => Ok((size, Some(response.freeze()))),
+                Some(response_stream) => {
+                    response_stream
+                        .send(Some(response.freeze()))
+                        .await
+                        .map_err(|_| RequestResponseError::Canceled)?;
+                    let _ = response_stream.send(None).await;
+
+                    Ok((size, None))
+                }
+            };
+        }
+
+        let mut response = BytesMut::new();
+        let mut size = 0usize;
+
+        loop {
+            let Some(Ok(frame)) = substream.next().await else {
+                return Err(RequestResponseError::Rejected);
+            };
+
+            // empty frame marks the end of the response
+            if frame.is_empty() {
+                return match response_stream {
+                    None => Ok((size, Some(response.freeze()))),
+                    Some(response_stream) => {
+                        let _ = response_stream.send(None).await;
+                        Ok((size, None))
+                    }
+                };
+            }
+
+            size += frame.len();
+
+            match &response_stream {
+                Some(response_stream) => response_stream
+                    .send(Some(frame.freeze()))
+                    .await
+                    .map_err(|_| RequestResponseError::Canceled)?,
+                None => {
+                    if response.len() + frame.len() > max_response_size {
+                        return Err(RequestResponseError::TooLargePayload);
+                    }
+
+                    response.extend_from_slice(&frame);
+                }
+            }
+        }
+    }
+
+    /// Send `response` to an inbound request over `substream`.
+    ///
+    /// If the protocol uses streaming responses, the response is sent as a sequence of non-empty
+    /// frames followed by an empty frame. Otherwise the response is sent as one frame.
+    async fn send_response(
+        substream: &mut Substream,
+        streaming_responses: bool,
+        response: Response,
+    ) -> crate::Result<()> {
+        match (response, streaming_responses) {
+            (Response::Buffered(response), false) => substream.send_framed(response).await,
+            (Response::Buffered(response), true) => {
+                if !response.is_empty() {
+                    substream.send_framed(response).await?;
+                }
+
+                substream.send_framed(Bytes::new()).await
+            }
+            (Response::Streamed(mut response), true) => {
+                while let Some(frame) = response.next().await {
+                    if !frame.is_empty() {
+                        substream.send_framed(frame).await?;
+                    }
+                }
+
+                substream.send_framed(Bytes::new()).await
+            }
+            (Response::Streamed(mut response), false) => {
+                let mut buffer = BytesMut::new();
+
+                while let Some(frame) = response.next().await {
+                    buffer.extend_from_slice(&frame);
+                }
+
+                substream.send_framed(buffer.freeze()).await
+            }
+        }
+    }
+
     /// Handle pending inbound response.
     async fn on_inbound_request(
         &mut self,
@@ -441,23 +701,18 @@ impl RequestResponseProtocol {
         request_id: RequestId,
         request: crate::Result<BytesMut>,
     ) -> crate::Result<()> {
-        let fallback = self
-            .peers
-            .get_mut(&peer)
-            .ok_or(Error::PeerDoesntExist(peer))?
-            .active_inbound
-            .remove(&request_id)
-            .ok_or_else(|| {
-                tracing::debug!(
-                    target: LOG_TARGET,
-                    ?peer,
-                    protocol = %self.protocol,
-                    ?request_id,
-                    "no active inbound request",
-                );
+        let context = self.peers.get_mut(&peer).ok_or(Error::PeerDoesntExist(peer))?;
+        let fallback = context.active_inbound.remove(&request_id).ok_or_else(|| {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?peer,
+                protocol = %self.protocol,
+                ?request_id,
+                "no active inbound request",
+            );
 
-                Error::InvalidState
-            })?;
+            Error::InvalidState
+        })?;
         let mut substream =
             self.pending_inbound_requests.remove(&(peer, request_id)).ok_or_else(|| {
                 tracing::debug!(
@@ -489,8 +744,10 @@ impl RequestResponseProtocol {
                 ?request,
                 "failed to read request from substream",
             );
+            self.process_queued_inbound_substreams();
             return Err(Error::InvalidData);
         };
+        context.pending_responses += 1;
 
         // once the request has been read from the substream, start a future which waits
         // for an input from the user.
@@ -498,8 +755,10 @@ impl RequestResponseProtocol {
         // the input is either a response (succes) or rejection (failure) which is communicated
         // by sending the response over the `oneshot::Sender` or closing it, respectively.
         let timeout = self.timeout;
+        let max_response_size = self.max_response_size;
+        let streaming_responses = self.streaming_responses;
         let (response_tx, rx): (
-            oneshot::Sender<(Vec<u8>, Option<channel::oneshot::Sender<()>>)>,
+            oneshot::Sender<(Response, Option<channel::oneshot::Sender<()>>)>,
             _,
         ) = oneshot::channel();
 
@@ -523,9 +782,13 @@ impl RequestResponseProtocol {
                         ?request_id,
                         "send response",
                     );
+                    substream.set_max_frame_size(max_response_size);
 
-                    match tokio::time::timeout(timeout, substream.send_framed(response.into()))
-                        .await
+                    match tokio::time::timeout(
+                        timeout,
+                        Self::send_response(&mut substream, streaming_responses, response),
+                    )
+                    .await
                     {
                         Err(_) => tracing::debug!(
                             target: LOG_TARGET,
@@ -548,6 +811,8 @@ impl RequestResponseProtocol {
                     }
                 }
             }
+
+            peer
         }));
 
         self.event_tx
@@ -555,7 +820,7 @@ impl RequestResponseProtocol {
                 peer,
                 fallback,
                 request_id,
-                request: request.freeze().into(),
+                request: request.freeze(),
                 response_tx,
             })
             .await
@@ -571,51 +836,129 @@ impl RequestResponseProtocol {
     ) -> crate::Result<()> {
         tracing::trace!(target: LOG_TARGET, ?peer, protocol = %self.protocol, "handle inbound substream");
 
+        if !self.peers.contains_key(&peer) {
+            return Err(Error::PeerDoesntExist(peer));
+        }
+
+        if self.can_accept_inbound_request(&peer) {
+            self.accept_inbound_substream(peer, fallback, substream);
+            return Ok(());
+        }
+
+        if self.queued_inbound_substreams.len() < self.inbound_request_queue_size {
+            tracing::trace!(
+                target: LOG_TARGET,
+                ?peer,
+                protocol = %self.protocol,
+                ?fallback,
+                "queue inbound request as already at maximum",
+            );
+
+            self.queued_inbound_substreams.push_back((peer, fallback, substream));
+            return Ok(());
+        }
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            ?peer,
+            protocol = %self.protocol,
+            ?fallback,
+            max_requests = ?self.max_concurrent_inbound_requests,
+            max_requests_per_peer = ?self.max_concurrent_inbound_requests_per_peer,
+            "rejecting request as already at maximum",
+        );
+
+        let _ = substream.close().await;
+        Ok(())
+    }
+
+    /// Check if a new inbound request from `peer` can be processed without exceeding the
+    /// protocol-wide or per-peer limits for concurrent inbound requests.
+    fn can_accept_inbound_request(&self, peer: &PeerId) -> bool {
         if let Some(max_requests) = self.max_concurrent_inbound_requests {
             let num_inbound_requests =
                 self.pending_inbound_requests.len() + self.pending_outbound_responses.len();
 
             if max_requests <= num_inbound_requests {
-                tracing::debug!(
-                    target: LOG_TARGET,
-                    ?peer,
-                    protocol = %self.protocol,
-                    ?fallback,
-                    ?max_requests,
-                    "rejecting request as already at maximum",
-                );
-
-                let _ = substream.close().await;
-                return Ok(());
+                return false;
             }
         }
 
+        match (
+            self.max_concurrent_inbound_requests_per_peer,
+            self.peers.get(peer),
+        ) {
+            (Some(max_requests), Some(context)) =>
+                context.active_inbound.len() + context.pending_responses < max_requests,
+            _ => true,
+        }
+    }
+
+    /// Start reading an inbound request from `substream`.
+    fn accept_inbound_substream(
+        &mut self,
+        peer: PeerId,
+        fallback: Option<ProtocolName>,
+        mut substream: Substream,
+    ) {
         // allocate ephemeral id for the inbound request and return it to the user protocol
         //
         // when user responds to the request, this is used to associate the response with the
         // correct substream.
         let request_id = self.next_request_id();
-        self.peers
-            .get_mut(&peer)
-            .ok_or(Error::PeerDoesntExist(peer))?
-            .active_inbound
-            .insert(request_id, fallback);
+        let Some(context) = self.peers.get_mut(&peer) else {
+            return;
+        };
+
+        context.active_inbound.insert(request_id, fallback);
+        substream.set_max_frame_size(self.max_request_size);
         self.pending_inbound_requests.insert((peer, request_id), substream);
+    }
 
-        Ok(())
+    /// Start processing queued inbound substreams for which there is now room.
+    fn process_queued_inbound_substreams(&mut self) {
+        while let Some(index) = self
+            .queued_inbound_substreams
+            .iter()
+            .position(|(peer, _, _)| self.can_accept_inbound_request(peer))
+        {
+            let (peer, fallback, substream) = self
+                .queued_inbound_substreams
+                .remove(index)
+                .expect("entry to exist since its index was just found");
+
+            self.accept_inbound_substream(peer, fallback, substream);
+        }
+    }
+
+    /// Response to an inbound request of `peer` has been sent or the request was rejected.
+    fn on_outbound_response_finished(&mut self, peer: PeerId) {
+        if let Some(context) = self.peers.get_mut(&peer) {
+            context.pending_responses = context.pending_responses.saturating_sub(1);
+        }
+
+        self.process_queued_inbound_substreams();
     }
 
     async fn on_dial_failure(&mut self, peer: PeerId) {
-        if let Some(context) = self.pending_dials.remove(&peer) {
-            tracing::debug!(target: LOG_TARGET, ?peer, protocol = %self.protocol, "failed to dial peer");
+        if let Some(requests) = self.pending_dials.remove(&peer) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?peer,
+                protocol = %self.protocol,
+                num_requests = ?requests.len(),
+                "failed to dial peer",
+            );
 
-            let _ = self
-                .peers
-                .get_mut(&peer)
-                .map(|peer_context| peer_context.active.remove(&context.request_id));
-            let _ = self
-                .report_request_failure(peer, context.request_id, RequestResponseError::Rejected)
-                .await;
+            for context in requests {
+                let _ = self
+                    .report_request_failure(
+                        peer,
+                        context.request_id,
+                        RequestResponseError::DialFailure,
+                    )
+                    .await;
+            }
         }
     }
 
@@ -625,6 +968,10 @@ impl RequestResponseProtocol {
         substream: SubstreamId,
         error: Error,
     ) -> crate::Result<()> {
+        if self.discarded_substreams.remove(&substream) {
+            return Ok(());
+        }
+
         let Some(RequestContext {
             request_id, peer, ..
         }) = self.pending_outbound.remove(&substream)
@@ -654,20 +1001,16 @@ impl RequestResponseProtocol {
             .peers
             .get_mut(&peer)
             .map(|peer_context| peer_context.active.remove(&request_id));
+        self.process_queued_requests(peer);
 
-        self.event_tx
-            .send(InnerRequestResponseEvent::RequestFailed {
-                peer,
-                request_id,
-                error: match error {
-                    Error::NegotiationError(NegotiationError::MultistreamSelectError(
-                        MultistreamFailed,
-                    )) => RequestResponseError::UnsupportedProtocol,
-                    _ => RequestResponseError::Rejected,
-                },
-            })
-            .await
-            .map_err(From::from)
+        let error = match error {
+            Error::NegotiationError(NegotiationError::MultistreamSelectError(
+                MultistreamFailed,
+            )) => RequestResponseError::UnsupportedProtocol,
+            _ => RequestResponseError::Rejected,
+        };
+
+        self.report_request_failure(peer, request_id, error).await
     }
 
     /// Report request send failure to user.
@@ -677,6 +1020,12 @@ impl RequestResponseProtocol {
         request_id: RequestId,
         error: RequestResponseError,
     ) -> crate::Result<()> {
+        self.metrics.on_request_failed(&error);
+
+        if let Some(fan_out_id) = self.fan_out_requests.remove(&request_id) {
+            return self.on_fan_out_result(fan_out_id, request_id, peer, Err(error)).await;
+        }
+
         self.event_tx
             .send(InnerRequestResponseEvent::RequestFailed {
                 peer,
@@ -690,18 +1039,20 @@ impl RequestResponseProtocol {
     /// Send request to remote peer.
     async fn on_send_request(
         &mut self,
-        peer: PeerId,
-        request_id: RequestId,
-        request: Vec<u8>,
+        request: RequestContext,
         dial_options: DialOptions,
-        fallback: Option<(ProtocolName, Vec<u8>)>,
     ) -> crate::Result<()> {
+        let peer = request.peer;
+        let request_id = request.request_id;
+        let deadline = request.deadline;
+
         tracing::trace!(
             target: LOG_TARGET,
             ?peer,
             protocol = %self.protocol,
             ?request_id,
             ?dial_options,
+            ?deadline,
             "send request to remote peer",
         );
 
@@ -725,43 +1076,69 @@ impl RequestResponseProtocol {
                         )
                         .await;
                 }
-                DialOptions::Dial => match self.service.dial(&peer) {
-                    Ok(_) => {
-                        tracing::trace!(
-                            target: LOG_TARGET,
-                            ?peer,
-                            protocol = %self.protocol,
-                            ?request_id,
-                            "started dialing peer",
-                        );
+                DialOptions::Dial => {
+                    // if the peer is already being dialed, the request is sent once the dial
+                    // concludes
+                    let result = match self.pending_dials.contains_key(&peer) {
+                        true => Ok(()),
+                        false => self.service.dial(&peer),
+                    };
+
+                    match result {
+                        // the connection may have been established but not yet reported to the
+                        // protocol in which case the request is sent once it's reported
+                        Ok(()) | Err(Error::AlreadyConnected) => {
+                            tracing::trace!(
+                                target: LOG_TARGET,
+                                ?peer,
+                                protocol = %self.protocol,
+                                ?request_id,
+                                "dialing peer, queue request",
+                            );
 
-                        self.pending_dials.insert(
-                            peer,
-                            RequestContext::new(peer, request_id, request, fallback),
-                        );
-                        return Ok(());
-                    }
-                    Err(error) => {
-                        tracing::debug!(
-                            target: LOG_TARGET,
-                            ?peer,
-                            protocol = %self.protocol,
-                            ?error,
-                            "failed to dial peer"
-                        );
+                            self.pending_dials.entry(peer).or_default().push(request);
+                            self.pending_timeouts
+                                .push(Box::pin(sleep_until(deadline).map(move |_| request_id)));
+                            return Ok(());
+                        }
+                        Err(error) => {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?peer,
+                                protocol = %self.protocol,
+                                ?error,
+                                "failed to dial peer"
+                            );
 
-                        return self
-                            .report_request_failure(
-                                peer,
-                                request_id,
-                                RequestResponseError::Rejected,
-                            )
-                            .await;
+                            return self
+                                .report_request_failure(
+                                    peer,
+                                    request_id,
+                                    RequestResponseError::DialFailure,
+                                )
+                                .await;
+                        }
                     }
-                },
+                }
             }
         };
 
+        self.pending_timeouts
+            .push(Box::pin(sleep_until(deadline).map(move |_| request_id)));
+
+        if !Self::has_outbound_capacity(self.max_outbound_requests_per_peer, context) {
+            tracing::trace!(
+                target: LOG_TARGET,
+                ?peer,
+                protocol = %self.protocol,
+                ?request_id,
+                "too many active requests to peer, queue request",
+            );
+
+            Self::queue_request(context, request);
+            return Ok(());
+        }
+
         // open substream and push it pending outbound substreams
         // once the substream is opened, send the request.
         match self.service.open_substream(peer) {
@@ -769,10 +1146,7 @@ impl RequestResponseProtocol {
                 let unique_request_id = context.active.insert(request_id);
                 debug_assert!(unique_request_id);
 
-                self.pending_outbound.insert(
-                    substream_id,
-                    RequestContext::new(peer, request_id, request, fallback),
-                );
+                self.pending_outbound.insert(substream_id, request);
 
                 Ok(())
             }
@@ -792,13 +1166,83 @@ impl RequestResponseProtocol {
         }
     }
 
+    /// Check if another outbound request can be sent to the peer of `context`.
+    fn has_outbound_capacity(max_requests: Option<usize>, context: &PeerContext) -> bool {
+        max_requests.map_or(true, |max_requests| context.active.len() < max_requests)
+    }
+
+    /// Queue `request` to be sent to the peer of `context` once it has capacity for more requests.
+    ///
+    /// The queue is kept ordered by priority and requests of the same priority are sent in the
+    /// order they were queued.
+    fn queue_request(context: &mut PeerContext, request: RequestContext) {
+        let index = context
+            .queued
+            .iter()
+            .position(|queued| queued.priority < request.priority)
+            .unwrap_or(context.queued.len());
+
+        context.queued.insert(index, request);
+    }
+
+    /// Send queued outbound requests to `peer` while the number of active requests is below the
+    /// limit.
+    ///
+    /// If a substream fails to open, the failure is reported through `pending_inbound` like the
+    /// failures of requests which have already been sent.
+    fn process_queued_requests(&mut self, peer: PeerId) {
+        let Some(context) = self.peers.get_mut(&peer) else {
+            return;
+        };
+
+        while Self::has_outbound_capacity(self.max_outbound_requests_per_peer, context) {
+            let Some(request) = context.queued.pop_front() else {
+                return;
+            };
+            let request_id = request.request_id;
+            context.active.insert(request_id);
+
+            match self.service.open_substream(peer) {
+                Ok(substream_id) => {
+                    tracing::trace!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        protocol = %self.protocol,
+                        ?request_id,
+                        ?substream_id,
+                        "send queued request",
+                    );
+
+                    self.pending_outbound.insert(substream_id, request);
+                }
+                Err(error) => {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        protocol = %self.protocol,
+                        ?request_id,
+                        ?error,
+                        "failed to open substream for queued request",
+                    );
+
+                    self.pending_inbound.push(Box::pin(futures::future::ready((
+                        peer,
+                        request_id,
+                        None,
+                        Err(RequestResponseError::Rejected),
+                    ))));
+                }
+            }
+        }
+    }
+
     /// Handle substream event.
     async fn on_substream_event(
         &mut self,
         peer: PeerId,
         request_id: RequestId,
         fallback: Option<ProtocolName>,
-        message: Result<Vec<u8>, RequestResponseError>,
+        message: Result<Option<Bytes>, RequestResponseError>,
     ) -> crate::Result<()> {
         if !self
             .peers
@@ -816,42 +1260,237 @@ impl RequestResponseProtocol {
             );
             return Err(Error::InvalidState);
         }
+        self.process_queued_requests(peer);
+
+        if let Err(error) = &message {
+            self.metrics.on_request_failed(error);
+        }
+
+        if let Some(fan_out_id) = self.fan_out_requests.remove(&request_id) {
+            let result = message.map(Option::unwrap_or_default);
+            return self.on_fan_out_result(fan_out_id, request_id, peer, result).await;
+        }
 
         let event = match message {
-            Ok(response) => InnerRequestResponseEvent::ResponseReceived {
+            Ok(Some(response)) => InnerRequestResponseEvent::ResponseReceived {
                 peer,
                 request_id,
                 response,
                 fallback,
             },
-            Err(error) => match error {
-                RequestResponseError::Canceled => {
-                    tracing::debug!(
-                        target: LOG_TARGET,
-                        ?peer,
-                        protocol = %self.protocol,
-                        ?request_id,
-                        "request canceled by local node",
-                    );
-                    return Ok(());
-                }
-                error => InnerRequestResponseEvent::RequestFailed {
-                    peer,
-                    request_id,
-                    error,
-                },
+            Ok(None) => InnerRequestResponseEvent::ResponseStreamFinished { request_id },
+            Err(error) => InnerRequestResponseEvent::RequestFailed {
+                peer,
+                request_id,
+                error,
             },
         };
 
         self.event_tx.send(event).await.map_err(From::from)
     }
 
+    /// Send request to multiple peers.
+    async fn on_send_fan_out_request(
+        &mut self,
+        request_id: RequestId,
+        peers: Vec<(PeerId, RequestId)>,
+        request: Bytes,
+        dial_options: DialOptions,
+        mode: FanOutMode,
+    ) -> crate::Result<()> {
+        tracing::trace!(
+            target: LOG_TARGET,
+            protocol = %self.protocol,
+            ?request_id,
+            num_peers = ?peers.len(),
+            ?mode,
+            "send request to multiple peers",
+        );
+
+        self.fan_outs.insert(
+            request_id,
+            FanOutContext {
+                mode,
+                pending: peers
+                    .iter()
+                    .map(|(peer, peer_request_id)| (*peer_request_id, *peer))
+                    .collect(),
+                responses: Vec::new(),
+            },
+        );
+
+        if peers.is_empty() {
+            return self.finish_fan_out(request_id).await;
+        }
+
+        let deadline = Instant::now() + self.timeout;
+
+        for (peer, peer_request_id) in peers {
+            self.fan_out_requests.insert(peer_request_id, request_id);
+
+            let context =
+                RequestContext::new(peer, peer_request_id, request.clone(), Vec::new(), deadline);
+            self.on_send_request(context, dial_options).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Request sent to `peer` as part of a fan-out request finished.
+    async fn on_fan_out_result(
+        &mut self,
+        fan_out_id: RequestId,
+        request_id: RequestId,
+        peer: PeerId,
+        result: Result<Bytes, RequestResponseError>,
+    ) -> crate::Result<()> {
+        // the fan-out request may have already finished
+        let Some(context) = self.fan_outs.get_mut(&fan_out_id) else {
+            return Ok(());
+        };
+
+        let success = result.is_ok();
+        context.pending.remove(&request_id);
+        context.responses.push((peer, result));
+
+        let finished = match context.mode {
+            FanOutMode::FirstSuccess => success || context.pending.is_empty(),
+            FanOutMode::All => context.pending.is_empty(),
+        };
+
+        match finished {
+            true => self.finish_fan_out(fan_out_id).await,
+            false => Ok(()),
+        }
+    }
+
+    /// Cancel the pending requests of a fan-out request and report the collected responses to
+    /// the user.
+    async fn finish_fan_out(&mut self, fan_out_id: RequestId) -> crate::Result<()> {
+        let Some(mut context) = self.fan_outs.remove(&fan_out_id) else {
+            return Ok(());
+        };
+
+        for (request_id, peer) in context.pending {
+            // requests which have been sent are canceled by the future waiting for the response
+            // and their events are discarded once received, others are removed immediately
+            match self.pending_outbound_cancels.remove(&request_id) {
+                Some(tx) => {
+                    let _ = tx.send(());
+                }
+                None => {
+                    self.fan_out_requests.remove(&request_id);
+                    self.remove_pending_request(request_id);
+                }
+            }
+
+            context.responses.push((peer, Err(RequestResponseError::Canceled)));
+        }
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            protocol = %self.protocol,
+            request_id = ?fan_out_id,
+            num_responses = ?context.responses.len(),
+            "fan-out request finished",
+        );
+
+        self.event_tx
+            .send(InnerRequestResponseEvent::FanOutFinished {
+                request_id: fan_out_id,
+                responses: context.responses,
+            })
+            .await
+            .map_err(From::from)
+    }
+
+    /// Remove an outbound request which is waiting for a dial, for a substream to open or for
+    /// earlier requests to the peer to finish.
+    ///
+    /// Returns the peer of the request if the request existed and its failure hasn't been
+    /// reported to the user yet.
+    fn remove_pending_request(&mut self, request_id: RequestId) -> Option<PeerId> {
+        if let Some(peer) = self.pending_dials.iter().find_map(|(peer, requests)| {
+            requests.iter().any(|context| context.request_id == request_id).then_some(*peer)
+        }) {
+            if let Entry::Occupied(mut entry) = self.pending_dials.entry(peer) {
+                entry.get_mut().retain(|context| context.request_id != request_id);
+
+                if entry.get().is_empty() {
+                    entry.remove();
+                }
+            }
+
+            return Some(peer);
+        }
+
+        if let Some((peer, context)) = self.peers.iter_mut().find(|(_, context)| {
+            context.queued.iter().any(|request| request.request_id == request_id)
+        }) {
+            context.queued.retain(|request| request.request_id != request_id);
+            return Some(*peer);
+        }
+
+        let (substream_id, peer) =
+            self.pending_outbound.iter().find_map(|(substream_id, context)| {
+                (context.request_id == request_id).then_some((*substream_id, context.peer))
+            })?;
+
+        self.pending_outbound.remove(&substream_id);
+        self.discarded_substreams.insert(substream_id);
+
+        // the failure may have already been reported if the connection was closed
+        let removed = self
+            .peers
+            .get_mut(&peer)
+            .map_or(false, |context| context.active.remove(&request_id));
+        self.process_queued_requests(peer);
+
+        removed.then_some(peer)
+    }
+
+    /// Timer of an outbound request expired.
+    ///
+    /// If the request is still waiting for a dial or for a substream to open, it's removed and
+    /// the timeout is reported to the user. Requests which have already been sent time out on
+    /// their own.
+    async fn on_request_timeout(&mut self, request_id: RequestId) -> crate::Result<()> {
+        let Some(peer) = self.remove_pending_request(request_id) else {
+            return Ok(());
+        };
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            ?peer,
+            protocol = %self.protocol,
+            ?request_id,
+            "request timed out before it was sent",
+        );
+
+        self.report_request_failure(peer, request_id, RequestResponseError::Timeout)
+            .await
+    }
+
     /// Cancel outbound request.
+    ///
+    /// [`RequestResponseError::Canceled`] is reported for the request unless a response or a
+    /// failure has already been reported for it.
+    /// [`RequestResponseHandle`] discards the events of canceled requests.
     async fn on_cancel_request(&mut self, request_id: RequestId) -> crate::Result<()> {
         tracing::trace!(target: LOG_TARGET, protocol = %self.protocol, ?request_id, "cancel outbound request");
 
-        match self.pending_outbound_cancels.remove(&request_id) {
-            Some(tx) => tx.send(()).map_err(|_| Error::SubstreamDoesntExist),
+        if self.fan_outs.contains_key(&request_id) {
+            return self.finish_fan_out(request_id).await;
+        }
+
+        if let Some(tx) = self.pending_outbound_cancels.remove(&request_id) {
+            return tx.send(()).map_err(|_| Error::SubstreamDoesntExist);
+        }
+
+        match self.remove_pending_request(request_id) {
+            Some(peer) =>
+                self.report_request_failure(peer, request_id, RequestResponseError::Canceled)
+                    .await,
             None => {
                 tracing::debug!(
                     target: LOG_TARGET,
@@ -865,6 +1504,116 @@ impl RequestResponseProtocol {
         }
     }
 
+    /// Handle command received from [`RequestResponseHandle`].
+    async fn on_command(&mut self, command: RequestResponseCommand) {
+        match command {
+            RequestResponseCommand::SendRequest {
+                peer,
+                request_id,
+                request,
+                dial_options,
+                timeout,
+                response_stream,
+                priority,
+            } => {
+                let deadline = Instant::now() + timeout.unwrap_or(self.timeout);
+                let request = RequestContext {
+                    response_stream,
+                    priority,
+                    ..RequestContext::new(peer, request_id, request, Vec::new(), deadline)
+                };
+
+                if let Err(error) = self.on_send_request(request, dial_options).await {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        protocol = %self.protocol,
+                        ?request_id,
+                        ?error,
+                        "failed to send request",
+                    );
+                }
+            }
+            RequestResponseCommand::CancelRequest { request_id } => {
+                if let Err(error) = self.on_cancel_request(request_id).await {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        protocol = %self.protocol,
+                        ?request_id,
+                        ?error,
+                        "failed to cancel reqeuest",
+                    );
+                }
+            }
+            RequestResponseCommand::BanPeer { peer, duration } => {
+                if let Err(error) = self.service.ban_peer(peer, duration) {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        protocol = %self.protocol,
+                        ?error,
+                        "failed to ban peer",
+                    );
+                }
+            }
+            RequestResponseCommand::Disconnect { peer } => {
+                if let Err(error) = self.service.disconnect(peer) {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        protocol = %self.protocol,
+                        ?error,
+                        "failed to disconnect peer",
+                    );
+                }
+            }
+            RequestResponseCommand::AddKnownAddress { peer, addresses } => {
+                self.service.add_known_address(&peer, addresses.into_iter());
+            }
+            RequestResponseCommand::SendFanOutRequest {
+                request_id,
+                peers,
+                request,
+                dial_options,
+                mode,
+            } => {
+                if let Err(error) = self
+                    .on_send_fan_out_request(request_id, peers, request, dial_options, mode)
+                    .await
+                {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        protocol = %self.protocol,
+                        ?request_id,
+                        ?error,
+                        "failed to send request to multiple peers",
+                    );
+                }
+            }
+            RequestResponseCommand::SendRequestWithFallback {
+                peer,
+                request_id,
+                request,
+                fallbacks,
+                dial_options,
+            } => {
+                let deadline = Instant::now() + self.timeout;
+                let request = RequestContext::new(peer, request_id, request, fallbacks, deadline);
+
+                if let Err(error) = self.on_send_request(request, dial_options).await {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        protocol = %self.protocol,
+                        ?request_id,
+                        ?error,
+                        "failed to send request",
+                    );
+                }
+            }
+        }
+    }
+
     /// Start [`RequestResponseProtocol`] event loop.
     pub async fn run(mut self) {
         tracing::debug!(target: LOG_TARGET, "starting request-response event loop");
@@ -879,7 +1628,7 @@ impl RequestResponseProtocol {
                     Some(TransportEvent::ConnectionEstablished { peer, .. }) => {
                         let _ = self.on_connection_established(peer).await;
                     }
-                    Some(TransportEvent::ConnectionClosed { peer }) => {
+                    Some(TransportEvent::ConnectionClosed { peer, .. }) => {
                         self.on_connection_closed(peer).await;
                     }
                     Some(TransportEvent::SubstreamOpened {
@@ -933,7 +1682,20 @@ impl RequestResponseProtocol {
 
                     self.pending_outbound_cancels.remove(&request_id);
                 }
-                _ = self.pending_outbound_responses.next(), if !self.pending_outbound_responses.is_empty() => {}
+                peer = self.pending_outbound_responses.select_next_some(), if !self.pending_outbound_responses.is_empty() => {
+                    self.on_outbound_response_finished(peer);
+                }
+                request_id = self.pending_timeouts.select_next_some(), if !self.pending_timeouts.is_empty() => {
+                    if let Err(error) = self.on_request_timeout(request_id).await {
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            protocol = %self.protocol,
+                            ?request_id,
+                            ?error,
+                            "failed to handle request timeout",
+                        );
+                    }
+                }
                 event = self.pending_inbound_requests.next() => match event {
                     Some(((peer, request_id), message)) => {
                         if let Err(error) = self.on_inbound_request(peer, request_id, message).await {
@@ -949,48 +1711,19 @@ impl RequestResponseProtocol {
                     }
                     None => return,
                 },
-                command = self.command_rx.recv() => match command {
+                command = self.priority_command_rx.recv() => match command {
                     None => {
                         tracing::debug!(target: LOG_TARGET, protocol = %self.protocol, "user protocol has exited, exiting");
                         return
                     }
-                    Some(command) => match command {
-                        RequestResponseCommand::SendRequest { peer, request_id, request, dial_options } => {
-                            if let Err(error) = self.on_send_request(peer, request_id, request, dial_options, None).await {
-                                tracing::debug!(
-                                    target: LOG_TARGET,
-                                    ?peer,
-                                    protocol = %self.protocol,
-                                    ?request_id,
-                                    ?error,
-                                    "failed to send request",
-                                );
-                            }
-                        }
-                        RequestResponseCommand::CancelRequest { request_id } => {
-                            if let Err(error) = self.on_cancel_request(request_id).await {
-                                tracing::debug!(
-                                    target: LOG_TARGET,
-                                    protocol = %self.protocol,
-                                    ?request_id,
-                                    ?error,
-                                    "failed to cancel reqeuest",
-                                );
-                            }
-                        }
-                        RequestResponseCommand::SendRequestWithFallback { peer, request_id, request, fallback, dial_options } => {
-                            if let Err(error) = self.on_send_request(peer, request_id, request, dial_options, Some(fallback)).await {
-                                tracing::debug!(
-                                    target: LOG_TARGET,
-                                    ?peer,
-                                    protocol = %self.protocol,
-                                    ?request_id,
-                                    ?error,
-                                    "failed to send request",
-                                );
-                            }
-                        }
+                    Some(command) => self.on_command(command).await,
+                },
+                command = self.command_rx.recv() => match command {
+                    None => {
+                        tracing::debug!(target: LOG_TARGET, protocol = %self.protocol, "user protocol has exited, exiting");
+                        return
                     }
+                    Some(command) => self.on_command(command).await,
                 },
             }
         }
@@ -23,8 +23,10 @@ use crate::{
     mock::substream::{DummySubstream, MockSubstream},
     protocol::{
         request_response::{
-            ConfigBuilder, DialOptions, RequestResponseError, RequestResponseEvent,
-            RequestResponseHandle, RequestResponseProtocol,
+            handle::InnerRequestResponseEvent, Codec, ConfigBuilder, DialOptions, FanOutContext,
+            FanOutMode, RequestContext, RequestPriority, RequestResponseError,
+            RequestResponseEvent, RequestResponseHandle, RequestResponseProtocol,
+            TypedRequestResponse, TypedRequestResponseEvent,
         },
         InnerTransportEvent, TransportService,
     },
@@ -34,10 +36,11 @@ use crate::{
     BandwidthSink, Error, PeerId, ProtocolName,
 };
 
+use bytes::Bytes;
 use futures::StreamExt;
-use tokio::sync::mpsc::Sender;
+use tokio::{sync::mpsc::Sender, time::Instant};
 
-use std::{collections::HashSet, task::Poll};
+use std::{collections::HashSet, task::Poll, time::Duration};
 
 // create new protocol for testing
 fn protocol() -> (
@@ -160,7 +163,12 @@ async fn substream_event_for_unknown_peer() {
     assert!(protocol.peers.contains_key(&peer));
 
     match protocol
-        .on_substream_event(peer, RequestId::from(1337usize), None, Ok(vec![13, 37]))
+        .on_substream_event(
+            peer,
+            RequestId::from(1337usize),
+            None,
+            Ok(Some(Bytes::from_static(&[13, 37]))),
+        )
         .await
     {
         Err(Error::InvalidState) => {}
@@ -272,11 +280,14 @@ async fn request_failure_reported_once() {
     // since the peer wasn't properly registered, opening substream to them will fail
     protocol
         .on_send_request(
-            peer,
-            RequestId::from(1337usize),
-            vec![1, 2, 3, 4],
+            RequestContext::new(
+                peer,
+                RequestId::from(1337usize),
+                Bytes::from_static(&[1, 2, 3, 4]),
+                Vec::new(),
+                Instant::now() + Duration::from_secs(5),
+            ),
             DialOptions::Reject,
-            None,
         )
         .await
         .unwrap();
@@ -304,3 +315,624 @@ async fn request_failure_reported_once() {
     })
     .await;
 }
+
+#[tokio::test]
+async fn request_timed_out_while_opening_substream() {
+    let (mut protocol, mut handle, _manager, _tx) = protocol();
+
+    let peer = PeerId::random();
+    let request_id = RequestId::from(1337usize);
+    let substream_id = SubstreamId::from(1338usize);
+
+    protocol.on_connection_established(peer).await.unwrap();
+    protocol.peers.get_mut(&peer).unwrap().active.insert(request_id);
+    protocol.pending_outbound.insert(
+        substream_id,
+        RequestContext::new(
+            peer,
+            request_id,
+            Bytes::from_static(&[1, 2, 3, 4]),
+            Vec::new(),
+            Instant::now(),
+        ),
+    );
+
+    protocol.on_request_timeout(request_id).await.unwrap();
+    assert!(protocol.pending_outbound.is_empty());
+    assert!(protocol.peers.get(&peer).unwrap().active.is_empty());
+
+    match handle.next().await {
+        Some(RequestResponseEvent::RequestFailed {
+            peer: request_peer,
+            request_id: failed_request_id,
+            error,
+        }) => {
+            assert_eq!(request_peer, peer);
+            assert_eq!(failed_request_id, request_id);
+            assert_eq!(error, RequestResponseError::Timeout);
+        }
+        event => panic!("unexpected event: {event:?}"),
+    }
+
+    // the substream opens after the request has timed out and is closed without sending the request
+    protocol
+        .on_outbound_substream(
+            peer,
+            substream_id,
+            Substream::new_mock(
+                peer,
+                SubstreamId::from(0usize),
+                Box::new(DummySubstream::new()),
+            ),
+            None,
+        )
+        .await
+        .unwrap();
+    assert!(protocol.discarded_substreams.is_empty());
+}
+
+#[tokio::test]
+async fn cancel_request_while_opening_substream() {
+    let (mut protocol, mut handle, _manager, _tx) = protocol();
+
+    let peer = PeerId::random();
+    let request_id = RequestId::from(1337usize);
+    let substream_id = SubstreamId::from(1338usize);
+
+    protocol.on_connection_established(peer).await.unwrap();
+    protocol.peers.get_mut(&peer).unwrap().active.insert(request_id);
+    protocol.pending_outbound.insert(
+        substream_id,
+        RequestContext::new(
+            peer,
+            request_id,
+            Bytes::from_static(&[1, 2, 3, 4]),
+            Vec::new(),
+            Instant::now() + Duration::from_secs(5),
+        ),
+    );
+
+    protocol.on_cancel_request(request_id).await.unwrap();
+    assert!(protocol.pending_outbound.is_empty());
+    assert!(protocol.discarded_substreams.contains(&substream_id));
+
+    match handle.next().await {
+        Some(RequestResponseEvent::RequestFailed {
+            request_id: failed_request_id,
+            error,
+            ..
+        }) => {
+            assert_eq!(failed_request_id, request_id);
+            assert_eq!(error, RequestResponseError::Canceled);
+        }
+        event => panic!("unexpected event: {event:?}"),
+    }
+
+    // timer of the canceled request expires and nothing is reported
+    protocol.on_request_timeout(request_id).await.unwrap();
+
+    futures::future::poll_fn(|cx| match handle.poll_next_unpin(cx) {
+        Poll::Pending => Poll::Ready(()),
+        event => panic!("read an unexpected event from handle: {event:?}"),
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn requests_queued_while_dialing() {
+    let (mut protocol, mut handle, _manager, _tx) = protocol();
+
+    let peer = PeerId::random();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    protocol.pending_dials.insert(
+        peer,
+        vec![RequestContext::new(
+            peer,
+            RequestId::from(1usize),
+            Bytes::from_static(&[1, 2, 3, 4]),
+            Vec::new(),
+            deadline,
+        )],
+    );
+
+    // peer is already being dialed so the request is queued
+    protocol
+        .on_send_request(
+            RequestContext::new(
+                peer,
+                RequestId::from(2usize),
+                Bytes::from_static(&[5, 6, 7, 8]),
+                Vec::new(),
+                Instant::now() + Duration::from_secs(5),
+            ),
+            DialOptions::Dial,
+        )
+        .await
+        .unwrap();
+    assert_eq!(protocol.pending_dials.get(&peer).unwrap().len(), 2);
+
+    // both requests fail when the dial fails
+    protocol.on_dial_failure(peer).await;
+    assert!(protocol.pending_dials.is_empty());
+
+    for expected in [RequestId::from(1usize), RequestId::from(2usize)] {
+        match handle.next().await {
+            Some(RequestResponseEvent::RequestFailed {
+                peer: request_peer,
+                request_id,
+                error,
+            }) => {
+                assert_eq!(request_peer, peer);
+                assert_eq!(request_id, expected);
+                assert_eq!(error, RequestResponseError::DialFailure);
+            }
+            event => panic!("unexpected event: {event:?}"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn request_for_negotiated_fallback_sent() {
+    let (mut protocol, _handle, _manager, _tx) = protocol();
+
+    let peer = PeerId::random();
+    let request_id = RequestId::from(1337usize);
+    let substream_id = SubstreamId::from(1338usize);
+
+    protocol.on_connection_established(peer).await.unwrap();
+    protocol.peers.get_mut(&peer).unwrap().active.insert(request_id);
+    protocol.pending_outbound.insert(
+        substream_id,
+        RequestContext::new(
+            peer,
+            request_id,
+            Bytes::from_static(&[1, 1, 1, 1]),
+            vec![
+                (
+                    ProtocolName::from("/req/1/fallback/1"),
+                    Bytes::from_static(&[2, 2, 2, 2]),
+                ),
+                (
+                    ProtocolName::from("/req/1/fallback/2"),
+                    Bytes::from_static(&[3, 3, 3, 3]),
+                ),
+            ],
+            Instant::now() + Duration::from_secs(5),
+        ),
+    );
+
+    // verify that the request for the negotiated fallback is sent
+    let mut substream = MockSubstream::new();
+    substream.expect_poll_ready().times(1).return_once(|_| Poll::Ready(Ok(())));
+    substream
+        .expect_start_send()
+        .withf(|request| request.as_ref() == [3, 3, 3, 3])
+        .times(1)
+        .return_once(|_| Ok(()));
+    substream.expect_poll_flush().times(1).return_once(|_| Poll::Ready(Ok(())));
+    substream
+        .expect_poll_next()
+        .times(1)
+        .return_once(|_| Poll::Ready(Some(Ok(bytes::BytesMut::from(&[4, 4, 4, 4][..])))));
+
+    protocol
+        .on_outbound_substream(
+            peer,
+            substream_id,
+            Substream::new_mock(peer, SubstreamId::from(0usize), Box::new(substream)),
+            Some(ProtocolName::from("/req/1/fallback/2")),
+        )
+        .await
+        .unwrap();
+
+    let (_, _, fallback, response) = protocol.pending_inbound.next().await.unwrap();
+    assert_eq!(fallback, Some(ProtocolName::from("/req/1/fallback/2")));
+    assert_eq!(response, Ok(Some(Bytes::from_static(&[4, 4, 4, 4]))));
+}
+
+#[test]
+fn channel_size_configured() {
+    let (config, _handle) = ConfigBuilder::new(ProtocolName::from("/req/1"))
+        .with_max_size(1024)
+        .with_channel_size(16)
+        .build();
+
+    assert_eq!(config.event_tx.max_capacity(), 16);
+}
+
+#[tokio::test]
+async fn excess_inbound_requests_from_peer_queued() {
+    let (mut protocol, _handle, _manager, _tx) = protocol();
+    protocol.max_concurrent_inbound_requests_per_peer = Some(1);
+    protocol.inbound_request_queue_size = 1;
+
+    let peer1 = PeerId::random();
+    let peer2 = PeerId::random();
+    protocol.on_connection_established(peer1).await.unwrap();
+    protocol.on_connection_established(peer2).await.unwrap();
+
+    let mut substream = MockSubstream::new();
+    substream
+        .expect_poll_next()
+        .times(1)
+        .return_once(|_| Poll::Ready(Some(Err(Error::Unknown))));
+
+    // first request of `peer1` is accepted, second is queued and third is rejected
+    protocol
+        .on_inbound_substream(
+            peer1,
+            None,
+            Substream::new_mock(peer1, SubstreamId::from(0usize), Box::new(substream)),
+        )
+        .await
+        .unwrap();
+
+    for substream_id in 1..3usize {
+        protocol
+            .on_inbound_substream(
+                peer1,
+                None,
+                Substream::new_mock(
+                    peer1,
+                    SubstreamId::from(substream_id),
+                    Box::new(DummySubstream::new()),
+                ),
+            )
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(protocol.peers.get(&peer1).unwrap().active_inbound.len(), 1);
+    assert_eq!(protocol.queued_inbound_substreams.len(), 1);
+
+    // request from another peer is not affected by the limit of `peer1`
+    protocol
+        .on_inbound_substream(
+            peer2,
+            None,
+            Substream::new_mock(
+                peer2,
+                SubstreamId::from(3usize),
+                Box::new(DummySubstream::new()),
+            ),
+        )
+        .await
+        .unwrap();
+    assert_eq!(protocol.peers.get(&peer2).unwrap().active_inbound.len(), 1);
+
+    // reading the first request fails which frees up a slot for the queued request
+    let ((peer, request_id), event) = protocol.pending_inbound_requests.next().await.unwrap();
+    assert_eq!(peer, peer1);
+    assert!(protocol.on_inbound_request(peer, request_id, event).await.is_err());
+
+    assert_eq!(protocol.peers.get(&peer1).unwrap().active_inbound.len(), 1);
+    assert!(protocol.queued_inbound_substreams.is_empty());
+}
+
+// codec which encodes `u32`s as little-endian bytes
+struct U32Codec;
+
+impl Codec<u32> for U32Codec {
+    fn encode(&self, message: &u32) -> Bytes {
+        Bytes::copy_from_slice(&message.to_le_bytes())
+    }
+
+    fn decode(&self, bytes: Bytes) -> crate::Result<u32> {
+        Ok(u32::from_le_bytes(
+            bytes[..].try_into().map_err(|_| Error::InvalidData)?,
+        ))
+    }
+}
+
+#[tokio::test]
+async fn typed_handle_decodes_messages() {
+    let (protocol, handle, _manager, _tx) = protocol();
+    let mut handle = TypedRequestResponse::<u32, u32, _>::new(handle, U32Codec);
+    let peer = PeerId::random();
+
+    // valid and invalid response
+    for (request_id, response) in [
+        (1usize, Bytes::copy_from_slice(&1337u32.to_le_bytes())),
+        (2usize, Bytes::from_static(&[1, 2, 3])),
+    ] {
+        protocol
+            .event_tx
+            .send(InnerRequestResponseEvent::ResponseReceived {
+                peer,
+                fallback: None,
+                request_id: RequestId::from(request_id),
+                response,
+            })
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(
+        handle.next().await.unwrap(),
+        TypedRequestResponseEvent::ResponseReceived {
+            peer,
+            request_id: RequestId::from(1usize),
+            fallback: None,
+            response: 1337u32,
+        }
+    );
+    assert_eq!(
+        handle.next().await.unwrap(),
+        TypedRequestResponseEvent::InvalidResponse {
+            peer,
+            request_id: RequestId::from(2usize),
+        }
+    );
+
+    // invalid request is rejected
+    let (response_tx, response_rx) = tokio::sync::oneshot::channel();
+    protocol
+        .event_tx
+        .send(InnerRequestResponseEvent::RequestReceived {
+            peer,
+            fallback: None,
+            request_id: RequestId::from(3usize),
+            request: Bytes::from_static(&[1, 2, 3, 4, 5]),
+            response_tx,
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(
+        handle.next().await.unwrap(),
+        TypedRequestResponseEvent::InvalidRequest {
+            peer,
+            request_id: RequestId::from(3usize),
+        }
+    );
+    assert!(response_rx.await.is_err());
+}
+
+#[tokio::test]
+async fn fan_out_request_to_disconnected_peers() {
+    let (mut protocol, mut handle, _manager, _tx) = protocol();
+    let peer1 = PeerId::random();
+    let peer2 = PeerId::random();
+
+    protocol
+        .on_send_fan_out_request(
+            RequestId::from(1usize),
+            vec![
+                (peer1, RequestId::from(2usize)),
+                (peer2, RequestId::from(3usize)),
+            ],
+            Bytes::from_static(&[1, 2, 3, 4]),
+            DialOptions::Reject,
+            FanOutMode::All,
+        )
+        .await
+        .unwrap();
+
+    // failures of individual requests are reported only once every request has finished
+    assert_eq!(
+        handle.next().await.unwrap(),
+        RequestResponseEvent::FanOutFinished {
+            request_id: RequestId::from(1usize),
+            responses: vec![
+                (peer1, Err(RequestResponseError::NotConnected)),
+                (peer2, Err(RequestResponseError::NotConnected)),
+            ],
+        }
+    );
+    assert!(protocol.fan_outs.is_empty());
+    assert!(protocol.fan_out_requests.is_empty());
+}
+
+#[tokio::test]
+async fn fan_out_request_finishes_on_first_success() {
+    let (mut protocol, mut handle, _manager, _tx) = protocol();
+    let peer1 = PeerId::random();
+    let peer2 = PeerId::random();
+
+    protocol.on_connection_established(peer1).await.unwrap();
+    protocol.on_connection_established(peer2).await.unwrap();
+
+    // register requests to both peers as if they had been sent
+    protocol.fan_outs.insert(
+        RequestId::from(1usize),
+        FanOutContext {
+            mode: FanOutMode::FirstSuccess,
+            pending: [
+                (RequestId::from(2usize), peer1),
+                (RequestId::from(3usize), peer2),
+            ]
+            .into_iter()
+            .collect(),
+            responses: Vec::new(),
+        },
+    );
+    for (peer, request_id) in [(peer1, 2usize), (peer2, 3usize)] {
+        protocol
+            .fan_out_requests
+            .insert(RequestId::from(request_id), RequestId::from(1usize));
+        protocol
+            .peers
+            .get_mut(&peer)
+            .unwrap()
+            .active
+            .insert(RequestId::from(request_id));
+    }
+
+    protocol
+        .on_substream_event(
+            peer1,
+            RequestId::from(2usize),
+            None,
+            Ok(Some(Bytes::from_static(&[1, 3, 3, 7]))),
+        )
+        .await
+        .unwrap();
+
+    // request to `peer2` is canceled once `peer1` responds
+    assert_eq!(
+        handle.next().await.unwrap(),
+        RequestResponseEvent::FanOutFinished {
+            request_id: RequestId::from(1usize),
+            responses: vec![
+                (peer1, Ok(Bytes::from_static(&[1, 3, 3, 7]))),
+                (peer2, Err(RequestResponseError::Canceled)),
+            ],
+        }
+    );
+    assert!(protocol.fan_outs.is_empty());
+    assert!(protocol.fan_out_requests.is_empty());
+}
+
+#[tokio::test]
+async fn outbound_requests_queued_when_peer_at_limit() {
+    let (mut protocol, mut handle, _manager, _tx) = protocol();
+    protocol.max_outbound_requests_per_peer = Some(1);
+
+    let peer = PeerId::random();
+    protocol.on_connection_established(peer).await.unwrap();
+
+    // register request as if it had been sent
+    protocol.peers.get_mut(&peer).unwrap().active.insert(RequestId::from(1usize));
+
+    // peer already has the maximum number of active requests so the request is queued
+    protocol
+        .on_send_request(
+            RequestContext::new(
+                peer,
+                RequestId::from(2usize),
+                Bytes::from_static(&[1, 2, 3, 4]),
+                Vec::new(),
+                Instant::now() + Duration::from_secs(5),
+            ),
+            DialOptions::Reject,
+        )
+        .await
+        .unwrap();
+    assert_eq!(protocol.peers.get(&peer).unwrap().queued.len(), 1);
+
+    // once the first request finishes, the queued request is sent
+    protocol
+        .on_substream_event(
+            peer,
+            RequestId::from(1usize),
+            None,
+            Ok(Some(Bytes::from_static(&[1, 3, 3, 7]))),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        handle.next().await.unwrap(),
+        RequestResponseEvent::ResponseReceived {
+            peer,
+            request_id: RequestId::from(1usize),
+            fallback: None,
+            response: Bytes::from_static(&[1, 3, 3, 7]),
+        }
+    );
+    assert!(protocol.peers.get(&peer).unwrap().queued.is_empty());
+
+    // since the peer wasn't properly registered, opening the substream fails
+    let (failed_peer, request_id, _, result) = protocol.pending_inbound.next().await.unwrap();
+    assert_eq!(failed_peer, peer);
+    assert_eq!(request_id, RequestId::from(2usize));
+    assert_eq!(result, Err(RequestResponseError::Rejected));
+}
+
+#[tokio::test]
+async fn queued_requests_ordered_by_priority() {
+    let (mut protocol, _handle, _manager, _tx) = protocol();
+    protocol.max_outbound_requests_per_peer = Some(1);
+
+    let peer = PeerId::random();
+    protocol.on_connection_established(peer).await.unwrap();
+    protocol.peers.get_mut(&peer).unwrap().active.insert(RequestId::from(1usize));
+
+    for (request_id, priority) in [
+        (2usize, RequestPriority::Low),
+        (3usize, RequestPriority::Normal),
+        (4usize, RequestPriority::High),
+        (5usize, RequestPriority::Normal),
+    ] {
+        protocol
+            .on_send_request(
+                RequestContext {
+                    priority,
+                    ..RequestContext::new(
+                        peer,
+                        RequestId::from(request_id),
+                        Bytes::from_static(&[1, 2, 3, 4]),
+                        Vec::new(),
+                        Instant::now() + Duration::from_secs(5),
+                    )
+                },
+                DialOptions::Reject,
+            )
+            .await
+            .unwrap();
+    }
+
+    // high-priority request is sent first and requests of the same priority retain their order
+    assert_eq!(
+        protocol
+            .peers
+            .get(&peer)
+            .unwrap()
+            .queued
+            .iter()
+            .map(|request| request.request_id)
+            .collect::<Vec<_>>(),
+        vec![
+            RequestId::from(4usize),
+            RequestId::from(3usize),
+            RequestId::from(5usize),
+            RequestId::from(2usize),
+        ],
+    );
+}
+
+#[tokio::test]
+async fn request_failures_recorded_in_metrics() {
+    let (mut protocol, handle, _manager, _tx) = protocol();
+    let peer = PeerId::random();
+
+    // peer is not connected and the request is rejected
+    protocol
+        .on_send_request(
+            RequestContext::new(
+                peer,
+                RequestId::from(1usize),
+                Bytes::from_static(&[1, 2, 3, 4]),
+                Vec::new(),
+                Instant::now() + Duration::from_secs(5),
+            ),
+            DialOptions::Reject,
+        )
+        .await
+        .unwrap();
+
+    // request to connected peer times out
+    protocol.on_connection_established(peer).await.unwrap();
+    protocol.peers.get_mut(&peer).unwrap().active.insert(RequestId::from(2usize));
+    protocol
+        .on_substream_event(
+            peer,
+            RequestId::from(2usize),
+            None,
+            Err(RequestResponseError::Timeout),
+        )
+        .await
+        .unwrap();
+
+    let metrics = handle.metrics();
+    assert_eq!(metrics.protocol, ProtocolName::from("/req/1"));
+    assert_eq!(
+        metrics.failures.get(&RequestResponseError::NotConnected),
+        Some(&1)
+    );
+    assert_eq!(
+        metrics.failures.get(&RequestResponseError::Timeout),
+        Some(&1)
+    );
+    assert_eq!(metrics.round_trip_time.count, 0);
+}
@@ -0,0 +1,385 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Typed request-response API.
+//!
+//! [`TypedRequestResponse`] wraps a [`RequestResponseHandle`] and encodes and decodes requests
+//! and responses using a [`Codec`], allowing the user protocol to work with its own message types
+//! instead of raw bytes.
+
+use crate::{
+    protocol::request_response::{
+        DialOptions, FanOutMode, RequestResponseError, RequestResponseEvent, RequestResponseHandle,
+    },
+    types::{protocol::ProtocolName, RequestId},
+    PeerId,
+};
+
+use bytes::Bytes;
+use futures::{channel, Stream};
+
+use std::{
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::request-response::typed";
+
+/// Codec for encoding and decoding messages of type `T`.
+///
+/// Implement this trait for other serialization formats, such as CBOR through `serde`.
+pub trait Codec<T> {
+    /// Encode `message` into bytes.
+    fn encode(&self, message: &T) -> Bytes;
+
+    /// Decode message from `bytes`.
+    ///
+    /// `bytes` is the payload received from the substream and codecs may retain parts of it in
+    /// the decoded message without copying them.
+    fn decode(&self, bytes: Bytes) -> crate::Result<T>;
+}
+
+/// [`Codec`] for Protocol Buffers messages generated with `prost`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ProstCodec;
+
+impl<T: prost::Message + Default> Codec<T> for ProstCodec {
+    fn encode(&self, message: &T) -> Bytes {
+        message.encode_to_vec().into()
+    }
+
+    fn decode(&self, bytes: Bytes) -> crate::Result<T> {
+        T::decode(bytes).map_err(From::from)
+    }
+}
+
+/// Typed request-response events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypedRequestResponseEvent<Req, Res> {
+    /// Request received from remote.
+    RequestReceived {
+        /// Peer Id.
+        peer: PeerId,
+
+        /// Fallback protocol, if the substream was negotiated using a fallback.
+        fallback: Option<ProtocolName>,
+
+        /// Request ID.
+        request_id: RequestId,
+
+        /// Received request.
+        request: Req,
+    },
+
+    /// Response received.
+    ResponseReceived {
+        /// Peer Id.
+        peer: PeerId,
+
+        /// Request ID.
+        request_id: RequestId,
+
+        /// Fallback protocol, if the substream was negotiated using a fallback.
+        fallback: Option<ProtocolName>,
+
+        /// Received response.
+        response: Res,
+    },
+
+    /// Request failed.
+    RequestFailed {
+        /// Peer Id.
+        peer: PeerId,
+
+        /// Request ID.
+        request_id: RequestId,
+
+        /// Request-response error.
+        error: RequestResponseError,
+    },
+
+    /// Request received from remote couldn't be decoded.
+    ///
+    /// The request has already been rejected.
+    InvalidRequest {
+        /// Peer Id.
+        peer: PeerId,
+
+        /// Request ID.
+        request_id: RequestId,
+    },
+
+    /// Response received from remote couldn't be decoded.
+    InvalidResponse {
+        /// Peer Id.
+        peer: PeerId,
+
+        /// Request ID.
+        request_id: RequestId,
+    },
+
+    /// Request sent to multiple peers has finished.
+    FanOutFinished {
+        /// Request ID.
+        request_id: RequestId,
+
+        /// Decoded responses and failures of the individual peers.
+        responses: Vec<(PeerId, Result<Res, RequestResponseError>)>,
+
+        /// Peers whose responses couldn't be decoded.
+        invalid_responses: Vec<PeerId>,
+    },
+}
+
+/// Request-response handle which sends and receives messages of type `Req` and `Res`.
+///
+/// Outbound requests and responses are encoded and inbound requests and responses are decoded
+/// using `C`. Inbound requests which fail to decode are rejected and reported as
+/// [`TypedRequestResponseEvent::InvalidRequest`], allowing the user protocol to, e.g., adjust the
+/// reputation of the peer.
+pub struct TypedRequestResponse<Req, Res, C = ProstCodec> {
+    /// Request-response handle.
+    handle: RequestResponseHandle,
+
+    /// Codec for requests and responses.
+    codec: C,
+
+    /// Marker for the message types.
+    _marker: PhantomData<fn() -> (Req, Res)>,
+}
+
+impl<Req, Res, C: Codec<Req> + Codec<Res>> TypedRequestResponse<Req, Res, C> {
+    /// Create new [`TypedRequestResponse`].
+    pub fn new(handle: RequestResponseHandle, codec: C) -> Self {
+        Self {
+            handle,
+            codec,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Get mutable access to the underlying [`RequestResponseHandle`].
+    pub fn handle_mut(&mut self) -> &mut RequestResponseHandle {
+        &mut self.handle
+    }
+
+    /// Consume the typed handle and return the underlying [`RequestResponseHandle`].
+    pub fn into_inner(self) -> RequestResponseHandle {
+        self.handle
+    }
+
+    /// Send request to remote peer.
+    ///
+    /// See [`RequestResponseHandle::send_request()`] for more details.
+    pub async fn send_request(
+        &mut self,
+        peer: PeerId,
+        request: &Req,
+        dial_options: DialOptions,
+    ) -> crate::Result<RequestId> {
+        let request = Codec::<Req>::encode(&self.codec, request);
+
+        self.handle.send_request(peer, request, dial_options).await
+    }
+
+    /// Send request to remote peer with a custom `timeout`.
+    ///
+    /// See [`RequestResponseHandle::send_request_with_timeout()`] for more details.
+    pub async fn send_request_with_timeout(
+        &mut self,
+        peer: PeerId,
+        request: &Req,
+        dial_options: DialOptions,
+        timeout: Duration,
+    ) -> crate::Result<RequestId> {
+        let request = Codec::<Req>::encode(&self.codec, request);
+
+        self.handle
+            .send_request_with_timeout(peer, request, dial_options, timeout)
+            .await
+    }
+
+    /// Send the same request to multiple peers concurrently.
+    ///
+    /// See [`RequestResponseHandle::send_request_to_peers()`] for more details.
+    pub async fn send_request_to_peers(
+        &mut self,
+        peers: impl IntoIterator<Item = PeerId>,
+        request: &Req,
+        dial_options: DialOptions,
+        mode: FanOutMode,
+    ) -> crate::Result<RequestId> {
+        let request = Codec::<Req>::encode(&self.codec, request);
+
+        self.handle.send_request_to_peers(peers, request, dial_options, mode).await
+    }
+
+    /// Attempt to send request to peer and if the channel is clogged, return
+    /// `Error::ChannelClogged`.
+    pub fn try_send_request(
+        &mut self,
+        peer: PeerId,
+        request: &Req,
+        dial_options: DialOptions,
+    ) -> crate::Result<RequestId> {
+        let request = Codec::<Req>::encode(&self.codec, request);
+
+        self.handle.try_send_request(peer, request, dial_options)
+    }
+
+    /// Send response to remote peer.
+    pub fn send_response(&mut self, request_id: RequestId, response: &Res) {
+        let response = Codec::<Res>::encode(&self.codec, response);
+
+        self.handle.send_response(request_id, response)
+    }
+
+    /// Send response to remote peer with feedback.
+    ///
+    /// See [`RequestResponseHandle::send_response_with_feedback()`] for more details.
+    pub fn send_response_with_feedback(
+        &mut self,
+        request_id: RequestId,
+        response: &Res,
+        feedback: channel::oneshot::Sender<()>,
+    ) {
+        let response = Codec::<Res>::encode(&self.codec, response);
+
+        self.handle.send_response_with_feedback(request_id, response, feedback)
+    }
+
+    /// Reject an inbound request.
+    pub fn reject_request(&mut self, request_id: RequestId) {
+        self.handle.reject_request(request_id)
+    }
+
+    /// Cancel an outbound request.
+    pub async fn cancel_request(&mut self, request_id: RequestId) {
+        self.handle.cancel_request(request_id).await
+    }
+
+    /// Decode `event` received from the request-response handle.
+    fn on_event(&mut self, event: RequestResponseEvent) -> TypedRequestResponseEvent<Req, Res> {
+        match event {
+            RequestResponseEvent::RequestReceived {
+                peer,
+                fallback,
+                request_id,
+                request,
+            } => match Codec::<Req>::decode(&self.codec, request) {
+                Ok(request) => TypedRequestResponseEvent::RequestReceived {
+                    peer,
+                    fallback,
+                    request_id,
+                    request,
+                },
+                Err(error) => {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        ?request_id,
+                        ?error,
+                        "failed to decode request, rejecting",
+                    );
+
+                    self.handle.reject_request(request_id);
+                    TypedRequestResponseEvent::InvalidRequest { peer, request_id }
+                }
+            },
+            RequestResponseEvent::ResponseReceived {
+                peer,
+                request_id,
+                fallback,
+                response,
+            } => match Codec::<Res>::decode(&self.codec, response) {
+                Ok(response) => TypedRequestResponseEvent::ResponseReceived {
+                    peer,
+                    request_id,
+                    fallback,
+                    response,
+                },
+                Err(error) => {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        ?request_id,
+                        ?error,
+                        "failed to decode response",
+                    );
+
+                    TypedRequestResponseEvent::InvalidResponse { peer, request_id }
+                }
+            },
+            RequestResponseEvent::RequestFailed {
+                peer,
+                request_id,
+                error,
+            } => TypedRequestResponseEvent::RequestFailed {
+                peer,
+                request_id,
+                error,
+            },
+            RequestResponseEvent::FanOutFinished {
+                request_id,
+                responses,
+            } => {
+                let mut decoded = Vec::with_capacity(responses.len());
+                let mut invalid_responses = Vec::new();
+
+                for (peer, response) in responses {
+                    match response.map(|response| Codec::<Res>::decode(&self.codec, response)) {
+                        Ok(Ok(response)) => decoded.push((peer, Ok(response))),
+                        Ok(Err(error)) => {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?peer,
+                                ?request_id,
+                                ?error,
+                                "failed to decode response",
+                            );
+
+                            invalid_responses.push(peer);
+                        }
+                        Err(error) => decoded.push((peer, Err(error))),
+                    }
+                }
+
+                TypedRequestResponseEvent::FanOutFinished {
+                    request_id,
+                    responses: decoded,
+                    invalid_responses,
+                }
+            }
+        }
+    }
+}
+
+impl<Req, Res, C: Codec<Req> + Codec<Res> + Unpin> Stream for TypedRequestResponse<Req, Res, C> {
+    type Item = TypedRequestResponseEvent<Req, Res>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let event = futures::ready!(Pin::new(&mut self.handle).poll_next(cx));
+
+        Poll::Ready(event.map(|event| self.on_event(event)))
+    }
+}
@@ -19,20 +19,32 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
-    error::Error,
-    protocol::{connection::ConnectionHandle, InnerTransportEvent, TransportEvent},
-    transport::{manager::TransportManagerHandle, Endpoint},
+    error::{Error, NegotiationError},
+    external_addresses::{ExternalAddresses, PublicAddress},
+    multistream_select::NegotiationError::Failed as MultistreamFailed,
+    peer_store::PeerStore,
+    protocol::{
+        connection::ConnectionHandle, Direction, InnerTransportEvent, ProtocolHandover,
+        TransportEvent,
+    },
+    reputation::Reputation,
+    transport::{
+        manager::{is_preferred_connection, SupportedTransport, TransportManagerHandle},
+        Endpoint,
+    },
     types::{protocol::ProtocolName, ConnectionId, SubstreamId},
-    PeerId, DEFAULT_CHANNEL_SIZE,
+    PeerId,
 };
 
 use futures::{future::BoxFuture, stream::FuturesUnordered, Stream, StreamExt};
 use multiaddr::{Multiaddr, Protocol};
-use multihash::Multihash;
-use tokio::sync::mpsc::{channel, Receiver, Sender};
+use tokio::sync::{
+    mpsc::{channel, Receiver, Sender},
+    oneshot,
+};
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashMap,
     fmt::Debug,
     pin::Pin,
     sync::{
@@ -46,11 +58,36 @@ use std::{
 /// Logging target for the file.
 const LOG_TARGET: &str = "litep2p::transport-service";
 
+/// Default timeout for opening an outbound substream.
+///
+/// Longer than the timeout transports apply to substream negotiation so that it only fires if the
+/// connection never answers.
+const SUBSTREAM_OPEN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Preference for the connection used to open outbound substreams when more than one connection
+/// is open to the peer.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionPreference {
+    /// Use any open connection, preferring the connection that was established first.
+    #[default]
+    Any,
+
+    /// Use only direct connections, i.e., connections that are not relayed.
+    Direct,
+
+    /// Use the connection with the lowest measured round-trip time.
+    ///
+    /// Round-trip time is currently measured only for QUIC connections and connections for which
+    /// it's not known are used only if no measured connection is open.
+    LowestLatency,
+}
+
 /// Connection context for the peer.
 ///
 /// Each peer is allowed to have at most two connections open. The first open connection is the
-/// primary connections which the local node uses to open substreams to remote. Secondary connection
-/// may be open if local and remote opened connections at the same time.
+/// primary connections which the local node uses to open substreams to remote, unless the protocol
+/// has configured a different [`ConnectionPreference`]. Secondary connection may be open if local
+/// and remote opened connections at the same time.
 ///
 /// Secondary connection may be promoted to a primary connection if the primary connections closes
 /// while the secondary connections remains open.
@@ -61,17 +98,49 @@ struct ConnectionContext {
 
     /// Secondary connection, if it exists.
     secondary: Option<ConnectionHandle>,
+
+    /// Endpoints of the open connections.
+    endpoints: HashMap<ConnectionId, Endpoint>,
 }
 
 impl ConnectionContext {
     /// Create new [`ConnectionContext`].
-    fn new(primary: ConnectionHandle) -> Self {
+    fn new(primary: ConnectionHandle, endpoint: Endpoint) -> Self {
         Self {
+            endpoints: HashMap::from_iter([(*primary.connection_id(), endpoint)]),
             primary,
             secondary: None,
         }
     }
 
+    /// Select the connection used to open a substream based on `preference`.
+    fn select(
+        &mut self,
+        preference: ConnectionPreference,
+        transport_handle: &TransportManagerHandle,
+    ) -> Option<&mut ConnectionHandle> {
+        let endpoints = &self.endpoints;
+        let mut connections = std::iter::once(&mut self.primary).chain(self.secondary.as_mut());
+
+        match preference {
+            ConnectionPreference::Any => connections.next(),
+            ConnectionPreference::Direct =>
+                connections.find(
+                    |connection| match endpoints.get(connection.connection_id()) {
+                        Some(endpoint) => !endpoint
+                            .address()
+                            .iter()
+                            .any(|protocol| std::matches!(protocol, Protocol::P2pCircuit)),
+                        None => false,
+                    },
+                ),
+            ConnectionPreference::LowestLatency => connections.min_by_key(|connection| {
+                let rtt = transport_handle.connection_rtt(connection.connection_id());
+                (rtt.is_none(), rtt)
+            }),
+        }
+    }
+
     /// Downgrade connection to non-active which means it will be closed
     /// if there are no substreams open over it.
     fn downgrade(&mut self, connection_id: &ConnectionId) {
@@ -110,6 +179,9 @@ pub struct TransportService {
     /// Fallback names for the protocol.
     fallback_names: Vec<ProtocolName>,
 
+    /// Preference for the connection used to open outbound substreams.
+    connection_preference: ConnectionPreference,
+
     /// Open connections.
     connections: HashMap<PeerId, ConnectionContext>,
 
@@ -124,10 +196,26 @@ pub struct TransportService {
 
     /// Pending keep-alive timeouts.
     keep_alive_timeouts: FuturesUnordered<BoxFuture<'static, (PeerId, ConnectionId)>>,
+
+    /// Timeout for opening an outbound substream.
+    substream_open_timeout: Duration,
+
+    /// Outbound substreams which are being opened.
+    pending_substreams: HashMap<SubstreamId, PeerId>,
+
+    /// Outbound substreams which timed out before the connection reported the result.
+    timed_out_substreams: HashMap<SubstreamId, PeerId>,
+
+    /// Pending substream open timeouts.
+    substream_open_timeouts: FuturesUnordered<BoxFuture<'static, SubstreamId>>,
+
+    /// Sender which is dropped together with the service, notifying its receiver.
+    exit_tx: Option<oneshot::Sender<()>>,
 }
 
 impl TransportService {
     /// Create new [`TransportService`].
+    #[cfg(test)]
     pub(crate) fn new(
         local_peer_id: PeerId,
         protocol: ProtocolName,
@@ -135,7 +223,27 @@ impl TransportService {
         next_substream_id: Arc<AtomicUsize>,
         transport_handle: TransportManagerHandle,
     ) -> (Self, Sender<InnerTransportEvent>) {
-        let (tx, rx) = channel(DEFAULT_CHANNEL_SIZE);
+        Self::with_channel_size(
+            local_peer_id,
+            protocol,
+            fallback_names,
+            next_substream_id,
+            transport_handle,
+            crate::DEFAULT_CHANNEL_SIZE,
+        )
+    }
+
+    /// Create new [`TransportService`] which receives events from connections over a channel of
+    /// size `channel_size`.
+    pub(crate) fn with_channel_size(
+        local_peer_id: PeerId,
+        protocol: ProtocolName,
+        fallback_names: Vec<ProtocolName>,
+        next_substream_id: Arc<AtomicUsize>,
+        transport_handle: TransportManagerHandle,
+        channel_size: usize,
+    ) -> (Self, Sender<InnerTransportEvent>) {
+        let (tx, rx) = channel(channel_size);
 
         (
             Self {
@@ -145,26 +253,43 @@ impl TransportService {
                 fallback_names,
                 transport_handle,
                 next_substream_id,
+                connection_preference: ConnectionPreference::Any,
                 connections: HashMap::new(),
                 keep_alive_timeouts: FuturesUnordered::new(),
+                substream_open_timeout: SUBSTREAM_OPEN_TIMEOUT,
+                pending_substreams: HashMap::new(),
+                timed_out_substreams: HashMap::new(),
+                substream_open_timeouts: FuturesUnordered::new(),
+                exit_tx: None,
             },
             tx,
         )
     }
 
+    /// Get a receiver which is notified once the [`TransportService`] has been dropped, i.e.,
+    /// once the event loop of the protocol has exited.
+    pub(crate) fn exit_notification(&mut self) -> oneshot::Receiver<()> {
+        let (tx, rx) = oneshot::channel();
+        self.exit_tx = Some(tx);
+
+        rx
+    }
+
     /// Handle connection established event.
     fn on_connection_established(
         &mut self,
         peer: PeerId,
         endpoint: Endpoint,
+        transport: SupportedTransport,
         connection_id: ConnectionId,
-        handle: ConnectionHandle,
+        mut handle: ConnectionHandle,
     ) -> Option<TransportEvent> {
         tracing::debug!(
             target: LOG_TARGET,
             ?peer,
             protocol = %self.protocol,
             ?endpoint,
+            ?transport,
             ?connection_id,
             "connection established",
         );
@@ -186,19 +311,49 @@ impl TransportService {
                         tokio::time::sleep(Duration::from_secs(5)).await;
                         (peer, connection_id)
                     }));
+
+                    // if the connections were opened in opposite directions, both nodes use the
+                    // preferred connection for new substreams and the other connection is closed
+                    // after its keep-alive timeout expires and its substreams have been closed
+                    let simultaneous = context
+                        .endpoints
+                        .get(context.primary.connection_id())
+                        .is_some_and(|primary| primary.is_listener() != endpoint.is_listener());
+
+                    context.endpoints.insert(connection_id, endpoint.clone());
+
+                    if simultaneous {
+                        if is_preferred_connection(&self.local_peer_id, &peer, &endpoint) {
+                            handle = std::mem::replace(&mut context.primary, handle);
+                        }
+
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            ?peer,
+                            protocol = %self.protocol,
+                            primary = ?context.primary.connection_id(),
+                            secondary = ?handle.connection_id(),
+                            "simultaneous connections, use preferred connection as primary",
+                        );
+                    }
                     context.secondary = Some(handle);
 
                     None
                 }
             },
             None => {
-                self.connections.insert(peer, ConnectionContext::new(handle));
+                self.connections.insert(peer, ConnectionContext::new(handle, endpoint.clone()));
                 self.keep_alive_timeouts.push(Box::pin(async move {
                     tokio::time::sleep(Duration::from_secs(5)).await;
                     (peer, connection_id)
                 }));
 
-                Some(TransportEvent::ConnectionEstablished { peer, endpoint })
+                Some(TransportEvent::ConnectionEstablished {
+                    peer,
+                    connection_id,
+                    endpoint,
+                    transport,
+                })
             }
         }
     }
@@ -220,6 +375,7 @@ impl TransportService {
             debug_assert!(false);
             return None;
         };
+        context.endpoints.remove(&connection_id);
 
         // if the primary connection was closed, check if there exist a secondary connection
         // and if it does, convert the secondary connection a primary connection
@@ -229,7 +385,11 @@ impl TransportService {
             match context.secondary.take() {
                 None => {
                     self.connections.remove(&peer);
-                    return Some(TransportEvent::ConnectionClosed { peer });
+                    self.timed_out_substreams.retain(|_, pending| pending != &peer);
+                    return Some(TransportEvent::ConnectionClosed {
+                        peer,
+                        connection_id,
+                    });
                 }
                 Some(handle) => {
                     tracing::debug!(
@@ -272,9 +432,11 @@ impl TransportService {
 
     /// Dial `peer` using `PeerId`.
     ///
-    /// Call fails if `Litep2p` doesn't have a known address for the peer.
+    /// Call fails if `Litep2p` doesn't have a known address for the peer. If the dial fails
+    /// after the call has returned, the outcome is reported to the protocol as
+    /// [`TransportEvent::DialFailure`].
     pub fn dial(&mut self, peer: &PeerId) -> crate::Result<()> {
-        self.transport_handle.dial(peer)
+        self.transport_handle.dial(peer, self.protocol.clone())
     }
 
     /// Dial peer using a `Multiaddr`.
@@ -286,34 +448,61 @@ impl TransportService {
     /// since `Litep2p` internally keeps track of all peer addresses it has learned through user
     /// calling this function, Kademlia peer discoveries and `Identify` responses.
     pub fn dial_address(&mut self, address: Multiaddr) -> crate::Result<()> {
-        self.transport_handle.dial_address(address)
+        self.transport_handle.dial_address(address, self.protocol.clone())
     }
 
     /// Add one or more addresses for `peer`.
     ///
     /// The list is filtered for duplicates and unsupported transports.
     pub fn add_known_address(&mut self, peer: &PeerId, addresses: impl Iterator<Item = Multiaddr>) {
-        let addresses: HashSet<Multiaddr> = addresses
-            .filter_map(|address| {
-                if !std::matches!(address.iter().last(), Some(Protocol::P2p(_))) {
-                    Some(address.with(Protocol::P2p(Multihash::from_bytes(&peer.to_bytes()).ok()?)))
-                } else {
-                    Some(address)
-                }
-            })
-            .collect();
+        self.transport_handle.add_known_address(peer, addresses);
+    }
+
+    /// Report the result of probing the connection to `peer` to the transport manager.
+    ///
+    /// See [`crate::config::ConfigBuilder::with_connection_probing()`] for more details.
+    pub(crate) fn report_connection_probe(&mut self, peer: PeerId, success: bool) {
+        if let Err(error) = self.transport_handle.report_connection_probe(peer, success) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?peer,
+                ?error,
+                "failed to report connection probe result",
+            );
+        }
+    }
+
+    /// Set the preference for the connection used to open outbound substreams.
+    pub fn set_connection_preference(&mut self, preference: ConnectionPreference) {
+        self.connection_preference = preference;
+    }
 
-        self.transport_handle.add_known_address(peer, addresses.into_iter());
+    /// Set the timeout for opening outbound substreams.
+    ///
+    /// If the connection hasn't reported the result of opening a substream within `timeout`,
+    /// [`TransportEvent::SubstreamOpenFailure`] is emitted with [`Error::Timeout`] and the
+    /// substream is dropped if it's opened later.
+    ///
+    /// By default the timeout is 10 seconds.
+    pub fn set_substream_open_timeout(&mut self, timeout: Duration) {
+        self.substream_open_timeout = timeout;
     }
 
     /// Open substream to `peer`.
     ///
-    /// Call fails if there is no connection open to `peer` or the channel towards
-    /// the connection is clogged.
+    /// The connection is selected based on the [`ConnectionPreference`] of the protocol.
+    ///
+    /// Call fails if there is no connection open to `peer` that matches the preference or the
+    /// channel towards the connection is clogged. If the substream is not opened within the
+    /// timeout, see [`TransportService::set_substream_open_timeout()`], the failure is reported
+    /// as [`TransportEvent::SubstreamOpenFailure`].
     pub fn open_substream(&mut self, peer: PeerId) -> crate::Result<SubstreamId> {
-        // always prefer the primary connection
-        let connection =
-            &mut self.connections.get_mut(&peer).ok_or(Error::PeerDoesntExist(peer))?.primary;
+        let connection = self
+            .connections
+            .get_mut(&peer)
+            .ok_or(Error::PeerDoesntExist(peer))?
+            .select(self.connection_preference, &self.transport_handle)
+            .ok_or(Error::NoMatchingConnection(peer))?;
 
         let permit = connection.try_get_permit().ok_or(Error::ConnectionClosed)?;
         let substream_id =
@@ -327,14 +516,167 @@ impl TransportService {
             "open substream",
         );
 
-        connection
-            .open_substream(
-                self.protocol.clone(),
-                self.fallback_names.clone(),
-                substream_id,
-                permit,
-            )
-            .map(|_| substream_id)
+        connection.open_substream(
+            self.protocol.clone(),
+            self.fallback_names.clone(),
+            substream_id,
+            permit,
+        )?;
+
+        let timeout = self.substream_open_timeout;
+        self.pending_substreams.insert(substream_id, peer);
+        self.substream_open_timeouts.push(Box::pin(async move {
+            tokio::time::sleep(timeout).await;
+            substream_id
+        }));
+
+        Ok(substream_id)
+    }
+
+    /// Check if the result of opening outbound substream `substream_id` should be reported.
+    ///
+    /// Results of substreams which have already timed out are ignored.
+    fn on_substream_result(&mut self, substream_id: &SubstreamId) -> bool {
+        self.pending_substreams.remove(substream_id);
+
+        if self.timed_out_substreams.remove(substream_id).is_some() {
+            tracing::debug!(
+                target: LOG_TARGET,
+                protocol = %self.protocol,
+                ?substream_id,
+                "ignoring result of a substream that timed out",
+            );
+            return false;
+        }
+
+        true
+    }
+
+    /// Record the outcome of a substream negotiation in the peer store.
+    fn on_negotiation_result(&self, event: &InnerTransportEvent) {
+        match event {
+            InnerTransportEvent::SubstreamOpened {
+                peer,
+                protocol,
+                fallback,
+                ..
+            } => self
+                .peer_store()
+                .on_protocol_negotiated(*peer, fallback.as_ref().unwrap_or(protocol).clone()),
+            InnerTransportEvent::SubstreamOpenFailure {
+                substream,
+                error:
+                    Error::NegotiationError(NegotiationError::MultistreamSelectError(MultistreamFailed)),
+            } => {
+                let Some(peer) = self
+                    .pending_substreams
+                    .get(substream)
+                    .or_else(|| self.timed_out_substreams.get(substream))
+                else {
+                    return;
+                };
+
+                self.peer_store().on_protocols_rejected(
+                    *peer,
+                    std::iter::once(self.protocol.clone())
+                        .chain(self.fallback_names.iter().cloned()),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    /// Check if `peer` supports the protocol or one of its fallback names.
+    ///
+    /// Support is learned from the protocols `peer` reported over `/ipfs/id/1.0.0` and from the
+    /// substreams negotiated with `peer` by any of the installed protocols. Returns `None` if it's
+    /// not known whether `peer` supports the protocol.
+    pub fn peer_supports_protocol(&self, peer: &PeerId) -> Option<bool> {
+        let mut unknown = false;
+
+        for protocol in std::iter::once(&self.protocol).chain(self.fallback_names.iter()) {
+            match self.peer_store().supports_protocol(peer, protocol) {
+                Some(true) => return Some(true),
+                Some(false) => {}
+                None => unknown = true,
+            }
+        }
+
+        (!unknown).then_some(false)
+    }
+
+    /// Get name of the protocol.
+    pub fn protocol(&self) -> &ProtocolName {
+        &self.protocol
+    }
+
+    /// Get peer store shared by all protocols.
+    pub fn peer_store(&self) -> &PeerStore {
+        self.transport_handle.peer_store()
+    }
+
+    /// Get external addresses of the local node.
+    pub(crate) fn external_addresses(&self) -> &ExternalAddresses {
+        self.transport_handle.external_addresses()
+    }
+
+    /// Get the addresses through which the local node believes it's reachable.
+    ///
+    /// See [`crate::Litep2p::public_addresses()`] for more details.
+    pub fn public_addresses(&self) -> Vec<PublicAddress> {
+        self.transport_handle.public_addresses()
+    }
+
+    /// Get reputations of remote peers, shared by all protocols.
+    ///
+    /// Protocols report the behavior of remote peers using [`Reputation::report()`].
+    pub fn reputation(&self) -> &Reputation {
+        self.transport_handle.reputation()
+    }
+
+    /// Get iterator over the peers the protocol is connected to.
+    pub fn connected_peers(&self) -> impl Iterator<Item = &PeerId> {
+        self.connections.keys()
+    }
+
+    /// Convert the service into a [`ProtocolHandover`] so the protocol task can be restarted
+    /// without closing its connections.
+    ///
+    /// This API is experimental. See [`ProtocolHandover`] for more details.
+    pub fn into_handover(self) -> ProtocolHandover {
+        tracing::debug!(
+            target: LOG_TARGET,
+            protocol = %self.protocol,
+            num_peers = ?self.connections.len(),
+            "hand over protocol",
+        );
+
+        ProtocolHandover::new(self)
+    }
+
+    /// Close all connections to `peer` and notify every installed protocol.
+    ///
+    /// Unlike [`TransportService::force_close()`], this also closes the connections this
+    /// protocol doesn't track.
+    pub fn disconnect(&mut self, peer: PeerId) -> crate::Result<()> {
+        tracing::debug!(target: LOG_TARGET, ?peer, protocol = %self.protocol, "disconnect peer");
+
+        self.transport_handle.disconnect(peer)
+    }
+
+    /// Ban `peer` for `duration`.
+    ///
+    /// See [`Litep2p::ban_peer()`](crate::Litep2p::ban_peer) for more details.
+    pub fn ban_peer(&mut self, peer: PeerId, duration: Duration) -> crate::Result<()> {
+        tracing::debug!(
+            target: LOG_TARGET,
+            ?peer,
+            protocol = %self.protocol,
+            ?duration,
+            "ban peer",
+        );
+
+        self.transport_handle.ban_peer(peer, duration)
     }
 
     /// Forcibly close the connection, even if other protocols have substreams open over it.
@@ -363,16 +705,21 @@ impl Stream for TransportService {
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         while let Poll::Ready(event) = self.rx.poll_recv(cx) {
+            if let Some(ref event) = event {
+                self.on_negotiation_result(event);
+            }
+
             match event {
                 None => return Poll::Ready(None),
                 Some(InnerTransportEvent::ConnectionEstablished {
                     peer,
                     endpoint,
+                    transport,
                     sender,
                     connection,
                 }) => {
-                    if let Some(event) =
-                        self.on_connection_established(peer, endpoint, connection, sender)
+                    if let Some(event) = self
+                        .on_connection_established(peer, endpoint, transport, connection, sender)
                     {
                         return Poll::Ready(Some(event));
                     }
@@ -382,10 +729,37 @@ impl Stream for TransportService {
                         return Poll::Ready(Some(event));
                     }
                 }
+                Some(InnerTransportEvent::SubstreamOpened {
+                    direction: Direction::Outbound(substream_id),
+                    ..
+                })
+                | Some(InnerTransportEvent::SubstreamOpenFailure {
+                    substream: substream_id,
+                    ..
+                }) if !self.on_substream_result(&substream_id) => {}
                 Some(event) => return Poll::Ready(Some(event.into())),
             }
         }
 
+        while let Poll::Ready(Some(substream_id)) = self.substream_open_timeouts.poll_next_unpin(cx)
+        {
+            if let Some(peer) = self.pending_substreams.remove(&substream_id) {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    protocol = %self.protocol,
+                    ?substream_id,
+                    "failed to open substream in time",
+                );
+
+                self.timed_out_substreams.insert(substream_id, peer);
+                return Poll::Ready(Some(TransportEvent::SubstreamOpenFailure {
+                    substream: substream_id,
+                    error: Error::Timeout,
+                }));
+            }
+        }
+
         while let Poll::Ready(Some((peer, connection_id))) =
             self.keep_alive_timeouts.poll_next_unpin(cx)
         {
@@ -409,10 +783,12 @@ impl Stream for TransportService {
 mod tests {
     use super::*;
     use crate::{
-        protocol::TransportService,
+        protocol::{ProtocolCommand, TransportService},
+        substream::Substream,
         transport::manager::{handle::InnerTransportManagerCommand, TransportManagerHandle},
     };
     use futures::StreamExt;
+    use multihash::Multihash;
     use parking_lot::RwLock;
     use std::collections::HashSet;
 
@@ -431,6 +807,7 @@ mod tests {
             cmd_tx,
             HashSet::new(),
             Default::default(),
+            Default::default(),
         );
 
         let (service, sender) = TransportService::new(
@@ -456,6 +833,7 @@ mod tests {
                 peer,
                 connection: ConnectionId::from(0usize),
                 endpoint: Endpoint::listener(Multiaddr::empty(), ConnectionId::from(0usize)),
+                transport: SupportedTransport::Tcp,
                 sender: ConnectionHandle::new(ConnectionId::from(0usize), cmd_tx1),
             })
             .await
@@ -463,11 +841,15 @@ mod tests {
 
         if let Some(TransportEvent::ConnectionEstablished {
             peer: connected_peer,
+            connection_id,
             endpoint,
+            transport,
         }) = service.next().await
         {
             assert_eq!(connected_peer, peer);
             assert_eq!(endpoint.address(), &Multiaddr::empty());
+            assert_eq!(connection_id, ConnectionId::from(0usize));
+            assert_eq!(transport, SupportedTransport::Tcp);
         } else {
             panic!("expected event from `TransportService`");
         };
@@ -479,6 +861,7 @@ mod tests {
                 peer,
                 connection: ConnectionId::from(1usize),
                 endpoint: Endpoint::listener(Multiaddr::empty(), ConnectionId::from(1usize)),
+                transport: SupportedTransport::Tcp,
                 sender: ConnectionHandle::new(ConnectionId::from(1usize), cmd_tx2),
             })
             .await
@@ -498,6 +881,63 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn simultaneous_connections_resolved() {
+        let (mut service, sender, _) = transport_service();
+        let peer = PeerId::random();
+
+        // register inbound connection
+        let (cmd_tx1, _cmd_rx1) = channel(64);
+        sender
+            .send(InnerTransportEvent::ConnectionEstablished {
+                peer,
+                connection: ConnectionId::from(0usize),
+                endpoint: Endpoint::listener(Multiaddr::empty(), ConnectionId::from(0usize)),
+                transport: SupportedTransport::Tcp,
+                sender: ConnectionHandle::new(ConnectionId::from(0usize), cmd_tx1),
+            })
+            .await
+            .unwrap();
+
+        let Some(TransportEvent::ConnectionEstablished { .. }) = service.next().await else {
+            panic!("expected event from `TransportService`");
+        };
+
+        // register outbound connection
+        let (cmd_tx2, _cmd_rx2) = channel(64);
+        sender
+            .send(InnerTransportEvent::ConnectionEstablished {
+                peer,
+                connection: ConnectionId::from(1usize),
+                endpoint: Endpoint::dialer(Multiaddr::empty(), ConnectionId::from(1usize)),
+                transport: SupportedTransport::Tcp,
+                sender: ConnectionHandle::new(ConnectionId::from(1usize), cmd_tx2),
+            })
+            .await
+            .unwrap();
+
+        futures::future::poll_fn(|cx| match service.poll_next_unpin(cx) {
+            std::task::Poll::Ready(_) => panic!("didn't expect event from `TransportService`"),
+            std::task::Poll::Pending => std::task::Poll::Ready(()),
+        })
+        .await;
+
+        // connection dialed by the peer with the smaller `PeerId` is used as the primary
+        // connection and the other connection is kept as the secondary connection
+        let (primary, secondary) = if service.local_peer_id < peer {
+            (ConnectionId::from(1usize), ConnectionId::from(0usize))
+        } else {
+            (ConnectionId::from(0usize), ConnectionId::from(1usize))
+        };
+
+        let context = service.connections.get(&peer).unwrap();
+        assert_eq!(context.primary.connection_id(), &primary);
+        assert_eq!(
+            context.secondary.as_ref().unwrap().connection_id(),
+            &secondary
+        );
+    }
+
     #[tokio::test]
     async fn tertiary_connection_ignored() {
         let (mut service, sender, _) = transport_service();
@@ -510,6 +950,7 @@ mod tests {
                 peer,
                 connection: ConnectionId::from(0usize),
                 endpoint: Endpoint::dialer(Multiaddr::empty(), ConnectionId::from(0usize)),
+                transport: SupportedTransport::Tcp,
                 sender: ConnectionHandle::new(ConnectionId::from(0usize), cmd_tx1),
             })
             .await
@@ -518,6 +959,7 @@ mod tests {
         if let Some(TransportEvent::ConnectionEstablished {
             peer: connected_peer,
             endpoint,
+            ..
         }) = service.next().await
         {
             assert_eq!(connected_peer, peer);
@@ -533,6 +975,7 @@ mod tests {
                 peer,
                 connection: ConnectionId::from(1usize),
                 endpoint: Endpoint::dialer(Multiaddr::empty(), ConnectionId::from(1usize)),
+                transport: SupportedTransport::Tcp,
                 sender: ConnectionHandle::new(ConnectionId::from(1usize), cmd_tx2),
             })
             .await
@@ -558,6 +1001,7 @@ mod tests {
                 peer,
                 connection: ConnectionId::from(2usize),
                 endpoint: Endpoint::listener(Multiaddr::empty(), ConnectionId::from(2usize)),
+                transport: SupportedTransport::Tcp,
                 sender: ConnectionHandle::new(ConnectionId::from(2usize), cmd_tx3),
             })
             .await
@@ -590,6 +1034,7 @@ mod tests {
                 peer,
                 connection: ConnectionId::from(0usize),
                 endpoint: Endpoint::dialer(Multiaddr::empty(), ConnectionId::from(0usize)),
+                transport: SupportedTransport::Tcp,
                 sender: ConnectionHandle::new(ConnectionId::from(0usize), cmd_tx1),
             })
             .await
@@ -598,6 +1043,7 @@ mod tests {
         if let Some(TransportEvent::ConnectionEstablished {
             peer: connected_peer,
             endpoint,
+            ..
         }) = service.next().await
         {
             assert_eq!(connected_peer, peer);
@@ -613,6 +1059,7 @@ mod tests {
                 peer,
                 connection: ConnectionId::from(1usize),
                 endpoint: Endpoint::dialer(Multiaddr::empty(), ConnectionId::from(1usize)),
+                transport: SupportedTransport::Tcp,
                 sender: ConnectionHandle::new(ConnectionId::from(1usize), cmd_tx2),
             })
             .await
@@ -664,7 +1111,8 @@ mod tests {
             .send(InnerTransportEvent::ConnectionEstablished {
                 peer,
                 connection: ConnectionId::from(0usize),
-                endpoint: Endpoint::dialer(Multiaddr::empty(), ConnectionId::from(0usize)),
+                endpoint: Endpoint::listener(Multiaddr::empty(), ConnectionId::from(0usize)),
+                transport: SupportedTransport::Tcp,
                 sender: ConnectionHandle::new(ConnectionId::from(0usize), cmd_tx1),
             })
             .await
@@ -673,6 +1121,7 @@ mod tests {
         if let Some(TransportEvent::ConnectionEstablished {
             peer: connected_peer,
             endpoint,
+            ..
         }) = service.next().await
         {
             assert_eq!(connected_peer, peer);
@@ -688,6 +1137,7 @@ mod tests {
                 peer,
                 connection: ConnectionId::from(1usize),
                 endpoint: Endpoint::listener(Multiaddr::empty(), ConnectionId::from(1usize)),
+                transport: SupportedTransport::Tcp,
                 sender: ConnectionHandle::new(ConnectionId::from(1usize), cmd_tx2),
             })
             .await
@@ -739,6 +1189,7 @@ mod tests {
 
         if let Some(TransportEvent::ConnectionClosed {
             peer: disconnected_peer,
+            ..
         }) = service.next().await
         {
             assert_eq!(disconnected_peer, peer);
@@ -763,6 +1214,7 @@ mod tests {
                 peer,
                 connection: ConnectionId::from(1337usize),
                 endpoint: Endpoint::dialer(Multiaddr::empty(), ConnectionId::from(1337usize)),
+                transport: SupportedTransport::Tcp,
                 sender: ConnectionHandle::new(ConnectionId::from(1337usize), cmd_tx1),
             })
             .await
@@ -771,6 +1223,7 @@ mod tests {
         if let Some(TransportEvent::ConnectionEstablished {
             peer: connected_peer,
             endpoint,
+            ..
         }) = service.next().await
         {
             assert_eq!(connected_peer, peer);
@@ -804,9 +1257,11 @@ mod tests {
         // verify that the protocols are notified of the connection closing as well
         if let Some(TransportEvent::ConnectionClosed {
             peer: connected_peer,
+            connection_id,
         }) = service.next().await
         {
             assert_eq!(connected_peer, peer);
+            assert_eq!(connection_id, ConnectionId::from(1337usize));
         } else {
             panic!("expected event from `TransportService`");
         }
@@ -826,6 +1281,7 @@ mod tests {
                 peer,
                 connection: ConnectionId::from(1338usize),
                 endpoint: Endpoint::listener(Multiaddr::empty(), ConnectionId::from(1338usize)),
+                transport: SupportedTransport::Tcp,
                 sender: ConnectionHandle::new(ConnectionId::from(1338usize), cmd_tx1),
             })
             .await
@@ -834,6 +1290,7 @@ mod tests {
         if let Some(TransportEvent::ConnectionEstablished {
             peer: connected_peer,
             endpoint,
+            ..
         }) = service.next().await
         {
             assert_eq!(connected_peer, peer);
@@ -860,4 +1317,295 @@ mod tests {
             Err(_) => {}
         }
     }
+
+    /// Register connections to `peer` over `addresses`, the first address being the primary
+    /// connection, and return the command receivers of the connections.
+    async fn register_connections(
+        service: &mut TransportService,
+        sender: &Sender<InnerTransportEvent>,
+        peer: PeerId,
+        addresses: Vec<Multiaddr>,
+    ) -> Vec<Receiver<ProtocolCommand>> {
+        let mut receivers = Vec::new();
+
+        for (i, address) in addresses.into_iter().enumerate() {
+            let (cmd_tx, cmd_rx) = channel(64);
+            sender
+                .send(InnerTransportEvent::ConnectionEstablished {
+                    peer,
+                    connection: ConnectionId::from(i),
+                    endpoint: Endpoint::dialer(address, ConnectionId::from(i)),
+                    transport: SupportedTransport::Tcp,
+                    sender: ConnectionHandle::new(ConnectionId::from(i), cmd_tx),
+                })
+                .await
+                .unwrap();
+            receivers.push(cmd_rx);
+        }
+
+        futures::future::poll_fn(|cx| {
+            while let std::task::Poll::Ready(_) = service.poll_next_unpin(cx) {}
+            std::task::Poll::Ready(())
+        })
+        .await;
+
+        receivers
+    }
+
+    #[tokio::test]
+    async fn direct_connection_preferred() {
+        let (mut service, sender, _) = transport_service();
+        let peer = PeerId::random();
+        let relay = PeerId::random();
+
+        let relayed = "/ip4/127.0.0.1/tcp/8888"
+            .parse::<Multiaddr>()
+            .unwrap()
+            .with(Protocol::P2p(Multihash::from(relay)))
+            .with(Protocol::P2pCircuit);
+        let direct = "/ip4/127.0.0.1/tcp/9999".parse::<Multiaddr>().unwrap();
+
+        let mut receivers =
+            register_connections(&mut service, &sender, peer, vec![relayed, direct]).await;
+        service.set_connection_preference(ConnectionPreference::Direct);
+        service.open_substream(peer).unwrap();
+
+        assert!(receivers[0].try_recv().is_err());
+        assert!(std::matches!(
+            receivers[1].try_recv(),
+            Ok(ProtocolCommand::OpenSubstream { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn no_direct_connection() {
+        let (mut service, sender, _) = transport_service();
+        let peer = PeerId::random();
+
+        let relayed = "/ip4/127.0.0.1/tcp/8888"
+            .parse::<Multiaddr>()
+            .unwrap()
+            .with(Protocol::P2p(Multihash::from(PeerId::random())))
+            .with(Protocol::P2pCircuit);
+
+        let mut receivers = register_connections(&mut service, &sender, peer, vec![relayed]).await;
+        service.set_connection_preference(ConnectionPreference::Direct);
+
+        match service.open_substream(peer) {
+            Err(Error::NoMatchingConnection(error_peer)) => assert_eq!(error_peer, peer),
+            result => panic!("invalid result: {result:?}"),
+        }
+        assert!(receivers[0].try_recv().is_err());
+
+        // any connection is still accepted by the default preference
+        service.set_connection_preference(ConnectionPreference::Any);
+        service.open_substream(peer).unwrap();
+        assert!(receivers[0].try_recv().is_ok());
+    }
+
+    #[tokio::test]
+    async fn lowest_latency_falls_back_to_primary() {
+        let (mut service, sender, _) = transport_service();
+        let peer = PeerId::random();
+
+        let mut receivers = register_connections(
+            &mut service,
+            &sender,
+            peer,
+            vec![
+                "/ip4/127.0.0.1/tcp/8888".parse().unwrap(),
+                "/ip4/127.0.0.1/tcp/9999".parse().unwrap(),
+            ],
+        )
+        .await;
+
+        // round-trip time is not known for either connection
+        service.set_connection_preference(ConnectionPreference::LowestLatency);
+        service.open_substream(peer).unwrap();
+
+        assert!(receivers[0].try_recv().is_ok());
+        assert!(receivers[1].try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn handover_preserves_connections_and_events() {
+        let (mut service, sender, _) = transport_service();
+        let peer = PeerId::random();
+
+        let mut receivers = register_connections(
+            &mut service,
+            &sender,
+            peer,
+            vec!["/ip4/127.0.0.1/tcp/8888".parse().unwrap()],
+        )
+        .await;
+
+        let mut handover = service.into_handover();
+        assert_eq!(handover.protocol(), &ProtocolName::from("/notif/1"));
+        assert_eq!(handover.connected_peers().collect::<Vec<_>>(), vec![&peer]);
+        handover.set_state(peer, bytes::Bytes::from_static(b"state"));
+
+        // events received while the handover is in transit are buffered
+        let other_peer = PeerId::random();
+        let (cmd_tx, _cmd_rx) = channel(64);
+        sender
+            .send(InnerTransportEvent::ConnectionEstablished {
+                peer: other_peer,
+                connection: ConnectionId::from(1usize),
+                endpoint: Endpoint::dialer(Multiaddr::empty(), ConnectionId::from(1usize)),
+                transport: SupportedTransport::Tcp,
+                sender: ConnectionHandle::new(ConnectionId::from(1usize), cmd_tx),
+            })
+            .await
+            .unwrap();
+
+        let (mut service, peers) = handover.resume();
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers.get(&peer).unwrap().state, &b"state"[..]);
+        assert!(peers.get(&peer).unwrap().substreams.is_empty());
+
+        match service.next().await {
+            Some(TransportEvent::ConnectionEstablished {
+                peer: connected_peer,
+                ..
+            }) => assert_eq!(connected_peer, other_peer),
+            event => panic!("invalid event: {event:?}"),
+        }
+
+        // connection opened before the handover is still usable
+        service.open_substream(peer).unwrap();
+        assert!(std::matches!(
+            receivers[0].try_recv(),
+            Ok(ProtocolCommand::OpenSubstream { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn substream_open_times_out() {
+        let (mut service, sender, _) = transport_service();
+        let peer = PeerId::random();
+
+        let (cmd_tx, mut cmd_rx) = channel(64);
+        sender
+            .send(InnerTransportEvent::ConnectionEstablished {
+                peer,
+                connection: ConnectionId::from(0usize),
+                endpoint: Endpoint::dialer(Multiaddr::empty(), ConnectionId::from(0usize)),
+                transport: SupportedTransport::Tcp,
+                sender: ConnectionHandle::new(ConnectionId::from(0usize), cmd_tx),
+            })
+            .await
+            .unwrap();
+
+        match service.next().await {
+            Some(TransportEvent::ConnectionEstablished { .. }) => {}
+            event => panic!("invalid event: {event:?}"),
+        }
+
+        // the connection never answers
+        service.set_substream_open_timeout(Duration::from_millis(100));
+        let substream_id = service.open_substream(peer).unwrap();
+        assert!(std::matches!(
+            cmd_rx.try_recv(),
+            Ok(ProtocolCommand::OpenSubstream { .. })
+        ));
+
+        match tokio::time::timeout(Duration::from_secs(5), service.next()).await {
+            Ok(Some(TransportEvent::SubstreamOpenFailure {
+                substream,
+                error: Error::Timeout,
+            })) => assert_eq!(substream, substream_id),
+            event => panic!("invalid event: {event:?}"),
+        }
+
+        // substream opened after the timeout is ignored
+        sender
+            .send(InnerTransportEvent::SubstreamOpened {
+                peer,
+                connection: ConnectionId::from(0usize),
+                protocol: ProtocolName::from("/notif/1"),
+                fallback: None,
+                direction: Direction::Outbound(substream_id),
+                substream: Substream::new_mock(
+                    peer,
+                    substream_id,
+                    Box::new(crate::mock::substream::MockSubstream::new()),
+                ),
+            })
+            .await
+            .unwrap();
+
+        futures::future::poll_fn(|cx| match service.poll_next_unpin(cx) {
+            std::task::Poll::Ready(event) => panic!("didn't expect event: {event:?}"),
+            std::task::Poll::Pending => std::task::Poll::Ready(()),
+        })
+        .await;
+        assert!(service.timed_out_substreams.is_empty());
+    }
+
+    #[tokio::test]
+    async fn negotiation_results_recorded() {
+        let (mut service, sender, _) = transport_service();
+        let peer = PeerId::random();
+
+        let (cmd_tx, _cmd_rx) = channel(64);
+        sender
+            .send(InnerTransportEvent::ConnectionEstablished {
+                peer,
+                connection: ConnectionId::from(0usize),
+                endpoint: Endpoint::dialer(Multiaddr::empty(), ConnectionId::from(0usize)),
+                transport: SupportedTransport::Tcp,
+                sender: ConnectionHandle::new(ConnectionId::from(0usize), cmd_tx),
+            })
+            .await
+            .unwrap();
+
+        match service.next().await {
+            Some(TransportEvent::ConnectionEstablished { .. }) => {}
+            event => panic!("invalid event: {event:?}"),
+        }
+        assert_eq!(service.peer_supports_protocol(&peer), None);
+
+        // remote rejects the protocol
+        let substream_id = service.open_substream(peer).unwrap();
+        sender
+            .send(InnerTransportEvent::SubstreamOpenFailure {
+                substream: substream_id,
+                error: Error::NegotiationError(NegotiationError::MultistreamSelectError(
+                    MultistreamFailed,
+                )),
+            })
+            .await
+            .unwrap();
+
+        match service.next().await {
+            Some(TransportEvent::SubstreamOpenFailure { substream, .. }) =>
+                assert_eq!(substream, substream_id),
+            event => panic!("invalid event: {event:?}"),
+        }
+        assert_eq!(service.peer_supports_protocol(&peer), Some(false));
+
+        // remote opens a substream using the protocol
+        sender
+            .send(InnerTransportEvent::SubstreamOpened {
+                peer,
+                connection: ConnectionId::from(0usize),
+                protocol: ProtocolName::from("/notif/1"),
+                fallback: None,
+                direction: Direction::Inbound,
+                substream: Substream::new_mock(
+                    peer,
+                    SubstreamId::from(1337usize),
+                    Box::new(crate::mock::substream::MockSubstream::new()),
+                ),
+            })
+            .await
+            .unwrap();
+
+        match service.next().await {
+            Some(TransportEvent::SubstreamOpened { .. }) => {}
+            event => panic!("invalid event: {event:?}"),
+        }
+        assert_eq!(service.peer_supports_protocol(&peer), Some(true));
+    }
 }
@@ -0,0 +1,261 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Reputation of remote peers.
+//!
+//! Protocols and the user report the behavior of remote peers, such as sending invalid messages,
+//! timing out or providing useful data. Each kind of behavior changes the reputation of the peer
+//! by a configurable weight and the reputation decays exponentially towards zero over time so
+//! that old behavior is eventually forgotten.
+//!
+//! If an inbound connection exceeds the IP limits configured with
+//! [`ConfigBuilder::with_ip_limits()`](crate::config::ConfigBuilder::with_ip_limits), the
+//! transport manager closes the connection of the least reputable peer competing for the same
+//! limit instead of rejecting the new connection, provided that the peer is less reputable than
+//! the new one.
+
+use crate::PeerId;
+
+use parking_lot::RwLock;
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::reputation";
+
+/// Reputations which have decayed below this are forgotten.
+const MIN_REPUTATION: f64 = 0.5;
+
+/// Reputation configuration.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Reputation change for sending an invalid message.
+    ///
+    /// By default `-100`.
+    pub invalid_message: i32,
+
+    /// Reputation change for timing out.
+    ///
+    /// By default `-10`.
+    pub timeout: i32,
+
+    /// Reputation change for providing useful data.
+    ///
+    /// By default `10`.
+    pub useful_data: i32,
+
+    /// Time it takes for a reputation to decay to half of its value.
+    ///
+    /// By default 10 minutes.
+    pub half_life: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            invalid_message: -100,
+            timeout: -10,
+            useful_data: 10,
+            half_life: Duration::from_secs(10 * 60),
+        }
+    }
+}
+
+/// Behavior of a remote peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Behavior {
+    /// Peer sent an invalid message.
+    InvalidMessage,
+
+    /// Peer timed out.
+    Timeout,
+
+    /// Peer provided useful data.
+    UsefulData,
+
+    /// Custom reputation change.
+    Custom(i32),
+}
+
+/// Reputation of a peer.
+#[derive(Debug)]
+struct Entry {
+    /// Reputation at the time of the last update.
+    value: f64,
+
+    /// When was the reputation last updated.
+    updated: Instant,
+}
+
+impl Entry {
+    /// Get the decayed value of the reputation at `now`.
+    fn value(&self, now: Instant, half_life: Duration) -> f64 {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+
+        self.value * 0.5f64.powf(elapsed / half_life.as_secs_f64().max(f64::EPSILON))
+    }
+}
+
+/// Shared state of the reputations.
+#[derive(Debug, Default)]
+struct Inner {
+    /// Reputation configuration.
+    config: Config,
+
+    /// Reputations of the peers.
+    peers: HashMap<PeerId, Entry>,
+}
+
+/// Handle to the reputations of remote peers.
+///
+/// Cloning is cheap and all clones refer to the same reputations.
+#[derive(Debug, Clone, Default)]
+pub struct Reputation {
+    inner: Arc<RwLock<Inner>>,
+}
+
+impl Reputation {
+    /// Create new [`Reputation`] with the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set reputation configuration.
+    pub(crate) fn set_config(&self, config: Config) {
+        self.inner.write().config = config;
+    }
+
+    /// Report `behavior` of `peer`.
+    ///
+    /// The reputation of the peer is changed by the weight configured for the behavior.
+    pub fn report(&self, peer: PeerId, behavior: Behavior) {
+        let change = {
+            let config = &self.inner.read().config;
+
+            match behavior {
+                Behavior::InvalidMessage => config.invalid_message,
+                Behavior::Timeout => config.timeout,
+                Behavior::UsefulData => config.useful_data,
+                Behavior::Custom(change) => change,
+            }
+        };
+
+        tracing::trace!(target: LOG_TARGET, ?peer, ?behavior, ?change, "report behavior");
+
+        self.adjust(peer, change);
+    }
+
+    /// Adjust the reputation of `peer` by `change`.
+    pub fn adjust(&self, peer: PeerId, change: i32) {
+        let mut inner = self.inner.write();
+        let now = Instant::now();
+        let half_life = inner.config.half_life;
+
+        let value = inner.peers.get(&peer).map_or(0f64, |entry| entry.value(now, half_life))
+            + f64::from(change);
+
+        if value.abs() < MIN_REPUTATION {
+            inner.peers.remove(&peer);
+            return;
+        }
+
+        inner.peers.insert(
+            peer,
+            Entry {
+                value,
+                updated: now,
+            },
+        );
+    }
+
+    /// Get the current reputation of `peer`.
+    ///
+    /// Peers without any reported behavior have a reputation of zero.
+    pub fn reputation(&self, peer: &PeerId) -> i32 {
+        let inner = self.inner.read();
+
+        inner.peers.get(peer).map_or(0i32, |entry| {
+            entry.value(Instant::now(), inner.config.half_life).round() as i32
+        })
+    }
+
+    /// Forget the reputations which have decayed to zero.
+    pub(crate) fn prune(&self) {
+        let mut inner = self.inner.write();
+        let now = Instant::now();
+        let half_life = inner.config.half_life;
+
+        inner
+            .peers
+            .retain(|_, entry| entry.value(now, half_life).abs() >= MIN_REPUTATION);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn behavior_weights() {
+        let reputation = Reputation::new();
+        let peer = PeerId::random();
+
+        assert_eq!(reputation.reputation(&peer), 0);
+
+        reputation.report(peer, Behavior::UsefulData);
+        reputation.report(peer, Behavior::Timeout);
+        reputation.report(peer, Behavior::Custom(5));
+        assert_eq!(reputation.reputation(&peer), 5);
+
+        reputation.report(peer, Behavior::InvalidMessage);
+        assert_eq!(reputation.reputation(&peer), -95);
+
+        reputation.set_config(Config {
+            useful_data: 95,
+            ..Default::default()
+        });
+        reputation.report(peer, Behavior::UsefulData);
+        assert_eq!(reputation.reputation(&peer), 0);
+        assert!(reputation.inner.read().peers.is_empty());
+    }
+
+    #[test]
+    fn reputation_decays() {
+        let reputation = Reputation::new();
+        reputation.set_config(Config {
+            half_life: Duration::from_secs(60),
+            ..Default::default()
+        });
+        let peer = PeerId::random();
+
+        reputation.adjust(peer, -100);
+        reputation.inner.write().peers.get_mut(&peer).unwrap().updated -= Duration::from_secs(60);
+        assert_eq!(reputation.reputation(&peer), -50);
+
+        reputation.inner.write().peers.get_mut(&peer).unwrap().updated -= Duration::from_secs(600);
+        reputation.prune();
+        assert_eq!(reputation.reputation(&peer), 0);
+        assert!(reputation.inner.read().peers.is_empty());
+    }
+}
@@ -0,0 +1,209 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Subscriptions to [`Litep2pEvent`]s.
+
+use crate::{Litep2pEvent, PeerId};
+
+use futures::Stream;
+use parking_lot::Mutex;
+use tokio::sync::mpsc::{channel, error::TrySendError, Receiver, Sender};
+
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::subscription";
+
+/// Default channel size for an event subscription.
+const DEFAULT_CHANNEL_SIZE: usize = 256;
+
+/// Event filter.
+type EventFilter = Box<dyn Fn(&Litep2pEvent) -> bool + Send + Sync>;
+
+/// Subscriber.
+struct Subscriber {
+    /// Filter for the events the subscriber is interested in.
+    filter: EventFilter,
+
+    /// TX channel for sending events to the subscriber.
+    tx: Sender<Litep2pEvent>,
+}
+
+/// Event subscriptions.
+#[derive(Clone, Default)]
+pub(crate) struct Subscriptions {
+    /// Active subscribers.
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+}
+
+impl Subscriptions {
+    /// Create new [`Subscriptions`].
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add new subscriber which receives the events accepted by `filter`.
+    pub(crate) fn subscribe(
+        &self,
+        filter: impl Fn(&Litep2pEvent) -> bool + Send + Sync + 'static,
+    ) -> EventStream {
+        let (tx, rx) = channel(DEFAULT_CHANNEL_SIZE);
+
+        self.subscribers.lock().push(Subscriber {
+            filter: Box::new(filter),
+            tx,
+        });
+
+        EventStream { rx }
+    }
+
+    /// Publish `event` to all subscribers interested in it.
+    ///
+    /// Subscribers whose stream has been dropped are removed and if the channel of a subscriber
+    /// is full, the event is dropped for that subscriber.
+    pub(crate) fn publish(&self, event: &Litep2pEvent) {
+        let mut subscribers = self.subscribers.lock();
+
+        if subscribers.is_empty() {
+            return;
+        }
+
+        subscribers.retain(|subscriber| {
+            if !(subscriber.filter)(event) {
+                return !subscriber.tx.is_closed();
+            }
+
+            let Some(event) = event.try_clone() else {
+                return true;
+            };
+
+            match subscriber.tx.try_send(event) {
+                Ok(()) => true,
+                Err(TrySendError::Full(event)) => {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?event,
+                        "subscriber channel full, dropping event",
+                    );
+                    true
+                }
+                Err(TrySendError::Closed(_)) => false,
+            }
+        });
+    }
+}
+
+/// Stream of [`Litep2pEvent`]s matching the filter of a subscription.
+///
+/// Created with [`Litep2p::subscribe()`](crate::Litep2p::subscribe) or
+/// [`Litep2pHandle::subscribe()`](crate::Litep2pHandle::subscribe). The subscription is
+/// cancelled when the stream is dropped.
+#[derive(Debug)]
+pub struct EventStream {
+    /// RX channel for receiving events.
+    rx: Receiver<Litep2pEvent>,
+}
+
+impl Stream for EventStream {
+    type Item = Litep2pEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+/// Filter which accepts only connection events.
+///
+/// Connection events are [`Litep2pEvent::ConnectionEstablished`],
+/// [`Litep2pEvent::ConnectionClosed`] and [`Litep2pEvent::ConnectionRejected`].
+pub fn connection_events(event: &Litep2pEvent) -> bool {
+    matches!(
+        event,
+        Litep2pEvent::ConnectionEstablished { .. }
+            | Litep2pEvent::ConnectionClosed { .. }
+            | Litep2pEvent::ConnectionRejected { .. }
+    )
+}
+
+/// Create filter which accepts only events concerning `peer`.
+pub fn peer_events(peer: PeerId) -> impl Fn(&Litep2pEvent) -> bool + Send + Sync + 'static {
+    move |event| event.peer() == Some(&peer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{transport::DisconnectReason, types::ConnectionId};
+    use futures::StreamExt;
+
+    fn closed(peer: PeerId) -> Litep2pEvent {
+        Litep2pEvent::ConnectionClosed {
+            peer,
+            connection_id: ConnectionId::new(),
+            reason: DisconnectReason::Inactive,
+        }
+    }
+
+    #[tokio::test]
+    async fn events_filtered_per_subscriber() {
+        let subscriptions = Subscriptions::new();
+        let peer1 = PeerId::random();
+        let peer2 = PeerId::random();
+
+        let mut all = subscriptions.subscribe(|_| true);
+        let mut peer1_events = subscriptions.subscribe(peer_events(peer1));
+        let mut listen_events =
+            subscriptions.subscribe(|event| matches!(event, Litep2pEvent::NewListenAddress { .. }));
+
+        subscriptions.publish(&closed(peer1));
+        subscriptions.publish(&closed(peer2));
+
+        assert!(std::matches!(
+            all.next().await,
+            Some(Litep2pEvent::ConnectionClosed { peer, .. }) if peer == peer1
+        ));
+        assert!(std::matches!(
+            all.next().await,
+            Some(Litep2pEvent::ConnectionClosed { peer, .. }) if peer == peer2
+        ));
+        assert!(std::matches!(
+            peer1_events.next().await,
+            Some(Litep2pEvent::ConnectionClosed { peer, .. }) if peer == peer1
+        ));
+        assert!(futures::poll!(peer1_events.next()).is_pending());
+        assert!(futures::poll!(listen_events.next()).is_pending());
+    }
+
+    #[tokio::test]
+    async fn dropped_subscriber_removed() {
+        let subscriptions = Subscriptions::new();
+        let stream = subscriptions.subscribe(connection_events);
+        let _other = subscriptions.subscribe(|_| false);
+
+        drop(stream);
+        subscriptions.publish(&closed(PeerId::random()));
+
+        assert_eq!(subscriptions.subscribers.lock().len(), 1);
+    }
+}
@@ -32,6 +32,7 @@ use crate::{
 use bytes::{Buf, Bytes, BytesMut};
 use futures::{Sink, Stream};
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadBuf};
+use tokio_util::io::poll_write_buf;
 use unsigned_varint::{decode, encode};
 
 use std::{
@@ -293,6 +294,29 @@ impl Substream {
         )
     }
 
+    /// Set priority of the substream.
+    ///
+    /// Data of substreams with higher priority is sent before data of lower-priority substreams
+    /// sharing the same connection. The default priority is `0`, or the priority configured for
+    /// the protocol. Only QUIC supports substream priorities and for other transports the call is
+    /// a no-op.
+    pub fn set_priority(&self, priority: i32) -> crate::Result<()> {
+        match &self.substream {
+            SubstreamType::Quic(substream) => substream.set_priority(priority),
+            _ => Ok(()),
+        }
+    }
+
+    /// Set the maximum size of frames sent and received over the substream.
+    ///
+    /// Only substreams using [`ProtocolCodec::UnsignedVarint`] with a size limit are affected,
+    /// for other codecs the call is a no-op.
+    pub(crate) fn set_max_frame_size(&mut self, max_size: usize) {
+        if let ProtocolCodec::UnsignedVarint(Some(ref mut current)) = self.codec {
+            *current = max_size;
+        }
+    }
+
     /// Close the substream.
     pub async fn close(self) {
         let _ = match self.substream {
@@ -322,6 +346,31 @@ impl Substream {
             .map_err(|_| Error::SubstreamError(SubstreamError::ConnectionClosed))
     }
 
+    /// Send length-prefixed payload to remote peer.
+    ///
+    /// The length prefix and the payload are written using vectored writes if `io` supports them
+    /// so the payload doesn't have to be copied into a separate buffer.
+    async fn send_unsigned_varint_payload<T: AsyncWrite + Unpin>(
+        io: &mut T,
+        payload: Bytes,
+    ) -> crate::Result<()> {
+        let mut buffer = [0u8; 10];
+        let len = unsigned_varint::encode::usize(payload.len(), &mut buffer);
+        let mut frame = Bytes::copy_from_slice(len).chain(payload);
+
+        while frame.has_remaining() {
+            let nwritten =
+                futures::future::poll_fn(|cx| poll_write_buf(Pin::new(&mut *io), cx, &mut frame))
+                    .await?;
+
+            if nwritten == 0 {
+                return Err(Error::SubstreamError(SubstreamError::ConnectionClosed));
+            }
+        }
+
+        io.flush().await.map_err(From::from)
+    }
+
     /// Send framed data to remote peer.
     ///
     /// This function may be faster than the provided [`futures::Sink`] implementation for
@@ -336,7 +385,7 @@ impl Substream {
     /// # Panics
     ///
     /// Panics if no codec is provided.
-    pub async fn send_framed(&mut self, mut bytes: Bytes) -> crate::Result<()> {
+    pub async fn send_framed(&mut self, bytes: Bytes) -> crate::Result<()> {
         tracing::trace!(
             target: LOG_TARGET,
             peer = ?self.peer,
@@ -356,20 +405,7 @@ impl Substream {
                 ProtocolCodec::UnsignedVarint(max_size) => {
                     check_size!(max_size, bytes.len());
 
-                    let mut buffer = [0u8; 10];
-                    let len = unsigned_varint::encode::usize(bytes.len(), &mut buffer);
-                    let mut offset = 0;
-
-                    while offset < len.len() {
-                        offset += substream.write(&len[offset..]).await?;
-                    }
-
-                    while bytes.has_remaining() {
-                        let nwritten = substream.write(&bytes).await?;
-                        bytes.advance(nwritten);
-                    }
-
-                    substream.flush().await.map_err(From::from)
+                    Self::send_unsigned_varint_payload(substream, bytes).await
                 }
             },
             SubstreamType::WebSocket(ref mut substream) => match self.codec {
@@ -379,20 +415,7 @@ impl Substream {
                 ProtocolCodec::UnsignedVarint(max_size) => {
                     check_size!(max_size, bytes.len());
 
-                    let mut buffer = [0u8; 10];
-                    let len = unsigned_varint::encode::usize(bytes.len(), &mut buffer);
-                    let mut offset = 0;
-
-                    while offset < len.len() {
-                        offset += substream.write(&len[offset..]).await?;
-                    }
-
-                    while bytes.has_remaining() {
-                        let nwritten = substream.write(&bytes).await?;
-                        bytes.advance(nwritten);
-                    }
-
-                    substream.flush().await.map_err(From::from)
+                    Self::send_unsigned_varint_payload(substream, bytes).await
                 }
             },
             SubstreamType::Quic(ref mut substream) => match self.codec {
@@ -590,6 +613,12 @@ impl Stream for Substream {
                                                 }
 
                                                 this.offset = 0;
+
+                                                // empty frame has no payload to read
+                                                if size == 0 {
+                                                    return Poll::Ready(Some(Ok(BytesMut::new())));
+                                                }
+
                                                 this.current_frame_size = Some(size);
                                                 this.read_buffer = BytesMut::zeroed(size);
                                             }
@@ -0,0 +1,119 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Allow-list of peers and address ranges.
+
+use crate::{transport::connection_rate::Source, PeerId};
+
+use multiaddr::{Multiaddr, Protocol};
+
+use std::{collections::HashSet, net::IpAddr};
+
+/// Allow-list of peers, and optionally address ranges, which may connect to or be dialed by the
+/// local node.
+///
+/// When configured with
+/// [`ConfigBuilder::with_allow_list()`](crate::config::ConfigBuilder::with_allow_list), dials to
+/// peers or addresses not on the list are refused with
+/// [`Error::PeerNotAllowed`](crate::Error::PeerNotAllowed) and inbound connections from them are
+/// rejected with
+/// [`RejectReason::NotAllowed`](crate::transport::RejectReason::NotAllowed) right after the
+/// handshake.
+///
+/// If address ranges are added, the remote address must also fall within one of them. Addresses
+/// which don't start with an IP address, such as DNS addresses, never match a range.
+#[derive(Debug, Clone, Default)]
+pub struct AllowList {
+    /// Allowed peers.
+    peers: HashSet<PeerId>,
+
+    /// Allowed address ranges.
+    ranges: Vec<Source>,
+}
+
+impl AllowList {
+    /// Create new [`AllowList`] from `peers`.
+    pub fn new(peers: impl IntoIterator<Item = PeerId>) -> Self {
+        Self {
+            peers: peers.into_iter().collect(),
+            ranges: Vec::new(),
+        }
+    }
+
+    /// Allow addresses within the subnet of `address` of `prefix` bits.
+    pub fn with_address_range(mut self, address: IpAddr, prefix: u8) -> Self {
+        self.ranges.push(Source::new(address, prefix));
+        self
+    }
+
+    /// Check if `peer` is allowed.
+    pub fn is_peer_allowed(&self, peer: &PeerId) -> bool {
+        self.peers.contains(peer)
+    }
+
+    /// Check if `address` is allowed.
+    ///
+    /// All addresses are allowed if no address range has been added.
+    pub fn is_address_allowed(&self, address: &Multiaddr) -> bool {
+        if self.ranges.is_empty() {
+            return true;
+        }
+
+        let ip = match address.iter().next() {
+            Some(Protocol::Ip4(ip)) => IpAddr::V4(ip),
+            Some(Protocol::Ip6(ip)) => IpAddr::V6(ip),
+            _ => return false,
+        };
+
+        self.ranges.iter().any(|range| range.contains(ip))
+    }
+
+    /// Check if a connection to `peer` over `address` is allowed.
+    pub fn is_allowed(&self, peer: &PeerId, address: &Multiaddr) -> bool {
+        self.is_peer_allowed(peer) && self.is_address_allowed(address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peers_and_ranges_checked() {
+        let peer = PeerId::random();
+        let allow_list = AllowList::new([peer]);
+        let address = "/ip4/10.0.1.5/tcp/8888".parse::<Multiaddr>().unwrap();
+
+        assert!(allow_list.is_allowed(&peer, &address));
+        assert!(!allow_list.is_allowed(&PeerId::random(), &address));
+
+        let allow_list = allow_list.with_address_range("10.0.0.0".parse().unwrap(), 16);
+
+        assert!(allow_list.is_allowed(&peer, &address));
+        assert!(!allow_list.is_allowed(
+            &peer,
+            &"/ip4/10.1.1.5/tcp/8888".parse::<Multiaddr>().unwrap()
+        ));
+        assert!(!allow_list.is_allowed(
+            &peer,
+            &"/dns/example.com/tcp/8888".parse::<Multiaddr>().unwrap()
+        ));
+    }
+}
@@ -0,0 +1,278 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Connecting to bootnodes.
+//!
+//! Bootnodes given to [`Litep2p::connect_to_bootnodes()`](crate::Litep2p::connect_to_bootnodes)
+//! are dialed by the transport manager in the order they were given, with at most
+//! [`Config::max_concurrent_dials`] dials in progress at a time. Once
+//! [`Config::min_connected`] bootnodes have been connected to, or all of them have been tried,
+//! the outcome is reported in
+//! [`Litep2pEvent::BootstrapCompleted`](crate::Litep2pEvent::BootstrapCompleted).
+
+use crate::{error::Error, PeerId};
+
+use futures::{future::BoxFuture, stream::FuturesUnordered, StreamExt};
+use multiaddr::Multiaddr;
+use tokio::sync::oneshot;
+
+use std::{
+    collections::VecDeque,
+    task::{Context, Poll},
+};
+
+/// Bootstrap configuration.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Maximum number of bootnodes dialed concurrently.
+    ///
+    /// By default at most `8` bootnodes are dialed at a time.
+    pub max_concurrent_dials: usize,
+
+    /// Number of bootnodes that must be connected to before the bootstrap is reported as
+    /// completed.
+    ///
+    /// The remaining bootnodes are still dialed after the outcome has been reported. By default
+    /// the bootstrap is completed once `1` bootnode has been connected to.
+    pub min_connected: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_concurrent_dials: 8,
+            min_connected: 1,
+        }
+    }
+}
+
+/// Outcome of the bootstrap.
+#[derive(Debug)]
+pub(crate) struct BootstrapOutcome {
+    /// Bootnodes which were connected to.
+    pub(crate) connected: Vec<PeerId>,
+
+    /// Addresses of the bootnodes which couldn't be connected to.
+    pub(crate) failed: Vec<(Multiaddr, Error)>,
+}
+
+/// Bootstrap state of the transport manager.
+pub(crate) struct Bootstrap {
+    /// Bootstrap configuration.
+    config: Config,
+
+    /// Bootnodes which haven't been dialed yet.
+    pending: VecDeque<Multiaddr>,
+
+    /// Dials in progress.
+    dials: FuturesUnordered<BoxFuture<'static, (Multiaddr, crate::Result<PeerId>)>>,
+
+    /// Bootnodes which were connected to.
+    connected: Vec<PeerId>,
+
+    /// Addresses of the bootnodes which couldn't be connected to.
+    failed: Vec<(Multiaddr, Error)>,
+
+    /// Has the outcome been reported.
+    reported: bool,
+}
+
+impl Bootstrap {
+    /// Create new [`Bootstrap`].
+    pub(crate) fn new(config: Config) -> Self {
+        Self {
+            config,
+            pending: VecDeque::new(),
+            dials: FuturesUnordered::new(),
+            connected: Vec::new(),
+            failed: Vec::new(),
+            reported: true,
+        }
+    }
+
+    /// Set bootstrap configuration.
+    pub(crate) fn set_config(&mut self, config: Config) {
+        self.config = config;
+    }
+
+    /// Start connecting to `bootnodes`.
+    ///
+    /// Bootnodes of an earlier bootstrap which haven't been dialed yet are discarded and the
+    /// results of its dials in progress are counted towards the new bootstrap.
+    pub(crate) fn start(&mut self, bootnodes: Vec<Multiaddr>) {
+        self.pending = bootnodes.into();
+        self.connected.clear();
+        self.failed.clear();
+        self.reported = false;
+    }
+
+    /// Get the next bootnode to dial, if another dial can be started.
+    pub(crate) fn next_dial(&mut self) -> Option<Multiaddr> {
+        if self.dials.len() >= self.config.max_concurrent_dials.max(1) {
+            return None;
+        }
+
+        self.pending.pop_front()
+    }
+
+    /// Register dial to `address` whose result is received over `rx`.
+    pub(crate) fn on_dial_started(
+        &mut self,
+        address: Multiaddr,
+        rx: oneshot::Receiver<crate::Result<PeerId>>,
+    ) {
+        self.dials.push(Box::pin(async move {
+            (address, rx.await.unwrap_or(Err(Error::EssentialTaskClosed)))
+        }));
+    }
+
+    /// Check if there are dials in progress.
+    pub(crate) fn is_dialing(&self) -> bool {
+        !self.dials.is_empty()
+    }
+
+    /// Check if all bootnodes have been dialed and the dials have concluded.
+    pub(crate) fn is_finished(&self) -> bool {
+        self.pending.is_empty() && self.dials.is_empty()
+    }
+
+    /// Poll the next dial to conclude.
+    pub(crate) fn poll_next_result(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<(Multiaddr, crate::Result<PeerId>)>> {
+        self.dials.poll_next_unpin(cx)
+    }
+
+    /// Handle result of a dial.
+    ///
+    /// Returns the outcome of the bootstrap if it should be reported.
+    pub(crate) fn on_dial_result(
+        &mut self,
+        address: Multiaddr,
+        result: crate::Result<PeerId>,
+    ) -> Option<BootstrapOutcome> {
+        match result {
+            Ok(peer) =>
+                if !self.connected.contains(&peer) {
+                    self.connected.push(peer);
+                },
+            Err(error) => self.failed.push((address, error)),
+        }
+
+        self.outcome()
+    }
+
+    /// Get the outcome of the bootstrap if it's completed and hasn't been reported yet.
+    pub(crate) fn outcome(&mut self) -> Option<BootstrapOutcome> {
+        if self.reported {
+            return None;
+        }
+
+        if self.connected.len() >= self.config.min_connected || self.is_finished() {
+            self.reported = true;
+
+            return Some(BootstrapOutcome {
+                connected: self.connected.clone(),
+                failed: std::mem::take(&mut self.failed),
+            });
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn completed_once_enough_bootnodes_connected() {
+        let config = Config {
+            max_concurrent_dials: 2,
+            min_connected: 2,
+        };
+        let bootnodes: Vec<Multiaddr> = vec![
+            "/ip4/127.0.0.1/tcp/1".parse().unwrap(),
+            "/ip4/127.0.0.1/tcp/2".parse().unwrap(),
+            "/ip4/127.0.0.1/tcp/3".parse().unwrap(),
+        ];
+        let mut bootstrap = Bootstrap::new(config);
+        bootstrap.start(bootnodes.clone());
+
+        assert_eq!(bootstrap.next_dial(), Some(bootnodes[0].clone()));
+        let (tx1, rx1) = oneshot::channel();
+        bootstrap.on_dial_started(bootnodes[0].clone(), rx1);
+
+        assert_eq!(bootstrap.next_dial(), Some(bootnodes[1].clone()));
+        let (tx2, rx2) = oneshot::channel();
+        bootstrap.on_dial_started(bootnodes[1].clone(), rx2);
+
+        // concurrency limit reached
+        assert_eq!(bootstrap.next_dial(), None);
+
+        tx1.send(Err(Error::Unknown)).unwrap();
+        let (address, result) =
+            futures::future::poll_fn(|cx| bootstrap.poll_next_result(cx)).await.unwrap();
+        assert!(bootstrap.on_dial_result(address, result).is_none());
+
+        let peer = PeerId::random();
+        tx2.send(Ok(peer)).unwrap();
+        let (address, result) =
+            futures::future::poll_fn(|cx| bootstrap.poll_next_result(cx)).await.unwrap();
+        assert!(bootstrap.on_dial_result(address, result).is_none());
+
+        assert_eq!(bootstrap.next_dial(), Some(bootnodes[2].clone()));
+        let (tx3, rx3) = oneshot::channel();
+        bootstrap.on_dial_started(bootnodes[2].clone(), rx3);
+
+        let peer2 = PeerId::random();
+        tx3.send(Ok(peer2)).unwrap();
+        let (address, result) =
+            futures::future::poll_fn(|cx| bootstrap.poll_next_result(cx)).await.unwrap();
+        let outcome = bootstrap.on_dial_result(address, result).unwrap();
+
+        assert_eq!(outcome.connected, vec![peer, peer2]);
+        assert_eq!(outcome.failed.len(), 1);
+        assert!(bootstrap.is_finished());
+    }
+
+    #[tokio::test]
+    async fn completed_once_all_bootnodes_failed() {
+        let address: Multiaddr = "/ip4/127.0.0.1/tcp/1".parse().unwrap();
+        let mut bootstrap = Bootstrap::new(Default::default());
+        bootstrap.start(vec![address.clone()]);
+
+        assert_eq!(bootstrap.next_dial(), Some(address.clone()));
+        let (tx, rx) = oneshot::channel();
+        bootstrap.on_dial_started(address, rx);
+        drop(tx);
+
+        let (address, result) =
+            futures::future::poll_fn(|cx| bootstrap.poll_next_result(cx)).await.unwrap();
+        let outcome = bootstrap.on_dial_result(address, result).unwrap();
+
+        assert!(outcome.connected.is_empty());
+        assert!(std::matches!(
+            outcome.failed[0],
+            (_, Error::EssentialTaskClosed)
+        ));
+    }
+}
@@ -0,0 +1,455 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Inbound connection establishment rates, tracked per source subnet.
+
+use parking_lot::Mutex;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::connection-rate";
+
+/// Maximum number of tracked sources.
+///
+/// Sources whose window has expired are pruned once the limit is reached and if there is still
+/// no space, new sources are not tracked until old sources expire.
+const MAX_SOURCES: usize = 8192;
+
+/// Channel size for threshold events.
+const EVENT_CHANNEL_SIZE: usize = 64;
+
+/// Connection rate configuration.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Length of the window over which connection attempts are counted.
+    ///
+    /// By default the window is `60` seconds.
+    pub window: Duration,
+
+    /// Prefix length used to group IPv4 sources.
+    ///
+    /// By default each IPv4 address is its own source.
+    pub ipv4_prefix: u8,
+
+    /// Prefix length used to group IPv6 sources.
+    ///
+    /// By default IPv6 addresses are grouped by their `/64` subnet.
+    pub ipv6_prefix: u8,
+
+    /// Number of inbound connection attempts from one source within a window after which
+    /// [`Litep2pEvent::ConnectionRateExceeded`](crate::Litep2pEvent::ConnectionRateExceeded) is
+    /// emitted.
+    ///
+    /// By default the threshold is disabled.
+    pub max_attempts: Option<usize>,
+
+    /// Handshake failure ratio of one source within a window after which
+    /// [`Litep2pEvent::ConnectionRateExceeded`](crate::Litep2pEvent::ConnectionRateExceeded) is
+    /// emitted.
+    ///
+    /// The ratio is evaluated only after [`Config::min_handshakes`] handshakes have completed
+    /// within the window.
+    ///
+    /// By default the threshold is disabled.
+    pub max_failure_ratio: Option<f64>,
+
+    /// Minimum number of completed handshakes before the failure ratio is evaluated.
+    ///
+    /// By default the ratio is evaluated after `10` handshakes.
+    pub min_handshakes: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(60),
+            ipv4_prefix: 32,
+            ipv6_prefix: 64,
+            max_attempts: None,
+            max_failure_ratio: None,
+            min_handshakes: 10,
+        }
+    }
+}
+
+/// Source of inbound connections.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Source {
+    /// Network address of the source subnet.
+    pub address: IpAddr,
+
+    /// Prefix length of the subnet.
+    pub prefix: u8,
+}
+
+impl Source {
+    /// Create new [`Source`] for `address` by masking it to `prefix` bits.
+    pub(crate) fn new(address: IpAddr, prefix: u8) -> Self {
+        match address {
+            IpAddr::V4(address) => {
+                let prefix = prefix.min(32);
+                let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+
+                Self {
+                    address: IpAddr::V4(Ipv4Addr::from(u32::from(address) & mask)),
+                    prefix,
+                }
+            }
+            IpAddr::V6(address) => {
+                let prefix = prefix.min(128);
+                let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+
+                Self {
+                    address: IpAddr::V6(Ipv6Addr::from(u128::from(address) & mask)),
+                    prefix,
+                }
+            }
+        }
+    }
+
+    /// Check if `address` belongs to the source subnet.
+    pub fn contains(&self, address: IpAddr) -> bool {
+        Source::new(address, self.prefix) == *self
+    }
+}
+
+/// Connection establishment statistics of a source within the current window.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct SourceStats {
+    /// Number of inbound connection attempts.
+    pub attempts: usize,
+
+    /// Number of successful handshakes.
+    pub successes: usize,
+
+    /// Number of failed handshakes.
+    pub failures: usize,
+}
+
+impl SourceStats {
+    /// Get the ratio of failed handshakes to completed handshakes.
+    ///
+    /// Returns `0.0` if no handshake has completed.
+    pub fn failure_ratio(&self) -> f64 {
+        match self.successes + self.failures {
+            0 => 0.0,
+            completed => self.failures as f64 / completed as f64,
+        }
+    }
+}
+
+/// Statistics of a tracked source.
+#[derive(Debug)]
+struct SourceEntry {
+    /// When the current window started.
+    window_start: Instant,
+
+    /// Statistics of the current window.
+    stats: SourceStats,
+
+    /// Has the source been reported within the current window.
+    reported: bool,
+}
+
+/// Inner state of [`ConnectionRates`].
+#[derive(Debug)]
+struct InnerConnectionRates {
+    /// Configuration.
+    config: Config,
+
+    /// Tracked sources.
+    sources: HashMap<Source, SourceEntry>,
+}
+
+impl InnerConnectionRates {
+    /// Get source of `address`.
+    fn source(&self, address: IpAddr) -> Source {
+        match address {
+            IpAddr::V4(_) => Source::new(address, self.config.ipv4_prefix),
+            IpAddr::V6(_) => Source::new(address, self.config.ipv6_prefix),
+        }
+    }
+
+    /// Get entry of `source`, starting a new window if the previous one has expired.
+    ///
+    /// Returns `None` if the maximum number of sources are tracked.
+    fn entry(&mut self, source: Source, now: Instant) -> Option<&mut SourceEntry> {
+        let window = self.config.window;
+
+        if !self.sources.contains_key(&source) && self.sources.len() >= MAX_SOURCES {
+            self.sources.retain(|_, entry| now.duration_since(entry.window_start) < window);
+
+            if self.sources.len() >= MAX_SOURCES {
+                return None;
+            }
+        }
+
+        let entry = self.sources.entry(source).or_insert_with(|| SourceEntry {
+            window_start: now,
+            stats: SourceStats::default(),
+            reported: false,
+        });
+
+        if now.duration_since(entry.window_start) >= window {
+            entry.window_start = now;
+            entry.stats = SourceStats::default();
+            entry.reported = false;
+        }
+
+        Some(entry)
+    }
+}
+
+/// Inbound connection establishment rates shared by all enabled transports.
+///
+/// Inbound connection attempts and handshake results are tracked per source subnet over a
+/// configurable window and if a source exceeds the configured thresholds,
+/// [`Litep2pEvent::ConnectionRateExceeded`](crate::Litep2pEvent::ConnectionRateExceeded) is
+/// emitted once per window.
+#[derive(Debug, Clone)]
+pub struct ConnectionRates {
+    /// Inner state.
+    inner: Arc<Mutex<InnerConnectionRates>>,
+
+    /// TX channel for reporting sources that exceeded the thresholds.
+    tx: Sender<(Source, SourceStats)>,
+}
+
+impl Default for ConnectionRates {
+    fn default() -> Self {
+        Self::new(Config::default()).0
+    }
+}
+
+impl ConnectionRates {
+    /// Create new [`ConnectionRates`].
+    pub(crate) fn new(config: Config) -> (Self, Receiver<(Source, SourceStats)>) {
+        let (tx, rx) = channel(EVENT_CHANNEL_SIZE);
+
+        (
+            Self {
+                inner: Arc::new(Mutex::new(InnerConnectionRates {
+                    config,
+                    sources: HashMap::new(),
+                })),
+                tx,
+            },
+            rx,
+        )
+    }
+
+    /// Set configuration.
+    pub(crate) fn set_config(&self, config: Config) {
+        let mut inner = self.inner.lock();
+
+        inner.config = config;
+        inner.sources.clear();
+    }
+
+    /// Get statistics of the source `address` belongs to.
+    pub fn source_stats(&self, address: IpAddr) -> Option<SourceStats> {
+        let inner = self.inner.lock();
+        let source = inner.source(address);
+
+        inner
+            .sources
+            .get(&source)
+            .filter(|entry| entry.window_start.elapsed() < inner.config.window)
+            .map(|entry| entry.stats)
+    }
+
+    /// Get statistics of all sources that have been active within the current window.
+    pub fn sources(&self) -> HashMap<Source, SourceStats> {
+        let inner = self.inner.lock();
+
+        inner
+            .sources
+            .iter()
+            .filter(|(_, entry)| entry.window_start.elapsed() < inner.config.window)
+            .map(|(source, entry)| (*source, entry.stats))
+            .collect()
+    }
+
+    /// Register inbound connection attempt from `address`.
+    pub(crate) fn on_inbound_attempt(&self, address: IpAddr) {
+        self.update(address, |stats| stats.attempts += 1);
+    }
+
+    /// Register completed handshake of an inbound connection from `address`.
+    pub(crate) fn on_handshake(&self, address: IpAddr, success: bool) {
+        self.update(address, |stats| match success {
+            true => stats.successes += 1,
+            false => stats.failures += 1,
+        });
+    }
+
+    /// Update statistics of the source `address` belongs to and check the thresholds.
+    fn update(&self, address: IpAddr, update: impl FnOnce(&mut SourceStats)) {
+        let mut inner = self.inner.lock();
+        let source = inner.source(address);
+        let max_attempts = inner.config.max_attempts;
+        let max_failure_ratio = inner.config.max_failure_ratio;
+        let min_handshakes = inner.config.min_handshakes;
+
+        let Some(entry) = inner.entry(source, Instant::now()) else {
+            tracing::trace!(target: LOG_TARGET, ?address, "too many tracked sources");
+            return;
+        };
+        update(&mut entry.stats);
+
+        if entry.reported {
+            return;
+        }
+
+        let attempts_exceeded = match max_attempts {
+            Some(max_attempts) => entry.stats.attempts > max_attempts,
+            None => false,
+        };
+        let failures_exceeded = match max_failure_ratio {
+            Some(max_ratio) =>
+                entry.stats.successes + entry.stats.failures >= min_handshakes
+                    && entry.stats.failure_ratio() > max_ratio,
+            None => false,
+        };
+
+        if attempts_exceeded || failures_exceeded {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?source,
+                stats = ?entry.stats,
+                "source exceeded connection rate thresholds",
+            );
+
+            entry.reported = true;
+            let _ = self.tx.try_send((source, entry.stats));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sources_grouped_by_prefix() {
+        let (rates, _rx) = ConnectionRates::new(Config {
+            ipv4_prefix: 24,
+            ..Default::default()
+        });
+
+        rates.on_inbound_attempt("10.0.0.1".parse().unwrap());
+        rates.on_inbound_attempt("10.0.0.2".parse().unwrap());
+        rates.on_inbound_attempt("10.0.1.1".parse().unwrap());
+        rates.on_inbound_attempt("2001:db8::1".parse().unwrap());
+        rates.on_inbound_attempt("2001:db8::2".parse().unwrap());
+
+        let stats = rates.source_stats("10.0.0.3".parse().unwrap()).unwrap();
+        assert_eq!(stats.attempts, 2);
+
+        let sources = rates.sources();
+        assert_eq!(sources.len(), 3);
+        assert!(sources.contains_key(&Source {
+            address: "10.0.1.0".parse().unwrap(),
+            prefix: 24,
+        }));
+        assert_eq!(
+            sources
+                .get(&Source {
+                    address: "2001:db8::".parse().unwrap(),
+                    prefix: 64,
+                })
+                .unwrap()
+                .attempts,
+            2
+        );
+    }
+
+    #[test]
+    fn attempt_threshold_reported_once() {
+        let (rates, mut rx) = ConnectionRates::new(Config {
+            max_attempts: Some(2),
+            ..Default::default()
+        });
+        let address: IpAddr = "127.0.0.1".parse().unwrap();
+
+        rates.on_inbound_attempt(address);
+        rates.on_inbound_attempt(address);
+        assert!(rx.try_recv().is_err());
+
+        rates.on_inbound_attempt(address);
+        rates.on_inbound_attempt(address);
+
+        let (source, stats) = rx.try_recv().unwrap();
+        assert!(source.contains(address));
+        assert_eq!(stats.attempts, 3);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn failure_ratio_threshold() {
+        let (rates, mut rx) = ConnectionRates::new(Config {
+            max_failure_ratio: Some(0.5),
+            min_handshakes: 4,
+            ..Default::default()
+        });
+        let address: IpAddr = "::1".parse().unwrap();
+
+        rates.on_handshake(address, false);
+        rates.on_handshake(address, false);
+        rates.on_handshake(address, false);
+        assert!(rx.try_recv().is_err());
+
+        rates.on_handshake(address, true);
+
+        let (_, stats) = rx.try_recv().unwrap();
+        assert_eq!(stats.failures, 3);
+        assert_eq!(stats.successes, 1);
+        assert_eq!(stats.failure_ratio(), 0.75);
+    }
+
+    #[test]
+    fn window_expires() {
+        let (rates, mut rx) = ConnectionRates::new(Config {
+            window: Duration::from_millis(50),
+            max_attempts: Some(1),
+            ..Default::default()
+        });
+        let address: IpAddr = "127.0.0.1".parse().unwrap();
+
+        rates.on_inbound_attempt(address);
+        rates.on_inbound_attempt(address);
+        assert!(rx.try_recv().is_ok());
+
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(rates.source_stats(address).is_none());
+
+        // new window, source is reported again
+        rates.on_inbound_attempt(address);
+        rates.on_inbound_attempt(address);
+        assert!(rx.try_recv().is_ok());
+    }
+}
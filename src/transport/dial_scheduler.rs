@@ -0,0 +1,400 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Scheduling of outbound dials.
+//!
+//! All outbound dials started by the transport manager, regardless of the transport they use,
+//! pass through a single queue. At most [`Config::max_concurrent_dials`] handshakes are in
+//! progress at a time, consecutive dials are spaced at least [`Config::min_dial_interval`] apart
+//! and dials to the same destination, i.e., the same IP address or DNS name, are spaced at least
+//! [`Config::min_destination_interval`] apart. Dials which can't be started right away are queued
+//! and started in the order they were made once the limits permit it.
+
+use crate::types::ConnectionId;
+
+use multiaddr::{Multiaddr, Protocol};
+use tokio::time::Sleep;
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::dial-scheduler";
+
+/// Dial scheduler configuration.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Maximum number of outbound handshakes in progress at a time, across all transports.
+    ///
+    /// By default at most `64` handshakes are in progress at a time.
+    pub max_concurrent_dials: usize,
+
+    /// Minimum interval between two consecutive dials.
+    ///
+    /// By default dials are not spaced out.
+    pub min_dial_interval: Duration,
+
+    /// Minimum interval between two consecutive dials to the same IP address or DNS name.
+    ///
+    /// By default dials are not spaced out.
+    pub min_destination_interval: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_concurrent_dials: 64,
+            min_dial_interval: Duration::ZERO,
+            min_destination_interval: Duration::ZERO,
+        }
+    }
+}
+
+/// Get the destination of `address`, i.e., its IP address or DNS name.
+fn destination(address: &Multiaddr) -> Option<Multiaddr> {
+    match address.iter().next()? {
+        protocol @ (Protocol::Ip4(_)
+        | Protocol::Ip6(_)
+        | Protocol::Dns(_)
+        | Protocol::Dns4(_)
+        | Protocol::Dns6(_)) => Some(Multiaddr::empty().with(protocol)),
+        _ => None,
+    }
+}
+
+/// Dial waiting in the queue.
+struct QueuedDial<T> {
+    /// Connection ID of the dial.
+    connection_id: ConnectionId,
+
+    /// Destinations of the dialed addresses.
+    destinations: Vec<Multiaddr>,
+
+    /// Dial.
+    dial: T,
+}
+
+/// Dial scheduler of the transport manager.
+pub(crate) struct DialScheduler<T> {
+    /// Dial scheduler configuration.
+    config: Config,
+
+    /// Dials which haven't been started yet.
+    queue: VecDeque<QueuedDial<T>>,
+
+    /// Dials in progress.
+    in_progress: HashSet<ConnectionId>,
+
+    /// When was the last dial started.
+    last_dial: Option<Instant>,
+
+    /// When was the last dial to a destination started.
+    last_destination_dial: HashMap<Multiaddr, Instant>,
+
+    /// Timer which fires when the next queued dial can be started.
+    timer: Option<Pin<Box<Sleep>>>,
+}
+
+impl<T> DialScheduler<T> {
+    /// Create new [`DialScheduler`].
+    pub(crate) fn new(config: Config) -> Self {
+        Self {
+            config,
+            queue: VecDeque::new(),
+            in_progress: HashSet::new(),
+            last_dial: None,
+            last_destination_dial: HashMap::new(),
+            timer: None,
+        }
+    }
+
+    /// Set dial scheduler configuration.
+    pub(crate) fn set_config(&mut self, config: Config) {
+        self.config = config;
+        self.timer = None;
+    }
+
+    /// Get the number of queued dials.
+    pub(crate) fn num_queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Schedule `dial` to `addresses`.
+    ///
+    /// Returns the dial back if it can be started right away, otherwise the dial is queued and
+    /// returned from [`DialScheduler::poll_next_dial()`] once it can be started.
+    pub(crate) fn schedule<'a>(
+        &mut self,
+        connection_id: ConnectionId,
+        addresses: impl IntoIterator<Item = &'a Multiaddr>,
+        dial: T,
+    ) -> Option<T> {
+        let destinations = addresses.into_iter().filter_map(destination).collect::<Vec<_>>();
+        let now = Instant::now();
+
+        if self.queue.is_empty() && self.next_start(&destinations, now).is_some_and(|at| at <= now)
+        {
+            self.on_dial_started(connection_id, destinations, now);
+            return Some(dial);
+        }
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            ?connection_id,
+            queued = self.queue.len(),
+            "dial queued",
+        );
+
+        self.queue.push_back(QueuedDial {
+            connection_id,
+            destinations,
+            dial,
+        });
+        self.timer = None;
+
+        None
+    }
+
+    /// Release the dials in progress whose handshake has concluded.
+    ///
+    /// `is_pending` is called for each dial in progress and queued dial, and dials for which it
+    /// returns `false` are removed.
+    pub(crate) fn release(&mut self, is_pending: impl Fn(&ConnectionId) -> bool) {
+        self.in_progress.retain(|connection_id| is_pending(connection_id));
+        self.queue.retain(|dial| is_pending(&dial.connection_id));
+    }
+
+    /// Poll the next queued dial which can be started.
+    pub(crate) fn poll_next_dial(&mut self, cx: &mut Context<'_>) -> Poll<(ConnectionId, T)> {
+        loop {
+            let now = Instant::now();
+            let mut next_start: Option<Instant> = None;
+
+            for index in 0..self.queue.len() {
+                match self.next_start(&self.queue[index].destinations, now) {
+                    // concurrency limit has been reached, no dial can be started until one of
+                    // the dials in progress concludes
+                    None => return Poll::Pending,
+                    Some(at) if at <= now => {
+                        let QueuedDial {
+                            connection_id,
+                            destinations,
+                            dial,
+                        } = self.queue.remove(index).expect("entry to exist");
+
+                        self.on_dial_started(connection_id, destinations, now);
+                        self.timer = None;
+
+                        return Poll::Ready((connection_id, dial));
+                    }
+                    Some(at) => {
+                        next_start = Some(next_start.map_or(at, |next| next.min(at)));
+                    }
+                }
+            }
+
+            let Some(next_start) = next_start else {
+                return Poll::Pending;
+            };
+
+            let timer = self.timer.get_or_insert_with(|| {
+                Box::pin(tokio::time::sleep_until(tokio::time::Instant::from_std(
+                    next_start,
+                )))
+            });
+
+            futures::ready!(timer.as_mut().poll(cx));
+            self.timer = None;
+        }
+    }
+
+    /// Get the earliest time a dial to `destinations` can be started, or `None` if the
+    /// concurrency limit has been reached.
+    fn next_start(&self, destinations: &[Multiaddr], now: Instant) -> Option<Instant> {
+        if self.in_progress.len() >= self.config.max_concurrent_dials.max(1) {
+            return None;
+        }
+
+        let global = self.last_dial.map_or(now, |last| last + self.config.min_dial_interval);
+
+        Some(
+            destinations
+                .iter()
+                .filter_map(|destination| self.last_destination_dial.get(destination))
+                .map(|last| *last + self.config.min_destination_interval)
+                .fold(global, std::cmp::max),
+        )
+    }
+
+    /// Register dial which was started at `now`.
+    fn on_dial_started(
+        &mut self,
+        connection_id: ConnectionId,
+        destinations: Vec<Multiaddr>,
+        now: Instant,
+    ) {
+        let interval = self.config.min_destination_interval;
+
+        self.in_progress.insert(connection_id);
+        self.last_dial = Some(now);
+        self.last_destination_dial
+            .retain(|_, last| now.saturating_duration_since(*last) < interval);
+
+        if !interval.is_zero() {
+            self.last_destination_dial
+                .extend(destinations.into_iter().map(|destination| (destination, now)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::poll_fn;
+
+    fn address(ip: [u8; 4], port: u16) -> Multiaddr {
+        Multiaddr::empty().with(Protocol::Ip4(ip.into())).with(Protocol::Tcp(port))
+    }
+
+    #[tokio::test]
+    async fn concurrent_dials_are_capped() {
+        let mut scheduler = DialScheduler::new(Config {
+            max_concurrent_dials: 2,
+            ..Default::default()
+        });
+        let mut pending = HashSet::new();
+
+        for id in 0..3usize {
+            let address = address([1, 1, 1, id as u8], 8888);
+            let result = scheduler.schedule(ConnectionId::from(id), [&address], id);
+
+            if id < 2 {
+                assert_eq!(result, Some(id));
+                pending.insert(ConnectionId::from(id));
+            } else {
+                assert_eq!(result, None);
+            }
+        }
+        assert_eq!(scheduler.num_queued(), 1);
+
+        // queued dial is started once one of the dials in progress concludes
+        poll_fn(|cx| match scheduler.poll_next_dial(cx) {
+            Poll::Pending => Poll::Ready(()),
+            Poll::Ready(_) => panic!("dial started over the limit"),
+        })
+        .await;
+
+        pending.remove(&ConnectionId::from(0usize));
+        pending.insert(ConnectionId::from(2usize));
+        scheduler.release(|connection_id| pending.contains(connection_id));
+
+        let (connection_id, dial) = poll_fn(|cx| scheduler.poll_next_dial(cx)).await;
+        assert_eq!(connection_id, ConnectionId::from(2usize));
+        assert_eq!(dial, 2);
+        assert_eq!(scheduler.num_queued(), 0);
+    }
+
+    #[tokio::test]
+    async fn released_queued_dial_is_discarded() {
+        let mut scheduler = DialScheduler::new(Config {
+            max_concurrent_dials: 1,
+            ..Default::default()
+        });
+
+        let address = address([1, 1, 1, 1], 8888);
+        assert_eq!(
+            scheduler.schedule(ConnectionId::from(0usize), [&address], 0),
+            Some(0)
+        );
+        assert_eq!(
+            scheduler.schedule(ConnectionId::from(1usize), [&address], 1),
+            None
+        );
+
+        // the queued dial was resolved by other means, e.g., an inbound connection
+        scheduler.release(|connection_id| connection_id == &ConnectionId::from(0usize));
+        assert_eq!(scheduler.num_queued(), 0);
+    }
+
+    #[tokio::test]
+    async fn dials_to_same_destination_are_spaced_out() {
+        let mut scheduler = DialScheduler::new(Config {
+            min_destination_interval: Duration::from_millis(200),
+            ..Default::default()
+        });
+
+        let address1 = address([1, 1, 1, 1], 8888);
+        let address2 = address([1, 1, 1, 1], 9999);
+        let address3 = address([2, 2, 2, 2], 8888);
+
+        assert_eq!(
+            scheduler.schedule(ConnectionId::from(0usize), [&address1], 0),
+            Some(0)
+        );
+        assert_eq!(
+            scheduler.schedule(ConnectionId::from(1usize), [&address2], 1),
+            None
+        );
+
+        assert_eq!(
+            scheduler.schedule(ConnectionId::from(2usize), [&address3], 2),
+            None
+        );
+
+        // dial to another destination is not blocked by the queued dial
+        let started = Instant::now();
+        let (connection_id, _) = poll_fn(|cx| scheduler.poll_next_dial(cx)).await;
+        assert_eq!(connection_id, ConnectionId::from(2usize));
+
+        let (connection_id, _) = poll_fn(|cx| scheduler.poll_next_dial(cx)).await;
+        assert_eq!(connection_id, ConnectionId::from(1usize));
+        assert!(started.elapsed() >= Duration::from_millis(150));
+    }
+
+    #[tokio::test]
+    async fn dials_are_spaced_out() {
+        let mut scheduler = DialScheduler::new(Config {
+            min_dial_interval: Duration::from_millis(100),
+            ..Default::default()
+        });
+
+        let address1 = address([1, 1, 1, 1], 8888);
+        let address2 = address([2, 2, 2, 2], 8888);
+
+        assert_eq!(
+            scheduler.schedule(ConnectionId::from(0usize), [&address1], 0),
+            Some(0)
+        );
+        assert_eq!(
+            scheduler.schedule(ConnectionId::from(1usize), [&address2], 1),
+            None
+        );
+
+        let started = Instant::now();
+        let (connection_id, _) = poll_fn(|cx| scheduler.poll_next_dial(cx)).await;
+        assert_eq!(connection_id, ConnectionId::from(1usize));
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,189 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! DNS resolution with caching of the resolved addresses.
+
+use crate::error::Error;
+
+use multiaddr::{Multiaddr, Protocol};
+use parking_lot::RwLock;
+use trust_dns_resolver::{
+    config::{ResolverConfig, ResolverOpts},
+    TokioAsyncResolver,
+};
+
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Instant,
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::dns";
+
+/// Cached DNS lookup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsCacheEntry {
+    /// Resolved IP addresses.
+    pub addresses: Vec<IpAddr>,
+
+    /// Time after which the lookup must be performed again.
+    pub valid_until: Instant,
+}
+
+/// DNS resolver shared by all enabled transports.
+///
+/// Resolved addresses are cached until the TTL of the DNS records expires so repeated dials to
+/// the same DNS address don't cause the name to be resolved again.
+#[derive(Debug, Clone, Default)]
+pub struct DnsResolver {
+    /// Cached lookups.
+    cache: Arc<RwLock<HashMap<String, DnsCacheEntry>>>,
+}
+
+impl DnsResolver {
+    /// Create new [`DnsResolver`].
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the cached, unexpired lookups.
+    pub fn cached_entries(&self) -> HashMap<String, DnsCacheEntry> {
+        let now = Instant::now();
+
+        self.cache
+            .read()
+            .iter()
+            .filter(|(_, entry)| entry.valid_until > now)
+            .map(|(name, entry)| (name.clone(), entry.clone()))
+            .collect()
+    }
+
+    /// Remove all cached lookups.
+    pub fn clear_cache(&self) {
+        self.cache.write().clear();
+    }
+
+    /// Resolve `name` of DNS `address` into a socket address using `port`.
+    ///
+    /// The IP version of the returned socket address is selected based on the first protocol of
+    /// `address`.
+    pub(crate) async fn resolve(
+        &self,
+        address: &Multiaddr,
+        name: String,
+        port: u16,
+    ) -> crate::Result<SocketAddr> {
+        let cached = self
+            .cache
+            .read()
+            .get(&name)
+            .filter(|entry| entry.valid_until > Instant::now())
+            .map(|entry| entry.addresses.clone());
+
+        let addresses = match cached {
+            Some(addresses) => {
+                tracing::trace!(target: LOG_TARGET, ?address, "use cached lookup");
+                addresses
+            }
+            None => {
+                let lookup =
+                    TokioAsyncResolver::tokio(ResolverConfig::default(), ResolverOpts::default())
+                        .lookup_ip(name.clone())
+                        .await
+                        .map_err(|_| Error::Unknown)?;
+                let addresses = lookup.iter().collect::<Vec<_>>();
+
+                self.cache.write().insert(
+                    name,
+                    DnsCacheEntry {
+                        addresses: addresses.clone(),
+                        valid_until: lookup.valid_until(),
+                    },
+                );
+
+                addresses
+            }
+        };
+
+        for ip in addresses {
+            match (
+                address.iter().next().expect("protocol to exist"),
+                ip.is_ipv4(),
+            ) {
+                (Protocol::Dns(_), true)
+                | (Protocol::Dns4(_), true)
+                | (Protocol::Dns6(_), false) => {
+                    tracing::trace!(target: LOG_TARGET, ?address, ?ip, "address resolved");
+
+                    return Ok(SocketAddr::new(ip, port));
+                }
+                _ => {}
+            }
+        }
+
+        Err(Error::Unknown)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{net::Ipv4Addr, time::Duration};
+
+    #[tokio::test]
+    async fn cached_lookup_is_used() {
+        let resolver = DnsResolver::new();
+        let address: Multiaddr = "/dns4/example.invalid/tcp/8888".parse().unwrap();
+
+        resolver.cache.write().insert(
+            String::from("example.invalid"),
+            DnsCacheEntry {
+                addresses: vec![IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))],
+                valid_until: Instant::now() + Duration::from_secs(60),
+            },
+        );
+
+        assert_eq!(
+            resolver.resolve(&address, String::from("example.invalid"), 8888).await.unwrap(),
+            "127.0.0.1:8888".parse().unwrap(),
+        );
+        assert_eq!(resolver.cached_entries().len(), 1);
+
+        resolver.clear_cache();
+        assert!(resolver.cached_entries().is_empty());
+    }
+
+    #[tokio::test]
+    async fn expired_lookup_is_not_reported() {
+        let resolver = DnsResolver::new();
+
+        resolver.cache.write().insert(
+            String::from("example.invalid"),
+            DnsCacheEntry {
+                addresses: vec![IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))],
+                valid_until: Instant::now(),
+            },
+        );
+
+        assert!(resolver.cached_entries().is_empty());
+    }
+}
@@ -38,6 +38,9 @@ use std::{
 pub(crate) struct DummyTransport {
     /// Events.
     events: VecDeque<TransportEvent>,
+
+    /// Has the transport been closed.
+    closed: bool,
 }
 
 impl DummyTransport {
@@ -46,6 +49,7 @@ impl DummyTransport {
     pub(crate) fn new() -> Self {
         Self {
             events: VecDeque::new(),
+            closed: false,
         }
     }
 
@@ -54,6 +58,12 @@ impl DummyTransport {
     pub(crate) fn inject_event(&mut self, event: TransportEvent) {
         self.events.push_back(event);
     }
+
+    /// Close `DummyTransport` after the injected events have been returned.
+    #[cfg(test)]
+    pub(crate) fn close(&mut self) {
+        self.closed = true;
+    }
 }
 
 impl Stream for DummyTransport {
@@ -61,7 +71,10 @@ impl Stream for DummyTransport {
 
     fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         if self.events.is_empty() {
-            return Poll::Pending;
+            return match self.closed {
+                true => Poll::Ready(None),
+                false => Poll::Pending,
+            };
         }
 
         Poll::Ready(self.events.pop_front())
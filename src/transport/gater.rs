@@ -0,0 +1,54 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Connection gating.
+//!
+//! [`ConnectionGater`] allows the user to reject inbound connections before they're reported
+//! to the installed protocols, either as soon as the socket is accepted, based on the remote
+//! address, or after the handshake has completed, based on the remote's `PeerId`.
+
+use crate::PeerId;
+
+use multiaddr::Multiaddr;
+
+/// Connection gater.
+///
+/// The methods are called from the transports and the transport manager so they must return
+/// quickly and must not block.
+///
+/// By default all connections are allowed.
+pub trait ConnectionGater: Send + Sync {
+    /// Called when an inbound socket is accepted from `address`.
+    ///
+    /// Returning `false` closes the socket before the handshake is started.
+    fn allow_inbound_address(&self, _address: &Multiaddr) -> bool {
+        true
+    }
+
+    /// Called when the handshake of an inbound connection from `address` has completed and
+    /// the remote has been authenticated as `peer`.
+    ///
+    /// Returning `false` closes the connection before any protocol is notified of it and
+    /// [`Litep2pEvent::ConnectionRejected`](crate::Litep2pEvent::ConnectionRejected) is emitted
+    /// with [`RejectReason::Gated`](crate::transport::RejectReason::Gated).
+    fn allow_inbound_peer(&self, _peer: &PeerId, _address: &Multiaddr) -> bool {
+        true
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Network interface monitor.
+
+use futures::Stream;
+use network_interface::{Addr, NetworkInterface, NetworkInterfaceConfig};
+use tokio::time::{Instant, Interval, MissedTickBehavior};
+
+use std::{
+    collections::{HashSet, VecDeque},
+    net::IpAddr,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::interfaces";
+
+/// Change in the addresses of network interfaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum InterfaceEvent {
+    /// Address was added to a network interface.
+    Up(IpAddr),
+
+    /// Address was removed from a network interface.
+    Down(IpAddr),
+}
+
+/// Network interface monitor.
+///
+/// Polls the addresses of network interfaces periodically and reports the changes.
+pub(crate) struct InterfaceMonitor {
+    /// Current interface addresses.
+    addresses: HashSet<IpAddr>,
+
+    /// Poll interval.
+    interval: Interval,
+
+    /// Pending events.
+    pending_events: VecDeque<InterfaceEvent>,
+}
+
+impl InterfaceMonitor {
+    /// Create new [`InterfaceMonitor`].
+    pub(crate) fn new(poll_interval: Duration) -> Self {
+        let mut interval = tokio::time::interval_at(Instant::now() + poll_interval, poll_interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        Self {
+            interval,
+            addresses: Self::interface_addresses().unwrap_or_default(),
+            pending_events: VecDeque::new(),
+        }
+    }
+
+    /// Get addresses of all network interfaces, excluding link-local IPv6 addresses.
+    pub(crate) fn interface_addresses() -> crate::Result<HashSet<IpAddr>> {
+        let interfaces = NetworkInterface::show().map_err(|error| {
+            tracing::warn!(target: LOG_TARGET, ?error, "failed to fetch network interfaces");
+            crate::Error::Other(error.to_string())
+        })?;
+
+        Ok(interfaces
+            .into_iter()
+            .flat_map(|interface| interface.addr.into_iter())
+            .filter_map(|address| match address {
+                Addr::V4(inner) => Some(IpAddr::V4(inner.ip)),
+                Addr::V6(inner) => match inner.ip.segments().first() {
+                    Some(0xfe80) => None,
+                    _ => Some(IpAddr::V6(inner.ip)),
+                },
+            })
+            .collect())
+    }
+
+    /// Update interface addresses and queue events for the addresses that changed.
+    fn on_interface_addresses(&mut self, addresses: HashSet<IpAddr>) {
+        for address in self.addresses.difference(&addresses) {
+            tracing::debug!(target: LOG_TARGET, ?address, "interface address removed");
+            self.pending_events.push_back(InterfaceEvent::Down(*address));
+        }
+
+        for address in addresses.difference(&self.addresses) {
+            tracing::debug!(target: LOG_TARGET, ?address, "interface address added");
+            self.pending_events.push_back(InterfaceEvent::Up(*address));
+        }
+
+        self.addresses = addresses;
+    }
+}
+
+impl Stream for InterfaceMonitor {
+    type Item = InterfaceEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(event) = self.pending_events.pop_front() {
+                return Poll::Ready(Some(event));
+            }
+
+            futures::ready!(self.interval.poll_tick(cx));
+
+            if let Ok(addresses) = Self::interface_addresses() {
+                self.on_interface_addresses(addresses);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    #[tokio::test]
+    async fn address_changes_reported() {
+        let mut monitor = InterfaceMonitor::new(Duration::from_secs(3600));
+        let address1 = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1));
+        let address2 = IpAddr::V6(Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 1));
+
+        monitor.addresses = HashSet::from_iter([address1]);
+        monitor.on_interface_addresses(HashSet::from_iter([address2]));
+
+        let mut events = vec![monitor.next().await.unwrap(), monitor.next().await.unwrap()];
+        events.sort_by_key(|event| matches!(event, InterfaceEvent::Up(_)));
+
+        assert_eq!(
+            events,
+            vec![InterfaceEvent::Down(address1), InterfaceEvent::Up(address2)]
+        );
+        assert!(monitor.pending_events.is_empty());
+    }
+}
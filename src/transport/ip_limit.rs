@@ -0,0 +1,154 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Limits for inbound connections originating from the same IP address or subnet.
+
+use crate::transport::connection_rate::Source;
+
+use multiaddr::{Multiaddr, Protocol};
+
+use std::net::IpAddr;
+
+/// Configuration for limiting inbound connections per IP address and per subnet.
+///
+/// Inbound connections which would exceed either limit are rejected right after the handshake
+/// with [`RejectReason::IpLimit`](crate::transport::RejectReason::IpLimit), unless a less
+/// reputable peer has an inbound connection counted towards the same limit, in which case that
+/// connection is closed instead. See [`crate::reputation`] for more details. Outbound connections
+/// are neither limited nor counted towards the limits.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Maximum number of connections from one IP address.
+    ///
+    /// By default the limit is disabled.
+    pub max_connections_per_ip: Option<usize>,
+
+    /// Maximum number of connections from one subnet.
+    ///
+    /// By default the limit is disabled.
+    pub max_connections_per_subnet: Option<usize>,
+
+    /// Prefix length used to group IPv4 addresses into subnets.
+    ///
+    /// By default IPv4 addresses are grouped by their `/24` subnet.
+    pub ipv4_prefix: u8,
+
+    /// Prefix length used to group IPv6 addresses into subnets.
+    ///
+    /// By default IPv6 addresses are grouped by their `/48` subnet.
+    pub ipv6_prefix: u8,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_connections_per_ip: None,
+            max_connections_per_subnet: None,
+            ipv4_prefix: 24,
+            ipv6_prefix: 48,
+        }
+    }
+}
+
+impl Config {
+    /// Get subnet of `address`.
+    fn subnet(&self, address: IpAddr) -> Source {
+        match address {
+            IpAddr::V4(_) => Source::new(address, self.ipv4_prefix),
+            IpAddr::V6(_) => Source::new(address, self.ipv6_prefix),
+        }
+    }
+
+    /// Check if a new connection from `address` is within the limits, given the addresses of
+    /// the open inbound connections.
+    ///
+    /// Addresses which don't start with an IP address are not limited.
+    pub(crate) fn is_within_limits<'a>(
+        &self,
+        address: &Multiaddr,
+        open: impl Iterator<Item = &'a Multiaddr>,
+    ) -> bool {
+        let Some(ip) = ip_address(address) else {
+            return true;
+        };
+        let subnet = self.subnet(ip);
+        let (mut same_ip, mut same_subnet) = (0usize, 0usize);
+
+        for open in open.filter_map(ip_address) {
+            same_ip += usize::from(open == ip);
+            same_subnet += usize::from(subnet.contains(open));
+        }
+
+        self.max_connections_per_ip.is_none_or(|limit| same_ip < limit)
+            && self.max_connections_per_subnet.is_none_or(|limit| same_subnet < limit)
+    }
+
+    /// Check if a connection from `open` counts towards a limit the connection from `address`
+    /// is subject to.
+    pub(crate) fn shares_limit(&self, address: &Multiaddr, open: &Multiaddr) -> bool {
+        let (Some(ip), Some(open)) = (ip_address(address), ip_address(open)) else {
+            return false;
+        };
+
+        (self.max_connections_per_ip.is_some() && ip == open)
+            || (self.max_connections_per_subnet.is_some() && self.subnet(ip).contains(open))
+    }
+}
+
+/// Extract IP address from `address`.
+fn ip_address(address: &Multiaddr) -> Option<IpAddr> {
+    match address.iter().next() {
+        Some(Protocol::Ip4(ip)) => Some(IpAddr::V4(ip)),
+        Some(Protocol::Ip6(ip)) => Some(IpAddr::V6(ip)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ip_and_subnet_limits() {
+        let config = Config {
+            max_connections_per_ip: Some(2),
+            max_connections_per_subnet: Some(3),
+            ..Default::default()
+        };
+        let address = |address: &str| address.parse::<Multiaddr>().unwrap();
+        let open = vec![
+            address("/ip4/10.0.0.1/tcp/1"),
+            address("/ip4/10.0.0.2/tcp/1"),
+            address("/ip4/10.0.1.1/tcp/1"),
+        ];
+
+        assert!(config.is_within_limits(&address("/ip4/10.0.0.1/tcp/2"), open.iter()));
+        assert!(!config.is_within_limits(
+            &address("/ip4/10.0.0.1/tcp/2"),
+            open.iter().chain([&open[0]])
+        ));
+        assert!(!config.is_within_limits(
+            &address("/ip4/10.0.0.3/tcp/2"),
+            open.iter().chain([&open[0]])
+        ));
+        assert!(config.is_within_limits(&address("/ip4/10.0.1.2/tcp/2"), open.iter()));
+        assert!(config.is_within_limits(&address("/dns/example.com/tcp/2"), open.iter()));
+    }
+}
@@ -0,0 +1,163 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Limits which can be adjusted while the node is running.
+//!
+//! The limits are initialized from [`ConfigBuilder`](crate::config::ConfigBuilder) and can be
+//! changed later through a [`LimitsHandle`] obtained from
+//! [`Litep2p::limits()`](crate::Litep2p::limits), e.g., to loosen them during an incident without
+//! restarting the node.
+
+use crate::transport::{
+    connection_rate::{Config as ConnectionRateConfig, ConnectionRates},
+    ip_limit::Config as IpLimitConfig,
+};
+
+use parking_lot::RwLock;
+
+use std::{sync::Arc, time::Duration};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::limits";
+
+/// Current values of the runtime-adjustable limits.
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    /// Limits for inbound connections per IP address and subnet.
+    ///
+    /// See [`ConfigBuilder::with_ip_limits()`](crate::config::ConfigBuilder::with_ip_limits)
+    /// for more details.
+    pub ip_limits: Option<IpLimitConfig>,
+
+    /// Maximum number of open substreams per connection.
+    ///
+    /// See
+    /// [`ConfigBuilder::with_max_substreams_per_connection()`](crate::config::ConfigBuilder::with_max_substreams_per_connection)
+    /// for more details.
+    pub max_substreams: Option<usize>,
+
+    /// Keep-alive timeout of idle connections.
+    ///
+    /// See
+    /// [`ConfigBuilder::with_keep_alive_timeout()`](crate::config::ConfigBuilder::with_keep_alive_timeout)
+    /// for more details.
+    pub keep_alive_timeout: Option<Duration>,
+}
+
+/// Handle for adjusting the limits of a running node.
+///
+/// The IP limits and the connection rate configuration apply to inbound connections accepted
+/// after the change. The substream limit and the keep-alive timeout are set when a connection is
+/// established so they apply to connections established after the change.
+///
+/// Cloning is cheap and all clones refer to the same limits.
+#[derive(Debug, Clone, Default)]
+pub struct LimitsHandle {
+    /// Current limits.
+    limits: Arc<RwLock<Limits>>,
+
+    /// Inbound connection establishment rates.
+    connection_rates: ConnectionRates,
+}
+
+impl LimitsHandle {
+    /// Create new [`LimitsHandle`].
+    pub(crate) fn new(connection_rates: ConnectionRates) -> Self {
+        Self {
+            limits: Default::default(),
+            connection_rates,
+        }
+    }
+
+    /// Get the current limits.
+    pub fn limits(&self) -> Limits {
+        self.limits.read().clone()
+    }
+
+    /// Set limits for inbound connections per IP address and subnet.
+    ///
+    /// `None` disables the limits.
+    pub fn set_ip_limits(&self, config: Option<IpLimitConfig>) {
+        tracing::debug!(target: LOG_TARGET, ?config, "set ip limits");
+
+        self.limits.write().ip_limits = config;
+    }
+
+    /// Set the maximum number of open substreams per connection.
+    ///
+    /// `None` disables the limit.
+    pub fn set_max_substreams(&self, max_substreams: Option<usize>) {
+        tracing::debug!(target: LOG_TARGET, ?max_substreams, "set substream limit");
+
+        self.limits.write().max_substreams = max_substreams;
+    }
+
+    /// Set the keep-alive timeout of idle connections.
+    ///
+    /// `None` keeps idle connections open until they're closed by a protocol or the remote peer.
+    pub fn set_keep_alive_timeout(&self, timeout: Option<Duration>) {
+        tracing::debug!(target: LOG_TARGET, ?timeout, "set keep-alive timeout");
+
+        self.limits.write().keep_alive_timeout = timeout;
+    }
+
+    /// Set the configuration for tracking inbound connection establishment rates.
+    ///
+    /// The statistics collected with the previous configuration are discarded.
+    pub fn set_connection_rate_config(&self, config: ConnectionRateConfig) {
+        tracing::debug!(target: LOG_TARGET, ?config, "set connection rate config");
+
+        self.connection_rates.set_config(config);
+    }
+
+    /// Get limits for inbound connections per IP address and subnet.
+    pub(crate) fn ip_limits(&self) -> Option<IpLimitConfig> {
+        self.limits.read().ip_limits.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_shared_between_clones() {
+        let handle = LimitsHandle::default();
+        let clone = handle.clone();
+
+        clone.set_max_substreams(Some(16));
+        clone.set_keep_alive_timeout(Some(Duration::from_secs(5)));
+        clone.set_ip_limits(Some(IpLimitConfig {
+            max_connections_per_ip: Some(2),
+            ..Default::default()
+        }));
+
+        let limits = handle.limits();
+        assert_eq!(limits.max_substreams, Some(16));
+        assert_eq!(limits.keep_alive_timeout, Some(Duration::from_secs(5)));
+        assert_eq!(
+            handle.ip_limits().and_then(|config| config.max_connections_per_ip),
+            Some(2)
+        );
+
+        handle.set_ip_limits(None);
+        assert!(clone.limits().ip_limits.is_none());
+    }
+}
@@ -0,0 +1,112 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Local-only operation mode.
+//!
+//! If enabled with
+//! [`ConfigBuilder::with_local_only()`](crate::config::ConfigBuilder::with_local_only), the
+//! local node only dials and listens on loopback and private addresses.
+
+use multiaddr::{Multiaddr, Protocol};
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Check if `ip` is a loopback, private or link-local address.
+fn is_local_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+        IpAddr::V6(ip) =>
+            ip.is_loopback()
+                // unique local, `fc00::/7`
+                || (ip.segments()[0] & 0xfe00) == 0xfc00
+                // link-local, `fe80::/10`
+                || (ip.segments()[0] & 0xffc0) == 0xfe80,
+    }
+}
+
+/// Check if `address` is a local address.
+///
+/// DNS addresses are only considered local if they refer to `localhost`.
+pub(crate) fn is_local_address(address: &Multiaddr) -> bool {
+    match address.iter().next() {
+        Some(Protocol::Ip4(ip)) => is_local_ip(IpAddr::V4(ip)),
+        Some(Protocol::Ip6(ip)) => is_local_ip(IpAddr::V6(ip)),
+        Some(Protocol::Dns(host) | Protocol::Dns4(host) | Protocol::Dns6(host)) =>
+            host == "localhost",
+        _ => false,
+    }
+}
+
+/// Restrict listen `address` to local addresses.
+///
+/// Unspecified addresses are replaced with the loopback address of the same IP version.
+/// Returns `None` if the address is not local.
+pub(crate) fn local_listen_address(address: Multiaddr) -> Option<Multiaddr> {
+    let restricted = match address.iter().next()? {
+        Protocol::Ip4(ip) if ip.is_unspecified() =>
+            address.replace(0, |_| Some(Protocol::Ip4(Ipv4Addr::LOCALHOST)))?,
+        Protocol::Ip6(ip) if ip.is_unspecified() =>
+            address.replace(0, |_| Some(Protocol::Ip6(Ipv6Addr::LOCALHOST)))?,
+        _ => address,
+    };
+
+    is_local_address(&restricted).then_some(restricted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_addresses() {
+        let address = |address: &str| address.parse::<Multiaddr>().unwrap();
+
+        assert!(is_local_address(&address("/ip4/127.0.0.1/tcp/1")));
+        assert!(is_local_address(&address("/ip4/10.0.0.1/tcp/1")));
+        assert!(is_local_address(&address("/ip4/192.168.1.1/udp/1/quic-v1")));
+        assert!(is_local_address(&address("/ip6/::1/tcp/1")));
+        assert!(is_local_address(&address("/ip6/fd00::1/tcp/1")));
+        assert!(is_local_address(&address("/dns/localhost/tcp/1")));
+
+        assert!(!is_local_address(&address("/ip4/1.1.1.1/tcp/1")));
+        assert!(!is_local_address(&address("/ip4/0.0.0.0/tcp/1")));
+        assert!(!is_local_address(&address("/ip6/2001:db8::1/tcp/1")));
+        assert!(!is_local_address(&address("/dns/example.com/tcp/1")));
+    }
+
+    #[test]
+    fn listen_addresses_restricted() {
+        let address = |address: &str| address.parse::<Multiaddr>().unwrap();
+
+        assert_eq!(
+            local_listen_address(address("/ip4/0.0.0.0/tcp/0")),
+            Some(address("/ip4/127.0.0.1/tcp/0"))
+        );
+        assert_eq!(
+            local_listen_address(address("/ip6/::/tcp/0/ws")),
+            Some(address("/ip6/::1/tcp/0/ws"))
+        );
+        assert_eq!(
+            local_listen_address(address("/ip4/10.0.0.1/tcp/0")),
+            Some(address("/ip4/10.0.0.1/tcp/0"))
+        );
+        assert_eq!(local_listen_address(address("/ip4/1.1.1.1/tcp/0")), None);
+    }
+}
@@ -22,17 +22,28 @@ use crate::{
     crypto::ed25519::Keypair,
     error::{AddressError, Error},
     executor::Executor,
-    protocol::ProtocolSet,
-    transport::manager::{
-        address::{AddressRecord, AddressStore},
-        types::{PeerContext, PeerState, SupportedTransport},
-        ProtocolContext, TransportManagerEvent, LOG_TARGET,
+    external_addresses::{AddressConfidence, ExternalAddresses, PublicAddress},
+    peer_store::PeerStore,
+    protocol::{ProtocolSet, SlowProtocolPolicy},
+    reputation::Reputation,
+    transport::{
+        connection_rate::ConnectionRates,
+        dns::DnsResolver,
+        gater::ConnectionGater,
+        limits::LimitsHandle,
+        manager::{
+            address::{AddressRecord, AddressStore},
+            types::{PeerContext, PeerState, SupportedTransport},
+            ProtocolRegistry, TransportManagerEvent, LOG_TARGET,
+        },
+        quic::QuicConnections,
     },
     types::{protocol::ProtocolName, ConnectionId},
     BandwidthSink, PeerId,
 };
 
 use multiaddr::{Multiaddr, Protocol};
+use multihash::Multihash;
 use parking_lot::RwLock;
 use tokio::sync::mpsc::{error::TrySendError, Sender};
 
@@ -42,6 +53,7 @@ use std::{
         atomic::{AtomicUsize, Ordering},
         Arc,
     },
+    time::Duration,
 };
 
 /// Inner commands sent from [`TransportManagerHandle`] to
@@ -51,12 +63,66 @@ pub enum InnerTransportManagerCommand {
     DialPeer {
         /// Remote peer ID.
         peer: PeerId,
+
+        /// Protocol which requested the dial.
+        protocol: ProtocolName,
     },
 
     /// Dial address.
     DialAddress {
         /// Remote address.
         address: Multiaddr,
+
+        /// Protocol which requested the dial.
+        protocol: ProtocolName,
+    },
+
+    /// Result of probing a newly established connection.
+    ConnectionProbed {
+        /// Remote peer ID.
+        peer: PeerId,
+
+        /// Did the probe succeed.
+        success: bool,
+    },
+
+    /// Close all connections to peer.
+    Disconnect {
+        /// Remote peer ID.
+        peer: PeerId,
+    },
+
+    /// Ban peer.
+    BanPeer {
+        /// Remote peer ID.
+        peer: PeerId,
+
+        /// Duration of the ban.
+        duration: Duration,
+    },
+
+    /// Mark peer as persistent.
+    AddPersistentPeer {
+        /// Remote peer ID.
+        peer: PeerId,
+    },
+
+    /// Stop reconnecting to peer.
+    RemovePersistentPeer {
+        /// Remote peer ID.
+        peer: PeerId,
+    },
+
+    /// Mark peer as reserved.
+    AddReservedPeer {
+        /// Remote peer ID.
+        peer: PeerId,
+    },
+
+    /// Remove peer from reserved peers.
+    RemoveReservedPeer {
+        /// Remote peer ID.
+        peer: PeerId,
     },
 }
 
@@ -77,6 +143,21 @@ pub struct TransportManagerHandle {
 
     /// Local listen addresess.
     listen_addresses: Arc<RwLock<HashSet<Multiaddr>>>,
+
+    /// Open QUIC connections.
+    quic_connections: QuicConnections,
+
+    /// Peer store.
+    peer_store: PeerStore,
+
+    /// External addresses of the local node.
+    external_addresses: ExternalAddresses,
+
+    /// Reputations of remote peers.
+    reputation: Reputation,
+
+    /// Runtime-adjustable limits.
+    limits: LimitsHandle,
 }
 
 impl TransportManagerHandle {
@@ -87,6 +168,7 @@ impl TransportManagerHandle {
         cmd_tx: Sender<InnerTransportManagerCommand>,
         supported_transport: HashSet<SupportedTransport>,
         listen_addresses: Arc<RwLock<HashSet<Multiaddr>>>,
+        limits: LimitsHandle,
     ) -> Self {
         Self {
             peers,
@@ -94,7 +176,84 @@ impl TransportManagerHandle {
             local_peer_id,
             listen_addresses,
             supported_transport,
+            quic_connections: Default::default(),
+            peer_store: PeerStore::new(),
+            external_addresses: ExternalAddresses::new(local_peer_id),
+            reputation: Reputation::new(),
+            limits,
+        }
+    }
+
+    /// Get peer store.
+    pub(crate) fn peer_store(&self) -> &PeerStore {
+        &self.peer_store
+    }
+
+    /// Get external addresses of the local node.
+    pub(crate) fn external_addresses(&self) -> &ExternalAddresses {
+        &self.external_addresses
+    }
+
+    /// Get the addresses through which the local node believes it's reachable.
+    ///
+    /// The listen addresses are merged with the confirmed external addresses. An address which
+    /// is both is reported once, with the higher confidence. Listen addresses bound to an
+    /// unspecified IP address are skipped as remote peers can't dial them.
+    ///
+    /// The addresses are sorted from the highest confidence to the lowest.
+    pub(crate) fn public_addresses(&self) -> Vec<PublicAddress> {
+        let mut addresses = self
+            .listen_addresses
+            .read()
+            .iter()
+            .filter(|address| std::matches!(address.iter().last(), Some(Protocol::P2p(_))))
+            .filter(|address| match address.iter().next() {
+                Some(Protocol::Ip4(ip)) => !ip.is_unspecified(),
+                Some(Protocol::Ip6(ip)) => !ip.is_unspecified(),
+                _ => true,
+            })
+            .map(|address| (address.clone(), AddressConfidence::Listen))
+            .collect::<HashMap<_, _>>();
+
+        for address in self.external_addresses.confirmed() {
+            addresses.insert(address, AddressConfidence::Confirmed);
         }
+
+        let mut addresses = addresses
+            .into_iter()
+            .map(|(address, confidence)| PublicAddress {
+                address,
+                confidence,
+            })
+            .collect::<Vec<_>>();
+        addresses.sort_by_key(|address| std::cmp::Reverse(address.confidence));
+
+        addresses
+    }
+
+    /// Get handle for adjusting the runtime-adjustable limits.
+    pub(crate) fn limits(&self) -> &LimitsHandle {
+        &self.limits
+    }
+
+    /// Get reputations of remote peers.
+    pub(crate) fn reputation(&self) -> &Reputation {
+        &self.reputation
+    }
+
+    /// Get open QUIC connections.
+    pub(crate) fn quic_connections(&self) -> QuicConnections {
+        Arc::clone(&self.quic_connections)
+    }
+
+    /// Get the measured round-trip time of the connection.
+    ///
+    /// Round-trip time is only measured for QUIC connections.
+    pub(crate) fn connection_rtt(&self, connection_id: &ConnectionId) -> Option<Duration> {
+        self.quic_connections
+            .read()
+            .get(connection_id)
+            .map(|connection| connection.rtt())
     }
 
     /// Register new transport to [`TransportManagerHandle`].
@@ -102,6 +261,11 @@ impl TransportManagerHandle {
         self.supported_transport.insert(transport);
     }
 
+    /// Unregister stopped transport from [`TransportManagerHandle`].
+    pub(crate) fn unregister_transport(&mut self, transport: SupportedTransport) {
+        self.supported_transport.remove(&transport);
+    }
+
     /// Check if `address` is supported by one of the enabled transports.
     pub fn supported_transport(&self, address: &Multiaddr) -> bool {
         let mut iter = address.iter();
@@ -135,7 +299,7 @@ impl TransportManagerHandle {
                 iter.next(),
                 self.supported_transport.contains(&SupportedTransport::Quic),
             ) {
-                (Some(Protocol::QuicV1), true) => true,
+                (Some(Protocol::QuicV1) | Some(Protocol::Quic), true) => true,
                 _ => false,
             },
             _ => false,
@@ -154,7 +318,9 @@ impl TransportManagerHandle {
 
     /// Add one or more known addresses for peer.
     ///
-    /// If peer doesn't exist, it will be added to known peers.
+    /// If peer doesn't exist, it will be added to known peers. Addresses that don't end in
+    /// `/p2p/<peer>` are extended with the `PeerId` of `peer` and addresses that belong to
+    /// another peer are ignored.
     ///
     /// Returns the number of added addresses after non-supported transports were filtered out.
     pub fn add_known_address(
@@ -165,6 +331,23 @@ impl TransportManagerHandle {
         let mut peers = self.peers.write();
         let addresses = addresses
             .filter_map(|address| {
+                let address = match address.iter().last() {
+                    Some(Protocol::P2p(multihash)) => {
+                        if PeerId::from_multihash(multihash).ok() != Some(*peer) {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?peer,
+                                ?address,
+                                "address belongs to another peer",
+                            );
+                            return None;
+                        }
+
+                        address
+                    }
+                    _ => address.with(Protocol::P2p(Multihash::from(*peer))),
+                };
+
                 (self.supported_transport(&address) && !self.is_local_address(&address))
                     .then_some(AddressRecord::from_multiaddr(address)?)
             })
@@ -188,6 +371,10 @@ impl TransportManagerHandle {
             ?addresses,
             "add known addresses",
         );
+        self.peer_store.add_addresses(
+            *peer,
+            addresses.iter().map(|record| record.address().clone()),
+        );
 
         match peers.get_mut(&peer) {
             Some(context) =>
@@ -211,10 +398,26 @@ impl TransportManagerHandle {
         num_added
     }
 
-    /// Dial peer using `PeerId`.
+    /// Get connected peers and the number of connections open to each of them.
+    pub fn connected_peers(&self) -> HashMap<PeerId, usize> {
+        self.peers
+            .read()
+            .iter()
+            .filter_map(|(peer, context)| match context.state {
+                PeerState::Connected { .. } => Some((
+                    *peer,
+                    1 + usize::from(context.secondary_connection.is_some()),
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Dial peer using `PeerId` on behalf of `protocol`.
     ///
-    /// Returns an error if the peer is unknown or the peer is already connected.
-    pub fn dial(&self, peer: &PeerId) -> crate::Result<()> {
+    /// Returns an error if the peer is unknown or the peer is already connected. If the dial
+    /// fails once the transport manager processes it, `protocol` is notified of the failure.
+    pub fn dial(&self, peer: &PeerId, protocol: ProtocolName) -> crate::Result<()> {
         if peer == &self.local_peer_id {
             return Err(Error::TriedToDialSelf);
         }
@@ -254,23 +457,97 @@ impl TransportManagerHandle {
         }
 
         self.cmd_tx
-            .try_send(InnerTransportManagerCommand::DialPeer { peer: *peer })
+            .try_send(InnerTransportManagerCommand::DialPeer {
+                peer: *peer,
+                protocol,
+            })
             .map_err(|error| match error {
                 TrySendError::Full(_) => Error::ChannelClogged,
                 TrySendError::Closed(_) => Error::EssentialTaskClosed,
             })
     }
 
-    /// Dial peer using `Multiaddr`.
+    /// Dial peer using `Multiaddr` on behalf of `protocol`.
     ///
-    /// Returns an error if address it not valid.
-    pub fn dial_address(&self, address: Multiaddr) -> crate::Result<()> {
+    /// Returns an error if address it not valid. If the dial fails once the transport manager
+    /// processes it, `protocol` is notified of the failure.
+    pub fn dial_address(&self, address: Multiaddr, protocol: ProtocolName) -> crate::Result<()> {
         if !address.iter().any(|protocol| std::matches!(protocol, Protocol::P2p(_))) {
             return Err(Error::AddressError(AddressError::PeerIdMissing));
         }
 
         self.cmd_tx
-            .try_send(InnerTransportManagerCommand::DialAddress { address })
+            .try_send(InnerTransportManagerCommand::DialAddress { address, protocol })
+            .map_err(|error| match error {
+                TrySendError::Full(_) => Error::ChannelClogged,
+                TrySendError::Closed(_) => Error::EssentialTaskClosed,
+            })
+    }
+
+    /// Close all connections to `peer`.
+    pub fn disconnect(&self, peer: PeerId) -> crate::Result<()> {
+        self.cmd_tx
+            .try_send(InnerTransportManagerCommand::Disconnect { peer })
+            .map_err(|error| match error {
+                TrySendError::Full(_) => Error::ChannelClogged,
+                TrySendError::Closed(_) => Error::EssentialTaskClosed,
+            })
+    }
+
+    /// Ban `peer` for `duration`.
+    pub fn ban_peer(&self, peer: PeerId, duration: Duration) -> crate::Result<()> {
+        self.cmd_tx
+            .try_send(InnerTransportManagerCommand::BanPeer { peer, duration })
+            .map_err(|error| match error {
+                TrySendError::Full(_) => Error::ChannelClogged,
+                TrySendError::Closed(_) => Error::EssentialTaskClosed,
+            })
+    }
+
+    /// Mark `peer` as persistent.
+    pub fn add_persistent_peer(&self, peer: PeerId) -> crate::Result<()> {
+        self.cmd_tx
+            .try_send(InnerTransportManagerCommand::AddPersistentPeer { peer })
+            .map_err(|error| match error {
+                TrySendError::Full(_) => Error::ChannelClogged,
+                TrySendError::Closed(_) => Error::EssentialTaskClosed,
+            })
+    }
+
+    /// Stop reconnecting to `peer`.
+    pub fn remove_persistent_peer(&self, peer: PeerId) -> crate::Result<()> {
+        self.cmd_tx
+            .try_send(InnerTransportManagerCommand::RemovePersistentPeer { peer })
+            .map_err(|error| match error {
+                TrySendError::Full(_) => Error::ChannelClogged,
+                TrySendError::Closed(_) => Error::EssentialTaskClosed,
+            })
+    }
+
+    /// Mark `peer` as reserved.
+    pub fn add_reserved_peer(&self, peer: PeerId) -> crate::Result<()> {
+        self.cmd_tx
+            .try_send(InnerTransportManagerCommand::AddReservedPeer { peer })
+            .map_err(|error| match error {
+                TrySendError::Full(_) => Error::ChannelClogged,
+                TrySendError::Closed(_) => Error::EssentialTaskClosed,
+            })
+    }
+
+    /// Remove `peer` from reserved peers.
+    pub fn remove_reserved_peer(&self, peer: PeerId) -> crate::Result<()> {
+        self.cmd_tx
+            .try_send(InnerTransportManagerCommand::RemoveReservedPeer { peer })
+            .map_err(|error| match error {
+                TrySendError::Full(_) => Error::ChannelClogged,
+                TrySendError::Closed(_) => Error::EssentialTaskClosed,
+            })
+    }
+
+    /// Report the result of probing the connection to `peer`.
+    pub(crate) fn report_connection_probe(&self, peer: PeerId, success: bool) -> crate::Result<()> {
+        self.cmd_tx
+            .try_send(InnerTransportManagerCommand::ConnectionProbed { peer, success })
             .map_err(|error| match error {
                 TrySendError::Full(_) => Error::ChannelClogged,
                 TrySendError::Closed(_) => Error::EssentialTaskClosed,
@@ -280,28 +557,67 @@ impl TransportManagerHandle {
 
 // TODO: add getters for these
 pub struct TransportHandle {
+    pub transport: SupportedTransport,
     pub keypair: Keypair,
     pub tx: Sender<TransportManagerEvent>,
-    pub protocols: HashMap<ProtocolName, ProtocolContext>,
+    pub protocols: ProtocolRegistry,
     pub next_connection_id: Arc<AtomicUsize>,
     pub next_substream_id: Arc<AtomicUsize>,
     pub protocol_names: Vec<ProtocolName>,
     pub bandwidth_sink: BandwidthSink,
     pub executor: Arc<dyn Executor>,
+    pub dns_resolver: DnsResolver,
+    pub connection_rates: ConnectionRates,
+    pub connection_gater: Option<Arc<dyn ConnectionGater>>,
+    pub quic_connections: QuicConnections,
+    pub max_connection_lifetime: Option<Duration>,
+    pub limits: LimitsHandle,
+    pub reserved_peers: Arc<RwLock<HashSet<PeerId>>>,
+    pub slow_protocol_policy: SlowProtocolPolicy,
+    pub channel_size: usize,
 }
 
 impl TransportHandle {
     pub fn protocol_set(&self, connection_id: ConnectionId) -> ProtocolSet {
-        ProtocolSet::new(
+        let (version, protocols) = self.protocols.protocols();
+        let mut protocol_set = ProtocolSet::new(
             connection_id,
+            self.transport,
             self.tx.clone(),
             self.next_substream_id.clone(),
-            self.protocols.clone(),
-        )
+            protocols,
+            self.channel_size,
+        );
+        protocol_set.set_protocol_registry(self.protocols.clone(), version);
+
+        if let Some(lifetime) = self.max_connection_lifetime {
+            protocol_set.set_max_lifetime(lifetime);
+        }
+
+        let limits = self.limits.limits();
+
+        if let Some(timeout) = limits.keep_alive_timeout {
+            protocol_set.set_keep_alive_timeout(timeout);
+        }
+
+        if let Some(max_substreams) = limits.max_substreams {
+            protocol_set.set_max_substreams(max_substreams);
+        }
+        protocol_set.set_slow_protocol_policy(self.slow_protocol_policy);
+        protocol_set.set_reserved_peers(self.reserved_peers.clone());
+
+        protocol_set
+    }
+
+    /// Check if the connection gater allows an inbound connection from `address`.
+    pub fn allow_inbound_address(&self, address: &Multiaddr) -> bool {
+        self.connection_gater
+            .as_ref()
+            .is_none_or(|gater| gater.allow_inbound_address(address))
     }
 
     /// Get next connection ID.
-    pub fn next_connection_id(&mut self) -> ConnectionId {
+    pub fn next_connection_id(&self) -> ConnectionId {
         let connection_id = self.next_connection_id.fetch_add(1usize, Ordering::Relaxed);
 
         ConnectionId::from(connection_id)
@@ -327,6 +643,11 @@ mod tests {
                 peers: Default::default(),
                 supported_transport: HashSet::new(),
                 listen_addresses: Default::default(),
+                quic_connections: Default::default(),
+                peer_store: Default::default(),
+                external_addresses: ExternalAddresses::new(PeerId::random()),
+                reputation: Default::default(),
+                limits: Default::default(),
             },
             cmd_rx,
         )
@@ -438,7 +759,7 @@ mod tests {
             peer
         };
 
-        match handle.dial(&peer) {
+        match handle.dial(&peer, ProtocolName::from("/notif/1")) {
             Err(Error::AlreadyConnected) => {}
             _ => panic!("invalid return value"),
         }
@@ -480,7 +801,7 @@ mod tests {
             peer
         };
 
-        match handle.dial(&peer) {
+        match handle.dial(&peer, ProtocolName::from("/notif/1")) {
             Ok(()) => {}
             _ => panic!("invalid return value"),
         }
@@ -508,7 +829,7 @@ mod tests {
             peer
         };
 
-        match handle.dial(&peer) {
+        match handle.dial(&peer, ProtocolName::from("/notif/1")) {
             Err(Error::NoAddressAvailable(failed_peer)) => {
                 assert_eq!(failed_peer, peer);
             }
@@ -554,7 +875,7 @@ mod tests {
             peer
         };
 
-        match handle.dial(&peer) {
+        match handle.dial(&peer, ProtocolName::from("/notif/1")) {
             Ok(()) => {}
             _ => panic!("invalid return value"),
         }
@@ -566,7 +887,7 @@ mod tests {
         let (mut handle, mut rx) = make_transport_manager_handle();
         handle.supported_transport.insert(SupportedTransport::Tcp);
 
-        match handle.dial(&handle.local_peer_id) {
+        match handle.dial(&handle.local_peer_id, ProtocolName::from("/notif/1")) {
             Err(Error::TriedToDialSelf) => {}
             _ => panic!("invalid return value"),
         }
@@ -592,6 +913,11 @@ mod tests {
                     .parse()
                     .expect("valid multiaddress"),
             ]))),
+            quic_connections: Default::default(),
+            peer_store: Default::default(),
+            external_addresses: ExternalAddresses::new(PeerId::random()),
+            reputation: Default::default(),
+            limits: Default::default(),
         };
 
         // local addresses
@@ -630,4 +956,38 @@ mod tests {
         assert!(!handle
             .is_local_address(&"/ip4/127.0.0.1/tcp/7777".parse().expect("valid multiaddress")));
     }
+
+    #[test]
+    fn public_addresses() {
+        let (mut handle, _rx) = make_transport_manager_handle();
+        handle.external_addresses = ExternalAddresses::new(handle.local_peer_id);
+
+        let with_peer_id = |address: &str| {
+            address
+                .parse::<Multiaddr>()
+                .expect("valid multiaddress")
+                .with(Protocol::P2p(Multihash::from(handle.local_peer_id)))
+        };
+        handle.listen_addresses.write().extend([
+            "/ip4/10.0.0.1/tcp/8888".parse().expect("valid multiaddress"),
+            with_peer_id("/ip4/10.0.0.1/tcp/8888"),
+            with_peer_id("/ip4/0.0.0.0/tcp/8888"),
+            with_peer_id("/ip4/1.1.1.1/tcp/8888"),
+        ]);
+        handle.external_addresses.add(with_peer_id("/ip4/1.1.1.1/tcp/8888"));
+        handle.external_addresses.add(with_peer_id("/ip4/2.2.2.2/tcp/8888"));
+
+        let addresses = handle.public_addresses();
+        assert_eq!(addresses.len(), 3);
+        assert!(addresses[..2]
+            .iter()
+            .all(|address| address.confidence == AddressConfidence::Confirmed));
+        assert_eq!(
+            addresses[2],
+            PublicAddress {
+                address: with_peer_id("/ip4/10.0.0.1/tcp/8888"),
+                confidence: AddressConfidence::Listen,
+            }
+        );
+    }
 }
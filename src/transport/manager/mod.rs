@@ -23,38 +23,55 @@ use crate::{
     crypto::ed25519::Keypair,
     error::{AddressError, Error},
     executor::Executor,
-    protocol::{InnerTransportEvent, TransportService},
+    protocol::{ConnectionHandle, InnerTransportEvent, SlowProtocolPolicy, TransportService},
     transport::{
+        allow_list::AllowList,
+        bootstrap::{Bootstrap, Config as BootstrapConfig},
+        connection_rate::{Config as ConnectionRateConfig, ConnectionRates, Source, SourceStats},
+        dial_scheduler::{Config as DialSchedulerConfig, DialScheduler},
+        dns::DnsResolver,
+        gater::ConnectionGater,
+        ip_limit::Config as IpLimitConfig,
+        limits::LimitsHandle,
+        local,
         manager::{
             address::{AddressRecord, AddressStore},
             handle::InnerTransportManagerCommand,
-            types::{PeerContext, PeerState},
+            types::{ConnectionApproval, OpenConnection, PeerContext, PeerState, ScheduledDial},
         },
-        Endpoint, Transport, TransportEvent,
+        quic::PathStats,
+        reconnect::Config as ReconnectConfig,
+        DisconnectReason, Endpoint, RawConnection, RejectReason, Transport, TransportEvent,
     },
     types::{protocol::ProtocolName, ConnectionId},
     BandwidthSink, PeerId,
 };
 
-use futures::{Stream, StreamExt};
+use futures::{future::BoxFuture, stream::FuturesUnordered, Stream, StreamExt};
 use indexmap::IndexMap;
 use multiaddr::{Multiaddr, Protocol};
 use multihash::Multihash;
 use parking_lot::RwLock;
-use tokio::sync::mpsc::{channel, Receiver, Sender};
+use tokio::sync::{
+    mpsc::{channel, error::TrySendError, Receiver, Sender},
+    oneshot,
+};
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     pin::Pin,
     sync::{
         atomic::{AtomicUsize, Ordering},
         Arc,
     },
     task::{Context, Poll},
+    time::{Duration, Instant},
 };
 
 pub use handle::{TransportHandle, TransportManagerHandle};
-pub use types::SupportedTransport;
+pub use types::{
+    ConnectionApprovalHandle, ConnectionInfo, ProtocolRegistry, ShutdownSummary, SupportedTransport,
+};
 
 mod address;
 mod types;
@@ -75,6 +92,43 @@ const SCORE_DIAL_SUCCESS: i32 = 100i32;
 /// Score for a non-working address.
 const SCORE_DIAL_FAILURE: i32 = -100i32;
 
+/// Check if the connection to `peer` over `endpoint` is preferred over a connection opened in the
+/// opposite direction.
+///
+/// If the local node and `peer` dial each other at the same time, both of them keep the connection
+/// dialed by the node with the smaller `PeerId` and let the other connection close once it becomes
+/// idle. As both nodes apply the same rule, they agree on which connection is used.
+pub(crate) fn is_preferred_connection(
+    local_peer_id: &PeerId,
+    peer: &PeerId,
+    endpoint: &Endpoint,
+) -> bool {
+    endpoint.is_listener() != (local_peer_id < peer)
+}
+
+/// Get the transport which is used to dial or listen on `address`.
+fn address_transport(address: &Multiaddr) -> SupportedTransport {
+    if address
+        .iter()
+        .any(|protocol| std::matches!(protocol, Protocol::QuicV1 | Protocol::Quic))
+    {
+        return SupportedTransport::Quic;
+    }
+
+    if address.iter().any(|protocol| std::matches!(protocol, Protocol::WebRTC)) {
+        return SupportedTransport::WebRtc;
+    }
+
+    if address
+        .iter()
+        .any(|protocol| std::matches!(protocol, Protocol::Ws(_) | Protocol::Wss(_)))
+    {
+        return SupportedTransport::WebSocket;
+    }
+
+    SupportedTransport::Tcp
+}
+
 /// TODO:
 enum ConnectionEstablishedResult {
     /// Accept connection and inform `Litep2p` about the connection.
@@ -86,6 +140,21 @@ enum ConnectionEstablishedResult {
 
 /// [`crate::transport::manager::TransportManager`] events.
 pub enum TransportManagerEvent {
+    /// Connection established to remote peer and reported to protocols.
+    ConnectionEstablished {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// Connection ID.
+        connection: ConnectionId,
+
+        /// Handle to the connection.
+        handle: ConnectionHandle,
+
+        /// Protocols negotiated over the connection.
+        protocols: Arc<RwLock<HashSet<ProtocolName>>>,
+    },
+
     /// Connection closed to remote peer.
     ConnectionClosed {
         /// Peer ID.
@@ -93,6 +162,21 @@ pub enum TransportManagerEvent {
 
         /// Connection ID.
         connection: ConnectionId,
+
+        /// Reason why the connection was closed.
+        reason: DisconnectReason,
+    },
+
+    /// Path statistics of a QUIC connection.
+    QuicPathStats {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// Connection ID.
+        connection: ConnectionId,
+
+        /// Path statistics.
+        stats: PathStats,
     },
 }
 
@@ -107,6 +191,9 @@ pub struct ProtocolContext {
 
     /// Fallback names for the protocol.
     pub fallback_names: Vec<ProtocolName>,
+
+    /// Default priority of the protocol's substreams on transports that support it.
+    pub stream_priority: i32,
 }
 
 impl ProtocolContext {
@@ -120,6 +207,7 @@ impl ProtocolContext {
             tx,
             codec,
             fallback_names,
+            stream_priority: 0,
         }
     }
 }
@@ -142,11 +230,6 @@ impl TransportContext {
         }
     }
 
-    /// Get an iterator of supported transports.
-    pub fn keys(&self) -> impl Iterator<Item = &SupportedTransport> {
-        self.transports.keys()
-    }
-
     /// Get mutable access to transport.
     pub fn get_mut(
         &mut self,
@@ -163,6 +246,19 @@ impl TransportContext {
     ) {
         assert!(self.transports.insert(name, transport).is_none());
     }
+
+    /// Check if `transport` is registered.
+    pub fn contains(&self, transport: &SupportedTransport) -> bool {
+        self.transports.contains_key(transport)
+    }
+
+    /// Remove `transport` from `TransportContext`.
+    pub fn remove_transport(
+        &mut self,
+        transport: &SupportedTransport,
+    ) -> Option<Box<dyn Transport<Item = TransportEvent>>> {
+        self.transports.shift_remove(transport)
+    }
 }
 
 impl Stream for TransportContext {
@@ -179,10 +275,24 @@ impl Stream for TransportContext {
             let index = self.index % len;
             self.index += 1;
 
-            let (key, stream) = self.transports.get_index_mut(index).expect("transport to exist");
+            let Some((key, stream)) = self.transports.get_index_mut(index) else {
+                return Poll::Pending;
+            };
             match stream.poll_next_unpin(cx) {
                 Poll::Pending => {}
-                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Ready(None) => {
+                    // transport exited, remove it so the other transports keep running
+                    let key = *key;
+                    self.transports.shift_remove_index(index);
+
+                    return Poll::Ready(Some((
+                        key,
+                        TransportEvent::TransportFailed {
+                            transport: key,
+                            error: Error::EssentialTaskClosed,
+                        },
+                    )));
+                }
                 Poll::Ready(Some(event)) => return Poll::Ready(Some((*key, event))),
             }
 
@@ -210,6 +320,12 @@ pub struct TransportManager {
     /// Installed protocols.
     protocols: HashMap<ProtocolName, ProtocolContext>,
 
+    /// Receivers notified when the event loops of the installed protocols exit.
+    protocol_exits: HashMap<ProtocolName, oneshot::Receiver<()>>,
+
+    /// Installed protocols, shared with transports and connections.
+    protocol_registry: ProtocolRegistry,
+
     /// All names (main and fallback(s)) of the installed protocols.
     protocol_names: HashSet<ProtocolName>,
 
@@ -242,28 +358,154 @@ pub struct TransportManager {
 
     /// Pending connections.
     pending_connections: HashMap<ConnectionId, PeerId>,
+
+    /// Per-address errors of pending dials, collected across transports.
+    pending_dial_errors: HashMap<ConnectionId, Vec<(Multiaddr, Error)>>,
+
+    /// Dials whose result the user is waiting for.
+    pending_dial_results: HashMap<ConnectionId, Vec<oneshot::Sender<crate::Result<PeerId>>>>,
+
+    /// Injected connections which have not yet been reported as established.
+    injected_connections: HashSet<ConnectionId>,
+
+    /// DNS resolver shared by all transports.
+    dns_resolver: DnsResolver,
+
+    /// Inbound connection rates shared by all transports.
+    connection_rates: ConnectionRates,
+
+    /// RX channel for receiving sources that exceeded the connection rate thresholds.
+    connection_rate_rx: Receiver<(Source, SourceStats)>,
+
+    /// Connection gater, if set.
+    connection_gater: Option<Arc<dyn ConnectionGater>>,
+
+    /// Allow-list of peers and addresses, if the node only accepts known peers.
+    allow_list: Option<AllowList>,
+
+    /// Runtime-adjustable limits.
+    limits: LimitsHandle,
+
+    /// Should inbound connections be approved by the user before they're accepted.
+    connection_approval: bool,
+
+    /// Events to protocols which are sent in the background because the channels were clogged.
+    pending_notifications: FuturesUnordered<BoxFuture<'static, ()>>,
+
+    /// Inbound connections awaiting approval from the user.
+    pending_approvals: FuturesUnordered<
+        BoxFuture<'static, (SupportedTransport, PeerId, Endpoint, ConnectionApproval)>,
+    >,
+
+    /// Should connections be probed before they're reported to the user.
+    connection_probing: bool,
+
+    /// Established connections held until the probe of the peer's primary connection concludes.
+    pending_probes: HashMap<PeerId, Vec<Endpoint>>,
+
+    /// Connections that failed the probe and are being closed.
+    failed_probes: HashSet<ConnectionId>,
+
+    /// Handles to open connections, used to disconnect peers.
+    connection_handles: HashMap<PeerId, HashMap<ConnectionId, ConnectionHandle>>,
+
+    /// Open connections.
+    open_connections: HashMap<PeerId, HashMap<ConnectionId, OpenConnection>>,
+
+    /// Banned peers and the expiration times of their bans.
+    banned_peers: HashMap<PeerId, Instant>,
+
+    /// Pending events.
+    pending_events: VecDeque<TransportEvent>,
+
+    /// Maximum lifetime of a connection.
+    max_connection_lifetime: Option<Duration>,
+
+    /// Policy for handling substreams opened for slow protocols.
+    slow_protocol_policy: SlowProtocolPolicy,
+
+    /// Size of the channel each installed protocol receives connection events from.
+    protocol_channel_size: usize,
+
+    /// Size of the command and event channels of the transport manager and the connections.
+    transport_channel_size: usize,
+
+    /// Deadlines of outbound connections which are re-established when their lifetime expires.
+    connection_deadlines: HashMap<ConnectionId, Instant>,
+
+    /// Reconnection configuration for persistent peers.
+    reconnect_config: ReconnectConfig,
+
+    /// Persistent peers and the number of their consecutive reconnection attempts.
+    persistent_peers: HashMap<PeerId, u32>,
+
+    /// Reserved peers, shared with the connections.
+    reserved_peers: Arc<RwLock<HashSet<PeerId>>>,
+
+    /// Persistent peers which have a reconnection attempt scheduled.
+    scheduled_reconnects: HashSet<PeerId>,
+
+    /// Pending reconnection attempts.
+    pending_reconnects: FuturesUnordered<BoxFuture<'static, PeerId>>,
+
+    /// Bootnodes being connected to.
+    bootstrap: Bootstrap,
+
+    /// Scheduler of outbound dials.
+    dial_scheduler: DialScheduler<ScheduledDial>,
+
+    /// Should dialing be restricted to local addresses.
+    local_only: bool,
 }
 
 impl TransportManager {
     /// Create new [`crate::transport::manager::TransportManager`].
     // TODO: don't return handle here
+    #[cfg(test)]
     pub fn new(
         keypair: Keypair,
         supported_transports: HashSet<SupportedTransport>,
         bandwidth_sink: BandwidthSink,
         max_parallel_dials: usize,
+    ) -> (Self, TransportManagerHandle) {
+        Self::with_channel_sizes(
+            keypair,
+            supported_transports,
+            bandwidth_sink,
+            max_parallel_dials,
+            crate::DEFAULT_CHANNEL_SIZE,
+            crate::transport::TRANSPORT_CHANNEL_SIZE,
+        )
+    }
+
+    /// Create new [`crate::transport::manager::TransportManager`] with custom channel sizes.
+    ///
+    /// `protocol_channel_size` is the size of the channel each installed protocol receives
+    /// connection events from and `transport_channel_size` is the size of the command and event
+    /// channels of the transport manager and the connections.
+    pub(crate) fn with_channel_sizes(
+        keypair: Keypair,
+        supported_transports: HashSet<SupportedTransport>,
+        bandwidth_sink: BandwidthSink,
+        max_parallel_dials: usize,
+        protocol_channel_size: usize,
+        transport_channel_size: usize,
     ) -> (Self, TransportManagerHandle) {
         let local_peer_id = PeerId::from_public_key(&keypair.public().into());
         let peers = Arc::new(RwLock::new(HashMap::new()));
-        let (cmd_tx, cmd_rx) = channel(256);
-        let (event_tx, event_rx) = channel(256);
+        let (cmd_tx, cmd_rx) = channel(transport_channel_size);
+        let (event_tx, event_rx) = channel(transport_channel_size);
         let listen_addresses = Arc::new(RwLock::new(HashSet::new()));
+        let (connection_rates, connection_rate_rx) =
+            ConnectionRates::new(ConnectionRateConfig::default());
+        let limits = LimitsHandle::new(connection_rates.clone());
         let handle = TransportManagerHandle::new(
             local_peer_id,
             peers.clone(),
             cmd_tx,
             supported_transports,
             Arc::clone(&listen_addresses),
+            limits.clone(),
         );
 
         (
@@ -277,11 +519,45 @@ impl TransportManager {
                 bandwidth_sink,
                 listen_addresses,
                 max_parallel_dials,
+                protocol_channel_size,
+                transport_channel_size,
                 protocols: HashMap::new(),
+                protocol_exits: HashMap::new(),
+                protocol_registry: ProtocolRegistry::default(),
                 transports: TransportContext::new(),
                 protocol_names: HashSet::new(),
                 transport_manager_handle: handle.clone(),
                 pending_connections: HashMap::new(),
+                pending_dial_errors: HashMap::new(),
+                pending_dial_results: HashMap::new(),
+                injected_connections: HashSet::new(),
+                dns_resolver: DnsResolver::new(),
+                connection_rates,
+                connection_rate_rx,
+                connection_gater: None,
+                allow_list: None,
+                limits,
+                connection_approval: false,
+                pending_notifications: FuturesUnordered::new(),
+                pending_approvals: FuturesUnordered::new(),
+                connection_probing: false,
+                pending_probes: HashMap::new(),
+                failed_probes: HashSet::new(),
+                connection_handles: HashMap::new(),
+                open_connections: HashMap::new(),
+                banned_peers: HashMap::new(),
+                pending_events: VecDeque::new(),
+                max_connection_lifetime: None,
+                slow_protocol_policy: SlowProtocolPolicy::default(),
+                connection_deadlines: HashMap::new(),
+                reconnect_config: ReconnectConfig::default(),
+                persistent_peers: HashMap::new(),
+                reserved_peers: Default::default(),
+                scheduled_reconnects: HashSet::new(),
+                pending_reconnects: FuturesUnordered::new(),
+                bootstrap: Bootstrap::new(BootstrapConfig::default()),
+                dial_scheduler: DialScheduler::new(DialSchedulerConfig::default()),
+                local_only: false,
                 next_substream_id: Arc::new(AtomicUsize::new(0usize)),
                 next_connection_id: Arc::new(AtomicUsize::new(0usize)),
             },
@@ -289,14 +565,220 @@ impl TransportManager {
         )
     }
 
+    /// Set connection gater.
+    ///
+    /// See [`crate::config::ConfigBuilder::with_connection_gater()`] for more details.
+    pub(crate) fn set_connection_gater(&mut self, gater: Arc<dyn ConnectionGater>) {
+        self.connection_gater = Some(gater);
+    }
+
+    /// Only allow connections to and from peers and addresses on `allow_list`.
+    ///
+    /// See [`crate::config::ConfigBuilder::with_allow_list()`] for more details.
+    pub(crate) fn set_allow_list(&mut self, allow_list: AllowList) {
+        self.allow_list = Some(allow_list);
+    }
+
+    /// Set limits for inbound connections per IP address and subnet.
+    ///
+    /// See [`crate::config::ConfigBuilder::with_ip_limits()`] for more details.
+    pub(crate) fn set_ip_limits(&mut self, config: IpLimitConfig) {
+        self.limits.set_ip_limits(Some(config));
+    }
+
+    /// Check if an inbound connection from `address` is within the IP limits, if they are set.
+    fn is_within_ip_limits(&self, address: &Multiaddr) -> bool {
+        self.limits.ip_limits().is_none_or(|config| {
+            config.is_within_limits(
+                address,
+                self.open_connections
+                    .values()
+                    .flat_map(|connections| connections.values())
+                    .filter(|connection| connection.endpoint.is_listener())
+                    .map(|connection| connection.endpoint.address()),
+            )
+        })
+    }
+
+    /// Make room for an inbound connection from `peer` which exceeds the IP limits by closing
+    /// an inbound connection of the least reputable peer counted towards the same limit.
+    ///
+    /// A connection is only closed if its peer is less reputable than `peer` and not reserved.
+    /// Returns `true` if a connection was closed.
+    fn evict_less_reputable(&mut self, peer: PeerId, address: &Multiaddr) -> bool {
+        let Some(config) = self.limits.ip_limits() else {
+            return false;
+        };
+        let reputation = self.transport_manager_handle.reputation();
+        let threshold = reputation.reputation(&peer);
+        let reserved_peers = self.reserved_peers.read();
+
+        let Some((evicted, connection_id)) = self
+            .open_connections
+            .iter()
+            .filter(|(open, _)| **open != peer && !reserved_peers.contains(*open))
+            .flat_map(|(open, connections)| {
+                connections
+                    .iter()
+                    .map(move |(connection_id, connection)| (open, connection_id, connection))
+            })
+            .filter(|(_, _, connection)| {
+                connection.endpoint.is_listener()
+                    && config.shares_limit(address, connection.endpoint.address())
+            })
+            .map(|(open, connection_id, _)| (reputation.reputation(open), *open, *connection_id))
+            .filter(|(value, _, _)| *value < threshold)
+            .min_by_key(|(value, _, _)| *value)
+            .map(|(_, open, connection_id)| (open, connection_id))
+        else {
+            return false;
+        };
+
+        let Some(handle) = self
+            .connection_handles
+            .get_mut(&evicted)
+            .and_then(|handles| handles.get_mut(&connection_id))
+        else {
+            return false;
+        };
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            ?peer,
+            ?evicted,
+            ?connection_id,
+            "close connection of less reputable peer to make room for inbound connection",
+        );
+
+        handle.force_close().is_ok()
+    }
+
+    /// Check if `peer` is allowed by the allow-list, if one is set.
+    fn is_peer_allowed(&self, peer: &PeerId) -> bool {
+        self.allow_list
+            .as_ref()
+            .is_none_or(|allow_list| allow_list.is_peer_allowed(peer))
+    }
+
+    /// Hold negotiated inbound connections until the user has approved them.
+    ///
+    /// See [`crate::config::ConfigBuilder::with_connection_approval()`] for more details.
+    pub(crate) fn enable_connection_approval(&mut self) {
+        self.connection_approval = true;
+    }
+
+    /// Probe established connections before they're reported to the user.
+    ///
+    /// See [`crate::config::ConfigBuilder::with_connection_probing()`] for more details.
+    pub(crate) fn enable_connection_probing(&mut self) {
+        self.connection_probing = true;
+    }
+
+    /// Set reconnection configuration for persistent peers.
+    ///
+    /// See [`crate::config::ConfigBuilder::with_reconnect_config()`] for more details.
+    pub(crate) fn set_reconnect_config(&mut self, config: ReconnectConfig) {
+        self.reconnect_config = config;
+    }
+
+    /// Set configuration for connecting to bootnodes.
+    ///
+    /// See [`crate::config::ConfigBuilder::with_bootstrap_config()`] for more details.
+    pub(crate) fn set_bootstrap_config(&mut self, config: BootstrapConfig) {
+        self.bootstrap.set_config(config);
+    }
+
+    /// Set configuration for scheduling outbound dials.
+    ///
+    /// See [`crate::config::ConfigBuilder::with_dial_scheduler_config()`] for more details.
+    pub(crate) fn set_dial_scheduler_config(&mut self, config: DialSchedulerConfig) {
+        self.dial_scheduler.set_config(config);
+    }
+
+    /// Only dial local addresses.
+    ///
+    /// See [`crate::config::ConfigBuilder::with_local_only()`] for more details.
+    pub(crate) fn set_local_only(&mut self) {
+        self.local_only = true;
+    }
+
+    /// Start connecting to `bootnodes`.
+    ///
+    /// The outcome is reported in [`TransportEvent::BootstrapCompleted`].
+    pub fn connect_to_bootnodes(&mut self, bootnodes: Vec<Multiaddr>) {
+        tracing::debug!(target: LOG_TARGET, ?bootnodes, "connect to bootnodes");
+
+        self.bootstrap.start(bootnodes);
+        self.dial_bootnodes();
+    }
+
+    /// Dial bootnodes until the concurrency limit of the bootstrap is reached.
+    fn dial_bootnodes(&mut self) {
+        while let Some(address) = self.bootstrap.next_dial() {
+            let rx = self.dial_address_with_result(address.clone());
+            self.bootstrap.on_dial_started(address, rx);
+        }
+
+        if let Some(outcome) = self.bootstrap.outcome() {
+            self.pending_events.push_back(TransportEvent::BootstrapCompleted {
+                connected: outcome.connected,
+                failed: outcome.failed,
+            });
+        }
+    }
+
+    /// Set maximum lifetime for connections.
+    ///
+    /// See [`crate::config::ConfigBuilder::with_max_connection_lifetime()`] for more details.
+    pub(crate) fn set_max_connection_lifetime(&mut self, lifetime: Duration) {
+        self.max_connection_lifetime = Some(lifetime);
+    }
+
+    /// Set how long connections without open substreams are kept open.
+    ///
+    /// See [`crate::config::ConfigBuilder::with_keep_alive_timeout()`] for more details.
+    pub(crate) fn set_keep_alive_timeout(&mut self, timeout: Duration) {
+        self.limits.set_keep_alive_timeout(Some(timeout));
+    }
+
+    /// Set the maximum number of concurrently open substreams per connection.
+    ///
+    /// See [`crate::config::ConfigBuilder::with_max_substreams_per_connection()`] for more
+    /// details.
+    pub(crate) fn set_max_substreams(&mut self, max_substreams: usize) {
+        self.limits.set_max_substreams(Some(max_substreams));
+    }
+
+    /// Set the policy for handling substreams opened for slow protocols.
+    ///
+    /// See [`crate::config::ConfigBuilder::with_slow_protocol_policy()`] for more details.
+    pub(crate) fn set_slow_protocol_policy(&mut self, policy: SlowProtocolPolicy) {
+        self.slow_protocol_policy = policy;
+    }
+
+    /// Get the DNS resolver shared by all transports.
+    pub fn dns_resolver(&self) -> &DnsResolver {
+        &self.dns_resolver
+    }
+
+    /// Set configuration for tracking inbound connection rates.
+    pub(crate) fn set_connection_rate_config(&mut self, config: ConnectionRateConfig) {
+        self.connection_rates.set_config(config);
+    }
+
+    /// Get the inbound connection rates shared by all transports.
+    pub fn connection_rates(&self) -> &ConnectionRates {
+        &self.connection_rates
+    }
+
     /// Get iterator to installed protocols.
     pub fn protocols(&self) -> impl Iterator<Item = &ProtocolName> {
         self.protocols.keys()
     }
 
-    /// Get iterator to installed transports
-    pub fn installed_transports(&self) -> impl Iterator<Item = &SupportedTransport> {
-        self.transports.keys()
+    /// Check if `protocol` is installed, either as a main protocol or as a fallback name.
+    pub(crate) fn is_protocol_installed(&self, protocol: &ProtocolName) -> bool {
+        self.protocol_names.contains(protocol)
     }
 
     /// Get next connection ID.
@@ -324,32 +806,80 @@ impl TransportManager {
             }
         }
 
-        let (service, sender) = TransportService::new(
+        let (mut service, sender) = TransportService::with_channel_size(
             self.local_peer_id,
             protocol.clone(),
             fallback_names.clone(),
             self.next_substream_id.clone(),
             self.transport_manager_handle.clone(),
+            self.protocol_channel_size,
         );
 
         self.protocols.insert(
             protocol.clone(),
             ProtocolContext::new(codec, sender, fallback_names.clone()),
         );
+        self.protocol_exits.insert(protocol.clone(), service.exit_notification());
         self.protocol_names.insert(protocol);
         self.protocol_names.extend(fallback_names);
+        self.protocol_registry.update(self.protocols.clone());
 
         service
     }
 
+    /// Unregister `protocol` from the [`crate::transport::manager::TransportManager`].
+    ///
+    /// The protocol is removed from all connections, including the ones which are already open,
+    /// and once every connection has dropped it, the event stream of its
+    /// [`TransportService`] is terminated.
+    pub(crate) fn unregister_protocol(&mut self, protocol: &ProtocolName) -> crate::Result<()> {
+        let context = self
+            .protocols
+            .remove(protocol)
+            .ok_or(Error::ProtocolNotSupported(protocol.to_string()))?;
+
+        self.protocol_exits.remove(protocol);
+        self.protocol_names.remove(protocol);
+        for fallback in &context.fallback_names {
+            self.protocol_names.remove(fallback);
+        }
+        self.protocol_registry.update(self.protocols.clone());
+
+        Ok(())
+    }
+
+    /// Set the default priority of substreams opened for `protocol`.
+    ///
+    /// Must be called before transports are created.
+    pub(crate) fn set_stream_priority(&mut self, protocol: &ProtocolName, priority: i32) {
+        if let Some(context) = self.protocols.get_mut(protocol) {
+            context.stream_priority = priority;
+            self.protocol_registry.update(self.protocols.clone());
+        }
+    }
+
     /// Acquire `TransportHandle`.
-    pub fn transport_handle(&self, executor: Arc<dyn Executor>) -> TransportHandle {
+    pub fn transport_handle(
+        &self,
+        transport: SupportedTransport,
+        executor: Arc<dyn Executor>,
+    ) -> TransportHandle {
         TransportHandle {
+            transport,
             tx: self.event_tx.clone(),
             executor,
             keypair: self.keypair.clone(),
-            protocols: self.protocols.clone(),
+            protocols: self.protocol_registry.clone(),
             bandwidth_sink: self.bandwidth_sink.clone(),
+            dns_resolver: self.dns_resolver.clone(),
+            connection_rates: self.connection_rates.clone(),
+            connection_gater: self.connection_gater.clone(),
+            quic_connections: self.transport_manager_handle.quic_connections(),
+            max_connection_lifetime: self.max_connection_lifetime,
+            limits: self.limits.clone(),
+            reserved_peers: self.reserved_peers.clone(),
+            slow_protocol_policy: self.slow_protocol_policy,
+            channel_size: self.transport_channel_size,
             protocol_names: self.protocol_names.iter().cloned().collect(),
             next_substream_id: self.next_substream_id.clone(),
             next_connection_id: self.next_connection_id.clone(),
@@ -380,6 +910,16 @@ impl TransportManager {
         )));
     }
 
+    /// Unregister listen address.
+    fn unregister_listen_address(&mut self, address: &Multiaddr) {
+        let mut listen_addresses = self.listen_addresses.write();
+
+        listen_addresses.remove(address);
+        listen_addresses.remove(&address.clone().with(Protocol::P2p(
+            Multihash::from_bytes(&self.local_peer_id.to_bytes()).unwrap(),
+        )));
+    }
+
     /// Add one or more known addresses for `peer`.
     pub fn add_known_address(
         &mut self,
@@ -392,10 +932,16 @@ impl TransportManager {
     /// Dial peer using `PeerId`.
     ///
     /// Returns an error if the peer is unknown or the peer is already connected.
-    pub async fn dial(&mut self, peer: PeerId) -> crate::Result<()> {
+    pub fn dial(&mut self, peer: PeerId) -> crate::Result<()> {
         if peer == self.local_peer_id {
             return Err(Error::TriedToDialSelf);
         }
+        if self.is_banned(&peer) {
+            return Err(Error::PeerBanned(peer));
+        }
+        if !self.is_peer_allowed(&peer) {
+            return Err(Error::PeerNotAllowed(peer));
+        }
         let mut peers = self.peers.write();
 
         // if the peer is disconnected, return its context
@@ -450,13 +996,48 @@ impl TransportManager {
             return Ok(());
         }
 
-        let mut records: HashMap<_, _> = addresses
-            .take(self.max_parallel_dials)
+        // skip addresses which are in backoff because of repeated dial failures and prefer
+        // addresses which were most recently dialed successfully, falling back to address score
+        let now = Instant::now();
+        let stats = self.transport_manager_handle.peer_store().address_stats(&peer);
+        let (mut candidates, backed_off): (Vec<_>, Vec<_>) =
+            addresses.take(usize::MAX).into_iter().partition(|record| {
+                stats.get(record.address()).is_none_or(|stats| !stats.is_backed_off(now))
+                    && self
+                        .allow_list
+                        .as_ref()
+                        .is_none_or(|allow_list| allow_list.is_address_allowed(record.address()))
+                    && (!self.local_only || local::is_local_address(record.address()))
+                    && self.transports.contains(&address_transport(record.address()))
+            });
+        candidates.sort_by_key(|record| {
+            std::cmp::Reverse(stats.get(record.address()).and_then(|stats| stats.last_success))
+        });
+
+        let unused = candidates.split_off(std::cmp::min(self.max_parallel_dials, candidates.len()));
+        addresses.extend(unused.into_iter().chain(backed_off));
+
+        let mut records: HashMap<_, _> = candidates
             .into_iter()
             .map(|record| (record.address().clone(), record))
             .collect();
 
         if records.is_empty() {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?peer,
+                "no dialable address, all known addresses are either unavailable or in backoff",
+            );
+
+            peers.insert(
+                peer,
+                PeerContext {
+                    state,
+                    secondary_connection,
+                    addresses,
+                },
+            );
+
             return Err(Error::NoAddressAvailable(peer));
         }
 
@@ -494,7 +1075,7 @@ impl TransportManager {
             record.set_connection_id(connection_id);
 
             let mut iter = address.iter();
-            match iter.find(|protocol| std::matches!(protocol, Protocol::QuicV1)) {
+            match iter.find(|protocol| std::matches!(protocol, Protocol::QuicV1 | Protocol::Quic)) {
                 Some(_) => {
                     quic.push(address.clone());
                     transports.insert(SupportedTransport::Quic);
@@ -528,36 +1109,156 @@ impl TransportManager {
             },
         );
 
-        if !tcp.is_empty() {
-            self.transports
-                .get_mut(&SupportedTransport::Tcp)
-                .expect("transport to be supported")
-                .open(connection_id, tcp)?;
-        }
+        drop(peers);
+
+        let dial = ScheduledDial::Open {
+            addresses: [
+                (SupportedTransport::Tcp, tcp),
+                (SupportedTransport::Quic, quic),
+                (SupportedTransport::WebSocket, websocket),
+            ]
+            .into_iter()
+            .filter(|(_, addresses)| !addresses.is_empty())
+            .collect(),
+        };
+
+        self.schedule_dial(connection_id, dial)?;
+        self.pending_connections.insert(connection_id, peer);
+
+        Ok(())
+    }
+
+    /// Schedule `dial` and start it right away if the dial scheduler permits it.
+    fn schedule_dial(
+        &mut self,
+        connection_id: ConnectionId,
+        dial: ScheduledDial,
+    ) -> crate::Result<()> {
+        let pending_connections = &self.pending_connections;
+        self.dial_scheduler
+            .release(|connection_id| pending_connections.contains_key(connection_id));
 
-        if !quic.is_empty() {
-            self.transports
-                .get_mut(&SupportedTransport::Quic)
-                .expect("transport to be supported")
-                .open(connection_id, quic)?;
+        match self.dial_scheduler.schedule(connection_id, dial.addresses(), dial) {
+            Some(dial) => self.start_dial(connection_id, dial),
+            None => Ok(()),
         }
+    }
+
+    /// Start `dial` over the transports.
+    fn start_dial(
+        &mut self,
+        connection_id: ConnectionId,
+        dial: ScheduledDial,
+    ) -> crate::Result<()> {
+        match dial {
+            ScheduledDial::Dial { transport, address } => self
+                .transports
+                .get_mut(&transport)
+                .ok_or(Error::TransportNotSupported(address.clone()))?
+                .dial(connection_id, address),
+            ScheduledDial::Open { addresses } => {
+                for (transport, addresses) in addresses {
+                    self.transports
+                        .get_mut(&transport)
+                        .ok_or(Error::TransportNotSupported(addresses[0].clone()))?
+                        .open(connection_id, addresses)?;
+                }
 
-        if !websocket.is_empty() {
-            self.transports
-                .get_mut(&SupportedTransport::WebSocket)
-                .expect("transport to be supported")
-                .open(connection_id, websocket)?;
+                Ok(())
+            }
         }
+    }
 
-        self.pending_connections.insert(connection_id, peer);
+    /// Start a queued `dial` once the dial scheduler permits it.
+    ///
+    /// If the dial can't be started, the failure is handled as if the transport had reported it.
+    fn start_queued_dial(&mut self, connection_id: ConnectionId, dial: ScheduledDial) {
+        tracing::trace!(target: LOG_TARGET, ?connection_id, ?dial, "start queued dial");
 
-        Ok(())
+        match dial {
+            ScheduledDial::Dial { transport, address } => {
+                let result = self
+                    .transports
+                    .get_mut(&transport)
+                    .ok_or(Error::TransportNotSupported(address.clone()))
+                    .and_then(|handle| handle.dial(connection_id, address.clone()));
+
+                if let Err(error) = result {
+                    let event = self.on_transport_event(
+                        transport,
+                        TransportEvent::DialFailure {
+                            connection_id,
+                            address,
+                            error,
+                        },
+                    );
+                    self.pending_events.extend(event);
+                }
+            }
+            ScheduledDial::Open { addresses } =>
+                for (transport, addresses) in addresses {
+                    let result = self
+                        .transports
+                        .get_mut(&transport)
+                        .ok_or(Error::TransportNotSupported(addresses[0].clone()))
+                        .and_then(|handle| handle.open(connection_id, addresses.clone()));
+
+                    // the error is attributed to the first address since it can't be cloned
+                    if let Err(error) = result {
+                        let event = self.on_transport_event(
+                            transport,
+                            TransportEvent::OpenFailure {
+                                connection_id,
+                                errors: vec![(addresses[0].clone(), error)],
+                            },
+                        );
+                        self.pending_events.extend(event);
+                    }
+                },
+        }
     }
 
     /// Dial peer using `Multiaddr`.
     ///
     /// Returns an error if address it not valid.
-    pub async fn dial_address(&mut self, address: Multiaddr) -> crate::Result<()> {
+    pub fn dial_address(&mut self, address: Multiaddr) -> crate::Result<()> {
+        self.start_address_dial(address).map(|_| ())
+    }
+
+    /// Dial peer using `Multiaddr` and get notified of the result of the dial.
+    ///
+    /// The returned receiver resolves to the ID of the dialed peer once the connection has
+    /// been established and accepted, or to an error if the dial failed. A failed dial is not
+    /// reported as [`TransportEvent::DialFailure`].
+    pub fn dial_address_with_result(
+        &mut self,
+        address: Multiaddr,
+    ) -> oneshot::Receiver<crate::Result<PeerId>> {
+        let (tx, rx) = oneshot::channel();
+
+        match self.start_address_dial(address.clone()) {
+            Ok(Some(connection_id)) => {
+                self.pending_dial_results.entry(connection_id).or_default().push(tx);
+            }
+            Ok(None) => {
+                let _ = tx.send(
+                    PeerId::try_from_multiaddr(&address)
+                        .ok_or(Error::AddressError(AddressError::PeerIdMissing)),
+                );
+            }
+            Err(error) => {
+                let _ = tx.send(Err(error));
+            }
+        }
+
+        rx
+    }
+
+    /// Start dialing `address`.
+    ///
+    /// Returns the ID of the connection that is being opened to the peer, or `None` if the peer
+    /// is already connected.
+    fn start_address_dial(&mut self, address: Multiaddr) -> crate::Result<Option<ConnectionId>> {
         let mut record = AddressRecord::from_multiaddr(address)
             .ok_or(Error::AddressError(AddressError::PeerIdMissing))?;
 
@@ -565,6 +1266,11 @@ impl TransportManager {
             return Err(Error::TriedToDialSelf);
         }
 
+        if self.local_only && !local::is_local_address(record.address()) {
+            tracing::debug!(target: LOG_TARGET, address = ?record.address(), "refusing to dial non-local address");
+            return Err(Error::NonLocalAddress(record.address().clone()));
+        }
+
         tracing::debug!(target: LOG_TARGET, address = ?record.address(), "dial remote peer over address");
 
         let mut protocol_stack = record.as_ref().iter();
@@ -597,9 +1303,9 @@ impl TransportManager {
                 .next()
                 .ok_or_else(|| Error::TransportNotSupported(record.address().clone()))?
             {
-                Protocol::QuicV1 => SupportedTransport::Quic,
+                Protocol::QuicV1 | Protocol::Quic => SupportedTransport::Quic,
                 _ => {
-                    tracing::debug!(target: LOG_TARGET, address = ?record.address(), "expected `quic-v1`");
+                    tracing::debug!(target: LOG_TARGET, address = ?record.address(), "expected `quic-v1` or `quic`");
                     return Err(Error::TransportNotSupported(record.address().clone()));
                 }
             },
@@ -618,6 +1324,17 @@ impl TransportManager {
         let remote_peer_id =
             PeerId::try_from_multiaddr(record.address()).expect("`PeerId` to exist");
 
+        if self.is_banned(&remote_peer_id) {
+            return Err(Error::PeerBanned(remote_peer_id));
+        }
+        if self
+            .allow_list
+            .as_ref()
+            .is_some_and(|allow_list| !allow_list.is_allowed(&remote_peer_id, record.address()))
+        {
+            return Err(Error::PeerNotAllowed(remote_peer_id));
+        }
+
         // set connection id for the address record and put peer into `Dialing` state
         let connection_id = self.next_connection_id();
         record.set_connection_id(connection_id);
@@ -640,12 +1357,17 @@ impl TransportManager {
                     );
                 }
                 Some(PeerContext {
-                    state:
-                        PeerState::Dialing { .. }
-                        | PeerState::Connected { .. }
-                        | PeerState::Opening { .. },
+                    state: PeerState::Dialing { record },
+                    ..
+                }) => return Ok(*record.connection_id()),
+                Some(PeerContext {
+                    state: PeerState::Opening { connection_id, .. },
+                    ..
+                }) => return Ok(Some(*connection_id)),
+                Some(PeerContext {
+                    state: PeerState::Connected { .. },
                     ..
-                }) => return Ok(()),
+                }) => return Ok(None),
                 Some(PeerContext { ref mut state, .. }) => {
                     // TODO: verify that the address is not in `addresses` already
                     // addresses.insert(address.clone());
@@ -656,15 +1378,486 @@ impl TransportManager {
             }
         }
 
-        self.transports
-            .get_mut(&supported_transport)
-            .ok_or(Error::TransportNotSupported(record.address().clone()))?
-            .dial(connection_id, record.address().clone())?;
+        let dial = ScheduledDial::Dial {
+            transport: supported_transport,
+            address: record.address().clone(),
+        };
+
+        self.schedule_dial(connection_id, dial)?;
         self.pending_connections.insert(connection_id, remote_peer_id);
 
+        Ok(Some(connection_id))
+    }
+
+    /// Report the result of a dial to the user if it's waiting for it.
+    fn report_dial_result(&mut self, connection_id: ConnectionId, result: crate::Result<PeerId>) {
+        let Some(waiters) = self.pending_dial_results.remove(&connection_id) else {
+            return;
+        };
+
+        match result {
+            Ok(peer) => waiters.into_iter().for_each(|tx| {
+                let _ = tx.send(Ok(peer));
+            }),
+            Err(error) => {
+                // only the first waiter gets the original error
+                let mut waiters = waiters.into_iter();
+                let error_message = error.to_string();
+
+                if let Some(tx) = waiters.next() {
+                    let _ = tx.send(Err(error));
+                }
+                waiters.for_each(|tx| {
+                    let _ = tx.send(Err(Error::Other(error_message.clone())));
+                });
+            }
+        }
+    }
+
+    /// Add pre-established connection to `peer`.
+    ///
+    /// The connection is handed over to the TCP transport which negotiates `yamux` over it
+    /// and runs the installed protocols the same way as for any other connection.
+    pub fn add_connection(
+        &mut self,
+        peer: PeerId,
+        connection: impl RawConnection,
+    ) -> crate::Result<()> {
+        if peer == self.local_peer_id {
+            return Err(Error::TriedToDialSelf);
+        }
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            ?peer,
+            role = ?connection.role(),
+            "inject pre-established connection",
+        );
+
+        let connection_id = self.next_connection_id();
+        let address = connection.address();
+
+        self.transports
+            .get_mut(&SupportedTransport::Tcp)
+            .ok_or(Error::TransportNotSupported(address))?
+            .inject(connection_id, peer, Box::new(connection))?;
+        self.injected_connections.insert(connection_id);
+
+        Ok(())
+    }
+
+    /// Ban `peer` for `duration`.
+    ///
+    /// Open connections to the peer are closed, inbound connections from the peer are rejected
+    /// after the handshake and dials to the peer are refused until the ban expires.
+    pub fn ban_peer(&mut self, peer: PeerId, duration: Duration) {
+        tracing::debug!(target: LOG_TARGET, ?peer, ?duration, "ban peer");
+
+        self.banned_peers.insert(peer, Instant::now() + duration);
+        self.pending_events.push_back(TransportEvent::PeerBanned { peer, duration });
+
+        if self.connection_handles.contains_key(&peer) {
+            let _ = self.disconnect(peer);
+        }
+    }
+
+    /// Notify `protocol` that the dial it requested to `peer` failed before any connection was
+    /// attempted.
+    fn report_dial_failure(&mut self, protocol: &ProtocolName, peer: PeerId, address: Multiaddr) {
+        let Some(context) = self.protocols.get(protocol) else {
+            tracing::debug!(target: LOG_TARGET, ?protocol, "dialing protocol doesn't exist");
+            return;
+        };
+
+        Self::notify_protocol(
+            &self.pending_notifications,
+            &context.tx,
+            InnerTransportEvent::DialFailure { peer, address },
+        );
+    }
+
+    /// Mark `peer` as persistent.
+    ///
+    /// The peer is dialed if it's not connected and re-dialed whenever it disconnects or a dial
+    /// to it fails, until [`TransportManager::remove_persistent_peer()`] is called.
+    pub fn add_persistent_peer(&mut self, peer: PeerId) {
+        if self.persistent_peers.insert(peer, 0).is_some() {
+            return;
+        }
+
+        tracing::debug!(target: LOG_TARGET, ?peer, "add persistent peer");
+
+        if !self.connection_handles.contains_key(&peer) {
+            self.schedule_reconnect(peer, Duration::ZERO);
+        }
+    }
+
+    /// Stop reconnecting to `peer`.
+    ///
+    /// Open connections to the peer are not closed.
+    pub fn remove_persistent_peer(&mut self, peer: PeerId) {
+        if self.persistent_peers.remove(&peer).is_some() {
+            tracing::debug!(target: LOG_TARGET, ?peer, "remove persistent peer");
+        }
+    }
+
+    /// Mark `peer` as reserved.
+    ///
+    /// Reserved peers are persistent and connections to them are exempt from the limits of the
+    /// local node: inbound connections from them are accepted even if they exceed the IP limits,
+    /// their connections are never closed to make room for other peers and their connections are
+    /// kept open even if no protocol is interested in them.
+    pub fn add_reserved_peer(&mut self, peer: PeerId) {
+        if !self.reserved_peers.write().insert(peer) {
+            return;
+        }
+
+        tracing::debug!(target: LOG_TARGET, ?peer, "add reserved peer");
+
+        self.add_persistent_peer(peer);
+    }
+
+    /// Remove `peer` from reserved peers and stop reconnecting to it.
+    ///
+    /// Open connections to the peer are not closed and the connections established while the peer
+    /// was reserved are kept open until either side closes them.
+    pub fn remove_reserved_peer(&mut self, peer: PeerId) {
+        if !self.reserved_peers.write().remove(&peer) {
+            return;
+        }
+
+        tracing::debug!(target: LOG_TARGET, ?peer, "remove reserved peer");
+
+        self.remove_persistent_peer(peer);
+    }
+
+    /// Check if `peer` is reserved.
+    fn is_reserved(&self, peer: &PeerId) -> bool {
+        self.reserved_peers.read().contains(peer)
+    }
+
+    /// Schedule reconnection attempt to `peer` after `delay`.
+    fn schedule_reconnect(&mut self, peer: PeerId, delay: Duration) {
+        if !self.scheduled_reconnects.insert(peer) {
+            return;
+        }
+
+        self.pending_reconnects.push(Box::pin(async move {
+            tokio::time::sleep(delay).await;
+            peer
+        }));
+    }
+
+    /// Schedule reconnection to `peer` if it's persistent and not connected.
+    fn on_peer_lost(&mut self, peer: PeerId) {
+        let Some(attempts) = self.persistent_peers.get_mut(&peer) else {
+            return;
+        };
+
+        if self.connection_handles.contains_key(&peer) {
+            return;
+        }
+
+        let delay = self.reconnect_config.backoff(*attempts);
+        *attempts = attempts.saturating_add(1);
+
+        tracing::trace!(target: LOG_TARGET, ?peer, ?delay, "schedule reconnection");
+
+        self.schedule_reconnect(peer, delay);
+    }
+
+    /// Check if `peer` is banned, removing the ban if it has expired.
+    fn is_banned(&mut self, peer: &PeerId) -> bool {
+        match self.banned_peers.get(peer) {
+            Some(expires) if *expires > Instant::now() => true,
+            Some(_) => {
+                tracing::trace!(target: LOG_TARGET, ?peer, "ban expired");
+
+                self.banned_peers.remove(peer);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Get information about the open connections of `peer`.
+    pub fn connection_info(&self, peer: &PeerId) -> Vec<ConnectionInfo> {
+        self.open_connections
+            .get(peer)
+            .map(|connections| connections.values().map(OpenConnection::info).collect())
+            .unwrap_or_default()
+    }
+
+    /// Disconnect `peer` by closing all connections to it.
+    ///
+    /// The connections are closed even if protocols have substreams open over them and
+    /// each installed protocol is notified of the closed connections.
+    pub fn disconnect(&mut self, peer: PeerId) -> crate::Result<()> {
+        let handles = self.connection_handles.get_mut(&peer).ok_or(Error::PeerDoesntExist(peer))?;
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            ?peer,
+            connections = ?handles.keys().collect::<Vec<_>>(),
+            "disconnect peer",
+        );
+
+        for handle in handles.values_mut() {
+            if let Err(error) = handle.force_close() {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    connection_id = ?handle.connection_id(),
+                    ?error,
+                    "failed to close connection",
+                );
+            }
+        }
+
         Ok(())
     }
 
+    /// Get the number of open connections.
+    fn num_connections(&self) -> usize {
+        self.open_connections.values().map(HashMap::len).sum()
+    }
+
+    /// Get the IDs of open connections.
+    fn open_connection_ids(&self) -> HashSet<ConnectionId> {
+        self.open_connections.values().flat_map(HashMap::keys).copied().collect()
+    }
+
+    /// Uninstall all protocols.
+    ///
+    /// The protocols are removed from all connections and their event loops exit once every
+    /// connection has dropped them. Returns receivers which are notified when the event loops of
+    /// the uninstalled protocols exit.
+    pub(crate) fn close_protocols(&mut self) -> HashMap<ProtocolName, oneshot::Receiver<()>> {
+        tracing::debug!(
+            target: LOG_TARGET,
+            protocols = ?self.protocols.keys().collect::<Vec<_>>(),
+            "close protocols",
+        );
+
+        self.protocols.clear();
+        self.protocol_names.clear();
+        self.protocol_registry.update(HashMap::new());
+
+        std::mem::take(&mut self.protocol_exits)
+    }
+
+    /// Forcibly close all open connections which are not in `closing` and stop reconnecting to
+    /// persistent peers.
+    ///
+    /// The IDs of the closed connections are added to `closing`.
+    pub(crate) fn close_connections(&mut self, closing: &mut HashSet<ConnectionId>) {
+        self.persistent_peers.clear();
+        self.reserved_peers.write().clear();
+        self.scheduled_reconnects.clear();
+        self.pending_reconnects = FuturesUnordered::new();
+
+        for (peer, handles) in self.connection_handles.iter_mut() {
+            for (connection_id, handle) in handles.iter_mut() {
+                if !closing.insert(*connection_id) {
+                    continue;
+                }
+
+                if let Err(error) = handle.force_close() {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        ?connection_id,
+                        ?error,
+                        "failed to close connection",
+                    );
+                }
+            }
+        }
+    }
+
+    /// Stop `transport` while the other transports keep running.
+    ///
+    /// The listeners of the transport are closed by dropping the transport, connections
+    /// established over it are closed and its dials in progress are reported as failed.
+    ///
+    /// Returns the listen addresses of the stopped transport.
+    pub(crate) fn stop_transport(
+        &mut self,
+        transport: SupportedTransport,
+    ) -> crate::Result<Vec<Multiaddr>> {
+        let Some(stopped) = self.transports.remove_transport(&transport) else {
+            return Err(Error::NotSupported(format!(
+                "transport `{transport:?}` is not running"
+            )));
+        };
+        self.transport_manager_handle.unregister_transport(transport);
+
+        tracing::debug!(target: LOG_TARGET, ?transport, "stop transport");
+
+        for (peer, connections) in &self.open_connections {
+            for (connection_id, _) in
+                connections.iter().filter(|(_, connection)| connection.transport == transport)
+            {
+                let Some(handle) = self
+                    .connection_handles
+                    .get_mut(peer)
+                    .and_then(|handles| handles.get_mut(connection_id))
+                else {
+                    continue;
+                };
+
+                if let Err(error) = handle.force_close() {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        ?connection_id,
+                        ?error,
+                        "failed to close connection",
+                    );
+                }
+            }
+        }
+
+        // dials in progress are never concluded by the stopped transport so report them as failed
+        let failures = {
+            let peers = self.peers.read();
+
+            self.pending_connections
+                .iter()
+                .filter_map(|(connection_id, peer)| match &peers.get(peer)?.state {
+                    PeerState::Dialing { record }
+                        if record.connection_id() == &Some(*connection_id)
+                            && address_transport(record.address()) == transport =>
+                        Some(TransportEvent::DialFailure {
+                            connection_id: *connection_id,
+                            address: record.address().clone(),
+                            error: Error::TransportNotSupported(record.address().clone()),
+                        }),
+                    PeerState::Opening {
+                        connection_id: opening,
+                        transports,
+                        ..
+                    } if opening == connection_id && transports.contains(&transport) =>
+                        Some(TransportEvent::OpenFailure {
+                            connection_id: *connection_id,
+                            errors: Vec::new(),
+                        }),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        for event in failures {
+            let event = self.on_transport_event(transport, event);
+            self.pending_events.extend(event);
+        }
+
+        let addresses = self
+            .listen_addresses
+            .read()
+            .iter()
+            .filter(|address| {
+                !address.iter().any(|protocol| std::matches!(protocol, Protocol::P2p(_)))
+                    && address_transport(address) == transport
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for address in &addresses {
+            self.unregister_listen_address(address);
+        }
+        drop(stopped);
+
+        Ok(addresses)
+    }
+
+    /// Stop all transports, unbinding their listeners.
+    pub(crate) fn close_transports(&mut self) {
+        tracing::debug!(
+            target: LOG_TARGET,
+            transports = ?self.transports.transports.keys().collect::<Vec<_>>(),
+            "close transports",
+        );
+
+        self.transports = TransportContext::new();
+        self.listen_addresses.write().clear();
+    }
+
+    /// Shut down protocols, connections and transports, in that order.
+    ///
+    /// The protocols are uninstalled first so their event loops exit before the connections are
+    /// closed and they don't mistake the closing connections for failures of the remote peers.
+    /// Each stage is given at most `timeout` to complete before moving on to the next stage.
+    /// Events emitted by the transports while the protocols and connections are being closed
+    /// are discarded.
+    pub(crate) async fn shutdown(&mut self, timeout: Duration) -> ShutdownSummary {
+        let mut summary = ShutdownSummary::default();
+        let mut exhausted = false;
+
+        // connections may close as soon as the protocols are closed if no protocol keeps them
+        // open so the connections are tracked from the start
+        let mut closing = self.open_connection_ids();
+
+        let mut pending = HashSet::new();
+        let mut protocols = self
+            .close_protocols()
+            .into_iter()
+            .map(|(protocol, exit)| {
+                pending.insert(protocol.clone());
+
+                async move {
+                    let _ = exit.await;
+                    protocol
+                }
+            })
+            .collect::<FuturesUnordered<_>>();
+
+        let deadline = tokio::time::sleep(timeout);
+        tokio::pin!(deadline);
+
+        while !protocols.is_empty() {
+            tokio::select! {
+                _ = &mut deadline => break,
+                Some(protocol) = protocols.next() => {
+                    tracing::trace!(target: LOG_TARGET, ?protocol, "protocol closed");
+
+                    pending.remove(&protocol);
+                    summary.protocols_closed += 1;
+                }
+                event = self.next(), if !exhausted => exhausted = event.is_none(),
+            }
+        }
+        summary.protocols_timed_out = pending.into_iter().collect();
+
+        // connections established while the connections are being closed are closed as well
+        let mut closed = HashSet::new();
+        self.close_connections(&mut closed);
+
+        let deadline = tokio::time::sleep(timeout);
+        tokio::pin!(deadline);
+
+        while self.num_connections() > 0 {
+            tokio::select! {
+                _ = &mut deadline => break,
+                event = self.next(), if !exhausted => {
+                    exhausted = event.is_none();
+                    self.close_connections(&mut closed);
+                }
+            }
+        }
+        closing.extend(closed);
+
+        let open = self.open_connection_ids();
+        summary.connections_closed = closing.difference(&open).count();
+        summary.connections_timed_out = open.len();
+
+        self.close_transports();
+
+        tracing::debug!(target: LOG_TARGET, ?summary, "shutdown complete");
+
+        summary
+    }
+
     /// Handle dial failure.
     fn on_dial_failure(&mut self, connection_id: ConnectionId) -> crate::Result<()> {
         let peer = self.pending_connections.remove(&connection_id).ok_or_else(|| {
@@ -699,6 +1892,9 @@ impl TransportManager {
                 debug_assert_eq!(record.connection_id(), &Some(connection_id));
 
                 record.update_score(SCORE_DIAL_FAILURE);
+                self.transport_manager_handle
+                    .peer_store()
+                    .on_address_dial_failure(peer, record.address().clone());
                 context.addresses.insert(record.clone());
 
                 context.state = PeerState::Disconnected { dial_record: None };
@@ -712,6 +1908,9 @@ impl TransportManager {
                 dial_record: Some(mut dial_record),
             } => {
                 dial_record.update_score(SCORE_DIAL_FAILURE);
+                self.transport_manager_handle
+                    .peer_store()
+                    .on_address_dial_failure(peer, dial_record.address().clone());
                 context.addresses.insert(dial_record);
 
                 context.state = PeerState::Connected {
@@ -731,6 +1930,9 @@ impl TransportManager {
                 );
 
                 dial_record.update_score(SCORE_DIAL_FAILURE);
+                self.transport_manager_handle
+                    .peer_store()
+                    .on_address_dial_failure(peer, dial_record.address().clone());
                 context.addresses.insert(dial_record);
 
                 Ok(())
@@ -758,6 +1960,7 @@ impl TransportManager {
         &mut self,
         peer: PeerId,
         connection_id: ConnectionId,
+        reason: DisconnectReason,
     ) -> crate::Result<Option<TransportEvent>> {
         let mut peers = self.peers.write();
         let Some(context) = peers.get_mut(&peer) else {
@@ -801,6 +2004,7 @@ impl TransportManager {
                         return Ok(Some(TransportEvent::ConnectionClosed {
                             peer,
                             connection_id,
+                            reason,
                         }));
                     }
                     Some(secondary_connection) => {
@@ -881,6 +2085,7 @@ impl TransportManager {
                     Ok(Some(TransportEvent::ConnectionClosed {
                         peer,
                         connection_id,
+                        reason,
                     }))
                 }
             },
@@ -1044,10 +2249,9 @@ impl TransportManager {
 
                     // cancel all pending dials
                     transports.iter().for_each(|transport| {
-                        self.transports
-                            .get_mut(transport)
-                            .expect("transport to exist")
-                            .cancel(connection_id);
+                        if let Some(transport) = self.transports.get_mut(transport) {
+                            transport.cancel(connection_id);
+                        }
                     });
 
                     // since an inbound connection was removed, the outbound connection can be
@@ -1172,6 +2376,8 @@ impl TransportManager {
             return Err(Error::InvalidState);
         };
 
+        self.pending_dial_errors.remove(&connection_id);
+
         let mut peers = self.peers.write();
         let context = peers.get_mut(&peer).ok_or_else(|| {
             tracing::warn!(
@@ -1205,11 +2411,9 @@ impl TransportManager {
 
                 // cancel open attempts for other transports as connection already exists
                 for transport in transports.iter() {
-                    let _ = self
-                        .transports
-                        .get_mut(&transport)
-                        .expect("transport to exist")
-                        .cancel(connection_id);
+                    if let Some(transport) = self.transports.get_mut(transport) {
+                        transport.cancel(connection_id);
+                    }
                 }
 
                 // set peer state to `Dialing` to signal that the connection is fully opening
@@ -1224,9 +2428,9 @@ impl TransportManager {
                 match self
                     .transports
                     .get_mut(&transport)
-                    .expect("transport to exist")
-                    .negotiate(connection_id)
-                {
+                    .map_or(Err(Error::EssentialTaskClosed), |transport| {
+                        transport.negotiate(connection_id)
+                    }) {
                     Ok(()) => {
                         tracing::trace!(
                             target: LOG_TARGET,
@@ -1281,10 +2485,14 @@ impl TransportManager {
     }
 
     /// Handle open failure for dialing attempt for `transport`
+    ///
+    /// Per-address `errors` are collected in `pending_dial_errors` until the last transport
+    /// dialing the peer fails, at which point the peer is returned.
     fn on_open_failure(
         &mut self,
         transport: SupportedTransport,
         connection_id: ConnectionId,
+        errors: Vec<(Multiaddr, Error)>,
     ) -> crate::Result<Option<PeerId>> {
         let Some(peer) = self.pending_connections.remove(&connection_id) else {
             tracing::warn!(
@@ -1310,6 +2518,8 @@ impl TransportManager {
             Error::InvalidState
         })?;
 
+        self.pending_dial_errors.entry(connection_id).or_default().extend(errors);
+
         match std::mem::replace(
             &mut context.state,
             PeerState::Disconnected { dial_record: None },
@@ -1331,6 +2541,9 @@ impl TransportManager {
                 if transports.is_empty() {
                     for (_, mut record) in records {
                         record.update_score(SCORE_DIAL_FAILURE);
+                        self.transport_manager_handle
+                            .peer_store()
+                            .on_address_dial_failure(peer, record.address().clone());
                         context.addresses.insert(record);
                     }
 
@@ -1362,6 +2575,7 @@ impl TransportManager {
                     "open failure but `PeerState` is not `Opening`",
                 );
                 context.state = state;
+                self.pending_dial_errors.remove(&connection_id);
 
                 debug_assert!(false);
                 Err(Error::InvalidState)
@@ -1369,247 +2583,784 @@ impl TransportManager {
         }
     }
 
-    /// Poll next event from [`crate::transport::manager::TransportManager`].
-    pub async fn next(&mut self) -> Option<TransportEvent> {
-        loop {
-            tokio::select! {
-                event = self.event_rx.recv() => match event? {
-                    TransportManagerEvent::ConnectionClosed {
-                        peer,
-                        connection: connection_id,
-                    } => match self.on_connection_closed(peer, connection_id) {
-                        Ok(None) => {}
-                        Ok(Some(event)) => return Some(event),
-                        Err(error) => tracing::error!(
-                            target: LOG_TARGET,
-                            ?error,
-                            "failed to handle closed connection",
-                        ),
+    /// Accept or reject a negotiated connection based on the state of the peer.
+    fn on_negotiated_connection(
+        &mut self,
+        transport: SupportedTransport,
+        peer: PeerId,
+        endpoint: Endpoint,
+    ) -> Option<TransportEvent> {
+        match self.on_connection_established(peer, &endpoint) {
+            Err(error) => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    ?endpoint,
+                    ?error,
+                    "failed to handle established connection",
+                );
+
+                let _ = self
+                    .transports
+                    .get_mut(&transport)
+                    .map(|transport| transport.reject(endpoint.connection_id()));
+                self.report_dial_result(endpoint.connection_id(), Err(error));
+
+                None
+            }
+            Ok(ConnectionEstablishedResult::Accept) => {
+                tracing::trace!(target: LOG_TARGET, ?peer, ?endpoint, "accept connection");
+
+                self.report_dial_result(endpoint.connection_id(), Ok(peer));
+
+                if let Some(attempts) = self.persistent_peers.get_mut(&peer) {
+                    *attempts = 0;
+                }
+
+                if !endpoint.is_listener() {
+                    self.transport_manager_handle
+                        .peer_store()
+                        .on_address_dial_success(peer, endpoint.address().clone());
+                }
+
+                // the lifetime of the connection starts when the transport accepts it so the
+                // deadline must be recorded before that to detect expiration reliably
+                if let Some(lifetime) = self.max_connection_lifetime {
+                    if !endpoint.is_listener() {
+                        self.connection_deadlines
+                            .insert(endpoint.connection_id(), Instant::now() + lifetime);
                     }
-                },
-                command = self.cmd_rx.recv() => match command? {
-                    InnerTransportManagerCommand::DialPeer { peer } => {
-                        if let Err(error) = self.dial(peer).await {
-                            tracing::debug!(target: LOG_TARGET, ?peer, ?error, "failed to dial peer")
-                        }
+                }
+
+                let _ = self
+                    .transports
+                    .get_mut(&transport)
+                    .map(|transport| transport.accept(endpoint.connection_id()));
+
+                self.open_connections.entry(peer).or_default().insert(
+                    endpoint.connection_id(),
+                    OpenConnection {
+                        endpoint: endpoint.clone(),
+                        transport,
+                        established: Instant::now(),
+                        protocols: Default::default(),
+                    },
+                );
+
+                if self.connection_probing {
+                    if let Some(endpoints) = self.pending_probes.get_mut(&peer) {
+                        endpoints.push(endpoint);
+                        return None;
                     }
-                    InnerTransportManagerCommand::DialAddress { address } => {
-                        if let Err(error) = self.dial_address(address).await {
-                            tracing::debug!(target: LOG_TARGET, ?error, "failed to dial peer")
-                        }
+
+                    // only the primary connection is probed, the other connections are
+                    // reported once the probe for the primary connection has succeeded
+                    if self.is_primary_connection(&peer, &endpoint) {
+                        tracing::trace!(
+                            target: LOG_TARGET,
+                            ?peer,
+                            ?endpoint,
+                            "probe connection before reporting it",
+                        );
+
+                        self.pending_probes.insert(peer, vec![endpoint]);
+                        return None;
                     }
-                },
-                event = self.transports.next() => {
-                    let (transport, event) = event?;
+                }
 
-                    match event {
-                        TransportEvent::DialFailure { connection_id, address, error } => {
-                            tracing::debug!(
-                                target: LOG_TARGET,
-                                ?connection_id,
-                                ?address,
-                                ?error,
-                                "failed to dial peer",
-                            );
+                Some(TransportEvent::ConnectionEstablished { peer, endpoint })
+            }
+            Ok(ConnectionEstablishedResult::Reject) => {
+                tracing::trace!(target: LOG_TARGET, ?peer, ?endpoint, "reject connection");
 
-                            if let Ok(()) = self.on_dial_failure(connection_id) {
-                                match address.iter().last() {
-                                    Some(Protocol::P2p(hash)) => match PeerId::from_multihash(hash) {
-                                        Ok(peer) => {
-                                            tracing::trace!(
-                                                target: LOG_TARGET,
-                                                ?connection_id,
-                                                ?error,
-                                                ?address,
-                                                num_protocols = self.protocols.len(),
-                                                "dial failure, notify protocols",
-                                            );
-
-                                            for (protocol, context) in &self.protocols {
-                                                tracing::trace!(
-                                                    target: LOG_TARGET,
-                                                    ?connection_id,
-                                                    ?error,
-                                                    ?address,
-                                                    ?protocol,
-                                                    "dial failure, notify protocol",
-                                                );
-                                                match context.tx.try_send(InnerTransportEvent::DialFailure {
-                                                    peer,
-                                                    address: address.clone(),
-                                                }) {
-                                                    Ok(()) => {}
-                                                    Err(_) => {
-                                                        tracing::trace!(
-                                                            target: LOG_TARGET,
-                                                            ?connection_id,
-                                                            ?error,
-                                                            ?address,
-                                                            ?protocol,
-                                                            "dial failure, channel to protocol clogged, use await",
-                                                        );
-                                                        let _ = context
-                                                            .tx
-                                                            .send(InnerTransportEvent::DialFailure {
-                                                                peer,
-                                                                address: address.clone(),
-                                                            })
-                                                            .await;
-                                                    }
-                                                }
-                                            }
-
-                                            tracing::trace!(
-                                                target: LOG_TARGET,
-                                                ?connection_id,
-                                                ?error,
-                                                ?address,
-                                                "all protocols notified",
-                                            );
-                                        }
-                                        Err(error) => {
-                                            tracing::warn!(
-                                                target: LOG_TARGET,
-                                                ?address,
-                                                ?connection_id,
-                                                ?error,
-                                                "failed to parse `PeerId` from `Multiaddr`",
-                                            );
-                                            debug_assert!(false);
-                                        }
-                                    },
-                                    _ => {
-                                        tracing::warn!(target: LOG_TARGET, ?address, ?connection_id, "address doesn't contain `PeerId`");
-                                        debug_assert!(false);
-                                    }
-                                }
-
-                                return Some(TransportEvent::DialFailure {
-                                    connection_id,
-                                    address,
-                                    error,
-                                })
-                            }
-                        }
-                        TransportEvent::ConnectionEstablished { peer, endpoint } => {
-                            match self.on_connection_established(peer, &endpoint) {
-                                Err(error) => {
-                                    tracing::debug!(
-                                        target: LOG_TARGET,
-                                        ?peer,
-                                        ?endpoint,
-                                        ?error,
-                                        "failed to handle established connection",
-                                    );
-
-                                    let _ = self
-                                        .transports
-                                        .get_mut(&transport)
-                                        .expect("transport to exist")
-                                        .reject(endpoint.connection_id());
-                                }
-                                Ok(ConnectionEstablishedResult::Accept) => {
-                                    tracing::trace!(
-                                        target: LOG_TARGET,
-                                        ?peer,
-                                        ?endpoint,
-                                        "accept connection",
-                                    );
-
-                                    let _ = self
-                                        .transports
-                                        .get_mut(&transport)
-                                        .expect("transport to exist")
-                                        .accept(endpoint.connection_id());
-
-                                    return Some(TransportEvent::ConnectionEstablished {
-                                        peer,
-                                        endpoint: endpoint,
-                                    });
-                                }
-                                Ok(ConnectionEstablishedResult::Reject) => {
-                                    tracing::trace!(
-                                        target: LOG_TARGET,
-                                        ?peer,
-                                        ?endpoint,
-                                        "reject connection",
-                                    );
-
-                                    let _ = self
-                                        .transports
-                                        .get_mut(&transport)
-                                        .expect("transport to exist")
-                                        .reject(endpoint.connection_id());
-                                }
-                            }
-                        }
-                        TransportEvent::ConnectionOpened { connection_id, address } => {
-                            if let Err(error) = self.on_connection_opened(transport, connection_id, address) {
+                let _ = self
+                    .transports
+                    .get_mut(&transport)
+                    .map(|transport| transport.reject(endpoint.connection_id()));
+                self.report_dial_result(
+                    endpoint.connection_id(),
+                    Err(Error::ConnectionRejected(RejectReason::ConnectionLimit)),
+                );
+
+                Some(TransportEvent::ConnectionRejected {
+                    peer,
+                    endpoint,
+                    reason: RejectReason::ConnectionLimit,
+                })
+            }
+        }
+    }
+
+    /// Check if `endpoint` is the primary connection of `peer`.
+    fn is_primary_connection(&self, peer: &PeerId, endpoint: &Endpoint) -> bool {
+        match self.peers.read().get(peer) {
+            Some(PeerContext {
+                state: PeerState::Connected { record, .. },
+                ..
+            }) => record.connection_id() == &Some(endpoint.connection_id()),
+            _ => false,
+        }
+    }
+
+    /// Handle the probe result of the primary connection of `peer`.
+    fn on_connection_probed(&mut self, peer: PeerId, success: bool) {
+        let endpoints = match self.pending_probes.remove(&peer) {
+            Some(endpoints) => endpoints,
+            None => {
+                tracing::trace!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    ?success,
+                    "probe result for a peer with no pending probe",
+                );
+                return;
+            }
+        };
+
+        tracing::trace!(target: LOG_TARGET, ?peer, ?success, "connection probed");
+
+        match success {
+            true => self.pending_events.extend(
+                endpoints
+                    .into_iter()
+                    .map(|endpoint| TransportEvent::ConnectionEstablished { peer, endpoint }),
+            ),
+            false => self
+                .failed_probes
+                .extend(endpoints.iter().map(|endpoint| endpoint.connection_id())),
+        }
+    }
+
+    /// Handle closed connection that may still be held for probing.
+    ///
+    /// Returns `true` if the connection was reported to the user.
+    fn on_probed_connection_closed(&mut self, peer: PeerId, connection_id: ConnectionId) -> bool {
+        if self.failed_probes.remove(&connection_id) {
+            return false;
+        }
+
+        let Some(endpoints) = self.pending_probes.get_mut(&peer) else {
+            return true;
+        };
+
+        match endpoints.iter().position(|endpoint| endpoint.connection_id() == connection_id) {
+            None => true,
+            Some(index) => {
+                endpoints.remove(index);
+
+                // the probed connection was closed before the probe concluded and since
+                // the remaining connections won't be probed, report them to the user
+                if index == 0 {
+                    let endpoints = self.pending_probes.remove(&peer).expect("entry to exist");
+
+                    self.pending_events.extend(
+                        endpoints.into_iter().map(|endpoint| {
+                            TransportEvent::ConnectionEstablished { peer, endpoint }
+                        }),
+                    );
+                }
+
+                false
+            }
+        }
+    }
+
+    /// Poll next event from [`crate::transport::manager::TransportManager`].
+    pub async fn next(&mut self) -> Option<TransportEvent> {
+        futures::future::poll_fn(|cx| self.poll_next_event(cx)).await
+    }
+
+    /// Send `event` to a protocol over `tx`.
+    ///
+    /// If the channel is clogged, the event is sent in the background so that the transport
+    /// manager doesn't block on a slow protocol.
+    fn notify_protocol(
+        pending_notifications: &FuturesUnordered<BoxFuture<'static, ()>>,
+        tx: &Sender<InnerTransportEvent>,
+        event: InnerTransportEvent,
+    ) {
+        if let Err(TrySendError::Full(event)) = tx.try_send(event) {
+            let tx = tx.clone();
+
+            pending_notifications.push(Box::pin(async move {
+                let _ = tx.send(event).await;
+            }));
+        }
+    }
+
+    /// Handle event received from a connection.
+    fn on_manager_event(&mut self, event: TransportManagerEvent) -> Option<TransportEvent> {
+        match event {
+            TransportManagerEvent::ConnectionEstablished {
+                peer,
+                connection: connection_id,
+                handle,
+                protocols,
+            } => {
+                self.transport_manager_handle.peer_store().on_connection_established(peer);
+                self.connection_handles.entry(peer).or_default().insert(connection_id, handle);
+
+                if let Some(connection) = self
+                    .open_connections
+                    .get_mut(&peer)
+                    .and_then(|connections| connections.get_mut(&connection_id))
+                {
+                    connection.protocols = protocols;
+                }
+
+                None
+            }
+            TransportManagerEvent::ConnectionClosed {
+                peer,
+                connection: connection_id,
+                reason,
+            } => {
+                self.transport_manager_handle.peer_store().on_connection_closed(peer);
+
+                if let Some(handles) = self.connection_handles.get_mut(&peer) {
+                    handles.remove(&connection_id);
+
+                    if handles.is_empty() {
+                        self.connection_handles.remove(&peer);
+                    }
+                }
+
+                if let Some(connections) = self.open_connections.get_mut(&peer) {
+                    connections.remove(&connection_id);
+
+                    if connections.is_empty() {
+                        self.open_connections.remove(&peer);
+                        self.transport_manager_handle.peer_store().on_peer_disconnected(peer);
+                        self.transport_manager_handle.reputation().prune();
+                    }
+                }
+
+                let reported = self.on_probed_connection_closed(peer, connection_id);
+                self.on_peer_lost(peer);
+                let expired = match self.connection_deadlines.remove(&connection_id) {
+                    Some(deadline) => deadline <= Instant::now(),
+                    None => false,
+                };
+
+                match self.on_connection_closed(peer, connection_id, reason) {
+                    Ok(Some(event)) if reported => {
+                        // the peer was disconnected because the lifetime of the
+                        // connection expired, re-establish the connection
+                        if expired {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?peer,
+                                ?connection_id,
+                                "connection lifetime expired, redial peer",
+                            );
+
+                            if let Err(error) = self.dial(peer) {
                                 tracing::debug!(
                                     target: LOG_TARGET,
-                                    ?connection_id,
+                                    ?peer,
                                     ?error,
-                                    "failed to handle opened connection",
+                                    "failed to redial peer",
                                 );
                             }
                         }
-                        TransportEvent::OpenFailure { connection_id } => {
-                            match self.on_open_failure(transport, connection_id) {
-                                Err(error) => tracing::debug!(
-                                    target: LOG_TARGET,
-                                    ?connection_id,
-                                    ?error,
-                                    "failed to handle opened connection",
-                                ),
-                                Ok(Some(peer)) => {
-                                    tracing::trace!(
-                                        target: LOG_TARGET,
-                                        ?peer,
-                                        ?connection_id,
-                                        num_protocols = self.protocols.len(),
-                                        "inform protocols about open failure",
-                                    );
-
-                                    for (protocol, context) in &self.protocols {
-                                        let _ = match context
-                                            .tx
-                                            .try_send(InnerTransportEvent::DialFailure {
-                                                peer,
-                                                address: Multiaddr::empty(),
-                                            }) {
-                                            Ok(_) => Ok(()),
-                                            Err(_) => {
-                                                tracing::trace!(
-                                                    target: LOG_TARGET,
-                                                    ?peer,
-                                                    %protocol,
-                                                    ?connection_id,
-                                                    "call to protocol would, block try sending in a blocking way",
-                                                );
-
-                                                context
-                                                    .tx
-                                                    .send(InnerTransportEvent::DialFailure {
-                                                        peer,
-                                                        address: Multiaddr::empty(),
-                                                    })
-                                                    .await
-                                            }
-                                        };
-                                    }
-
-                                    return Some(TransportEvent::DialFailure {
-                                        connection_id,
-                                        address: Multiaddr::empty(),
-                                        error: Error::Unknown,
-                                    })
-                                }
-                                Ok(None) => {}
+
+                        Some(event)
+                    }
+                    Ok(_) => None,
+                    Err(error) => {
+                        tracing::error!(
+                            target: LOG_TARGET,
+                            ?error,
+                            "failed to handle closed connection",
+                        );
+                        None
+                    }
+                }
+            }
+            TransportManagerEvent::QuicPathStats {
+                peer,
+                connection: connection_id,
+                stats,
+            } => Some(TransportEvent::QuicPathStats {
+                peer,
+                connection_id,
+                stats,
+            }),
+        }
+    }
+
+    /// Reconnect to `peer` once its reconnection delay has elapsed, if it's still persistent.
+    fn on_reconnect(&mut self, peer: PeerId) {
+        self.scheduled_reconnects.remove(&peer);
+
+        if self.persistent_peers.contains_key(&peer) {
+            match self.dial(peer) {
+                Ok(()) | Err(Error::AlreadyConnected) => {}
+                Err(error) => {
+                    tracing::debug!(target: LOG_TARGET, ?peer, ?error, "failed to reconnect to peer");
+                    self.on_peer_lost(peer);
+                }
+            }
+        }
+    }
+
+    /// Handle the user's decision on an inbound connection awaiting approval.
+    fn on_connection_approval(
+        &mut self,
+        transport: SupportedTransport,
+        peer: PeerId,
+        endpoint: Endpoint,
+        approval: ConnectionApproval,
+    ) -> Option<TransportEvent> {
+        match approval {
+            ConnectionApproval::Accept => self.on_negotiated_connection(transport, peer, endpoint),
+            ConnectionApproval::Reject { reason } => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    ?endpoint,
+                    ?reason,
+                    "inbound connection rejected by user",
+                );
+
+                let _ = self
+                    .transports
+                    .get_mut(&transport)
+                    .map(|transport| transport.reject(endpoint.connection_id()));
+
+                None
+            }
+        }
+    }
+
+    /// Handle command received from [`TransportManagerHandle`].
+    fn on_command(&mut self, command: InnerTransportManagerCommand) {
+        match command {
+            InnerTransportManagerCommand::DialPeer { peer, protocol } => match self.dial(peer) {
+                Ok(()) | Err(Error::AlreadyConnected) => {}
+                Err(error) => {
+                    tracing::debug!(target: LOG_TARGET, ?peer, ?error, "failed to dial peer");
+
+                    self.report_dial_failure(&protocol, peer, Multiaddr::empty());
+                }
+            },
+            InnerTransportManagerCommand::DialAddress { address, protocol } => {
+                if let Err(error) = self.dial_address(address.clone()) {
+                    tracing::debug!(target: LOG_TARGET, ?error, "failed to dial peer");
+
+                    if let Some(peer) = PeerId::try_from_multiaddr(&address) {
+                        self.report_dial_failure(&protocol, peer, address);
+                    }
+                }
+            }
+            InnerTransportManagerCommand::ConnectionProbed { peer, success } => {
+                self.on_connection_probed(peer, success);
+            }
+            InnerTransportManagerCommand::BanPeer { peer, duration } => {
+                self.ban_peer(peer, duration);
+            }
+            InnerTransportManagerCommand::AddPersistentPeer { peer } => {
+                self.add_persistent_peer(peer);
+            }
+            InnerTransportManagerCommand::RemovePersistentPeer { peer } => {
+                self.remove_persistent_peer(peer);
+            }
+            InnerTransportManagerCommand::AddReservedPeer { peer } => {
+                self.add_reserved_peer(peer);
+            }
+            InnerTransportManagerCommand::RemoveReservedPeer { peer } => {
+                self.remove_reserved_peer(peer);
+            }
+            InnerTransportManagerCommand::Disconnect { peer } => {
+                if let Err(error) = self.disconnect(peer) {
+                    tracing::debug!(target: LOG_TARGET, ?peer, ?error, "failed to disconnect peer")
+                }
+            }
+        }
+    }
+
+    /// Handle event received from `transport`.
+    fn on_transport_event(
+        &mut self,
+        transport: SupportedTransport,
+        event: TransportEvent,
+    ) -> Option<TransportEvent> {
+        match event {
+            TransportEvent::DialFailure {
+                connection_id,
+                address,
+                error,
+            } => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?connection_id,
+                    ?address,
+                    ?error,
+                    "failed to dial peer",
+                );
+
+                if self.on_dial_failure(connection_id).is_err() {
+                    return None;
+                }
+
+                match address.iter().last() {
+                    Some(Protocol::P2p(hash)) => match PeerId::from_multihash(hash) {
+                        Ok(peer) => {
+                            tracing::trace!(
+                                target: LOG_TARGET,
+                                ?connection_id,
+                                ?error,
+                                ?address,
+                                num_protocols = self.protocols.len(),
+                                "dial failure, notify protocols",
+                            );
+                            self.transport_manager_handle.peer_store().on_dial_failure(peer);
+                            self.on_peer_lost(peer);
+
+                            for context in self.protocols.values() {
+                                Self::notify_protocol(
+                                    &self.pending_notifications,
+                                    &context.tx,
+                                    InnerTransportEvent::DialFailure {
+                                        peer,
+                                        address: address.clone(),
+                                    },
+                                );
                             }
                         }
-                        _ => panic!("event not supported"),
+                        Err(error) => {
+                            tracing::warn!(
+                                target: LOG_TARGET,
+                                ?address,
+                                ?connection_id,
+                                ?error,
+                                "failed to parse `PeerId` from `Multiaddr`",
+                            );
+                            debug_assert!(false);
+                        }
+                    },
+                    _ => {
+                        tracing::warn!(target: LOG_TARGET, ?address, ?connection_id, "address doesn't contain `PeerId`");
+                        debug_assert!(false);
                     }
-                },
+                }
+
+                if self.pending_dial_results.contains_key(&connection_id) {
+                    self.report_dial_result(connection_id, Err(error));
+                    return None;
+                }
+
+                Some(TransportEvent::DialFailure {
+                    connection_id,
+                    address,
+                    error,
+                })
+            }
+            TransportEvent::ConnectionEstablished { peer, endpoint } => {
+                // injected connections were explicitly added by the user
+                let injected = self.injected_connections.remove(&endpoint.connection_id());
+
+                if endpoint.is_listener() && self.is_banned(&peer) {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        ?endpoint,
+                        "reject inbound connection from banned peer",
+                    );
+
+                    let _ = self
+                        .transports
+                        .get_mut(&transport)
+                        .map(|transport| transport.reject(endpoint.connection_id()));
+
+                    return Some(TransportEvent::ConnectionRejected {
+                        peer,
+                        endpoint,
+                        reason: RejectReason::PeerBanned,
+                    });
+                }
+
+                if endpoint.is_listener()
+                    && !injected
+                    && self
+                        .allow_list
+                        .as_ref()
+                        .is_some_and(|allow_list| !allow_list.is_allowed(&peer, endpoint.address()))
+                {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        ?endpoint,
+                        "reject inbound connection from peer not on the allow-list",
+                    );
+
+                    let _ = self
+                        .transports
+                        .get_mut(&transport)
+                        .map(|transport| transport.reject(endpoint.connection_id()));
+
+                    return Some(TransportEvent::ConnectionRejected {
+                        peer,
+                        endpoint,
+                        reason: RejectReason::NotAllowed,
+                    });
+                }
+
+                if endpoint.is_listener()
+                    && !injected
+                    && !self.is_reserved(&peer)
+                    && !self.is_within_ip_limits(endpoint.address())
+                    && !self.evict_less_reputable(peer, endpoint.address())
+                {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        ?endpoint,
+                        "reject inbound connection, too many connections from the address",
+                    );
+
+                    let _ = self
+                        .transports
+                        .get_mut(&transport)
+                        .map(|transport| transport.reject(endpoint.connection_id()));
+
+                    return Some(TransportEvent::ConnectionRejected {
+                        peer,
+                        endpoint,
+                        reason: RejectReason::IpLimit,
+                    });
+                }
+
+                if endpoint.is_listener()
+                    && !injected
+                    && self
+                        .connection_gater
+                        .as_ref()
+                        .is_some_and(|gater| !gater.allow_inbound_peer(&peer, endpoint.address()))
+                {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        ?endpoint,
+                        "inbound connection rejected by connection gater",
+                    );
+
+                    let _ = self
+                        .transports
+                        .get_mut(&transport)
+                        .map(|transport| transport.reject(endpoint.connection_id()));
+
+                    return Some(TransportEvent::ConnectionRejected {
+                        peer,
+                        endpoint,
+                        reason: RejectReason::Gated,
+                    });
+                }
+
+                if self.connection_approval && endpoint.is_listener() && !injected {
+                    tracing::trace!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        ?endpoint,
+                        "inbound connection awaiting approval",
+                    );
+
+                    let (approval_handle, rx) =
+                        ConnectionApprovalHandle::new(endpoint.connection_id());
+                    let address = endpoint.address().clone();
+
+                    self.pending_approvals.push(Box::pin(async move {
+                        let approval = rx.await.unwrap_or(ConnectionApproval::Reject {
+                            reason: String::from("approval handle dropped"),
+                        });
+
+                        (transport, peer, endpoint, approval)
+                    }));
+
+                    return Some(TransportEvent::IncomingConnection {
+                        peer,
+                        address,
+                        approval_handle,
+                    });
+                }
+
+                self.on_negotiated_connection(transport, peer, endpoint)
+            }
+            TransportEvent::ConnectionOpened {
+                connection_id,
+                address,
+            } => {
+                if let Err(error) = self.on_connection_opened(transport, connection_id, address) {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?connection_id,
+                        ?error,
+                        "failed to handle opened connection",
+                    );
+                }
+
+                None
+            }
+            TransportEvent::OpenFailure {
+                connection_id,
+                errors,
+            } => match self.on_open_failure(transport, connection_id, errors) {
+                Err(error) => {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?connection_id,
+                        ?error,
+                        "failed to handle opened connection",
+                    );
+                    None
+                }
+                Ok(Some(peer)) => {
+                    tracing::trace!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        ?connection_id,
+                        num_protocols = self.protocols.len(),
+                        "inform protocols about open failure",
+                    );
+                    self.transport_manager_handle.peer_store().on_dial_failure(peer);
+
+                    for context in self.protocols.values() {
+                        Self::notify_protocol(
+                            &self.pending_notifications,
+                            &context.tx,
+                            InnerTransportEvent::DialFailure {
+                                peer,
+                                address: Multiaddr::empty(),
+                            },
+                        );
+                    }
+
+                    Some(TransportEvent::DialFailure {
+                        connection_id,
+                        address: Multiaddr::empty(),
+                        error: Error::DialFailures(
+                            self.pending_dial_errors.remove(&connection_id).unwrap_or_default(),
+                        ),
+                    })
+                }
+                Ok(None) => None,
+            },
+            TransportEvent::NewListenAddress { address } => {
+                tracing::debug!(target: LOG_TARGET, ?address, "new listen address");
+
+                self.register_listen_address(address.clone());
+                Some(TransportEvent::NewListenAddress { address })
+            }
+            TransportEvent::ExpiredListenAddress { address } => {
+                tracing::debug!(target: LOG_TARGET, ?address, "listen address expired");
+
+                self.unregister_listen_address(&address);
+                Some(TransportEvent::ExpiredListenAddress { address })
+            }
+            TransportEvent::TransportFailed { transport, error } => {
+                tracing::warn!(target: LOG_TARGET, ?transport, ?error, "transport failed");
+
+                Some(TransportEvent::TransportFailed { transport, error })
+            }
+            event => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?transport,
+                    ?event,
+                    "ignoring unsupported transport event",
+                );
+                None
+            }
+        }
+    }
+
+    /// Poll next event from [`crate::transport::manager::TransportManager`].
+    pub(crate) fn poll_next_event(&mut self, cx: &mut Context<'_>) -> Poll<Option<TransportEvent>> {
+        loop {
+            if let Some(event) = self.pending_events.pop_front() {
+                return Poll::Ready(Some(event));
+            }
+
+            while let Poll::Ready(Some(())) = self.pending_notifications.poll_next_unpin(cx) {}
+
+            // each source is polled at most once per iteration so that a busy source doesn't
+            // starve the others
+            let mut progress = false;
+
+            match self.event_rx.poll_recv(cx) {
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Ready(Some(event)) => {
+                    progress = true;
+                    let event = self.on_manager_event(event);
+                    self.pending_events.extend(event);
+                }
+                Poll::Pending => {}
+            }
+
+            if let Poll::Ready(Some(peer)) = self.pending_reconnects.poll_next_unpin(cx) {
+                progress = true;
+                self.on_reconnect(peer);
+            }
+
+            if self.bootstrap.is_dialing() {
+                if let Poll::Ready(Some((address, result))) = self.bootstrap.poll_next_result(cx) {
+                    tracing::trace!(target: LOG_TARGET, ?address, ?result, "bootnode dialed");
+
+                    progress = true;
+                    if let Some(outcome) = self.bootstrap.on_dial_result(address, result) {
+                        self.pending_events.push_back(TransportEvent::BootstrapCompleted {
+                            connected: outcome.connected,
+                            failed: outcome.failed,
+                        });
+                    }
+                    self.dial_bootnodes();
+                }
+            }
+
+            if let Poll::Ready(Some((source, stats))) = self.connection_rate_rx.poll_recv(cx) {
+                progress = true;
+                self.pending_events
+                    .push_back(TransportEvent::ConnectionRateExceeded { source, stats });
+            }
+
+            if let Poll::Ready(Some((transport, peer, endpoint, approval))) =
+                self.pending_approvals.poll_next_unpin(cx)
+            {
+                progress = true;
+                let event = self.on_connection_approval(transport, peer, endpoint, approval);
+                self.pending_events.extend(event);
+            }
+
+            match self.cmd_rx.poll_recv(cx) {
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Ready(Some(command)) => {
+                    progress = true;
+                    self.on_command(command);
+                }
+                Poll::Pending => {}
+            }
+
+            match self.transports.poll_next_unpin(cx) {
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Ready(Some((transport, event))) => {
+                    progress = true;
+                    let event = self.on_transport_event(transport, event);
+                    self.pending_events.extend(event);
+                }
+                Poll::Pending => {}
+            }
+
+            let pending_connections = &self.pending_connections;
+            self.dial_scheduler
+                .release(|connection_id| pending_connections.contains_key(connection_id));
+
+            if let Poll::Ready((connection_id, dial)) = self.dial_scheduler.poll_next_dial(cx) {
+                progress = true;
+                self.start_queued_dial(connection_id, dial);
+            }
+
+            if !progress {
+                return Poll::Pending;
             }
         }
     }
@@ -1619,7 +3370,8 @@ impl TransportManager {
 mod tests {
     use super::*;
     use crate::{
-        crypto::ed25519::Keypair, executor::DefaultExecutor, transport::dummy::DummyTransport,
+        crypto::ed25519::Keypair, executor::DefaultExecutor, protocol::ProtocolCommand,
+        reputation::Behavior, transport::dummy::DummyTransport,
     };
     use std::{
         net::{Ipv4Addr, Ipv6Addr},
@@ -1714,32 +3466,71 @@ mod tests {
         let sink = BandwidthSink::new();
         let (mut manager, _handle) = TransportManager::new(keypair, HashSet::new(), sink, 8usize);
 
-        assert!(manager.dial(local_peer_id).await.is_err());
+        assert!(manager.dial(local_peer_id).is_err());
+    }
+
+    #[tokio::test]
+    async fn try_to_dial_over_disabled_transport() {
+        let (mut manager, _handle) = TransportManager::new(
+            Keypair::generate(),
+            HashSet::new(),
+            BandwidthSink::new(),
+            8usize,
+        );
+        let _handle =
+            manager.transport_handle(SupportedTransport::Tcp, Arc::new(DefaultExecutor {}));
+        manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
+
+        let address = Multiaddr::empty()
+            .with(Protocol::Ip4(Ipv4Addr::new(127, 0, 0, 1)))
+            .with(Protocol::Udp(8888))
+            .with(Protocol::QuicV1)
+            .with(Protocol::P2p(
+                Multihash::from_bytes(&PeerId::random().to_bytes()).unwrap(),
+            ));
+
+        assert!(std::matches!(
+            manager.dial_address(address),
+            Err(Error::TransportNotSupported(_))
+        ));
     }
 
     #[tokio::test]
-    async fn try_to_dial_over_disabled_transport() {
+    async fn failed_transport_is_removed() {
         let (mut manager, _handle) = TransportManager::new(
             Keypair::generate(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
         );
-        let _handle = manager.transport_handle(Arc::new(DefaultExecutor {}));
-        manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
+        let transport = Box::new({
+            let mut transport = DummyTransport::new();
+            transport.close();
+            transport
+        });
+        manager.register_transport(SupportedTransport::Tcp, transport);
+        manager.register_transport(SupportedTransport::Quic, Box::new(DummyTransport::new()));
+
+        match manager.next().await.unwrap() {
+            TransportEvent::TransportFailed {
+                transport: SupportedTransport::Tcp,
+                error: Error::EssentialTaskClosed,
+            } => {}
+            event => panic!("invalid event: {event:?}"),
+        }
 
         let address = Multiaddr::empty()
             .with(Protocol::Ip4(Ipv4Addr::new(127, 0, 0, 1)))
-            .with(Protocol::Udp(8888))
-            .with(Protocol::QuicV1)
+            .with(Protocol::Tcp(8888))
             .with(Protocol::P2p(
                 Multihash::from_bytes(&PeerId::random().to_bytes()).unwrap(),
             ));
 
         assert!(std::matches!(
-            manager.dial_address(address).await,
+            manager.dial_address(address),
             Err(Error::TransportNotSupported(_))
         ));
+        assert!(manager.transports.get_mut(&SupportedTransport::Quic).is_some());
     }
 
     #[tokio::test]
@@ -1772,7 +3563,7 @@ mod tests {
         });
         manager.register_transport(SupportedTransport::Tcp, transport);
 
-        assert!(manager.dial_address(dial_address.clone()).await.is_ok());
+        assert!(manager.dial_address(dial_address.clone()).is_ok());
         assert!(!manager.pending_connections.is_empty());
 
         {
@@ -1803,6 +3594,161 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn dial_result_reported_on_success() {
+        let (mut manager, _handle) = TransportManager::new(
+            Keypair::generate(),
+            HashSet::new(),
+            BandwidthSink::new(),
+            8usize,
+        );
+        let peer = PeerId::random();
+        let dial_address = Multiaddr::empty()
+            .with(Protocol::Ip4(Ipv4Addr::new(127, 0, 0, 1)))
+            .with(Protocol::Tcp(8888))
+            .with(Protocol::P2p(
+                Multihash::from_bytes(&peer.to_bytes()).unwrap(),
+            ));
+
+        let transport = Box::new({
+            let mut transport = DummyTransport::new();
+            transport.inject_event(TransportEvent::ConnectionEstablished {
+                peer,
+                endpoint: Endpoint::dialer(dial_address.clone(), ConnectionId::from(0usize)),
+            });
+            transport
+        });
+        manager.register_transport(SupportedTransport::Tcp, transport);
+
+        let result = manager.dial_address_with_result(dial_address.clone());
+
+        match manager.next().await.unwrap() {
+            TransportEvent::ConnectionEstablished {
+                peer: event_peer, ..
+            } => assert_eq!(peer, event_peer),
+            event => panic!("invalid event: {event:?}"),
+        }
+        assert_eq!(result.await.unwrap().unwrap(), peer);
+    }
+
+    #[tokio::test]
+    async fn dial_result_reported_on_failure() {
+        let (mut manager, _handle) = TransportManager::new(
+            Keypair::generate(),
+            HashSet::new(),
+            BandwidthSink::new(),
+            8usize,
+        );
+        let dial_address = Multiaddr::empty()
+            .with(Protocol::Ip4(Ipv4Addr::new(127, 0, 0, 1)))
+            .with(Protocol::Tcp(8888))
+            .with(Protocol::P2p(
+                Multihash::from_bytes(&PeerId::random().to_bytes()).unwrap(),
+            ));
+
+        let transport = Box::new({
+            let mut transport = DummyTransport::new();
+            transport.inject_event(TransportEvent::DialFailure {
+                connection_id: ConnectionId::from(0usize),
+                address: dial_address.clone(),
+                error: Error::Unknown,
+            });
+            transport
+        });
+        manager.register_transport(SupportedTransport::Tcp, transport);
+
+        let result = manager.dial_address_with_result(dial_address);
+
+        // the dial failure is reported only to the waiter
+        tokio::select! {
+            event = manager.next() => panic!("unexpected event: {event:?}"),
+            result = result => assert!(std::matches!(result, Ok(Err(Error::Unknown)))),
+        }
+    }
+
+    #[tokio::test]
+    async fn local_only_rejects_public_address() {
+        let (mut manager, _handle) = TransportManager::new(
+            Keypair::generate(),
+            HashSet::new(),
+            BandwidthSink::new(),
+            8usize,
+        );
+        manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
+        manager.set_local_only();
+
+        let peer = PeerId::random();
+        let address = Multiaddr::empty()
+            .with(Protocol::Ip4(Ipv4Addr::new(1, 1, 1, 1)))
+            .with(Protocol::Tcp(8888))
+            .with(Protocol::P2p(
+                Multihash::from_bytes(&peer.to_bytes()).unwrap(),
+            ));
+
+        assert!(std::matches!(
+            manager.dial_address(address.clone()),
+            Err(Error::NonLocalAddress(_))
+        ));
+
+        // public addresses of known peers are not dialed either
+        manager.add_known_address(peer, std::iter::once(address));
+        assert!(std::matches!(
+            manager.dial(peer),
+            Err(Error::NoAddressAvailable(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn ip_limit_evicts_less_reputable_peer() {
+        let (mut manager, handle) = TransportManager::new(
+            Keypair::generate(),
+            HashSet::new(),
+            BandwidthSink::new(),
+            8usize,
+        );
+        manager.set_ip_limits(IpLimitConfig {
+            max_connections_per_ip: Some(1),
+            ..Default::default()
+        });
+
+        let address = Multiaddr::empty()
+            .with(Protocol::Ip4(Ipv4Addr::new(10, 0, 0, 1)))
+            .with(Protocol::Tcp(8888));
+        let (open, new) = (PeerId::random(), PeerId::random());
+        let (tx, mut rx) = channel(8);
+
+        manager.open_connections.entry(open).or_default().insert(
+            ConnectionId::from(0usize),
+            OpenConnection {
+                endpoint: Endpoint::listener(address.clone(), ConnectionId::from(0usize)),
+                transport: SupportedTransport::Tcp,
+                established: Instant::now(),
+                protocols: Default::default(),
+            },
+        );
+        manager.connection_handles.entry(open).or_default().insert(
+            ConnectionId::from(0usize),
+            ConnectionHandle::new(ConnectionId::from(0usize), tx),
+        );
+        assert!(!manager.is_within_ip_limits(&address));
+
+        // equally reputable peers don't evict each other
+        assert!(!manager.evict_less_reputable(new, &address));
+
+        handle.reputation().report(open, Behavior::InvalidMessage);
+
+        // reserved peers are never evicted
+        manager.reserved_peers.write().insert(open);
+        assert!(!manager.evict_less_reputable(new, &address));
+        manager.reserved_peers.write().remove(&open);
+
+        assert!(manager.evict_less_reputable(new, &address));
+        assert!(std::matches!(
+            rx.try_recv(),
+            Ok(ProtocolCommand::ForceClose)
+        ));
+    }
+
     #[tokio::test]
     async fn try_to_dial_same_peer_twice() {
         let _ = tracing_subscriber::fmt()
@@ -1815,7 +3761,8 @@ mod tests {
             BandwidthSink::new(),
             8usize,
         );
-        let _handle = manager.transport_handle(Arc::new(DefaultExecutor {}));
+        let _handle =
+            manager.transport_handle(SupportedTransport::Tcp, Arc::new(DefaultExecutor {}));
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
 
         let peer = PeerId::random();
@@ -1826,10 +3773,68 @@ mod tests {
                 Multihash::from_bytes(&peer.to_bytes()).unwrap(),
             ));
 
-        assert!(manager.dial_address(dial_address.clone()).await.is_ok());
+        assert!(manager.dial_address(dial_address.clone()).is_ok());
+        assert_eq!(manager.pending_connections.len(), 1);
+
+        assert!(manager.dial_address(dial_address.clone()).is_ok());
         assert_eq!(manager.pending_connections.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn dial_queued_until_concurrent_dial_concludes() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        let (mut manager, _handle) = TransportManager::new(
+            Keypair::generate(),
+            HashSet::new(),
+            BandwidthSink::new(),
+            8usize,
+        );
+        manager.set_dial_scheduler_config(DialSchedulerConfig {
+            max_concurrent_dials: 1,
+            ..Default::default()
+        });
+        let _handle =
+            manager.transport_handle(SupportedTransport::Tcp, Arc::new(DefaultExecutor {}));
+        manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
+
+        let peer1 = PeerId::random();
+        let peer2 = PeerId::random();
+        let address1 = Multiaddr::empty()
+            .with(Protocol::Ip4(Ipv4Addr::new(127, 0, 0, 1)))
+            .with(Protocol::Tcp(8888))
+            .with(Protocol::P2p(
+                Multihash::from_bytes(&peer1.to_bytes()).unwrap(),
+            ));
+        let address2 = Multiaddr::empty()
+            .with(Protocol::Ip4(Ipv4Addr::new(127, 0, 0, 2)))
+            .with(Protocol::Tcp(8888))
+            .with(Protocol::P2p(
+                Multihash::from_bytes(&peer2.to_bytes()).unwrap(),
+            ));
+
+        assert!(manager.dial_address(address1).is_ok());
+        assert!(manager.dial_address(address2).is_ok());
+        assert_eq!(manager.pending_connections.len(), 2);
+        assert_eq!(manager.dial_scheduler.num_queued(), 1);
+
+        let connection_id = manager
+            .pending_connections
+            .iter()
+            .find_map(|(connection_id, peer)| (peer == &peer1).then_some(*connection_id))
+            .unwrap();
+        manager.on_dial_failure(connection_id).unwrap();
+
+        // the queued dial is started once the first dial has concluded
+        futures::future::poll_fn(|cx| {
+            assert!(manager.poll_next_event(cx).is_pending());
+            Poll::Ready(())
+        })
+        .await;
 
-        assert!(manager.dial_address(dial_address.clone()).await.is_ok());
+        assert_eq!(manager.dial_scheduler.num_queued(), 0);
         assert_eq!(manager.pending_connections.len(), 1);
     }
 
@@ -1845,7 +3850,8 @@ mod tests {
             BandwidthSink::new(),
             8usize,
         );
-        let _handle = manager.transport_handle(Arc::new(DefaultExecutor {}));
+        let _handle =
+            manager.transport_handle(SupportedTransport::Tcp, Arc::new(DefaultExecutor {}));
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
 
         let peer = PeerId::random();
@@ -1859,7 +3865,6 @@ mod tests {
                         Multihash::from_bytes(&peer.to_bytes()).unwrap(),
                     ))
             )
-            .await
             .is_ok());
         assert_eq!(manager.pending_connections.len(), 1);
 
@@ -1872,7 +3877,6 @@ mod tests {
                         Multihash::from_bytes(&peer.to_bytes()).unwrap(),
                     ))
             )
-            .await
             .is_ok());
         assert_eq!(manager.pending_connections.len(), 1);
     }
@@ -1889,10 +3893,11 @@ mod tests {
             BandwidthSink::new(),
             8usize,
         );
-        let _handle = manager.transport_handle(Arc::new(DefaultExecutor {}));
+        let _handle =
+            manager.transport_handle(SupportedTransport::Tcp, Arc::new(DefaultExecutor {}));
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
 
-        assert!(manager.dial(PeerId::random()).await.is_err());
+        assert!(manager.dial(PeerId::random()).is_err());
     }
 
     #[tokio::test]
@@ -1907,7 +3912,8 @@ mod tests {
             BandwidthSink::new(),
             8usize,
         );
-        let _handle = manager.transport_handle(Arc::new(DefaultExecutor {}));
+        let _handle =
+            manager.transport_handle(SupportedTransport::Tcp, Arc::new(DefaultExecutor {}));
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
 
         let peer = PeerId::random();
@@ -1920,7 +3926,146 @@ mod tests {
             },
         );
 
-        assert!(manager.dial(peer).await.is_err());
+        assert!(manager.dial(peer).is_err());
+    }
+
+    #[tokio::test]
+    async fn refused_dial_reported_to_protocol() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        let (mut manager, _handle) = TransportManager::new(
+            Keypair::generate(),
+            HashSet::new(),
+            BandwidthSink::new(),
+            8usize,
+        );
+        let mut service = manager.register_protocol(
+            ProtocolName::from("/notif/1"),
+            Vec::new(),
+            ProtocolCodec::UnsignedVarint(None),
+        );
+        let _handle =
+            manager.transport_handle(SupportedTransport::Tcp, Arc::new(DefaultExecutor {}));
+        manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
+
+        let peer = PeerId::random();
+        manager.peers.write().insert(
+            peer,
+            PeerContext {
+                state: PeerState::Disconnected { dial_record: None },
+                addresses: AddressStore::from_iter([AddressRecord::new(
+                    &peer,
+                    "/ip4/127.0.0.1/tcp/1111".parse().unwrap(),
+                    0,
+                    None,
+                )]),
+                secondary_connection: None,
+            },
+        );
+        manager.ban_peer(peer, Duration::from_secs(60));
+
+        // the dial is accepted by the handle but refused by the transport manager
+        service.dial(&peer).unwrap();
+        tokio::spawn(async move { while manager.next().await.is_some() {} });
+
+        match tokio::time::timeout(Duration::from_secs(5), service.next()).await {
+            Ok(Some(crate::protocol::TransportEvent::DialFailure { peer: dialed, .. })) =>
+                assert_eq!(dialed, peer),
+            event => panic!("invalid event: {event:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn dial_skips_backed_off_addresses() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        let (mut manager, _handle) = TransportManager::new(
+            Keypair::generate(),
+            HashSet::new(),
+            BandwidthSink::new(),
+            1usize,
+        );
+        let _handle =
+            manager.transport_handle(SupportedTransport::Tcp, Arc::new(DefaultExecutor {}));
+        manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
+
+        let peer = PeerId::random();
+        let failing = AddressRecord::new(
+            &peer,
+            "/ip4/127.0.0.1/tcp/1111".parse().unwrap(),
+            1000,
+            None,
+        );
+        let scored =
+            AddressRecord::new(&peer, "/ip4/127.0.0.1/tcp/2222".parse().unwrap(), 500, None);
+        let succeeded =
+            AddressRecord::new(&peer, "/ip4/127.0.0.1/tcp/3333".parse().unwrap(), 0, None);
+
+        let peer_store = manager.transport_manager_handle.peer_store().clone();
+        peer_store.on_address_dial_failure(peer, failing.address().clone());
+        peer_store.on_address_dial_failure(peer, failing.address().clone());
+        peer_store.on_address_dial_success(peer, succeeded.address().clone());
+
+        manager.peers.write().insert(
+            peer,
+            PeerContext {
+                state: PeerState::Disconnected { dial_record: None },
+                addresses: AddressStore::from_iter([
+                    failing.clone(),
+                    scored.clone(),
+                    succeeded.clone(),
+                ]),
+                secondary_connection: None,
+            },
+        );
+
+        // address in backoff is skipped and the recently successful address is preferred
+        // over the address with a higher score
+        manager.dial(peer).unwrap();
+
+        match &manager.peers.read().get(&peer).unwrap() {
+            PeerContext {
+                state: PeerState::Opening { records, .. },
+                addresses,
+                ..
+            } => {
+                assert_eq!(records.len(), 1);
+                assert!(records.contains_key(succeeded.address()));
+                assert!(addresses.contains(failing.address()));
+                assert!(addresses.contains(scored.address()));
+            }
+            state => panic!("invalid peer state: {state:?}"),
+        }
+
+        // if all addresses are in backoff, the peer cannot be dialed
+        let peer = PeerId::random();
+        let failing = AddressRecord::new(
+            &peer,
+            "/ip4/127.0.0.1/tcp/1111".parse().unwrap(),
+            1000,
+            None,
+        );
+        peer_store.on_address_dial_failure(peer, failing.address().clone());
+        peer_store.on_address_dial_failure(peer, failing.address().clone());
+
+        manager.peers.write().insert(
+            peer,
+            PeerContext {
+                state: PeerState::Disconnected { dial_record: None },
+                addresses: AddressStore::from_iter([failing.clone()]),
+                secondary_connection: None,
+            },
+        );
+
+        assert!(std::matches!(
+            manager.dial(peer),
+            Err(Error::NoAddressAvailable(_))
+        ));
+        assert!(manager.peers.read().get(&peer).unwrap().addresses.contains(failing.address()));
     }
 
     #[tokio::test]
@@ -1993,7 +4138,8 @@ mod tests {
             BandwidthSink::new(),
             8usize,
         );
-        let _handle = manager.transport_handle(Arc::new(DefaultExecutor {}));
+        let _handle =
+            manager.transport_handle(SupportedTransport::Tcp, Arc::new(DefaultExecutor {}));
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
 
         let peer = PeerId::random();
@@ -2009,7 +4155,7 @@ mod tests {
             .with(Protocol::P2p(
                 Multihash::from_bytes(&peer.to_bytes()).unwrap(),
             ));
-        assert!(manager.dial_address(dial_address.clone()).await.is_ok());
+        assert!(manager.dial_address(dial_address.clone()).is_ok());
         assert_eq!(manager.pending_connections.len(), 1);
 
         match &manager.peers.read().get(&peer).unwrap().state {
@@ -2059,7 +4205,8 @@ mod tests {
             BandwidthSink::new(),
             8usize,
         );
-        let _handle = manager.transport_handle(Arc::new(DefaultExecutor {}));
+        let _handle =
+            manager.transport_handle(SupportedTransport::Tcp, Arc::new(DefaultExecutor {}));
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
 
         let peer = PeerId::random();
@@ -2075,7 +4222,7 @@ mod tests {
             .with(Protocol::P2p(
                 Multihash::from_bytes(&peer.to_bytes()).unwrap(),
             ));
-        assert!(manager.dial_address(dial_address.clone()).await.is_ok());
+        assert!(manager.dial_address(dial_address.clone()).is_ok());
         assert_eq!(manager.pending_connections.len(), 1);
 
         match &manager.peers.read().get(&peer).unwrap().state {
@@ -2094,7 +4241,13 @@ mod tests {
             .unwrap();
 
         // connection to remote was closed while the dial was still in progress
-        manager.on_connection_closed(peer, ConnectionId::from(1usize)).unwrap();
+        manager
+            .on_connection_closed(
+                peer,
+                ConnectionId::from(1usize),
+                DisconnectReason::RemoteClosed,
+            )
+            .unwrap();
 
         // verify that the peer state is `Disconnected`
         {
@@ -2145,7 +4298,8 @@ mod tests {
             BandwidthSink::new(),
             8usize,
         );
-        let _handle = manager.transport_handle(Arc::new(DefaultExecutor {}));
+        let _handle =
+            manager.transport_handle(SupportedTransport::Tcp, Arc::new(DefaultExecutor {}));
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
 
         let peer = PeerId::random();
@@ -2161,7 +4315,7 @@ mod tests {
             .with(Protocol::P2p(
                 Multihash::from_bytes(&peer.to_bytes()).unwrap(),
             ));
-        assert!(manager.dial_address(dial_address.clone()).await.is_ok());
+        assert!(manager.dial_address(dial_address.clone()).is_ok());
         assert_eq!(manager.pending_connections.len(), 1);
 
         match &manager.peers.read().get(&peer).unwrap().state {
@@ -2180,7 +4334,13 @@ mod tests {
             .unwrap();
 
         // connection to remote was closed while the dial was still in progress
-        manager.on_connection_closed(peer, ConnectionId::from(1usize)).unwrap();
+        manager
+            .on_connection_closed(
+                peer,
+                ConnectionId::from(1usize),
+                DisconnectReason::RemoteClosed,
+            )
+            .unwrap();
 
         // verify that the peer state is `Disconnected`
         {
@@ -2404,7 +4564,13 @@ mod tests {
         drop(peers);
 
         // close the secondary connection and verify that the peer remains connected
-        let emit_event = manager.on_connection_closed(peer, ConnectionId::from(1usize)).unwrap();
+        let emit_event = manager
+            .on_connection_closed(
+                peer,
+                ConnectionId::from(1usize),
+                DisconnectReason::RemoteClosed,
+            )
+            .unwrap();
         assert!(emit_event.is_none());
 
         let peers = manager.peers.read();
@@ -2507,7 +4673,13 @@ mod tests {
 
         // close the primary connection and verify that the peer remains connected
         // while the primary connection address is stored in peer addresses
-        let emit_event = manager.on_connection_closed(peer, ConnectionId::from(0usize)).unwrap();
+        let emit_event = manager
+            .on_connection_closed(
+                peer,
+                ConnectionId::from(0usize),
+                DisconnectReason::RemoteClosed,
+            )
+            .unwrap();
         assert!(emit_event.is_none());
 
         let peers = manager.peers.read();
@@ -2635,7 +4807,13 @@ mod tests {
         drop(peers);
 
         // close the tertiary connection that was ignored
-        let emit_event = manager.on_connection_closed(peer, ConnectionId::from(2usize)).unwrap();
+        let emit_event = manager
+            .on_connection_closed(
+                peer,
+                ConnectionId::from(2usize),
+                DisconnectReason::RemoteClosed,
+            )
+            .unwrap();
         assert!(emit_event.is_none());
 
         // verify that the state remains unchanged
@@ -2707,7 +4885,13 @@ mod tests {
             BandwidthSink::new(),
             8usize,
         );
-        manager.on_connection_closed(PeerId::random(), ConnectionId::random()).unwrap();
+        manager
+            .on_connection_closed(
+                PeerId::random(),
+                ConnectionId::random(),
+                DisconnectReason::RemoteClosed,
+            )
+            .unwrap();
     }
 
     #[tokio::test]
@@ -2798,7 +4982,7 @@ mod tests {
         );
 
         manager
-            .on_open_failure(SupportedTransport::Tcp, ConnectionId::random())
+            .on_open_failure(SupportedTransport::Tcp, ConnectionId::random(), Vec::new())
             .unwrap();
     }
 
@@ -2820,7 +5004,9 @@ mod tests {
         let peer = PeerId::random();
 
         manager.pending_connections.insert(connection_id, peer);
-        manager.on_open_failure(SupportedTransport::Tcp, connection_id).unwrap();
+        manager
+            .on_open_failure(SupportedTransport::Tcp, connection_id, Vec::new())
+            .unwrap();
     }
 
     #[tokio::test]
@@ -2880,7 +5066,7 @@ mod tests {
             peer
         };
 
-        match manager.dial(peer).await {
+        match manager.dial(peer) {
             Err(Error::AlreadyConnected) => {}
             _ => panic!("invalid return value"),
         }
@@ -2926,7 +5112,7 @@ mod tests {
             peer
         };
 
-        manager.dial(peer).await.unwrap();
+        manager.dial(peer).unwrap();
     }
 
     #[tokio::test]
@@ -2965,7 +5151,7 @@ mod tests {
             peer
         };
 
-        manager.dial(peer).await.unwrap();
+        manager.dial(peer).unwrap();
     }
 
     #[tokio::test]
@@ -2984,7 +5170,7 @@ mod tests {
         // transport doesn't start with ip/dns
         {
             let address = Multiaddr::empty().with(Protocol::P2p(Multihash::from(PeerId::random())));
-            match manager.dial_address(address.clone()).await {
+            match manager.dial_address(address.clone()) {
                 Err(Error::TransportNotSupported(dial_address)) => {
                     assert_eq!(dial_address, address);
                 }
@@ -2999,7 +5185,7 @@ mod tests {
                 .with(Protocol::Udp(8888))
                 .with(Protocol::Utp)
                 .with(Protocol::P2p(Multihash::from(PeerId::random())));
-            match manager.dial_address(address.clone()).await {
+            match manager.dial_address(address.clone()) {
                 Err(Error::TransportNotSupported(dial_address)) => {
                     assert_eq!(dial_address, address);
                 }
@@ -3013,7 +5199,7 @@ mod tests {
                 .with(Protocol::Ip4(std::net::Ipv4Addr::new(127, 0, 0, 1)))
                 .with(Protocol::Sctp(8888))
                 .with(Protocol::P2p(Multihash::from(PeerId::random())));
-            match manager.dial_address(address.clone()).await {
+            match manager.dial_address(address.clone()) {
                 Err(Error::TransportNotSupported(dial_address)) => {
                     assert_eq!(dial_address, address);
                 }
@@ -3028,7 +5214,7 @@ mod tests {
                 .with(Protocol::Tcp(8888))
                 .with(Protocol::Utp)
                 .with(Protocol::P2p(Multihash::from(PeerId::random())));
-            match manager.dial_address(address.clone()).await {
+            match manager.dial_address(address.clone()) {
                 Err(Error::TransportNotSupported(dial_address)) => {
                     assert_eq!(dial_address, address);
                 }
@@ -3047,7 +5233,7 @@ mod tests {
         );
 
         async fn call_manager(manager: &mut TransportManager, address: Multiaddr) {
-            match manager.dial_address(address).await {
+            match manager.dial_address(address) {
                 Err(Error::AddressError(AddressError::PeerIdMissing)) => {}
                 _ => panic!("invalid return value"),
             }
@@ -3125,7 +5311,7 @@ mod tests {
             .into_iter(),
         );
 
-        assert!(manager.dial(peer).await.is_ok());
+        assert!(manager.dial(peer).is_ok());
         assert!(!manager.pending_connections.is_empty());
 
         {
@@ -3216,7 +5402,7 @@ mod tests {
             .into_iter(),
         );
 
-        assert!(manager.dial(peer).await.is_ok());
+        assert!(manager.dial(peer).is_ok());
         assert!(!manager.pending_connections.is_empty());
 
         {
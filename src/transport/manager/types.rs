@@ -19,13 +19,26 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
-    transport::manager::address::{AddressRecord, AddressStore},
-    types::ConnectionId,
+    transport::{
+        manager::{
+            address::{AddressRecord, AddressStore},
+            ProtocolContext,
+        },
+        Endpoint,
+    },
+    types::{protocol::ProtocolName, ConnectionId},
 };
 
+use futures::task::AtomicWaker;
 use multiaddr::Multiaddr;
+use parking_lot::{Mutex, RwLock};
+use tokio::sync::oneshot;
 
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Weak},
+    time::{Duration, Instant},
+};
 
 /// Supported protocols.
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
@@ -103,3 +116,225 @@ pub struct PeerContext {
     /// Known addresses of peer.
     pub addresses: AddressStore,
 }
+
+/// Information about an open connection.
+#[derive(Debug, Clone)]
+pub struct ConnectionInfo {
+    /// Endpoint of the connection.
+    ///
+    /// Holds the remote address of the connection and tells whether the connection is inbound
+    /// ([`Endpoint::Listener`]) or outbound ([`Endpoint::Dialer`]).
+    pub endpoint: Endpoint,
+
+    /// Transport of the connection.
+    pub transport: SupportedTransport,
+
+    /// Protocols for which a substream has been negotiated over the connection.
+    pub protocols: HashSet<ProtocolName>,
+
+    /// How long has the connection been open.
+    pub uptime: Duration,
+}
+
+/// Summary of an ordered shutdown.
+///
+/// See [`Litep2p::shutdown()`](crate::Litep2p::shutdown) for more details.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShutdownSummary {
+    /// Number of protocols whose event loops exited.
+    pub protocols_closed: usize,
+
+    /// Protocols whose event loops didn't exit within the timeout.
+    pub protocols_timed_out: Vec<ProtocolName>,
+
+    /// Number of connections that were closed.
+    pub connections_closed: usize,
+
+    /// Number of connections that didn't close within the timeout.
+    pub connections_timed_out: usize,
+
+    /// Listen addresses that were unbound.
+    pub listen_addresses: Vec<Multiaddr>,
+}
+
+/// Open connection tracked by the transport manager.
+#[derive(Debug)]
+pub(crate) struct OpenConnection {
+    /// Endpoint of the connection.
+    pub(crate) endpoint: Endpoint,
+
+    /// Transport of the connection.
+    pub(crate) transport: SupportedTransport,
+
+    /// When was the connection accepted.
+    pub(crate) established: Instant,
+
+    /// Protocols negotiated over the connection, updated by the connection.
+    pub(crate) protocols: Arc<RwLock<HashSet<ProtocolName>>>,
+}
+
+impl OpenConnection {
+    /// Get [`ConnectionInfo`] of the connection.
+    pub(crate) fn info(&self) -> ConnectionInfo {
+        ConnectionInfo {
+            endpoint: self.endpoint.clone(),
+            transport: self.transport,
+            protocols: self.protocols.read().clone(),
+            uptime: self.established.elapsed(),
+        }
+    }
+}
+
+/// Outbound dial started once the dial scheduler permits it.
+#[derive(Debug)]
+pub(crate) enum ScheduledDial {
+    /// Dial a single address.
+    Dial {
+        /// Transport used to dial the address.
+        transport: SupportedTransport,
+
+        /// Dialed address.
+        address: Multiaddr,
+    },
+
+    /// Open connections to addresses in parallel, possibly over multiple transports.
+    Open {
+        /// Dialed addresses, grouped by transport.
+        addresses: Vec<(SupportedTransport, Vec<Multiaddr>)>,
+    },
+}
+
+impl ScheduledDial {
+    /// Get the dialed addresses.
+    pub(crate) fn addresses(&self) -> Vec<&Multiaddr> {
+        match self {
+            ScheduledDial::Dial { address, .. } => vec![address],
+            ScheduledDial::Open { addresses } =>
+                addresses.iter().flat_map(|(_, addresses)| addresses).collect(),
+        }
+    }
+}
+
+/// Decision made by the user about an inbound connection awaiting approval.
+#[derive(Debug)]
+pub(crate) enum ConnectionApproval {
+    /// Accept the connection.
+    Accept,
+
+    /// Reject the connection.
+    Reject {
+        /// Reason for the rejection.
+        reason: String,
+    },
+}
+
+/// Handle for accepting or rejecting an inbound connection.
+///
+/// Dropping the handle without calling [`ConnectionApprovalHandle::accept()`] or
+/// [`ConnectionApprovalHandle::reject()`] rejects the connection.
+#[derive(Debug)]
+pub struct ConnectionApprovalHandle {
+    /// Connection ID.
+    connection_id: ConnectionId,
+
+    /// TX channel for sending the decision to [`crate::transport::manager::TransportManager`].
+    tx: oneshot::Sender<ConnectionApproval>,
+}
+
+impl ConnectionApprovalHandle {
+    /// Create new [`ConnectionApprovalHandle`].
+    pub(crate) fn new(
+        connection_id: ConnectionId,
+    ) -> (Self, oneshot::Receiver<ConnectionApproval>) {
+        let (tx, rx) = oneshot::channel();
+
+        (Self { connection_id, tx }, rx)
+    }
+
+    /// Get ID of the connection awaiting approval.
+    pub fn connection_id(&self) -> ConnectionId {
+        self.connection_id
+    }
+
+    /// Accept the connection.
+    pub fn accept(self) {
+        let _ = self.tx.send(ConnectionApproval::Accept);
+    }
+
+    /// Reject the connection with `reason`.
+    pub fn reject(self, reason: impl Into<String>) {
+        let _ = self.tx.send(ConnectionApproval::Reject {
+            reason: reason.into(),
+        });
+    }
+}
+
+/// Installed protocols and the version of the protocol set.
+#[derive(Debug, Default)]
+struct InstalledProtocols {
+    /// Version of the protocol set, incremented each time the set changes.
+    version: usize,
+
+    /// Installed protocols.
+    protocols: HashMap<ProtocolName, ProtocolContext>,
+}
+
+/// Protocols installed to [`TransportManager`](super::TransportManager).
+///
+/// The registry is shared with transports and the connections they open so protocols installed
+/// while the node is running become available over connections which are already open.
+#[derive(Debug, Default, Clone)]
+pub struct ProtocolRegistry {
+    /// Installed protocols.
+    installed: Arc<RwLock<InstalledProtocols>>,
+
+    /// Wakers of the connections which are notified when the protocol set changes.
+    wakers: Arc<Mutex<Vec<Weak<AtomicWaker>>>>,
+}
+
+impl ProtocolRegistry {
+    /// Get the current version of the protocol set.
+    pub fn version(&self) -> usize {
+        self.installed.read().version
+    }
+
+    /// Get the current version of the protocol set and the installed protocols.
+    pub fn protocols(&self) -> (usize, HashMap<ProtocolName, ProtocolContext>) {
+        let installed = self.installed.read();
+
+        (installed.version, installed.protocols.clone())
+    }
+
+    /// Replace the installed protocols with `protocols` and notify connections about the change.
+    pub(crate) fn update(&self, protocols: HashMap<ProtocolName, ProtocolContext>) {
+        {
+            let mut installed = self.installed.write();
+            installed.version += 1;
+            installed.protocols = protocols;
+        }
+
+        self.wakers.lock().retain(|waker| match waker.upgrade() {
+            Some(waker) => {
+                waker.wake();
+                true
+            }
+            None => false,
+        });
+    }
+
+    /// Register `waker` of a connection to be woken up when the protocol set changes.
+    pub fn register_waker(&self, waker: &Arc<AtomicWaker>) {
+        let mut wakers = self.wakers.lock();
+
+        wakers.retain(|waker| waker.strong_count() > 0);
+        wakers.push(Arc::downgrade(waker));
+    }
+}
+
+impl From<HashMap<ProtocolName, ProtocolContext>> for ProtocolRegistry {
+    fn from(protocols: HashMap<ProtocolName, ProtocolContext>) -> Self {
+        let registry = Self::default();
+        registry.update(protocols);
+        registry
+    }
+}
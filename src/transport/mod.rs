@@ -20,20 +20,43 @@
 
 //! Transport protocol implementations provided by [`Litep2p`](`crate::Litep2p`).
 
-use crate::{transport::manager::TransportHandle, types::ConnectionId, Error, PeerId};
+use crate::{
+    config::Role,
+    transport::{
+        connection_rate::{Source, SourceStats},
+        manager::TransportHandle,
+        quic::PathStats,
+    },
+    types::ConnectionId,
+    Error, PeerId,
+};
 
-use futures::Stream;
+use futures::{AsyncRead, AsyncWrite, Stream};
 use multiaddr::Multiaddr;
 
 use std::{fmt::Debug, time::Duration};
 
+pub mod allow_list;
+pub mod bootstrap;
+pub mod connection_rate;
+pub mod dial_scheduler;
+pub mod dns;
+pub mod gater;
+pub mod ip_limit;
+pub mod limits;
+pub(crate) mod local;
 pub mod quic;
+pub mod reconnect;
 pub mod tcp;
 pub mod webrtc;
 pub mod websocket;
 
+pub use manager::{ConnectionApprovalHandle, ConnectionInfo, ShutdownSummary, SupportedTransport};
+
 pub(crate) mod dummy;
+pub(crate) mod interfaces;
 pub(crate) mod manager;
+pub(crate) mod muxer;
 
 /// Timeout for opening a connection.
 pub(crate) const CONNECTION_OPEN_TIMEOUT: Duration = Duration::from_secs(10);
@@ -44,6 +67,32 @@ pub(crate) const SUBSTREAM_OPEN_TIMEOUT: Duration = Duration::from_secs(5);
 /// Maximum number of parallel dial attempts.
 pub(crate) const MAX_PARALLEL_DIALS: usize = 8;
 
+/// Default size of the command and event channels of the transport manager and connections.
+pub(crate) const TRANSPORT_CHANNEL_SIZE: usize = 256;
+
+/// Default timeout for each stage of an ordered shutdown.
+pub(crate) const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often are network interfaces polled for address changes.
+pub(crate) const INTERFACE_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Connection which has been established and authenticated outside of `litep2p`.
+///
+/// See [`Litep2p::add_connection()`](crate::Litep2p::add_connection).
+pub trait RawConnection: AsyncRead + AsyncWrite + Unpin + Send + 'static {
+    /// Role of the local node in the connection.
+    ///
+    /// The two sides of the connection must have different roles.
+    fn role(&self) -> Role;
+
+    /// Address of the remote peer.
+    ///
+    /// By default the address is empty.
+    fn address(&self) -> Multiaddr {
+        Multiaddr::empty()
+    }
+}
+
 /// Connection endpoint.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Endpoint {
@@ -105,6 +154,66 @@ impl Endpoint {
     }
 }
 
+/// Reason why a connection was closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisconnectReason {
+    /// Connection was closed by the remote peer.
+    RemoteClosed,
+
+    /// Connection was closed by the local node, for example because the peer was disconnected,
+    /// banned or the lifetime of the connection expired.
+    LocalClosed,
+
+    /// None of the installed protocols kept the connection open.
+    Inactive,
+
+    /// Connection failed with an error.
+    Error,
+}
+
+impl From<&crate::yamux::ConnectionError> for DisconnectReason {
+    fn from(error: &crate::yamux::ConnectionError) -> Self {
+        use crate::yamux::{ConnectionError, FrameDecodeError};
+
+        let error = match error {
+            ConnectionError::Closed => return DisconnectReason::RemoteClosed,
+            ConnectionError::Io(error) | ConnectionError::Decode(FrameDecodeError::Io(error)) =>
+                error,
+            _ => return DisconnectReason::Error,
+        };
+
+        // remote closing the socket without closing the yamux session first
+        match error.kind() {
+            std::io::ErrorKind::UnexpectedEof
+            | std::io::ErrorKind::ConnectionReset
+            | std::io::ErrorKind::BrokenPipe => DisconnectReason::RemoteClosed,
+            _ => DisconnectReason::Error,
+        }
+    }
+}
+
+/// Reason why a negotiated connection was rejected by the local node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectReason {
+    /// Remote peer is banned.
+    PeerBanned,
+
+    /// Local node already has the maximum number of connections open to the peer.
+    ConnectionLimit,
+
+    /// Connection was rejected by the
+    /// [`ConnectionGater`](crate::transport::gater::ConnectionGater).
+    Gated,
+
+    /// Remote peer or its address is not on the
+    /// [`AllowList`](crate::transport::allow_list::AllowList).
+    NotAllowed,
+
+    /// Local node already has the maximum number of connections open from the IP address or
+    /// subnet of the remote peer.
+    IpLimit,
+}
+
 /// Transport event.
 #[derive(Debug)]
 pub(crate) enum TransportEvent {
@@ -117,6 +226,18 @@ pub(crate) enum TransportEvent {
         endpoint: Endpoint,
     },
 
+    /// Inbound connection negotiated and awaiting approval from the user.
+    IncomingConnection {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// Address of the connection.
+        address: Multiaddr,
+
+        /// Handle for accepting or rejecting the connection.
+        approval_handle: ConnectionApprovalHandle,
+    },
+
     /// Connection opened to remote but not yet negotiated.
     ConnectionOpened {
         /// Connection ID.
@@ -134,6 +255,21 @@ pub(crate) enum TransportEvent {
 
         /// Connection ID.
         connection_id: ConnectionId,
+
+        /// Reason why the connection was closed.
+        reason: DisconnectReason,
+    },
+
+    /// Negotiated connection was rejected.
+    ConnectionRejected {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// Endpoint.
+        endpoint: Endpoint,
+
+        /// Reason for the rejection.
+        reason: RejectReason,
     },
 
     /// Failed to dial remote peer.
@@ -152,6 +288,75 @@ pub(crate) enum TransportEvent {
     OpenFailure {
         /// Connection ID.
         connection_id: ConnectionId,
+
+        /// Addresses that were tried and the error each of them failed with.
+        errors: Vec<(Multiaddr, Error)>,
+    },
+
+    /// Peer was banned.
+    PeerBanned {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// Duration of the ban.
+        duration: Duration,
+    },
+
+    /// Path statistics of a QUIC connection.
+    QuicPathStats {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// Connection ID.
+        connection_id: ConnectionId,
+
+        /// Path statistics.
+        stats: PathStats,
+    },
+
+    /// Listener failed to accept an inbound connection.
+    ListenerError {
+        /// Error.
+        error: Error,
+    },
+
+    /// Bootstrap has completed.
+    BootstrapCompleted {
+        /// Bootnodes which were connected to.
+        connected: Vec<PeerId>,
+
+        /// Addresses of the bootnodes which couldn't be connected to.
+        failed: Vec<(Multiaddr, Error)>,
+    },
+
+    /// Transport failed and was removed from the installed transports.
+    TransportFailed {
+        /// Transport that failed.
+        transport: SupportedTransport,
+
+        /// Error.
+        error: Error,
+    },
+
+    /// Transport started listening on a new address.
+    NewListenAddress {
+        /// Listen address.
+        address: Multiaddr,
+    },
+
+    /// Transport is no longer listening on an address.
+    ExpiredListenAddress {
+        /// Listen address.
+        address: Multiaddr,
+    },
+
+    /// Source of inbound connections exceeded the connection rate thresholds.
+    ConnectionRateExceeded {
+        /// Source of the connections.
+        source: Source,
+
+        /// Statistics of the source.
+        stats: SourceStats,
     },
 }
 
@@ -190,4 +395,19 @@ pub(crate) trait Transport: Stream + Unpin + Send {
     ///
     /// This is a no-op for connections that have already succeeded/canceled.
     fn cancel(&mut self, connection_id: ConnectionId);
+
+    /// Inject a connection that was established and authenticated outside of `litep2p`.
+    ///
+    /// The connection is reported as negotiated and must be accepted or rejected like any other
+    /// negotiated connection.
+    ///
+    /// By default injected connections are not supported.
+    fn inject(
+        &mut self,
+        _connection_id: ConnectionId,
+        _peer: PeerId,
+        _connection: Box<dyn RawConnection>,
+    ) -> crate::Result<()> {
+        Err(Error::NotSupported(String::from("injected connections")))
+    }
 }
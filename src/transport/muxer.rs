@@ -0,0 +1,152 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Yamux connection driven by the event loop of the connection.
+//!
+//! Substreams are opened by polling the yamux connection directly from the event loop which also
+//! polls the inbound substreams and the commands of the protocols, instead of sending each open
+//! request to the yamux connection over a channel and waiting for the response over another one.
+
+use crate::{
+    protocol::Permit,
+    types::{protocol::ProtocolName, SubstreamId},
+    yamux::{Connection, ConnectionError, Stream},
+};
+
+use futures::{AsyncRead, AsyncWrite};
+
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Outbound substream waiting to be opened.
+#[derive(Debug)]
+pub(crate) struct OutboundSubstream {
+    /// Protocol name.
+    pub(crate) protocol: ProtocolName,
+
+    /// Fallback names of the protocol.
+    pub(crate) fallback_names: Vec<ProtocolName>,
+
+    /// Substream ID.
+    pub(crate) substream_id: SubstreamId,
+
+    /// Connection permit.
+    pub(crate) permit: Permit,
+}
+
+/// Event emitted by [`Muxer`].
+#[derive(Debug)]
+pub(crate) enum MuxerEvent {
+    /// Remote peer opened a substream.
+    Inbound(Stream),
+
+    /// Outbound substream was opened.
+    Outbound {
+        /// Opened substream.
+        stream: Stream,
+
+        /// Outbound substream that was opened.
+        substream: OutboundSubstream,
+    },
+
+    /// Failed to open outbound substream.
+    OutboundFailure {
+        /// Outbound substream that failed to open.
+        substream: OutboundSubstream,
+
+        /// Error.
+        error: ConnectionError,
+    },
+}
+
+/// Yamux connection.
+pub(crate) struct Muxer<S> {
+    /// Yamux connection.
+    connection: Connection<S>,
+
+    /// Outbound substreams waiting to be opened, in the order they were requested.
+    pending_outbound: VecDeque<OutboundSubstream>,
+
+    /// Pending events.
+    pending_events: VecDeque<MuxerEvent>,
+}
+
+impl<S> Muxer<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    /// Create new [`Muxer`].
+    pub(crate) fn new(connection: Connection<S>) -> Self {
+        Self {
+            connection,
+            pending_outbound: VecDeque::new(),
+            pending_events: VecDeque::new(),
+        }
+    }
+
+    /// Open outbound substream.
+    ///
+    /// The substream is returned in [`MuxerEvent::Outbound`] once it has been opened.
+    pub(crate) fn open_substream(&mut self, substream: OutboundSubstream) {
+        self.pending_outbound.push_back(substream);
+    }
+}
+
+impl<S> futures::Stream for Muxer<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    type Item = Result<MuxerEvent, ConnectionError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            // polling the connection also flushes the frames of opened outbound substreams
+            if let Poll::Ready(event) = this.connection.poll_next_inbound(cx) {
+                return Poll::Ready(event.map(|result| result.map(MuxerEvent::Inbound)));
+            }
+
+            if let Some(event) = this.pending_events.pop_front() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+
+            if this.pending_outbound.is_empty() {
+                return Poll::Pending;
+            }
+
+            match this.connection.poll_new_outbound(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(result) => {
+                    let substream =
+                        this.pending_outbound.pop_front().expect("pending substream to exist");
+
+                    this.pending_events.push_back(match result {
+                        Ok(stream) => MuxerEvent::Outbound { stream, substream },
+                        Err(error) => MuxerEvent::OutboundFailure { substream, error },
+                    });
+                }
+            }
+        }
+    }
+}
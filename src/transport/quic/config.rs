@@ -26,8 +26,11 @@ use multiaddr::Multiaddr;
 
 use std::time::Duration;
 
+/// Default maximum number of inbound connections that are negotiated concurrently.
+const MAX_PENDING_INBOUND_CONNECTIONS: usize = 256;
+
 /// QUIC transport configuration.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Config {
     /// Listen address for the transport.
     ///
@@ -45,6 +48,34 @@ pub struct Config {
     /// How long should litep2p wait for a substream to be opened before considering
     /// the substream rejected.
     pub substream_open_timeout: Duration,
+
+    /// Path statistics reporting interval.
+    ///
+    /// If set, each QUIC connection periodically reports its path statistics (RTT, congestion
+    /// window, packet loss) as
+    /// [`Litep2pEvent::QuicPathStats`](crate::Litep2pEvent::QuicPathStats).
+    ///
+    /// Statistics can also be queried on demand using
+    /// [`Litep2p::quic_path_stats()`](crate::Litep2p::quic_path_stats).
+    pub path_stats_interval: Option<Duration>,
+
+    /// UDP port the dialer binds to.
+    ///
+    /// By default each dial binds to a random port. If set, one client endpoint is bound to
+    /// this port for each IP version and it's used for all outbound connections, keeping the
+    /// local address of the dialer stable across dials.
+    ///
+    /// The port must not be the same as any of the listen ports.
+    pub dial_bind_port: Option<u16>,
+
+    /// Maximum number of inbound connections that are negotiated concurrently.
+    ///
+    /// Once the limit is reached, new inbound connections are refused until at least one of the
+    /// pending connections has been negotiated.
+    ///
+    /// By default at most `256` inbound connections are negotiated concurrently. `None` disables
+    /// the limit.
+    pub max_pending_inbound_connections: Option<usize>,
 }
 
 impl Default for Config {
@@ -53,6 +84,9 @@ impl Default for Config {
             listen_addresses: vec!["/ip4/127.0.0.1/udp/0/quic-v1".parse().expect("valid address")],
             connection_open_timeout: CONNECTION_OPEN_TIMEOUT,
             substream_open_timeout: SUBSTREAM_OPEN_TIMEOUT,
+            path_stats_interval: None,
+            dial_bind_port: None,
+            max_pending_inbound_connections: Some(MAX_PENDING_INBOUND_CONNECTIONS),
         }
     }
 }
@@ -29,8 +29,11 @@ use crate::{
     protocol::{Direction, Permit, ProtocolCommand, ProtocolSet},
     substream,
     transport::{
-        quic::substream::{NegotiatingSubstream, Substream},
-        Endpoint,
+        quic::{
+            substream::{NegotiatingSubstream, Substream},
+            PathStats,
+        },
+        DisconnectReason, Endpoint,
     },
     types::{protocol::ProtocolName, SubstreamId},
     BandwidthSink, PeerId,
@@ -38,6 +41,7 @@ use crate::{
 
 use futures::{future::BoxFuture, stream::FuturesUnordered, AsyncRead, AsyncWrite, StreamExt};
 use quinn::{Connection as QuinnConnection, RecvStream, SendStream};
+use tokio::time::Interval;
 
 /// Logging target for the file.
 const LOG_TARGET: &str = "litep2p::quic::connection";
@@ -110,6 +114,9 @@ pub struct QuicConnection {
     /// Pending substreams.
     pending_substreams:
         FuturesUnordered<BoxFuture<'static, Result<NegotiatedSubstream, ConnectionError>>>,
+
+    /// Interval for reporting path statistics, if enabled.
+    path_stats_interval: Option<Interval>,
 }
 
 impl QuicConnection {
@@ -121,6 +128,7 @@ impl QuicConnection {
         protocol_set: ProtocolSet,
         bandwidth_sink: BandwidthSink,
         substream_open_timeout: Duration,
+        path_stats_interval: Option<Duration>,
     ) -> Self {
         Self {
             peer,
@@ -130,6 +138,21 @@ impl QuicConnection {
             bandwidth_sink,
             substream_open_timeout,
             pending_substreams: FuturesUnordered::new(),
+            path_stats_interval: path_stats_interval.map(|period| {
+                tokio::time::interval_at(tokio::time::Instant::now() + period, period)
+            }),
+        }
+    }
+
+    /// Wait until path statistics should be reported next.
+    ///
+    /// Never resolves if path statistics reporting is disabled.
+    async fn next_path_stats_tick(interval: &mut Option<Interval>) {
+        match interval {
+            Some(interval) => {
+                interval.tick().await;
+            }
+            None => futures::future::pending().await,
         }
     }
 
@@ -243,7 +266,18 @@ impl QuicConnection {
 
                         let substream = self.protocol_set.next_substream_id();
                         let protocols = self.protocol_set.protocols();
-                        let permit = self.protocol_set.try_get_permit().ok_or(Error::ConnectionClosed)?;
+                        // protocols have released the connection
+                        let Some(permit) = self.protocol_set.try_get_permit() else {
+                            return self.protocol_set.report_connection_closed(self.peer, self.endpoint.connection_id(), DisconnectReason::Inactive).await;
+                        };
+                        if self.protocol_set.substream_limit_exceeded() {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                peer = ?self.peer,
+                                "substream limit reached, rejecting inbound substream",
+                            );
+                            continue;
+                        }
                         let stream = NegotiatingSubstream::new(send_stream, receive_stream);
                         let substream_open_timeout = self.substream_open_timeout;
 
@@ -269,7 +303,13 @@ impl QuicConnection {
                     }
                     Err(error) => {
                         tracing::debug!(target: LOG_TARGET, peer = ?self.peer, ?error, "failed to accept substream");
-                        return self.protocol_set.report_connection_closed(self.peer, self.endpoint.connection_id()).await;
+                        let reason = match error {
+                            quinn::ConnectionError::ApplicationClosed(_)
+                            | quinn::ConnectionError::ConnectionClosed(_) => DisconnectReason::RemoteClosed,
+                            _ => DisconnectReason::Error,
+                        };
+
+                        return self.protocol_set.report_connection_closed(self.peer, self.endpoint.connection_id(), reason).await;
                     }
                 },
                 substream = self.pending_substreams.select_next_some(), if !self.pending_substreams.is_empty() => {
@@ -301,15 +341,30 @@ impl QuicConnection {
                             let substream_id = substream.substream_id;
                             let direction = substream.direction;
                             let bandwidth_sink = self.bandwidth_sink.clone();
+                            let substream = Substream::new(
+                                substream.permit,
+                                substream.sender,
+                                substream.receiver,
+                                bandwidth_sink
+                            );
+
+                            let priority = self.protocol_set.stream_priority(&protocol);
+                            if priority != 0 {
+                                if let Err(error) = substream.set_priority(priority) {
+                                    tracing::debug!(
+                                        target: LOG_TARGET,
+                                        ?protocol,
+                                        ?substream_id,
+                                        ?error,
+                                        "failed to set substream priority",
+                                    );
+                                }
+                            }
+
                             let substream = substream::Substream::new_quic(
                                 self.peer,
                                 substream_id,
-                                Substream::new(
-                                    substream.permit,
-                                    substream.sender,
-                                    substream.receiver,
-                                    bandwidth_sink
-                                ),
+                                substream,
                                 self.protocol_set.protocol_codec(&protocol)
                             );
 
@@ -327,7 +382,7 @@ impl QuicConnection {
                             connection_id = ?self.endpoint.connection_id(),
                             "protocols have dropped connection"
                         );
-                        return self.protocol_set.report_connection_closed(self.peer, self.endpoint.connection_id()).await;
+                        return self.protocol_set.report_connection_closed(self.peer, self.endpoint.connection_id(), DisconnectReason::Inactive).await;
                     }
                     Some(ProtocolCommand::OpenSubstream { protocol, fallback_names, substream_id, permit }) => {
                         let connection = self.connection.clone();
@@ -375,8 +430,23 @@ impl QuicConnection {
                             "force closing connection",
                         );
 
-                        return self.protocol_set.report_connection_closed(self.peer, self.endpoint.connection_id()).await;
+                        return self.protocol_set.report_connection_closed(self.peer, self.endpoint.connection_id(), DisconnectReason::LocalClosed).await;
                     }
+                },
+                _ = Self::next_path_stats_tick(&mut self.path_stats_interval) => {
+                    let stats = PathStats::from(&self.connection);
+
+                    tracing::trace!(
+                        target: LOG_TARGET,
+                        peer = ?self.peer,
+                        connection_id = ?self.endpoint.connection_id(),
+                        ?stats,
+                        "report path statistics",
+                    );
+
+                    self.protocol_set
+                        .report_quic_path_stats(self.peer, self.endpoint.connection_id(), stats)
+                        .await?;
                 }
             }
         }
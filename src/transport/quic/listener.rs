@@ -52,36 +52,40 @@ pub struct QuicListener {
 
 impl QuicListener {
     /// Create new [`QuicListener`].
+    ///
+    /// The listener accepts connections using both QUIC v1 and the legacy draft-29 version.
+    /// Listen addresses are reported as `/quic` if they were configured as `/quic` and as
+    /// `/quic-v1` otherwise.
     pub fn new(
         keypair: &Keypair,
         addresses: Vec<Multiaddr>,
     ) -> crate::Result<(Self, Vec<Multiaddr>)> {
         let mut listeners: Vec<Endpoint> = Vec::new();
         let mut listen_addresses = Vec::new();
+        let mut listen_multi_addresses = Vec::new();
 
         for address in addresses.into_iter() {
             let (listen_address, _) = Self::get_socket_address(&address)?;
-            let crypto_config = Arc::new(make_server_config(keypair).expect("to succeed"));
+            let quic = match address.iter().any(|protocol| std::matches!(protocol, Protocol::Quic))
+            {
+                true => Protocol::Quic,
+                false => Protocol::QuicV1,
+            };
+            let crypto_config = Arc::new(make_server_config(keypair)?);
             let server_config = ServerConfig::with_crypto(crypto_config);
-            let listener = Endpoint::server(server_config, listen_address).unwrap();
+            let listener = Endpoint::server(server_config, listen_address)?;
 
             let listen_address = listener.local_addr()?;
+            listen_multi_addresses.push(
+                Multiaddr::empty()
+                    .with(Protocol::from(listen_address.ip()))
+                    .with(Protocol::Udp(listen_address.port()))
+                    .with(quic),
+            );
             listen_addresses.push(listen_address);
             listeners.push(listener);
-            // );
         }
 
-        let listen_multi_addresses = listen_addresses
-            .iter()
-            .cloned()
-            .map(|address| {
-                Multiaddr::empty()
-                    .with(Protocol::from(address.ip()))
-                    .with(Protocol::Udp(address.port()))
-                    .with(Protocol::QuicV1)
-            })
-            .collect();
-
         Ok((
             Self {
                 incoming: listeners
@@ -136,7 +140,7 @@ impl QuicListener {
 
         // verify that quic exists
         match iter.next() {
-            Some(Protocol::QuicV1) => {}
+            Some(Protocol::QuicV1) | Some(Protocol::Quic) => {}
             _ => return Err(Error::AddressError(AddressError::InvalidProtocol)),
         }
 
@@ -239,6 +243,16 @@ mod tests {
             &"/ip6/::1/udp/8888/quic-v1/utp".parse().expect("valid multiaddress")
         )
         .is_err());
+        assert!(QuicListener::get_socket_address(
+            &"/ip4/127.0.0.1/udp/8888/quic".parse().expect("valid multiaddress")
+        )
+        .is_ok());
+        assert!(QuicListener::get_socket_address(
+            &"/ip6/::1/udp/8888/quic/p2p/12D3KooWT2ouvz5uMmCvHJGzAGRHiqDts5hzXR7NdoQ27pGdzp9Q"
+                .parse()
+                .expect("valid multiaddress")
+        )
+        .is_ok());
     }
 
     #[tokio::test]
@@ -23,7 +23,7 @@
 //! QUIC transport.
 
 use crate::{
-    crypto::tls::make_client_config,
+    crypto::{ed25519::Keypair, tls::make_client_config},
     error::{AddressError, Error},
     transport::{
         manager::TransportHandle,
@@ -36,6 +36,7 @@ use crate::{
 
 use futures::{future::BoxFuture, stream::FuturesUnordered, Stream, StreamExt};
 use multiaddr::{Multiaddr, Protocol};
+use parking_lot::RwLock;
 use quinn::{ClientConfig, Connection, Endpoint, IdleTimeout};
 
 use std::{
@@ -44,6 +45,7 @@ use std::{
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
+    time::Duration,
 };
 
 pub(crate) use substream::Substream;
@@ -57,6 +59,86 @@ pub mod config;
 /// Logging target for the file.
 const LOG_TARGET: &str = "litep2p::quic";
 
+/// QUIC version 1.
+const QUIC_V1: u32 = 0x0000_0001;
+
+/// Legacy QUIC draft-29 version, advertised as `/quic`.
+const QUIC_DRAFT_29: u32 = 0xff00_001d;
+
+/// Get the QUIC version used to dial `address`.
+fn quic_version(address: &Multiaddr) -> u32 {
+    match address.iter().any(|protocol| std::matches!(protocol, Protocol::Quic)) {
+        true => QUIC_DRAFT_29,
+        false => QUIC_V1,
+    }
+}
+
+/// Create client configuration for dialing `peer` over `address`.
+fn client_config(
+    keypair: &Keypair,
+    peer: PeerId,
+    address: &Multiaddr,
+    connection_open_timeout: Duration,
+) -> crate::Result<ClientConfig> {
+    let crypto_config = Arc::new(make_client_config(keypair, Some(peer))?);
+    let mut transport_config = quinn::TransportConfig::default();
+    let timeout = IdleTimeout::try_from(connection_open_timeout)
+        .map_err(|error| Error::Other(error.to_string()))?;
+    transport_config.max_idle_timeout(Some(timeout));
+    let mut client_config = ClientConfig::new(crypto_config);
+    client_config.transport_config(Arc::new(transport_config));
+    client_config.version(quic_version(address));
+
+    Ok(client_config)
+}
+
+/// Open QUIC connections, shared with [`Litep2p`](crate::Litep2p) for statistics queries and
+/// with installed protocols for connection selection.
+pub(crate) type QuicConnections = Arc<RwLock<HashMap<ConnectionId, Connection>>>;
+
+/// Path statistics of a QUIC connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathStats {
+    /// Current best estimate of the connection's round-trip time.
+    pub rtt: Duration,
+
+    /// Current congestion window of the connection, in bytes.
+    pub congestion_window: u64,
+
+    /// Number of congestion events on the path.
+    pub congestion_events: u64,
+
+    /// Number of packets lost on the path.
+    pub lost_packets: u64,
+
+    /// Number of bytes lost on the path.
+    pub lost_bytes: u64,
+
+    /// Number of packets sent on the path.
+    pub sent_packets: u64,
+
+    /// Maximum size of an unreliable datagram that fits into the current path MTU estimate.
+    ///
+    /// `None` if the remote peer doesn't support QUIC datagrams.
+    pub max_datagram_size: Option<usize>,
+}
+
+impl From<&Connection> for PathStats {
+    fn from(connection: &Connection) -> Self {
+        let path = connection.stats().path;
+
+        Self {
+            rtt: path.rtt,
+            congestion_window: path.cwnd,
+            congestion_events: path.congestion_events,
+            lost_packets: path.lost_packets,
+            lost_bytes: path.lost_bytes,
+            sent_packets: path.sent_packets,
+            max_datagram_size: connection.max_datagram_size(),
+        }
+    }
+}
+
 #[derive(Debug)]
 struct NegotiatedConnection {
     /// Remote peer ID.
@@ -89,7 +171,13 @@ pub(crate) struct QuicTransport {
 
     /// Pending raw, unnegotiated connections.
     pending_raw_connections: FuturesUnordered<
-        BoxFuture<'static, Result<(ConnectionId, Multiaddr, NegotiatedConnection), ConnectionId>>,
+        BoxFuture<
+            'static,
+            Result<
+                (ConnectionId, Multiaddr, NegotiatedConnection),
+                (ConnectionId, Vec<(Multiaddr, Error)>),
+            >,
+        >,
     >,
 
     /// Opened raw connection, waiting for approval/rejection from `TransportManager`.
@@ -97,15 +185,61 @@ pub(crate) struct QuicTransport {
 
     /// Canceled raw connections.
     canceled: HashSet<ConnectionId>,
+
+    /// Open connections.
+    connections: QuicConnections,
+
+    /// Client endpoints bound to the configured dial port, one for each IP version.
+    client_endpoints: HashMap<IpAddr, Endpoint>,
+
+    /// Inbound connections which are being negotiated.
+    pending_inbound: HashSet<ConnectionId>,
 }
 
 impl QuicTransport {
+    /// Get client endpoint for dialing `address`.
+    ///
+    /// If [`QuicConfig::dial_bind_port`] is not set, a new endpoint bound to a random port is
+    /// created for each dial. Otherwise the endpoint is bound to the configured port once and
+    /// reused for all dials over the same IP version.
+    fn client_endpoint(&mut self, address: &Multiaddr) -> crate::Result<Endpoint> {
+        let ip = match address.iter().next() {
+            Some(Protocol::Ip6(_)) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            Some(Protocol::Ip4(_)) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            _ => return Err(Error::AddressError(AddressError::InvalidProtocol)),
+        };
+
+        let Some(port) = self.config.dial_bind_port else {
+            return Endpoint::client(SocketAddr::new(ip, 0))
+                .map_err(|error| Error::Other(error.to_string()));
+        };
+
+        if let Some(client) = self.client_endpoints.get(&ip) {
+            return Ok(client.clone());
+        }
+
+        let client = Endpoint::client(SocketAddr::new(ip, port))
+            .map_err(|error| Error::Other(error.to_string()))?;
+        self.client_endpoints.insert(ip, client.clone());
+
+        Ok(client)
+    }
+
+    /// Check if the number of inbound connections being negotiated allows accepting a new
+    /// connection.
+    fn can_accept_inbound(&self) -> bool {
+        match self.config.max_pending_inbound_connections {
+            Some(max) => self.pending_inbound.len() < max,
+            None => true,
+        }
+    }
+
     /// Attempt to extract `PeerId` from connection certificates.
     fn extract_peer_id(connection: &Connection) -> Option<PeerId> {
         let certificates: Box<Vec<rustls::Certificate>> =
             connection.peer_identity()?.downcast().ok()?;
-        let p2p_cert = crate::crypto::tls::certificate::parse(certificates.get(0)?)
-            .expect("the certificate was validated during TLS handshake; qed");
+        // the certificate was validated during TLS handshake
+        let p2p_cert = crate::crypto::tls::certificate::parse(certificates.get(0)?).ok()?;
 
         Some(p2p_cert.peer_id())
     }
@@ -184,12 +318,17 @@ impl TransportBuilder for QuicTransport {
             std::mem::replace(&mut config.listen_addresses, Vec::new()),
         )?;
 
+        let connections = context.quic_connections.clone();
+
         Ok((
             Self {
                 context,
                 config,
                 listener,
+                connections,
                 canceled: HashSet::new(),
+                client_endpoints: HashMap::new(),
+                pending_inbound: HashSet::new(),
                 opened_raw: HashMap::new(),
                 pending_open: HashMap::new(),
                 pending_dials: HashMap::new(),
@@ -207,23 +346,14 @@ impl Transport for QuicTransport {
             return Err(Error::AddressError(AddressError::PeerIdMissing));
         };
 
-        let crypto_config =
-            Arc::new(make_client_config(&self.context.keypair, Some(peer)).expect("to succeed"));
-        let mut transport_config = quinn::TransportConfig::default();
-        let timeout =
-            IdleTimeout::try_from(self.config.connection_open_timeout).expect("to succeed");
-        transport_config.max_idle_timeout(Some(timeout));
-        let mut client_config = ClientConfig::new(crypto_config);
-        client_config.transport_config(Arc::new(transport_config));
-
-        let client_listen_address = match address.iter().next() {
-            Some(Protocol::Ip6(_)) => SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0),
-            Some(Protocol::Ip4(_)) => SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
-            _ => return Err(Error::AddressError(AddressError::InvalidProtocol)),
-        };
-
-        let client = Endpoint::client(client_listen_address)
-            .map_err(|error| Error::Other(error.to_string()))?;
+        let client_config = client_config(
+            &self.context.keypair,
+            peer,
+            &address,
+            self.config.connection_open_timeout,
+        )?;
+        let client = self.client_endpoint(&address)?;
+        let client_listen_address = client.local_addr().ok();
         let connection = client
             .connect_with(client_config, socket_address, "l")
             .map_err(|error| Error::Other(error.to_string()))?;
@@ -261,6 +391,8 @@ impl Transport for QuicTransport {
         let bandwidth_sink = self.context.bandwidth_sink.clone();
         let protocol_set = self.context.protocol_set(connection_id);
         let substream_open_timeout = self.config.substream_open_timeout;
+        let path_stats_interval = self.config.path_stats_interval;
+        let connections = Arc::clone(&self.connections);
 
         tracing::trace!(
             target: LOG_TARGET,
@@ -268,18 +400,26 @@ impl Transport for QuicTransport {
             "start connection",
         );
 
-        self.context.executor.run(Box::pin(async move {
-            let _ = QuicConnection::new(
-                connection.peer,
-                endpoint,
-                connection.connection,
-                protocol_set,
-                bandwidth_sink,
-                substream_open_timeout,
-            )
-            .start()
-            .await;
-        }));
+        connections.write().insert(connection_id, connection.connection.clone());
+
+        self.context.executor.run_with_name(
+            "litep2p-quic-connection",
+            Box::pin(async move {
+                let _ = QuicConnection::new(
+                    connection.peer,
+                    endpoint,
+                    connection.connection,
+                    protocol_set,
+                    bandwidth_sink,
+                    substream_open_timeout,
+                    path_stats_interval,
+                )
+                .start()
+                .await;
+
+                connections.write().remove(&connection_id);
+            }),
+        );
 
         Ok(())
     }
@@ -301,6 +441,7 @@ impl Transport for QuicTransport {
             .map(|address| {
                 let keypair = self.context.keypair.clone();
                 let connection_open_timeout = self.config.connection_open_timeout;
+                let client = self.client_endpoint(&address);
 
                 async move {
                     let Ok((socket_address, Some(peer))) =
@@ -308,51 +449,36 @@ impl Transport for QuicTransport {
                     else {
                         return (
                             connection_id,
-                            Err(Error::AddressError(AddressError::PeerIdMissing)),
+                            Err((address, Error::AddressError(AddressError::PeerIdMissing))),
                         );
                     };
 
-                    let crypto_config =
-                        Arc::new(make_client_config(&keypair, Some(peer)).expect("to succeed"));
-                    let mut transport_config = quinn::TransportConfig::default();
-                    let timeout =
-                        IdleTimeout::try_from(connection_open_timeout).expect("to succeed");
-                    transport_config.max_idle_timeout(Some(timeout));
-                    let mut client_config = ClientConfig::new(crypto_config);
-                    client_config.transport_config(Arc::new(transport_config));
-
-                    let client_listen_address = match address.iter().next() {
-                        Some(Protocol::Ip6(_)) =>
-                            SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0),
-                        Some(Protocol::Ip4(_)) =>
-                            SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
-                        _ =>
-                            return (
-                                connection_id,
-                                Err(Error::AddressError(AddressError::InvalidProtocol)),
-                            ),
-                    };
-
-                    let client = match Endpoint::client(client_listen_address) {
+                    let client_config =
+                        match client_config(&keypair, peer, &address, connection_open_timeout) {
+                            Ok(client_config) => client_config,
+                            Err(error) => return (connection_id, Err((address, error))),
+                        };
+                    let client = match client {
                         Ok(client) => client,
-                        Err(error) => {
-                            return (connection_id, Err(Error::Other(error.to_string())));
-                        }
+                        Err(error) => return (connection_id, Err((address, error))),
                     };
                     let connection = match client.connect_with(client_config, socket_address, "l") {
                         Ok(connection) => connection,
                         Err(error) => {
-                            return (connection_id, Err(Error::Other(error.to_string())));
+                            return (
+                                connection_id,
+                                Err((address, Error::Other(error.to_string()))),
+                            );
                         }
                     };
 
                     let connection = match connection.await {
                         Ok(connection) => connection,
-                        Err(error) => return (connection_id, Err(error.into())),
+                        Err(error) => return (connection_id, Err((address, error.into()))),
                     };
 
                     let Some(peer) = Self::extract_peer_id(&connection) else {
-                        return (connection_id, Err(Error::InvalidCertificate));
+                        return (connection_id, Err((address, Error::InvalidCertificate)));
                     };
 
                     (
@@ -364,21 +490,27 @@ impl Transport for QuicTransport {
             .collect();
 
         self.pending_raw_connections.push(Box::pin(async move {
+            let mut errors = Vec::with_capacity(futures.len());
+
             while let Some(result) = futures.next().await {
                 let (connection_id, result) = result;
 
                 match result {
                     Ok((address, connection)) => return Ok((connection_id, address, connection)),
-                    Err(error) => tracing::debug!(
-                        target: LOG_TARGET,
-                        ?connection_id,
-                        ?error,
-                        "failed to open connection",
-                    ),
+                    Err((address, error)) => {
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            ?connection_id,
+                            ?address,
+                            ?error,
+                            "failed to open connection",
+                        );
+                        errors.push((address, error));
+                    }
                 }
             }
 
-            Err(connection_id)
+            Err((connection_id, errors))
         }));
 
         Ok(())
@@ -407,6 +539,34 @@ impl Stream for QuicTransport {
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         while let Poll::Ready(Some(connection)) = self.listener.poll_next_unpin(cx) {
+            let address = Multiaddr::empty()
+                .with(Protocol::from(connection.remote_address().ip()))
+                .with(Protocol::Udp(connection.remote_address().port()))
+                .with(Protocol::QuicV1);
+
+            // dropping `Connecting` refuses the connection and stops its handshake
+            if !self.context.allow_inbound_address(&address) {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?address,
+                    "inbound connection rejected by connection gater",
+                );
+                continue;
+            }
+
+            let ip = connection.remote_address().ip();
+            let connection_rates = self.context.connection_rates.clone();
+            connection_rates.on_inbound_attempt(ip);
+
+            if !self.can_accept_inbound() {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    address = ?connection.remote_address(),
+                    "too many pending inbound connections, refuse connection",
+                );
+                continue;
+            }
+
             let connection_id = self.context.next_connection_id();
 
             tracing::trace!(
@@ -415,17 +575,18 @@ impl Stream for QuicTransport {
                 "accept connection",
             );
 
+            self.pending_inbound.insert(connection_id);
             self.pending_connections.push(Box::pin(async move {
-                let connection = match connection.await {
-                    Ok(connection) => connection,
-                    Err(error) => return (connection_id, Err(error.into())),
-                };
-
-                let Some(peer) = Self::extract_peer_id(&connection) else {
-                    return (connection_id, Err(Error::InvalidCertificate));
+                let result = match connection.await {
+                    Ok(connection) => match Self::extract_peer_id(&connection) {
+                        Some(peer) => Ok(NegotiatedConnection { peer, connection }),
+                        None => Err(Error::InvalidCertificate),
+                    },
+                    Err(error) => Err(error.into()),
                 };
+                connection_rates.on_handshake(ip, result.is_ok());
 
-                (connection_id, Ok(NegotiatedConnection { peer, connection }))
+                (connection_id, result)
             }));
         }
 
@@ -449,15 +610,19 @@ impl Stream for QuicTransport {
                         }));
                     }
                 }
-                Err(connection_id) =>
+                Err((connection_id, errors)) =>
                     if !self.canceled.remove(&connection_id) {
-                        return Poll::Ready(Some(TransportEvent::OpenFailure { connection_id }));
+                        return Poll::Ready(Some(TransportEvent::OpenFailure {
+                            connection_id,
+                            errors,
+                        }));
                     },
             }
         }
 
         while let Poll::Ready(Some(connection)) = self.pending_connections.poll_next_unpin(cx) {
             let (connection_id, result) = connection;
+            self.pending_inbound.remove(&connection_id);
 
             match self.on_connection_established(connection_id, result) {
                 Some(event) => return Poll::Ready(Some(event)),
@@ -476,7 +641,7 @@ mod tests {
         codec::ProtocolCodec,
         crypto::ed25519::Keypair,
         executor::DefaultExecutor,
-        transport::manager::{ProtocolContext, TransportHandle},
+        transport::manager::{ProtocolContext, SupportedTransport, TransportHandle},
         types::protocol::ProtocolName,
         BandwidthSink,
     };
@@ -494,6 +659,7 @@ mod tests {
         let (event_tx1, _event_rx1) = channel(64);
 
         let handle1 = TransportHandle {
+            transport: SupportedTransport::Quic,
             executor: Arc::new(DefaultExecutor {}),
             protocol_names: Vec::new(),
             next_substream_id: Default::default(),
@@ -501,15 +667,26 @@ mod tests {
             keypair: keypair1.clone(),
             tx: event_tx1,
             bandwidth_sink: BandwidthSink::new(),
+            dns_resolver: Default::default(),
+            connection_rates: Default::default(),
+            connection_gater: None,
+            quic_connections: Default::default(),
+            max_connection_lifetime: None,
+            limits: Default::default(),
+            reserved_peers: Default::default(),
+            slow_protocol_policy: Default::default(),
+            channel_size: 64,
 
             protocols: HashMap::from_iter([(
                 ProtocolName::from("/notif/1"),
                 ProtocolContext {
                     tx: tx1,
                     codec: ProtocolCodec::Identity(32),
+                    stream_priority: 0,
                     fallback_names: Vec::new(),
                 },
-            )]),
+            )])
+            .into(),
         };
 
         let (mut transport1, listen_addresses) =
@@ -521,6 +698,7 @@ mod tests {
         let (event_tx2, _event_rx2) = channel(64);
 
         let handle2 = TransportHandle {
+            transport: SupportedTransport::Quic,
             executor: Arc::new(DefaultExecutor {}),
             protocol_names: Vec::new(),
             next_substream_id: Default::default(),
@@ -528,15 +706,26 @@ mod tests {
             keypair: keypair2.clone(),
             tx: event_tx2,
             bandwidth_sink: BandwidthSink::new(),
+            dns_resolver: Default::default(),
+            connection_rates: Default::default(),
+            connection_gater: None,
+            quic_connections: Default::default(),
+            max_connection_lifetime: None,
+            limits: Default::default(),
+            reserved_peers: Default::default(),
+            slow_protocol_policy: Default::default(),
+            channel_size: 64,
 
             protocols: HashMap::from_iter([(
                 ProtocolName::from("/notif/1"),
                 ProtocolContext {
                     tx: tx2,
                     codec: ProtocolCodec::Identity(32),
+                    stream_priority: 0,
                     fallback_names: Vec::new(),
                 },
-            )]),
+            )])
+            .into(),
         };
 
         let (mut transport2, _) = QuicTransport::new(handle2, Default::default()).unwrap();
@@ -558,4 +747,80 @@ mod tests {
             Some(TransportEvent::ConnectionEstablished { .. })
         ));
     }
+
+    #[tokio::test]
+    async fn pending_inbound_connections_limited() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        let keypair1 = Keypair::generate();
+        let (event_tx1, _event_rx1) = channel(64);
+        let handle1 = TransportHandle {
+            transport: SupportedTransport::Quic,
+            executor: Arc::new(DefaultExecutor {}),
+            protocol_names: Vec::new(),
+            next_substream_id: Default::default(),
+            next_connection_id: Default::default(),
+            keypair: keypair1.clone(),
+            tx: event_tx1,
+            bandwidth_sink: BandwidthSink::new(),
+            dns_resolver: Default::default(),
+            connection_rates: Default::default(),
+            connection_gater: None,
+            quic_connections: Default::default(),
+            max_connection_lifetime: None,
+            limits: Default::default(),
+            reserved_peers: Default::default(),
+            slow_protocol_policy: Default::default(),
+            channel_size: 64,
+            protocols: HashMap::new().into(),
+        };
+        let config1 = QuicConfig {
+            max_pending_inbound_connections: Some(1),
+            ..Default::default()
+        };
+        let (mut transport1, listen_addresses) = QuicTransport::new(handle1, config1).unwrap();
+
+        let (event_tx2, _event_rx2) = channel(64);
+        let handle2 = TransportHandle {
+            transport: SupportedTransport::Quic,
+            executor: Arc::new(DefaultExecutor {}),
+            protocol_names: Vec::new(),
+            next_substream_id: Default::default(),
+            next_connection_id: Default::default(),
+            keypair: Keypair::generate(),
+            tx: event_tx2,
+            bandwidth_sink: BandwidthSink::new(),
+            dns_resolver: Default::default(),
+            connection_rates: Default::default(),
+            connection_gater: None,
+            quic_connections: Default::default(),
+            max_connection_lifetime: None,
+            limits: Default::default(),
+            reserved_peers: Default::default(),
+            slow_protocol_policy: Default::default(),
+            channel_size: 64,
+            protocols: HashMap::new().into(),
+        };
+        let (mut transport2, _) = QuicTransport::new(handle2, Default::default()).unwrap();
+
+        let peer1: PeerId = PeerId::from_public_key(&keypair1.public().into());
+        let listen_address = listen_addresses[0].clone().with(Protocol::P2p(
+            Multihash::from_bytes(&peer1.to_bytes()).unwrap(),
+        ));
+
+        // dial twice and let both handshakes arrive before the listener is polled
+        transport2.dial(ConnectionId::from(0usize), listen_address.clone()).unwrap();
+        transport2.dial(ConnectionId::from(1usize), listen_address).unwrap();
+        tokio::spawn(async move { while let Some(_) = transport2.next().await {} });
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        // only one of the connections is accepted
+        assert!(std::matches!(
+            transport1.next().await,
+            Some(TransportEvent::ConnectionEstablished { .. })
+        ));
+        assert!(tokio::time::timeout(Duration::from_secs(1), transport1.next()).await.is_err());
+    }
 }
@@ -62,6 +62,16 @@ impl Substream {
         }
     }
 
+    /// Set priority of the substream.
+    ///
+    /// Data of substreams with higher priority is sent before data of substreams with lower
+    /// priority. By default the priority is `0`.
+    pub fn set_priority(&self, priority: i32) -> crate::Result<()> {
+        self.send_stream
+            .set_priority(priority)
+            .map_err(|_| Error::SubstreamError(SubstreamError::ConnectionClosed))
+    }
+
     /// Write `buffers` to the underlying socket.
     pub async fn write_all_chunks(&mut self, buffers: &mut [Bytes]) -> crate::Result<()> {
         let nwritten = buffers.iter().fold(0usize, |acc, buffer| acc + buffer.len());
@@ -0,0 +1,106 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Automatic reconnection to persistent peers.
+//!
+//! Peers marked as persistent using
+//! [`Litep2p::add_persistent_peer()`](crate::Litep2p::add_persistent_peer) are re-dialed by the
+//! transport manager after they disconnect or a dial to them fails. Consecutive attempts are
+//! delayed using exponential backoff with jitter, and the backoff is reset once a connection to
+//! the peer has been established.
+
+use rand::Rng;
+
+use std::time::Duration;
+
+/// Reconnection configuration.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Delay before the first reconnection attempt.
+    ///
+    /// By default the first attempt is made after `1` second.
+    pub initial_backoff: Duration,
+
+    /// Maximum delay between reconnection attempts.
+    ///
+    /// By default the delay is capped at `5` minutes.
+    pub max_backoff: Duration,
+
+    /// Fraction of the delay which is randomized to avoid synchronized reconnection attempts.
+    ///
+    /// By default the delay is randomized by up to `20%` in either direction.
+    pub jitter: f64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5 * 60),
+            jitter: 0.2,
+        }
+    }
+}
+
+impl Config {
+    /// Get the delay before the reconnection attempt which follows `attempts` failed attempts.
+    pub(crate) fn backoff(&self, attempts: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempts))
+            .min(self.max_backoff);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+
+        if jitter == 0.0 {
+            return backoff;
+        }
+
+        backoff.mul_f64(1.0 + rand::thread_rng().gen_range(-jitter..=jitter))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_exponentially() {
+        let config = Config {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(10),
+            jitter: 0.0,
+        };
+
+        assert_eq!(config.backoff(0), Duration::from_secs(1));
+        assert_eq!(config.backoff(3), Duration::from_secs(8));
+        assert_eq!(config.backoff(4), Duration::from_secs(10));
+        assert_eq!(config.backoff(u32::MAX), Duration::from_secs(10));
+
+        let config = Config {
+            jitter: 0.5,
+            ..config
+        };
+
+        for _ in 0..100 {
+            let backoff = config.backoff(1);
+            assert!(backoff >= Duration::from_secs(1) && backoff <= Duration::from_secs(3));
+        }
+    }
+}
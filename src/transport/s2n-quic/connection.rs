@@ -25,7 +25,7 @@ use crate::{
     multistream_select::{dialer_select_proto, listener_select_proto, Negotiated, Version},
     protocol::{Direction, Permit, ProtocolCommand, ProtocolSet},
     substream::Substream as SubstreamT,
-    transport::substream::Substream,
+    transport::{substream::Substream, DisconnectReason},
     types::{protocol::ProtocolName, ConnectionId, SubstreamId},
     PeerId,
 };
@@ -224,7 +224,18 @@ impl QuicConnection {
                     Ok(Some(stream)) => {
                         let substream = self.protocol_set.next_substream_id();
                         let protocols = self.protocol_set.protocols();
-                        let permit = self.protocol_set.try_get_permit().ok_or(Error::ConnectionClosed)?;
+                        // protocols have released the connection
+                        let Some(permit) = self.protocol_set.try_get_permit() else {
+                            return self.protocol_set.report_connection_closed(self.peer, self.connection_id, DisconnectReason::Inactive).await;
+                        };
+                        if self.protocol_set.substream_limit_exceeded() {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                peer = ?self.peer,
+                                "substream limit reached, rejecting inbound substream",
+                            );
+                            continue;
+                        }
 
                         self.pending_substreams.push(Box::pin(async move {
                             match tokio::time::timeout(
@@ -248,7 +259,7 @@ impl QuicConnection {
                     }
                     Ok(None) => {
                         tracing::debug!(target: LOG_TARGET, peer = ?self.peer, "connection closed");
-                        self.protocol_set.report_connection_closed(self.peer, self.connection_id).await?;
+                        self.protocol_set.report_connection_closed(self.peer, self.connection_id, DisconnectReason::RemoteClosed).await?;
 
                         return Ok(())
                     }
@@ -259,7 +270,7 @@ impl QuicConnection {
                             ?error,
                             "connection closed with error"
                         );
-                        self.protocol_set.report_connection_closed(self.peer, self.connection_id).await?;
+                        self.protocol_set.report_connection_closed(self.peer, self.connection_id, DisconnectReason::Error).await?;
 
                         return Ok(())
                     }
@@ -364,7 +375,7 @@ impl QuicConnection {
                     }
                     None => {
                         tracing::debug!(target: LOG_TARGET, "protocols have exited, shutting down connection");
-                        return self.protocol_set.report_connection_closed(self.peer, self.connection_id).await
+                        return self.protocol_set.report_connection_closed(self.peer, self.connection_id, DisconnectReason::Inactive).await
                     }
                 }
             }
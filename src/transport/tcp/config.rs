@@ -22,9 +22,12 @@
 
 use crate::{
     crypto::noise::{MAX_READ_AHEAD_FACTOR, MAX_WRITE_BUFFER_SIZE},
-    transport::{CONNECTION_OPEN_TIMEOUT, SUBSTREAM_OPEN_TIMEOUT},
+    transport::{CONNECTION_OPEN_TIMEOUT, INTERFACE_POLL_INTERVAL, SUBSTREAM_OPEN_TIMEOUT},
 };
 
+/// Default listen backlog.
+const LISTEN_BACKLOG: u32 = 1024;
+
 /// TCP transport configuration.
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -72,6 +75,32 @@ pub struct Config {
     ///
     /// By default, port reuse is enabled.
     pub disable_port_reuse: bool,
+
+    /// Listen backlog.
+    ///
+    /// Maximum number of connections the operating system queues for each listener before they're
+    /// accepted by litep2p.
+    ///
+    /// By default the backlog is set to `1024`.
+    pub listen_backlog: u32,
+
+    /// Maximum number of inbound connections that are negotiated concurrently.
+    ///
+    /// Once the limit is reached, litep2p stops accepting new connections until at least one of
+    /// the pending connections has been negotiated and the connections are left in the listen
+    /// backlog.
+    ///
+    /// By default the number of concurrently negotiated inbound connections is not limited.
+    pub max_pending_inbound_connections: Option<usize>,
+
+    /// Network interface poll interval.
+    ///
+    /// If the transport listens on all network interfaces (`0.0.0.0` or `::`), the interfaces are
+    /// polled at this interval and listen addresses are added and removed as addresses appear on
+    /// and disappear from the interfaces. Polling is disabled if set to `None`.
+    ///
+    /// By default the interfaces are polled every 10 seconds.
+    pub interface_poll_interval: Option<std::time::Duration>,
 }
 
 impl Default for Config {
@@ -87,6 +116,9 @@ impl Default for Config {
             connection_open_timeout: CONNECTION_OPEN_TIMEOUT,
             substream_open_timeout: SUBSTREAM_OPEN_TIMEOUT,
             disable_port_reuse: false,
+            listen_backlog: LISTEN_BACKLOG,
+            max_pending_inbound_connections: None,
+            interface_poll_interval: Some(INTERFACE_POLL_INTERVAL),
         }
     }
 }
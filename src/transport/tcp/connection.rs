@@ -29,8 +29,9 @@ use crate::{
     protocol::{Direction, Permit, ProtocolCommand, ProtocolSet},
     substream,
     transport::{
+        muxer::{Muxer, MuxerEvent, OutboundSubstream},
         tcp::{listener::AddressType, substream::Substream},
-        Endpoint,
+        DisconnectReason, Endpoint, RawConnection,
     },
     types::{protocol::ProtocolName, ConnectionId, SubstreamId},
     BandwidthSink, PeerId,
@@ -105,12 +106,9 @@ enum ConnectionError {
 }
 
 /// Connection context for an opened connection that hasn't yet started its event loop.
-pub struct NegotiatedConnection {
+pub struct NegotiatedConnection<S = NoiseSocket<Compat<TcpStream>>> {
     /// Yamux connection.
-    connection: crate::yamux::ControlledConnection<NoiseSocket<Compat<TcpStream>>>,
-
-    /// Yamux control.
-    control: crate::yamux::Control,
+    connection: crate::yamux::Connection<S>,
 
     /// Remote peer ID.
     peer: PeerId,
@@ -122,7 +120,32 @@ pub struct NegotiatedConnection {
     substream_open_timeout: Duration,
 }
 
-impl NegotiatedConnection {
+impl NegotiatedConnection<Box<dyn RawConnection>> {
+    /// Create [`NegotiatedConnection`] from a connection that was established and authenticated
+    /// outside of `litep2p`.
+    ///
+    /// The connection is multiplexed with `yamux` without negotiating it first.
+    pub(super) fn injected(
+        connection: Box<dyn RawConnection>,
+        peer: PeerId,
+        connection_id: ConnectionId,
+        yamux_config: crate::yamux::Config,
+        substream_open_timeout: Duration,
+    ) -> Self {
+        let role = connection.role();
+        let endpoint = Endpoint::listener(connection.address(), connection_id);
+        let connection = crate::yamux::Connection::new(connection, yamux_config, role.into());
+
+        Self {
+            peer,
+            connection,
+            endpoint,
+            substream_open_timeout,
+        }
+    }
+}
+
+impl<S> NegotiatedConnection<S> {
     /// Get `ConnectionId` of the negotiated connection.
     pub fn connection_id(&self) -> ConnectionId {
         self.endpoint.connection_id()
@@ -140,15 +163,12 @@ impl NegotiatedConnection {
 }
 
 /// TCP connection.
-pub struct TcpConnection {
+pub struct TcpConnection<S = NoiseSocket<Compat<TcpStream>>> {
     /// Protocol context.
     protocol_set: ProtocolSet,
 
     /// Yamux connection.
-    connection: crate::yamux::ControlledConnection<NoiseSocket<Compat<TcpStream>>>,
-
-    /// Yamux control.
-    control: crate::yamux::Control,
+    muxer: Muxer<S>,
 
     /// Remote peer ID.
     peer: PeerId,
@@ -170,7 +190,7 @@ pub struct TcpConnection {
         FuturesUnordered<BoxFuture<'static, Result<NegotiatedSubstream, ConnectionError>>>,
 }
 
-impl fmt::Debug for TcpConnection {
+impl<S> fmt::Debug for TcpConnection<S> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("TcpConnection")
             .field("peer", &self.peer)
@@ -179,17 +199,19 @@ impl fmt::Debug for TcpConnection {
     }
 }
 
-impl TcpConnection {
+impl<S> TcpConnection<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
     /// Create new [`TcpConnection`] from [`NegotiatedConnection`].
     pub(super) fn new(
-        context: NegotiatedConnection,
+        context: NegotiatedConnection<S>,
         protocol_set: ProtocolSet,
         bandwidth_sink: BandwidthSink,
         next_substream_id: Arc<AtomicUsize>,
     ) -> Self {
         let NegotiatedConnection {
             connection,
-            control,
             peer,
             endpoint,
             substream_open_timeout,
@@ -197,8 +219,7 @@ impl TcpConnection {
 
         Self {
             protocol_set,
-            connection,
-            control,
+            muxer: Muxer::new(connection),
             peer,
             endpoint,
             bandwidth_sink,
@@ -207,7 +228,9 @@ impl TcpConnection {
             substream_open_timeout,
         }
     }
+}
 
+impl TcpConnection {
     /// Open connection to remote peer at `address`.
     // TODO: this function can be removed
     pub(super) async fn open_connection(
@@ -251,32 +274,16 @@ impl TcpConnection {
         }
     }
 
-    /// Open substream for `protocol`.
+    /// Negotiate `protocol` for an opened outbound substream.
     pub(super) async fn open_substream(
-        mut control: crate::yamux::Control,
+        stream: crate::yamux::Stream,
         substream_id: SubstreamId,
         permit: Permit,
         protocol: ProtocolName,
         fallback_names: Vec<ProtocolName>,
         open_timeout: Duration,
     ) -> crate::Result<NegotiatedSubstream> {
-        tracing::debug!(target: LOG_TARGET, ?protocol, ?substream_id, "open substream");
-
-        let stream = match control.open_stream().await {
-            Ok(stream) => {
-                tracing::trace!(target: LOG_TARGET, ?substream_id, "substream opened");
-                stream
-            }
-            Err(error) => {
-                tracing::debug!(
-                    target: LOG_TARGET,
-                    ?substream_id,
-                    ?error,
-                    "failed to open substream"
-                );
-                return Err(Error::YamuxError(Direction::Outbound(substream_id), error));
-            }
-        };
+        tracing::trace!(target: LOG_TARGET, ?protocol, ?substream_id, "negotiate outbound substream");
 
         // TODO: protocols don't change after they've been initialized so this should be done only
         // once
@@ -452,7 +459,6 @@ impl TcpConnection {
         tracing::trace!(target: LOG_TARGET, "`yamux` negotiated");
 
         let connection = crate::yamux::Connection::new(stream.inner(), yamux_config, role.into());
-        let (control, connection) = crate::yamux::Control::new(connection);
 
         let address = match address {
             AddressType::Socket(address) => Multiaddr::empty()
@@ -469,13 +475,17 @@ impl TcpConnection {
 
         Ok(NegotiatedConnection {
             peer,
-            control,
             connection,
             endpoint,
             substream_open_timeout,
         })
     }
+}
 
+impl<S> TcpConnection<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
     /// Start connection event loop.
     pub(crate) async fn start(mut self) -> crate::Result<()> {
         self.protocol_set
@@ -484,20 +494,31 @@ impl TcpConnection {
 
         loop {
             tokio::select! {
-                substream = self.connection.next() => match substream {
-                    Some(Ok(stream)) => {
+                event = self.muxer.next() => match event {
+                    Some(Ok(MuxerEvent::Inbound(stream))) => {
                         let substream_id = {
                             let substream_id = self.next_substream_id.fetch_add(1usize, Ordering::Relaxed);
                             SubstreamId::from(substream_id)
                         };
                         let protocols = self.protocol_set.protocols();
-                        let permit = self.protocol_set.try_get_permit().ok_or(Error::ConnectionClosed)?;
+                        // protocols have released the connection
+                        let Some(permit) = self.protocol_set.try_get_permit() else {
+                            return self.protocol_set.report_connection_closed(self.peer, self.endpoint.connection_id(), DisconnectReason::Inactive).await;
+                        };
+                        if self.protocol_set.substream_limit_exceeded() {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                peer = ?self.peer,
+                                "substream limit reached, rejecting inbound substream",
+                            );
+                            continue;
+                        }
                         let open_timeout = self.substream_open_timeout;
 
                         self.pending_substreams.push(Box::pin(async move {
                             match tokio::time::timeout(
                                 open_timeout,
-                                Self::accept_substream(stream, permit, substream_id, protocols, open_timeout),
+                                <TcpConnection>::accept_substream(stream, permit, substream_id, protocols, open_timeout),
                             )
                             .await
                             {
@@ -514,6 +535,65 @@ impl TcpConnection {
                             }
                         }));
                     },
+                    Some(Ok(MuxerEvent::Outbound { stream, substream })) => {
+                        let OutboundSubstream { protocol, fallback_names, substream_id, permit } = substream;
+                        let open_timeout = self.substream_open_timeout;
+
+                        tracing::trace!(target: LOG_TARGET, ?protocol, ?substream_id, "substream opened");
+
+                        self.pending_substreams.push(Box::pin(async move {
+                            match tokio::time::timeout(
+                                open_timeout,
+                                <TcpConnection>::open_substream(
+                                    stream,
+                                    substream_id,
+                                    permit,
+                                    protocol.clone(),
+                                    fallback_names,
+                                    open_timeout,
+                                ),
+                            )
+                            .await
+                            {
+                                Ok(Ok(substream)) => Ok(substream),
+                                Ok(Err(error)) => Err(ConnectionError::FailedToNegotiate {
+                                    protocol: Some(protocol),
+                                    substream_id: Some(substream_id),
+                                    error,
+                                }),
+                                Err(_) => Err(ConnectionError::Timeout {
+                                    protocol: Some(protocol),
+                                    substream_id: Some(substream_id)
+                                }),
+                            }
+                        }));
+                    }
+                    Some(Ok(MuxerEvent::OutboundFailure { substream, error })) => {
+                        let OutboundSubstream { protocol, substream_id, .. } = substream;
+
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            ?protocol,
+                            ?substream_id,
+                            ?error,
+                            "failed to open substream",
+                        );
+
+                        if let Err(error) = self.protocol_set
+                            .report_substream_open_failure(
+                                protocol,
+                                substream_id,
+                                Error::YamuxError(Direction::Outbound(substream_id), error),
+                            )
+                            .await
+                        {
+                            tracing::error!(
+                                target: LOG_TARGET,
+                                ?error,
+                                "failed to report substream open failure to protocol",
+                            );
+                        }
+                    }
                     Some(Err(error)) => {
                         tracing::debug!(
                             target: LOG_TARGET,
@@ -521,13 +601,13 @@ impl TcpConnection {
                             ?error,
                             "connection closed with error",
                         );
-                        self.protocol_set.report_connection_closed(self.peer, self.endpoint.connection_id()).await?;
+                        self.protocol_set.report_connection_closed(self.peer, self.endpoint.connection_id(), DisconnectReason::from(&error)).await?;
 
                         return Ok(())
                     }
                     None => {
                         tracing::debug!(target: LOG_TARGET, peer = ?self.peer, "connection closed");
-                        self.protocol_set.report_connection_closed(self.peer, self.endpoint.connection_id()).await?;
+                        self.protocol_set.report_connection_closed(self.peer, self.endpoint.connection_id(), DisconnectReason::RemoteClosed).await?;
 
                         return Ok(())
                     }
@@ -597,9 +677,6 @@ impl TcpConnection {
                 }
                 protocol = self.protocol_set.next() => match protocol {
                     Some(ProtocolCommand::OpenSubstream { protocol, fallback_names, substream_id, permit }) => {
-                        let control = self.control.clone();
-                        let open_timeout = self.substream_open_timeout;
-
                         tracing::trace!(
                             target: LOG_TARGET,
                             ?protocol,
@@ -607,32 +684,12 @@ impl TcpConnection {
                             "open substream",
                         );
 
-                        self.pending_substreams.push(Box::pin(async move {
-                            match tokio::time::timeout(
-                                open_timeout,
-                                Self::open_substream(
-                                    control,
-                                    substream_id,
-                                    permit,
-                                    protocol.clone(),
-                                    fallback_names,
-                                    open_timeout,
-                                ),
-                            )
-                            .await
-                            {
-                                Ok(Ok(substream)) => Ok(substream),
-                                Ok(Err(error)) => Err(ConnectionError::FailedToNegotiate {
-                                    protocol: Some(protocol),
-                                    substream_id: Some(substream_id),
-                                    error,
-                                }),
-                                Err(_) => Err(ConnectionError::Timeout {
-                                    protocol: Some(protocol),
-                                    substream_id: Some(substream_id)
-                                }),
-                            }
-                        }));
+                        self.muxer.open_substream(OutboundSubstream {
+                            protocol,
+                            fallback_names,
+                            substream_id,
+                            permit,
+                        });
                     }
                     Some(ProtocolCommand::ForceClose) => {
                         tracing::debug!(
@@ -642,11 +699,11 @@ impl TcpConnection {
                             "force closing connection",
                         );
 
-                        return self.protocol_set.report_connection_closed(self.peer, self.endpoint.connection_id()).await
+                        return self.protocol_set.report_connection_closed(self.peer, self.endpoint.connection_id(), DisconnectReason::LocalClosed).await
                     }
                     None => {
                         tracing::debug!(target: LOG_TARGET, "protocols have disconnected, closing connection");
-                        return self.protocol_set.report_connection_closed(self.peer, self.endpoint.connection_id()).await
+                        return self.protocol_set.report_connection_closed(self.peer, self.endpoint.connection_id(), DisconnectReason::Inactive).await
                     }
                 }
             }
@@ -682,6 +739,7 @@ mod tests {
             Default::default(),
             Duration::from_secs(10),
             false,
+            Default::default(),
         )
         .await
         .unwrap();
@@ -777,6 +835,7 @@ mod tests {
             Default::default(),
             Duration::from_secs(10),
             false,
+            Default::default(),
         )
         .await
         .unwrap();
@@ -919,6 +978,7 @@ mod tests {
             Default::default(),
             Duration::from_secs(10),
             false,
+            Default::default(),
         )
         .await
         .unwrap();
@@ -965,6 +1025,7 @@ mod tests {
             Default::default(),
             Duration::from_secs(10),
             false,
+            Default::default(),
         )
         .await
         .unwrap();
@@ -1120,6 +1181,7 @@ mod tests {
             Default::default(),
             Duration::from_secs(10),
             false,
+            Default::default(),
         )
         .await
         .unwrap();
@@ -1230,6 +1292,7 @@ mod tests {
             Default::default(),
             Duration::from_secs(10),
             false,
+            Default::default(),
         )
         .await
         .unwrap();
@@ -89,7 +89,7 @@ impl DialAddresses {
 
 impl TcpListener {
     /// Create new [`TcpListener`]
-    pub fn new(addresses: Vec<Multiaddr>) -> (Self, Vec<Multiaddr>, DialAddresses) {
+    pub fn new(addresses: Vec<Multiaddr>, backlog: u32) -> (Self, Vec<Multiaddr>, DialAddresses) {
         let (listeners, listen_addresses): (_, Vec<Vec<_>>) = addresses
             .into_iter()
             .filter_map(|address| {
@@ -120,7 +120,7 @@ impl TcpListener {
                 #[cfg(unix)]
                 socket.set_reuse_port(true).ok()?;
                 socket.bind(&address.into()).ok()?;
-                socket.listen(1024).ok()?;
+                socket.listen(backlog.try_into().unwrap_or(i32::MAX)).ok()?;
 
                 let socket: std::net::TcpListener = socket.into();
                 let listener = TokioTcpListener::from_std(socket).ok()?;
@@ -187,6 +187,31 @@ impl TcpListener {
         )
     }
 
+    /// Check if any of the listeners is bound to all network interfaces.
+    pub(super) fn has_wildcard_listener(&self) -> bool {
+        self.listeners.iter().any(|listener| match listener.local_addr() {
+            Ok(address) => address.ip().is_unspecified(),
+            Err(_) => false,
+        })
+    }
+
+    /// Get the listen addresses an interface address `address` provides for listeners that are
+    /// bound to all network interfaces of the same IP version.
+    pub(super) fn interface_listen_addresses(&self, address: IpAddr) -> Vec<Multiaddr> {
+        self.listeners
+            .iter()
+            .filter_map(|listener| listener.local_addr().ok())
+            .filter(|local_address| {
+                local_address.ip().is_unspecified() && local_address.is_ipv4() == address.is_ipv4()
+            })
+            .map(|local_address| {
+                Multiaddr::empty()
+                    .with(Protocol::from(address))
+                    .with(Protocol::Tcp(local_address.port()))
+            })
+            .collect()
+    }
+
     /// Extract socket address and `PeerId`, if found, from `address`.
     pub(super) fn get_socket_address(
         address: &Multiaddr,
@@ -320,7 +345,7 @@ mod tests {
 
     #[tokio::test]
     async fn no_listeners() {
-        let (mut listener, _, _) = TcpListener::new(Vec::new());
+        let (mut listener, _, _) = TcpListener::new(Vec::new(), 1024);
 
         futures::future::poll_fn(|cx| match listener.poll_next_unpin(cx) {
             Poll::Pending => Poll::Ready(()),
@@ -332,7 +357,7 @@ mod tests {
     #[tokio::test]
     async fn one_listener() {
         let address: Multiaddr = "/ip6/::1/tcp/0".parse().unwrap();
-        let (mut listener, listen_addresses, _) = TcpListener::new(vec![address.clone()]);
+        let (mut listener, listen_addresses, _) = TcpListener::new(vec![address.clone()], 1024);
         let Some(Protocol::Tcp(port)) =
             listen_addresses.iter().next().unwrap().clone().iter().skip(1).next()
         else {
@@ -345,11 +370,32 @@ mod tests {
         assert!(res1.unwrap().is_ok() && res2.is_ok());
     }
 
+    #[tokio::test]
+    async fn interface_listen_addresses() {
+        let address1: Multiaddr = "/ip4/0.0.0.0/tcp/0".parse().unwrap();
+        let address2: Multiaddr = "/ip6/::1/tcp/0".parse().unwrap();
+        let (listener, _, _) = TcpListener::new(vec![address1, address2], 1024);
+        assert!(listener.has_wildcard_listener());
+
+        let interface_address = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let addresses = listener.interface_listen_addresses(interface_address);
+        assert_eq!(addresses.len(), 1);
+
+        let mut iter = addresses[0].iter();
+        assert_eq!(iter.next(), Some(Protocol::Ip4(Ipv4Addr::new(10, 0, 0, 1))));
+        assert!(std::matches!(iter.next(), Some(Protocol::Tcp(port)) if port != 0));
+
+        // ipv6 listener is bound only to loopback
+        assert!(listener
+            .interface_listen_addresses(IpAddr::V6(Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 1)))
+            .is_empty());
+    }
+
     #[tokio::test]
     async fn two_listeners() {
         let address1: Multiaddr = "/ip6/::1/tcp/0".parse().unwrap();
         let address2: Multiaddr = "/ip4/127.0.0.1/tcp/0".parse().unwrap();
-        let (mut listener, listen_addresses, _) = TcpListener::new(vec![address1, address2]);
+        let (mut listener, listen_addresses, _) = TcpListener::new(vec![address1, address2], 1024);
         let Some(Protocol::Tcp(port1)) =
             listen_addresses.iter().next().unwrap().clone().iter().skip(1).next()
         else {
@@ -396,7 +442,7 @@ mod tests {
     async fn show_all_addresses() {
         let address1: Multiaddr = "/ip6/::/tcp/0".parse().unwrap();
         let address2: Multiaddr = "/ip4/0.0.0.0/tcp/0".parse().unwrap();
-        let (_, listen_addresses, _) = TcpListener::new(vec![address1, address2]);
+        let (_, listen_addresses, _) = TcpListener::new(vec![address1, address2], 1024);
 
         println!("{listen_addresses:#?}");
     }
@@ -25,31 +25,31 @@ use crate::{
     config::Role,
     error::Error,
     transport::{
+        dns::DnsResolver,
+        interfaces::{InterfaceEvent, InterfaceMonitor},
         manager::TransportHandle,
         tcp::{
             config::Config,
             connection::{NegotiatedConnection, TcpConnection},
             listener::{AddressType, DialAddresses, TcpListener},
         },
-        Transport, TransportBuilder, TransportEvent,
+        RawConnection, Transport, TransportBuilder, TransportEvent,
     },
     types::ConnectionId,
+    PeerId,
 };
 
 use futures::{
     future::BoxFuture,
     stream::{FuturesUnordered, Stream, StreamExt},
+    AsyncRead, AsyncWrite,
 };
 use multiaddr::{Multiaddr, Protocol};
 use socket2::{Domain, Socket, Type};
 use tokio::net::TcpStream;
-use trust_dns_resolver::{
-    config::{ResolverConfig, ResolverOpts},
-    TokioAsyncResolver,
-};
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     net::SocketAddr,
     pin::Pin,
     task::{Context, Poll},
@@ -90,7 +90,10 @@ pub(crate) struct TcpTransport {
 
     /// Pending raw, unnegotiated connections.
     pending_raw_connections: FuturesUnordered<
-        BoxFuture<'static, Result<(ConnectionId, Multiaddr, TcpStream), ConnectionId>>,
+        BoxFuture<
+            'static,
+            Result<(ConnectionId, Multiaddr, TcpStream), (ConnectionId, Vec<(Multiaddr, Error)>)>,
+        >,
     >,
 
     /// Opened raw connection, waiting for approval/rejection from `TransportManager`.
@@ -102,11 +105,36 @@ pub(crate) struct TcpTransport {
     /// Connections which have been opened and negotiated but are being validated by the
     /// `TransportManager`.
     pending_open: HashMap<ConnectionId, NegotiatedConnection>,
+
+    /// Injected connections which are being validated by the `TransportManager`.
+    pending_injected: HashMap<ConnectionId, NegotiatedConnection<Box<dyn RawConnection>>>,
+
+    /// Inbound connections which are being negotiated.
+    pending_inbound: HashSet<ConnectionId>,
+
+    /// Network interface monitor, if the transport listens on all interfaces.
+    interface_monitor: Option<InterfaceMonitor>,
+
+    /// Pending events.
+    pending_events: VecDeque<TransportEvent>,
 }
 
 impl TcpTransport {
     /// Handle inbound TCP connection.
     fn on_inbound_connection(&mut self, connection: TcpStream, address: SocketAddr) {
+        let remote_address = Multiaddr::empty()
+            .with(Protocol::from(address.ip()))
+            .with(Protocol::Tcp(address.port()));
+
+        if !self.context.allow_inbound_address(&remote_address) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?address,
+                "inbound connection rejected by connection gater",
+            );
+            return;
+        }
+
         let connection_id = self.context.next_connection_id();
         let yamux_config = self.config.yamux_config.clone();
         let max_read_ahead_factor = self.config.noise_read_ahead_frame_count;
@@ -114,9 +142,12 @@ impl TcpTransport {
         let connection_open_timeout = self.config.connection_open_timeout;
         let substream_open_timeout = self.config.substream_open_timeout;
         let keypair = self.context.keypair.clone();
+        let connection_rates = self.context.connection_rates.clone();
 
+        connection_rates.on_inbound_attempt(address.ip());
+        self.pending_inbound.insert(connection_id);
         self.pending_connections.push(Box::pin(async move {
-            TcpConnection::accept_connection(
+            let result = TcpConnection::accept_connection(
                 connection,
                 connection_id,
                 keypair,
@@ -127,67 +158,102 @@ impl TcpTransport {
                 connection_open_timeout,
                 substream_open_timeout,
             )
-            .await
-            .map_err(|error| (connection_id, error))
+            .await;
+            connection_rates.on_handshake(address.ip(), result.is_ok());
+
+            result.map_err(|error| (connection_id, error))
         }));
     }
 
+    /// Poll network interface monitor and queue events for listen addresses that changed.
+    fn poll_interfaces(&mut self, cx: &mut Context<'_>) {
+        let Some(monitor) = self.interface_monitor.as_mut() else {
+            return;
+        };
+
+        while let Poll::Ready(Some(event)) = monitor.poll_next_unpin(cx) {
+            match event {
+                InterfaceEvent::Up(address) => self.pending_events.extend(
+                    self.listener
+                        .interface_listen_addresses(address)
+                        .into_iter()
+                        .map(|address| TransportEvent::NewListenAddress { address }),
+                ),
+                InterfaceEvent::Down(address) => self.pending_events.extend(
+                    self.listener
+                        .interface_listen_addresses(address)
+                        .into_iter()
+                        .map(|address| TransportEvent::ExpiredListenAddress { address }),
+                ),
+            }
+        }
+    }
+
+    /// Check if the number of inbound connections being negotiated allows accepting a new
+    /// connection.
+    fn can_accept_inbound(&self) -> bool {
+        match self.config.max_pending_inbound_connections {
+            Some(max) => self.pending_inbound.len() < max,
+            None => true,
+        }
+    }
+
+    /// Start the event loop of an accepted connection.
+    fn start_connection<S>(&self, context: NegotiatedConnection<S>)
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let connection_id = context.connection_id();
+        let protocol_set = self.context.protocol_set(connection_id);
+        let bandwidth_sink = self.context.bandwidth_sink.clone();
+        let next_substream_id = self.context.next_substream_id.clone();
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            ?connection_id,
+            "start connection",
+        );
+
+        self.context.executor.run_with_name(
+            "litep2p-tcp-connection",
+            Box::pin(async move {
+                if let Err(error) =
+                    TcpConnection::new(context, protocol_set, bandwidth_sink, next_substream_id)
+                        .start()
+                        .await
+                {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?connection_id,
+                        ?error,
+                        "connection exited with error",
+                    );
+                }
+            }),
+        );
+    }
+
     /// Dial remote peer
     async fn dial_peer(
         address: Multiaddr,
         dial_addresses: DialAddresses,
         connection_open_timeout: Duration,
         disable_port_reuse: bool,
+        dns_resolver: DnsResolver,
     ) -> crate::Result<(Multiaddr, TcpStream)> {
         let (socket_address, _) = TcpListener::get_socket_address(&address)?;
         let remote_address = match socket_address {
             AddressType::Socket(address) => address,
-            AddressType::Dns(url, port) => {
-                let address = address.clone();
-                let future = async move {
-                    match TokioAsyncResolver::tokio(
-                        ResolverConfig::default(),
-                        ResolverOpts::default(),
-                    )
-                    .lookup_ip(url.clone())
-                    .await
-                    {
-                        // TODO: ugly
-                        Ok(lookup) => {
-                            let mut iter = lookup.iter();
-                            while let Some(ip) = iter.next() {
-                                match (
-                                    address.iter().next().expect("protocol to exist"),
-                                    ip.is_ipv4(),
-                                ) {
-                                    (Protocol::Dns(_), true)
-                                    | (Protocol::Dns4(_), true)
-                                    | (Protocol::Dns6(_), false) => {
-                                        tracing::trace!(
-                                            target: LOG_TARGET,
-                                            ?address,
-                                            ?ip,
-                                            "address resolved",
-                                        );
-
-                                        return Ok(SocketAddr::new(ip, port));
-                                    }
-                                    _ => {}
-                                }
-                            }
-
-                            Err(Error::Unknown)
-                        }
-                        Err(_) => Err(Error::Unknown),
-                    }
-                };
-
-                match tokio::time::timeout(connection_open_timeout, future).await {
-                    Err(_) => return Err(Error::Timeout),
-                    Ok(Err(error)) => return Err(error),
-                    Ok(Ok(address)) => address,
-                }
-            }
+            AddressType::Dns(url, port) => match tokio::time::timeout(
+                connection_open_timeout,
+                dns_resolver.resolve(&address, url, port),
+            )
+            .await
+            {
+                Err(_) => return Err(Error::Timeout),
+                Ok(Err(error)) => return Err(error),
+                Ok(Ok(address)) => address,
+            },
         };
 
         let domain = match remote_address.is_ipv4() {
@@ -261,8 +327,18 @@ impl TransportBuilder for TcpTransport {
         );
 
         // start tcp listeners for all listen addresses
-        let (listener, listen_addresses, dial_addresses) =
-            TcpListener::new(std::mem::replace(&mut config.listen_addresses, Vec::new()));
+        let (listener, listen_addresses, dial_addresses) = TcpListener::new(
+            std::mem::replace(&mut config.listen_addresses, Vec::new()),
+            config.listen_backlog,
+        );
+
+        let interface_monitor = match (
+            config.interface_poll_interval,
+            listener.has_wildcard_listener(),
+        ) {
+            (Some(poll_interval), true) => Some(InterfaceMonitor::new(poll_interval)),
+            _ => None,
+        };
 
         Ok((
             Self {
@@ -273,6 +349,10 @@ impl TransportBuilder for TcpTransport {
                 canceled: HashSet::new(),
                 opened_raw: HashMap::new(),
                 pending_open: HashMap::new(),
+                pending_injected: HashMap::new(),
+                pending_inbound: HashSet::new(),
+                interface_monitor,
+                pending_events: VecDeque::new(),
                 pending_dials: HashMap::new(),
                 pending_connections: FuturesUnordered::new(),
                 pending_raw_connections: FuturesUnordered::new(),
@@ -294,6 +374,7 @@ impl Transport for TcpTransport {
         let substream_open_timeout = self.config.substream_open_timeout;
         let disable_port_reuse = self.config.disable_port_reuse;
         let dial_addresses = self.dial_addresses.clone();
+        let dns_resolver = self.context.dns_resolver.clone();
         let keypair = self.context.keypair.clone();
 
         self.pending_dials.insert(connection_id, address.clone());
@@ -303,6 +384,7 @@ impl Transport for TcpTransport {
                 dial_addresses,
                 connection_open_timeout,
                 disable_port_reuse,
+                dns_resolver,
             )
             .await
             .map_err(|error| (connection_id, error))?;
@@ -327,45 +409,55 @@ impl Transport for TcpTransport {
     }
 
     fn accept(&mut self, connection_id: ConnectionId) -> crate::Result<()> {
+        if let Some(context) = self.pending_injected.remove(&connection_id) {
+            self.start_connection(context);
+            return Ok(());
+        }
+
         let context = self
             .pending_open
             .remove(&connection_id)
             .ok_or(Error::ConnectionDoesntExist(connection_id))?;
-        let protocol_set = self.context.protocol_set(connection_id);
-        let bandwidth_sink = self.context.bandwidth_sink.clone();
-        let next_substream_id = self.context.next_substream_id.clone();
-
-        tracing::trace!(
-            target: LOG_TARGET,
-            ?connection_id,
-            "start connection",
-        );
-
-        self.context.executor.run(Box::pin(async move {
-            if let Err(error) =
-                TcpConnection::new(context, protocol_set, bandwidth_sink, next_substream_id)
-                    .start()
-                    .await
-            {
-                tracing::debug!(
-                    target: LOG_TARGET,
-                    ?connection_id,
-                    ?error,
-                    "connection exited with error",
-                );
-            }
-        }));
+        self.start_connection(context);
 
         Ok(())
     }
 
     fn reject(&mut self, connection_id: ConnectionId) -> crate::Result<()> {
+        if self.pending_injected.remove(&connection_id).is_some() {
+            return Ok(());
+        }
+
         self.canceled.insert(connection_id);
         self.pending_open
             .remove(&connection_id)
             .map_or(Err(Error::ConnectionDoesntExist(connection_id)), |_| Ok(()))
     }
 
+    fn inject(
+        &mut self,
+        connection_id: ConnectionId,
+        peer: PeerId,
+        connection: Box<dyn RawConnection>,
+    ) -> crate::Result<()> {
+        tracing::trace!(target: LOG_TARGET, ?connection_id, ?peer, "inject connection");
+
+        let context = NegotiatedConnection::injected(
+            connection,
+            peer,
+            connection_id,
+            self.config.yamux_config.clone(),
+            self.config.substream_open_timeout,
+        );
+        let endpoint = context.endpoint();
+
+        self.pending_injected.insert(connection_id, context);
+        self.pending_events
+            .push_back(TransportEvent::ConnectionEstablished { peer, endpoint });
+
+        Ok(())
+    }
+
     fn open(
         &mut self,
         connection_id: ConnectionId,
@@ -377,33 +469,42 @@ impl Transport for TcpTransport {
                 let dial_addresses = self.dial_addresses.clone();
                 let connection_open_timeout = self.config.connection_open_timeout;
                 let disable_port_reuse = self.config.disable_port_reuse;
+                let dns_resolver = self.context.dns_resolver.clone();
 
                 async move {
                     TcpTransport::dial_peer(
-                        address,
+                        address.clone(),
                         dial_addresses,
                         connection_open_timeout,
                         disable_port_reuse,
+                        dns_resolver,
                     )
                     .await
+                    .map_err(|error| (address, error))
                 }
             })
             .collect();
 
         self.pending_raw_connections.push(Box::pin(async move {
+            let mut errors = Vec::with_capacity(futures.len());
+
             while let Some(result) = futures.next().await {
                 match result {
                     Ok((address, stream)) => return Ok((connection_id, address, stream)),
-                    Err(error) => tracing::debug!(
-                        target: LOG_TARGET,
-                        ?connection_id,
-                        ?error,
-                        "failed to open connection",
-                    ),
+                    Err((address, error)) => {
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            ?connection_id,
+                            ?address,
+                            ?error,
+                            "failed to open connection",
+                        );
+                        errors.push((address, error));
+                    }
                 }
             }
 
-            Err(connection_id)
+            Err((connection_id, errors))
         }));
 
         Ok(())
@@ -469,14 +570,29 @@ impl Stream for TcpTransport {
     type Item = TransportEvent;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        while let Poll::Ready(event) = self.listener.poll_next_unpin(cx) {
-            match event {
-                None | Some(Err(_)) => return Poll::Ready(None),
-                Some(Ok((connection, address))) => {
+        self.poll_interfaces(cx);
+
+        if let Some(event) = self.pending_events.pop_front() {
+            return Poll::Ready(Some(event));
+        }
+
+        while self.can_accept_inbound() {
+            match self.listener.poll_next_unpin(cx) {
+                Poll::Pending => break,
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Ready(Some(Err(error))) => {
+                    tracing::debug!(target: LOG_TARGET, ?error, "failed to accept connection");
+
+                    return Poll::Ready(Some(TransportEvent::ListenerError {
+                        error: error.into(),
+                    }));
+                }
+                Poll::Ready(Some(Ok((connection, address)))) => {
                     self.on_inbound_connection(connection, address);
                 }
             }
         }
+        let throttled = !self.can_accept_inbound();
 
         while let Poll::Ready(Some(result)) = self.pending_raw_connections.poll_next_unpin(cx) {
             match result {
@@ -498,9 +614,12 @@ impl Stream for TcpTransport {
                         }));
                     }
                 }
-                Err(connection_id) =>
+                Err((connection_id, errors)) =>
                     if !self.canceled.remove(&connection_id) {
-                        return Poll::Ready(Some(TransportEvent::OpenFailure { connection_id }));
+                        return Poll::Ready(Some(TransportEvent::OpenFailure {
+                            connection_id,
+                            errors,
+                        }));
                     },
             }
         }
@@ -510,6 +629,7 @@ impl Stream for TcpTransport {
                 Ok(connection) => {
                     let peer = connection.peer();
                     let endpoint = connection.endpoint();
+                    self.pending_inbound.remove(&connection.connection_id());
                     self.pending_open.insert(connection.connection_id(), connection);
 
                     return Poll::Ready(Some(TransportEvent::ConnectionEstablished {
@@ -518,6 +638,8 @@ impl Stream for TcpTransport {
                     }));
                 }
                 Err((connection_id, error)) => {
+                    self.pending_inbound.remove(&connection_id);
+
                     if let Some(address) = self.pending_dials.remove(&connection_id) {
                         return Poll::Ready(Some(TransportEvent::DialFailure {
                             connection_id,
@@ -529,6 +651,11 @@ impl Stream for TcpTransport {
             }
         }
 
+        // inbound connection failed to negotiate while the listener wasn't being polled
+        if throttled && self.can_accept_inbound() {
+            cx.waker().wake_by_ref();
+        }
+
         Poll::Pending
     }
 }
@@ -544,7 +671,6 @@ mod tests {
         types::protocol::ProtocolName,
         BandwidthSink, PeerId,
     };
-    use multiaddr::Protocol;
     use multihash::Multihash;
     use std::{collections::HashSet, sync::Arc};
     use tokio::sync::mpsc::channel;
@@ -561,6 +687,7 @@ mod tests {
         let bandwidth_sink = BandwidthSink::new();
 
         let handle1 = crate::transport::manager::TransportHandle {
+            transport: SupportedTransport::Tcp,
             executor: Arc::new(DefaultExecutor {}),
             protocol_names: Vec::new(),
             next_substream_id: Default::default(),
@@ -568,15 +695,26 @@ mod tests {
             keypair: keypair1.clone(),
             tx: event_tx1,
             bandwidth_sink: bandwidth_sink.clone(),
+            dns_resolver: Default::default(),
+            connection_rates: Default::default(),
+            connection_gater: None,
+            quic_connections: Default::default(),
+            max_connection_lifetime: None,
+            limits: Default::default(),
+            reserved_peers: Default::default(),
+            slow_protocol_policy: Default::default(),
+            channel_size: 64,
 
             protocols: HashMap::from_iter([(
                 ProtocolName::from("/notif/1"),
                 ProtocolContext {
                     tx: tx1,
                     codec: ProtocolCodec::Identity(32),
+                    stream_priority: 0,
                     fallback_names: Vec::new(),
                 },
-            )]),
+            )])
+            .into(),
         };
         let transport_config1 = Config {
             listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
@@ -592,6 +730,7 @@ mod tests {
         let (event_tx2, _event_rx2) = channel(64);
 
         let handle2 = crate::transport::manager::TransportHandle {
+            transport: SupportedTransport::Tcp,
             executor: Arc::new(DefaultExecutor {}),
             protocol_names: Vec::new(),
             next_substream_id: Default::default(),
@@ -599,15 +738,26 @@ mod tests {
             keypair: keypair2.clone(),
             tx: event_tx2,
             bandwidth_sink: bandwidth_sink.clone(),
+            dns_resolver: Default::default(),
+            connection_rates: Default::default(),
+            connection_gater: None,
+            quic_connections: Default::default(),
+            max_connection_lifetime: None,
+            limits: Default::default(),
+            reserved_peers: Default::default(),
+            slow_protocol_policy: Default::default(),
+            channel_size: 64,
 
             protocols: HashMap::from_iter([(
                 ProtocolName::from("/notif/1"),
                 ProtocolContext {
                     tx: tx2,
                     codec: ProtocolCodec::Identity(32),
+                    stream_priority: 0,
                     fallback_names: Vec::new(),
                 },
-            )]),
+            )])
+            .into(),
         };
         let transport_config2 = Config {
             listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
@@ -629,6 +779,62 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn pending_inbound_connections_limited() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        let (event_tx, _event_rx) = channel(64);
+        let handle = crate::transport::manager::TransportHandle {
+            transport: SupportedTransport::Tcp,
+            executor: Arc::new(DefaultExecutor {}),
+            protocol_names: Vec::new(),
+            next_substream_id: Default::default(),
+            next_connection_id: Default::default(),
+            keypair: Keypair::generate(),
+            tx: event_tx,
+            bandwidth_sink: BandwidthSink::new(),
+            dns_resolver: Default::default(),
+            connection_rates: Default::default(),
+            connection_gater: None,
+            quic_connections: Default::default(),
+            max_connection_lifetime: None,
+            limits: Default::default(),
+            reserved_peers: Default::default(),
+            slow_protocol_policy: Default::default(),
+            channel_size: 64,
+            protocols: HashMap::new().into(),
+        };
+        let transport_config = Config {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            max_pending_inbound_connections: Some(1),
+            ..Default::default()
+        };
+
+        let (mut transport, listen_addresses) =
+            TcpTransport::new(handle, transport_config).unwrap();
+        let port = match listen_addresses[0].iter().nth(1) {
+            Some(Protocol::Tcp(port)) => port,
+            _ => panic!("invalid listen address"),
+        };
+
+        // open two connections which never start the noise handshake
+        let _stream1 = TcpStream::connect(("::1", port)).await.unwrap();
+        let _stream2 = TcpStream::connect(("::1", port)).await.unwrap();
+
+        for _ in 0..5 {
+            futures::future::poll_fn(|cx| {
+                let _ = transport.poll_next_unpin(cx);
+                Poll::Ready(())
+            })
+            .await;
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+
+        assert_eq!(transport.pending_inbound.len(), 1);
+    }
+
     #[tokio::test]
     async fn dial_failure() {
         let _ = tracing_subscriber::fmt()
@@ -641,6 +847,7 @@ mod tests {
         let bandwidth_sink = BandwidthSink::new();
 
         let handle1 = crate::transport::manager::TransportHandle {
+            transport: SupportedTransport::Tcp,
             executor: Arc::new(DefaultExecutor {}),
             protocol_names: Vec::new(),
             next_substream_id: Default::default(),
@@ -648,15 +855,26 @@ mod tests {
             keypair: keypair1.clone(),
             tx: event_tx1,
             bandwidth_sink: bandwidth_sink.clone(),
+            dns_resolver: Default::default(),
+            connection_rates: Default::default(),
+            connection_gater: None,
+            quic_connections: Default::default(),
+            max_connection_lifetime: None,
+            limits: Default::default(),
+            reserved_peers: Default::default(),
+            slow_protocol_policy: Default::default(),
+            channel_size: 64,
 
             protocols: HashMap::from_iter([(
                 ProtocolName::from("/notif/1"),
                 ProtocolContext {
                     tx: tx1,
                     codec: ProtocolCodec::Identity(32),
+                    stream_priority: 0,
                     fallback_names: Vec::new(),
                 },
-            )]),
+            )])
+            .into(),
         };
         let (mut transport1, _) = TcpTransport::new(handle1, Default::default()).unwrap();
 
@@ -668,6 +886,16 @@ mod tests {
                     TransportEvent::DialFailure { .. } => {}
                     TransportEvent::ConnectionOpened { .. } => {}
                     TransportEvent::OpenFailure { .. } => {}
+                    TransportEvent::QuicPathStats { .. } => {}
+                    TransportEvent::IncomingConnection { .. } => {}
+                    TransportEvent::NewListenAddress { .. } => {}
+                    TransportEvent::ExpiredListenAddress { .. } => {}
+                    TransportEvent::ConnectionRateExceeded { .. } => {}
+                    TransportEvent::PeerBanned { .. } => {}
+                    TransportEvent::ConnectionRejected { .. } => {}
+                    TransportEvent::ListenerError { .. } => {}
+                    TransportEvent::TransportFailed { .. } => {}
+                    TransportEvent::BootstrapCompleted { .. } => {}
                 }
             }
         });
@@ -677,6 +905,7 @@ mod tests {
         let (event_tx2, _event_rx2) = channel(64);
 
         let handle2 = crate::transport::manager::TransportHandle {
+            transport: SupportedTransport::Tcp,
             executor: Arc::new(DefaultExecutor {}),
             protocol_names: Vec::new(),
             next_substream_id: Default::default(),
@@ -684,15 +913,26 @@ mod tests {
             keypair: keypair2.clone(),
             tx: event_tx2,
             bandwidth_sink: bandwidth_sink.clone(),
+            dns_resolver: Default::default(),
+            connection_rates: Default::default(),
+            connection_gater: None,
+            quic_connections: Default::default(),
+            max_connection_lifetime: None,
+            limits: Default::default(),
+            reserved_peers: Default::default(),
+            slow_protocol_policy: Default::default(),
+            channel_size: 64,
 
             protocols: HashMap::from_iter([(
                 ProtocolName::from("/notif/1"),
                 ProtocolContext {
                     tx: tx2,
                     codec: ProtocolCodec::Identity(32),
+                    stream_priority: 0,
                     fallback_names: Vec::new(),
                 },
-            )]),
+            )])
+            .into(),
         };
 
         let (mut transport2, _) = TcpTransport::new(handle2, Default::default()).unwrap();
@@ -734,7 +974,8 @@ mod tests {
             BandwidthSink::new(),
             8usize,
         );
-        let handle = manager.transport_handle(Arc::new(DefaultExecutor {}));
+        let handle =
+            manager.transport_handle(SupportedTransport::Tcp, Arc::new(DefaultExecutor {}));
         manager.register_transport(
             SupportedTransport::Tcp,
             Box::new(crate::transport::dummy::DummyTransport::new()),
@@ -756,7 +997,7 @@ mod tests {
             .with(Protocol::P2p(
                 Multihash::from_bytes(&peer_id.to_bytes()).unwrap(),
             ));
-        manager.dial_address(multiaddr.clone()).await.unwrap();
+        manager.dial_address(multiaddr.clone()).unwrap();
 
         assert!(transport.pending_dials.is_empty());
 
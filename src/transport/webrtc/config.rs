@@ -23,8 +23,11 @@
 use multiaddr::Multiaddr;
 
 /// WebRTC transport configuration.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Config {
     /// WebRTC listening address.
+    ///
+    /// The address reported by litep2p contains `/certhash` of the DTLS certificate used by the
+    /// transport so it doesn't have to be specified here.
     pub listen_addresses: Vec<Multiaddr>,
 }
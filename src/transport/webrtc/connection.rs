@@ -28,6 +28,7 @@ use crate::{
     protocol::{Direction, Permit, ProtocolCommand, ProtocolSet},
     substream::Substream,
     transport::{
+        manager::TransportHandle,
         webrtc::{
             substream::SubstreamBackend,
             util::{SubstreamContext, WebRtcMessage},
@@ -154,8 +155,11 @@ pub(super) struct WebRtcConnection {
     /// Connection state.
     state: State,
 
-    /// Protocol set.
-    protocol_set: ProtocolSet,
+    /// Transport context.
+    context: Arc<TransportHandle>,
+
+    /// Protocol set, created once the remote peer has completed the Noise handshake.
+    protocol_set: Option<ProtocolSet>,
 
     /// Peer address
     peer_address: SocketAddr,
@@ -188,7 +192,7 @@ impl WebRtcConnection {
         connection_id: ConnectionId,
         _noise_channel_id: ChannelId,
         id_keypair: Keypair,
-        protocol_set: ProtocolSet,
+        context: Arc<TransportHandle>,
         peer_address: SocketAddr,
         local_address: SocketAddr,
         socket: Arc<UdpSocket>,
@@ -198,7 +202,7 @@ impl WebRtcConnection {
             rtc,
             socket,
             dgram_rx,
-            protocol_set,
+            context,
             id_keypair,
             peer_address,
             local_address,
@@ -209,6 +213,17 @@ impl WebRtcConnection {
             backend: SubstreamBackend::new(),
             substream_id: SubstreamId::new(),
             pending_outbound: HashMap::new(),
+            protocol_set: None,
+        }
+    }
+
+    /// Get next command from the protocol set.
+    ///
+    /// Pending until the protocol set has been created.
+    async fn next_command(protocol_set: &mut Option<ProtocolSet>) -> Option<ProtocolCommand> {
+        match protocol_set {
+            Some(protocol_set) => protocol_set.next().await,
+            None => futures::future::pending().await,
         }
     }
 
@@ -439,12 +454,15 @@ impl WebRtcConnection {
             .with(Protocol::Certhash(certificate))
             .with(Protocol::P2p(PeerId::from(public_key).into()));
 
-        self.protocol_set
+        // protocols are informed of the connection only after the remote peer has authenticated
+        let mut protocol_set = self.context.protocol_set(self.connection_id);
+        protocol_set
             .report_connection_established(
                 remote_peer_id,
                 Endpoint::listener(address, self.connection_id),
             )
             .await?;
+        self.protocol_set = Some(protocol_set);
 
         self.state = State::Open {
             peer: remote_peer_id,
@@ -494,8 +512,8 @@ impl WebRtcConnection {
 
         let payload = WebRtcMessage::decode(&d.data)?.payload.ok_or(Error::InvalidData)?;
 
-        let (protocol, response) =
-            listener_negotiate(&mut self.protocol_set.protocols().iter(), payload.into())?;
+        let protocols = self.protocol_set.as_mut().ok_or(Error::InvalidState)?.protocols();
+        let (protocol, response) = listener_negotiate(&mut protocols.iter(), payload.into())?;
 
         let message = WebRtcMessage::encode(response.to_vec(), None);
 
@@ -689,7 +707,7 @@ impl WebRtcConnection {
                         }
                     }
                 }
-                event = self.protocol_set.next() => match event {
+                event = Self::next_command(&mut self.protocol_set) => match event {
                     Some(event) => match event {
                         ProtocolCommand::OpenSubstream { protocol, fallback_names, substream_id, permit } => {
                             self.open_substream(protocol, fallback_names, substream_id, permit);
@@ -83,7 +83,10 @@ const REMOTE_FINGERPRINT: &str =
 /// WebRTC transport.
 pub(crate) struct WebRtcTransport {
     /// Transport context.
-    context: TransportHandle,
+    ///
+    /// Shared with the connections which create their protocol set only after the remote peer
+    /// has completed the Noise handshake.
+    context: Arc<TransportHandle>,
 
     /// UDP socket.
     socket: Arc<UdpSocket>,
@@ -99,6 +102,25 @@ pub(crate) struct WebRtcTransport {
 }
 
 impl WebRtcTransport {
+    /// Create the advertised listen address for `listen_address`.
+    ///
+    /// The address contains the `/certhash` of `dtls_cert` which browsers need in order to
+    /// verify the certificate of the listener. It must be recomputed whenever the certificate
+    /// used by the transport changes.
+    fn listen_multi_address(listen_address: SocketAddr, dtls_cert: &DtlsCert) -> Multiaddr {
+        const MULTIHASH_SHA256_CODE: u64 = 0x12;
+
+        let fingerprint = dtls_cert.fingerprint().bytes;
+        let certificate = Multihash::wrap(MULTIHASH_SHA256_CODE, &fingerprint)
+            .expect("fingerprint's len to be 32 bytes");
+
+        Multiaddr::empty()
+            .with(Protocol::from(listen_address.ip()))
+            .with(Protocol::Udp(listen_address.port()))
+            .with(Protocol::WebRTC)
+            .with(Protocol::Certhash(certificate))
+    }
+
     /// Extract socket address and `PeerId`, if found, from `address`.
     fn get_socket_address(address: &Multiaddr) -> crate::Result<(SocketAddr, Option<PeerId>)> {
         tracing::trace!(target: LOG_TARGET, ?address, "parse multi address");
@@ -223,6 +245,20 @@ impl WebRtcTransport {
 
         match contents {
             DatagramRecv::Stun(message) => {
+                let address = Multiaddr::empty()
+                    .with(Protocol::from(source.ip()))
+                    .with(Protocol::Udp(source.port()))
+                    .with(Protocol::WebRTC);
+
+                if !self.context.allow_inbound_address(&address) {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?source,
+                        "inbound connection rejected by connection gater",
+                    );
+                    return Ok(());
+                }
+
                 if let Some((ufrag, pass)) = message.split_username() {
                     tracing::debug!(
                         target: LOG_TARGET,
@@ -258,16 +294,19 @@ impl WebRtcTransport {
                         connection_id,
                         noise_channel_id,
                         self.context.keypair.clone(),
-                        self.context.protocol_set(connection_id),
+                        Arc::clone(&self.context),
                         source,
                         self.listen_address,
                         Arc::clone(&self.socket),
                         rx,
                     );
 
-                    self.context.executor.run(Box::pin(async move {
-                        let _ = connection.run().await;
-                    }));
+                    self.context.executor.run_with_name(
+                        "litep2p-webrtc-connection",
+                        Box::pin(async move {
+                            let _ = connection.run().await;
+                        }),
+                    );
                     self.peers.insert(source, tx);
                 }
             }
@@ -324,23 +363,11 @@ impl TransportBuilder for WebRtcTransport {
         let listen_address = socket.local_addr()?;
         let dtls_cert = DtlsCert::new();
 
-        let listen_multi_addresses = {
-            let fingerprint = dtls_cert.fingerprint().bytes;
-
-            const MULTIHASH_SHA256_CODE: u64 = 0x12;
-            let certificate = Multihash::wrap(MULTIHASH_SHA256_CODE, &fingerprint)
-                .expect("fingerprint's len to be 32 bytes");
-
-            vec![Multiaddr::empty()
-                .with(Protocol::from(listen_address.ip()))
-                .with(Protocol::Udp(listen_address.port()))
-                .with(Protocol::WebRTC)
-                .with(Protocol::Certhash(certificate))]
-        };
+        let listen_multi_addresses = vec![Self::listen_multi_address(listen_address, &dtls_cert)];
 
         Ok((
             Self {
-                context,
+                context: Arc::new(context),
                 dtls_cert,
                 listen_address,
                 peers: HashMap::new(),
@@ -22,11 +22,11 @@
 
 use crate::{
     crypto::noise::{MAX_READ_AHEAD_FACTOR, MAX_WRITE_BUFFER_SIZE},
-    transport::{CONNECTION_OPEN_TIMEOUT, SUBSTREAM_OPEN_TIMEOUT},
+    transport::{CONNECTION_OPEN_TIMEOUT, INTERFACE_POLL_INTERVAL, SUBSTREAM_OPEN_TIMEOUT},
 };
 
 /// WebSocket transport configuration.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Config {
     /// Listen address address for the transport.
     ///
@@ -67,6 +67,15 @@ pub struct Config {
     /// How long should litep2p wait for a substream to be opened before considering
     /// the substream rejected.
     pub substream_open_timeout: std::time::Duration,
+
+    /// Network interface poll interval.
+    ///
+    /// If the transport listens on all network interfaces (`0.0.0.0` or `::`), the interfaces are
+    /// polled at this interval and listen addresses are added and removed as addresses appear on
+    /// and disappear from the interfaces. Polling is disabled if set to `None`.
+    ///
+    /// By default the interfaces are polled every 10 seconds.
+    pub interface_poll_interval: Option<std::time::Duration>,
 }
 
 impl Default for Config {
@@ -81,6 +90,7 @@ impl Default for Config {
             noise_write_buffer_size: MAX_WRITE_BUFFER_SIZE,
             connection_open_timeout: CONNECTION_OPEN_TIMEOUT,
             substream_open_timeout: SUBSTREAM_OPEN_TIMEOUT,
+            interface_poll_interval: Some(INTERFACE_POLL_INTERVAL),
         }
     }
 }
@@ -29,8 +29,9 @@ use crate::{
     protocol::{Direction, Permit, ProtocolCommand, ProtocolSet},
     substream,
     transport::{
+        muxer::{Muxer, MuxerEvent, OutboundSubstream},
         websocket::{stream::BufferedStream, substream::Substream},
-        Endpoint,
+        DisconnectReason, Endpoint,
     },
     types::{protocol::ProtocolName, ConnectionId, SubstreamId},
     BandwidthSink, PeerId,
@@ -106,11 +107,7 @@ pub(super) struct NegotiatedConnection {
     endpoint: Endpoint,
 
     /// Yamux connection.
-    connection:
-        crate::yamux::ControlledConnection<NoiseSocket<BufferedStream<MaybeTlsStream<TcpStream>>>>,
-
-    /// Yamux control.
-    control: crate::yamux::Control,
+    connection: crate::yamux::Connection<NoiseSocket<BufferedStream<MaybeTlsStream<TcpStream>>>>,
 }
 
 impl NegotiatedConnection {
@@ -136,11 +133,7 @@ pub(crate) struct WebSocketConnection {
     protocol_set: ProtocolSet,
 
     /// Yamux connection.
-    connection:
-        crate::yamux::ControlledConnection<NoiseSocket<BufferedStream<MaybeTlsStream<TcpStream>>>>,
-
-    /// Yamux control.
-    control: crate::yamux::Control,
+    muxer: Muxer<NoiseSocket<BufferedStream<MaybeTlsStream<TcpStream>>>>,
 
     /// Remote peer ID.
     peer: PeerId,
@@ -174,14 +167,12 @@ impl WebSocketConnection {
             peer,
             endpoint,
             connection,
-            control,
         } = connection;
 
         Self {
             connection_id: endpoint.connection_id(),
             protocol_set,
-            connection,
-            control,
+            muxer: Muxer::new(connection),
             peer,
             endpoint,
             bandwidth_sink,
@@ -323,7 +314,6 @@ impl WebSocketConnection {
         tracing::trace!(target: LOG_TARGET, "`yamux` negotiated");
 
         let connection = crate::yamux::Connection::new(stream.inner(), yamux_config, role.into());
-        let (control, connection) = crate::yamux::Control::new(connection);
 
         let address = match role {
             Role::Dialer => address,
@@ -332,7 +322,6 @@ impl WebSocketConnection {
 
         Ok(NegotiatedConnection {
             peer,
-            control,
             connection,
             endpoint: match role {
                 Role::Dialer => Endpoint::dialer(address, connection_id),
@@ -372,31 +361,15 @@ impl WebSocketConnection {
         })
     }
 
-    /// Open substream for `protocol`.
+    /// Negotiate `protocol` for an opened outbound substream.
     pub async fn open_substream(
-        mut control: crate::yamux::Control,
+        stream: crate::yamux::Stream,
         permit: Permit,
         substream_id: SubstreamId,
         protocol: ProtocolName,
         fallback_names: Vec<ProtocolName>,
     ) -> crate::Result<NegotiatedSubstream> {
-        tracing::debug!(target: LOG_TARGET, ?protocol, ?substream_id, "open substream");
-
-        let stream = match control.open_stream().await {
-            Ok(stream) => {
-                tracing::trace!(target: LOG_TARGET, ?substream_id, "substream opened");
-                stream
-            }
-            Err(error) => {
-                tracing::debug!(
-                    target: LOG_TARGET,
-                    ?substream_id,
-                    ?error,
-                    "failed to open substream"
-                );
-                return Err(Error::YamuxError(Direction::Outbound(substream_id), error));
-            }
-        };
+        tracing::trace!(target: LOG_TARGET, ?protocol, ?substream_id, "negotiate outbound substream");
 
         // TODO: protocols don't change after they've been initialized so this should be done only
         // once
@@ -423,11 +396,22 @@ impl WebSocketConnection {
 
         loop {
             tokio::select! {
-                substream = self.connection.next() => match substream {
-                    Some(Ok(stream)) => {
+                event = self.muxer.next() => match event {
+                    Some(Ok(MuxerEvent::Inbound(stream))) => {
                         let substream = self.protocol_set.next_substream_id();
                         let protocols = self.protocol_set.protocols();
-                        let permit = self.protocol_set.try_get_permit().ok_or(Error::ConnectionClosed)?;
+                        // protocols have released the connection
+                        let Some(permit) = self.protocol_set.try_get_permit() else {
+                            return self.protocol_set.report_connection_closed(self.peer, self.connection_id, DisconnectReason::Inactive).await;
+                        };
+                        if self.protocol_set.substream_limit_exceeded() {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                peer = ?self.peer,
+                                "substream limit reached, rejecting inbound substream",
+                            );
+                            continue;
+                        }
                         let substream_open_timeout = self.substream_open_timeout;
 
                         self.pending_substreams.push(Box::pin(async move {
@@ -450,6 +434,57 @@ impl WebSocketConnection {
                             }
                         }));
                     },
+                    Some(Ok(MuxerEvent::Outbound { stream, substream })) => {
+                        let OutboundSubstream { protocol, fallback_names, substream_id, permit } = substream;
+                        let substream_open_timeout = self.substream_open_timeout;
+
+                        tracing::trace!(target: LOG_TARGET, ?protocol, ?substream_id, "substream opened");
+
+                        self.pending_substreams.push(Box::pin(async move {
+                            match tokio::time::timeout(
+                                substream_open_timeout,
+                                Self::open_substream(
+                                    stream,
+                                    permit,
+                                    substream_id,
+                                    protocol.clone(),
+                                    fallback_names
+                                ),
+                            )
+                            .await
+                            {
+                                Ok(Ok(substream)) => Ok(substream),
+                                Ok(Err(error)) => Err(ConnectionError::FailedToNegotiate {
+                                    protocol: Some(protocol),
+                                    substream_id: Some(substream_id),
+                                    error,
+                                }),
+                                Err(_) => Err(ConnectionError::Timeout {
+                                    protocol: Some(protocol),
+                                    substream_id: Some(substream_id)
+                                }),
+                            }
+                        }));
+                    }
+                    Some(Ok(MuxerEvent::OutboundFailure { substream, error })) => {
+                        let OutboundSubstream { protocol, substream_id, .. } = substream;
+
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            ?protocol,
+                            ?substream_id,
+                            ?error,
+                            "failed to open substream"
+                        );
+
+                        self.protocol_set
+                            .report_substream_open_failure(
+                                protocol,
+                                substream_id,
+                                Error::YamuxError(Direction::Outbound(substream_id), error),
+                            )
+                            .await?;
+                    }
                     Some(Err(error)) => {
                         tracing::debug!(
                             target: LOG_TARGET,
@@ -457,13 +492,13 @@ impl WebSocketConnection {
                             ?error,
                             "connection closed with error"
                         );
-                        self.protocol_set.report_connection_closed(self.peer, self.connection_id).await?;
+                        self.protocol_set.report_connection_closed(self.peer, self.connection_id, DisconnectReason::from(&error)).await?;
 
                         return Ok(())
                     }
                     None => {
                         tracing::debug!(target: LOG_TARGET, peer = ?self.peer, "connection closed");
-                        self.protocol_set.report_connection_closed(self.peer, self.connection_id).await?;
+                        self.protocol_set.report_connection_closed(self.peer, self.connection_id, DisconnectReason::RemoteClosed).await?;
 
                         return Ok(())
                     }
@@ -516,9 +551,6 @@ impl WebSocketConnection {
                 }
                 protocol = self.protocol_set.next() => match protocol {
                     Some(ProtocolCommand::OpenSubstream { protocol, fallback_names, substream_id, permit }) => {
-                        let control = self.control.clone();
-                        let substream_open_timeout = self.substream_open_timeout;
-
                         tracing::trace!(
                             target: LOG_TARGET,
                             ?protocol,
@@ -526,31 +558,12 @@ impl WebSocketConnection {
                             "open substream"
                         );
 
-                        self.pending_substreams.push(Box::pin(async move {
-                            match tokio::time::timeout(
-                                substream_open_timeout,
-                                Self::open_substream(
-                                    control,
-                                    permit,
-                                    substream_id,
-                                    protocol.clone(),
-                                    fallback_names
-                                ),
-                            )
-                            .await
-                            {
-                                Ok(Ok(substream)) => Ok(substream),
-                                Ok(Err(error)) => Err(ConnectionError::FailedToNegotiate {
-                                    protocol: Some(protocol),
-                                    substream_id: Some(substream_id),
-                                    error,
-                                }),
-                                Err(_) => Err(ConnectionError::Timeout {
-                                    protocol: Some(protocol),
-                                    substream_id: Some(substream_id)
-                                }),
-                            }
-                        }));
+                        self.muxer.open_substream(OutboundSubstream {
+                            protocol,
+                            fallback_names,
+                            substream_id,
+                            permit,
+                        });
                     }
                     Some(ProtocolCommand::ForceClose) => {
                         tracing::debug!(
@@ -560,11 +573,11 @@ impl WebSocketConnection {
                             "force closing connection",
                         );
 
-                        return self.protocol_set.report_connection_closed(self.peer, self.connection_id).await
+                        return self.protocol_set.report_connection_closed(self.peer, self.connection_id, DisconnectReason::LocalClosed).await
                     }
                     None => {
                         tracing::debug!(target: LOG_TARGET, "protocols have exited, shutting down connection");
-                        return self.protocol_set.report_connection_closed(self.peer, self.connection_id).await
+                        return self.protocol_set.report_connection_closed(self.peer, self.connection_id, DisconnectReason::Inactive).await
                     }
                 }
             }
@@ -192,6 +192,32 @@ impl WebSocketListener {
         )
     }
 
+    /// Check if any of the listeners is bound to all network interfaces.
+    pub(super) fn has_wildcard_listener(&self) -> bool {
+        self.listeners.iter().any(|listener| match listener.local_addr() {
+            Ok(address) => address.ip().is_unspecified(),
+            Err(_) => false,
+        })
+    }
+
+    /// Get the listen addresses an interface address `address` provides for listeners that are
+    /// bound to all network interfaces of the same IP version.
+    pub(super) fn interface_listen_addresses(&self, address: IpAddr) -> Vec<Multiaddr> {
+        self.listeners
+            .iter()
+            .filter_map(|listener| listener.local_addr().ok())
+            .filter(|local_address| {
+                local_address.ip().is_unspecified() && local_address.is_ipv4() == address.is_ipv4()
+            })
+            .map(|local_address| {
+                Multiaddr::empty()
+                    .with(Protocol::from(address))
+                    .with(Protocol::Tcp(local_address.port()))
+                    .with(Protocol::Ws(std::borrow::Cow::Owned("/".to_string())))
+            })
+            .collect()
+    }
+
     /// Extract socket address and `PeerId`, if found, from `address`.
     pub(super) fn get_socket_address(
         address: &Multiaddr,
@@ -24,6 +24,8 @@ use crate::{
     config::Role,
     error::{AddressError, Error},
     transport::{
+        dns::DnsResolver,
+        interfaces::{InterfaceEvent, InterfaceMonitor},
         manager::TransportHandle,
         websocket::{
             config::Config,
@@ -41,15 +43,10 @@ use multiaddr::{Multiaddr, Protocol};
 use socket2::{Domain, Socket, Type};
 use tokio::net::TcpStream;
 use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
-use trust_dns_resolver::{
-    config::{ResolverConfig, ResolverOpts},
-    TokioAsyncResolver,
-};
 use url::Url;
 
 use std::{
-    collections::{HashMap, HashSet},
-    net::SocketAddr,
+    collections::{HashMap, HashSet, VecDeque},
     pin::Pin,
     task::{Context, Poll},
     time::Duration,
@@ -116,7 +113,7 @@ pub(crate) struct WebSocketTransport {
                     Multiaddr,
                     WebSocketStream<MaybeTlsStream<TcpStream>>,
                 ),
-                ConnectionId,
+                (ConnectionId, Vec<(Multiaddr, Error)>),
             >,
         >,
     >,
@@ -129,9 +126,38 @@ pub(crate) struct WebSocketTransport {
 
     /// Negotiated connections waiting validation.
     pending_open: HashMap<ConnectionId, NegotiatedConnection>,
+
+    /// Network interface monitor, if the transport listens on all interfaces.
+    interface_monitor: Option<InterfaceMonitor>,
+
+    /// Pending events.
+    pending_events: VecDeque<TransportEvent>,
 }
 
 impl WebSocketTransport {
+    /// Poll network interface monitor and queue events for listen addresses that changed.
+    fn poll_interfaces(&mut self, cx: &mut Context<'_>) {
+        let Some(monitor) = self.interface_monitor.as_mut() else {
+            return;
+        };
+
+        while let Poll::Ready(Some(event)) = monitor.poll_next_unpin(cx) {
+            match event {
+                InterfaceEvent::Up(address) => self.pending_events.extend(
+                    self.listener
+                        .interface_listen_addresses(address)
+                        .into_iter()
+                        .map(|address| TransportEvent::NewListenAddress { address }),
+                ),
+                InterfaceEvent::Down(address) => self.pending_events.extend(
+                    self.listener
+                        .interface_listen_addresses(address)
+                        .into_iter()
+                        .map(|address| TransportEvent::ExpiredListenAddress { address }),
+                ),
+            }
+        }
+    }
     /// Convert `Multiaddr` into `url::Url`
     fn multiaddr_into_url(address: Multiaddr) -> crate::Result<(Url, PeerId)> {
         let mut protocol_stack = address.iter();
@@ -182,58 +208,23 @@ impl WebSocketTransport {
         address: Multiaddr,
         dial_addresses: DialAddresses,
         connection_open_timeout: Duration,
+        dns_resolver: DnsResolver,
     ) -> crate::Result<(Multiaddr, WebSocketStream<MaybeTlsStream<TcpStream>>)> {
         let (url, _) = Self::multiaddr_into_url(address.clone())?;
         let (socket_address, _) = WebSocketListener::get_socket_address(&address)?;
 
         let remote_address = match socket_address {
             AddressType::Socket(address) => address,
-            AddressType::Dns(url, port) => {
-                let address = address.clone();
-                let future = async move {
-                    match TokioAsyncResolver::tokio(
-                        ResolverConfig::default(),
-                        ResolverOpts::default(),
-                    )
-                    .lookup_ip(url.clone())
-                    .await
-                    {
-                        // TODO: ugly
-                        Ok(lookup) => {
-                            let mut iter = lookup.iter();
-                            while let Some(ip) = iter.next() {
-                                match (
-                                    address.iter().next().expect("protocol to exist"),
-                                    ip.is_ipv4(),
-                                ) {
-                                    (Protocol::Dns(_), true)
-                                    | (Protocol::Dns4(_), true)
-                                    | (Protocol::Dns6(_), false) => {
-                                        tracing::trace!(
-                                            target: LOG_TARGET,
-                                            ?address,
-                                            ?ip,
-                                            "address resolved",
-                                        );
-
-                                        return Ok(SocketAddr::new(ip, port));
-                                    }
-                                    _ => {}
-                                }
-                            }
-
-                            Err(Error::Unknown)
-                        }
-                        Err(_) => Err(Error::Unknown),
-                    }
-                };
-
-                match tokio::time::timeout(connection_open_timeout, future).await {
-                    Err(_) => return Err(Error::Timeout),
-                    Ok(Err(error)) => return Err(error),
-                    Ok(Ok(address)) => address,
-                }
-            }
+            AddressType::Dns(url, port) => match tokio::time::timeout(
+                connection_open_timeout,
+                dns_resolver.resolve(&address, url, port),
+            )
+            .await
+            {
+                Err(_) => return Err(Error::Timeout),
+                Ok(Err(error)) => return Err(error),
+                Ok(Ok(address)) => address,
+            },
         };
 
         let domain = match remote_address.is_ipv4() {
@@ -314,6 +305,14 @@ impl TransportBuilder for WebSocketTransport {
         let (listener, listen_addresses, dial_addresses) =
             WebSocketListener::new(std::mem::replace(&mut config.listen_addresses, Vec::new()));
 
+        let interface_monitor = match (
+            config.interface_poll_interval,
+            listener.has_wildcard_listener(),
+        ) {
+            (Some(poll_interval), true) => Some(InterfaceMonitor::new(poll_interval)),
+            _ => None,
+        };
+
         Ok((
             Self {
                 listener,
@@ -326,6 +325,8 @@ impl TransportBuilder for WebSocketTransport {
                 pending_dials: HashMap::new(),
                 pending_connections: FuturesUnordered::new(),
                 pending_raw_connections: FuturesUnordered::new(),
+                interface_monitor,
+                pending_events: VecDeque::new(),
             },
             listen_addresses,
         ))
@@ -341,6 +342,7 @@ impl Transport for WebSocketTransport {
         let max_read_ahead_factor = self.config.noise_read_ahead_frame_count;
         let max_write_buffer_size = self.config.noise_write_buffer_size;
         let dial_addresses = self.dial_addresses.clone();
+        let dns_resolver = self.context.dns_resolver.clone();
         self.pending_dials.insert(connection_id, address.clone());
 
         tracing::debug!(target: LOG_TARGET, ?connection_id, ?address, "open connection");
@@ -351,6 +353,7 @@ impl Transport for WebSocketTransport {
                     address.clone(),
                     dial_addresses,
                     connection_open_timeout,
+                    dns_resolver,
                 )
                 .await
                 .map_err(|error| WebSocketError::new(error, Some(connection_id)))?;
@@ -395,24 +398,27 @@ impl Transport for WebSocketTransport {
             "start connection",
         );
 
-        self.context.executor.run(Box::pin(async move {
-            if let Err(error) = WebSocketConnection::new(
-                context,
-                protocol_set,
-                bandwidth_sink,
-                substream_open_timeout,
-            )
-            .start()
-            .await
-            {
-                tracing::debug!(
-                    target: LOG_TARGET,
-                    ?connection_id,
-                    ?error,
-                    "connection exited with error",
-                );
-            }
-        }));
+        self.context.executor.run_with_name(
+            "litep2p-websocket-connection",
+            Box::pin(async move {
+                if let Err(error) = WebSocketConnection::new(
+                    context,
+                    protocol_set,
+                    bandwidth_sink,
+                    substream_open_timeout,
+                )
+                .start()
+                .await
+                {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?connection_id,
+                        ?error,
+                        "connection exited with error",
+                    );
+                }
+            }),
+        );
 
         Ok(())
     }
@@ -434,28 +440,41 @@ impl Transport for WebSocketTransport {
             .map(|address| {
                 let connection_open_timeout = self.config.connection_open_timeout;
                 let dial_addresses = self.dial_addresses.clone();
+                let dns_resolver = self.context.dns_resolver.clone();
 
                 async move {
-                    WebSocketTransport::dial_peer(address, dial_addresses, connection_open_timeout)
-                        .await
+                    WebSocketTransport::dial_peer(
+                        address.clone(),
+                        dial_addresses,
+                        connection_open_timeout,
+                        dns_resolver,
+                    )
+                    .await
+                    .map_err(|error| (address, error))
                 }
             })
             .collect();
 
         self.pending_raw_connections.push(Box::pin(async move {
+            let mut errors = Vec::with_capacity(futures.len());
+
             while let Some(result) = futures.next().await {
                 match result {
                     Ok((address, stream)) => return Ok((connection_id, address, stream)),
-                    Err(error) => tracing::debug!(
-                        target: LOG_TARGET,
-                        ?connection_id,
-                        ?error,
-                        "failed to open connection",
-                    ),
+                    Err((address, error)) => {
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            ?connection_id,
+                            ?address,
+                            ?error,
+                            "failed to open connection",
+                        );
+                        errors.push((address, error));
+                    }
                 }
             }
 
-            Err(connection_id)
+            Err((connection_id, errors))
         }));
 
         Ok(())
@@ -522,41 +541,69 @@ impl Stream for WebSocketTransport {
     type Item = TransportEvent;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_interfaces(cx);
+
+        if let Some(event) = self.pending_events.pop_front() {
+            return Poll::Ready(Some(event));
+        }
+
         while let Poll::Ready(Some(connection)) = self.listener.poll_next_unpin(cx) {
             match connection {
-                Err(_) => return Poll::Ready(None),
+                Err(error) => {
+                    tracing::debug!(target: LOG_TARGET, ?error, "failed to accept connection");
+
+                    return Poll::Ready(Some(TransportEvent::ListenerError {
+                        error: error.into(),
+                    }));
+                }
                 Ok((stream, address)) => {
-                    let connection_id = self.context.next_connection_id();
                     let keypair = self.context.keypair.clone();
                     let yamux_config = self.config.yamux_config.clone();
                     let connection_open_timeout = self.config.connection_open_timeout;
                     let max_read_ahead_factor = self.config.noise_read_ahead_frame_count;
                     let max_write_buffer_size = self.config.noise_write_buffer_size;
+                    let connection_rates = self.context.connection_rates.clone();
+                    let ip = address.ip();
                     let address = Multiaddr::empty()
                         .with(Protocol::from(address.ip()))
                         .with(Protocol::Tcp(address.port()))
                         .with(Protocol::Ws(std::borrow::Cow::Owned("/".to_string())));
 
+                    if !self.context.allow_inbound_address(&address) {
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            ?address,
+                            "inbound connection rejected by connection gater",
+                        );
+                        continue;
+                    }
+
+                    let connection_id = self.context.next_connection_id();
+                    connection_rates.on_inbound_attempt(ip);
                     self.pending_connections.push(Box::pin(async move {
-                        match tokio::time::timeout(connection_open_timeout, async move {
-                            WebSocketConnection::accept_connection(
-                                stream,
-                                connection_id,
-                                keypair,
-                                address,
-                                yamux_config,
-                                max_read_ahead_factor,
-                                max_write_buffer_size,
-                            )
+                        let result =
+                            match tokio::time::timeout(connection_open_timeout, async move {
+                                WebSocketConnection::accept_connection(
+                                    stream,
+                                    connection_id,
+                                    keypair,
+                                    address,
+                                    yamux_config,
+                                    max_read_ahead_factor,
+                                    max_write_buffer_size,
+                                )
+                                .await
+                                .map_err(|error| WebSocketError::new(error, None))
+                            })
                             .await
-                            .map_err(|error| WebSocketError::new(error, None))
-                        })
-                        .await
-                        {
-                            Err(_) => Err(WebSocketError::new(Error::Timeout, None)),
-                            Ok(Err(error)) => Err(error),
-                            Ok(Ok(result)) => Ok(result),
-                        }
+                            {
+                                Err(_) => Err(WebSocketError::new(Error::Timeout, None)),
+                                Ok(Err(error)) => Err(error),
+                                Ok(Ok(result)) => Ok(result),
+                            };
+                        connection_rates.on_handshake(ip, result.is_ok());
+
+                        result
                     }));
                 }
             }
@@ -582,9 +629,12 @@ impl Stream for WebSocketTransport {
                         }));
                     }
                 }
-                Err(connection_id) =>
+                Err((connection_id, errors)) =>
                     if !self.canceled.remove(&connection_id) {
-                        return Poll::Ready(Some(TransportEvent::OpenFailure { connection_id }));
+                        return Poll::Ready(Some(TransportEvent::OpenFailure {
+                            connection_id,
+                            errors,
+                        }));
                     },
             }
         }
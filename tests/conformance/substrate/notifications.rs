@@ -563,7 +563,7 @@ async fn litep2p_close_substream() {
                     assert_eq!(peer.to_bytes(), libp2p_peer.to_bytes());
                     notif_count += 1;
                 }
-                NotificationEvent::NotificationStreamClosed { .. } => {
+                NotificationEvent::NotificationStreamClosedLocally { .. } => {
                     break;
                 }
                 event => tracing::error!("unhanled notification event: {event:?}"),
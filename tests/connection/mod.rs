@@ -19,13 +19,18 @@
 // DEALINGS IN THE SOFTWARE.
 
 use litep2p::{
-    config::ConfigBuilder,
+    config::{ConfigBuilder, Role},
     crypto::ed25519::Keypair,
     error::{AddressError, Error},
+    peer_store::FileBackend,
     protocol::libp2p::ping::{Config as PingConfig, PingEvent},
+    subscription,
     transport::{
-        quic::config::Config as QuicConfig, tcp::config::Config as TcpConfig,
-        websocket::config::Config as WebSocketConfig,
+        allow_list::AllowList, connection_rate::Config as ConnectionRateConfig,
+        gater::ConnectionGater, ip_limit::Config as IpLimitConfig,
+        quic::config::Config as QuicConfig, reconnect::Config as ReconnectConfig,
+        tcp::config::Config as TcpConfig, websocket::config::Config as WebSocketConfig,
+        DisconnectReason, RawConnection, RejectReason, SupportedTransport,
     },
     Litep2p, Litep2pEvent, PeerId,
 };
@@ -35,6 +40,18 @@ use multiaddr::{Multiaddr, Protocol};
 use multihash::Multihash;
 use network_interface::{NetworkInterface, NetworkInterfaceConfig};
 use tokio::net::{TcpListener, UdpSocket};
+use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
+
+use std::{
+    collections::HashMap,
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
 
 #[cfg(test)]
 mod protocol_dial_invalid_address;
@@ -1352,3 +1369,1852 @@ async fn unspecified_listen_address_websocket() {
         }
     }
 }
+
+#[tokio::test]
+async fn quic_path_stats() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (ping_config1, _ping_event_stream1) = PingConfig::default();
+    let config1 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_quic(QuicConfig {
+            path_stats_interval: Some(std::time::Duration::from_millis(200)),
+            ..Default::default()
+        })
+        .with_libp2p_ping(ping_config1)
+        .build();
+
+    let (ping_config2, _ping_event_stream2) = PingConfig::default();
+    let config2 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_quic(Default::default())
+        .with_libp2p_ping(ping_config2)
+        .build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+    let peer2 = *litep2p2.local_peer_id();
+
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address).await.unwrap();
+
+    let connection_id = match litep2p1.next_event().await {
+        Some(Litep2pEvent::ConnectionEstablished { endpoint, .. }) => endpoint.connection_id(),
+        event => panic!("invalid event: {event:?}"),
+    };
+    tokio::spawn(async move { while let Some(_) = litep2p2.next_event().await {} });
+
+    loop {
+        match tokio::time::timeout(std::time::Duration::from_secs(5), litep2p1.next_event())
+            .await
+            .expect("path statistics to be reported")
+        {
+            Some(Litep2pEvent::QuicPathStats {
+                peer,
+                connection_id: event_connection_id,
+                stats,
+            }) => {
+                assert_eq!(peer, peer2);
+                assert_eq!(event_connection_id, connection_id);
+                assert!(stats.sent_packets > 0);
+                break;
+            }
+            Some(_) => {}
+            None => panic!("litep2p exited"),
+        }
+    }
+
+    assert!(litep2p1.quic_path_stats(connection_id).is_some());
+}
+
+#[tokio::test]
+async fn inbound_connection_approved() {
+    inbound_connection_approval(true).await;
+}
+
+#[tokio::test]
+async fn inbound_connection_rejected() {
+    inbound_connection_approval(false).await;
+}
+
+async fn inbound_connection_approval(approve: bool) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (ping_config1, _ping_event_stream1) = PingConfig::default();
+    let config1 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        })
+        .with_libp2p_ping(ping_config1)
+        .build();
+
+    let (ping_config2, _ping_event_stream2) = PingConfig::default();
+    let config2 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        })
+        .with_libp2p_ping(ping_config2)
+        .with_connection_approval()
+        .build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+    let peer1 = *litep2p1.local_peer_id();
+
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address).await.unwrap();
+    tokio::spawn(async move { while let Some(_) = litep2p1.next_event().await {} });
+
+    match litep2p2.next_event().await {
+        Some(Litep2pEvent::IncomingConnection {
+            peer,
+            approval_handle,
+            ..
+        }) => {
+            assert_eq!(peer, peer1);
+
+            match approve {
+                true => approval_handle.accept(),
+                false => approval_handle.reject("not allowed"),
+            }
+        }
+        event => panic!("invalid event: {event:?}"),
+    }
+
+    match tokio::time::timeout(std::time::Duration::from_secs(2), litep2p2.next_event()).await {
+        Ok(Some(Litep2pEvent::ConnectionEstablished { peer, endpoint })) => {
+            assert!(approve);
+            assert_eq!(peer, peer1);
+            assert!(endpoint.is_listener());
+        }
+        Err(_) => assert!(!approve),
+        event => panic!("invalid event: {event:?}"),
+    }
+}
+
+#[tokio::test]
+async fn connection_probe_succeeds() {
+    connection_probing(true).await;
+}
+
+#[tokio::test]
+async fn connection_probe_fails() {
+    connection_probing(false).await;
+}
+
+async fn connection_probing(remote_supports_ping: bool) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let config1 = ConfigBuilder::new().with_keypair(Keypair::generate()).with_tcp(TcpConfig {
+        listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+        ..Default::default()
+    });
+    let config1 = match remote_supports_ping {
+        true => config1.with_libp2p_ping(PingConfig::default().0).build(),
+        false => config1.build(),
+    };
+
+    let config2 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        })
+        .with_connection_probing()
+        .build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+    let peer1 = *litep2p1.local_peer_id();
+
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address).await.unwrap();
+    tokio::spawn(async move { while let Some(_) = litep2p1.next_event().await {} });
+
+    match tokio::time::timeout(std::time::Duration::from_secs(5), litep2p2.next_event()).await {
+        Ok(Some(Litep2pEvent::ConnectionEstablished { peer, endpoint })) => {
+            assert!(remote_supports_ping);
+            assert_eq!(peer, peer1);
+            assert!(endpoint.is_listener());
+        }
+        Err(_) => assert!(!remote_supports_ping),
+        event => panic!("invalid event: {event:?}"),
+    }
+}
+
+#[tokio::test]
+async fn quic_dial_from_configured_port() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    // reserve a free port for the dialer
+    let dial_port = {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        socket.local_addr().unwrap().port()
+    };
+
+    let config1 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_quic(QuicConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/udp/0/quic-v1".parse().unwrap()],
+            dial_bind_port: Some(dial_port),
+            ..Default::default()
+        })
+        .build();
+
+    let config2 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_quic(QuicConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/udp/0/quic-v1".parse().unwrap()],
+            ..Default::default()
+        })
+        .build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address).await.unwrap();
+    tokio::spawn(async move { while let Some(_) = litep2p1.next_event().await {} });
+
+    match tokio::time::timeout(std::time::Duration::from_secs(5), litep2p2.next_event()).await {
+        Ok(Some(Litep2pEvent::ConnectionEstablished { endpoint, .. })) => {
+            assert!(endpoint.is_listener());
+            assert!(endpoint.address().iter().any(|protocol| protocol == Protocol::Udp(dial_port)));
+        }
+        event => panic!("invalid event: {event:?}"),
+    }
+}
+
+#[tokio::test]
+async fn connection_rate_exceeded() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let config = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_tcp(TcpConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        })
+        .with_connection_rate_config(ConnectionRateConfig {
+            max_attempts: Some(1),
+            ..Default::default()
+        })
+        .build();
+    let mut litep2p = Litep2p::new(config).unwrap();
+    let address = litep2p.listen_addresses().next().unwrap().clone();
+
+    // dial the node from two different peers
+    for _ in 0..2 {
+        let config = ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .build();
+        let mut dialer = Litep2p::new(config).unwrap();
+
+        dialer.dial_address(address.clone()).await.unwrap();
+        tokio::spawn(async move { while let Some(_) = dialer.next_event().await {} });
+    }
+
+    let (source, stats) = loop {
+        match tokio::time::timeout(std::time::Duration::from_secs(5), litep2p.next_event()).await {
+            Ok(Some(Litep2pEvent::ConnectionRateExceeded { source, stats })) =>
+                break (source, stats),
+            Ok(Some(_)) => {}
+            event => panic!("invalid event: {event:?}"),
+        }
+    };
+
+    assert!(source.contains("127.0.0.1".parse().unwrap()));
+    assert_eq!(stats.attempts, 2);
+    assert_eq!(
+        litep2p
+            .connection_rates()
+            .source_stats("127.0.0.1".parse().unwrap())
+            .unwrap()
+            .attempts,
+        2
+    );
+}
+
+#[tokio::test]
+async fn quic_draft_29_dial() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let config1 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_quic(QuicConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/udp/0/quic-v1".parse().unwrap()],
+            ..Default::default()
+        })
+        .build();
+
+    let config2 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_quic(QuicConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/udp/0/quic".parse().unwrap()],
+            ..Default::default()
+        })
+        .build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    assert!(address.iter().any(|protocol| protocol == Protocol::Quic));
+    litep2p1.dial_address(address).await.unwrap();
+
+    let mut litep2p1_connected = false;
+    let mut litep2p2_connected = false;
+
+    while !litep2p1_connected || !litep2p2_connected {
+        tokio::select! {
+            event = litep2p1.next_event() => match event {
+                Some(Litep2pEvent::ConnectionEstablished { .. }) => litep2p1_connected = true,
+                Some(Litep2pEvent::DialFailure { .. }) => panic!("failed to dial: {event:?}"),
+                _ => {}
+            },
+            event = litep2p2.next_event() => if let Some(Litep2pEvent::ConnectionEstablished { .. }) = event {
+                litep2p2_connected = true;
+            },
+            _ = tokio::time::sleep(std::time::Duration::from_secs(5)) => panic!("failed to connect"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn connection_reestablished_after_max_lifetime() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (ping_config1, _ping_event_stream1) = PingConfig::default();
+    let config1 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_quic(QuicConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/udp/0/quic-v1".parse().unwrap()],
+            ..Default::default()
+        })
+        .with_libp2p_ping(ping_config1)
+        .with_max_connection_lifetime(std::time::Duration::from_secs(1))
+        .build();
+
+    let (ping_config2, _ping_event_stream2) = PingConfig::default();
+    let config2 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_quic(QuicConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/udp/0/quic-v1".parse().unwrap()],
+            ..Default::default()
+        })
+        .with_libp2p_ping(ping_config2)
+        .build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+    let peer2 = *litep2p2.local_peer_id();
+
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address).await.unwrap();
+    tokio::spawn(async move { while let Some(_) = litep2p2.next_event().await {} });
+
+    let mut events = Vec::new();
+    while events.len() < 3 {
+        match tokio::time::timeout(std::time::Duration::from_secs(10), litep2p1.next_event()).await
+        {
+            Ok(Some(Litep2pEvent::ConnectionEstablished { peer, .. })) => {
+                assert_eq!(peer, peer2);
+                events.push(true);
+            }
+            Ok(Some(Litep2pEvent::ConnectionClosed { peer, .. })) => {
+                assert_eq!(peer, peer2);
+                events.push(false);
+            }
+            Ok(_) => {}
+            Err(_) => panic!("connection was not re-established"),
+        }
+    }
+
+    assert_eq!(events, vec![true, false, true]);
+}
+
+#[tokio::test]
+async fn dial_failure_reports_error_for_each_address() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let mut litep2p = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .build(),
+    )
+    .unwrap();
+
+    // reserve two ports and close the listeners so that dialing them is refused
+    let peer = PeerId::random();
+    let mut addresses = Vec::new();
+
+    for _ in 0..2 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        addresses.push(
+            Multiaddr::empty()
+                .with(Protocol::Ip4(std::net::Ipv4Addr::new(127, 0, 0, 1)))
+                .with(Protocol::Tcp(port))
+                .with(Protocol::P2p(Multihash::from(peer))),
+        );
+    }
+
+    assert_eq!(
+        litep2p.add_known_address(peer, addresses.clone().into_iter()),
+        2
+    );
+    litep2p.dial(&peer).await.unwrap();
+
+    let event = tokio::time::timeout(std::time::Duration::from_secs(10), async {
+        loop {
+            if let Some(Litep2pEvent::DialFailure { address, error }) = litep2p.next_event().await {
+                break (address, error);
+            }
+        }
+    })
+    .await
+    .expect("dial failure to be reported");
+
+    match event {
+        (address, Error::DialFailures(errors)) => {
+            assert!(address.is_empty());
+            assert_eq!(errors.len(), 2);
+
+            for address in addresses {
+                assert!(errors.iter().any(|(dialed, _)| dialed == &address));
+            }
+        }
+        event => panic!("invalid event received: {event:?}"),
+    }
+}
+
+/// In-memory connection used to test connection injection.
+struct MemoryConnection {
+    role: Role,
+    io: Compat<tokio::io::DuplexStream>,
+}
+
+impl futures::AsyncRead for MemoryConnection {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_read(cx, buf)
+    }
+}
+
+impl futures::AsyncWrite for MemoryConnection {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_close(cx)
+    }
+}
+
+impl RawConnection for MemoryConnection {
+    fn role(&self) -> Role {
+        self.role
+    }
+}
+
+#[tokio::test]
+async fn injected_connection_works() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (ping_config1, mut ping_event_stream1) = PingConfig::default();
+    let mut litep2p1 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_libp2p_ping(ping_config1)
+            .build(),
+    )
+    .unwrap();
+
+    let (ping_config2, mut ping_event_stream2) = PingConfig::default();
+    let mut litep2p2 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_libp2p_ping(ping_config2)
+            .build(),
+    )
+    .unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+    let (io1, io2) = tokio::io::duplex(64 * 1024);
+
+    litep2p1
+        .add_connection(
+            peer2,
+            MemoryConnection {
+                role: Role::Dialer,
+                io: io1.compat(),
+            },
+        )
+        .unwrap();
+    litep2p2
+        .add_connection(
+            peer1,
+            MemoryConnection {
+                role: Role::Listener,
+                io: io2.compat(),
+            },
+        )
+        .unwrap();
+
+    let mut litep2p1_connected = false;
+    let mut litep2p2_connected = false;
+    let mut litep2p1_ping = false;
+    let mut litep2p2_ping = false;
+
+    let future = async {
+        while !(litep2p1_connected && litep2p2_connected && litep2p1_ping && litep2p2_ping) {
+            tokio::select! {
+                event = litep2p1.next_event() => match event {
+                    Some(Litep2pEvent::ConnectionEstablished { peer, .. }) => {
+                        assert_eq!(peer, peer2);
+                        litep2p1_connected = true;
+                    }
+                    Some(_) => {}
+                    None => panic!("litep2p exited"),
+                },
+                event = litep2p2.next_event() => match event {
+                    Some(Litep2pEvent::ConnectionEstablished { peer, .. }) => {
+                        assert_eq!(peer, peer1);
+                        litep2p2_connected = true;
+                    }
+                    Some(_) => {}
+                    None => panic!("litep2p exited"),
+                },
+                event = ping_event_stream1.next() => match event {
+                    Some(PingEvent::Ping { peer, .. }) => {
+                        assert_eq!(peer, peer2);
+                        litep2p1_ping = true;
+                    }
+                    None => panic!("ping exited"),
+                },
+                event = ping_event_stream2.next() => match event {
+                    Some(PingEvent::Ping { peer, .. }) => {
+                        assert_eq!(peer, peer1);
+                        litep2p2_ping = true;
+                    }
+                    None => panic!("ping exited"),
+                },
+            }
+        }
+    };
+
+    tokio::time::timeout(std::time::Duration::from_secs(10), future)
+        .await
+        .expect("injected connection to work");
+}
+
+#[tokio::test]
+async fn connection_info() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (ping_config1, mut ping_event_stream1) = PingConfig::default();
+    let mut litep2p1 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_libp2p_ping(ping_config1)
+            .build(),
+    )
+    .unwrap();
+
+    let (ping_config2, mut ping_event_stream2) = PingConfig::default();
+    let mut litep2p2 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_libp2p_ping(ping_config2)
+            .build(),
+    )
+    .unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+    assert!(litep2p1.connection_info(&peer2).is_empty());
+
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address).await.unwrap();
+
+    let mut litep2p1_ping = false;
+    let mut litep2p2_ping = false;
+
+    // substreams for ping have been negotiated in both directions once both nodes have pinged
+    let future = async {
+        while !(litep2p1_ping && litep2p2_ping) {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p2.next_event() => {},
+                event = ping_event_stream1.next() => if let Some(PingEvent::Ping { .. }) = event {
+                    litep2p1_ping = true;
+                },
+                event = ping_event_stream2.next() => if let Some(PingEvent::Ping { .. }) = event {
+                    litep2p2_ping = true;
+                },
+            }
+        }
+    };
+    tokio::time::timeout(std::time::Duration::from_secs(10), future)
+        .await
+        .expect("peers to ping each other");
+
+    let ping = litep2p::ProtocolName::from("/ipfs/ping/1.0.0");
+
+    let info = litep2p1.connection_info(&peer2);
+    assert_eq!(info.len(), 1);
+    assert!(!info[0].endpoint.is_listener());
+    assert_eq!(info[0].transport, SupportedTransport::Tcp);
+    assert!(info[0].protocols.contains(&ping));
+
+    let info = litep2p2.connection_info(&peer1);
+    assert_eq!(info.len(), 1);
+    assert!(info[0].endpoint.is_listener());
+    assert_eq!(info[0].transport, SupportedTransport::Tcp);
+    assert!(info[0].protocols.contains(&ping));
+    assert!(info[0].uptime > std::time::Duration::ZERO);
+}
+
+#[tokio::test]
+async fn injecting_connection_to_self_fails() {
+    let mut litep2p = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .build(),
+    )
+    .unwrap();
+
+    let peer = *litep2p.local_peer_id();
+    let (io, _remote) = tokio::io::duplex(1024);
+
+    assert!(matches!(
+        litep2p.add_connection(
+            peer,
+            MemoryConnection {
+                role: Role::Dialer,
+                io: io.compat(),
+            }
+        ),
+        Err(Error::TriedToDialSelf)
+    ));
+}
+
+#[tokio::test]
+async fn disconnect_peer() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (ping_config1, _ping_event_stream1) = PingConfig::default();
+    let mut litep2p1 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_libp2p_ping(ping_config1)
+            .build(),
+    )
+    .unwrap();
+
+    let (ping_config2, _ping_event_stream2) = PingConfig::default();
+    let mut litep2p2 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_libp2p_ping(ping_config2)
+            .build(),
+    )
+    .unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+
+    // no connection is open to the peer
+    match litep2p1.disconnect(&peer2) {
+        Err(Error::PeerDoesntExist(peer)) => assert_eq!(peer, peer2),
+        result => panic!("invalid result: {result:?}"),
+    }
+
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address).await.unwrap();
+
+    let mut litep2p1_connected = false;
+    let mut litep2p2_connected = false;
+
+    let future = async {
+        while !(litep2p1_connected && litep2p2_connected) {
+            tokio::select! {
+                event = litep2p1.next_event() => if let Some(Litep2pEvent::ConnectionEstablished { .. }) = event {
+                    litep2p1_connected = true;
+                },
+                event = litep2p2.next_event() => if let Some(Litep2pEvent::ConnectionEstablished { .. }) = event {
+                    litep2p2_connected = true;
+                },
+            }
+        }
+    };
+    tokio::time::timeout(std::time::Duration::from_secs(10), future)
+        .await
+        .expect("peers to connect");
+
+    assert_eq!(
+        litep2p1.connected_peers(),
+        HashMap::from_iter([(peer2, 1usize)])
+    );
+
+    // the connection handle is registered asynchronously so retry until the peer is known
+    let future = async {
+        loop {
+            match litep2p1.disconnect(&peer2) {
+                Ok(()) => break,
+                Err(Error::PeerDoesntExist(_)) => {
+                    let _ = tokio::time::timeout(
+                        std::time::Duration::from_millis(100),
+                        litep2p1.next_event(),
+                    )
+                    .await;
+                }
+                Err(error) => panic!("failed to disconnect peer: {error:?}"),
+            }
+        }
+    };
+    tokio::time::timeout(std::time::Duration::from_secs(10), future)
+        .await
+        .expect("peer to be disconnected");
+
+    let mut litep2p1_closed = false;
+    let mut litep2p2_closed = false;
+
+    let future = async {
+        while !(litep2p1_closed && litep2p2_closed) {
+            tokio::select! {
+                event = litep2p1.next_event() => if let Some(Litep2pEvent::ConnectionClosed { peer, reason, .. }) = event {
+                    assert_eq!(peer, peer2);
+                    assert_eq!(reason, DisconnectReason::LocalClosed);
+                    litep2p1_closed = true;
+                },
+                event = litep2p2.next_event() => if let Some(Litep2pEvent::ConnectionClosed { peer, reason, .. }) = event {
+                    assert_eq!(peer, peer1);
+                    assert_eq!(reason, DisconnectReason::RemoteClosed);
+                    litep2p2_closed = true;
+                },
+            }
+        }
+    };
+    tokio::time::timeout(std::time::Duration::from_secs(10), future)
+        .await
+        .expect("connection to be closed");
+
+    assert!(litep2p1.connected_peers().is_empty());
+    assert!(litep2p2.connected_peers().is_empty());
+}
+
+#[tokio::test]
+async fn banned_peer_rejected_until_ban_expires() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let mut litep2p1 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .build(),
+    )
+    .unwrap();
+
+    let mut litep2p2 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .build(),
+    )
+    .unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let address1 = litep2p1.listen_addresses().next().unwrap().clone();
+    let address2 = litep2p2.listen_addresses().next().unwrap().clone();
+
+    litep2p2.ban_peer(peer1, std::time::Duration::from_secs(2));
+    match litep2p2.next_event().await {
+        Some(Litep2pEvent::PeerBanned { peer, duration }) => {
+            assert_eq!(peer, peer1);
+            assert_eq!(duration, std::time::Duration::from_secs(2));
+        }
+        event => panic!("invalid event: {event:?}"),
+    }
+
+    // dialing the banned peer is refused
+    match litep2p2.dial_address(address1).await {
+        Err(Error::PeerBanned(peer)) => assert_eq!(peer, peer1),
+        result => panic!("invalid result: {result:?}"),
+    }
+
+    // inbound connection from the banned peer is rejected
+    litep2p1.dial_address(address2.clone()).await.unwrap();
+
+    let mut rejected = false;
+
+    let future = async {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                event = litep2p2.next_event() => match event {
+                    Some(Litep2pEvent::ConnectionEstablished { .. }) =>
+                        panic!("connection from banned peer accepted"),
+                    Some(Litep2pEvent::ConnectionRejected { peer, endpoint, reason }) => {
+                        assert_eq!(peer, peer1);
+                        assert!(endpoint.is_listener());
+                        assert_eq!(reason, RejectReason::PeerBanned);
+                        rejected = true;
+                    }
+                    _ => {}
+                },
+            }
+        }
+    };
+    let _ = tokio::time::timeout(std::time::Duration::from_secs(2), future).await;
+    assert!(rejected);
+
+    // the ban has expired and the connection is accepted
+    litep2p1.dial_address(address2).await.unwrap();
+
+    let future = async {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                event = litep2p2.next_event() => if let Some(Litep2pEvent::ConnectionEstablished { peer, .. }) = event {
+                    assert_eq!(peer, peer1);
+                    break;
+                },
+            }
+        }
+    };
+    tokio::time::timeout(std::time::Duration::from_secs(10), future)
+        .await
+        .expect("connection to be established after the ban expired");
+}
+
+#[tokio::test]
+async fn dial_peer_using_known_address() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let mut litep2p1 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .build(),
+    )
+    .unwrap();
+
+    let mut litep2p2 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .build(),
+    )
+    .unwrap();
+
+    let peer2 = *litep2p2.local_peer_id();
+    let mut address = litep2p2.listen_addresses().next().unwrap().clone();
+    assert!(std::matches!(address.pop(), Some(Protocol::P2p(_))));
+
+    // address of another peer is ignored
+    let other = address.clone().with(Protocol::P2p(Multihash::from(PeerId::random())));
+    assert_eq!(litep2p1.add_known_address(peer2, std::iter::once(other)), 0);
+
+    // address without `PeerId` is extended with the `PeerId` of the peer
+    assert_eq!(
+        litep2p1.add_known_address(peer2, std::iter::once(address)),
+        1
+    );
+    litep2p1.dial(&peer2).await.unwrap();
+
+    let future = async {
+        loop {
+            tokio::select! {
+                event = litep2p1.next_event() => if let Some(Litep2pEvent::ConnectionEstablished { peer, .. }) = event {
+                    assert_eq!(peer, peer2);
+                    break;
+                },
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    };
+    tokio::time::timeout(std::time::Duration::from_secs(10), future)
+        .await
+        .expect("connection to be established");
+}
+
+#[tokio::test]
+async fn known_peers_persisted_across_restarts() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let path = std::env::temp_dir().join(format!("litep2p-peers-{}", PeerId::random()));
+    let peer = PeerId::random();
+    let address = Multiaddr::empty()
+        .with(Protocol::Ip4(std::net::Ipv4Addr::new(127, 0, 0, 1)))
+        .with(Protocol::Tcp(8888))
+        .with(Protocol::P2p(Multihash::from(peer)));
+
+    let mut litep2p = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(Default::default())
+            .with_peer_store_backend(FileBackend::new(&path))
+            .build(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        litep2p.add_known_address(peer, std::iter::once(address.clone())),
+        1
+    );
+    litep2p.peer_store().flush().unwrap();
+    drop(litep2p);
+
+    // the peer is known after a restart
+    let mut litep2p = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(Default::default())
+            .with_peer_store_backend(FileBackend::new(&path))
+            .build(),
+    )
+    .unwrap();
+
+    let record = litep2p.peer_store().peer(&peer).unwrap();
+    assert!(record.addresses.contains(&address));
+    litep2p.dial(&peer).await.unwrap();
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+struct TestGater {
+    denied_peer: PeerId,
+    deny_addresses: AtomicBool,
+}
+
+impl ConnectionGater for TestGater {
+    fn allow_inbound_address(&self, _address: &Multiaddr) -> bool {
+        !self.deny_addresses.load(Ordering::SeqCst)
+    }
+
+    fn allow_inbound_peer(&self, peer: &PeerId, _address: &Multiaddr) -> bool {
+        peer != &self.denied_peer
+    }
+}
+
+#[tokio::test]
+async fn connection_gater_rejects_inbound_connections() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let keypair1 = Keypair::generate();
+    let peer1 = PeerId::from_public_key(&keypair1.public().into());
+    let gater = Arc::new(TestGater {
+        denied_peer: peer1,
+        deny_addresses: AtomicBool::new(false),
+    });
+
+    let mut litep2p1 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(keypair1)
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .build(),
+    )
+    .unwrap();
+
+    let mut litep2p2 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_connection_gater(gater.clone())
+            .build(),
+    )
+    .unwrap();
+
+    let address2 = litep2p2.listen_addresses().next().unwrap().clone();
+
+    // connection from the denied peer is rejected after the handshake
+    litep2p1.dial_address(address2.clone()).await.unwrap();
+
+    let mut rejected = false;
+    let mut closed = false;
+
+    let future = async {
+        while !rejected || !closed {
+            tokio::select! {
+                event = litep2p1.next_event() => match event {
+                    Some(Litep2pEvent::ConnectionClosed { .. })
+                    | Some(Litep2pEvent::DialFailure { .. }) => closed = true,
+                    _ => {}
+                },
+                event = litep2p2.next_event() => match event {
+                    Some(Litep2pEvent::ConnectionEstablished { .. }) =>
+                        panic!("connection from denied peer accepted"),
+                    Some(Litep2pEvent::ConnectionRejected { peer, endpoint, reason }) => {
+                        assert_eq!(peer, peer1);
+                        assert!(endpoint.is_listener());
+                        assert_eq!(reason, RejectReason::Gated);
+                        rejected = true;
+                    }
+                    _ => {}
+                },
+            }
+        }
+    };
+    tokio::time::timeout(std::time::Duration::from_secs(5), future)
+        .await
+        .expect("connection to be rejected");
+
+    // socket is closed before the handshake when the address is denied
+    gater.deny_addresses.store(true, Ordering::SeqCst);
+    litep2p1.dial_address(address2).await.unwrap();
+
+    let future = async {
+        loop {
+            tokio::select! {
+                event = litep2p1.next_event() => match event {
+                    Some(Litep2pEvent::DialFailure { .. }) => break,
+                    Some(Litep2pEvent::ConnectionEstablished { .. }) =>
+                        panic!("connection established"),
+                    _ => {}
+                },
+                event = litep2p2.next_event() => match event {
+                    Some(Litep2pEvent::ConnectionEstablished { .. })
+                    | Some(Litep2pEvent::ConnectionRejected { .. }) =>
+                        panic!("invalid event: {event:?}"),
+                    _ => {}
+                },
+            }
+        }
+    };
+    tokio::time::timeout(std::time::Duration::from_secs(5), future)
+        .await
+        .expect("dial to fail");
+}
+
+#[tokio::test]
+async fn allow_list_rejects_unknown_peers() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let keypair3 = Keypair::generate();
+    let peer3 = PeerId::from_public_key(&keypair3.public().into());
+
+    let mut litep2p1 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .build(),
+    )
+    .unwrap();
+
+    let mut litep2p2 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_allow_list(
+                AllowList::new([peer3]).with_address_range("127.0.0.0".parse().unwrap(), 8),
+            )
+            .build(),
+    )
+    .unwrap();
+
+    let mut litep2p3 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(keypair3)
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .build(),
+    )
+    .unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let address1 = litep2p1.listen_addresses().next().unwrap().clone();
+    let address2 = litep2p2.listen_addresses().next().unwrap().clone();
+
+    // dialing a peer which is not on the allow-list is refused
+    match litep2p2.dial_address(address1).await {
+        Err(Error::PeerNotAllowed(peer)) => assert_eq!(peer, peer1),
+        result => panic!("invalid result: {result:?}"),
+    }
+
+    // inbound connection from a peer which is not on the allow-list is rejected
+    litep2p1.dial_address(address2.clone()).await.unwrap();
+
+    let future = async {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                event = litep2p2.next_event() => match event {
+                    Some(Litep2pEvent::ConnectionEstablished { .. }) =>
+                        panic!("connection from unknown peer accepted"),
+                    Some(Litep2pEvent::ConnectionRejected { peer, endpoint, reason }) => {
+                        assert_eq!(peer, peer1);
+                        assert!(endpoint.is_listener());
+                        assert_eq!(reason, RejectReason::NotAllowed);
+                        break;
+                    }
+                    _ => {}
+                },
+            }
+        }
+    };
+    tokio::time::timeout(std::time::Duration::from_secs(5), future)
+        .await
+        .expect("connection to be rejected");
+
+    // connection from the allowed peer is accepted
+    litep2p3.dial_address(address2).await.unwrap();
+
+    let future = async {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p3.next_event() => {},
+                event = litep2p2.next_event() => if let Some(Litep2pEvent::ConnectionEstablished { peer, .. }) = event {
+                    assert_eq!(peer, peer3);
+                    break;
+                },
+            }
+        }
+    };
+    tokio::time::timeout(std::time::Duration::from_secs(5), future)
+        .await
+        .expect("connection to be established");
+}
+
+#[tokio::test]
+async fn ip_limit_rejects_inbound_connections() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    // connections are kept open by ping
+    let (ping_config1, _ping_event_stream1) = PingConfig::default();
+    let mut litep2p1 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_libp2p_ping(ping_config1)
+            .with_ip_limits(IpLimitConfig {
+                max_connections_per_ip: Some(1),
+                ..Default::default()
+            })
+            .build(),
+    )
+    .unwrap();
+
+    let (ping_config2, _ping_event_stream2) = PingConfig::default();
+    let mut litep2p2 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(Default::default())
+            .with_libp2p_ping(ping_config2)
+            .build(),
+    )
+    .unwrap();
+
+    let (ping_config3, _ping_event_stream3) = PingConfig::default();
+    let mut litep2p3 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(Default::default())
+            .with_libp2p_ping(ping_config3)
+            .build(),
+    )
+    .unwrap();
+
+    let peer2 = *litep2p2.local_peer_id();
+    let peer3 = *litep2p3.local_peer_id();
+    let address1 = litep2p1.listen_addresses().next().unwrap().clone();
+
+    // first inbound connection from `127.0.0.1` is accepted
+    litep2p2.dial_address(address1.clone()).await.unwrap();
+
+    let future = async {
+        loop {
+            tokio::select! {
+                _ = litep2p2.next_event() => {},
+                event = litep2p1.next_event() => if let Some(Litep2pEvent::ConnectionEstablished { peer, .. }) = event {
+                    assert_eq!(peer, peer2);
+                    break;
+                },
+            }
+        }
+    };
+    tokio::time::timeout(std::time::Duration::from_secs(5), future)
+        .await
+        .expect("connection to be established");
+
+    // second inbound connection from the same IP address is rejected
+    litep2p3.dial_address(address1).await.unwrap();
+
+    let future = async {
+        loop {
+            tokio::select! {
+                _ = litep2p2.next_event() => {},
+                _ = litep2p3.next_event() => {},
+                event = litep2p1.next_event() => match event {
+                    Some(Litep2pEvent::ConnectionEstablished { .. }) =>
+                        panic!("connection over the limit accepted"),
+                    Some(Litep2pEvent::ConnectionRejected { peer, endpoint, reason }) => {
+                        assert_eq!(peer, peer3);
+                        assert!(endpoint.is_listener());
+                        assert_eq!(reason, RejectReason::IpLimit);
+                        break;
+                    }
+                    _ => {}
+                },
+            }
+        }
+    };
+    tokio::time::timeout(std::time::Duration::from_secs(5), future)
+        .await
+        .expect("connection to be rejected");
+}
+
+#[tokio::test]
+async fn persistent_peer_reconnected() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (ping_config1, _ping_event_stream1) = PingConfig::default();
+    let mut litep2p1 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_libp2p_ping(ping_config1)
+            .with_reconnect_config(ReconnectConfig {
+                initial_backoff: std::time::Duration::from_millis(100),
+                jitter: 0.0,
+                ..Default::default()
+            })
+            .build(),
+    )
+    .unwrap();
+
+    let (ping_config2, _ping_event_stream2) = PingConfig::default();
+    let mut litep2p2 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_libp2p_ping(ping_config2)
+            .build(),
+    )
+    .unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+    let address2 = litep2p2.listen_addresses().next().unwrap().clone();
+
+    litep2p1.add_known_address(peer2, std::iter::once(address2));
+
+    // the persistent peer is dialed as soon as it's added
+    litep2p1.add_persistent_peer(peer2);
+
+    let mut connected = (false, false);
+    let future = async {
+        while !(connected.0 && connected.1) {
+            tokio::select! {
+                event = litep2p1.next_event() => if let Some(Litep2pEvent::ConnectionEstablished { peer, .. }) = event {
+                    assert_eq!(peer, peer2);
+                    connected.0 = true;
+                },
+                event = litep2p2.next_event() => if let Some(Litep2pEvent::ConnectionEstablished { peer, .. }) = event {
+                    assert_eq!(peer, peer1);
+                    connected.1 = true;
+                },
+            }
+        }
+    };
+    tokio::time::timeout(std::time::Duration::from_secs(5), future)
+        .await
+        .expect("connection to be established");
+
+    // let the connections register themselves with the transport managers
+    let future = async {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    };
+    let _ = tokio::time::timeout(std::time::Duration::from_millis(500), future).await;
+
+    // the remote closes the connection and the peer is dialed again
+    litep2p2.disconnect(&peer1).unwrap();
+
+    let mut closed = false;
+    let future = async {
+        loop {
+            tokio::select! {
+                event = litep2p1.next_event() => match event {
+                    Some(Litep2pEvent::ConnectionClosed { peer, .. }) => {
+                        assert_eq!(peer, peer2);
+                        closed = true;
+                    }
+                    Some(Litep2pEvent::ConnectionEstablished { peer, .. }) if closed => {
+                        assert_eq!(peer, peer2);
+                        break;
+                    }
+                    _ => {}
+                },
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    };
+    tokio::time::timeout(std::time::Duration::from_secs(5), future)
+        .await
+        .expect("peer to be reconnected");
+}
+
+#[tokio::test]
+async fn idle_connection_kept_open_for_keep_alive_timeout() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let mut litep2p1 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_keep_alive_timeout(std::time::Duration::from_secs(2))
+            .build(),
+    )
+    .unwrap();
+    let mut litep2p2 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_keep_alive_timeout(std::time::Duration::from_secs(2))
+            .build(),
+    )
+    .unwrap();
+    let peer2 = *litep2p2.local_peer_id();
+
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address).await.unwrap();
+    tokio::spawn(async move { while let Some(_) = litep2p2.next_event().await {} });
+
+    let established = tokio::time::timeout(std::time::Duration::from_secs(10), async {
+        loop {
+            if let Some(Litep2pEvent::ConnectionEstablished { peer, .. }) =
+                litep2p1.next_event().await
+            {
+                assert_eq!(peer, peer2);
+                break std::time::Instant::now();
+            }
+        }
+    })
+    .await
+    .expect("connection to be established");
+
+    // no protocol opens a substream so the connection is closed once the timeout expires
+    tokio::time::timeout(std::time::Duration::from_secs(10), async {
+        loop {
+            if let Some(Litep2pEvent::ConnectionClosed { peer, .. }) = litep2p1.next_event().await {
+                assert_eq!(peer, peer2);
+                break;
+            }
+        }
+    })
+    .await
+    .expect("connection to be closed");
+
+    assert!(established.elapsed() >= std::time::Duration::from_millis(1500));
+}
+
+#[tokio::test]
+async fn reserved_peer_connection_not_closed_when_idle() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let mut litep2p1 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_keep_alive_timeout(std::time::Duration::from_secs(1))
+            .build(),
+    )
+    .unwrap();
+    let mut litep2p2 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_keep_alive_timeout(std::time::Duration::from_secs(1))
+            .build(),
+    )
+    .unwrap();
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+
+    // both sides reserve each other so that neither closes the idle connection
+    litep2p1.add_known_address(peer2, std::iter::once(address));
+    litep2p1.add_reserved_peer(peer2);
+    litep2p2.add_reserved_peer(peer1);
+    tokio::spawn(async move { while let Some(_) = litep2p2.next_event().await {} });
+
+    tokio::time::timeout(std::time::Duration::from_secs(10), async {
+        loop {
+            if let Some(Litep2pEvent::ConnectionEstablished { peer, .. }) =
+                litep2p1.next_event().await
+            {
+                assert_eq!(peer, peer2);
+                break;
+            }
+        }
+    })
+    .await
+    .expect("connection to be established");
+
+    // no protocol opens a substream but the connection outlives the keep-alive timeout
+    let future = async {
+        loop {
+            if let Some(Litep2pEvent::ConnectionClosed { .. }) = litep2p1.next_event().await {
+                panic!("connection to reserved peer closed");
+            }
+        }
+    };
+    assert!(tokio::time::timeout(std::time::Duration::from_secs(3), future).await.is_err());
+}
+
+#[tokio::test]
+async fn subscribers_receive_filtered_events() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (ping_config1, _ping_event_stream1) = PingConfig::default();
+    let mut litep2p1 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_libp2p_ping(ping_config1)
+            .build(),
+    )
+    .unwrap();
+    let (ping_config2, _ping_event_stream2) = PingConfig::default();
+    let mut litep2p2 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_libp2p_ping(ping_config2)
+            .build(),
+    )
+    .unwrap();
+    let peer2 = *litep2p2.local_peer_id();
+
+    let mut connection_events = litep2p1.subscribe(subscription::connection_events);
+    let mut peer_events = litep2p1.litep2p_handle().subscribe(subscription::peer_events(peer2));
+    let mut other_peer_events = litep2p1.subscribe(subscription::peer_events(PeerId::random()));
+
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address).await.unwrap();
+
+    tokio::spawn(async move { while let Some(_) = litep2p2.next_event().await {} });
+    tokio::spawn(async move { while let Some(_) = litep2p1.next_event().await {} });
+
+    for events in [&mut connection_events, &mut peer_events] {
+        match tokio::time::timeout(std::time::Duration::from_secs(10), events.next())
+            .await
+            .expect("event to be received")
+        {
+            Some(Litep2pEvent::ConnectionEstablished { peer, .. }) => assert_eq!(peer, peer2),
+            event => panic!("invalid event: {event:?}"),
+        }
+    }
+
+    assert!(
+        tokio::time::timeout(std::time::Duration::from_secs(1), other_peer_events.next())
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn stop_transport_at_runtime() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (ping_config1, _ping_event_stream1) = PingConfig::default();
+    let mut litep2p1 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_quic(QuicConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/udp/0/quic-v1".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_libp2p_ping(ping_config1)
+            .build(),
+    )
+    .unwrap();
+    let (ping_config2, _ping_event_stream2) = PingConfig::default();
+    let mut litep2p2 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_libp2p_ping(ping_config2)
+            .build(),
+    )
+    .unwrap();
+    let peer2 = *litep2p2.local_peer_id();
+
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address).await.unwrap();
+
+    tokio::spawn(async move { while let Some(_) = litep2p2.next_event().await {} });
+
+    loop {
+        match tokio::time::timeout(std::time::Duration::from_secs(10), litep2p1.next_event())
+            .await
+            .expect("connection to be established")
+        {
+            Some(Litep2pEvent::ConnectionEstablished { peer, .. }) => {
+                assert_eq!(peer, peer2);
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    let tcp_address = litep2p1
+        .listen_addresses()
+        .find(|address| address.iter().any(|protocol| std::matches!(protocol, Protocol::Tcp(_))))
+        .unwrap()
+        .clone();
+    litep2p1.stop_transport(SupportedTransport::Tcp).unwrap();
+
+    assert!(std::matches!(
+        litep2p1.stop_transport(SupportedTransport::Tcp),
+        Err(Error::NotSupported(_))
+    ));
+    assert!(litep2p1
+        .listen_addresses()
+        .all(|address| address.iter().any(|protocol| std::matches!(protocol, Protocol::QuicV1))));
+
+    let mut expired = false;
+    let mut closed = false;
+
+    while !expired || !closed {
+        match tokio::time::timeout(std::time::Duration::from_secs(10), litep2p1.next_event())
+            .await
+            .expect("event to be received")
+        {
+            Some(Litep2pEvent::ExpiredListenAddress { address }) => {
+                assert_eq!(address, tcp_address);
+                expired = true;
+            }
+            Some(Litep2pEvent::ConnectionClosed { peer, .. }) => {
+                assert_eq!(peer, peer2);
+                closed = true;
+            }
+            _ => {}
+        }
+    }
+
+    // the stopped transport can't be used for dialing anymore
+    let address = Multiaddr::empty()
+        .with(Protocol::Ip4(std::net::Ipv4Addr::new(127, 0, 0, 1)))
+        .with(Protocol::Tcp(8888))
+        .with(Protocol::P2p(
+            Multihash::from_bytes(&PeerId::random().to_bytes()).unwrap(),
+        ));
+    assert!(std::matches!(
+        litep2p1.dial_address(address).await,
+        Err(Error::TransportNotSupported(_))
+    ));
+}
+
+#[tokio::test]
+async fn litep2p_polled_as_stream() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (ping_config1, _ping_event_stream1) = PingConfig::default();
+    let mut litep2p1 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_libp2p_ping(ping_config1)
+            .build(),
+    )
+    .unwrap();
+    let (ping_config2, _ping_event_stream2) = PingConfig::default();
+    let mut litep2p2 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_libp2p_ping(ping_config2)
+            .build(),
+    )
+    .unwrap();
+    let peer2 = *litep2p2.local_peer_id();
+    let mut connection_events = litep2p1.subscribe(subscription::connection_events);
+
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address).await.unwrap();
+
+    tokio::spawn(async move { while let Some(_) = litep2p2.next().await {} });
+
+    match tokio::time::timeout(std::time::Duration::from_secs(10), litep2p1.next())
+        .await
+        .expect("event to be received")
+    {
+        Some(Litep2pEvent::ConnectionEstablished { peer, .. }) => assert_eq!(peer, peer2),
+        event => panic!("invalid event: {event:?}"),
+    }
+
+    // events yielded by the stream are published to the subscriptions
+    match connection_events.next().await {
+        Some(Litep2pEvent::ConnectionEstablished { peer, .. }) => assert_eq!(peer, peer2),
+        event => panic!("invalid event: {event:?}"),
+    }
+}
+
+#[tokio::test]
+async fn shutdown_closes_protocols_before_connections() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (ping_config1, mut ping_event_stream1) = PingConfig::default();
+    let mut litep2p1 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_libp2p_ping(ping_config1)
+            .with_shutdown_timeout(std::time::Duration::from_secs(2))
+            .build(),
+    )
+    .unwrap();
+    let (ping_config2, _ping_event_stream2) = PingConfig::default();
+    let mut litep2p2 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_libp2p_ping(ping_config2)
+            .build(),
+    )
+    .unwrap();
+    let peer1 = *litep2p1.local_peer_id();
+
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address).await.unwrap();
+
+    tokio::time::timeout(std::time::Duration::from_secs(10), async {
+        let mut connected = (false, false);
+
+        while connected != (true, true) {
+            tokio::select! {
+                event = litep2p1.next_event() => if let Some(Litep2pEvent::ConnectionEstablished { .. }) = event {
+                    connected.0 = true;
+                },
+                event = litep2p2.next_event() => if let Some(Litep2pEvent::ConnectionEstablished { .. }) = event {
+                    connected.1 = true;
+                },
+            }
+        }
+    })
+    .await
+    .expect("connection to be established");
+
+    let mut events =
+        litep2p1.subscribe(|event| std::matches!(event, Litep2pEvent::ShutdownCompleted { .. }));
+    let listen_addresses = litep2p1.listen_addresses().cloned().collect::<Vec<_>>();
+    let handle = tokio::spawn(async move {
+        while let Some(event) = litep2p2.next_event().await {
+            if let Litep2pEvent::ConnectionClosed { peer, .. } = event {
+                return peer;
+            }
+        }
+
+        panic!("litep2p2 exited");
+    });
+
+    let summary = litep2p1.shutdown().await;
+    assert_eq!(summary.protocols_closed, 1);
+    assert!(summary.protocols_timed_out.is_empty());
+    assert_eq!(summary.connections_closed, 1);
+    assert_eq!(summary.connections_timed_out, 0);
+    assert_eq!(summary.listen_addresses, listen_addresses);
+    assert_eq!(litep2p1.listen_addresses().count(), 0);
+
+    // event loop of ping exited without observing the connection close
+    tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        while let Some(_) = ping_event_stream1.next().await {}
+    })
+    .await
+    .expect("ping to exit");
+
+    match events.next().await {
+        Some(Litep2pEvent::ShutdownCompleted { summary: published }) =>
+            assert_eq!(published, summary),
+        event => panic!("invalid event: {event:?}"),
+    }
+
+    assert_eq!(
+        tokio::time::timeout(std::time::Duration::from_secs(5), handle)
+            .await
+            .expect("connection to be closed")
+            .unwrap(),
+        peer1,
+    );
+}
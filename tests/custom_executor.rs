@@ -39,7 +39,12 @@ use bytes::BytesMut;
 use futures::{future::BoxFuture, stream::FuturesUnordered, StreamExt};
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 
-use std::{future::Future, pin::Pin, sync::Arc};
+use std::{
+    collections::HashSet,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
 
 struct TaskExecutor {
     rx: Receiver<Pin<Box<dyn Future<Output = ()> + Send>>>,
@@ -71,6 +76,7 @@ impl TaskExecutor {
 
 struct TaskExecutorHandle {
     tx: Sender<Pin<Box<dyn Future<Output = ()> + Send>>>,
+    names: Arc<Mutex<HashSet<&'static str>>>,
 }
 
 impl Executor for TaskExecutorHandle {
@@ -78,7 +84,8 @@ impl Executor for TaskExecutorHandle {
         let _ = self.tx.try_send(future);
     }
 
-    fn run_with_name(&self, _: &'static str, future: Pin<Box<dyn Future<Output = ()> + Send>>) {
+    fn run_with_name(&self, name: &'static str, future: Pin<Box<dyn Future<Output = ()> + Send>>) {
+        self.names.lock().unwrap().insert(name);
         let _ = self.tx.try_send(future);
     }
 }
@@ -112,7 +119,11 @@ async fn custom_executor() {
             .with_max_size(1024)
             .build();
 
-    let handle = TaskExecutorHandle { tx: sender.clone() };
+    let names = Arc::new(Mutex::new(HashSet::new()));
+    let handle = TaskExecutorHandle {
+        tx: sender.clone(),
+        names: Arc::clone(&names),
+    };
     let config1 = ConfigBuilder::new()
         .with_keypair(Keypair::generate())
         .with_notification_protocol(notif_config1)
@@ -139,7 +150,10 @@ async fn custom_executor() {
             .with_max_size(1024)
             .build();
 
-    let handle = TaskExecutorHandle { tx: sender };
+    let handle = TaskExecutorHandle {
+        tx: sender,
+        names: Arc::clone(&names),
+    };
     let config2 = ConfigBuilder::new()
         .with_keypair(Keypair::generate())
         .with_notification_protocol(notif_config2)
@@ -284,4 +298,18 @@ async fn custom_executor() {
         }
         event => panic!("unexpected event: {event:?}"),
     }
+
+    // verify that the protocols and connections were spawned using the executor
+    let names = names.lock().unwrap();
+    for name in [
+        "litep2p-notification",
+        "litep2p-request-response",
+        "litep2p-tcp-connection",
+        "litep2p-notification-connection",
+    ] {
+        assert!(
+            names.contains(name),
+            "{name} not spawned using the executor"
+        );
+    }
 }
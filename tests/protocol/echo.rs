@@ -0,0 +1,145 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use litep2p::{
+    config::ConfigBuilder,
+    protocol::{
+        echo::{NotificationEcho, RequestEcho},
+        notification::NotificationEvent,
+        request_response::{DialOptions, RequestResponseEvent},
+    },
+    transport::tcp::config::Config as TcpConfig,
+    Litep2p,
+};
+
+use futures::StreamExt;
+
+fn tcp_config() -> TcpConfig {
+    TcpConfig {
+        listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn notification_echo() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (echo_config, echo) = NotificationEcho::new(1024);
+    let (client_config, mut handle) = NotificationEcho::client(1024);
+
+    let mut litep2p1 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_tcp(tcp_config())
+            .with_notification_protocol(client_config)
+            .build(),
+    )
+    .unwrap();
+    let mut litep2p2 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_tcp(tcp_config())
+            .with_notification_protocol(echo_config)
+            .build(),
+    )
+    .unwrap();
+
+    let peer = *litep2p2.local_peer_id();
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.add_known_address(peer, vec![address].into_iter());
+
+    tokio::spawn(echo.run());
+    tokio::spawn(async move { while let Some(_) = litep2p2.next_event().await {} });
+
+    handle.open_substream(peer).await.unwrap();
+
+    loop {
+        tokio::select! {
+            _ = litep2p1.next_event() => {}
+            event = handle.next() => match event.unwrap() {
+                NotificationEvent::NotificationStreamOpened { .. } => {
+                    handle.send_sync_notification(peer, vec![1, 3, 3, 7]).unwrap();
+                }
+                NotificationEvent::NotificationReceived { peer: remote, notification } => {
+                    assert_eq!(remote, peer);
+                    assert_eq!(notification, vec![1, 3, 3, 7]);
+                    break
+                }
+                _ => {}
+            },
+            _ = tokio::time::sleep(std::time::Duration::from_secs(10)) => {
+                panic!("failed to receive echoed notification")
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn request_echo() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (echo_config, echo) = RequestEcho::new(1024);
+    let (client_config, mut handle) = RequestEcho::client(1024);
+
+    let mut litep2p1 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_tcp(tcp_config())
+            .with_request_response_protocol(client_config)
+            .build(),
+    )
+    .unwrap();
+    let mut litep2p2 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_tcp(tcp_config())
+            .with_request_response_protocol(echo_config)
+            .build(),
+    )
+    .unwrap();
+
+    let peer = *litep2p2.local_peer_id();
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.add_known_address(peer, vec![address].into_iter());
+
+    tokio::spawn(echo.run());
+    tokio::spawn(async move { while let Some(_) = litep2p2.next_event().await {} });
+
+    let request_id = handle.send_request(peer, vec![1, 3, 3, 7], DialOptions::Dial).await.unwrap();
+
+    loop {
+        tokio::select! {
+            _ = litep2p1.next_event() => {}
+            event = handle.next() => match event.unwrap() {
+                RequestResponseEvent::ResponseReceived { peer: remote, request_id: id, response, .. } => {
+                    assert_eq!(remote, peer);
+                    assert_eq!(id, request_id);
+                    assert_eq!(response, vec![1, 3, 3, 7]);
+                    break
+                }
+                event => panic!("unexpected event: {event:?}"),
+            },
+            _ = tokio::time::sleep(std::time::Duration::from_secs(10)) => {
+                panic!("failed to receive echoed response")
+            }
+        }
+    }
+}
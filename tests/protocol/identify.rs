@@ -30,8 +30,10 @@ use litep2p::{
         quic::config::Config as QuicConfig, tcp::config::Config as TcpConfig,
         websocket::config::Config as WebSocketConfig,
     },
-    Litep2p, Litep2pEvent,
+    AddressConfidence, Litep2p, Litep2pEvent, ProtocolName,
 };
+use multiaddr::{Multiaddr, Protocol};
+use multihash::Multihash;
 
 enum Transport {
     Quic(QuicConfig),
@@ -278,3 +280,159 @@ async fn identify_not_supported(transport1: Transport, transport2: Transport) {
 
     assert!(identify_event_stream2.next().now_or_never().is_none());
 }
+
+#[tokio::test]
+async fn identified_peer_recorded_in_peer_store() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (identify_config1, mut identify_event_stream1) =
+        Config::new("/proto/1".to_string(), None, Vec::new());
+    let mut litep2p1 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(Default::default())
+            .with_libp2p_identify(identify_config1)
+            .build(),
+    )
+    .unwrap();
+
+    let (identify_config2, _identify_event_stream2) =
+        Config::new("/proto/2".to_string(), None, Vec::new());
+    let mut litep2p2 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(Default::default())
+            .with_libp2p_identify(identify_config2)
+            .build(),
+    )
+    .unwrap();
+
+    let peer2 = *litep2p2.local_peer_id();
+    let address2 = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address2).await.unwrap();
+
+    loop {
+        tokio::select! {
+            _event = litep2p1.next_event() => {}
+            _event = litep2p2.next_event() => {}
+            event = identify_event_stream1.next() => {
+                let IdentifyEvent::PeerIdentified { peer, .. } = event.unwrap();
+                assert_eq!(peer, peer2);
+                break
+            }
+        }
+    }
+
+    let record = litep2p1.peer_store().peer(&peer2).unwrap();
+    assert!(record.protocols.contains(&ProtocolName::from("/ipfs/id/1.0.0")));
+    assert!(!record.addresses.is_empty());
+
+    // metadata is visible through every clone of the store
+    litep2p1.peer_store().set_metadata(peer2, "score", vec![1, 2, 3]);
+    assert_eq!(
+        litep2p1.peer_store().metadata(&peer2, "score"),
+        Some(vec![1, 2, 3])
+    );
+
+    loop {
+        tokio::select! {
+            event = litep2p1.next_event() => if let Some(Litep2pEvent::ConnectionClosed { .. }) = event {
+                break
+            },
+            _event = litep2p2.next_event() => {}
+        }
+    }
+
+    let history = litep2p1.peer_store().peer(&peer2).unwrap().history;
+    assert_eq!(history.num_established, 1);
+    assert!(history.last_disconnected.is_some());
+}
+
+/// Get the confirmed external addresses of `litep2p`.
+fn confirmed_addresses(litep2p: &Litep2p) -> Vec<Multiaddr> {
+    litep2p
+        .public_addresses()
+        .into_iter()
+        .filter(|public| public.confidence == AddressConfidence::Confirmed)
+        .map(|public| public.address)
+        .collect()
+}
+
+#[tokio::test]
+async fn external_addresses_advertised() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (identify_config1, mut identify_event_stream1) =
+        Config::new("/proto/1".to_string(), None, Vec::new());
+    let mut litep2p1 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(Default::default())
+            .with_libp2p_identify(identify_config1)
+            .with_external_address_confirmations(1)
+            .build(),
+    )
+    .unwrap();
+
+    let (identify_config2, mut identify_event_stream2) =
+        Config::new("/proto/2".to_string(), None, Vec::new());
+    let mut litep2p2 = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(Default::default())
+            .with_libp2p_identify(identify_config2)
+            .build(),
+    )
+    .unwrap();
+
+    let external_address: Multiaddr = "/ip4/1.1.1.1/tcp/8888".parse().unwrap();
+    let external_address =
+        external_address.with(Protocol::P2p(Multihash::from(*litep2p2.local_peer_id())));
+    assert!(litep2p2.add_external_address(external_address.clone()));
+    assert_eq!(
+        confirmed_addresses(&litep2p2),
+        vec![external_address.clone()]
+    );
+    assert!(litep2p2
+        .public_addresses()
+        .iter()
+        .any(|public| public.confidence == AddressConfidence::Listen));
+
+    let address2 = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address2).await.unwrap();
+
+    let mut observed = None;
+    let mut litep2p1_done = false;
+    let mut litep2p2_done = false;
+
+    while !litep2p1_done || !litep2p2_done {
+        tokio::select! {
+            _event = litep2p1.next_event() => {}
+            _event = litep2p2.next_event() => {}
+            event = identify_event_stream1.next() => {
+                let IdentifyEvent::PeerIdentified { listen_addresses, observed_address, .. } = event.unwrap();
+                assert!(listen_addresses.contains(&external_address));
+
+                observed = Some(observed_address);
+                litep2p1_done = true;
+            }
+            event = identify_event_stream2.next() => {
+                let IdentifyEvent::PeerIdentified { .. } = event.unwrap();
+                litep2p2_done = true;
+            }
+        }
+    }
+
+    // the address `litep2p2` observed `litep2p1` at is confirmed after a single observation
+    let observed = observed.unwrap();
+    let public_addresses = confirmed_addresses(&litep2p1);
+    assert_eq!(public_addresses.len(), 1);
+    assert!(public_addresses[0].to_string().starts_with(&observed.to_string()));
+
+    assert!(litep2p2.remove_external_address(external_address));
+    assert!(confirmed_addresses(&litep2p2).is_empty());
+}
@@ -18,6 +18,8 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+#[cfg(all(test, feature = "testing"))]
+mod echo;
 #[cfg(test)]
 mod identify;
 #[cfg(test)]
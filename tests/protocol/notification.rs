@@ -24,7 +24,7 @@ use litep2p::{
     error::Error,
     protocol::notification::{
         Config as NotificationConfig, ConfigBuilder, Direction, NotificationError,
-        NotificationEvent, NotificationHandle, ValidationResult,
+        NotificationEvent, NotificationHandle, NotificationStreamInfo, ValidationResult,
     },
     transport::{
         quic::config::Config as QuicConfig, tcp::config::Config as TcpConfig,
@@ -272,6 +272,30 @@ async fn open_substreams(transport1: Transport, transport2: Transport) {
             notification: BytesMut::from(&[1, 3, 3, 8][..]),
         }
     );
+
+    assert!(handle1.send_broadcast_notification(vec![1, 3, 3, 9]).is_empty());
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::NotificationReceived {
+            peer: peer1,
+            notification: BytesMut::from(&[1, 3, 3, 9][..]),
+        }
+    );
+
+    // oversized notifications are rejected before they're sent
+    assert_eq!(
+        handle1.send_sync_notification(peer2, vec![0u8; 1025]),
+        Err(NotificationError::NotificationTooLarge {
+            size: 1025,
+            max_size: 1024,
+        })
+    );
+    assert!(std::matches!(
+        handle1.send_async_notification(peer2, vec![0u8; 1025]).await,
+        Err(Error::NotificationError(
+            litep2p::error::NotificationError::NotificationTooLarge { .. }
+        ))
+    ));
 }
 
 #[tokio::test]
@@ -399,6 +423,131 @@ async fn reject_substream(transport1: Transport, transport2: Transport) {
     );
 }
 
+#[tokio::test]
+async fn reject_substream_with_code_tcp() {
+    reject_substream_with_code(
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn reject_substream_with_code_quic() {
+    reject_substream_with_code(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn reject_substream_with_code_websocket() {
+    reject_substream_with_code(
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+async fn reject_substream_with_code(transport1: Transport, transport2: Transport) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (notif_config1, mut handle1) = NotificationConfig::new(
+        ProtocolName::from("/notif/1"),
+        1024usize,
+        vec![1, 2, 3, 4],
+        Vec::new(),
+        false,
+        64,
+        64,
+        true,
+    );
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_notification_protocol(notif_config1);
+
+    let config1 = match transport1 {
+        Transport::Tcp(config) => config1.with_tcp(config),
+        Transport::Quic(config) => config1.with_quic(config),
+        Transport::WebSocket(config) => config1.with_websocket(config),
+    }
+    .build();
+
+    let (notif_config2, mut handle2) = NotificationConfig::new(
+        ProtocolName::from("/notif/1"),
+        1024usize,
+        vec![1, 2, 3, 4],
+        Vec::new(),
+        false,
+        64,
+        64,
+        true,
+    );
+    let config2 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_notification_protocol(notif_config2);
+
+    let config2 = match transport2 {
+        Transport::Tcp(config) => config2.with_tcp(config),
+        Transport::Quic(config) => config2.with_quic(config),
+        Transport::WebSocket(config) => config2.with_websocket(config),
+    }
+    .build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+
+    // wait until peers have connected and spawn the litep2p objects in the background
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    });
+
+    // open substream for `peer2` and accept it
+    handle1.open_substream(peer2).await.unwrap();
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::ValidateSubstream {
+            protocol: ProtocolName::from("/notif/1"),
+            fallback: None,
+            peer: peer1,
+            handshake: vec![1, 2, 3, 4],
+        }
+    );
+    handle2.send_validation_result(peer1, ValidationResult::RejectWithCode(7));
+
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpenFailure {
+            peer: peer2,
+            error: NotificationError::RejectedWithCode(7),
+        }
+    );
+}
+
 #[tokio::test]
 async fn notification_stream_closed_tcp() {
     notification_stream_closed(
@@ -565,12 +714,171 @@ async fn notification_stream_closed(transport1: Transport, transport2: Transport
         }
     );
 
+    assert_eq!(handle2.peers().collect::<Vec<_>>(), vec![&peer1]);
+    assert_eq!(
+        handle2.stream_info(&peer1),
+        Some(&NotificationStreamInfo {
+            protocol: ProtocolName::from("/notif/1"),
+            fallback: None,
+            direction: Direction::Inbound,
+            handshake: vec![1, 2, 3, 4],
+        })
+    );
+
     handle1.close_substream(peer2).await;
 
     match handle2.next().await.unwrap() {
         NotificationEvent::NotificationStreamClosed { peer } => assert_eq!(peer, peer1),
         _ => panic!("invalid event received"),
     }
+
+    assert_eq!(handle2.peers().count(), 0);
+    assert!(handle2.stream_info(&peer1).is_none());
+}
+
+#[tokio::test]
+async fn acknowledged_close_tcp() {
+    acknowledged_close(
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn acknowledged_close_quic() {
+    acknowledged_close(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn acknowledged_close_websocket() {
+    acknowledged_close(
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+async fn acknowledged_close(transport1: Transport, transport2: Transport) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (notif_config1, mut handle1) = NotificationConfig::new(
+        ProtocolName::from("/notif/1"),
+        1024usize,
+        vec![1, 2, 3, 4],
+        Vec::new(),
+        false,
+        64,
+        64,
+        true,
+    );
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_notification_protocol(notif_config1);
+
+    let config1 = match transport1 {
+        Transport::Tcp(config) => config1.with_tcp(config),
+        Transport::Quic(config) => config1.with_quic(config),
+        Transport::WebSocket(config) => config1.with_websocket(config),
+    }
+    .build();
+
+    let (notif_config2, mut handle2) = NotificationConfig::new(
+        ProtocolName::from("/notif/1"),
+        1024usize,
+        vec![1, 2, 3, 4],
+        Vec::new(),
+        false,
+        64,
+        64,
+        true,
+    );
+    let config2 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_notification_protocol(notif_config2);
+
+    let config2 = match transport2 {
+        Transport::Tcp(config) => config2.with_tcp(config),
+        Transport::Quic(config) => config2.with_quic(config),
+        Transport::WebSocket(config) => config2.with_websocket(config),
+    }
+    .build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+
+    // wait until peers have connected and spawn the litep2p objects in the background
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    });
+
+    // open substream for `peer2` and accept it
+    handle1.open_substream(peer2).await.unwrap();
+    assert!(std::matches!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::ValidateSubstream { .. }
+    ));
+    handle2.send_validation_result(peer1, ValidationResult::Accept);
+
+    assert!(std::matches!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::ValidateSubstream { .. }
+    ));
+    handle1.send_validation_result(peer2, ValidationResult::Accept);
+
+    assert!(std::matches!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpened { .. }
+    ));
+    assert!(std::matches!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpened { .. }
+    ));
+
+    // close the substream and wait until remote has closed its side of the stream
+    tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        handle1.close_substream_acknowledged(peer2),
+    )
+    .await
+    .expect("close to be acknowledged");
+
+    match handle1.next().await.unwrap() {
+        NotificationEvent::NotificationStreamClosedLocally { peer } => assert_eq!(peer, peer2),
+        _ => panic!("invalid event received"),
+    }
+
+    match handle2.next().await.unwrap() {
+        NotificationEvent::NotificationStreamClosed { peer } => assert_eq!(peer, peer1),
+        _ => panic!("invalid event received"),
+    }
 }
 
 #[tokio::test]
@@ -728,7 +1036,7 @@ async fn reconnect_after_disconnect(transport1: Transport, transport2: Transport
     handle2.close_substream(peer1).await;
 
     match handle2.next().await.unwrap() {
-        NotificationEvent::NotificationStreamClosed { peer } => assert_eq!(peer, peer1),
+        NotificationEvent::NotificationStreamClosedLocally { peer } => assert_eq!(peer, peer1),
         _ => panic!("invalid event received"),
     }
 
@@ -959,7 +1267,7 @@ async fn set_new_handshake(transport1: Transport, transport2: Transport) {
     handle2.close_substream(peer1).await;
 
     match handle2.next().await.unwrap() {
-        NotificationEvent::NotificationStreamClosed { peer } => assert_eq!(peer, peer1),
+        NotificationEvent::NotificationStreamClosedLocally { peer } => assert_eq!(peer, peer1),
         _ => panic!("invalid event received"),
     }
 
@@ -1123,6 +1431,12 @@ async fn both_nodes_open_substreams(transport1: Transport, transport2: Transport
         }
     });
 
+    // the race is resolved in favor of the node with the smaller `PeerId`
+    let (direction1, direction2) = match peer1 < peer2 {
+        true => (Direction::Outbound, Direction::Inbound),
+        false => (Direction::Inbound, Direction::Outbound),
+    };
+
     // both nodes open a substream at the same time
     handle1.open_substream(peer2).await.unwrap();
     handle2.open_substream(peer1).await.unwrap();
@@ -1156,7 +1470,7 @@ async fn both_nodes_open_substreams(transport1: Transport, transport2: Transport
         NotificationEvent::NotificationStreamOpened {
             protocol: ProtocolName::from("/notif/1"),
             fallback: None,
-            direction: Direction::Outbound,
+            direction: direction2,
             peer: peer1,
             handshake: vec![1, 2, 3, 4],
         }
@@ -1166,7 +1480,7 @@ async fn both_nodes_open_substreams(transport1: Transport, transport2: Transport
         NotificationEvent::NotificationStreamOpened {
             protocol: ProtocolName::from("/notif/1"),
             fallback: None,
-            direction: Direction::Outbound,
+            direction: direction1,
             peer: peer2,
             handshake: vec![1, 2, 3, 4],
         }
@@ -2564,6 +2878,9 @@ async fn send_using_notification_sink(transport1: Transport, transport2: Transpo
     let sink1 = handle1.notification_sink(peer2).unwrap();
     let sink2 = handle2.notification_sink(peer1).unwrap();
 
+    assert_eq!(sink1.peer(), peer2);
+    assert!(!sink1.is_closed());
+
     sink1.send_sync_notification(vec![1, 3, 3, 7]).unwrap();
     sink2.send_sync_notification(vec![1, 3, 3, 8]).unwrap();
 
@@ -2586,8 +2903,11 @@ async fn send_using_notification_sink(transport1: Transport, transport2: Transpo
     handle2.close_substream(peer1).await;
 
     // allow `peer1` to detect that the substream has been closed
-    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    tokio::time::timeout(std::time::Duration::from_secs(2), sink1.closed())
+        .await
+        .expect("sink to be invalidated");
 
+    assert!(sink1.is_closed());
     assert_eq!(
         sink1.send_sync_notification(vec![1, 3, 3, 7]),
         Err(NotificationError::NoConnection),
@@ -4149,3 +4469,134 @@ async fn clogged_channel_disconnects_peer(transport1: Transport, transport2: Tra
         Ok(()) => {}
     }
 }
+
+#[tokio::test]
+async fn keepalives_not_reported_tcp() {
+    keepalives_not_reported(
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn keepalives_not_reported_quic() {
+    keepalives_not_reported(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn keepalives_not_reported_websocket() {
+    keepalives_not_reported(
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+async fn keepalives_not_reported(transport1: Transport, transport2: Transport) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (notif_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+        .with_max_size(1024usize)
+        .with_handshake(vec![1, 2, 3, 4])
+        .with_auto_accept_inbound(true)
+        .with_keepalive(Duration::from_millis(500), Duration::from_secs(5))
+        .build();
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_notification_protocol(notif_config1);
+
+    let config1 = match transport1 {
+        Transport::Tcp(config) => config1.with_tcp(config),
+        Transport::Quic(config) => config1.with_quic(config),
+        Transport::WebSocket(config) => config1.with_websocket(config),
+    }
+    .build();
+
+    let (notif_config2, mut handle2) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+        .with_max_size(1024usize)
+        .with_handshake(vec![1, 2, 3, 4])
+        .with_keepalive(Duration::from_millis(500), Duration::from_secs(5))
+        .build();
+    let config2 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_notification_protocol(notif_config2);
+
+    let config2 = match transport2 {
+        Transport::Tcp(config) => config2.with_tcp(config),
+        Transport::Quic(config) => config2.with_quic(config),
+        Transport::WebSocket(config) => config2.with_websocket(config),
+    }
+    .build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+
+    // wait until peers have connected and spawn the litep2p objects in the background
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    });
+
+    // open substream for `peer2` and accept it
+    handle1.open_substream(peer2).await.unwrap();
+    assert!(std::matches!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::ValidateSubstream { .. }
+    ));
+    handle2.send_validation_result(peer1, ValidationResult::Accept);
+
+    assert!(std::matches!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpened { .. }
+    ));
+    assert!(std::matches!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpened { .. }
+    ));
+
+    // let both streams idle long enough for several keepalives to be exchanged
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    handle1.send_sync_notification(peer2, vec![1, 3, 3, 7]).unwrap();
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::NotificationReceived {
+            peer: peer1,
+            notification: BytesMut::from(&[1, 3, 3, 7][..]),
+        }
+    );
+
+    futures::future::poll_fn(|cx| match handle1.poll_next_unpin(cx) {
+        Poll::Pending => Poll::Ready(()),
+        _ => panic!("invalid event"),
+    })
+    .await;
+    assert_eq!(handle1.peers().collect::<Vec<_>>(), vec![&peer2]);
+}
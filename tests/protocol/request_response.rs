@@ -21,9 +21,13 @@
 use litep2p::{
     config::ConfigBuilder as Litep2pConfigBuilder,
     crypto::ed25519::Keypair,
-    protocol::request_response::{
-        Config as RequestResponseConfig, ConfigBuilder, DialOptions, RequestResponseError,
-        RequestResponseEvent,
+    protocol::{
+        libp2p::ping::Config as PingConfig,
+        request_response::{
+            Config as RequestResponseConfig, ConfigBuilder, DialOptions, RequestResponseError,
+            RequestResponseEvent,
+        },
+        ConnectionPreference,
     },
     transport::{
         quic::config::Config as QuicConfig, tcp::config::Config as TcpConfig,
@@ -33,6 +37,7 @@ use litep2p::{
     Litep2p, Litep2pEvent, PeerId,
 };
 
+use bytes::Bytes;
 use futures::{channel, StreamExt};
 use multiaddr::{Multiaddr, Protocol};
 use multihash::Multihash;
@@ -193,7 +198,7 @@ async fn send_request_receive_response(transport1: Transport, transport2: Transp
             peer: peer1,
             fallback: None,
             request_id,
-            request: vec![1, 3, 3, 7],
+            request: Bytes::from(vec![1, 3, 3, 7]),
         }
     );
 
@@ -204,7 +209,7 @@ async fn send_request_receive_response(transport1: Transport, transport2: Transp
         RequestResponseEvent::ResponseReceived {
             peer: peer2,
             request_id,
-            response: vec![1, 3, 3, 8],
+            response: Bytes::from(vec![1, 3, 3, 8]),
             fallback: None,
         }
     );
@@ -1059,7 +1064,7 @@ async fn response_too_big(transport1: Transport, transport2: Transport) {
             peer: peer1,
             fallback: None,
             request_id,
-            request: vec![0u8; 256],
+            request: Bytes::from(vec![0u8; 256]),
         }
     );
 
@@ -1076,6 +1081,137 @@ async fn response_too_big(transport1: Transport, transport2: Transport) {
     );
 }
 
+#[tokio::test]
+async fn separate_request_and_response_size_limits_tcp() {
+    separate_request_and_response_size_limits(
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn separate_request_and_response_size_limits_quic() {
+    separate_request_and_response_size_limits(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn separate_request_and_response_size_limits_websocket() {
+    separate_request_and_response_size_limits(
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+async fn separate_request_and_response_size_limits(transport1: Transport, transport2: Transport) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (req_resp_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/protocol/1"))
+        .with_max_request_size(256)
+        .with_max_response_size(1024)
+        .build();
+
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_request_response_protocol(req_resp_config1);
+
+    let config1 = match transport1 {
+        Transport::Tcp(config) => config1.with_tcp(config),
+        Transport::Quic(config) => config1.with_quic(config),
+        Transport::WebSocket(config) => config1.with_websocket(config),
+    }
+    .build();
+
+    let (req_resp_config2, mut handle2) = ConfigBuilder::new(ProtocolName::from("/protocol/1"))
+        .with_max_request_size(256)
+        .with_max_response_size(1024)
+        .build();
+
+    let config2 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_request_response_protocol(req_resp_config2);
+
+    let config2 = match transport2 {
+        Transport::Tcp(config) => config2.with_tcp(config),
+        Transport::Quic(config) => config2.with_quic(config),
+        Transport::WebSocket(config) => config2.with_websocket(config),
+    }
+    .build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+
+    // wait until peers have connected
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    });
+
+    // request larger than the request size limit is not sent
+    let request_id =
+        handle1.send_request(peer2, vec![0u8; 257], DialOptions::Reject).await.unwrap();
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        RequestResponseEvent::RequestFailed {
+            peer: peer2,
+            request_id,
+            error: RequestResponseError::TooLargePayload,
+        }
+    );
+
+    // response larger than the request size limit but within the response size limit is received
+    let request_id =
+        handle1.send_request(peer2, vec![0u8; 256], DialOptions::Reject).await.unwrap();
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        RequestResponseEvent::RequestReceived {
+            peer: peer1,
+            fallback: None,
+            request_id,
+            request: Bytes::from(vec![0u8; 256]),
+        }
+    );
+
+    handle2.send_response(request_id, vec![1u8; 1024]);
+
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        RequestResponseEvent::ResponseReceived {
+            peer: peer2,
+            request_id,
+            response: Bytes::from(vec![1u8; 1024]),
+            fallback: None,
+        }
+    );
+}
+
 #[tokio::test]
 async fn too_many_pending_requests() {
     let _ = tracing_subscriber::fmt()
@@ -1298,7 +1434,7 @@ async fn dialer_fallback_protocol_works(transport1: Transport, transport2: Trans
             peer: peer1,
             fallback: None,
             request_id,
-            request: vec![1, 3, 3, 7],
+            request: Bytes::from(vec![1, 3, 3, 7]),
         }
     );
 
@@ -1309,7 +1445,7 @@ async fn dialer_fallback_protocol_works(transport1: Transport, transport2: Trans
         RequestResponseEvent::ResponseReceived {
             peer: peer2,
             request_id,
-            response: vec![1, 3, 3, 8],
+            response: Bytes::from(vec![1, 3, 3, 8]),
             fallback: Some(ProtocolName::from("/protocol/1")),
         }
     );
@@ -1423,7 +1559,7 @@ async fn listener_fallback_protocol_works(transport1: Transport, transport2: Tra
             peer: peer1,
             fallback: Some(ProtocolName::from("/protocol/1")),
             request_id,
-            request: vec![1, 3, 3, 7],
+            request: Bytes::from(vec![1, 3, 3, 7]),
         }
     );
 
@@ -1434,7 +1570,7 @@ async fn listener_fallback_protocol_works(transport1: Transport, transport2: Tra
         RequestResponseEvent::ResponseReceived {
             peer: peer2,
             request_id,
-            response: vec![1, 3, 3, 8],
+            response: Bytes::from(vec![1, 3, 3, 8]),
             fallback: None,
         }
     );
@@ -1548,7 +1684,7 @@ async fn dial_peer_when_sending_request(transport1: Transport, transport2: Trans
             peer: peer1,
             fallback: Some(ProtocolName::from("/protocol/1")),
             request_id,
-            request: vec![1, 3, 3, 7],
+            request: Bytes::from(vec![1, 3, 3, 7]),
         }
     );
 
@@ -1559,7 +1695,7 @@ async fn dial_peer_when_sending_request(transport1: Transport, transport2: Trans
         RequestResponseEvent::ResponseReceived {
             peer: peer2,
             request_id,
-            response: vec![1, 3, 3, 8],
+            response: Bytes::from(vec![1, 3, 3, 8]),
             fallback: None,
         }
     );
@@ -1667,7 +1803,7 @@ async fn dial_peer_but_no_known_address(transport1: Transport, transport2: Trans
         RequestResponseEvent::RequestFailed {
             peer: peer2,
             request_id,
-            error: RequestResponseError::Rejected,
+            error: RequestResponseError::DialFailure,
         }
     );
 }
@@ -1780,7 +1916,7 @@ async fn cancel_request(transport1: Transport, transport2: Transport) {
             peer: peer1,
             fallback: None,
             request_id,
-            request: vec![1, 3, 3, 7],
+            request: Bytes::from(vec![1, 3, 3, 7]),
         }
     );
 
@@ -2144,7 +2280,7 @@ async fn feedback_received_for_succesful_response(transport1: Transport, transpo
             peer: peer1,
             fallback: None,
             request_id,
-            request: vec![1, 3, 3, 7]
+            request: Bytes::from(vec![1, 3, 3, 7])
         },
     );
 
@@ -2157,7 +2293,7 @@ async fn feedback_received_for_succesful_response(transport1: Transport, transpo
         RequestResponseEvent::ResponseReceived {
             peer: peer2,
             request_id,
-            response: vec![1, 3, 3, 8],
+            response: Bytes::from(vec![1, 3, 3, 8]),
             fallback: None,
         }
     );
@@ -2265,7 +2401,7 @@ async fn feedback_not_received_for_failed_response(transport1: Transport, transp
             peer: peer1,
             fallback: None,
             request_id,
-            request: vec![1, 3, 3, 7]
+            request: Bytes::from(vec![1, 3, 3, 7])
         },
     );
 
@@ -2378,6 +2514,108 @@ async fn custom_timeout(transport1: Transport, transport2: Transport) {
     );
 }
 
+#[tokio::test]
+async fn per_request_timeout_tcp() {
+    per_request_timeout(
+        Transport::Tcp(Default::default()),
+        Transport::Tcp(Default::default()),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn per_request_timeout_quic() {
+    per_request_timeout(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn per_request_timeout_websocket() {
+    per_request_timeout(
+        Transport::WebSocket(Default::default()),
+        Transport::WebSocket(Default::default()),
+    )
+    .await;
+}
+
+async fn per_request_timeout(transport1: Transport, transport2: Transport) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (req_resp_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/protocol/1"))
+        .with_max_size(1024)
+        .with_timeout(Duration::from_secs(20))
+        .build();
+
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_request_response_protocol(req_resp_config1);
+
+    let config1 = match transport1 {
+        Transport::Tcp(config) => config1.with_tcp(config),
+        Transport::Quic(config) => config1.with_quic(config),
+        Transport::WebSocket(config) => config1.with_websocket(config),
+    }
+    .build();
+
+    let (req_resp_config2, _handle2) = ConfigBuilder::new(ProtocolName::from("/protocol/1"))
+        .with_max_size(1024)
+        .build();
+
+    let config2 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_request_response_protocol(req_resp_config2);
+
+    let config2 = match transport2 {
+        Transport::Tcp(config) => config2.with_tcp(config),
+        Transport::Quic(config) => config2.with_quic(config),
+        Transport::WebSocket(config) => config2.with_websocket(config),
+    }
+    .build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+    let peer2 = *litep2p2.local_peer_id();
+
+    // wait until peers have connected
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p2.next_event() => {},
+                _ = litep2p1.next_event() => {},
+            }
+        }
+    });
+
+    let request_id = handle1
+        .send_request_with_timeout(
+            peer2,
+            vec![1, 3, 3, 7],
+            DialOptions::Reject,
+            Duration::from_secs(2),
+        )
+        .await
+        .unwrap();
+
+    // verify that the request times out well before the timeout of the protocol
+    match tokio::time::timeout(Duration::from_secs(5), handle1.next()).await {
+        Ok(Some(event)) => assert_eq!(
+            event,
+            RequestResponseEvent::RequestFailed {
+                peer: peer2,
+                request_id,
+                error: RequestResponseError::Timeout
+            }
+        ),
+        _ => panic!("expected request to time out"),
+    }
+}
+
 #[tokio::test]
 async fn outbound_request_for_unconnected_peer_tcp() {
     outbound_request_for_unconnected_peer(Transport::Tcp(Default::default())).await;
@@ -2494,67 +2732,135 @@ async fn dial_failure(transport: Transport) {
 
     let request_id = handle.send_request(peer, vec![1, 3, 3, 7], DialOptions::Dial).await.unwrap();
 
-    // verify that the request is reported as rejected since the dial failed
+    // verify that the request is reported as failed since the dial failed
     assert_eq!(
         handle.next().await.unwrap(),
         RequestResponseEvent::RequestFailed {
             peer,
             request_id,
-            error: RequestResponseError::Rejected
+            error: RequestResponseError::DialFailure
         }
     );
 }
 
 #[tokio::test]
-async fn large_response_tcp() {
-    large_response(
-        Transport::Tcp(Default::default()),
-        Transport::Tcp(Default::default()),
-    )
-    .await;
+async fn cancel_request_while_dialing_tcp() {
+    cancel_request_while_dialing(Transport::Tcp(Default::default())).await;
 }
 
 #[tokio::test]
-async fn large_response_quic() {
-    large_response(
-        Transport::Quic(Default::default()),
-        Transport::Quic(Default::default()),
-    )
-    .await;
+async fn cancel_request_while_dialing_quic() {
+    cancel_request_while_dialing(Transport::Quic(Default::default())).await;
 }
 
 #[tokio::test]
-async fn large_response_websocket() {
-    large_response(
-        Transport::WebSocket(Default::default()),
-        Transport::WebSocket(Default::default()),
-    )
-    .await;
+async fn cancel_request_while_dialing_websocket() {
+    cancel_request_while_dialing(Transport::WebSocket(Default::default())).await;
 }
 
-async fn large_response(transport1: Transport, transport2: Transport) {
+async fn cancel_request_while_dialing(transport: Transport) {
     let _ = tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .try_init();
 
-    let (req_resp_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/protocol/1"))
-        .with_max_size(16 * 1024 * 1024)
-        .with_timeout(Duration::from_secs(8))
+    let (req_resp_config, mut handle) = ConfigBuilder::new(ProtocolName::from("/protocol/1"))
+        .with_max_size(1024)
         .build();
 
-    let config1 = Litep2pConfigBuilder::new()
+    let litep2p_config = Litep2pConfigBuilder::new()
         .with_keypair(Keypair::generate())
-        .with_request_response_protocol(req_resp_config1);
+        .with_request_response_protocol(req_resp_config);
 
-    let config1 = match transport1 {
-        Transport::Tcp(config) => config1.with_tcp(config),
-        Transport::Quic(config) => config1.with_quic(config),
-        Transport::WebSocket(config) => config1.with_websocket(config),
+    let peer = PeerId::random();
+    let known_address = match &transport {
+        Transport::Tcp(_) => Multiaddr::empty()
+            .with(Protocol::Ip6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)))
+            .with(Protocol::Tcp(5))
+            .with(Protocol::P2p(Multihash::from(peer))),
+        Transport::Quic(_) => Multiaddr::empty()
+            .with(Protocol::Ip4(Ipv4Addr::new(127, 0, 0, 1)))
+            .with(Protocol::Udp(5))
+            .with(Protocol::QuicV1)
+            .with(Protocol::P2p(Multihash::from(peer))),
+        Transport::WebSocket(_) => Multiaddr::empty()
+            .with(Protocol::Ip6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)))
+            .with(Protocol::Tcp(5))
+            .with(Protocol::Ws(std::borrow::Cow::Owned("/".to_string())))
+            .with(Protocol::P2p(Multihash::from(peer))),
+    };
+
+    let config = match transport {
+        Transport::Tcp(config) => litep2p_config.with_tcp(config),
+        Transport::Quic(config) => litep2p_config.with_quic(config),
+        Transport::WebSocket(config) => litep2p_config.with_websocket(config),
     }
     .build();
 
-    let (req_resp_config2, mut handle2) = ConfigBuilder::new(ProtocolName::from("/protocol/1"))
-        .with_max_size(16 * 1024 * 1024)
+    let mut litep2p = Litep2p::new(config).unwrap();
+    litep2p.add_known_address(peer, vec![known_address].into_iter());
+    tokio::spawn(async move { while let Some(_) = litep2p.next_event().await {} });
+
+    // cancel the request before the dial concludes
+    let request_id = handle.send_request(peer, vec![1, 3, 3, 7], DialOptions::Dial).await.unwrap();
+    handle.cancel_request(request_id).await;
+
+    // verify that the dial failure is not reported since the request was canceled
+    match tokio::time::timeout(Duration::from_secs(3), handle.next()).await {
+        Err(_) => {}
+        Ok(event) => panic!("invalid event received: {event:?}"),
+    }
+}
+
+#[tokio::test]
+async fn large_response_tcp() {
+    large_response(
+        Transport::Tcp(Default::default()),
+        Transport::Tcp(Default::default()),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn large_response_quic() {
+    large_response(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn large_response_websocket() {
+    large_response(
+        Transport::WebSocket(Default::default()),
+        Transport::WebSocket(Default::default()),
+    )
+    .await;
+}
+
+async fn large_response(transport1: Transport, transport2: Transport) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (req_resp_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/protocol/1"))
+        .with_max_size(16 * 1024 * 1024)
+        .with_timeout(Duration::from_secs(8))
+        .build();
+
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_request_response_protocol(req_resp_config1);
+
+    let config1 = match transport1 {
+        Transport::Tcp(config) => config1.with_tcp(config),
+        Transport::Quic(config) => config1.with_quic(config),
+        Transport::WebSocket(config) => config1.with_websocket(config),
+    }
+    .build();
+
+    let (req_resp_config2, mut handle2) = ConfigBuilder::new(ProtocolName::from("/protocol/1"))
+        .with_max_size(16 * 1024 * 1024)
         .build();
 
     let config2 = Litep2pConfigBuilder::new()
@@ -2598,7 +2904,7 @@ async fn large_response(transport1: Transport, transport2: Transport) {
             peer: peer1,
             fallback: None,
             request_id,
-            request: vec![1, 3, 3, 7],
+            request: Bytes::from(vec![1, 3, 3, 7]),
         }
     );
 
@@ -2711,7 +3017,7 @@ async fn binary_incompatible_fallback(transport1: Transport, transport2: Transpo
             peer: peer1,
             fallback: None,
             request_id,
-            request: vec![5, 6, 7, 8],
+            request: Bytes::from(vec![5, 6, 7, 8]),
         }
     );
 
@@ -2722,7 +3028,7 @@ async fn binary_incompatible_fallback(transport1: Transport, transport2: Transpo
         RequestResponseEvent::ResponseReceived {
             peer: peer2,
             request_id,
-            response: vec![1, 3, 3, 7],
+            response: Bytes::from(vec![1, 3, 3, 7]),
             fallback: Some(ProtocolName::from("/protocol/1")),
         }
     );
@@ -2822,7 +3128,7 @@ async fn binary_incompatible_fallback_inbound_request(
             peer: peer2,
             fallback: Some(ProtocolName::from("/protocol/1")),
             request_id,
-            request: vec![1, 2, 3, 4],
+            request: Bytes::from(vec![1, 2, 3, 4]),
         }
     );
 
@@ -2833,7 +3139,7 @@ async fn binary_incompatible_fallback_inbound_request(
         RequestResponseEvent::ResponseReceived {
             peer: peer1,
             request_id,
-            response: vec![1, 3, 3, 8],
+            response: Bytes::from(vec![1, 3, 3, 8]),
             fallback: None,
         }
     );
@@ -2944,7 +3250,7 @@ async fn binary_incompatible_fallback_two_fallback_protocols(
             peer: peer1,
             fallback: None,
             request_id,
-            request: vec![5, 6, 7, 8],
+            request: Bytes::from(vec![5, 6, 7, 8]),
         }
     );
 
@@ -2955,7 +3261,7 @@ async fn binary_incompatible_fallback_two_fallback_protocols(
         RequestResponseEvent::ResponseReceived {
             peer: peer2,
             request_id,
-            response: vec![1, 3, 3, 7],
+            response: Bytes::from(vec![1, 3, 3, 7]),
             fallback: Some(ProtocolName::from("/genesis/protocol/1")),
         }
     );
@@ -3061,7 +3367,7 @@ async fn binary_incompatible_fallback_two_fallback_protocols_inbound_request(
             peer: peer2,
             fallback: Some(ProtocolName::from("/genesis/protocol/1")),
             request_id,
-            request: vec![1, 2, 3, 4],
+            request: Bytes::from(vec![1, 2, 3, 4]),
         }
     );
 
@@ -3072,7 +3378,7 @@ async fn binary_incompatible_fallback_two_fallback_protocols_inbound_request(
         RequestResponseEvent::ResponseReceived {
             peer: peer1,
             request_id,
-            response: vec![1, 3, 3, 7],
+            response: Bytes::from(vec![1, 3, 3, 7]),
             fallback: None,
         }
     );
@@ -3187,7 +3493,7 @@ async fn binary_incompatible_fallback_compatible_nodes(
             peer: peer1,
             fallback: None,
             request_id,
-            request: vec![1, 2, 3, 4],
+            request: Bytes::from(vec![1, 2, 3, 4]),
         }
     );
 
@@ -3198,7 +3504,489 @@ async fn binary_incompatible_fallback_compatible_nodes(
         RequestResponseEvent::ResponseReceived {
             peer: peer2,
             request_id,
-            response: vec![1, 3, 3, 7],
+            response: Bytes::from(vec![1, 3, 3, 7]),
+            fallback: None,
+        }
+    );
+}
+
+#[tokio::test]
+async fn request_response_with_stream_priority_quic() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (req_resp_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/protocol/1"))
+        .with_max_size(1024)
+        .with_stream_priority(10)
+        .build();
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_quic(Default::default())
+        .with_request_response_protocol(req_resp_config1)
+        .build();
+
+    let (req_resp_config2, mut handle2) = ConfigBuilder::new(ProtocolName::from("/protocol/1"))
+        .with_max_size(1024)
+        .with_stream_priority(-10)
+        .build();
+    let config2 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_quic(Default::default())
+        .with_request_response_protocol(req_resp_config2)
+        .build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+
+    // wait until peers have connected
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    });
+
+    // send request to remote peer
+    let request_id = handle1
+        .send_request(peer2, vec![1, 3, 3, 7], DialOptions::Reject)
+        .await
+        .unwrap();
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        RequestResponseEvent::RequestReceived {
+            peer: peer1,
+            fallback: None,
+            request_id,
+            request: Bytes::from(vec![1, 3, 3, 7]),
+        }
+    );
+
+    // send response to the received request
+    handle2.send_response(request_id, vec![1, 3, 3, 8]);
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        RequestResponseEvent::ResponseReceived {
+            peer: peer2,
+            request_id,
+            response: Bytes::from(vec![1, 3, 3, 8]),
+            fallback: None,
+        }
+    );
+}
+
+#[tokio::test]
+async fn request_response_with_connection_preference_quic() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (req_resp_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/protocol/1"))
+        .with_max_size(1024)
+        .with_connection_preference(ConnectionPreference::LowestLatency)
+        .build();
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_quic(Default::default())
+        .with_request_response_protocol(req_resp_config1)
+        .build();
+
+    let (req_resp_config2, mut handle2) = ConfigBuilder::new(ProtocolName::from("/protocol/1"))
+        .with_max_size(1024)
+        .with_connection_preference(ConnectionPreference::Direct)
+        .build();
+    let config2 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_quic(Default::default())
+        .with_request_response_protocol(req_resp_config2)
+        .build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+
+    // wait until peers have connected
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    });
+
+    // send request to remote peer
+    let request_id = handle1
+        .send_request(peer2, vec![1, 3, 3, 7], DialOptions::Reject)
+        .await
+        .unwrap();
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        RequestResponseEvent::RequestReceived {
+            peer: peer1,
+            fallback: None,
+            request_id,
+            request: Bytes::from(vec![1, 3, 3, 7]),
+        }
+    );
+
+    // send response to the received request
+    handle2.send_response(request_id, vec![1, 3, 3, 8]);
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        RequestResponseEvent::ResponseReceived {
+            peer: peer2,
+            request_id,
+            response: Bytes::from(vec![1, 3, 3, 8]),
+            fallback: None,
+        }
+    );
+}
+
+#[tokio::test]
+async fn protocol_installed_over_open_connection() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (ping_config1, _ping_event_stream1) = PingConfig::default();
+    let mut litep2p1 = Litep2p::new(
+        Litep2pConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_libp2p_ping(ping_config1)
+            .build(),
+    )
+    .unwrap();
+
+    let (ping_config2, _ping_event_stream2) = PingConfig::default();
+    let mut litep2p2 = Litep2p::new(
+        Litep2pConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_libp2p_ping(ping_config2)
+            .build(),
+    )
+    .unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+
+    // wait until peers have connected and only then install the protocol
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
+
+    let (req_resp_config1, mut handle1) = RequestResponseConfig::new(
+        ProtocolName::from("/protocol/1"),
+        Vec::new(),
+        1024,
+        Duration::from_secs(5),
+        None,
+    );
+    let (req_resp_config2, mut handle2) = RequestResponseConfig::new(
+        ProtocolName::from("/protocol/1"),
+        Vec::new(),
+        1024,
+        Duration::from_secs(5),
+        None,
+    );
+    litep2p1.install_request_response_protocol(req_resp_config1).unwrap();
+    litep2p2.install_request_response_protocol(req_resp_config2).unwrap();
+
+    let (req_resp_config3, _handle3) = RequestResponseConfig::new(
+        ProtocolName::from("/protocol/1"),
+        Vec::new(),
+        1024,
+        Duration::from_secs(5),
+        None,
+    );
+    assert!(litep2p1.install_request_response_protocol(req_resp_config3).is_err());
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    });
+
+    // the open connection is reported to the installed protocol
+    sleep(Duration::from_millis(500)).await;
+    let request_id = handle1
+        .send_request(peer2, vec![1, 3, 3, 7], DialOptions::Reject)
+        .await
+        .unwrap();
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        RequestResponseEvent::RequestReceived {
+            peer: peer1,
+            fallback: None,
+            request_id,
+            request: Bytes::from(vec![1, 3, 3, 7]),
+        }
+    );
+
+    handle2.send_response(request_id, vec![1, 3, 3, 8]);
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        RequestResponseEvent::ResponseReceived {
+            peer: peer2,
+            request_id,
+            response: Bytes::from(vec![1, 3, 3, 8]),
+            fallback: None,
+        }
+    );
+}
+
+#[tokio::test]
+async fn uninstalled_protocol_rejects_requests() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (ping_config1, _ping_event_stream1) = PingConfig::default();
+    let (req_resp_config1, mut handle1) = RequestResponseConfig::new(
+        ProtocolName::from("/protocol/1"),
+        Vec::new(),
+        1024,
+        Duration::from_secs(5),
+        None,
+    );
+    let mut litep2p1 = Litep2p::new(
+        Litep2pConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_libp2p_ping(ping_config1)
+            .with_request_response_protocol(req_resp_config1)
+            .build(),
+    )
+    .unwrap();
+
+    let (ping_config2, _ping_event_stream2) = PingConfig::default();
+    let (req_resp_config2, mut handle2) = RequestResponseConfig::new(
+        ProtocolName::from("/protocol/1"),
+        Vec::new(),
+        1024,
+        Duration::from_secs(5),
+        None,
+    );
+    let mut litep2p2 = Litep2p::new(
+        Litep2pConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_libp2p_ping(ping_config2)
+            .with_request_response_protocol(req_resp_config2)
+            .build(),
+    )
+    .unwrap();
+
+    let peer2 = *litep2p2.local_peer_id();
+
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
+    litep2p2.uninstall_protocol(&ProtocolName::from("/protocol/1")).unwrap();
+    assert!(litep2p2.uninstall_protocol(&ProtocolName::from("/protocol/1")).is_err());
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    });
+
+    // event loop of the uninstalled protocol exits
+    match tokio::time::timeout(Duration::from_secs(5), handle2.next()).await {
+        Ok(None) => {}
+        event => panic!("invalid event: {event:?}"),
+    }
+
+    let request_id = handle1
+        .send_request(peer2, vec![1, 3, 3, 7], DialOptions::Reject)
+        .await
+        .unwrap();
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        RequestResponseEvent::RequestFailed {
+            peer: peer2,
+            request_id,
+            error: RequestResponseError::UnsupportedProtocol,
+        }
+    );
+}
+
+#[tokio::test]
+async fn streaming_response_tcp() {
+    streaming_response(
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn streaming_response_quic() {
+    streaming_response(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn streaming_response_websocket() {
+    streaming_response(
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+async fn streaming_response(transport1: Transport, transport2: Transport) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (req_resp_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/protocol/1"))
+        .with_max_size(1024)
+        .with_streaming_responses()
+        .build();
+
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_request_response_protocol(req_resp_config1);
+
+    let config1 = match transport1 {
+        Transport::Tcp(config) => config1.with_tcp(config),
+        Transport::Quic(config) => config1.with_quic(config),
+        Transport::WebSocket(config) => config1.with_websocket(config),
+    }
+    .build();
+
+    let (req_resp_config2, mut handle2) = ConfigBuilder::new(ProtocolName::from("/protocol/1"))
+        .with_max_size(1024)
+        .with_streaming_responses()
+        .build();
+
+    let config2 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_request_response_protocol(req_resp_config2);
+
+    let config2 = match transport2 {
+        Transport::Tcp(config) => config2.with_tcp(config),
+        Transport::Quic(config) => config2.with_quic(config),
+        Transport::WebSocket(config) => config2.with_websocket(config),
+    }
+    .build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+
+    // wait until peers have connected
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    });
+
+    // send request and receive the response as a stream of frames whose total size
+    // exceeds the maximum response size
+    let (request_id, mut response) = handle1
+        .send_request_with_response_stream(peer2, vec![1, 3, 3, 7], DialOptions::Reject)
+        .await
+        .unwrap();
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        RequestResponseEvent::RequestReceived {
+            peer: peer1,
+            fallback: None,
+            request_id,
+            request: Bytes::from(vec![1, 3, 3, 7]),
+        }
+    );
+
+    handle2.send_response_stream(
+        request_id,
+        futures::stream::iter((0..4u8).map(|i| Bytes::from(vec![i; 1024]))),
+    );
+
+    let mut frames = Vec::new();
+    while let Some(frame) = response.next().await {
+        frames.push(frame);
+    }
+
+    assert!(response.is_complete());
+    assert_eq!(
+        frames,
+        (0..4u8).map(|i| Bytes::from(vec![i; 1024])).collect::<Vec<_>>()
+    );
+
+    // response to a regular request is buffered and reported as one response
+    let request_id = handle1
+        .send_request(peer2, vec![1, 3, 3, 8], DialOptions::Reject)
+        .await
+        .unwrap();
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        RequestResponseEvent::RequestReceived {
+            peer: peer1,
+            fallback: None,
+            request_id,
+            request: Bytes::from(vec![1, 3, 3, 8]),
+        }
+    );
+
+    handle2.send_response_stream(
+        request_id,
+        futures::stream::iter(vec![Bytes::from(vec![1, 3]), Bytes::from(vec![3, 9])]),
+    );
+
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        RequestResponseEvent::ResponseReceived {
+            peer: peer2,
+            request_id,
+            response: Bytes::from(vec![1, 3, 3, 9]),
             fallback: None,
         }
     );
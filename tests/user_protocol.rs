@@ -69,7 +69,7 @@ impl UserProtocol for CustomProtocol {
                     TransportEvent::ConnectionEstablished { peer, .. } => {
                         self.peers.insert(peer);
                     }
-                    TransportEvent::ConnectionClosed { peer } => {
+                    TransportEvent::ConnectionClosed { peer, .. } => {
                         self.peers.remove(&peer);
                     }
                     _ => {}
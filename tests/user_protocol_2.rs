@@ -74,9 +74,10 @@ impl UserProtocol for CustomProtocol {
                     TransportEvent::ConnectionEstablished { peer, .. } => {
                         self.peers.insert(peer);
                     }
-                    TransportEvent::ConnectionClosed { peer: _ } => {}
+                    TransportEvent::ConnectionClosed { peer: _, connection_id: _ } => {}
                     TransportEvent::SubstreamOpened {
                         peer: _,
+                        connection_id: _,
                         protocol: _,
                         direction: _,
                         substream: _,